@@ -1,6 +1,13 @@
-use std::{ffi::{OsStr, OsString}, os::unix::ffi::OsStrExt, path::{Path, PathBuf}};
+use std::{
+	ffi::{OsStr, OsString},
+	path::{Path, PathBuf},
+	time::Duration,
+};
 
-use clap::{AppSettings, clap_app, crate_authors, crate_description, crate_version};
+use clap::{AppSettings, ArgMatches, clap_app, crate_authors, crate_description, crate_version};
+
+use crate::ffi::{self, OsStrExt};
+use crate::runtime::{Budget, Permission, Policy};
 
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -22,6 +29,18 @@ pub struct Args {
 	pub print_ast: bool,
 	/// Print the program.
 	pub print_program: bool,
+	/// Print commands instead of executing them.
+	pub dry_run: bool,
+	/// Print commands before executing them.
+	pub xtrace: bool,
+	/// The resources the script may access.
+	pub policy: Policy,
+	/// The limits on the resources used by the script.
+	pub budget: Budget,
+	/// Write an lcov coverage report to the given path.
+	pub coverage: Option<PathBuf>,
+	/// Sample the call stack, and write a profile when finished.
+	pub profile: bool,
 	/// Arguments for the script.
 	pub script_args: Box<[Box<[u8]>]>
 }
@@ -32,6 +51,21 @@ where
 	A: IntoIterator<Item = T>,
 	T: Into<OsString> + Clone
 {
+	let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+
+	// A `--` before the first argument ends the options, and makes the following argument
+	// the script path even if it's not an existing file. As options require their values
+	// after an equals sign, the first argument is the first one that doesn't start with a
+	// dash.
+	let explicit_script = matches!(
+		args
+			.iter()
+			.skip(1)
+			.map(|arg| arg.as_bytes())
+			.find(|arg| *arg == b"--" || !arg.starts_with(b"-")),
+		Some(b"--")
+	);
+
 	let app =
 		clap_app!(
 			Hush =>
@@ -42,9 +76,21 @@ where
 				(@arg lex: --lex "Print the lexemes")
 				(@arg ast: --ast "Print the AST")
 				(@arg program: --program "Print the PROGAM")
+				(@arg dryrun: -n --("dry-run") "Print commands instead of executing them")
+				(@arg xtrace: -x --xtrace "Print commands before executing them")
+				(@arg sandbox: --sandbox "Deny access to commands, paths, network and environment variables, unless allowed")
+				(@arg allow_command: --("allow-command") +takes_value +multiple +require_equals !use_delimiter number_of_values(1) value_name("PROGRAM") "Allow executing the given program")
+				(@arg allow_path: --("allow-path") +takes_value +multiple +require_equals !use_delimiter number_of_values(1) value_name("PATH") "Allow accessing the given path, and everything below it")
+				(@arg allow_host: --("allow-host") +takes_value +multiple +require_equals !use_delimiter number_of_values(1) value_name("HOST") "Allow network access to the given host")
+				(@arg allow_env: --("allow-env") +takes_value +multiple +require_equals !use_delimiter number_of_values(1) value_name("NAME") "Allow reading the given environment variable")
+				(@arg max_instructions: --("max-instructions") +takes_value +require_equals {is_count} value_name("COUNT") "Abort the script after executing the given amount of instructions")
+				(@arg max_heap: --("max-heap") +takes_value +require_equals {is_count} value_name("BYTES") "Abort the script when its values use more than the given amount of memory")
+				(@arg timeout: --timeout +takes_value +require_equals {is_seconds} value_name("SECONDS") "Abort the script after the given time")
+				(@arg profile: --profile "Sample the call stack, writing the time per function to stderr and the call stacks for flamegraph tools to hush-profile.folded")
+				(@arg coverage: --coverage +takes_value +require_equals value_name("FILE") "Count the execution of statements, and write an lcov report to the given file")
 				// The script path must not be a separate parameter because we must prevent clap
 				// from parsing flags to the right of the script path.
-				(@arg arguments: ... +allow_hyphen_values "Script and/or arguments")
+				(@arg arguments: ... +allow_hyphen_values "Script and/or arguments, optionally after --")
 		)
 		.setting(AppSettings::TrailingVarArg);
 
@@ -60,8 +106,10 @@ where
 			let script_path = match arguments.next() {
 				None => None,
 				Some(b"-") => None,
+				Some(arg) if explicit_script => Some(ffi::os_str(arg).into_owned().into()),
 				Some(arg) => {
-					let path = Path::new(OsStr::from_bytes(arg));
+					let path = ffi::os_str(arg);
+					let path = Path::new(&path);
 					if path.is_file() {
 						Some(path.to_owned())
 					} else {
@@ -81,6 +129,12 @@ where
 						print_lexemes: matches.is_present("lex"),
 						print_ast: matches.is_present("ast"),
 						print_program: matches.is_present("program"),
+						dry_run: matches.is_present("dryrun"),
+						xtrace: matches.is_present("xtrace"),
+						policy: policy(&matches),
+						budget: budget(&matches),
+						coverage: matches.value_of_os("coverage").map(PathBuf::from),
+						profile: matches.is_present("profile"),
 						script_args: script_args.into_boxed_slice(),
 					}
 				)
@@ -98,3 +152,56 @@ where
 		}
 	}
 }
+
+
+/// Build the sandbox policy from the allow options. With --sandbox, the kinds of resources
+/// which have no allow options are denied, and otherwise they are allowed.
+fn policy(matches: &ArgMatches) -> Policy {
+	let sandbox = matches.is_present("sandbox");
+
+	Policy {
+		commands: permission(matches, "allow_command", sandbox).map(Box::from),
+		paths: permission(matches, "allow_path", sandbox).map(PathBuf::from),
+		network: permission(matches, "allow_host", sandbox).map(|host| host.to_string_lossy().into()),
+		env: permission(matches, "allow_env", sandbox).map(Box::from),
+	}
+}
+
+
+/// The permission for a kind of resource, from it's allow option.
+fn permission<'a>(matches: &'a ArgMatches, name: &str, sandbox: bool) -> Permission<&'a OsStr> {
+	match matches.values_of_os(name) {
+		Some(values) => Permission::Only(values.collect()),
+		None if sandbox => Permission::Deny,
+		None => Permission::Allow,
+	}
+}
+
+
+/// Build the budget from the limit options. The values have been validated.
+fn budget(matches: &ArgMatches) -> Budget {
+	Budget {
+		instructions: matches.value_of("max_instructions").and_then(|count| count.parse().ok()),
+		time: matches
+			.value_of("timeout")
+			.and_then(|seconds| seconds.parse().ok())
+			.map(Duration::from_secs_f64),
+		heap: matches.value_of("max_heap").and_then(|bytes| bytes.parse().ok()),
+	}
+}
+
+
+fn is_count(value: String) -> Result<(), String> {
+	value
+		.parse::<u64>()
+		.map(|_| ())
+		.map_err(|_| format!("invalid count: {}", value))
+}
+
+
+fn is_seconds(value: String) -> Result<(), String> {
+	match value.parse::<f64>() {
+		Ok(seconds) if seconds >= 0.0 && seconds < u64::MAX as f64 => Ok(()),
+		_ => Err(format!("invalid amount of seconds: {}", value)),
+	}
+}
@@ -0,0 +1,154 @@
+//! Conversions between byte strings, which are the strings of the language, and the
+//! strings of the operating system. On Unix, both are plain bytes. On Windows, operating
+//! system strings are converted from and to WTF-8, and bytes which are not valid WTF-8
+//! are converted lossily.
+
+use std::{borrow::Cow, ffi::OsStr};
+
+#[cfg(unix)]
+pub use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+#[cfg(windows)]
+pub use self::windows::{OsStrExt, OsStringExt};
+
+
+/// Convert bytes to an operating system string, borrowing them when possible.
+#[cfg(unix)]
+pub fn os_str(bytes: &[u8]) -> Cow<'_, OsStr> {
+	Cow::Borrowed(OsStr::from_bytes(bytes))
+}
+
+
+/// Convert bytes to an operating system string, borrowing them when possible.
+#[cfg(windows)]
+pub fn os_str(bytes: &[u8]) -> Cow<'_, OsStr> {
+	if is_wtf8(bytes) {
+		// Safety: the bytes were checked to be valid WTF-8, which is the encoding of OsStr.
+		Cow::Borrowed(unsafe { OsStr::from_encoded_bytes_unchecked(bytes) })
+	} else {
+		Cow::Owned(String::from_utf8_lossy(bytes).into_owned().into())
+	}
+}
+
+
+/// Check whether the bytes are valid WTF-8: UTF-8 which may contain unpaired surrogates.
+#[cfg(any(windows, test))]
+fn is_wtf8(bytes: &[u8]) -> bool {
+	let mut ix = 0;
+	// Whether the previous code point is a leading surrogate, which must not be followed by
+	// a trailing surrogate, as the pair must be encoded as a single code point instead.
+	let mut leading = false;
+
+	while ix < bytes.len() {
+		let first = bytes[ix];
+
+		let (len, min) = match first {
+			0x00 ..= 0x7F => {
+				leading = false;
+				ix += 1;
+				continue;
+			}
+			0xC2 ..= 0xDF => (2, 0x80),
+			0xE0 ..= 0xEF => (3, 0x800),
+			0xF0 ..= 0xF4 => (4, 0x10000),
+			_ => return false,
+		};
+
+		let sequence = match bytes.get(ix .. ix + len) {
+			Some(sequence) => sequence,
+			None => return false,
+		};
+
+		let mut code_point = u32::from(first) & (0x7F >> len);
+		for &byte in &sequence[1 ..] {
+			if byte & 0xC0 != 0x80 {
+				return false;
+			}
+			code_point = (code_point << 6) | u32::from(byte & 0x3F);
+		}
+
+		if code_point < min || code_point > 0x10FFFF {
+			return false;
+		}
+
+		let is_trailing = (0xDC00 ..= 0xDFFF).contains(&code_point);
+		if leading && is_trailing {
+			return false;
+		}
+
+		leading = (0xD800 ..= 0xDBFF).contains(&code_point);
+		ix += len;
+	}
+
+	true
+}
+
+
+#[cfg(windows)]
+mod windows {
+	use std::ffi::{OsStr, OsString};
+
+	use super::is_wtf8;
+
+
+	/// Access the bytes of an operating system string, like the Unix extension trait.
+	pub trait OsStrExt {
+		fn as_bytes(&self) -> &[u8];
+	}
+
+
+	impl OsStrExt for OsStr {
+		fn as_bytes(&self) -> &[u8] {
+			self.as_encoded_bytes()
+		}
+	}
+
+
+	/// Convert operating system strings from and to bytes, like the Unix extension trait.
+	pub trait OsStringExt {
+		fn from_vec(vec: Vec<u8>) -> Self;
+		fn into_vec(self) -> Vec<u8>;
+	}
+
+
+	impl OsStringExt for OsString {
+		fn from_vec(vec: Vec<u8>) -> Self {
+			if is_wtf8(&vec) {
+				// Safety: the bytes were checked to be valid WTF-8.
+				unsafe { OsString::from_encoded_bytes_unchecked(vec) }
+			} else {
+				String::from_utf8_lossy(&vec).into_owned().into()
+			}
+		}
+
+
+		fn into_vec(self) -> Vec<u8> {
+			self.into_encoded_bytes()
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::is_wtf8;
+
+
+	#[test]
+	fn test_wtf8() {
+		assert!(is_wtf8(b""));
+		assert!(is_wtf8("hush ação 🐚".as_bytes()));
+		// Unpaired surrogates.
+		assert!(is_wtf8(b"\xED\xA0\x80"));
+		assert!(is_wtf8(b"\xED\xB0\x80x\xED\xA0\x80"));
+
+		// A surrogate pair must be encoded as a single code point.
+		assert!(!is_wtf8(b"\xED\xA0\x80\xED\xB0\x80"));
+		// Overlong encodings, out of range code points and truncated sequences.
+		assert!(!is_wtf8(b"\xC0\x80"));
+		assert!(!is_wtf8(b"\xE0\x80\x80"));
+		assert!(!is_wtf8(b"\xF4\x90\x80\x80"));
+		assert!(!is_wtf8(b"\xE2\x82"));
+		assert!(!is_wtf8(b"\xFF"));
+	}
+}
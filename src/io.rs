@@ -1,10 +1,307 @@
-use std::os::unix::prelude::{AsRawFd, RawFd};
+#[cfg(unix)]
+mod users;
 
+use std::{
+	cell::RefCell,
+	ffi::OsStr,
+	fs::File,
+	io::Write,
+	path::{Path, PathBuf},
+};
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+#[cfg(windows)]
+use std::os::windows::io::{AsRawHandle, FromRawHandle, IntoRawHandle, RawHandle};
 
+use os_pipe::{PipeReader, PipeWriter};
+
+use crate::{ffi::OsStrExt, runtime::env};
+#[cfg(unix)]
+pub use users::{group, group_id, group_name, groups, user, user_home, user_id, user_name, users, Group, User};
+
+
+/// Get the home directory of the given user. Windows has no user database to look it up.
+#[cfg(windows)]
+pub fn user_home(_user: &[u8]) -> Option<PathBuf> {
+	None
+}
+
+
+/// A file descriptor in redirections. On Windows, only the standard ones are supported.
+#[cfg(unix)]
 pub type FileDescriptor = RawFd;
 
+/// A file descriptor in redirections. On Windows, only the standard ones are supported.
+#[cfg(windows)]
+pub type FileDescriptor = std::os::raw::c_int;
+
 
 /// Get the file descriptor for stdout.
+#[cfg(unix)]
 pub fn stdout_fd() -> FileDescriptor {
 	std::io::stdout().as_raw_fd()
 }
+
+
+/// Get the file descriptor for stdout.
+#[cfg(windows)]
+pub fn stdout_fd() -> FileDescriptor {
+	1
+}
+
+
+/// Get the file descriptor for stderr.
+#[cfg(unix)]
+pub fn stderr_fd() -> FileDescriptor {
+	std::io::stderr().as_raw_fd()
+}
+
+
+/// Get the file descriptor for stderr.
+#[cfg(windows)]
+pub fn stderr_fd() -> FileDescriptor {
+	2
+}
+
+
+/// The standard file descriptors saved by `replace_stdio`, which are restored on drop.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct SavedStdio([RawFd; 3]);
+
+
+#[cfg(unix)]
+impl Drop for SavedStdio {
+	fn drop(&mut self) {
+		flush_stdio();
+
+		for (target, &saved) in self.0.iter().enumerate() {
+			if saved >= 0 {
+				// Safety: dup2 and close have no memory safety requirements.
+				unsafe {
+					libc::dup2(saved, target as RawFd);
+					libc::close(saved);
+				}
+			}
+		}
+	}
+}
+
+
+/// Temporarily replace the standard file descriptors (stdin, stdout and stderr) of the
+/// process. They are restored when the returned value is dropped.
+#[cfg(unix)]
+pub fn replace_stdio(
+	stdin: &PipeReader,
+	stdout: &PipeWriter,
+	stderr: &PipeWriter,
+) -> std::io::Result<SavedStdio> {
+	flush_stdio();
+
+	let fds = [stdin.as_raw_fd(), stdout.as_raw_fd(), stderr.as_raw_fd()];
+	let mut saved = SavedStdio([-1; 3]);
+
+	for (target, &fd) in fds.iter().enumerate() {
+		let target = target as RawFd;
+
+		// Close on exec, so that processes spawned in the meantime don't inherit the saved fd.
+		// Safety: fcntl with F_DUPFD_CLOEXEC has no memory safety requirements.
+		let saved_fd = unsafe { libc::fcntl(target, libc::F_DUPFD_CLOEXEC, 0) };
+		if saved_fd < 0 {
+			return Err(std::io::Error::last_os_error()); // Drop restores the previous fds.
+		}
+		saved.0[target as usize] = saved_fd;
+
+		// Safety: dup2 has no memory safety requirements.
+		if unsafe { libc::dup2(fd, target) } < 0 {
+			return Err(std::io::Error::last_os_error());
+		}
+	}
+
+	Ok(saved)
+}
+
+
+/// The standard handles of the process, in the order of the standard file descriptors.
+#[cfg(windows)]
+const STD_HANDLES: [windows_sys::Win32::System::Console::STD_HANDLE; 3] = [
+	windows_sys::Win32::System::Console::STD_INPUT_HANDLE,
+	windows_sys::Win32::System::Console::STD_OUTPUT_HANDLE,
+	windows_sys::Win32::System::Console::STD_ERROR_HANDLE,
+];
+
+
+/// The standard handles saved by `replace_stdio`, which are restored on drop.
+#[cfg(windows)]
+#[derive(Debug)]
+pub struct SavedStdio([Option<RawHandle>; 3]);
+
+
+#[cfg(windows)]
+impl Drop for SavedStdio {
+	fn drop(&mut self) {
+		flush_stdio();
+
+		for (&id, &saved) in STD_HANDLES.iter().zip(self.0.iter()) {
+			if let Some(saved) = saved {
+				// Safety: SetStdHandle doesn't take ownership of the handle.
+				unsafe { windows_sys::Win32::System::Console::SetStdHandle(id, saved as _) };
+			}
+		}
+	}
+}
+
+
+/// Temporarily replace the standard handles (stdin, stdout and stderr) of the process.
+/// They are restored when the returned value is dropped. The given handles must outlive
+/// the returned value.
+#[cfg(windows)]
+pub fn replace_stdio(
+	stdin: &PipeReader,
+	stdout: &PipeWriter,
+	stderr: &PipeWriter,
+) -> std::io::Result<SavedStdio> {
+	use windows_sys::Win32::System::Console::{GetStdHandle, SetStdHandle};
+
+	flush_stdio();
+
+	let handles = [stdin.as_raw_handle(), stdout.as_raw_handle(), stderr.as_raw_handle()];
+	let mut saved = SavedStdio([None; 3]);
+
+	for (ix, (&id, &handle)) in STD_HANDLES.iter().zip(handles.iter()).enumerate() {
+		// Safety: GetStdHandle and SetStdHandle don't take ownership of the handles.
+		unsafe {
+			saved.0[ix] = Some(GetStdHandle(id) as RawHandle);
+
+			if SetStdHandle(id, handle as _) == 0 {
+				return Err(std::io::Error::last_os_error()); // Drop restores the previous handles.
+			}
+		}
+	}
+
+	Ok(saved)
+}
+
+
+/// Use a file as the read end of a pipe, for redirections.
+pub fn file_reader(file: File) -> PipeReader {
+	// Safety: ownership of the file is transferred to the pipe.
+	#[cfg(unix)]
+	unsafe { PipeReader::from_raw_fd(file.into_raw_fd()) }
+	// Safety: ownership of the file is transferred to the pipe.
+	#[cfg(windows)]
+	unsafe { PipeReader::from_raw_handle(file.into_raw_handle()) }
+}
+
+
+/// Use a file as the write end of a pipe, for redirections.
+pub fn file_writer(file: File) -> PipeWriter {
+	// Safety: ownership of the file is transferred to the pipe.
+	#[cfg(unix)]
+	unsafe { PipeWriter::from_raw_fd(file.into_raw_fd()) }
+	// Safety: ownership of the file is transferred to the pipe.
+	#[cfg(windows)]
+	unsafe { PipeWriter::from_raw_handle(file.into_raw_handle()) }
+}
+
+
+/// Flush the buffered standard outputs, so that no output is written to the wrong file
+/// descriptor.
+pub fn flush_stdio() {
+	let _ = std::io::stdout().flush();
+	let _ = std::io::stderr().flush();
+}
+
+
+/// Change the current directory, updating the PWD and OLDPWD environment variables.
+pub fn change_dir<P: AsRef<Path>>(dir: P) -> std::io::Result<()> {
+	let previous = std::env::current_dir();
+
+	std::env::set_current_dir(dir)?;
+
+	if let Ok(previous) = previous {
+		env::set_var(OsStr::new("OLDPWD"), Some(previous.as_os_str()));
+	}
+
+	if let Ok(current) = std::env::current_dir() {
+		env::set_var(OsStr::new("PWD"), Some(current.as_os_str()));
+	}
+
+	Ok(())
+}
+
+
+/// The environment variable with the home directory of the current user.
+#[cfg(unix)]
+pub const HOME: &str = "HOME";
+#[cfg(windows)]
+pub const HOME: &str = "USERPROFILE";
+
+
+/// The path of the null device.
+#[cfg(unix)]
+pub const NULL: &str = "/dev/null";
+#[cfg(windows)]
+pub const NULL: &str = "NUL";
+
+
+/// Resolve the target directory of the cd command. No target means the home directory,
+/// and `-` means the previous directory.
+pub fn cd_target(target: Option<&OsStr>) -> std::io::Result<PathBuf> {
+	let (var, dir) = match target {
+		None => (HOME, env::var(OsStr::new(HOME))),
+		Some(target) if target.as_bytes() == b"-" => ("OLDPWD", env::var(OsStr::new("OLDPWD"))),
+		Some(target) => return Ok(target.into()),
+	};
+
+	dir
+		.map(PathBuf::from)
+		.ok_or_else(
+			|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("{} not set", var))
+		)
+}
+
+
+thread_local! {
+	/// The directory stack, for pushd and popd.
+	static DIR_STACK: RefCell<Vec<PathBuf>> = RefCell::new(Vec::new());
+}
+
+
+/// Push the current directory to the directory stack, and change to the given directory.
+pub fn push_dir<P: AsRef<Path>>(dir: P) -> std::io::Result<()> {
+	let current = std::env::current_dir()?;
+
+	change_dir(dir)?;
+
+	DIR_STACK.with(|stack| stack.borrow_mut().push(current));
+
+	Ok(())
+}
+
+
+/// Pop a directory from the directory stack, and change to it.
+/// Returns the new current directory.
+pub fn pop_dir() -> std::io::Result<PathBuf> {
+	let dir = DIR_STACK
+		.with(|stack| stack.borrow_mut().pop())
+		.ok_or_else(
+			|| std::io::Error::new(std::io::ErrorKind::Other, "directory stack empty")
+		)?;
+
+	change_dir(&dir)?;
+
+	Ok(dir)
+}
+
+
+/// Get a copy of the directory stack.
+pub fn dir_stack() -> Vec<PathBuf> {
+	DIR_STACK.with(|stack| stack.borrow().clone())
+}
+
+
+/// Replace the directory stack.
+pub fn set_dir_stack(dirs: Vec<PathBuf>) {
+	DIR_STACK.with(|stack| *stack.borrow_mut() = dirs)
+}
@@ -0,0 +1,297 @@
+//! The password and group databases, which are only available on Unix.
+
+use std::{
+	ffi::{CStr, CString, OsStr, OsString},
+	os::unix::ffi::OsStrExt,
+	path::PathBuf,
+};
+
+
+/// Run a reentrant password or group database lookup, growing the buffer while it's too
+/// small. The lookup returns the error status, or the entry if one was found.
+fn database_lookup<T, F>(mut lookup: F) -> Option<T>
+where
+	F: FnMut(&mut [libc::c_char]) -> Result<Option<T>, libc::c_int>,
+{
+	let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+
+	loop {
+		match lookup(&mut buffer) {
+			Ok(entry) => return entry,
+			Err(libc::ERANGE) => buffer.resize(buffer.len() * 2, 0),
+			Err(_) => return None, // Lookup error.
+		}
+	}
+}
+
+
+/// Look up a user in the password database, by name or by id, extracting a field.
+fn passwd_lookup<T, F>(user: Result<&CStr, libc::uid_t>, field: F) -> Option<T>
+where
+	F: Fn(&libc::passwd) -> T,
+{
+	// Safety: passwd is a plain C struct, for which zero is a valid bit pattern.
+	let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+	let mut result = std::ptr::null_mut();
+
+	database_lookup(
+		|buffer| {
+			// Safety: all pointers are valid, and the buffer length is correct.
+			let status = unsafe {
+				match user {
+					Ok(name) => libc::getpwnam_r(
+						name.as_ptr(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result
+					),
+					Err(uid) => libc::getpwuid_r(
+						uid, &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result
+					),
+				}
+			};
+
+			match status {
+				0 if result.is_null() => Ok(None), // User not found.
+				0 => Ok(Some(field(&passwd))),
+				error => Err(error),
+			}
+		}
+	)
+}
+
+
+/// Look up a group in the group database, by name or by id, extracting a field.
+fn group_lookup<T, F>(group: Result<&CStr, libc::gid_t>, field: F) -> Option<T>
+where
+	F: Fn(&libc::group) -> T,
+{
+	// Safety: group is a plain C struct, for which zero is a valid bit pattern.
+	let mut entry: libc::group = unsafe { std::mem::zeroed() };
+	let mut result = std::ptr::null_mut();
+
+	database_lookup(
+		|buffer| {
+			// Safety: all pointers are valid, and the buffer length is correct.
+			let status = unsafe {
+				match group {
+					Ok(name) => libc::getgrnam_r(
+						name.as_ptr(), &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result
+					),
+					Err(gid) => libc::getgrgid_r(
+						gid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result
+					),
+				}
+			};
+
+			match status {
+				0 if result.is_null() => Ok(None), // Group not found.
+				0 => Ok(Some(field(&entry))),
+				error => Err(error),
+			}
+		}
+	)
+}
+
+
+/// Copy a string from a database entry.
+///
+/// # Safety
+/// The pointer must point to a nul terminated string.
+unsafe fn entry_string(string: *const libc::c_char) -> OsString {
+	OsStr::from_bytes(CStr::from_ptr(string).to_bytes()).to_owned()
+}
+
+
+/// Get the home directory of the given user from the password database.
+pub fn user_home(user: &[u8]) -> Option<PathBuf> {
+	let user = CString::new(user).ok()?;
+
+	// Safety: on success, pw_dir points to a nul terminated string inside the buffer.
+	passwd_lookup(Ok(&user), |passwd| unsafe { entry_string(passwd.pw_dir) }.into())
+}
+
+
+/// Get the name of the given user id from the password database.
+pub fn user_name(uid: libc::uid_t) -> Option<OsString> {
+	// Safety: on success, pw_name points to a nul terminated string inside the buffer.
+	passwd_lookup(Err(uid), |passwd| unsafe { entry_string(passwd.pw_name) })
+}
+
+
+/// Get the id of the given user name from the password database.
+pub fn user_id(user: &[u8]) -> Option<libc::uid_t> {
+	let user = CString::new(user).ok()?;
+	passwd_lookup(Ok(&user), |passwd| passwd.pw_uid)
+}
+
+
+/// Get the name of the given group id from the group database.
+pub fn group_name(gid: libc::gid_t) -> Option<OsString> {
+	// Safety: on success, gr_name points to a nul terminated string inside the buffer.
+	group_lookup(Err(gid), |group| unsafe { entry_string(group.gr_name) })
+}
+
+
+/// Get the id of the given group name from the group database.
+pub fn group_id(group: &[u8]) -> Option<libc::gid_t> {
+	let group = CString::new(group).ok()?;
+	group_lookup(Ok(&group), |group| group.gr_gid)
+}
+
+
+/// An entry of the password database.
+#[derive(Debug)]
+pub struct User {
+	pub name: OsString,
+	pub uid: libc::uid_t,
+	pub gid: libc::gid_t,
+	/// The user information field, usually the full name.
+	pub gecos: OsString,
+	pub home: PathBuf,
+	pub shell: OsString,
+}
+
+
+impl User {
+	/// Copy a user from a database entry.
+	///
+	/// # Safety
+	/// The string fields of the entry must be null or point to nul terminated strings.
+	unsafe fn from_entry(passwd: &libc::passwd) -> Self {
+		Self {
+			name: optional_entry_string(passwd.pw_name),
+			uid: passwd.pw_uid,
+			gid: passwd.pw_gid,
+			gecos: optional_entry_string(passwd.pw_gecos),
+			home: optional_entry_string(passwd.pw_dir).into(),
+			shell: optional_entry_string(passwd.pw_shell),
+		}
+	}
+}
+
+
+/// An entry of the group database.
+#[derive(Debug)]
+pub struct Group {
+	pub name: OsString,
+	pub gid: libc::gid_t,
+	/// The names of the users which have the group as a supplementary group.
+	pub members: Vec<OsString>,
+}
+
+
+impl Group {
+	/// Copy a group from a database entry.
+	///
+	/// # Safety
+	/// The name must be null or point to a nul terminated string, and the members must be
+	/// null or point to a null terminated array of nul terminated strings.
+	unsafe fn from_entry(group: &libc::group) -> Self {
+		let mut members = Vec::new();
+
+		if !group.gr_mem.is_null() {
+			let mut member = group.gr_mem;
+			while !(*member).is_null() {
+				members.push(entry_string(*member));
+				member = member.add(1);
+			}
+		}
+
+		Self {
+			name: optional_entry_string(group.gr_name),
+			gid: group.gr_gid,
+			members,
+		}
+	}
+}
+
+
+/// Copy a string from a database entry, which may be null.
+///
+/// # Safety
+/// The pointer must be null or point to a nul terminated string.
+unsafe fn optional_entry_string(string: *const libc::c_char) -> OsString {
+	if string.is_null() {
+		OsString::new()
+	} else {
+		entry_string(string)
+	}
+}
+
+
+/// Look up a user in the password database, by name or by id.
+pub fn user(user: Result<&[u8], libc::uid_t>) -> Option<User> {
+	// Safety: on success, the entry's strings point inside the buffer.
+	let lookup = |user: Result<&CStr, libc::uid_t>| passwd_lookup(
+		user,
+		|passwd| unsafe { User::from_entry(passwd) }
+	);
+
+	match user {
+		Ok(name) => lookup(Ok(&CString::new(name).ok()?)),
+		Err(uid) => lookup(Err(uid)),
+	}
+}
+
+
+/// Look up a group in the group database, by name or by id.
+pub fn group(group: Result<&[u8], libc::gid_t>) -> Option<Group> {
+	// Safety: on success, the entry's strings point inside the buffer.
+	let lookup = |group: Result<&CStr, libc::gid_t>| group_lookup(
+		group,
+		|entry| unsafe { Group::from_entry(entry) }
+	);
+
+	match group {
+		Ok(name) => lookup(Ok(&CString::new(name).ok()?)),
+		Err(gid) => lookup(Err(gid)),
+	}
+}
+
+
+/// Get all entries of the password database.
+pub fn users() -> Vec<User> {
+	let mut users = Vec::new();
+
+	// Safety: the entries returned by getpwent are valid until the next call, and they are
+	// copied before that. The enumeration is not reentrant, but the shell only enumerates
+	// from the main thread.
+	unsafe {
+		libc::setpwent();
+
+		loop {
+			let entry = libc::getpwent();
+			if entry.is_null() {
+				break;
+			}
+			users.push(User::from_entry(&*entry));
+		}
+
+		libc::endpwent();
+	}
+
+	users
+}
+
+
+/// Get all entries of the group database.
+pub fn groups() -> Vec<Group> {
+	let mut groups = Vec::new();
+
+	// Safety: the entries returned by getgrent are valid until the next call, and they are
+	// copied before that. The enumeration is not reentrant, but the shell only enumerates
+	// from the main thread.
+	unsafe {
+		libc::setgrent();
+
+		loop {
+			let entry = libc::getgrent();
+			if entry.is_null() {
+				break;
+			}
+			groups.push(Group::from_entry(&*entry));
+		}
+
+		libc::endgrent();
+	}
+
+	groups
+}
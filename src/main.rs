@@ -1,6 +1,7 @@
 #![allow(dead_code)] // This is temporarily used for the inital development.
 
 mod args;
+mod ffi;
 mod fmt;
 mod io;
 mod runtime;
@@ -11,20 +12,27 @@ mod term;
 #[cfg(test)]
 mod tests;
 
-use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
 
+use ffi::OsStrExt;
 use term::color;
 
 use args::{Args, Command};
 use runtime::{Panic, SourcePos, Runtime};
 
 
+/// The file to which the call stacks are written when profiling, in the working directory.
+const PROFILE_PATH: &str = "hush-profile.folded";
+
+
 #[derive(Debug)]
 enum ExitStatus {
 	Success,
 	InvalidArgs,
 	StaticError,
 	Panic,
+	/// std.exit was called with the given status.
+	Exit(u8),
 }
 
 
@@ -35,6 +43,7 @@ impl From<ExitStatus> for i32 {
 			ExitStatus::InvalidArgs => 1,
 			ExitStatus::StaticError => 2,
 			ExitStatus::Panic => 127,
+			ExitStatus::Exit(code) => code.into(),
 		}
 	}
 }
@@ -93,7 +102,8 @@ fn run(args: Args) -> ExitStatus {
 	};
 
 	// ----------------------------------------------------------------------------------------
-	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	let arena = syntax::Arena::new();
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner, &arena);
 	let has_syntax_errors = !syntactic_analysis.is_ok();
 
 	if has_syntax_errors {
@@ -161,6 +171,9 @@ fn run(args: Args) -> ExitStatus {
 		println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
 	}
 
+	// The program doesn't refer to the AST, which may then be released before running.
+	drop(arena);
+
 	// ----------------------------------------------------------------------------------------
 	if has_syntax_errors {
 		return ExitStatus::StaticError;
@@ -176,11 +189,74 @@ fn run(args: Args) -> ExitStatus {
 		interner
 	);
 
-	match runtime.eval(program) {
+	let options = runtime.options_mut();
+	options.dry_run = args.dry_run;
+	options.xtrace = args.xtrace;
+
+	runtime.set_policy(args.policy);
+	runtime.set_budget(args.budget);
+
+	// Scripts may change the working directory, so paths are resolved beforehand.
+	let coverage_path = args.coverage.map(absolute);
+	let profile_path = args.profile.then(|| absolute(PROFILE_PATH.into()));
+
+	if coverage_path.is_some() {
+		runtime.enable_coverage();
+	}
+
+	if profile_path.is_some() {
+		runtime.enable_profiling();
+	}
+
+	// The runtime is dropped before returning, releasing any resources still held by the
+	// script, which is why std.exit unwinds instead of terminating the process.
+	let result = runtime.eval(program);
+
+	// Reports are written even if the script panicked, as failed tests are covered too.
+	let mut reports = Ok(());
+
+	if let (Some(path), Some(coverage)) = (&coverage_path, runtime.coverage()) {
+		reports = reports.and_then(
+			|()| std::fs::File::create(path)
+				.and_then(|file| coverage.write_lcov(runtime.interner(), std::io::BufWriter::new(file)))
+		);
+	}
+
+	if let (Some(path), Some(profiler)) = (&profile_path, runtime.profiler()) {
+		reports = reports
+			.and_then(
+				|()| std::fs::File::create(path)
+					.and_then(|file| profiler.write_folded(runtime.interner(), std::io::BufWriter::new(file)))
+			)
+			.and_then(|()| profiler.write_report(runtime.interner(), std::io::stderr().lock()));
+	}
+
+	if let Err(error) = reports {
+		eprintln!(
+			"{}",
+			fmt::Show(
+				Panic::io(error, SourcePos::file(program.source)),
+				runtime.interner()
+			)
+		);
+		return ExitStatus::Panic;
+	}
+
+	match result {
     Ok(_) => ExitStatus::Success,
+    Err(Panic::Exit { code }) => ExitStatus::Exit(code),
     Err(panic) => {
 			eprintln!("{}", fmt::Show(panic, runtime.interner()));
 			ExitStatus::Panic
 		}
 	}
 }
+
+
+/// Resolve a path against the working directory.
+fn absolute(path: PathBuf) -> PathBuf {
+	match std::env::current_dir() {
+		Ok(dir) => dir.join(path),
+		Err(_) => path,
+	}
+}
@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use super::heap;
+
+
+/// The amount of instructions between checks of the time and heap limits, which are more
+/// expensive to check than the instruction limit.
+const CHECK_INTERVAL: u64 = 1024;
+
+
+/// Limits on the resources used by the script itself, for embedding and CI use. Exceeding
+/// a limit aborts the script with a panic which can't be caught.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Budget {
+	/// The maximum amount of executed instructions.
+	pub instructions: Option<u64>,
+	/// The maximum wall-clock time.
+	pub time: Option<Duration>,
+	/// The maximum amount of bytes allocated for arrays, dicts, strings and buffers.
+	pub heap: Option<usize>,
+}
+
+
+/// A limit of the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+	Instructions,
+	Time,
+	Heap,
+}
+
+
+/// The resources used so far, checked against a budget. Time and heap usage are measured
+/// from the creation of the meter.
+#[derive(Debug)]
+pub struct Meter {
+	budget: Budget,
+	instructions: u64,
+	start: Instant,
+	/// The bytes which were already allocated when the meter was created.
+	heap_start: usize,
+}
+
+
+impl Meter {
+	pub fn new(budget: Budget) -> Self {
+		Self {
+			budget,
+			instructions: 0,
+			start: Instant::now(),
+			heap_start: heap::used(),
+		}
+	}
+
+
+	/// The budget being checked.
+	pub fn budget(&self) -> Budget {
+		self.budget
+	}
+
+
+	/// Account for an executed instruction. The time and heap limits are only checked
+	/// periodically.
+	#[inline]
+	pub fn tick(&mut self) -> Result<(), Limit> {
+		self.instructions += 1;
+
+		if matches!(self.budget.instructions, Some(max) if self.instructions > max) {
+			return Err(Limit::Instructions);
+		}
+
+		if self.instructions % CHECK_INTERVAL == 0 {
+			self.check()
+		} else {
+			Ok(())
+		}
+	}
+
+
+	/// Check the time and heap limits. Garbage is collected before giving up on the heap
+	/// limit, so that only live values count.
+	fn check(&self) -> Result<(), Limit> {
+		if matches!(self.budget.time, Some(max) if self.start.elapsed() > max) {
+			return Err(Limit::Time);
+		}
+
+		if let Some(max) = self.budget.heap {
+			let exceeded = || heap::used().saturating_sub(self.heap_start) > max;
+
+			if exceeded() {
+				gc::force_collect();
+
+				if exceeded() {
+					return Err(Limit::Heap);
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+
+impl Default for Meter {
+	fn default() -> Self {
+		Self::new(Budget::default())
+	}
+}
+
+
+impl std::fmt::Display for Limit {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Instructions => write!(f, "instruction limit"),
+			Self::Time => write!(f, "time limit"),
+			Self::Heap => write!(f, "heap limit"),
+		}
+	}
+}
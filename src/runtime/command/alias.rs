@@ -0,0 +1,112 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+
+thread_local! {
+	/// The alias table, sorted by name. Aliases are only used in the main thread, as
+	/// built-ins are not allowed in asynchronous contexts.
+	static ALIASES: RefCell<BTreeMap<Box<[u8]>, Box<[u8]>>> = RefCell::new(BTreeMap::new());
+}
+
+
+/// Define an alias, returning the previous definition.
+pub fn define(name: Box<[u8]>, value: Box<[u8]>) -> Option<Box<[u8]>> {
+	ALIASES.with(|aliases| aliases.borrow_mut().insert(name, value))
+}
+
+
+/// Remove an alias, returning it's definition.
+pub fn remove(name: &[u8]) -> Option<Box<[u8]>> {
+	ALIASES.with(|aliases| aliases.borrow_mut().remove(name))
+}
+
+
+/// Remove all aliases.
+pub fn clear() {
+	ALIASES.with(|aliases| aliases.borrow_mut().clear())
+}
+
+
+/// Get the definition of an alias.
+pub fn get(name: &[u8]) -> Option<Box<[u8]>> {
+	ALIASES.with(|aliases| aliases.borrow().get(name).cloned())
+}
+
+
+/// Get all aliases, sorted by name.
+pub fn list() -> Vec<(Box<[u8]>, Box<[u8]>)> {
+	ALIASES.with(
+		|aliases| aliases
+			.borrow()
+			.iter()
+			.map(|(name, value)| (name.clone(), value.clone()))
+			.collect()
+	)
+}
+
+
+/// Expand an alias into it's words. Aliases are not expanded recursively, and empty
+/// aliases are not expanded at all.
+pub fn expand(name: &[u8]) -> Option<Vec<Box<[u8]>>> {
+	let value = get(name)?;
+	let words = words(&value)?;
+
+	if words.is_empty() {
+		None
+	} else {
+		Some(words)
+	}
+}
+
+
+/// Split the value of an alias into words, which are separated by whitespace, like the
+/// arguments of a command. Whitespace is kept when quoted or escaped: single quotes keep
+/// their contents as they are, double quotes allow escaping quotes and backslashes, and
+/// backslashes escape any character outside quotes. Values with unterminated quotes or
+/// trailing backslashes are invalid.
+pub fn words(value: &[u8]) -> Option<Vec<Box<[u8]>>> {
+	let mut words = Vec::new();
+	let mut word: Option<Vec<u8>> = None;
+	let mut bytes = value.iter().copied();
+
+	while let Some(byte) = bytes.next() {
+		match byte {
+			byte if byte.is_ascii_whitespace() => words.extend(word.take().map(Vec::into_boxed_slice)),
+
+			b'\\' => word.get_or_insert_with(Vec::new).push(bytes.next()?),
+
+			b'\'' => {
+				let word = word.get_or_insert_with(Vec::new);
+
+				loop {
+					match bytes.next()? {
+						b'\'' => break,
+						byte => word.push(byte),
+					}
+				}
+			}
+
+			b'"' => {
+				let word = word.get_or_insert_with(Vec::new);
+
+				loop {
+					match bytes.next()? {
+						b'"' => break,
+
+						b'\\' => match bytes.next()? {
+							byte @ (b'"' | b'\\') => word.push(byte),
+							byte => word.extend([ b'\\', byte ]),
+						},
+
+						byte => word.push(byte),
+					}
+				}
+			}
+
+			byte => word.get_or_insert_with(Vec::new).push(byte),
+		}
+	}
+
+	words.extend(word.map(Vec::into_boxed_slice));
+
+	Some(words)
+}
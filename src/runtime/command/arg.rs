@@ -1,9 +1,9 @@
 use std::{
 	borrow::Cow,
 	ffi::OsString,
-	os::unix::ffi::OsStringExt,
 };
 
+use crate::ffi::OsStringExt;
 use super::exec;
 
 
@@ -157,7 +157,7 @@ impl Args {
 
 
 	fn is_pattern_meta(c: u8) -> bool {
-		matches!(c, b'?' | b'*' | b'[' | b']')
+		matches!(c, b'?' | b'*' | b'[' | b']' | b'(')
 	}
 }
 
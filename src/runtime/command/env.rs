@@ -0,0 +1,155 @@
+use std::{
+	cell::RefCell,
+	collections::BTreeMap,
+	ffi::{OsStr, OsString},
+	process,
+	sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+
+/// Changes to the exported variables, which are applied to commands when spawned. The
+/// environment of the shell process is never changed, as that is unsound while other
+/// threads may read it. Removed variables are kept as None, so that they are removed from
+/// the environment of commands.
+static EXPORTS: RwLock<BTreeMap<OsString, Option<OsString>>> = RwLock::new(BTreeMap::new());
+
+
+thread_local! {
+	/// The shell-local variables, which are not passed to commands.
+	static LOCALS: RefCell<BTreeMap<OsString, OsString>> = RefCell::new(BTreeMap::new());
+}
+
+
+fn exports() -> RwLockReadGuard<'static, BTreeMap<OsString, Option<OsString>>> {
+	// The map remains consistent even if a thread panicked while holding the lock.
+	EXPORTS.read().unwrap_or_else(|error| error.into_inner())
+}
+
+
+fn exports_mut() -> RwLockWriteGuard<'static, BTreeMap<OsString, Option<OsString>>> {
+	EXPORTS.write().unwrap_or_else(|error| error.into_inner())
+}
+
+
+/// Get a variable, either local or exported.
+pub fn get(name: &OsStr) -> Option<OsString> {
+	LOCALS
+		.with(|locals| locals.borrow().get(name).cloned())
+		.or_else(|| var(name))
+}
+
+
+/// Get an exported variable, which is passed to commands.
+pub fn var(name: &OsStr) -> Option<OsString> {
+	match exports().get(name) {
+		Some(value) => value.clone(),
+		None => std::env::var_os(name),
+	}
+}
+
+
+/// Set or remove an exported variable, regardless of whether it's local.
+pub fn set_var(name: &OsStr, value: Option<&OsStr>) {
+	exports_mut().insert(name.to_owned(), value.map(ToOwned::to_owned));
+}
+
+
+/// Set a variable. Exported variables remain exported, and other variables are local.
+pub fn set(name: &OsStr, value: &OsStr) {
+	if is_exported(name) {
+		set_var(name, Some(value));
+	} else {
+		LOCALS.with(|locals| locals.borrow_mut().insert(name.to_owned(), value.to_owned()));
+	}
+}
+
+
+/// Export a variable, so that it's passed to commands. If no value is given, the local
+/// value is exported. Returns whether the variable is defined.
+pub fn export(name: &OsStr, value: Option<&OsStr>) -> bool {
+	let local = LOCALS.with(|locals| locals.borrow_mut().remove(name));
+
+	match value.map(ToOwned::to_owned).or(local) {
+		Some(value) => {
+			set_var(name, Some(&value));
+			true
+		}
+
+		None => is_exported(name),
+	}
+}
+
+
+/// Remove a variable, either local or exported.
+pub fn unset(name: &OsStr) {
+	LOCALS.with(|locals| locals.borrow_mut().remove(name));
+	set_var(name, None);
+}
+
+
+/// Check whether the variable is exported.
+pub fn is_exported(name: &OsStr) -> bool {
+	var(name).is_some()
+}
+
+
+/// Get the exported variables.
+pub fn vars() -> Vec<(OsString, OsString)> {
+	let exports = exports();
+
+	let mut vars: BTreeMap<OsString, OsString> = std::env::vars_os()
+		.filter(|(name, _)| !exports.contains_key(name))
+		.collect();
+
+	for (name, value) in exports.iter() {
+		if let Some(value) = value {
+			vars.insert(name.clone(), value.clone());
+		}
+	}
+
+	vars.into_iter().collect()
+}
+
+
+/// Apply the changes to the exported variables to the environment of the command.
+pub fn apply(command: &mut process::Command) {
+	for (name, value) in exports().iter() {
+		match value {
+			Some(value) => command.env(name, value),
+			None => command.env_remove(name),
+		};
+	}
+}
+
+
+/// Get the local variables, sorted by name.
+pub fn locals() -> Vec<(OsString, OsString)> {
+	LOCALS.with(
+		|locals| locals
+			.borrow()
+			.iter()
+			.map(|(name, value)| (name.clone(), value.clone()))
+			.collect()
+	)
+}
+
+
+/// Replace all local variables.
+pub fn set_locals(variables: Vec<(OsString, OsString)>) {
+	LOCALS.with(|locals| *locals.borrow_mut() = variables.into_iter().collect());
+}
+
+
+/// Get the changes to the exported variables.
+pub fn changes() -> Vec<(OsString, Option<OsString>)> {
+	exports()
+		.iter()
+		.map(|(name, value)| (name.clone(), value.clone()))
+		.collect()
+}
+
+
+/// Replace all changes to the exported variables.
+pub fn set_changes(changes: Vec<(OsString, Option<OsString>)>) {
+	*exports_mut() = changes.into_iter().collect();
+}
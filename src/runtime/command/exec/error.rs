@@ -1,6 +1,6 @@
 use std::{
 	io,
-	collections::HashMap, ffi::OsString,
+	ffi::OsString,
 };
 
 use crate::{
@@ -28,7 +28,26 @@ pub enum Panic {
 	InvalidPattern {
 		pattern: OsString,
 		pos: SourcePos,
-	}
+	},
+	/// Invalid option in the set built-in.
+	InvalidOption {
+		option: OsString,
+		pos: SourcePos,
+	},
+	/// Pattern had no matches, and the failglob option is set.
+	NoMatches {
+		pattern: OsString,
+		pos: SourcePos,
+	},
+	/// A function used as a command panicked. The actual panic is kept by the function
+	/// runner, as it may hold values which can't be sent across threads.
+	FunctionPanic { pos: SourcePos },
+	/// Access denied by the sandbox policy.
+	PolicyViolation {
+		access: &'static str,
+		target: OsString,
+		pos: SourcePos,
+	},
 }
 
 
@@ -47,6 +66,26 @@ impl Panic {
 	pub fn invalid_pattern(pattern: OsString, pos: SourcePos) -> Self {
 		Self::InvalidPattern { pattern, pos }
 	}
+
+	/// Invalid option in the set built-in.
+	pub fn invalid_option(option: OsString, pos: SourcePos) -> Self {
+		Self::InvalidOption { option, pos }
+	}
+
+	/// Pattern had no matches, and the failglob option is set.
+	pub fn no_matches(pattern: OsString, pos: SourcePos) -> Self {
+		Self::NoMatches { pattern, pos }
+	}
+
+	/// A function used as a command panicked.
+	pub fn function_panic(pos: SourcePos) -> Self {
+		Self::FunctionPanic { pos }
+	}
+
+	/// Access denied by the sandbox policy.
+	pub fn policy_violation(access: &'static str, target: OsString, pos: SourcePos) -> Self {
+		Self::PolicyViolation { access, target, pos }
+	}
 }
 
 
@@ -80,6 +119,33 @@ impl std::fmt::Display for Panic {
 					panic,
 					color::Fg(color::Yellow, pattern)
 				),
+
+			Self::InvalidOption { option, .. } =>
+				write!(
+					f,
+					"{}: invalid option ({:?})",
+					panic,
+					color::Fg(color::Yellow, option)
+				),
+
+			Self::NoMatches { pattern, .. } =>
+				write!(
+					f,
+					"{}: no matches for pattern ({:?})",
+					panic,
+					color::Fg(color::Yellow, pattern)
+				),
+
+			Self::FunctionPanic { .. } => write!(f, "{}: function panicked", panic),
+
+			Self::PolicyViolation { access, target, .. } =>
+				write!(
+					f,
+					"{}: {} ({:?}) denied by policy",
+					panic,
+					access,
+					color::Fg(color::Yellow, target)
+				),
 		}
 	}
 }
@@ -96,6 +162,10 @@ impl From<Panic> for crate::runtime::Panic {
 			Panic::InvalidArgs { object, items, pos } => P::invalid_command_args(object, items, pos),
 			Panic::UnsupportedFileDescriptor { fd, pos } => P::unsupported_fd(fd, pos),
 			Panic::InvalidPattern { pattern, pos } => P::invalid_pattern(pattern, pos),
+			Panic::InvalidOption { option, pos } => P::value_error(option.into(), "valid option", pos),
+			Panic::NoMatches { pattern, pos } => P::no_matches(pattern, pos),
+			Panic::FunctionPanic { .. } => unreachable!("function panics should be kept by the runner"),
+			Panic::PolicyViolation { access, target, pos } => P::policy_violation(access, target.into(), pos),
 		}
 	}
 }
@@ -113,7 +183,7 @@ impl IntoValue for ErrorStatus {
 
 		let description = std::mem::take(&mut self.description).into();
 
-		let mut context = HashMap::new();
+		let mut context = value::DictMap::default();
 		STATUS.with(
 			|status| context.insert(status.copy(), Value::Int(self.status as i64))
 		);
@@ -1,11 +1,11 @@
 use std::{
 	fmt::Display,
-	os::unix::ffi::OsStrExt,
 };
 
-use super::{Argument, RedirectionTarget, Redirection, Builtin, BasicCommand, Command, Block};
+use super::{Argument, RedirectionTarget, Redirection, Builtin, Program, BasicCommand, Command, Block};
 
 use crate::{
+	ffi::OsStrExt,
 	syntax::lexer::CommandOperator,
 	fmt::{self, Indentation},
 	term::color,
@@ -36,6 +36,11 @@ impl Display for RedirectionTarget {
 				arg.fmt(f)
 			}
 
+			Self::Clobber(arg) => {
+				">|".fmt(f)?;
+				arg.fmt(f)
+			}
+
 			Self::Append(arg) => {
 				">>".fmt(f)?;
 				arg.fmt(f)
@@ -69,12 +74,17 @@ impl Display for Redirection {
 
 impl Display for Builtin {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		let command = match self {
-			Self::Alias => "alias",
-			Self::Cd => "cd",
-		};
+		color::Fg(color::Green, self.name()).fmt(f)
+	}
+}
+
 
-		color::Fg(color::Green, command).fmt(f)
+impl Display for Program {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::External(program) => program.fmt(f),
+			Self::Function { name, .. } => write!(f, "{}", name.to_string_lossy()),
+		}
 	}
 }
 
@@ -0,0 +1,597 @@
+//! Asynchronous command blocks (`&{ }`), timers and socket reads, which are all waited by a
+//! single reactor thread. Timers back std.time.after and std.time.sleep, and socket waits
+//! back the asynchronous methods of std.net sockets. Any of these are only waited by their
+//! join method, which std.await merely calls, and checked by their is_finished method.
+//! Other IO from the standard library, such as file IO, still blocks the script.
+
+use std::{
+	convert::TryFrom,
+	io,
+	sync::{
+		mpsc,
+		Arc,
+		Condvar,
+		Mutex,
+		MutexGuard,
+		PoisonError,
+	},
+	thread,
+	time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+	os::unix::io::RawFd,
+	sync::atomic::{AtomicI32, Ordering},
+};
+#[cfg(windows)]
+use std::sync::atomic::{AtomicIsize, Ordering};
+
+#[cfg(target_os = "linux")]
+use libc::__errno_location as errno_location;
+
+#[cfg(all(unix, not(target_os = "linux")))]
+use libc::__error as errno_location;
+
+#[cfg(windows)]
+use windows_sys::Win32::{
+	Foundation::HANDLE,
+	Networking::WinSock::{WSAPoll, POLLRDNORM, SOCKET, WSAPOLLFD},
+	System::Threading::{CreateEventW, SetEvent, WaitForMultipleObjects, INFINITE},
+};
+
+use super::{
+	Block,
+	BlockStats,
+	Command,
+	CommandExec,
+	Error,
+	FunctionRunner,
+	Options,
+	Panic,
+	Pipeline,
+	PipelineErrors,
+	Started,
+};
+
+
+/// The queue of the reactor thread, which is started along with the first job.
+static QUEUE: Mutex<Option<mpsc::Sender<Task>>> = Mutex::new(None);
+
+/// The write end of the pipe which wakes the reactor thread, or -1 if it has not been
+/// created yet. A byte is written to it when a child process exits, from the SIGCHLD
+/// handler, and when a job is queued.
+#[cfg(unix)]
+static WAKE: AtomicI32 = AtomicI32::new(-1);
+
+/// The event which wakes the reactor thread, or zero if it has not been created yet. It
+/// is set when a job is queued. Exits are noticed by waiting on the process handles, as
+/// Windows has no SIGCHLD.
+#[cfg(windows)]
+static WAKE: AtomicIsize = AtomicIsize::new(0);
+
+/// The read end of the wake pipe, or the wake event on Windows.
+#[cfg(unix)]
+type Waker = RawFd;
+#[cfg(windows)]
+type Waker = HANDLE;
+
+/// A socket which may be waited by the reactor.
+#[cfg(unix)]
+pub type Source = RawFd;
+#[cfg(windows)]
+pub type Source = SOCKET;
+
+/// The maximum amount of handles that may be waited at once on Windows.
+#[cfg(windows)]
+const MAXIMUM_WAIT_OBJECTS: usize = 64;
+
+/// How long the reactor waits on Windows when there are more processes than handles
+/// that may be waited at once, or sockets, in milliseconds.
+#[cfg(windows)]
+const POLL_TIMEOUT: u32 = 50;
+
+
+/// The result of a command block.
+type Outcome = Result<(Box<[PipelineErrors]>, BlockStats), Panic>;
+
+
+/// The result of a wait: whether the socket became readable, or the deadline passed
+/// first. Errors from the socket are considered readable, so that reading reports them.
+pub type Ready = io::Result<bool>;
+
+
+/// The state of a wait for a timer or socket, shared with the script.
+pub type WaitHandle = JobHandle<Ready>;
+
+
+#[derive(Debug)]
+enum State<T> {
+	Running,
+	Finished(T),
+	Joined,
+}
+
+
+/// The state of an asynchronous command block, or of a wait, shared with the script.
+#[derive(Debug)]
+pub struct JobHandle<T = Outcome> {
+	state: Mutex<State<T>>,
+	finished: Condvar,
+}
+
+
+impl<T> JobHandle<T> {
+	fn new() -> Arc<Self> {
+		Arc::new(
+			Self {
+				state: Mutex::new(State::Running),
+				finished: Condvar::new(),
+			}
+		)
+	}
+
+
+	/// Check whether the block has finished, without waiting.
+	pub fn is_finished(&self) -> bool {
+		!matches!(*self.state(), State::Running)
+	}
+
+
+	/// Wait for the block to finish, and take it's result. Returns None if the result has
+	/// already been taken.
+	pub fn join(&self) -> Option<T> {
+		let mut state = self.state();
+
+		while let State::Running = *state {
+			state = self.finished
+				.wait(state)
+				.unwrap_or_else(PoisonError::into_inner);
+		}
+
+		match std::mem::replace(&mut *state, State::Joined) {
+			State::Finished(outcome) => Some(outcome),
+			_ => None,
+		}
+	}
+
+
+	fn complete(&self, outcome: T) {
+		*self.state() = State::Finished(outcome);
+		self.finished.notify_all();
+	}
+
+
+	fn state(&self) -> MutexGuard<'_, State<T>> {
+		self.state
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+
+/// An asynchronous command block, which is executed by the reactor thread.
+struct Job {
+	commands: std::vec::IntoIter<Command>,
+	options: Options,
+	runner: Box<dyn FunctionRunner + Send>,
+	/// The pipeline that is currently running, if any.
+	running: Option<Pipeline>,
+	errors: Vec<PipelineErrors>,
+	stats: BlockStats,
+	start: Instant,
+	handle: Arc<JobHandle>,
+}
+
+
+/// A wait for a socket to become readable, or for a deadline, executed by the reactor
+/// thread. Either may be missing, but not both.
+struct Wait {
+	source: Option<Source>,
+	deadline: Option<Instant>,
+	/// Whether the socket was found readable by the reactor.
+	ready: bool,
+	handle: Arc<WaitHandle>,
+}
+
+
+impl Wait {
+	fn poll(&mut self) -> Progress {
+		let ready = if self.ready {
+			true
+		} else if self.deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+			false
+		} else {
+			return Progress::Blocked;
+		};
+
+		self.handle.complete(Ok(ready));
+
+		Progress::Finished
+	}
+}
+
+
+/// Work for the reactor thread.
+enum Task {
+	Block(Box<Job>),
+	Wait(Wait),
+}
+
+
+/// The result of polling a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Progress {
+	/// The job is waiting for the running processes, the socket or the deadline.
+	Blocked,
+	Finished,
+}
+
+
+impl Job {
+	/// Advance the job as far as possible without blocking.
+	fn poll(&mut self) -> Progress {
+		let result = match self.advance() {
+			Ok(Progress::Finished) => Ok(std::mem::take(&mut self.errors).into()),
+			Ok(progress) => return progress,
+			Err(error) => Err(error),
+		};
+
+		self.stats.duration = self.start.elapsed();
+
+		self.handle.complete(
+			Block::outcome(result, std::mem::take(&mut self.stats))
+		);
+
+		Progress::Finished
+	}
+
+
+	fn advance(&mut self) -> Result<Progress, Error> {
+		loop {
+			if let Some(pipeline) = self.running.take() {
+				if !pipeline.is_finished() {
+					self.running = Some(pipeline);
+					return Ok(Progress::Blocked);
+				}
+
+				let exec = pipeline.finish(&mut *self.runner)?;
+				if self.record(exec) {
+					return Ok(Progress::Finished);
+				}
+			}
+
+			let command = match self.commands.next() {
+				Some(command) => command,
+				None => return Ok(Progress::Finished),
+			};
+
+			let pos = command.pos();
+			let stdout = os_pipe::dup_stdout()
+				.map_err(|error| Error::io(error, pos.copy()))?;
+			let stderr = os_pipe::dup_stderr()
+				.map_err(|error| Error::io(error, pos))?;
+
+			match command.start(stdout, stderr, &mut self.options, &mut *self.runner)? {
+				Started::Finished(exec) => {
+					if self.record(exec) {
+						return Ok(Progress::Finished);
+					}
+				}
+
+				Started::Running(pipeline) => self.running = Some(pipeline),
+			}
+		}
+	}
+
+
+	/// Record the result of a command, returning whether to abort the block.
+	fn record(&mut self, exec: CommandExec) -> bool {
+		self.stats.processes.extend(exec.processes);
+
+		if !exec.errors.is_empty() {
+			self.errors.push(exec.errors);
+		}
+
+		exec.abort
+	}
+}
+
+
+/// Execute the block in the reactor thread.
+pub fn spawn<R>(block: Block, runner: R) -> Arc<JobHandle>
+where
+	R: FunctionRunner + Send + 'static,
+{
+	let handle = JobHandle::new();
+
+	let commands: Vec<Command> = std::iter::once(block.head)
+		.chain(block.tail.into_vec()) // Use vec's owned iterator.
+		.collect();
+
+	let pos = commands[0].pos();
+
+	let job = Job {
+		commands: commands.into_iter(),
+		options: block.options,
+		runner: Box::new(runner),
+		running: None,
+		errors: Vec::new(),
+		stats: BlockStats::default(),
+		start: Instant::now(),
+		handle: handle.clone(),
+	};
+
+	// Without the pipe, the job can't be run.
+	if let Err(error) = submit(Task::Block(Box::new(job))) {
+		handle.complete(
+			Block::outcome(Err(Error::io(error, pos)), BlockStats::default())
+		);
+	}
+
+	handle
+}
+
+
+/// Wait in the reactor thread until the socket becomes readable, or until the deadline.
+/// Without a socket, this is a timer.
+pub fn wait(source: Option<Source>, deadline: Option<Instant>) -> Arc<WaitHandle> {
+	let handle = JobHandle::new();
+
+	let wait = Wait {
+		source,
+		deadline,
+		ready: false,
+		handle: handle.clone(),
+	};
+
+	if let Err(error) = submit(Task::Wait(wait)) {
+		handle.complete(Err(error));
+	}
+
+	handle
+}
+
+
+/// Queue a task in the reactor thread, starting it if needed.
+fn submit(task: Task) -> io::Result<()> {
+	let mut queue = QUEUE
+		.lock()
+		.unwrap_or_else(PoisonError::into_inner);
+
+	let sender = match &*queue {
+		Some(sender) => sender,
+		None => {
+			let (sender, receiver) = mpsc::channel();
+			start_reactor(receiver)?;
+			queue.insert(sender)
+		}
+	};
+
+	sender
+		.send(task)
+		.expect("reactor thread should be running");
+
+	wake();
+
+	Ok(())
+}
+
+
+/// Create the wake pipe, install the SIGCHLD handler and start the reactor thread.
+#[cfg(unix)]
+fn start_reactor(receiver: mpsc::Receiver<Task>) -> io::Result<()> {
+	let mut fds = [0; 2];
+
+	// Safety: the pointer is valid for two file descriptors.
+	if unsafe { libc::pipe(fds.as_mut_ptr()) } < 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	for &fd in &fds {
+		// Safety: fcntl has no memory safety requirements. The handler must never block,
+		// and the reactor drains the pipe until it's empty.
+		unsafe {
+			libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+			libc::fcntl(fd, libc::F_SETFL, libc::fcntl(fd, libc::F_GETFL) | libc::O_NONBLOCK);
+		}
+	}
+
+	let [read, write] = fds;
+
+	extern "C" fn handler(_: libc::c_int) {
+		// The handler must not change errno for the interrupted code.
+		let errno = io::Error::last_os_error();
+		wake();
+		// Safety: errno is thread local, and the pointer is valid.
+		unsafe { *errno_location() = errno.raw_os_error().unwrap_or(0) };
+	}
+
+	// Safety: sigaction is a plain C struct, for which zero is a valid bit pattern.
+	let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+	action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+	// Stopped children are not waited by the jobs.
+	action.sa_flags = libc::SA_RESTART | libc::SA_NOCLDSTOP;
+
+	// The pipe is created before the handler is installed, so the handler always has
+	// somewhere to write.
+	WAKE.store(write, Ordering::Release);
+
+	// Safety: the handler only writes to a non-blocking pipe, which is async signal safe.
+	unsafe {
+		libc::sigemptyset(&mut action.sa_mask);
+		libc::sigaction(libc::SIGCHLD, &action, std::ptr::null_mut());
+	}
+
+	thread::spawn(move || run(receiver, read));
+
+	Ok(())
+}
+
+
+/// Create the wake event and start the reactor thread.
+#[cfg(windows)]
+fn start_reactor(receiver: mpsc::Receiver<Task>) -> io::Result<()> {
+	// An auto reset event, which is initially unset.
+	// Safety: the optional arguments may be null.
+	let event = unsafe { CreateEventW(std::ptr::null(), 0, 0, std::ptr::null()) };
+
+	if event == 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	WAKE.store(event, Ordering::Release);
+
+	thread::spawn(move || run(receiver, event));
+
+	Ok(())
+}
+
+
+/// Wake the reactor thread. This is async signal safe.
+#[cfg(unix)]
+fn wake() {
+	let fd = WAKE.load(Ordering::Acquire);
+
+	if fd >= 0 {
+		// The pipe may be full, in which case the reactor will be woken anyway.
+		// Safety: the pointer is valid for one byte.
+		unsafe { libc::write(fd, [0u8].as_ptr().cast(), 1) };
+	}
+}
+
+
+/// Wake the reactor thread.
+#[cfg(windows)]
+fn wake() {
+	let event = WAKE.load(Ordering::Acquire);
+
+	if event != 0 {
+		// Safety: the event is never closed.
+		unsafe { SetEvent(event) };
+	}
+}
+
+
+/// How long until the earliest deadline of the waits, if any.
+fn timeout(waits: &[Wait]) -> Option<Duration> {
+	let now = Instant::now();
+
+	waits
+		.iter()
+		.filter_map(|wait| wait.deadline)
+		.min()
+		.map(|deadline| deadline.saturating_duration_since(now))
+}
+
+
+/// Convert a timeout to milliseconds, rounding up so that the deadline has passed when
+/// the reactor wakes.
+fn millis(timeout: Duration) -> u32 {
+	let millis = timeout.as_nanos().div_ceil(1_000_000);
+	u32::try_from(millis).unwrap_or(u32::MAX)
+}
+
+
+/// Block until the reactor is woken, a socket becomes readable or a deadline passes, and
+/// empty the wake pipe.
+#[cfg(unix)]
+fn sleep(wake: Waker, _: &[Job], waits: &mut [Wait]) {
+	let mut fds: Vec<libc::pollfd> = std::iter::once(wake)
+		.chain(waits.iter().filter_map(|wait| wait.source))
+		.map(|fd| libc::pollfd { fd, events: libc::POLLIN, revents: 0 })
+		.collect();
+
+	let timeout = timeout(waits)
+		.map_or(-1, |timeout| millis(timeout).min(i32::MAX as u32) as libc::c_int);
+
+	// Interruptions by signals, such as SIGCHLD itself, wake the reactor as well.
+	// Safety: the pointer is valid for the given amount of pollfds.
+	unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) };
+
+	// Hangups and errors are readable too, as reading reports them.
+	let mut readable = fds[1 ..].iter().map(|fd| fd.revents != 0);
+	for wait in waits.iter_mut().filter(|wait| wait.source.is_some()) {
+		wait.ready |= readable.next().unwrap_or(false);
+	}
+
+	let mut buffer = [0u8; 64];
+	// Safety: the pointer is valid for the length of the buffer.
+	while unsafe { libc::read(wake, buffer.as_mut_ptr().cast(), buffer.len()) } > 0 { }
+}
+
+
+/// Block until the reactor is woken, a running process exits or a deadline passes.
+/// Sockets can't be waited along handles, so they are checked periodically.
+#[cfg(windows)]
+fn sleep(wake: Waker, jobs: &[Job], waits: &mut [Wait]) {
+	let handles: Vec<HANDLE> = std::iter::once(wake)
+		.chain(
+			jobs
+				.iter()
+				.filter_map(|job| job.running.as_ref())
+				.flat_map(Pipeline::handles)
+		)
+		.collect();
+
+	let mut sockets: Vec<WSAPOLLFD> = waits
+		.iter()
+		.filter_map(|wait| wait.source)
+		.map(|fd| WSAPOLLFD { fd, events: POLLRDNORM, revents: 0 })
+		.collect();
+
+	// Beyond the limit, the remaining processes are checked periodically.
+	let count = handles.len().min(MAXIMUM_WAIT_OBJECTS);
+	let mut timeout =
+		if handles.len() <= MAXIMUM_WAIT_OBJECTS && sockets.is_empty() {
+			INFINITE
+		} else {
+			POLL_TIMEOUT
+		};
+
+	if let Some(deadline) = self::timeout(waits) {
+		timeout = timeout.min(millis(deadline));
+	}
+
+	// Safety: the pointer is valid for the given amount of handles, which are owned by
+	// the running pipelines.
+	unsafe { WaitForMultipleObjects(count as u32, handles.as_ptr(), 0, timeout) };
+
+	if sockets.is_empty() {
+		return;
+	}
+
+	// Safety: the pointer is valid for the given amount of sockets.
+	unsafe { WSAPoll(sockets.as_mut_ptr(), sockets.len() as u32, 0) };
+
+	// Hangups and errors are readable too, as reading reports them.
+	let mut readable = sockets.iter().map(|socket| socket.revents != 0);
+	for wait in waits.iter_mut().filter(|wait| wait.source.is_some()) {
+		wait.ready |= readable.next().unwrap_or(false);
+	}
+}
+
+
+/// The reactor thread. Asynchronous command blocks are executed by a single thread, which
+/// starts the commands of each block in order, and checks the running processes whenever
+/// a child process exits or a task is queued, so that any amount of blocks may run
+/// concurrently without a thread each. Likewise, waits are completed when their socket
+/// becomes readable or their deadline passes. The pipe is emptied before checking, so
+/// that exits during the checks wake the reactor again.
+fn run(receiver: mpsc::Receiver<Task>, wake: Waker) {
+	let mut jobs: Vec<Job> = Vec::new();
+	let mut waits: Vec<Wait> = Vec::new();
+
+	loop {
+		sleep(wake, &jobs, &mut waits);
+
+		for task in receiver.try_iter() {
+			match task {
+				Task::Block(job) => jobs.push(*job),
+				Task::Wait(wait) => waits.push(wait),
+			}
+		}
+
+		jobs.retain_mut(|job| job.poll() == Progress::Blocked);
+		waits.retain_mut(|wait| wait.poll() == Progress::Blocked);
+	}
+}
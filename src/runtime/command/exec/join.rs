@@ -1,30 +1,31 @@
+use std::sync::Arc;
+
 use gc::{Finalize, GcCell, Trace};
 
 use crate::runtime::value::{CallContext, NativeFun, Value};
 
-use super::{Panic, PipelineErrors, IntoValue};
+use super::{JobHandle, IntoValue};
 
 
 #[derive(Finalize)]
-struct JoinHandle(
-	std::thread::JoinHandle<Result<Box<[PipelineErrors]>, Panic>>
-);
+struct Handle(Arc<JobHandle>);
 
 
-unsafe impl Trace for JoinHandle {
+unsafe impl Trace for Handle {
 	gc::unsafe_empty_trace!();
 }
 
 
+/// Wait for an asynchronous command block to finish, and get it's result.
 #[derive(Trace, Finalize)]
-pub struct Join(GcCell<Option<JoinHandle>>);
+pub struct Join(GcCell<Option<Handle>>);
 
 
 impl Join {
-	pub fn new(handle: std::thread::JoinHandle<Result<Box<[PipelineErrors]>, Panic>>) -> Self {
+	pub fn new(handle: Arc<JobHandle>) -> Self {
 		Self(
 			GcCell::new(
-				Some(JoinHandle(handle))
+				Some(Handle(handle))
 			)
 		)
 	}
@@ -35,17 +36,15 @@ impl NativeFun for Join {
 	fn name(&self) -> &'static str { "<command>.join" }
 
 	fn call(&self, context: CallContext) -> Result<Value, crate::runtime::Panic> {
-		match self.0.borrow_mut().take() {
-			Some(JoinHandle(join_handle)) => {
-				let result = match join_handle.join() {
-					Ok(result) => result,
-					Err(error) => std::panic::resume_unwind(error),
-				};
-
-				result
-					.map(|errors| errors.into_value(context.interner()))
-					.map_err(Into::into)
-			},
+		let outcome = self.0
+			.borrow_mut()
+			.take()
+			.and_then(|Handle(handle)| handle.join());
+
+		match outcome {
+			Some(result) => result
+				.map(|(errors, _)| errors.into_value(context.interner()))
+				.map_err(Into::into),
 
 			None => Err(
 				crate::runtime::Panic::invalid_join(context.pos),
@@ -53,3 +52,29 @@ impl NativeFun for Join {
 		}
 	}
 }
+
+
+/// Check whether an asynchronous command block has finished, without waiting.
+#[derive(Trace, Finalize)]
+pub struct IsFinished(Handle);
+
+
+impl IsFinished {
+	pub fn new(handle: Arc<JobHandle>) -> Self {
+		Self(Handle(handle))
+	}
+}
+
+
+impl NativeFun for IsFinished {
+	fn name(&self) -> &'static str { "<command>.is_finished" }
+
+	fn call(&self, context: CallContext) -> Result<Value, crate::runtime::Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(crate::runtime::Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(self.0.0.is_finished().into())
+	}
+}
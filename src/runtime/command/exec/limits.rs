@@ -0,0 +1,179 @@
+use std::process;
+#[cfg(unix)]
+use std::{io, os::unix::process::CommandExt};
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+
+/// Scheduling limits applied to spawned commands, between fork and exec. Only the
+/// niceness is supported on Windows, where it's mapped to a priority class.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+	/// The niceness of the process, from -20 (highest priority) to 19 (lowest priority).
+	pub nice: Option<i32>,
+	/// The io scheduling priority of the process. Only supported on Linux.
+	pub ionice: Option<IoPriority>,
+	/// The file mode creation mask of the process.
+	pub umask: Option<u32>,
+}
+
+
+/// An io scheduling priority, as in ionice(1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoPriority {
+	pub class: IoClass,
+	/// The priority level within the class, from 0 (highest) to 7 (lowest). Ignored for
+	/// the idle class.
+	pub level: u8,
+}
+
+
+/// An io scheduling class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoClass {
+	RealTime,
+	BestEffort,
+	Idle,
+}
+
+
+impl IoClass {
+	/// Parse a class name, as used by ionice(1).
+	pub fn parse(name: &[u8]) -> Option<Self> {
+		match name {
+			b"realtime" => Some(Self::RealTime),
+			b"best-effort" => Some(Self::BestEffort),
+			b"idle" => Some(Self::Idle),
+			_ => None,
+		}
+	}
+}
+
+
+impl Limits {
+	/// Check whether no limit is set.
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+
+
+	/// Override the limits that are set in the given limits.
+	pub fn merge(&mut self, other: Limits) {
+		self.nice = other.nice.or(self.nice);
+		self.ionice = other.ionice.or(self.ionice);
+		self.umask = other.umask.or(self.umask);
+	}
+
+
+	/// Apply the limits to the command, once it's forked.
+	#[cfg(unix)]
+	pub fn apply(&self, command: &mut process::Command) {
+		if self.is_empty() {
+			return;
+		}
+
+		let limits = *self;
+
+		// Safety: the closure only calls async signal safe functions.
+		unsafe {
+			command.pre_exec(move || limits.set());
+		}
+	}
+
+
+	/// Apply the limits to the command, through it's creation flags.
+	#[cfg(windows)]
+	pub fn apply(&self, command: &mut process::Command) {
+		command.creation_flags(self.creation_flags());
+	}
+
+
+	/// The creation flags that set the priority class of a process.
+	#[cfg(windows)]
+	pub fn creation_flags(&self) -> u32 {
+		use windows_sys::Win32::System::Threading::{
+			ABOVE_NORMAL_PRIORITY_CLASS,
+			BELOW_NORMAL_PRIORITY_CLASS,
+			HIGH_PRIORITY_CLASS,
+			IDLE_PRIORITY_CLASS,
+			NORMAL_PRIORITY_CLASS,
+		};
+
+		match self.nice {
+			None => 0,
+			Some(nice) if nice < -10 => HIGH_PRIORITY_CLASS,
+			Some(nice) if nice < 0 => ABOVE_NORMAL_PRIORITY_CLASS,
+			Some(0) => NORMAL_PRIORITY_CLASS,
+			Some(nice) if nice < 10 => BELOW_NORMAL_PRIORITY_CLASS,
+			Some(_) => IDLE_PRIORITY_CLASS,
+		}
+	}
+
+
+	/// Set the limits for the current process.
+	#[cfg(unix)]
+	fn set(&self) -> io::Result<()> {
+		if let Some(nice) = self.nice {
+			// Safety: setpriority has no memory safety requirements. The type of the first
+			// parameter varies with the libc, hence the cast.
+			let result = unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) };
+			if result < 0 {
+				return Err(io::Error::last_os_error());
+			}
+		}
+
+		if let Some(priority) = self.ionice {
+			set_io_priority(priority)?;
+		}
+
+		if let Some(umask) = self.umask {
+			// Safety: umask has no memory safety requirements, and always succeeds.
+			unsafe { libc::umask(umask as libc::mode_t) };
+		}
+
+		Ok(())
+	}
+}
+
+
+/// Set the io priority of the current process.
+#[cfg(target_os = "linux")]
+fn set_io_priority(priority: IoPriority) -> io::Result<()> {
+	const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+	const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+	let class = match priority.class {
+		IoClass::RealTime => 1,
+		IoClass::BestEffort => 2,
+		IoClass::Idle => 3,
+	};
+
+	let level = match priority.class {
+		IoClass::Idle => 0,
+		_ => priority.level as libc::c_int,
+	};
+
+	// Safety: ioprio_set has no memory safety requirements.
+	let result = unsafe {
+		libc::syscall(
+			libc::SYS_ioprio_set,
+			IOPRIO_WHO_PROCESS,
+			0,
+			class << IOPRIO_CLASS_SHIFT | level,
+		)
+	};
+
+	if result < 0 {
+		Err(io::Error::last_os_error())
+	} else {
+		Ok(())
+	}
+}
+
+
+/// Set the io priority of the current process.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_io_priority(_: IoPriority) -> io::Result<()> {
+	// Allocating is not allowed after fork, hence the raw error.
+	Err(io::Error::from_raw_os_error(libc::ENOSYS))
+}
@@ -1,19 +1,40 @@
 mod error;
 mod fmt;
+mod job;
 mod join;
+mod limits;
+mod options;
+mod pty;
+mod remote;
+mod stats;
+mod trace;
 
 use std::{
 	ffi::{OsStr, OsString},
 	fs::{File, OpenOptions},
-	io::{self, Write},
-	os::unix::prelude::{FromRawFd, OsStrExt, ExitStatusExt, IntoRawFd},
+	io::{self, Read, Write},
+	path::{Path, PathBuf},
 	process,
+	sync::Arc,
+	time::{Duration, Instant},
 };
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
 
-use crate::io::FileDescriptor;
-use super::{program, SourcePos};
-pub use join::Join;
+use crate::{
+	ffi::{OsStrExt, OsStringExt},
+	io::FileDescriptor,
+	runtime::pattern,
+};
+use super::{alias, env, lookup, program, SourcePos};
+pub use job::{wait, JobHandle, WaitHandle, Source};
+pub use join::{Join, IsFinished};
+pub use limits::{Limits, IoPriority, IoClass};
+pub use options::{Options, OptionError};
+pub use remote::Remote;
 pub use error::{Panic, Error, PipelineErrors, IntoValue};
+pub use stats::{BlockStats, ProcessStats};
+use pty::{Pty, Relay};
 
 
 /// Status to be produced when an IO error occurs
@@ -32,38 +53,64 @@ pub struct ErrorStatus {
 
 
 impl ErrorStatus {
-	/// Wait a child process, and return the status.
-	fn wait_child(mut child: Child) -> Option<Self> {
-		let status = match child.process.wait() {
-			Ok(status) => status,
-			Err(error) => return Some(
-				Self {
-					description: error.to_string(),
-					status: IO_ERROR_STATUS,
-					pos: child.pos,
-				}
+	/// Wait a child process, and return the status and resource usage.
+	fn wait_process(process: process::Child, pos: SourcePos) -> (Option<Self>, Option<ProcessStats>) {
+		let (status, stats) = match ProcessStats::wait(&process) {
+			Ok(result) => result,
+			Err(error) => return (
+				Some(
+					Self {
+						description: error.to_string(),
+						status: IO_ERROR_STATUS,
+						pos,
+					}
+				),
+				None
 			)
 		};
 
+		#[cfg(unix)]
+		let signal = status.signal();
+		// Processes are not terminated by signals on Windows.
+		#[cfg(windows)]
+		let signal: Option<i32> = None;
+
 		let code = status
 			.code()
 			.or_else(
-				|| status
-					.signal()
-					.map(
-						|status| status + SIGNAL_STATUS_OFFSET
-					)
+				|| signal.map(
+					|status| status + SIGNAL_STATUS_OFFSET
+				)
 			)
 			.unwrap_or(255);
 
-		if code == 0 {
+		let error =
+			if code == 0 {
+				None
+			} else {
+				Some(
+					Self {
+						description: "command returned non-zero".into(),
+						status: code,
+						pos,
+					}
+				)
+			};
+
+		(error, Some(stats))
+	}
+
+
+	/// The status of a function used as a command.
+	fn function(status: i32, description: &str, pos: SourcePos) -> Option<Self> {
+		if status == 0 {
 			None
 		} else {
 			Some(
 				Self {
-					description: "command returned non-zero".into(),
-					status: code,
-					pos: child.pos,
+					description: description.into(),
+					status,
+					pos,
 				}
 			)
 		}
@@ -82,8 +129,8 @@ pub enum Argument {
 
 
 impl Argument {
-	/// Resolve the argument in the current directory.
-	pub fn resolve(self, pos: SourcePos) -> Result<Box<[Box<OsStr>]>, Panic> {
+	/// Resolve the argument in the working directory of commands.
+	pub fn resolve(self, options: &Options, pos: SourcePos) -> Result<Box<[Box<OsStr>]>, Panic> {
 		match self {
 			Self::Literal(lit) => Ok(Box::new([lit])),
 			Self::Pattern(pattern) => {
@@ -95,9 +142,26 @@ impl Argument {
 
 				let is_absolute = pattern_str.starts_with('/');
 
-				let entries = glob::glob(&pattern_str)
-					.map_err(|_| Panic::invalid_pattern(pattern_str.into(), pos))?
-					.filter_map(Result::ok)
+				let dir = options.cwd.as_deref().unwrap_or_else(|| Path::new(""));
+
+				let paths = match pattern::expand_in(dir, &pattern_str, options.pattern()) {
+					Ok(paths) => paths,
+					Err(_) => return Err(Panic::invalid_pattern(pattern_str.into(), pos)),
+				};
+
+				if paths.is_empty() {
+					return match options {
+						Options { failglob: true, .. } => Err(Panic::no_matches(pattern_str.into(), pos)),
+						Options { nullglob: true, .. } => Ok(Box::default()),
+						_ => {
+							let literal = OsString::from(pattern::unescape(&pattern_str));
+							Ok(Box::new([literal.into_boxed_os_str()]))
+						}
+					};
+				}
+
+				let entries = paths
+					.into_iter()
 					.map(
 						|path| if is_absolute {
 							OsString::from(path).into_boxed_os_str()
@@ -124,6 +188,9 @@ pub enum RedirectionTarget {
 	Fd(FileDescriptor),
 	/// Overwrite a file. Panics if the argument does not expand to a single literal.
 	Overwrite(Argument),
+	/// Overwrite a file, even if noclobber is set. Panics if the argument does not expand to
+	/// a single literal.
+	Clobber(Argument),
 	/// Append to a file. Panics if the argument does not expand to a single literal.
 	Append(Argument),
 }
@@ -152,44 +219,286 @@ pub enum Redirection {
 pub enum Builtin {
 	Alias,
 	Cd,
+	Hash,
+	Pwd,
+	Pushd,
+	Popd,
+	Set,
+	Unalias,
 }
 
 
 impl Builtin {
+	/// The name of the built-in.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Alias => "alias",
+			Self::Cd => "cd",
+			Self::Hash => "hash",
+			Self::Pwd => "pwd",
+			Self::Pushd => "pushd",
+			Self::Popd => "popd",
+			Self::Set => "set",
+			Self::Unalias => "unalias",
+		}
+	}
+
+
 	pub fn exec(
 		self,
 		arguments: Box<[Argument]>,
+		mut stdout: os_pipe::PipeWriter,
+		options: &mut Options,
 		pos: SourcePos,
 	) -> Result<Option<ErrorStatus>, Error> {
-		let mut arguments = arguments.into_vec();
+		let mut args = Vec::new();
+		for argument in arguments.into_vec() {
+			args.extend(argument.resolve(options, pos.copy())?.into_vec());
+		}
+
+		trace::print(
+			&trace::command_line(
+				std::iter::empty(),
+				std::iter::once(OsStr::new(self.name())).chain(args.iter().map(AsRef::as_ref)),
+			),
+			options,
+		);
+
+		// The set built-in still runs, so that dry-run mode may be disabled.
+		if options.dry_run && !matches!(self, Builtin::Set) {
+			return Ok(None);
+		}
+
+		let invalid_args = |args: &[Box<OsStr>], pos: SourcePos| -> Error {
+			Panic::invalid_args("argument", args.len() as u32, pos).into()
+		};
 
 		match self {
-			Builtin::Alias => todo!(),
+			Builtin::Alias => {
+				// Without arguments, list all aliases.
+				if args.is_empty() {
+					for (name, value) in alias::list() {
+						Self::write_alias(&mut stdout, &name, &value, pos.copy())?;
+					}
+				}
+
+				let mut missing = Vec::new();
+
+				// Arguments either define an alias (`name=value`), or print an alias.
+				for arg in args.iter() {
+					let arg = arg.as_bytes();
+
+					match arg.iter().position(|&c| c == b'=') {
+						Some(0) => return Ok(
+							Some(
+								ErrorStatus {
+									description: "invalid alias name".into(),
+									status: 1,
+									pos,
+								}
+							)
+						),
+
+						Some(ix) if alias::words(&arg[ix + 1 ..]).is_none() => return Ok(
+							Some(
+								ErrorStatus {
+									description: "unbalanced quotes in alias".into(),
+									status: 1,
+									pos,
+								}
+							)
+						),
+
+						Some(ix) => {
+							alias::define(arg[.. ix].into(), arg[ix + 1 ..].into());
+						}
+
+						None => match alias::get(arg) {
+							Some(value) => Self::write_alias(&mut stdout, arg, &value, pos.copy())?,
+							None => missing.push(arg),
+						}
+					}
+				}
+
+				Ok(Self::missing_aliases(&missing, pos))
+			}
 
 			Builtin::Cd => {
-				let arg = arguments
-					.pop()
-					.ok_or_else(|| Panic::invalid_args("argument", 0, pos.copy()))?;
+				let target = match args.as_slice() {
+					[] => None,
+					[ dir ] => Some(dir.as_ref()),
+					other => return Err(invalid_args(other, pos)),
+				};
 
-				if !arguments.is_empty() {
-					return Err(
-						Panic::invalid_args("argument", arguments.len() as u32 + 1, pos.copy()).into()
-					);
+				let target = crate::io::cd_target(target)
+					.map_err(|error| Error::io(error, pos.copy()))?;
+
+				// With a working directory for commands, cd changes it for the rest of the block.
+				if options.cwd.is_some() {
+					let dir = options.path(&target).into_owned();
+
+					if !dir.is_dir() {
+						let error = io::Error::new(io::ErrorKind::NotFound, "not a directory");
+						return Err(Error::io(error, pos));
+					}
+
+					options.cwd = Some(dir.into());
+				} else {
+					crate::io::change_dir(target)
+						.map_err(|error| Error::io(error, pos))?;
 				}
 
-				let args = arg.resolve(pos.copy())?;
+				Ok(None)
+			}
 
-				match args.as_ref() {
-					[ dir ] => std::env::set_current_dir(dir.as_ref())
-						.map_err(|error| Error::io(error, pos.copy()))?,
-					other => return Err(
-						Panic::invalid_args("argument", other.len() as u32, pos).into()
-					),
+			Builtin::Hash => {
+				if let [ reset ] = args.as_slice() {
+					if reset.as_bytes() == b"-r" {
+						lookup::clear();
+						return Ok(None);
+					}
+				}
+
+				// Without arguments, list all cached programs.
+				if args.is_empty() {
+					for (name, path) in lookup::list() {
+						stdout
+							.write_all(&[name.as_bytes(), b"=", path.as_os_str().as_bytes(), b"\n"].concat())
+							.map_err(|error| Error::io(error, pos.copy()))?;
+					}
+				}
+
+				// Arguments are looked up, so that they are cached.
+				let missing: Vec<String> = args
+					.iter()
+					.filter(|name| lookup::find(name).is_none())
+					.map(|name| name.to_string_lossy().into_owned())
+					.collect();
+
+				if missing.is_empty() {
+					Ok(None)
+				} else {
+					Ok(
+						Some(
+							ErrorStatus {
+								description: format!("command not found: {}", missing.join(", ")),
+								status: 1,
+								pos,
+							}
+						)
+					)
+				}
+			}
+
+			Builtin::Pwd => {
+				if !args.is_empty() {
+					return Err(invalid_args(&args, pos));
+				}
+
+				let mut dir = match &options.cwd {
+					Some(cwd) => cwd.as_os_str().as_bytes().to_owned(),
+					None => std::env::current_dir()
+						.map_err(|error| Error::io(error, pos.copy()))?
+						.into_os_string()
+						.into_vec(),
+				};
+				dir.push(b'\n');
+
+				stdout
+					.write_all(&dir)
+					.map_err(|error| Error::io(error, pos))?;
+
+				Ok(None)
+			}
+
+			Builtin::Pushd => {
+				match args.as_slice() {
+					[ dir ] => crate::io::push_dir(dir.as_ref())
+						.map_err(|error| Error::io(error, pos))?,
+					other => return Err(invalid_args(other, pos)),
 				};
 
 				Ok(None)
 			}
+
+			Builtin::Popd => {
+				if !args.is_empty() {
+					return Err(invalid_args(&args, pos));
+				}
+
+				crate::io::pop_dir()
+					.map_err(|error| Error::io(error, pos))?;
+
+				Ok(None)
+			}
+
+			Builtin::Set => {
+				options
+					.set_args(&args)
+					.map_err(
+						|error| match error {
+							Some(option) => Panic::invalid_option(option.into_os_string(), pos.copy()),
+							None => Panic::invalid_args("argument", args.len() as u32, pos.copy()),
+						}
+					)?;
+
+				Ok(None)
+			}
+
+			Builtin::Unalias => {
+				if let [ all ] = args.as_slice() {
+					if all.as_bytes() == b"-a" {
+						alias::clear();
+						return Ok(None);
+					}
+				}
+
+				if args.is_empty() {
+					return Err(invalid_args(&args, pos));
+				}
+
+				let missing: Vec<&[u8]> = args
+					.iter()
+					.map(|arg| arg.as_bytes())
+					.filter(|name| alias::remove(name).is_none())
+					.collect();
+
+				Ok(Self::missing_aliases(&missing, pos))
+			}
+		}
+	}
+
+
+	fn write_alias(
+		stdout: &mut os_pipe::PipeWriter,
+		name: &[u8],
+		value: &[u8],
+		pos: SourcePos,
+	) -> Result<(), Error> {
+		stdout
+			.write_all(&[name, b"=", value, b"\n"].concat())
+			.map_err(|error| Error::io(error, pos))
+	}
+
+
+	/// The error status for aliases that are not defined, if any.
+	fn missing_aliases(missing: &[&[u8]], pos: SourcePos) -> Option<ErrorStatus> {
+		if missing.is_empty() {
+			return None;
 		}
+
+		let names: Vec<String> = missing
+			.iter()
+			.map(|name| String::from_utf8_lossy(name).into_owned())
+			.collect();
+
+		Some(
+			ErrorStatus {
+				description: format!("alias not found: {}", names.join(", ")),
+				status: 1,
+				pos,
+			}
+		)
 	}
 }
 
@@ -199,6 +508,12 @@ impl<'a> From<&'a program::command::Builtin> for Builtin {
 		match builtin {
 			program::command::Builtin::Alias => Self::Alias,
 			program::command::Builtin::Cd => Self::Cd,
+			program::command::Builtin::Hash => Self::Hash,
+			program::command::Builtin::Pwd => Self::Pwd,
+			program::command::Builtin::Pushd => Self::Pushd,
+			program::command::Builtin::Popd => Self::Popd,
+			program::command::Builtin::Set => Self::Set,
+			program::command::Builtin::Unalias => Self::Unalias,
 		}
 	}
 }
@@ -212,11 +527,86 @@ pub struct Stdio {
 }
 
 
+impl Stdio {
+	fn try_clone(&self) -> io::Result<Self> {
+		Ok(
+			Self {
+				stdin: self.stdin.try_clone()?,
+				stdout: self.stdout.try_clone()?,
+				stderr: self.stderr.try_clone()?,
+			}
+		)
+	}
+}
+
+
+/// The program of a command.
+#[derive(Debug)]
+pub enum Program {
+	/// An external program. Panics if the argument does not expand to a single literal.
+	External(Argument),
+	/// A hush function, as an index in the function table of the command block.
+	/// Function values can't be sent across threads, hence the indirection.
+	Function {
+		index: usize,
+		/// The name of the function, for tracing.
+		name: Box<OsStr>,
+	},
+}
+
+
+/// Runs hush functions used as commands.
+pub trait FunctionRunner {
+	/// Call the function with the given index in the function table, returning the exit
+	/// status. The standard file descriptors are redirected during the call.
+	fn run(&mut self, function: usize, args: Box<[Box<OsStr>]>, pos: SourcePos) -> Result<i32, Panic>;
+
+	/// Whether there is a handler for programs that are not found.
+	fn handles_not_found(&self) -> bool;
+
+	/// Call the handler for a program that was not found, returning the exit status. The
+	/// standard file descriptors are redirected during the call.
+	fn not_found(
+		&mut self,
+		program: Box<OsStr>,
+		args: Box<[Box<OsStr>]>,
+		pos: SourcePos,
+	) -> Result<i32, Panic>;
+
+	/// Whether there are hooks to be called around spawned commands.
+	fn has_command_hooks(&self) -> bool;
+
+	/// Call the hook for commands about to be spawned, if any.
+	fn before_command(&mut self, command: CommandInfo, pos: SourcePos) -> Result<(), Panic>;
+
+	/// Call the hook for commands that have finished, if any.
+	fn after_command(
+		&mut self,
+		command: CommandInfo,
+		status: i32,
+		duration: Duration,
+		pos: SourcePos,
+	) -> Result<(), Panic>;
+}
+
+
+/// A spawned command, as seen by the command hooks.
+#[derive(Debug, Clone)]
+pub struct CommandInfo {
+	/// The program and the arguments.
+	pub argv: Box<[Box<OsStr>]>,
+	/// The directory in which the command runs.
+	pub cwd: PathBuf,
+	/// The environment variables assigned in the command.
+	pub env: Box<[(Box<OsStr>, Box<OsStr>)]>,
+}
+
+
 /// A single command, including possible redirections and try operator.
 #[derive(Debug)]
 pub struct BasicCommand {
-	/// The program to be executed. Panics if the argument does not expand to a single literal.
-	pub program: Argument,
+	/// The program to be executed.
+	pub program: Program,
 	/// Key-value pairs of environment variables.
 	pub env: Box<[(Box<OsStr>, Argument)]>,
 	/// Arguments to the program. The arguments may expand to an arbitrary number of literals.
@@ -231,51 +621,292 @@ pub struct BasicCommand {
 
 
 impl BasicCommand {
-	pub fn exec(self, stdio: Stdio) -> Result<Child, Error> {
+	/// Expand the patterns in the program, environment and arguments, producing a command
+	/// with only literals. Redirections are left as is.
+	fn resolve(self, options: &Options) -> Result<Self, Error> {
 		let pos = self.pos.copy();
 
-		let program_args = self.program.resolve(pos.copy())?;
+		let single = |argument: Argument, object| -> Result<Argument, Error> {
+			let mut args = argument.resolve(options, pos.copy())?.into_vec();
 
-		let mut command = match program_args.as_ref() {
-			[ program ] => process::Command::new(program),
-			other => return Err(
-				Panic::invalid_args("program", other.len() as u32, pos.copy()).into()
-			),
+			match args.len() {
+				1 => Ok(Argument::Literal(args.remove(0))),
+				other => Err(Panic::invalid_args(object, other as u32, pos.copy()).into()),
+			}
 		};
 
+		let program = match self.program {
+			Program::External(program) => Program::External(single(program, "program")?),
+			function => function,
+		};
+
+		let env = self.env
+			.into_vec() // Use vec's owned iterator.
+			.into_iter()
+			.map(|(key, value)| Ok((key, single(value, "env variable")?)))
+			.collect::<Result<_, Error>>()?;
+
+		let mut arguments = Vec::new();
+		for argument in self.arguments.into_vec() {
+			arguments.extend(
+				argument
+					.resolve(options, pos.copy())?
+					.into_vec()
+					.into_iter()
+					.map(Argument::Literal)
+			);
+		}
+
+		Ok(
+			Self {
+				program,
+				env,
+				arguments: arguments.into(),
+				..self
+			}
+		)
+	}
+
+
+	/// Describe the command for the command hooks, unless it's a function call. The command
+	/// should have been resolved.
+	fn info(&self, options: &Options) -> Option<CommandInfo> {
+		let literal = |argument: &Argument| match argument {
+			Argument::Literal(word) | Argument::Pattern(word) => word.clone(),
+		};
+
+		let program = match &self.program {
+			Program::External(program) => literal(program),
+			Program::Function { .. } => return None,
+		};
+
+		let cwd = match &options.cwd {
+			Some(cwd) => cwd.to_path_buf(),
+			None => std::env::current_dir().unwrap_or_default(),
+		};
+
+		Some(
+			CommandInfo {
+				argv: std::iter::once(program)
+					.chain(self.arguments.iter().map(literal))
+					.collect(),
+				cwd,
+				env: self.env
+					.iter()
+					.map(|(key, value)| (key.clone(), literal(value)))
+					.collect(),
+			}
+		)
+	}
+
+
+	/// Format the command line for tracing. The command should have been resolved.
+	fn command_line(&self) -> String {
+		fn word(argument: &Argument) -> &OsStr {
+			match argument {
+				Argument::Literal(word) | Argument::Pattern(word) => word,
+			}
+		}
+
+		let program = match &self.program {
+			Program::External(program) => word(program),
+			Program::Function { name, .. } => name,
+		};
+
+		trace::command_line(
+			self.env.iter().map(|(key, value)| (key.as_ref(), word(value))),
+			std::iter::once(program).chain(self.arguments.iter().map(word)),
+		)
+	}
+
+
+	/// Spawn the command. If the program is not found and there is a handler for such case,
+	/// the handler is called instead.
+	pub fn exec(self, stdio: Stdio, options: &Options, handle_not_found: bool) -> Result<Child, Error> {
+		let pos = self.pos.copy();
+
+		let mut env = Vec::with_capacity(self.env.len());
 		for (key, value) in self.env.into_vec() { // Use vec's owned iterator.
-			let value = value.resolve(pos.copy())?;
+			if !options.policy.allows_env(&key) {
+				return Err(
+					Panic::policy_violation("env variable", key.to_os_string().into(), pos).into()
+				);
+			}
+
+			let value = value.resolve(options, pos.copy())?;
 
 			match value.as_ref() {
-				[ value ] => command.env(key, value),
+				[ value ] => env.push((key, value.clone())),
 				other => return Err(
 					Panic::invalid_args("env variable", other.len() as u32, pos.copy()).into()
 				),
 			};
 		}
 
+		let mut args = Vec::new();
 		for argument in self.arguments.into_vec() {
-			let args = argument.resolve(pos.copy())?;
+			args.extend(argument.resolve(options, pos.copy())?.into_vec());
+		}
+
+		match self.program {
+			Program::Function { index, .. } => {
+				let stdio = Self::redirect(stdio, self.redirections, options, pos.copy())?;
+
+				Ok(
+					Child::Function(
+						FunctionCall {
+							callee: Callee::Function(index),
+							args: args.into(),
+							env,
+							stdio,
+							pos,
+						}
+					)
+				)
+			}
+
+			Program::External(program) => {
+				let program_args = program.resolve(options, pos.copy())?;
+
+				let program = match program_args.as_ref() {
+					[ program ] => program.clone(),
+					other => return Err(
+						Panic::invalid_args("program", other.len() as u32, pos.copy()).into()
+					),
+				};
+
+				if !options.policy.allows_command(&program) {
+					return Err(Panic::policy_violation("command", program.into(), pos).into());
+				}
 
-			for arg in args.iter() {
-				command.arg(arg);
+				let mut command = match &options.remote {
+					// The environment and working directory of the shell are not relevant in
+					// the remote host.
+					Some(remote) => remote.command(
+						env.iter().map(|(key, value)| (&**key, &**value)),
+						&program,
+						args.iter().map(|arg| &**arg),
+					),
+
+					None => {
+						// Programs are looked up in the PATH of the shell, unless the command
+						// overrides it.
+						let executable = Some(&program)
+							.filter(|_| !env.iter().any(|(key, _)| key.as_bytes() == b"PATH"))
+							.and_then(|program| lookup::find(program))
+							.filter(|executable| executable.is_absolute());
+
+						let mut command = match executable {
+							#[cfg(unix)]
+							Some(executable) => {
+								let mut command = process::Command::new(executable.as_os_str());
+								command.arg0(&program);
+								command
+							}
+							// Windows programs receive a command line, which is built by the
+							// standard library, quoting the arguments as the C runtime expects.
+							#[cfg(windows)]
+							Some(executable) => process::Command::new(executable.as_os_str()),
+							None => process::Command::new(&program),
+						};
+
+						if options.clean_env {
+							command.env_clear();
+
+							for name in options.pass_env.iter() {
+								if let Some(value) = env::var(name) {
+									command.env(name, value);
+								}
+							}
+						} else {
+							env::apply(&mut command);
+						}
+
+						command.envs(env.iter().map(|(key, value)| (key, value)));
+						command.args(args.iter());
+
+						if let Some(cwd) = &options.cwd {
+							command.current_dir(cwd);
+						}
+
+						command
+					}
+				};
+
+				options.limits.apply(&mut command);
+
+				let mut stdio = Self::redirect(stdio, self.redirections, options, pos.copy())?;
+
+				// The handler needs the stdio, which is consumed by the command. Programs are
+				// not looked up locally when running in a remote host.
+				let handler_stdio =
+					if handle_not_found && options.remote.is_none() {
+						Some(stdio.try_clone().map_err(|error| Error::io(error, pos.copy()))?)
+					} else {
+						None
+					};
+
+				// The pseudo-terminals must be attached after cloning the stdio for the
+				// handler, which should receive the original outputs.
+				let mut relays = Vec::new();
+
+				if options.pty {
+					stdio.stdout = Self::attach_pty(stdio.stdout, &mut relays, pos.copy())?;
+					stdio.stderr = Self::attach_pty(stdio.stderr, &mut relays, pos.copy())?;
+				}
+
+				command.stdin(stdio.stdin);
+				command.stdout(stdio.stdout);
+				command.stderr(stdio.stderr);
+
+				match (command.spawn(), handler_stdio) {
+					(Ok(process), _) => Ok(Child::Process { process, relays, pos }),
+
+					(Err(error), Some(stdio)) if error.kind() == io::ErrorKind::NotFound => Ok(
+						Child::Function(
+							FunctionCall {
+								callee: Callee::NotFound(program),
+								args: args.into(),
+								env,
+								stdio,
+								pos,
+							}
+						)
+					),
+
+					(Err(error), _) => Err(Error::io(error, pos)),
+				}
 			}
 		}
+	}
+
+
+	/// Attach the output to a pseudo-terminal, returning the terminal side for the command.
+	fn attach_pty(
+		output: os_pipe::PipeWriter,
+		relays: &mut Vec<Relay>,
+		pos: SourcePos,
+	) -> Result<os_pipe::PipeWriter, Error> {
+		let pty = Pty::open()
+			.map_err(|error| Error::io(error, pos))?;
+
+		let (terminal, relay) = pty.relay(output);
+		relays.push(relay);
 
-		Self::spawn(&mut command, stdio, self.redirections, self.pos)
+		Ok(terminal)
 	}
 
 
-	fn spawn(
-		command: &mut process::Command,
+	fn redirect(
 		mut stdio: Stdio,
 		redirections: Box<[Redirection]>,
+		options: &Options,
 		pos: SourcePos,
-	) -> Result<Child, Error> {
+	) -> Result<Stdio, Error> {
 		for redirection in redirections.into_vec() { // Use vec's owned iterator.
 			match redirection {
 				Redirection::Output { source, target } => {
-					let target = Self::resolve_target(target, &stdio, pos.copy())?;
+					let target = Self::resolve_target(target, &stdio, options, pos.copy())?;
 
 					match source {
 						1 => stdio.stdout = target,
@@ -287,7 +918,7 @@ impl BasicCommand {
 				}
 
 				Redirection::Input { literal, source } => {
-					let args = source.resolve(pos.copy())?;
+					let args = source.resolve(options, pos.copy())?;
 
 					let source = match args.as_ref() {
 						[ source ] => source,
@@ -309,12 +940,13 @@ impl BasicCommand {
 
 							reader
 						} else {
-							let file = File::open(source.as_ref())
-								.map_err(|error| Error::io(error, pos.copy()))?
-								.into_raw_fd();
+							let path = options.path(Path::new(source.as_ref()));
+							Self::check_path(&path, options, pos.copy())?;
+
+							let file = File::open(path)
+								.map_err(|error| Error::io(error, pos.copy()))?;
 
-							// SAFETY: converting from a FD originated from a File is fine.
-							unsafe { os_pipe::PipeReader::from_raw_fd(file) }
+							crate::io::file_reader(file)
 						};
 
 					stdio.stdin = stdin;
@@ -322,45 +954,75 @@ impl BasicCommand {
 			}
 		}
 
-		command.stdin(stdio.stdin);
-		command.stdout(stdio.stdout);
-		command.stderr(stdio.stderr);
+		Ok(stdio)
+	}
 
-		let process = command.spawn()
-			.map_err(|error| Error::io(error, pos.copy()))?;
 
-		Ok(Child { process, pos })
+	/// Check that the sandbox policy allows redirecting from or to the given path.
+	fn check_path(path: &Path, options: &Options, pos: SourcePos) -> Result<(), Error> {
+		if options.policy.allows_path(path) {
+			Ok(())
+		} else {
+			Err(Panic::policy_violation("path", path.as_os_str().to_owned(), pos).into())
+		}
 	}
 
 
-	fn resolve_target(target: RedirectionTarget, stdio: &Stdio, pos: SourcePos) -> Result<os_pipe::PipeWriter, Error> {
-		let open = |arg: Argument, append| {
-			let args = arg.resolve(pos.copy())?;
+	/// Open a file for output redirection. Unless clobbering is allowed, existing regular
+	/// files are not truncated. Other files, such as /dev/null, are still allowed.
+	fn open_output(path: &Path, append: bool, clobber: bool) -> io::Result<File> {
+		let mut options = OpenOptions::new();
+		options
+			.create(true)
+			.write(true)
+			.append(append)
+			.truncate(!append);
+
+		if !clobber {
+			match std::fs::metadata(path) {
+				Ok(metadata) if metadata.is_file() => return Err(
+					io::Error::new(io::ErrorKind::AlreadyExists, "cannot overwrite existing file")
+				),
+				// Fail if the file is created meanwhile.
+				Err(error) if error.kind() == io::ErrorKind::NotFound => { options.create_new(true); }
+				_ => (),
+			}
+		}
+
+		options.open(path)
+	}
+
+
+	fn resolve_target(
+		target: RedirectionTarget,
+		stdio: &Stdio,
+		options: &Options,
+		pos: SourcePos,
+	) -> Result<os_pipe::PipeWriter, Error> {
+		let open = |arg: Argument, append, clobber| {
+			let args = arg.resolve(options, pos.copy())?;
 
 			let file = match args.as_ref() {
-				[ file ] => OpenOptions::new()
-					.create(true)
-					.write(true)
-					.append(append)
-					.truncate(!append)
-					.open(file.as_ref())
-					.map_err(|error| Error::io(error, pos.copy()))?
-					.into_raw_fd(),
+				[ file ] => {
+					let path = options.path(Path::new(file.as_ref()));
+					Self::check_path(&path, options, pos.copy())?;
+
+					Self::open_output(&path, append, clobber)
+						.map_err(|error| Error::io(error, pos.copy()))?
+				}
 
 				other => return Err(
 					Panic::invalid_args("redirection", other.len() as u32, pos.copy()).into()
 				),
 			};
 
-			Ok(
-				// SAFETY: converting from a FD originated from a File is fine.
-				unsafe { os_pipe::PipeWriter::from_raw_fd(file) }
-			)
+			Ok(crate::io::file_writer(file))
 		};
 
 		match target {
-			RedirectionTarget::Overwrite(arg) => open(arg, false),
-			RedirectionTarget::Append(arg) => open(arg, true),
+			RedirectionTarget::Overwrite(arg) => open(arg, false, !options.noclobber),
+			RedirectionTarget::Clobber(arg) => open(arg, false, true),
+			RedirectionTarget::Append(arg) => open(arg, true, true),
 			RedirectionTarget::Fd(fd) => {
 				let writer = match fd {
 					1 => &stdio.stdout,
@@ -378,17 +1040,150 @@ impl BasicCommand {
 }
 
 
+/// The function invoked by a function call.
 #[derive(Debug)]
-pub struct Child {
-	process: process::Child,
+enum Callee {
+	/// A function from the function table.
+	Function(usize),
+	/// The handler for programs that are not found, with the program name.
+	NotFound(Box<OsStr>),
+}
+
+
+/// A function used as a command, with resolved arguments and redirections.
+#[derive(Debug)]
+pub struct FunctionCall {
+	callee: Callee,
+	args: Box<[Box<OsStr>]>,
+	env: Vec<(Box<OsStr>, Box<OsStr>)>,
+	stdio: Stdio,
 	pos: SourcePos,
 }
 
 
+impl FunctionCall {
+	/// Read the whole input into memory before feeding it to the function. Functions in a
+	/// pipeline run one at a time, so a function reading directly from another would
+	/// never see the end of it's input.
+	fn buffer_stdin(&mut self) -> Result<(), Error> {
+		let (reader, mut writer) = os_pipe::pipe()
+			.map_err(|error| Error::io(error, self.pos.copy()))?;
+
+		let mut input = std::mem::replace(&mut self.stdio.stdin, reader);
+
+		std::thread::spawn(
+			move || -> io::Result<()> {
+				let mut buffer = Vec::new();
+				input.read_to_end(&mut buffer)?;
+				writer.write_all(&buffer)
+			}
+		);
+
+		Ok(())
+	}
+
+
+	/// Call the function, with the standard file descriptors and environment variables
+	/// replaced for the duration of the call.
+	fn run(self, runner: &mut dyn FunctionRunner) -> Result<Option<ErrorStatus>, Error> {
+		let FunctionCall { callee, args, env, stdio, pos } = self;
+
+		let previous_env: Vec<_> = env
+			.into_iter()
+			.map(
+				|(key, value)| {
+					let previous = env::var(&key);
+					env::set_var(&key, Some(&value));
+					(key, previous)
+				}
+			)
+			.collect();
+
+		let description = match callee {
+			Callee::Function(_) => "function returned non-zero",
+			Callee::NotFound(_) => "command not found",
+		};
+
+		let result = match crate::io::replace_stdio(&stdio.stdin, &stdio.stdout, &stdio.stderr) {
+			Ok(saved) => {
+				let status = match callee {
+					Callee::Function(function) => runner.run(function, args, pos.copy()),
+					Callee::NotFound(program) => runner.not_found(program, args, pos.copy()),
+				};
+				drop(saved); // Restore the standard file descriptors.
+				status.map_err(Error::from)
+			}
+
+			Err(error) => Err(Error::io(error, pos.copy())),
+		};
+
+		for (key, previous) in previous_env.into_iter().rev() {
+			env::set_var(&key, previous.as_deref());
+		}
+
+		// Close the pipes, so that the following command sees the end of it's input.
+		drop(stdio);
+
+		Ok(ErrorStatus::function(result?, description, pos))
+	}
+}
+
+
+#[derive(Debug)]
+pub enum Child {
+	/// A spawned process.
+	Process {
+		process: process::Child,
+		/// The relays of the pseudo-terminals the process is attached to, if any.
+		relays: Vec<Relay>,
+		pos: SourcePos,
+	},
+	/// A function call, which runs only after all processes in the pipeline are spawned.
+	Function(FunctionCall),
+	/// A function call that has already been run.
+	Finished(Option<ErrorStatus>),
+}
+
+
+impl Child {
+	/// Run the child if it's a function call.
+	fn run(&mut self, runner: &mut dyn FunctionRunner) -> Result<(), Error> {
+		*self = match std::mem::replace(self, Child::Finished(None)) {
+			Child::Function(call) => Child::Finished(call.run(runner)?),
+			other => other,
+		};
+
+		Ok(())
+	}
+
+
+	/// Wait for the child to finish, and return the status and resource usage. Function
+	/// calls must have been run previously.
+	fn wait(self) -> (Option<ErrorStatus>, Option<ProcessStats>) {
+		match self {
+			Child::Process { process, relays, pos } => {
+				let result = ErrorStatus::wait_process(process, pos);
+
+				// Make sure all output is relayed before the command is considered finished.
+				for relay in relays {
+					relay.join();
+				}
+
+				result
+			}
+			Child::Finished(status) => (status, None),
+			Child::Function(_) => unreachable!("function call should have been run"),
+		}
+	}
+}
+
+
 #[derive(Debug)]
 pub struct CommandExec {
 	pub errors: PipelineErrors,
 	pub abort: bool,
+	/// The processes spawned by the command.
+	pub processes: Vec<ProcessStats>,
 }
 
 
@@ -420,26 +1215,94 @@ impl Command {
 		self,
 		stdout: os_pipe::PipeWriter,
 		stderr: os_pipe::PipeWriter,
+		options: &mut Options,
+		runner: &mut dyn FunctionRunner,
 	) -> Result<CommandExec, Error> {
+		match self.start(stdout, stderr, options, runner)? {
+			Started::Finished(exec) => Ok(exec),
+			Started::Running(pipeline) => pipeline.finish(runner),
+		}
+	}
+
+
+	/// Start the command, without waiting for the spawned processes.
+	pub fn start(
+		self,
+		stdout: os_pipe::PipeWriter,
+		stderr: os_pipe::PipeWriter,
+		options: &mut Options,
+		runner: &mut dyn FunctionRunner,
+	) -> Result<Started, Error> {
 		match self {
 			Command::Builtin { program, arguments, abort_on_error, pos } => {
-				let error = program.exec(arguments, pos)?;
+				let error = program.exec(arguments, stdout, options, pos)?;
 				let abort = abort_on_error && error.is_some();
 				Ok(
-					CommandExec {
-						errors: error.into(),
-						abort,
-					}
+					Started::Finished(
+						CommandExec {
+							errors: error.into(),
+							abort,
+							processes: Vec::new(),
+						}
+					)
 				)
 			}
 
 			Command::External { head, tail } => {
+				// Resolve all commands before spawning them, so that they may be traced in
+				// order, and not at all executed in dry-run mode.
+				let head = head.resolve(options)?;
+				let tail = tail
+					.into_vec() // Use vec's owned iterator.
+					.into_iter()
+					.map(|cmd| cmd.resolve(options))
+					.collect::<Result<Vec<_>, Error>>()?;
+
+				if options.xtrace || options.dry_run {
+					let line: Vec<String> = std::iter::once(&head)
+						.chain(tail.iter())
+						.map(BasicCommand::command_line)
+						.collect();
+
+					trace::print(&line.join(" | "), options);
+				}
+
+				if options.dry_run {
+					return Ok(
+						Started::Finished(
+							CommandExec {
+								errors: Vec::new().into(),
+								abort: false,
+								processes: Vec::new(),
+							}
+						)
+					);
+				}
+
+				// Function calls are not spawned, and therefore not hooked.
+				let infos: Vec<Option<(CommandInfo, SourcePos)>> =
+					if runner.has_command_hooks() {
+						std::iter::once(&head)
+							.chain(tail.iter())
+							.map(|cmd| cmd.info(options).map(|info| (info, cmd.pos.copy())))
+							.collect()
+					} else {
+						Vec::new()
+					};
+
+				for (info, pos) in infos.iter().flatten() {
+					runner.before_command(info.clone(), pos.copy())?;
+				}
+
+				let start = Instant::now();
+
 				let mut last_stdout = stdout;
 				let mut last_stderr = stderr;
 
 				let mut tail_children = Vec::new();
-				for cmd in tail.into_vec().into_iter().rev() {
+				for cmd in tail.into_iter().rev() {
 					let child_abort_on_error = cmd.abort_on_error;
+					let pos = cmd.pos.copy();
 
 					let (pipe_reader, pipe_writer) = os_pipe::pipe()
 						.map_err(|error| Error::io(error, cmd.pos.copy()))?;
@@ -449,12 +1312,14 @@ impl Command {
 							stdin: pipe_reader,
 							stdout: last_stdout,
 							stderr: last_stderr,
-						}
+						},
+						options,
+						runner.handles_not_found(),
 					)?;
 
 					last_stdout = pipe_writer;
 					last_stderr = os_pipe::dup_stderr()
-						.map_err(|error| Error::io(error, child.pos.copy()))?;
+						.map_err(|error| Error::io(error, pos))?;
 
 					tail_children.push((child, child_abort_on_error));
 				}
@@ -469,31 +1334,50 @@ impl Command {
 						stdin,
 						stdout: last_stdout,
 						stderr: last_stderr,
-					}
+					},
+					options,
+					runner.handles_not_found(),
 				)?;
 
-				let mut abort = false;
-				let mut errors = Vec::new();
+				// Without pipefail, only the last command in the pipeline is checked.
+				let pipefail = options.pipefail;
+				let last_ix = tail_children.len();
+				// The try operator in the last command applies to the whole pipeline.
+				let last_abort_on_error = tail_children
+					.first()
+					.map(|(_, abort_on_error)| *abort_on_error)
+					.unwrap_or(head_abort_on_error);
+
+				let mut children: Vec<_> = std::iter::once((head_child, head_abort_on_error))
+					.chain(tail_children.into_iter().rev())
+					.collect();
 
-				// Wait on head command.
-				if let Some(error) = ErrorStatus::wait_child(head_child) {
-					abort |= head_abort_on_error;
-					errors.push(error);
-				}
+				// Run function calls, in order, now that all processes are running.
+				for ix in 0 .. children.len() {
+					let (child, rest) = children[ix ..]
+						.split_first_mut()
+						.expect("index should be in range");
 
-				// Wait on tail commands.
-				for (child, abort_on_error) in tail_children.into_iter().rev() {
-					if let Some(error) = ErrorStatus::wait_child(child) {
-						abort |= abort_on_error;
-						errors.push(error);
+					if let Child::Function(_) = child.0 {
+						if let Some((Child::Function(next), _)) = rest.first_mut() {
+							next.buffer_stdin()?;
+						}
 					}
+
+					child.0.run(runner)?;
 				}
 
 				Ok(
-					CommandExec {
-						errors: errors.into(),
-						abort,
-					}
+					Started::Running(
+						Pipeline {
+							children,
+							infos,
+							start,
+							pipefail,
+							last_ix,
+							last_abort_on_error,
+						}
+					)
 				)
 			}
 		}
@@ -508,22 +1392,190 @@ impl Command {
 }
 
 
+/// A command that has been started.
+#[derive(Debug)]
+pub enum Started {
+	/// The command has already finished, as builtins and dry runs spawn no processes.
+	Finished(CommandExec),
+	/// The processes of the pipeline are running.
+	Running(Pipeline),
+}
+
+
+/// A pipeline whose processes have been spawned, and whose function calls have been run.
+#[derive(Debug)]
+pub struct Pipeline {
+	/// The commands, and whether they abort the block on error.
+	children: Vec<(Child, bool)>,
+	/// The commands to be reported to the command hooks, if any.
+	infos: Vec<Option<(CommandInfo, SourcePos)>>,
+	start: Instant,
+	pipefail: bool,
+	last_ix: usize,
+	last_abort_on_error: bool,
+}
+
+
+impl Pipeline {
+	/// Check whether all processes have exited, so that finishing won't block.
+	pub fn is_finished(&self) -> bool {
+		self.children
+			.iter()
+			.all(
+				|(child, _)| match child {
+					Child::Process { process, .. } => has_exited(process),
+					_ => true,
+				}
+			)
+	}
+
+
+	/// The handles of the processes which haven't exited yet. Handles become signaled
+	/// once the process exits.
+	#[cfg(windows)]
+	pub fn handles(&self) -> impl Iterator<Item = windows_sys::Win32::Foundation::HANDLE> + '_ {
+		use std::os::windows::io::AsRawHandle;
+
+		self.children
+			.iter()
+			.filter_map(
+				|(child, _)| match child {
+					Child::Process { process, .. } if !has_exited(process) => {
+						Some(process.as_raw_handle() as _)
+					}
+					_ => None,
+				}
+			)
+	}
+
+
+	/// Wait on all commands, in order.
+	pub fn finish(self, runner: &mut dyn FunctionRunner) -> Result<CommandExec, Error> {
+		let Pipeline { children, infos, start, pipefail, last_ix, last_abort_on_error } = self;
+
+		let mut abort = false;
+		let mut errors = Vec::new();
+		let mut processes = Vec::new();
+		// A panic in a hook must not prevent waiting the remaining commands.
+		let mut hook_result = Ok(());
+
+		for (ix, (child, abort_on_error)) in children.into_iter().enumerate() {
+			let checked = pipefail || ix == last_ix;
+
+			let (error, stats) = child.wait();
+			processes.extend(stats);
+
+			if let (Some(Some((info, pos))), Ok(())) = (infos.get(ix), &hook_result) {
+				let status = error.as_ref().map(|error| error.status).unwrap_or(0);
+				hook_result = runner.after_command(info.clone(), status, start.elapsed(), pos.copy());
+			}
+
+			if let Some(error) = error {
+				if checked {
+					abort |= abort_on_error && last_abort_on_error;
+					errors.push(error);
+				}
+			}
+		}
+
+		hook_result?;
+
+		Ok(
+			CommandExec {
+				errors: errors.into(),
+				abort,
+				processes,
+			}
+		)
+	}
+}
+
+
+/// Check whether the process has exited, without reaping it, so that it may still be
+/// waited for it's resource usage. Failures are reported as exited, so that waiting
+/// reports the error.
+#[cfg(unix)]
+fn has_exited(process: &process::Child) -> bool {
+	// Safety: siginfo_t is a plain C struct, for which zero is a valid value.
+	let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+
+	// Safety: the pointer is valid.
+	let result = unsafe {
+		libc::waitid(
+			libc::P_PID,
+			process.id() as libc::id_t,
+			&mut info,
+			libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+		)
+	};
+
+	// With WNOHANG, the pid is left zeroed if the process is still running.
+	// Safety: waitid has filled the struct, or left it zeroed.
+	result != 0 || unsafe { info.si_pid() } != 0
+}
+
+
+/// Check whether the process has exited. Process handles remain valid after exit, so the
+/// resource usage may still be collected.
+#[cfg(windows)]
+fn has_exited(process: &process::Child) -> bool {
+	use std::os::windows::io::AsRawHandle;
+	use windows_sys::Win32::{Foundation::WAIT_TIMEOUT, System::Threading::WaitForSingleObject};
+
+	// Safety: the handle is owned by the child, which outlives this call.
+	unsafe { WaitForSingleObject(process.as_raw_handle() as _, 0) != WAIT_TIMEOUT }
+}
+
+
 /// A command block.
 #[derive(Debug)]
 pub struct Block {
 	pub head: Command,
 	pub tail: Box<[Command]>,
+	/// The execution options. Changes made by the set built-in are local to the block.
+	pub options: Options,
 }
 
 
 impl Block {
-	pub fn exec<F, G>(self, stdout: F, stderr: G) -> Result<Box<[PipelineErrors]>, Panic>
+	/// Execute the block, returning the errors and the execution statistics.
+	pub fn exec<F, G>(
+		self,
+		stdout: F,
+		stderr: G,
+		runner: &mut dyn FunctionRunner,
+	) -> Result<(Box<[PipelineErrors]>, BlockStats), Panic>
 	where
 		F: FnMut() -> io::Result<os_pipe::PipeWriter>,
 		G: FnMut() -> io::Result<os_pipe::PipeWriter>,
 	{
-		match self._exec(stdout, stderr) {
-			Ok(status) => Ok(status),
+		let start = Instant::now();
+		let mut stats = BlockStats::default();
+
+		let result = self._exec(stdout, stderr, runner, &mut stats.processes);
+
+		stats.duration = start.elapsed();
+
+		Self::outcome(result, stats)
+	}
+
+
+	/// Execute the block in the background, without blocking the current thread.
+	pub fn spawn<R>(self, runner: R) -> Arc<JobHandle>
+	where
+		R: FunctionRunner + Send + 'static,
+	{
+		job::spawn(self, runner)
+	}
+
+
+	/// Convert the result of the execution. IO errors are reported as failed commands.
+	fn outcome(
+		result: Result<Box<[PipelineErrors]>, Error>,
+		stats: BlockStats,
+	) -> Result<(Box<[PipelineErrors]>, BlockStats), Panic> {
+		match result {
+			Ok(status) => Ok((status, stats)),
 			Err(Error::Panic(panic)) => Err(panic),
 			Err(Error::Io { error, pos }) => {
 				let error = ErrorStatus {
@@ -532,18 +1584,25 @@ impl Block {
 					pos,
 				};
 
-				Ok(Box::new([PipelineErrors::from(error)]))
+				Ok((Box::new([PipelineErrors::from(error)]), stats))
 			},
 		}
 	}
 
 
-	fn _exec<F, G>(self, mut stdout: F, mut stderr: G,) -> Result<Box<[PipelineErrors]>, Error>
+	fn _exec<F, G>(
+		self,
+		mut stdout: F,
+		mut stderr: G,
+		runner: &mut dyn FunctionRunner,
+		processes: &mut Vec<ProcessStats>,
+	) -> Result<Box<[PipelineErrors]>, Error>
 	where
 		F: FnMut() -> io::Result<os_pipe::PipeWriter>,
 		G: FnMut() -> io::Result<os_pipe::PipeWriter>,
 	{
 		let mut errors = Vec::new();
+		let mut options = self.options;
 
 		let pos = self.head.pos();
 		let head = self.head.exec(
@@ -551,8 +1610,12 @@ impl Block {
 				.map_err(|error| Error::io(error, pos.copy()))?,
 			stderr()
 				.map_err(|error| Error::io(error, pos.copy()))?,
+			&mut options,
+			runner,
 		)?;
 
+		processes.extend(head.processes);
+
 		if !head.errors.is_empty() {
 			errors.push(head.errors);
 		}
@@ -568,8 +1631,12 @@ impl Block {
 					.map_err(|error| Error::io(error, pos.copy()))?,
 				stderr()
 					.map_err(|error| Error::io(error, pos.copy()))?,
+				&mut options,
+				runner,
 			)?;
 
+			processes.extend(child.processes);
+
 			if !child.errors.is_empty() {
 				errors.push(child.errors);
 			}
@@ -0,0 +1,255 @@
+use std::{
+	borrow::Cow,
+	convert::TryFrom,
+	ffi::OsStr,
+	path::Path,
+	sync::Arc,
+};
+
+use crate::ffi::OsStrExt;
+use crate::runtime::{pattern, value::Value, Policy};
+use super::{Limits, Remote};
+
+
+/// Options that control how command blocks are executed.
+/// These may be changed globally through std.set_option, or locally in a command block
+/// through the set built-in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+	/// Whether a pipeline fails if any of its commands fails, instead of only the last one.
+	pub pipefail: bool,
+	/// Maximum amount of directory levels traversed by the recursive wildcard (`**`).
+	pub glob_depth: u32,
+	/// Whether wildcards match file names starting with a dot.
+	pub dotglob: bool,
+	/// Whether wildcards match case insensitively.
+	pub nocaseglob: bool,
+	/// Whether patterns with no matches expand to nothing, instead of the literal pattern.
+	pub nullglob: bool,
+	/// Whether patterns with no matches cause a panic. Takes precedence over nullglob.
+	pub failglob: bool,
+	/// Whether the first word of commands is expanded using aliases. Aliases are expanded
+	/// before the command block runs, so this is unaffected by the set built-in.
+	pub expand_aliases: bool,
+	/// Whether captures produce arrays of bytes instead of strings.
+	pub capture_bytes: bool,
+	/// Whether captures strip the trailing newline of the output.
+	pub strip_newline: bool,
+	/// Whether captures also forward the output to the shell's stdout and stderr.
+	pub tee_capture: bool,
+	/// Whether the outputs of commands are attached to pseudo-terminals, so that they
+	/// behave as if running interactively.
+	pub pty: bool,
+	/// Whether commands are printed instead of executed. The set built-in is still
+	/// executed, so that this may be disabled.
+	pub dry_run: bool,
+	/// Whether commands are printed before being executed.
+	pub xtrace: bool,
+	/// Whether output redirections refuse to overwrite existing files, unless forced with
+	/// `>|`.
+	pub noclobber: bool,
+	/// Whether results of synchronous and capture blocks include execution statistics.
+	pub command_stats: bool,
+	/// Whether commands are spawned with a clean environment, which contains only the
+	/// variables in passenv and the ones assigned in the command.
+	pub clean_env: bool,
+	/// The names of the environment variables passed to commands when cleanenv is set.
+	pub pass_env: Arc<[Box<OsStr>]>,
+	/// The directory in which commands are spawned, instead of the current directory.
+	/// Relative patterns and redirections are resolved in it as well. This is set through
+	/// std.with_cwd, and is not a named option.
+	pub cwd: Option<Arc<Path>>,
+	/// The scheduling limits of spawned commands. This is set through std.with_limits, and
+	/// is not a named option.
+	pub limits: Limits,
+	/// The remote host in which external commands are run. Builtins, function calls and
+	/// redirections are still handled locally. This is set through std.ssh, and is not a
+	/// named option.
+	pub remote: Option<Arc<Remote>>,
+	/// The restrictions on the resources the script may access. This is set through
+	/// Runtime::set_policy, and is not a named option.
+	pub policy: Arc<Policy>,
+}
+
+
+/// Failure to set an option.
+#[derive(Debug)]
+pub enum OptionError {
+	/// The given option name is not valid.
+	InvalidName,
+	/// The given value has an unexpected type or is out of range.
+	InvalidValue(&'static str),
+}
+
+
+impl Options {
+	/// Get the value of the option with the given name.
+	pub fn get<N: AsRef<[u8]>>(&self, name: N) -> Result<Value, OptionError> {
+		match name.as_ref() {
+			b"globdepth" => Ok(Value::Int(self.glob_depth.into())),
+			b"passenv" => {
+				let names: Vec<Value> = self.pass_env
+					.iter()
+					.map(|name| Value::from(name.as_bytes()))
+					.collect();
+
+				Ok(names.into())
+			}
+			name => self
+				.flag(name)
+				.map(|flag| Value::Bool(*flag)),
+		}
+	}
+
+
+	/// Set the option with the given name, returning it's previous value.
+	pub fn set<N: AsRef<[u8]>>(&mut self, name: N, value: Value) -> Result<Value, OptionError> {
+		let previous = self.get(&name)?;
+
+		match (name.as_ref(), value) {
+			(b"globdepth", Value::Int(value)) => {
+				self.glob_depth = u32::try_from(value)
+					.map_err(|_| OptionError::InvalidValue("positive integer"))?;
+			}
+			(b"globdepth", _) => return Err(OptionError::InvalidValue("int")),
+
+			(b"passenv", Value::Array(ref names)) => {
+				self.pass_env = names
+					.borrow()
+					.iter()
+					.map(
+						|name| match name {
+							Value::String(name) => Ok(AsRef::<OsStr>::as_ref(name).into()),
+							_ => Err(OptionError::InvalidValue("array of strings")),
+						}
+					)
+					.collect::<Result<_, _>>()?;
+			}
+			(b"passenv", _) => return Err(OptionError::InvalidValue("array of strings")),
+
+			(name, Value::Bool(value)) => *self.flag_mut(name)? = value,
+			(_, _) => return Err(OptionError::InvalidValue("bool")),
+		}
+
+		Ok(previous)
+	}
+
+
+	/// Set options from the arguments of the set built-in, which are pairs of `-o name`
+	/// (enable) or `+o name` (disable).
+	pub fn set_args(&mut self, args: &[Box<OsStr>]) -> Result<(), Option<Box<OsStr>>> {
+		if args.len() % 2 != 0 {
+			return Err(None);
+		}
+
+		for pair in args.chunks(2) {
+			let value = match pair[0].as_bytes() {
+				b"-o" => true,
+				b"+o" => false,
+				_ => return Err(Some(pair[0].clone())),
+			};
+
+			self
+				.set(pair[1].as_bytes(), value.into())
+				.map_err(|_| Some(pair[1].clone()))?;
+		}
+
+		Ok(())
+	}
+
+
+	/// The options for file name pattern expansion.
+	pub fn pattern(&self) -> pattern::Options {
+		pattern::Options {
+			max_depth: self.glob_depth,
+			dotfiles: self.dotglob,
+			case_sensitive: !self.nocaseglob,
+		}
+	}
+
+
+	/// Resolve a path in the working directory of commands.
+	pub fn path<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+		match &self.cwd {
+			Some(cwd) if path.is_relative() => cwd.join(path).into(),
+			_ => path.into(),
+		}
+	}
+
+
+	/// Get a boolean option by name.
+	fn flag(&self, name: &[u8]) -> Result<&bool, OptionError> {
+		match name {
+			b"pipefail" => Ok(&self.pipefail),
+			b"dotglob" => Ok(&self.dotglob),
+			b"nocaseglob" => Ok(&self.nocaseglob),
+			b"nullglob" => Ok(&self.nullglob),
+			b"failglob" => Ok(&self.failglob),
+			b"expandaliases" => Ok(&self.expand_aliases),
+			b"capturebytes" => Ok(&self.capture_bytes),
+			b"stripnewline" => Ok(&self.strip_newline),
+			b"teecapture" => Ok(&self.tee_capture),
+			b"pty" => Ok(&self.pty),
+			b"dryrun" => Ok(&self.dry_run),
+			b"xtrace" => Ok(&self.xtrace),
+			b"noclobber" => Ok(&self.noclobber),
+			b"commandstats" => Ok(&self.command_stats),
+			b"cleanenv" => Ok(&self.clean_env),
+			_ => Err(OptionError::InvalidName),
+		}
+	}
+
+
+	/// Get a mutable boolean option by name.
+	fn flag_mut(&mut self, name: &[u8]) -> Result<&mut bool, OptionError> {
+		match name {
+			b"pipefail" => Ok(&mut self.pipefail),
+			b"dotglob" => Ok(&mut self.dotglob),
+			b"nocaseglob" => Ok(&mut self.nocaseglob),
+			b"nullglob" => Ok(&mut self.nullglob),
+			b"failglob" => Ok(&mut self.failglob),
+			b"expandaliases" => Ok(&mut self.expand_aliases),
+			b"capturebytes" => Ok(&mut self.capture_bytes),
+			b"stripnewline" => Ok(&mut self.strip_newline),
+			b"teecapture" => Ok(&mut self.tee_capture),
+			b"pty" => Ok(&mut self.pty),
+			b"dryrun" => Ok(&mut self.dry_run),
+			b"xtrace" => Ok(&mut self.xtrace),
+			b"noclobber" => Ok(&mut self.noclobber),
+			b"commandstats" => Ok(&mut self.command_stats),
+			b"cleanenv" => Ok(&mut self.clean_env),
+			_ => Err(OptionError::InvalidName),
+		}
+	}
+}
+
+
+impl Default for Options {
+	fn default() -> Self {
+		let pattern = pattern::Options::default();
+
+		Self {
+			pipefail: true,
+			glob_depth: pattern.max_depth,
+			dotglob: pattern.dotfiles,
+			nocaseglob: !pattern.case_sensitive,
+			nullglob: true,
+			failglob: false,
+			expand_aliases: false,
+			capture_bytes: false,
+			strip_newline: false,
+			tee_capture: false,
+			pty: false,
+			dry_run: false,
+			xtrace: false,
+			noclobber: false,
+			command_stats: false,
+			clean_env: false,
+			pass_env: Arc::new([]),
+			cwd: None,
+			limits: Limits::default(),
+			remote: None,
+			policy: Arc::default(),
+		}
+	}
+}
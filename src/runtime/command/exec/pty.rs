@@ -0,0 +1,223 @@
+//! Pseudo-terminals, which are only available on Unix. Windows' pseudo-consoles take
+//! over the console of the shell, and are not supported.
+
+use std::{io, thread};
+#[cfg(unix)]
+use std::{
+	fs::File,
+	io::{Read, Write},
+	os::unix::prelude::{AsRawFd, FromRawFd, IntoRawFd, RawFd},
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Once,
+	},
+};
+
+
+/// Counter of window size changes of the shell's terminal, incremented by the SIGWINCH
+/// handler.
+#[cfg(unix)]
+static WINDOW_CHANGES: AtomicUsize = AtomicUsize::new(0);
+
+
+/// How long the relay waits for output before checking for window size changes, in
+/// milliseconds.
+#[cfg(unix)]
+const POLL_TIMEOUT: libc::c_int = 100;
+
+
+/// A pseudo-terminal, which makes commands behave as if attached to a terminal.
+#[derive(Debug)]
+pub struct Pty {
+	#[cfg(unix)]
+	master: File,
+	#[cfg(unix)]
+	slave: File,
+}
+
+
+#[cfg(windows)]
+impl Pty {
+	pub fn open() -> io::Result<Self> {
+		Err(
+			io::Error::new(io::ErrorKind::Unsupported, "pseudo-terminals are not supported on Windows")
+		)
+	}
+
+
+	pub fn relay(self, _: os_pipe::PipeWriter) -> (os_pipe::PipeWriter, Relay) {
+		unreachable!("pseudo-terminals can't be opened on Windows")
+	}
+}
+
+
+#[cfg(unix)]
+impl Pty {
+	/// Open a pseudo-terminal with the window size of the shell's terminal, if any.
+	pub fn open() -> io::Result<Self> {
+		let mut master = -1;
+		let mut slave = -1;
+
+		// Safety: the pointers are valid, and the optional arguments may be null.
+		let result = unsafe {
+			libc::openpty(
+				&mut master,
+				&mut slave,
+				std::ptr::null_mut(),
+				std::ptr::null(),
+				std::ptr::null(),
+			)
+		};
+
+		if result < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		// Safety: openpty produced these file descriptors, which we now own.
+		let pty = unsafe {
+			Self {
+				master: File::from_raw_fd(master),
+				slave: File::from_raw_fd(slave),
+			}
+		};
+
+		for fd in [master, slave] {
+			// Safety: fcntl has no memory safety requirements.
+			if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+				return Err(io::Error::last_os_error());
+			}
+		}
+
+		pty.raw_output()?;
+		copy_window_size(master);
+
+		Ok(pty)
+	}
+
+
+	/// Relay the output of the pseudo-terminal to the given writer, in a separate thread.
+	/// Returns the slave side, which should be given to the command, and the relay, which
+	/// finishes once all copies of the slave side are closed. Window size changes of the
+	/// shell's terminal are propagated meanwhile.
+	pub fn relay(self, mut writer: os_pipe::PipeWriter) -> (os_pipe::PipeWriter, Relay) {
+		install_window_handler();
+
+		let mut master = self.master;
+
+		let thread = thread::spawn(move || {
+			let mut changes = WINDOW_CHANGES.load(Ordering::Relaxed);
+			let mut buffer = [0; 4096];
+
+			loop {
+				let current = WINDOW_CHANGES.load(Ordering::Relaxed);
+				if current != changes {
+					changes = current;
+					copy_window_size(master.as_raw_fd());
+				}
+
+				let mut poll = libc::pollfd { fd: master.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+				// Safety: the pointer is valid for a single pollfd.
+				if unsafe { libc::poll(&mut poll, 1, POLL_TIMEOUT) } <= 0 {
+					continue; // Timeout or signal.
+				}
+
+				let size = match master.read(&mut buffer) {
+					Ok(0) => break,
+					Ok(size) => size,
+					Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+					// Reading the master fails with EIO once the slave is closed.
+					Err(_) => break,
+				};
+
+				if writer.write_all(&buffer[..size]).is_err() {
+					break;
+				}
+			}
+		});
+
+		// Safety: the fd is owned by the file, and ownership is transferred.
+		let slave = unsafe { os_pipe::PipeWriter::from_raw_fd(self.slave.into_raw_fd()) };
+
+		(slave, Relay(thread))
+	}
+
+
+	/// Disable output processing, so that newlines are not translated.
+	fn raw_output(&self) -> io::Result<()> {
+		let fd = self.slave.as_raw_fd();
+
+		// Safety: termios is a plain C struct, for which zero is a valid bit pattern.
+		let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+
+		// Safety: the pointer is valid.
+		if unsafe { libc::tcgetattr(fd, &mut termios) } < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		termios.c_oflag &= !libc::OPOST;
+
+		// Safety: the pointer is valid.
+		if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(())
+	}
+}
+
+
+/// The thread that relays the output of a pseudo-terminal.
+#[derive(Debug)]
+pub struct Relay(thread::JoinHandle<()>);
+
+
+impl Relay {
+	/// Wait until all output has been relayed.
+	pub fn join(self) {
+		if let Err(error) = self.0.join() {
+			std::panic::resume_unwind(error)
+		}
+	}
+}
+
+
+/// Copy the window size of the shell's terminal to the given pseudo-terminal.
+#[cfg(unix)]
+fn copy_window_size(pty: RawFd) {
+	// Safety: winsize is a plain C struct, for which zero is a valid bit pattern.
+	let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+
+	let found = [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO]
+		.iter()
+		// Safety: the pointer is valid.
+		.any(|&fd| unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } == 0);
+
+	if found {
+		// Safety: the pointer is valid.
+		unsafe { libc::ioctl(pty, libc::TIOCSWINSZ, &size) };
+	}
+}
+
+
+/// Install the SIGWINCH handler, which counts window size changes.
+#[cfg(unix)]
+fn install_window_handler() {
+	static INSTALL: Once = Once::new();
+
+	extern "C" fn handler(_: libc::c_int) {
+		WINDOW_CHANGES.fetch_add(1, Ordering::Relaxed);
+	}
+
+	INSTALL.call_once(|| {
+		// Safety: sigaction is a plain C struct, for which zero is a valid bit pattern.
+		let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+		action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+		action.sa_flags = libc::SA_RESTART;
+
+		// Safety: the handler only touches an atomic, which is async signal safe.
+		unsafe {
+			libc::sigemptyset(&mut action.sa_mask);
+			libc::sigaction(libc::SIGWINCH, &action, std::ptr::null_mut());
+		}
+	});
+}
@@ -0,0 +1,73 @@
+use std::{
+	ffi::{OsStr, OsString},
+	process,
+};
+
+use crate::ffi::{OsStrExt, OsStringExt};
+use crate::runtime::command::env;
+
+
+/// A remote host in which external commands are run, through ssh.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Remote {
+	/// The ssh program.
+	pub program: Box<OsStr>,
+	/// The destination, as in `[user@]host`.
+	pub destination: Box<OsStr>,
+	/// Additional arguments for ssh, such as the port.
+	pub args: Box<[Box<OsStr>]>,
+}
+
+
+impl Remote {
+	/// Build the ssh command that runs the given program in the remote host. The command
+	/// line is interpreted by the remote shell, so every word is quoted. The remote exit
+	/// status is preserved by ssh.
+	pub fn command<'a, E, A>(&self, env: E, program: &OsStr, args: A) -> process::Command
+	where
+		E: IntoIterator<Item = (&'a OsStr, &'a OsStr)>,
+		A: IntoIterator<Item = &'a OsStr>,
+	{
+		let mut line = Vec::new();
+
+		for (key, value) in env {
+			line.extend(key.as_bytes());
+			line.push(b'=');
+			quote(value.as_bytes(), &mut line);
+			line.push(b' ');
+		}
+
+		quote(program.as_bytes(), &mut line);
+
+		for arg in args {
+			line.push(b' ');
+			quote(arg.as_bytes(), &mut line);
+		}
+
+		let mut command = process::Command::new(&self.program);
+		command
+			.args(self.args.iter())
+			.arg("--")
+			.arg(&self.destination)
+			.arg(OsString::from_vec(line));
+
+		env::apply(&mut command);
+
+		command
+	}
+}
+
+
+/// Quote a word in single quotes for the POSIX shell.
+fn quote(word: &[u8], line: &mut Vec<u8>) {
+	line.push(b'\'');
+
+	for &c in word {
+		match c {
+			b'\'' => line.extend(b"'\\''"),
+			c => line.push(c),
+		}
+	}
+
+	line.push(b'\'');
+}
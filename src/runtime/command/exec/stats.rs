@@ -0,0 +1,198 @@
+use std::{io, process, time::Duration};
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+#[cfg(windows)]
+use std::os::windows::{io::AsRawHandle, process::ExitStatusExt};
+
+use crate::{runtime::value::{self, Value}, symbol};
+use super::IntoValue;
+
+
+/// Resource usage of a process, as reported by wait4, or GetProcessTimes and
+/// GetProcessMemoryInfo on Windows.
+#[derive(Debug)]
+pub struct ProcessStats {
+	pub pid: u32,
+	/// The signal that terminated the process, if any. Always none on Windows.
+	pub signal: Option<i32>,
+	/// Maximum resident set size, in kilobytes.
+	pub max_rss: i64,
+	/// Time spent in user mode.
+	pub user_time: Duration,
+	/// Time spent in kernel mode.
+	pub system_time: Duration,
+}
+
+
+impl ProcessStats {
+	/// Wait for a process, collecting it's resource usage.
+	#[cfg(unix)]
+	pub fn wait(process: &process::Child) -> io::Result<(process::ExitStatus, Self)> {
+		let pid = process.id();
+		let mut status = 0;
+		// Safety: rusage is a plain C struct, for which zero is a valid bit pattern.
+		let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+
+		loop {
+			// Safety: all pointers are valid.
+			let result = unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut usage) };
+
+			if result >= 0 {
+				break;
+			}
+
+			let error = io::Error::last_os_error();
+			if error.kind() != io::ErrorKind::Interrupted {
+				return Err(error);
+			}
+		}
+
+		let status = process::ExitStatus::from_raw(status);
+
+		let time = |time: libc::timeval| {
+			Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+		};
+
+		let stats = Self {
+			pid,
+			signal: status.signal(),
+			max_rss: usage.ru_maxrss as i64,
+			user_time: time(usage.ru_utime),
+			system_time: time(usage.ru_stime),
+		};
+
+		Ok((status, stats))
+	}
+
+
+	/// Wait for a process, collecting it's resource usage.
+	#[cfg(windows)]
+	pub fn wait(process: &process::Child) -> io::Result<(process::ExitStatus, Self)> {
+		use windows_sys::Win32::{
+			Foundation::{FILETIME, WAIT_FAILED},
+			System::{
+				ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS},
+				Threading::{GetExitCodeProcess, GetProcessTimes, WaitForSingleObject, INFINITE},
+			},
+		};
+
+		let handle = process.as_raw_handle() as _;
+
+		// Safety: the handle is owned by the child, which outlives this call.
+		if unsafe { WaitForSingleObject(handle, INFINITE) } == WAIT_FAILED {
+			return Err(io::Error::last_os_error());
+		}
+
+		let mut code = 0;
+		// Safety: the pointer is valid.
+		if unsafe { GetExitCodeProcess(handle, &mut code) } == 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		// Safety: FILETIME and PROCESS_MEMORY_COUNTERS are plain C structs, for which zero
+		// is a valid bit pattern.
+		let mut times: [FILETIME; 4] = unsafe { std::mem::zeroed() };
+		let mut memory: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+
+		let [creation, exit, kernel, user] = &mut times;
+		// Safety: the pointers are valid.
+		if unsafe { GetProcessTimes(handle, creation, exit, kernel, user) } == 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+		// Safety: the pointer is valid for the given size.
+		if unsafe { K32GetProcessMemoryInfo(handle, &mut memory, size) } == 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		// File times are counted in intervals of 100 nanoseconds.
+		let time = |time: &FILETIME| {
+			let intervals = (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+			Duration::from_nanos(intervals * 100)
+		};
+
+		let stats = Self {
+			pid: process.id(),
+			signal: None,
+			max_rss: (memory.PeakWorkingSetSize / 1024) as i64,
+			user_time: time(&times[3]),
+			system_time: time(&times[2]),
+		};
+
+		Ok((process::ExitStatus::from_raw(code), stats))
+	}
+}
+
+
+/// Execution statistics of a command block.
+#[derive(Debug, Default)]
+pub struct BlockStats {
+	/// Wall-clock duration of the block.
+	pub duration: Duration,
+	/// The processes spawned by the block, in order.
+	pub processes: Vec<ProcessStats>,
+}
+
+
+impl IntoValue for ProcessStats {
+	fn into_value(self, _: &symbol::Interner) -> Value {
+		thread_local! {
+			pub static PID: Value = "pid".into();
+			pub static SIGNAL: Value = "signal".into();
+			pub static MAX_RSS: Value = "maxrss".into();
+			pub static USER_TIME: Value = "utime".into();
+			pub static SYSTEM_TIME: Value = "stime".into();
+		}
+
+		let mut dict = value::DictMap::default();
+
+		PID.with(
+			|pid| dict.insert(pid.copy(), Value::Int(self.pid.into()))
+		);
+		SIGNAL.with(
+			|signal| dict.insert(
+				signal.copy(),
+				self.signal.map(|signal| Value::Int(signal.into())).unwrap_or_default()
+			)
+		);
+		MAX_RSS.with(
+			|max_rss| dict.insert(max_rss.copy(), Value::Int(self.max_rss))
+		);
+		USER_TIME.with(
+			|user_time| dict.insert(user_time.copy(), self.user_time.as_secs_f64().into())
+		);
+		SYSTEM_TIME.with(
+			|system_time| dict.insert(system_time.copy(), self.system_time.as_secs_f64().into())
+		);
+
+		value::Dict::new(dict).into()
+	}
+}
+
+
+impl IntoValue for BlockStats {
+	fn into_value(self, interner: &symbol::Interner) -> Value {
+		thread_local! {
+			pub static DURATION: Value = "duration".into();
+			pub static PROCESSES: Value = "processes".into();
+		}
+
+		let duration = self.duration;
+		let processes: Vec<Value> = self.processes
+			.into_iter()
+			.map(|process| process.into_value(interner))
+			.collect();
+
+		let mut dict = value::DictMap::default();
+
+		DURATION.with(
+			|key| dict.insert(key.copy(), duration.as_secs_f64().into())
+		);
+		PROCESSES.with(
+			|key| dict.insert(key.copy(), processes.into())
+		);
+
+		value::Dict::new(dict).into()
+	}
+}
@@ -0,0 +1,70 @@
+use std::{
+	ffi::OsStr,
+	io::Write,
+};
+
+use crate::ffi::OsStrExt;
+use super::Options;
+
+
+/// Format a command line for tracing, quoting the words that would be otherwise
+/// misinterpreted.
+pub fn command_line<'a, E, W>(env: E, words: W) -> String
+where
+	E: IntoIterator<Item = (&'a OsStr, &'a OsStr)>,
+	W: IntoIterator<Item = &'a OsStr>,
+{
+	let mut line = Vec::new();
+
+	for (key, value) in env {
+		line.extend(key.as_bytes());
+		line.push(b'=');
+		quote(value.as_bytes(), &mut line);
+		line.push(b' ');
+	}
+
+	for word in words {
+		quote(word.as_bytes(), &mut line);
+		line.push(b' ');
+	}
+
+	line.pop();
+
+	String::from_utf8_lossy(&line).into_owned()
+}
+
+
+/// Print a command line to the shell's stderr, if tracing is enabled. In dry-run mode, the
+/// line is printed as is, otherwise it's prefixed with a plus sign.
+pub fn print(line: &str, options: &Options) {
+	let prefix = match options {
+		Options { dry_run: true, .. } => "",
+		Options { xtrace: true, .. } => "+ ",
+		_ => return,
+	};
+
+	let _ = writeln!(std::io::stderr(), "{}{}", prefix, line);
+}
+
+
+/// Quote a word in single quotes if it contains any special characters.
+fn quote(word: &[u8], line: &mut Vec<u8>) {
+	let is_plain = |c: &u8| c.is_ascii_alphanumeric() || b"_-+=.,:/@%".contains(c);
+
+	if !word.is_empty() && word.iter().all(is_plain) {
+		line.extend(word);
+		return;
+	}
+
+	line.push(b'\'');
+
+	for &c in word {
+		match c {
+			b'\'' => line.extend(b"\\'"),
+			b'\\' => line.extend(b"\\\\"),
+			c => line.push(c),
+		}
+	}
+
+	line.push(b'\'');
+}
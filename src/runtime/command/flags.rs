@@ -0,0 +1,108 @@
+use super::{Dict, Panic, Runtime, SourcePos, Value};
+
+
+/// How dict entries are converted to command line flags.
+#[derive(Debug, Clone)]
+pub struct FlagStyle {
+	/// The prefix for the flag names.
+	pub prefix: Box<[u8]>,
+	/// The separator between the flag name and it's value. If none, the value is a
+	/// separate argument.
+	pub separator: Option<Box<[u8]>>,
+	/// The prefix for the names of false flags, after the flag prefix. If none, false
+	/// flags are omitted.
+	pub negation: Option<Box<[u8]>>,
+}
+
+
+impl Default for FlagStyle {
+	fn default() -> Self {
+		Self {
+			prefix: b"--".as_ref().into(),
+			separator: None,
+			negation: None,
+		}
+	}
+}
+
+
+impl FlagStyle {
+	/// Convert a dict to command line flags, in key order:
+	/// - true produces the flag alone;
+	/// - false produces the negated flag, or nothing if there is no negation prefix;
+	/// - nil produces nothing;
+	/// - arrays repeat the flag for each item;
+	/// - other values produce the flag followed by the value.
+	pub fn flags(&self, dict: &Dict, pos: SourcePos) -> Result<Vec<Vec<u8>>, Panic> {
+		let mut entries: Vec<(Value, Value)> = dict
+			.borrow()
+			.iter()
+			.map(|(key, value)| (key.copy(), value.copy()))
+			.collect();
+
+		entries.sort();
+
+		let mut flags = Vec::new();
+
+		for (key, value) in entries {
+			let name = match &key {
+				Value::String(name) => AsRef::<[u8]>::as_ref(name).to_owned(),
+				_ => return Err(Panic::type_error(key, "string", pos)),
+			};
+
+			match value {
+				Value::Nil => (),
+
+				Value::Bool(true) => flags.push(self.flag(&name)),
+
+				Value::Bool(false) => {
+					if let Some(negation) = &self.negation {
+						flags.push(self.flag(&[negation.as_ref(), &name].concat()));
+					}
+				}
+
+				Value::Array(ref array) => {
+					for item in array.borrow().iter() {
+						self.push_value(&mut flags, &name, item.copy(), pos.copy())?;
+					}
+				}
+
+				value => self.push_value(&mut flags, &name, value, pos.copy())?,
+			}
+		}
+
+		Ok(flags)
+	}
+
+
+	fn flag(&self, name: &[u8]) -> Vec<u8> {
+		[self.prefix.as_ref(), name].concat()
+	}
+
+
+	fn push_value(
+		&self,
+		flags: &mut Vec<Vec<u8>>,
+		name: &[u8],
+		value: Value,
+		pos: SourcePos,
+	) -> Result<(), Panic> {
+		let value = Runtime::build_basic_value(value, pos)?;
+		let mut flag = self.flag(name);
+
+		match &self.separator {
+			Some(separator) => {
+				flag.extend(separator.iter());
+				flag.extend(value.iter());
+				flags.push(flag);
+			}
+
+			None => {
+				flags.push(flag);
+				flags.push(value.into_vec());
+			}
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,49 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use super::{alias, env};
+
+
+/// A snapshot of the state that commands may change in the shell process: the working
+/// directory, the environment and local variables, the directory stack and the aliases. Isolated command
+/// blocks restore it after execution, so that their changes don't leak out.
+#[derive(Debug)]
+pub struct Isolation {
+	cwd: Option<PathBuf>,
+	env: Vec<(OsString, Option<OsString>)>,
+	locals: Vec<(OsString, OsString)>,
+	dir_stack: Vec<PathBuf>,
+	aliases: Vec<(Box<[u8]>, Box<[u8]>)>,
+}
+
+
+impl Isolation {
+	/// Take a snapshot of the current state.
+	pub fn save() -> Self {
+		Self {
+			cwd: std::env::current_dir().ok(),
+			env: env::changes(),
+			locals: env::locals(),
+			dir_stack: crate::io::dir_stack(),
+			aliases: alias::list(),
+		}
+	}
+
+
+	/// Restore the state from the snapshot.
+	pub fn restore(self) {
+		if let Some(cwd) = self.cwd {
+			// If the directory was removed meanwhile, there's nothing we can do.
+			let _ = std::env::set_current_dir(cwd);
+		}
+
+		env::set_changes(self.env);
+		env::set_locals(self.locals);
+
+		crate::io::set_dir_stack(self.dir_stack);
+
+		alias::clear();
+		for (name, value) in self.aliases {
+			alias::define(name, value);
+		}
+	}
+}
@@ -0,0 +1,199 @@
+use std::{
+	cell::RefCell,
+	collections::BTreeMap,
+	ffi::{OsStr, OsString},
+	path::{Path, PathBuf},
+};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use crate::ffi::OsStrExt;
+#[cfg(windows)]
+use crate::ffi::OsStringExt;
+use super::env;
+
+
+/// The extensions of executable files when PATHEXT is not set, as in cmd.exe.
+#[cfg(windows)]
+const DEFAULT_PATHEXT: &[u8] = b".COM;.EXE;.BAT;.CMD";
+
+
+thread_local! {
+	/// The cache of program paths, sorted by name. Like aliases, the cache is only
+	/// managed in the main thread, but other threads may use their own cache.
+	static CACHE: RefCell<Cache> = RefCell::new(Cache::default());
+}
+
+
+/// Program paths, along with the value of PATH in which they were found.
+#[derive(Debug, Default)]
+struct Cache {
+	path: Option<OsString>,
+	programs: BTreeMap<Box<OsStr>, Box<Path>>,
+}
+
+
+/// Find a program in PATH. Names containing a path separator are not looked up, and are
+/// only checked to be executable. On Windows, names are also tried with each extension in
+/// PATHEXT. Programs found in absolute directories are cached until PATH changes, or
+/// until they are no longer executable.
+pub fn find(name: &OsStr) -> Option<Box<Path>> {
+	if is_path(name.as_bytes()) {
+		return candidates(Path::new(name))
+			.find(|path| is_executable(path))
+			.map(Into::into);
+	}
+
+	let path = env::var(OsStr::new("PATH"));
+
+	CACHE.with(
+		|cache| {
+			let mut cache = cache.borrow_mut();
+
+			if cache.path != path {
+				cache.path = path.clone();
+				cache.programs.clear();
+			}
+
+			match cache.programs.get(name) {
+				Some(program) if is_executable(program) => return Some(program.clone()),
+				Some(_) => { cache.programs.remove(name); }
+				None => (),
+			}
+
+			let program = std::env::split_paths(path.as_deref()?)
+				.flat_map(|dir| candidates(&dir.join(name)))
+				.find(|program| is_executable(program))?;
+
+			// Relative directories depend on the current directory.
+			if program.is_absolute() {
+				cache.programs.insert(name.into(), program.clone().into());
+			}
+
+			Some(program.into())
+		}
+	)
+}
+
+
+/// Remove all cached paths.
+pub fn clear() {
+	CACHE.with(|cache| cache.borrow_mut().programs.clear())
+}
+
+
+/// Get all cached paths, sorted by name.
+pub fn list() -> Vec<(Box<OsStr>, Box<Path>)> {
+	CACHE.with(
+		|cache| cache
+			.borrow()
+			.programs
+			.iter()
+			.map(|(name, path)| (name.clone(), path.clone()))
+			.collect()
+	)
+}
+
+
+/// Check whether the path is an executable file.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+	path
+		.metadata()
+		.map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+		.unwrap_or(false)
+}
+
+
+/// Check whether the path is an executable file. The extension is checked by the
+/// candidates, as Windows has no execute permission.
+#[cfg(windows)]
+fn is_executable(path: &Path) -> bool {
+	path.is_file()
+}
+
+
+/// Check whether the name of a program is a path, which is not looked up. Windows paths
+/// may also be separated by backslashes, or start with a drive.
+fn is_path(name: &[u8]) -> bool {
+	if cfg!(windows) {
+		name.iter().any(|&byte| matches!(byte, b'/' | b'\\' | b':'))
+	} else {
+		name.contains(&b'/')
+	}
+}
+
+
+/// The paths that may hold a program.
+#[cfg(unix)]
+fn candidates(path: &Path) -> impl Iterator<Item = PathBuf> {
+	std::iter::once(path.to_owned())
+}
+
+
+/// The paths that may hold a program: the path itself, if it has an executable extension,
+/// and the path with each extension in PATHEXT.
+#[cfg(windows)]
+fn candidates(path: &Path) -> impl Iterator<Item = PathBuf> {
+	let pathext = env::var(OsStr::new("PATHEXT"));
+	let extensions = extensions(pathext.as_deref().map_or(DEFAULT_PATHEXT, OsStrExt::as_bytes));
+
+	with_extensions(path.as_os_str().as_bytes(), &extensions)
+		.into_iter()
+		.map(|path| OsString::from_vec(path).into())
+}
+
+
+/// Split the value of PATHEXT into lowercase extensions.
+#[cfg(any(windows, test))]
+fn extensions(pathext: &[u8]) -> Vec<Vec<u8>> {
+	pathext
+		.split(|&byte| byte == b';')
+		.filter(|extension| extension.starts_with(b".") && extension.len() > 1)
+		.map(<[u8]>::to_ascii_lowercase)
+		.collect()
+}
+
+
+/// Append each extension to the path, which is kept first if it already has one of them.
+/// Extensions are matched case-insensitively, as Windows file names.
+#[cfg(any(windows, test))]
+fn with_extensions(path: &[u8], extensions: &[Vec<u8>]) -> Vec<Vec<u8>> {
+	let path_lowercase = path.to_ascii_lowercase();
+
+	let is_executable = extensions
+		.iter()
+		.any(|extension| path_lowercase.ends_with(extension));
+
+	std::iter::once(path.to_owned())
+		.filter(|_| is_executable)
+		.chain(
+			extensions
+				.iter()
+				.map(|extension| [path, extension].concat())
+		)
+		.collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+
+	#[test]
+	fn test_pathext() {
+		let extensions = extensions(b".COM;.Exe;;bat;.CMD;");
+		assert_eq!(extensions, [b".com".to_vec(), b".exe".to_vec(), b".cmd".to_vec()]);
+
+		assert_eq!(
+			with_extensions(b"C:\\bin\\git", &extensions),
+			[&b"C:\\bin\\git.com"[..], b"C:\\bin\\git.exe", b"C:\\bin\\git.cmd"],
+		);
+
+		assert_eq!(
+			with_extensions(b"build.CMD", &extensions),
+			[&b"build.CMD"[..], b"build.CMD.com", b"build.CMD.exe", b"build.CMD.cmd"],
+		);
+	}
+}
@@ -1,17 +1,24 @@
+pub mod alias;
 mod arg;
+pub mod env;
 mod exec;
+mod flags;
+mod isolation;
+pub mod lookup;
 
 use std::{
 	borrow::Cow,
-	collections::HashMap,
-	os::unix::{ffi::OsStrExt, prelude::OsStringExt},
 	path::PathBuf,
-	ops::DerefMut, io::Read, ffi::{OsStr, OsString}, thread
+	ops::DerefMut, io::{self, Read, Write}, ffi::{OsStr, OsString}, thread, time::Duration,
 };
 
+use crate::ffi::{self, OsStrExt, OsStringExt};
+use crate::fmt;
 use super::{
 	program,
 	Dict,
+	DictMap,
+	Function,
 	Panic,
 	Runtime,
 	SourcePos,
@@ -19,6 +26,9 @@ use super::{
 };
 use arg::Args;
 use exec::IntoValue;
+use isolation::Isolation;
+pub use exec::{wait, Options, OptionError, Limits, IoPriority, IoClass, Remote, Source, WaitHandle};
+pub use flags::FlagStyle;
 
 
 impl Runtime {
@@ -27,22 +37,53 @@ impl Runtime {
 		block: &'static program::CommandBlock,
 		pos: SourcePos,
 	) -> Result<Value, Panic> {
-		let command_block = self.build_command_block(&block.head, &block.tail)?;
+		thread_local! {
+			pub static STATS: Value = "stats".into();
+		}
+
+		let mut functions = Vec::new();
+		let command_block = self.build_command_block(&block.head, &block.tail, &mut functions)?;
+
+		// The result options are taken from the global options, as changes from the set
+		// built-in are local to the block.
+		let options = command_block.options.clone();
 
 		match block.kind {
-			program::CommandBlockKind::Synchronous => {
-				command_block
-					.exec(
-						os_pipe::dup_stdout,
-						os_pipe::dup_stderr,
-					)
-					.map(|errors| errors.into_value(self.interner()))
-					.map_err(Into::into)
+			program::CommandBlockKind::Synchronous | program::CommandBlockKind::Isolated => {
+				let isolation = matches!(block.kind, program::CommandBlockKind::Isolated)
+					.then(Isolation::save);
+
+				let mut runner = FunctionRunner::new(self, functions);
+
+				let result = command_block.exec(
+					os_pipe::dup_stdout,
+					os_pipe::dup_stderr,
+					&mut runner,
+				);
+
+				if let Some(isolation) = isolation {
+					isolation.restore();
+				}
+
+				let (errors, stats) = result.map_err(|panic| runner.panic(panic))?;
+
+				let result = errors.into_value(self.interner());
+
+				if options.command_stats {
+					let mut fields = DictMap::default();
+
+					STATS.with(
+						|key| fields.insert(key.copy(), stats.into_value(self.interner()))
+					);
+
+					Ok(Self::attach_fields(result, fields))
+				} else {
+					Ok(result)
+				}
 			}
 
 			program::CommandBlockKind::Capture => {
 				thread_local! {
-					pub static ERROR: Value = "error".into();
 					pub static STDOUT: Value = "stdout".into();
 					pub static STDERR: Value = "stderr".into();
 				}
@@ -53,91 +94,89 @@ impl Runtime {
 				let (mut stderr_read, stderr_write) = os_pipe::pipe()
 					.map_err(|error| Panic::io(error, pos.copy()))?;
 
-				let stdout_reader = thread::spawn(move || {
-					let mut data = Vec::with_capacity(512);
-					stdout_read.read_to_end(&mut data)?;
-					Ok(data)
-				});
+				// With the tee option, the output is forwarded to the shell's own output as
+				// it's read.
+				let (stdout_tee, stderr_tee) =
+					if options.tee_capture {
+						let stdout = os_pipe::dup_stdout()
+							.map_err(|error| Panic::io(error, pos.copy()))?;
+						let stderr = os_pipe::dup_stderr()
+							.map_err(|error| Panic::io(error, pos.copy()))?;
+
+						(Some(stdout), Some(stderr))
+					} else {
+						(None, None)
+					};
 
-				let stderr_reader = thread::spawn(move || {
-					let mut data = Vec::with_capacity(512);
-					stderr_read.read_to_end(&mut data)?;
-					Ok(data)
-				});
+				let stdout_reader = thread::spawn(move || Self::pump(&mut stdout_read, stdout_tee));
+				let stderr_reader = thread::spawn(move || Self::pump(&mut stderr_read, stderr_tee));
 
-				let errors = command_block
+				let mut runner = FunctionRunner::new(self, functions);
+
+				let (errors, stats) = command_block
 					.exec(
 						// We must drop all writers before attempting to read, otherwise we'll deadlock.
 						move || stdout_write.try_clone(),
 						move || stderr_write.try_clone(),
+						&mut runner,
 					)
-					.map_err(Panic::from)?;
+					.map_err(|panic| runner.panic(panic))?;
 
-				let mut result = errors.into_value(self.interner());
-				let mut captures = {
+				let result = errors.into_value(self.interner());
+				let mut fields = {
 					let out = match stdout_reader.join() {
 						Err(error) => std::panic::resume_unwind(error),
 						Ok(result) => result
-							.map_err(|error| Panic::io(error, pos.copy()))?
-							.into_boxed_slice(),
+							.map_err(|error| Panic::io(error, pos.copy()))?,
 					};
 
 					let err = match stderr_reader.join() {
 						Err(error) => std::panic::resume_unwind(error),
 						Ok(result) => result
-							.map_err(|error| Panic::io(error, pos.copy()))?
-							.into_boxed_slice(),
+							.map_err(|error| Panic::io(error, pos.copy()))?,
 					};
 
-					let mut dict = HashMap::new();
+					let mut dict = DictMap::default();
 
 					STDOUT.with(
-						|stdout| dict.insert(stdout.copy(), out.into())
+						|stdout| dict.insert(stdout.copy(), Self::build_capture(out, &options))
 					);
 					STDERR.with(
-						|stderr| dict.insert(stderr.copy(), err.into())
+						|stderr| dict.insert(stderr.copy(), Self::build_capture(err, &options))
 					);
 
 					dict
 				};
 
-				match &mut result {
-					Value::Nil => Ok(Dict::new(captures).into()),
-					Value::Error(error) => {
-						let ctx = std::mem::take(error.context.borrow_mut().deref_mut());
-
-						ERROR.with(
-							|error| captures.insert(error.copy(), ctx)
-						);
-
-						*error.context.borrow_mut() = Dict::new(captures).into();
-
-						Ok(result)
-					},
-					_ => unreachable!("exec should only produce nil or error"),
+				if options.command_stats {
+					STATS.with(
+						|key| fields.insert(key.copy(), stats.into_value(self.interner()))
+					);
 				}
+
+				Ok(Self::attach_fields(result, fields))
 			}
 
 			program::CommandBlockKind::Asynchronous => {
 				thread_local! {
 					pub static JOIN: Value = "join".into();
+					pub static IS_FINISHED: Value = "is_finished".into();
 				}
 
-				let join_handle = std::thread::spawn(
-					|| command_block.exec(
-						os_pipe::dup_stdout,
-						os_pipe::dup_stderr,
-					)
-				);
+				// Functions can't be called from another thread.
+				if !functions.is_empty() {
+					return Err(Panic::async_function_command(pos));
+				}
 
-				let join_handle = exec::Join
-					::new(join_handle)
-					.into();
+				let handle = command_block.spawn(NoFunctions);
 
-				let mut dict = HashMap::new();
+				let mut dict = DictMap::default();
 
 				JOIN.with(
-					|join| dict.insert(join.copy(), join_handle)
+					|join| dict.insert(join.copy(), exec::Join::new(handle.clone()).into())
+				);
+				IS_FINISHED.with(
+					|is_finished| dict.insert(is_finished.copy(), exec::IsFinished::new(handle).into())
 				);
 
 				Ok(Dict::new(dict).into())
@@ -146,26 +185,103 @@ impl Runtime {
 	}
 
 
+	/// Attach fields to the result of a command block. A successful block produces a dict
+	/// of the fields, and a failed block has the fields in the error context, along with
+	/// the original context as the error field.
+	fn attach_fields(mut result: Value, mut fields: DictMap) -> Value {
+		thread_local! {
+			pub static ERROR: Value = "error".into();
+		}
+
+		match &mut result {
+			Value::Nil => Dict::new(fields).into(),
+			Value::Error(error) => {
+				let ctx = std::mem::take(error.context.borrow_mut().deref_mut());
+
+				ERROR.with(
+					|error| fields.insert(error.copy(), ctx)
+				);
+
+				*error.context.borrow_mut() = Dict::new(fields).into();
+
+				result
+			},
+			_ => unreachable!("exec should only produce nil or error"),
+		}
+	}
+
+
+	/// Read all the data from the reader, copying it to the tee writer as it arrives.
+	fn pump<R: Read>(reader: &mut R, tee: Option<os_pipe::PipeWriter>) -> io::Result<Vec<u8>> {
+		let mut data = Vec::with_capacity(512);
+
+		let mut tee = match tee {
+			Some(tee) => tee,
+			None => {
+				reader.read_to_end(&mut data)?;
+				return Ok(data);
+			}
+		};
+
+		let mut buffer = [0; 4096];
+
+		loop {
+			let size = match reader.read(&mut buffer) {
+				Ok(0) => break,
+				Ok(size) => size,
+				Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+				Err(error) => return Err(error),
+			};
+
+			data.extend_from_slice(&buffer[..size]);
+			tee.write_all(&buffer[..size])?;
+		}
+
+		Ok(data)
+	}
+
+
+	/// Convert captured output to a value, according to the options.
+	fn build_capture(mut output: Vec<u8>, options: &Options) -> Value {
+		if options.strip_newline && output.last() == Some(&b'\n') {
+			output.pop();
+		}
+
+		if options.capture_bytes {
+			let bytes: Vec<Value> = output
+				.into_iter()
+				.map(Value::Byte)
+				.collect();
+
+			bytes.into()
+		} else {
+			output.into_boxed_slice().into()
+		}
+	}
+
+
 	fn build_command_block(
 		&mut self,
 		head: &'static program::Command,
 		tail: &'static [program::Command],
+		functions: &mut Vec<Function>,
 	) -> Result<exec::Block, Panic> {
-		let head = self.build_command(head)?;
+		let head = self.build_command(head, functions)?;
 		let tail = tail
 			.iter()
 			.map(
-				|cmd| self.build_command(cmd)
+				|cmd| self.build_command(cmd, functions)
 			)
 			.collect::<Result<_, Panic>>()?;
 
-		Ok(exec::Block { head, tail })
+		Ok(exec::Block { head, tail, options: self.options.clone() })
 	}
 
 
 	fn build_command(
 		&mut self,
-		command: &'static program::Command
+		command: &'static program::Command,
+		functions: &mut Vec<Function>,
 	) -> Result<exec::Command, Panic> {
 		match command {
 			program::Command::Builtin { program, arguments, abort_on_error, pos } => {
@@ -188,11 +304,11 @@ impl Runtime {
 			}
 
 			program::Command::External { head, tail } => {
-				let head = self.build_basic_command(head)?;
+				let head = self.build_basic_command(head, functions)?;
 				let tail = tail
 					.iter()
 					.map(
-						|cmd| self.build_basic_command(cmd)
+						|cmd| self.build_basic_command(cmd, functions)
 					)
 					.collect::<Result<_, Panic>>()?;
 
@@ -205,17 +321,69 @@ impl Runtime {
 	fn build_basic_command(
 		&mut self,
 		command: &'static program::BasicCommand,
+		functions: &mut Vec<Function>,
 	) -> Result<exec::BasicCommand, Panic> {
 		let program_pos = command.program.pos.into();
 
-		let program = self.build_single_argument(
-			&command.program,
-			|items| Panic::invalid_command_args("program", items, program_pos)
-		)?;
+		// The program may be a function, either explicitly through a dollar, or named after
+		// a variable in scope.
+		let slot_ix = match command.program.parts.as_ref() {
+			[ program::ArgPart::Unit(program::ArgUnit::Dollar { slot_ix, .. }) ] => Some(slot_ix),
+			_ => command.function.as_ref(),
+		};
+
+		let function = slot_ix.and_then(
+			|slot_ix| match self.stack.fetch(slot_ix.into()) {
+				Value::Function(ref function) => Some(function.copy()),
+				_ => None,
+			}
+		);
 
-		let env = self.build_env_vars(&command.env)?;
+		let alias_words = match command.program.parts.as_ref() {
+			[ program::ArgPart::Unit(program::ArgUnit::Literal(name)) ] if self.options.expand_aliases => {
+				alias::expand(name)
+			}
+			_ => None,
+		};
+
+		let literal = |word: Box<[u8]>| exec::Argument::Literal(
+			OsString::from_vec(word.into()).into_boxed_os_str()
+		);
 
 		let mut args = Vec::new();
+
+		let program = match (alias_words, function) {
+			// The words of the alias are literals, the first being the program.
+			(Some(words), _) => {
+				let mut words = words.into_iter().map(literal);
+				let program = words.next().expect("alias should not be empty");
+				args.extend(words);
+				exec::Program::External(program)
+			}
+
+			(None, Some(function)) => {
+				let name = match command.program.parts.as_ref() {
+					[ program::ArgPart::Unit(program::ArgUnit::Literal(name)) ] => {
+						ffi::os_str(name).into()
+					}
+					_ => OsString::from(fmt::Show(&function, self.interner()).to_string()).into(),
+				};
+
+				functions.push(function);
+
+				exec::Program::Function { index: functions.len() - 1, name }
+			}
+
+			(None, None) => exec::Program::External(
+				self.build_single_argument(
+					&command.program,
+					|items| Panic::invalid_command_args("program", items, program_pos)
+				)?
+			),
+		};
+
+		let env = self.build_env_vars(&command.env)?;
+
 		for argument in command.arguments.iter() {
 			let arguments = self
 				.build_argument(argument)?
@@ -317,6 +485,17 @@ impl Runtime {
 				Ok(exec::RedirectionTarget::Overwrite(target))
 			}
 
+			program::RedirectionTarget::Clobber(arg) => {
+				let pos = arg.pos.into();
+
+				let target = self.build_single_argument(
+					arg,
+					|items| Panic::invalid_command_args("redirection", items, pos)
+				)?;
+
+				Ok(exec::RedirectionTarget::Clobber(target))
+			}
+
 			program::RedirectionTarget::Append(arg) => {
 				let pos = arg.pos.into();
 
@@ -397,9 +576,30 @@ impl Runtime {
 					}
 				}
 
+				program::ArgPart::Splice { slot_ix, pos } => {
+					let literals: Vec<Box<[u8]>> = match self.stack.fetch(slot_ix.into()) {
+						Value::Array(ref array) => array
+							.borrow()
+							.iter()
+							.map(|val| Self::build_basic_value(val.copy(), pos.into()))
+							.collect::<Result<_, Panic>>()?,
+
+						Value::Dict(ref dict) => FlagStyle::default()
+							.flags(dict, pos.into())?
+							.into_iter()
+							.map(Vec::into_boxed_slice)
+							.collect(),
+
+						other => return Err(Panic::type_error(other, "array or dict", pos.into())),
+					};
+
+					// Splices are always whole arguments, so each item becomes an argument.
+					args.push_literals(literals.iter());
+				}
+
 				program::ArgPart::Home => {
 					// TODO: should we emit an error value here?
-					let home = std::env::var_os("HOME")
+					let home = env::var(OsStr::new(crate::io::HOME))
 						.map(
 							|home| {
 								let mut path = PathBuf::from(home);
@@ -412,13 +612,23 @@ impl Runtime {
 					args.push_literal(home.as_bytes());
 				}
 
-				program::ArgPart::Range(from, to) => {
-					let items = (*from ..= *to)
-						.map(
-							|i| i.to_string().into_bytes()
-						);
+				program::ArgPart::UserHome(user) => {
+					let home = crate::io::user_home(user);
+					Self::push_dir(&mut args, home, || [&b"~"[..], &user[..], &b"/"[..]].concat());
+				}
+
+				program::ArgPart::WorkingDir => {
+					let dir = std::env::current_dir().ok();
+					Self::push_dir(&mut args, dir, || b"~+/".to_vec());
+				}
 
-					args.push_literals(items);
+				program::ArgPart::PreviousDir => {
+					let dir = env::var(OsStr::new("OLDPWD")).map(PathBuf::from);
+					Self::push_dir(&mut args, dir, || b"~-/".to_vec());
+				}
+
+				program::ArgPart::Range(range) => {
+					args.push_literals(Self::build_range(range));
 				},
 
 				program::ArgPart::Collection(items) => {
@@ -440,12 +650,23 @@ impl Runtime {
 				},
 
 				program::ArgPart::Star => args.push_pattern(b"*"),
+				program::ArgPart::DoubleStar => args.push_pattern(b"**"),
 				program::ArgPart::Percent => args.push_pattern(b"?"),
 				program::ArgPart::CharClass(class) => {
 					args.push_pattern(b"[");
 					args.push_pattern(class);
 					args.push_pattern(b"]");
 				}
+				program::ArgPart::Negation(patterns) => {
+					args.push_pattern(b"!(");
+					args.push_pattern(&patterns.join(&b'|'));
+					args.push_pattern(b")");
+				}
+				program::ArgPart::Alternation(patterns) => {
+					args.push_pattern(b"@(");
+					args.push_pattern(&patterns.join(&b'|'));
+					args.push_pattern(b")");
+				}
 			}
 		}
 
@@ -453,6 +674,50 @@ impl Runtime {
 	}
 
 
+	/// Push a directory with a trailing slash, or the given fallback if the directory is
+	/// unknown.
+	fn push_dir<F>(args: &mut Args, dir: Option<PathBuf>, fallback: F)
+	where
+		F: FnOnce() -> Vec<u8>,
+	{
+		match dir {
+			Some(mut dir) => {
+				dir.push("");
+				args.push_literal(dir.as_os_str().as_bytes());
+			}
+
+			None => args.push_literal(&fallback()),
+		}
+	}
+
+
+	/// The items of a brace range, in the direction from the first to the last bound.
+	fn build_range(range: &program::ArgRange) -> impl Iterator<Item = Vec<u8>> {
+		let range = *range;
+
+		let distance = (i128::from(range.to) - i128::from(range.from)).unsigned_abs();
+		let count = distance / u128::from(range.step) + 1;
+		let step =
+			if range.from <= range.to {
+				i128::from(range.step)
+			} else {
+				-i128::from(range.step)
+			};
+
+		(0 .. count).map(
+			move |ix| {
+				let value = i128::from(range.from) + step * ix as i128;
+
+				if range.chars {
+					vec![value as u8]
+				} else {
+					format!("{:0width$}", value, width = range.width).into_bytes()
+				}
+			}
+		)
+	}
+
+
 	fn build_basic_value(value: Value, pos: SourcePos) -> Result<Box<[u8]>, Panic> {
 		let literal: Option<Vec<u8>> = match &value {
 			Value::Nil => Some(Vec::default()),
@@ -461,6 +726,7 @@ impl Runtime {
 			Value::Float(float) => Some(float.to_string().into()),
 			Value::Byte(byte) => Some(vec![*byte]),
 			Value::String(string) => Some(AsRef::<[u8]>::as_ref(string).to_owned()),
+			Value::Buffer(buffer) => Some(buffer.borrow().clone()),
 
 			Value::Array(_) => None,
 			Value::Dict(_) => None,
@@ -470,6 +736,246 @@ impl Runtime {
 
 		literal
 			.map(Into::into)
-			.ok_or_else(|| Panic::type_error(value, "nil, bool, int, float, byte or string", pos))
+			.ok_or_else(|| Panic::type_error(value, "nil, bool, int, float, byte, string or buffer", pos))
+	}
+}
+
+
+/// Runs the hush functions used as commands in a command block.
+struct FunctionRunner<'a> {
+	runtime: &'a mut Runtime,
+	functions: Vec<Function>,
+	/// The panic of a function, which can't be carried by exec panics.
+	panic: Option<Panic>,
+}
+
+
+impl<'a> FunctionRunner<'a> {
+	fn new(runtime: &'a mut Runtime, functions: Vec<Function>) -> Self {
+		Self { runtime, functions, panic: None }
+	}
+
+
+	/// Convert a panic from the command block, recovering the panic of a function.
+	fn panic(&mut self, panic: exec::Panic) -> Panic {
+		match panic {
+			exec::Panic::FunctionPanic { .. } => self.panic
+				.take()
+				.expect("function panic should have been kept"),
+			panic => panic.into(),
+		}
+	}
+
+
+	/// Call a function used as a command. The return value determines the exit status:
+	/// ints are the status itself, errors and false are failures, and anything else is a
+	/// success.
+	fn call(&mut self, function: &Function, args: Vec<Value>, pos: SourcePos) -> Result<i32, exec::Panic> {
+		let args_start = self.runtime.arguments.len();
+		self.runtime.arguments.extend(args);
+
+		match self.runtime.call(Value::default(), function, args_start, pos.copy()) {
+			Ok(Value::Int(status)) => Ok(status as i32),
+			Ok(Value::Bool(false)) | Ok(Value::Error(_)) => Ok(1),
+			Ok(_) => Ok(0),
+			Err(panic) => {
+				self.panic = Some(panic);
+				Err(exec::Panic::function_panic(pos))
+			}
+		}
+	}
+
+
+	/// Call a command hook. Both hooks are disabled during the call, so that commands run
+	/// by the hook don't trigger them.
+	fn call_hook(
+		&mut self,
+		hook: fn(&mut Runtime) -> &mut Option<Function>,
+		command: exec::CommandInfo,
+		fields: DictMap,
+		pos: SourcePos,
+	) -> Result<(), exec::Panic> {
+		thread_local! {
+			pub static ARGV: Value = "argv".into();
+			pub static CWD: Value = "cwd".into();
+			pub static ENV: Value = "env".into();
+		}
+
+		let function = match hook(self.runtime).take() {
+			Some(function) => function,
+			None => return Ok(()),
+		};
+
+		let exec::CommandInfo { argv, cwd, env: vars, .. } = command;
+		let mut dict = fields;
+
+		ARGV.with(
+			|key| dict.insert(key.copy(), Self::build_args(argv))
+		);
+		CWD.with(
+			|key| dict.insert(key.copy(), cwd.into_os_string().into())
+		);
+		ENV.with(
+			|key| {
+				let vars: DictMap = vars
+					.into_vec()
+					.into_iter()
+					.map(|(key, value)| (key.into_os_string().into(), value.into_os_string().into()))
+					.collect();
+
+				dict.insert(key.copy(), Dict::new(vars).into())
+			}
+		);
+
+		let before = self.runtime.before_command.take();
+		let after = self.runtime.after_command.take();
+
+		let args_start = self.runtime.arguments.len();
+		self.runtime.arguments.push(Dict::new(dict).into());
+
+		let result = self.runtime.call(Value::default(), &function, args_start, pos.copy());
+
+		// The hooks may have been replaced during the call.
+		hook(self.runtime).get_or_insert(function);
+
+		if let Some(before) = before {
+			self.runtime.before_command.get_or_insert(before);
+		}
+
+		if let Some(after) = after {
+			self.runtime.after_command.get_or_insert(after);
+		}
+
+		match result {
+			Ok(_) => Ok(()),
+			Err(panic) => {
+				self.panic = Some(panic);
+				Err(exec::Panic::function_panic(pos))
+			}
+		}
+	}
+
+
+	fn build_args(args: Box<[Box<OsStr>]>) -> Value {
+		let args: Vec<Value> = args
+			.into_vec()
+			.into_iter()
+			.map(|arg| arg.into_os_string().into())
+			.collect();
+
+		args.into()
+	}
+}
+
+
+impl<'a> exec::FunctionRunner for FunctionRunner<'a> {
+	/// The function is called with a single argument: an array of the command arguments.
+	fn run(&mut self, function: usize, args: Box<[Box<OsStr>]>, pos: SourcePos) -> Result<i32, exec::Panic> {
+		let function = self.functions[function].copy();
+		self.call(&function, vec![ Self::build_args(args) ], pos)
+	}
+
+
+	fn handles_not_found(&self) -> bool {
+		self.runtime.command_not_found.is_some()
+	}
+
+
+	/// The handler is called with the program name and an array of the command arguments.
+	fn not_found(
+		&mut self,
+		program: Box<OsStr>,
+		args: Box<[Box<OsStr>]>,
+		pos: SourcePos,
+	) -> Result<i32, exec::Panic> {
+		let handler = self.runtime.command_not_found
+			.as_ref()
+			.expect("missing command not found handler")
+			.copy();
+
+		let args = vec![ program.into_os_string().into(), Self::build_args(args) ];
+
+		self.call(&handler, args, pos)
+	}
+
+
+	fn has_command_hooks(&self) -> bool {
+		self.runtime.before_command.is_some() || self.runtime.after_command.is_some()
+	}
+
+
+	/// The hook is called with a dict of the command's argv, cwd and assigned env.
+	fn before_command(&mut self, command: exec::CommandInfo, pos: SourcePos) -> Result<(), exec::Panic> {
+		self.call_hook(|runtime| &mut runtime.before_command, command, DictMap::default(), pos)
+	}
+
+
+	/// The hook is called with a dict of the command's argv, cwd and assigned env, along
+	/// with the exit status and the duration in seconds.
+	fn after_command(
+		&mut self,
+		command: exec::CommandInfo,
+		status: i32,
+		duration: Duration,
+		pos: SourcePos,
+	) -> Result<(), exec::Panic> {
+		thread_local! {
+			pub static STATUS: Value = "status".into();
+			pub static DURATION: Value = "duration".into();
+		}
+
+		let mut fields = DictMap::default();
+
+		STATUS.with(
+			|key| fields.insert(key.copy(), Value::Int(status.into()))
+		);
+		DURATION.with(
+			|key| fields.insert(key.copy(), duration.as_secs_f64().into())
+		);
+
+		self.call_hook(|runtime| &mut runtime.after_command, command, fields, pos)
+	}
+}
+
+
+/// A function runner for command blocks without function calls, which can't handle
+/// programs that are not found nor call command hooks either.
+struct NoFunctions;
+
+
+impl exec::FunctionRunner for NoFunctions {
+	fn run(&mut self, _: usize, _: Box<[Box<OsStr>]>, _: SourcePos) -> Result<i32, exec::Panic> {
+		unreachable!("command block should have no function calls")
+	}
+
+
+	fn handles_not_found(&self) -> bool {
+		false
+	}
+
+
+	fn not_found(&mut self, _: Box<OsStr>, _: Box<[Box<OsStr>]>, _: SourcePos) -> Result<i32, exec::Panic> {
+		unreachable!("command block should not handle programs that are not found")
+	}
+
+
+	fn has_command_hooks(&self) -> bool {
+		false
+	}
+
+
+	fn before_command(&mut self, _: exec::CommandInfo, _: SourcePos) -> Result<(), exec::Panic> {
+		unreachable!("command block should have no command hooks")
+	}
+
+
+	fn after_command(
+		&mut self,
+		_: exec::CommandInfo,
+		_: i32,
+		_: Duration,
+		_: SourcePos,
+	) -> Result<(), exec::Panic> {
+		unreachable!("command block should have no command hooks")
 	}
 }
@@ -0,0 +1,165 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	io::{self, Write},
+};
+
+use crate::symbol::{self, Symbol};
+use super::{program, vm, SourcePos};
+
+
+/// The execution counts of the statements of scripts, by source file and line, to measure
+/// the coverage of test suites. Statements are counted at the line they start.
+#[derive(Debug, Default)]
+pub struct Coverage(HashMap<Symbol, BTreeMap<u32, u64>>);
+
+
+impl Coverage {
+	/// Register the statements of a program, including those of it's functions, so that the
+	/// lines which never run are reported.
+	pub fn register(&mut self, program: &program::Program) {
+		self.block(&program.statements);
+	}
+
+
+	/// Count an execution of the statement at the given position.
+	pub fn hit(&mut self, pos: &SourcePos) {
+		*self.0
+			.entry(pos.path)
+			.or_default()
+			.entry(pos.line)
+			.or_default() += 1;
+	}
+
+
+	/// The execution counts by line of each source file.
+	pub fn files(&self) -> impl Iterator<Item = (Symbol, &BTreeMap<u32, u64>)> {
+		self.0
+			.iter()
+			.map(|(path, lines)| (*path, lines))
+	}
+
+
+	/// Write a report in the lcov tracefile format. Files are sorted by path.
+	pub fn write_lcov<W: Write>(&self, interner: &symbol::Interner, mut writer: W) -> io::Result<()> {
+		let mut files: Vec<(&[u8], &BTreeMap<u32, u64>)> = self.0
+			.iter()
+			.map(|(path, lines)| (interner.resolve(*path).unwrap_or_default(), lines))
+			.collect();
+
+		files.sort_by_key(|(path, _)| *path);
+
+		for (path, lines) in files {
+			writer.write_all(b"TN:\nSF:")?;
+			writer.write_all(path)?;
+			writer.write_all(b"\n")?;
+
+			for (line, count) in lines {
+				writeln!(writer, "DA:{},{}", line, count)?;
+			}
+
+			let hit = lines
+				.values()
+				.filter(|&&count| count > 0)
+				.count();
+
+			writeln!(writer, "LF:{}", lines.len())?;
+			writeln!(writer, "LH:{}", hit)?;
+			writeln!(writer, "end_of_record")?;
+		}
+
+		writer.flush()
+	}
+
+
+	fn block(&mut self, block: &program::Block) {
+		for statement in block.0.iter() {
+			if let Some(pos) = vm::statement_pos(statement) {
+				self.0
+					.entry(pos.path)
+					.or_default()
+					.entry(pos.line)
+					.or_default();
+			}
+
+			match statement {
+				program::Statement::Assign { left, right } => {
+					if let program::Lvalue::Access { object, field, .. } = left {
+						self.expr(object);
+						self.expr(field);
+					}
+
+					self.expr(right);
+				}
+
+				program::Statement::Return { expr } => self.expr(expr),
+
+				program::Statement::Break => (),
+
+				program::Statement::While { condition, block } => {
+					self.expr(condition);
+					self.block(block);
+				}
+
+				program::Statement::For { expr, block, .. } => {
+					self.expr(expr);
+					self.block(block);
+				}
+
+				program::Statement::Expr(expr) => self.expr(expr),
+			}
+		}
+	}
+
+
+	fn expr(&mut self, expr: &program::Expr) {
+		match expr {
+			program::Expr::Identifier { .. } => (),
+
+			program::Expr::Literal { literal, .. } => match literal {
+				program::Literal::Array(exprs) => {
+					for expr in exprs.iter() {
+						self.expr(expr);
+					}
+				}
+
+				program::Literal::Dict(entries) => {
+					for (_, expr) in entries.iter() {
+						self.expr(expr);
+					}
+				}
+
+				program::Literal::Function { body, .. } => self.block(body),
+
+				_ => (),
+			},
+
+			program::Expr::UnaryOp { operand, .. } => self.expr(operand),
+
+			program::Expr::BinaryOp { left, right, .. } => {
+				self.expr(left);
+				self.expr(right);
+			}
+
+			program::Expr::If { condition, then, otherwise, .. } => {
+				self.expr(condition);
+				self.block(then);
+				self.block(otherwise);
+			}
+
+			program::Expr::Access { object, field, .. } => {
+				self.expr(object);
+				self.expr(field);
+			}
+
+			program::Expr::Call { function, args, .. } => {
+				self.expr(function);
+
+				for arg in args.iter() {
+					self.expr(arg);
+				}
+			}
+
+			program::Expr::CommandBlock { .. } => (),
+		}
+	}
+}
@@ -0,0 +1,107 @@
+use std::{
+	cell::{Cell, RefCell},
+	collections::HashMap,
+	rc::Rc,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::Function;
+
+
+thread_local! {
+	/// The arrays and dicts which are watched for collection, by identity.
+	static WATCHED: RefCell<HashMap<Id, Watch>> = RefCell::new(HashMap::new());
+	/// The finalizers of the collected values, which are yet to run.
+	static PENDING: RefCell<Vec<Function>> = RefCell::new(Vec::new());
+}
+
+
+/// The identity of an array or dict, which is stored in the value. Identities are never
+/// reused, so that a new value can't be mistaken for a collected one.
+pub type Id = usize;
+
+
+/// Allocate an identity for a new value.
+pub fn new_id() -> Id {
+	static NEXT: AtomicUsize = AtomicUsize::new(0);
+	NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+
+/// A value which is watched for collection.
+struct Watch {
+	/// Whether the value is alive, shared with the weak references to it.
+	alive: Rc<Cell<bool>>,
+	finalizers: Vec<Function>,
+}
+
+
+impl Watch {
+	fn new() -> Self {
+		Self {
+			alive: Rc::new(Cell::new(true)),
+			finalizers: Vec::new(),
+		}
+	}
+}
+
+
+/// Watch a value, returning a flag which is cleared when it's collected.
+pub fn watch(id: Id) -> Rc<Cell<bool>> {
+	WATCHED.with(
+		|watched| watched
+			.borrow_mut()
+			.entry(id)
+			.or_insert_with(Watch::new)
+			.alive
+			.clone()
+	)
+}
+
+
+/// Register a function to be called after the value is collected.
+pub fn on_collect(id: Id, finalizer: Function) {
+	WATCHED.with(
+		|watched| watched
+			.borrow_mut()
+			.entry(id)
+			.or_insert_with(Watch::new)
+			.finalizers
+			.push(finalizer)
+	);
+}
+
+
+/// Notify that a value is being collected. This is called by the garbage collector before
+/// the value is freed, so values must not be allocated nor dereferenced here.
+pub fn collected(id: Id) {
+	// Values are also collected when the thread exits, after the thread locals may have
+	// been destroyed. There's nothing left to notify by then.
+	let watch = WATCHED
+		.try_with(
+			|watched| watched
+				.try_borrow_mut()
+				.ok()
+				.and_then(|mut watched| watched.remove(&id))
+		)
+		.ok()
+		.flatten();
+
+	if let Some(watch) = watch {
+		watch.alive.set(false);
+
+		let _ = PENDING.try_with(
+			|pending| {
+				if let Ok(mut pending) = pending.try_borrow_mut() {
+					pending.extend(watch.finalizers);
+				}
+			}
+		);
+	}
+}
+
+
+/// Take the finalizers of the values collected so far.
+pub fn pending() -> Vec<Function> {
+	PENDING.with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+}
@@ -0,0 +1,212 @@
+use std::{
+	cell::Cell,
+	mem,
+	ops::{Deref, DerefMut},
+};
+
+use gc::{unsafe_empty_trace, Finalize, Gc, GcCellRefMut, Trace};
+
+use super::value::{DictMap, Storage, Value};
+
+
+thread_local! {
+	/// The amount of bytes allocated for the values of this thread.
+	static USED: Cell<usize> = Cell::new(0);
+	/// The amount of values of this thread for which bytes are accounted.
+	static OBJECTS: Cell<usize> = Cell::new(0);
+	/// The amount of garbage collections noticed by canaries.
+	static COLLECTIONS: Cell<u64> = Cell::new(0);
+	/// Whether there is a canary waiting for the next collection.
+	static ARMED: Cell<bool> = Cell::new(false);
+}
+
+
+/// The amount of bytes allocated for arrays, dicts, strings and buffers, including the
+/// ones which are garbage but weren't collected yet.
+pub fn used() -> usize {
+	USED.with(Cell::get)
+}
+
+
+/// The amount of arrays, dicts, strings and buffers, including the ones which are garbage
+/// but weren't collected yet. The contents shared by clones are counted separately.
+pub fn objects() -> usize {
+	OBJECTS.with(Cell::get)
+}
+
+
+/// The amount of garbage collections, either automatic or forced, since the thread
+/// started. Collections are noticed by a canary, which is replaced when the next value is
+/// allocated. Hence, collections which happen before any value is allocated after the
+/// previous one are not counted.
+pub fn collections() -> u64 {
+	COLLECTIONS.with(Cell::get)
+}
+
+
+/// Make sure there's a canary waiting for the next collection.
+pub fn arm() {
+	if !ARMED.with(|armed| armed.replace(true)) {
+		// The box is unreachable right away, so that it's freed by the next collection.
+		drop(Gc::new(Canary));
+	}
+}
+
+
+/// A garbage collected box which is never referenced, to notice collections.
+#[derive(Finalize)]
+struct Canary;
+
+
+unsafe impl Trace for Canary {
+	unsafe_empty_trace!();
+}
+
+
+impl Drop for Canary {
+	fn drop(&mut self) {
+		// This runs while collecting, so nothing may be allocated. Canaries are also dropped
+		// when the thread is being torn down, after the counters.
+		let _ = COLLECTIONS.try_with(|collections| collections.set(collections.get() + 1));
+		let _ = ARMED.try_with(|armed| armed.set(false));
+	}
+}
+
+
+/// Replace an amount of allocated bytes by another.
+fn account(old: usize, new: usize) {
+	// Values may be dropped while the thread is being torn down, after the counter.
+	let _ = USED.try_with(
+		|used| used.set(used.get().saturating_sub(old) + new)
+	);
+}
+
+
+/// The contents of a value, for which the allocated bytes are accounted.
+pub trait Contents {
+	/// The amount of bytes allocated in the heap, excluding the value itself.
+	fn heap_size(&self) -> usize;
+}
+
+
+impl Contents for Vec<Value> {
+	fn heap_size(&self) -> usize {
+		self.capacity() * mem::size_of::<Value>()
+	}
+}
+
+
+impl Contents for DictMap {
+	fn heap_size(&self) -> usize {
+		// Each entry is stored along it's hash, and indexed by the hash table.
+		self.capacity() * (2 * mem::size_of::<Value>() + 2 * mem::size_of::<usize>())
+	}
+}
+
+
+impl Contents for Vec<u8> {
+	fn heap_size(&self) -> usize {
+		self.capacity()
+	}
+}
+
+
+impl Contents for Box<[u8]> {
+	fn heap_size(&self) -> usize {
+		self.len()
+	}
+}
+
+
+/// The bytes allocated for a value, which are accounted while it is alive. This is
+/// stored in the garbage collected box of the value, which is included in the amount.
+#[derive(Debug)]
+pub struct Bytes {
+	/// The size of the garbage collected box.
+	fixed: usize,
+	/// The size of the contents.
+	contents: Cell<usize>,
+}
+
+
+impl Bytes {
+	/// Account the bytes of a new value stored in a box of type T.
+	pub fn new<T, C: Contents>(contents: &C) -> Self {
+		let fixed = mem::size_of::<T>();
+		let contents = contents.heap_size();
+
+		account(0, fixed + contents);
+		OBJECTS.with(|objects| objects.set(objects.get() + 1));
+		arm();
+
+		Self { fixed, contents: Cell::new(contents) }
+	}
+
+
+	/// Update the size of the contents after they are changed.
+	pub fn update<C: Contents>(&self, contents: &C) {
+		let size = contents.heap_size();
+		account(self.contents.replace(size), size);
+	}
+}
+
+
+impl Drop for Bytes {
+	fn drop(&mut self) {
+		account(self.fixed + self.contents.get(), 0);
+		let _ = OBJECTS.try_with(|objects| objects.set(objects.get().saturating_sub(1)));
+	}
+}
+
+
+impl Finalize for Bytes { }
+
+
+unsafe impl Trace for Bytes {
+	unsafe_empty_trace!();
+}
+
+
+/// A mutable borrow of the contents of a value, which updates the accounted bytes when
+/// released.
+pub struct RefMut<'a, T: Trace + Contents + 'static> {
+	contents: GcCellRefMut<'a, Storage<T>, T>,
+	bytes: &'a Bytes,
+}
+
+
+impl<'a, T: Trace + Contents + 'static> RefMut<'a, T> {
+	pub fn new(contents: GcCellRefMut<'a, Storage<T>, T>, bytes: &'a Bytes) -> Self {
+		Self { contents, bytes }
+	}
+}
+
+
+impl<'a, T: Trace + Contents + 'static> Deref for RefMut<'a, T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.contents
+	}
+}
+
+
+impl<'a, T: Trace + Contents + 'static> DerefMut for RefMut<'a, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.contents
+	}
+}
+
+
+impl<'a, T: Trace + Contents + 'static> Drop for RefMut<'a, T> {
+	fn drop(&mut self) {
+		self.bytes.update(&*self.contents);
+	}
+}
+
+
+impl<'a, T: Trace + Contents + std::fmt::Debug + 'static> std::fmt::Debug for RefMut<'a, T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		std::fmt::Debug::fmt(&*self.contents, f)
+	}
+}
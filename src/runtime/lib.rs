@@ -1,10 +1,14 @@
 automod::dir!("src/runtime/lib");
 
+use ::gc::{Finalize, Trace};
+
 use super::{
 	keys,
 	Array,
+	Buffer,
 	CallContext,
 	Dict,
+	DictMap,
 	Error,
 	Float,
 	Function,
@@ -18,6 +22,16 @@ use super::{
 
 
 inventory::collect!(RustFun);
+inventory::collect!(Constant);
+
+
+/// A constant value in the stdlib, such as std.math.pi.
+pub struct Constant {
+	/// The full name, including the std prefix.
+	pub name: &'static str,
+	/// Build the value. Values are not shared between runtimes, hence the function.
+	pub value: fn() -> Value,
+}
 
 
 /// Instantiate the stdlib.
@@ -33,21 +47,93 @@ pub fn new() -> Value {
 		insert(path, fun.copy().into(), &mut dict);
 	}
 
+	for constant in inventory::iter::<Constant> {
+		let path = constant
+			.name
+			.strip_prefix("std.")
+			.expect("Builtin constant name missing std prefix.");
+
+		insert(path, (constant.value)(), &mut dict);
+	}
+
 	dict.into()
 }
 
 
+/// Get an iterator for a value, following the iteration protocol used by for loops.
+pub fn iterator(value: &Value) -> Option<Function> {
+	iter::iterator(value)
+}
+
+
+/// A module of the stdlib which may also be called as a function, such as std.env.
+#[derive(Trace, Finalize)]
+pub struct Module {
+	fun: RustFun,
+	members: Dict,
+}
+
+
+impl Module {
+	/// Get a member of the module.
+	pub fn get(&self, key: &Value) -> Option<Value> {
+		self.members.get(key).ok()
+	}
+}
+
+
+impl NativeFun for Module {
+	fn name(&self) -> &'static str { self.fun.name() }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		self.fun.call(context)
+	}
+
+	fn as_any(&self) -> Option<&dyn std::any::Any> {
+		Some(self)
+	}
+}
+
+
+/// Insert a value in the given path. A function and a module with the same path are
+/// merged into a callable module, regardless of the order in which they are inserted.
 fn insert(path: &str, value: Value, dict: &mut Dict) {
 	match path.split_once('.') {
-		None => dict.insert(path.into(), value),
+		None => {
+			let value = match (dict.get(&path.into()), value) {
+				(Ok(Value::Dict(ref members)), Value::Function(Function::Rust(ref fun))) => RustFun::from(
+					Module { fun: fun.copy(), members: members.copy() }
+				).into(),
+				(_, value) => value,
+			};
+
+			dict.insert(path.into(), value)
+		}
+
 		Some((key, path)) => {
-			let mut dict = dict.borrow_mut();
-			let dict = dict.entry(key.into()).or_insert_with(|| Dict::default().into());
+			let mut members = match dict.get(&key.into()) {
+				Ok(Value::Dict(ref members)) => members.copy(),
+
+				Ok(Value::Function(Function::Rust(ref fun))) => match fun.downcast_ref::<Module>() {
+					Some(module) => module.members.copy(),
+					None => {
+						let members = Dict::default();
+						let module = Module { fun: fun.copy(), members: members.copy() };
+						dict.insert(key.into(), RustFun::from(module).into());
+						members
+					}
+				},
+
+				Ok(_) => panic!("invalid value in std initialization"),
+
+				Err(_) => {
+					let members = Dict::default();
+					dict.insert(key.into(), members.copy().into());
+					members
+				}
+			};
 
-			match dict {
-				Value::Dict(dict) => insert(path, value, dict),
-				_ => panic!("invalid value in std initialization"),
-			}
+			insert(path, value, &mut members)
 		},
 	}
 }
@@ -0,0 +1,73 @@
+use gc::{Finalize, Trace};
+
+use crate::runtime::command::alias;
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(Alias) }
+inventory::submit!{ RustFun::from(Aliases) }
+
+#[derive(Trace, Finalize)]
+struct Alias;
+
+impl NativeFun for Alias {
+	fn name(&self) -> &'static str { "std.alias" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		// A nil value removes the alias. The previous value is returned.
+		let previous = match context.args() {
+			[ Value::String(ref name), Value::String(ref string) ] => {
+				let value: &[u8] = string.as_ref();
+
+				// Values are parsed when expanded, so they must be valid when defined.
+				if alias::words(value).is_none() {
+					return Err(Panic::value_error(string.copy().into(), "alias with balanced quotes", context.pos));
+				}
+
+				alias::define(AsRef::<[u8]>::as_ref(name).into(), value.into())
+			}
+
+			[ Value::String(ref name), Value::Nil ] => alias::remove(AsRef::<[u8]>::as_ref(name)),
+
+			[ Value::String(_), other ] => return Err(
+				Panic::type_error(other.copy(), "string or nil", context.pos)
+			),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		Ok(previous.map(Value::from).unwrap_or_default())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Aliases;
+
+impl NativeFun for Aliases {
+	fn name(&self) -> &'static str { "std.aliases" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				let aliases: DictMap = alias::list()
+					.into_iter()
+					.map(|(name, value)| (name.into(), value.into()))
+					.collect();
+
+				Ok(Dict::new(aliases).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
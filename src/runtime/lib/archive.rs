@@ -0,0 +1,511 @@
+use std::{
+	borrow::Cow,
+	ffi::OsStr,
+	fs::{self, File, Permissions},
+	io::{self, BufReader, BufWriter, Read, Write},
+	path::{Component, Path, PathBuf},
+};
+#[cfg(unix)]
+use std::os::unix::fs::{symlink, PermissionsExt};
+#[cfg(windows)]
+use std::os::windows::fs::symlink_file as symlink;
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use gc::{Finalize, Trace};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+	ffi::{self, OsStrExt},
+	runtime::{pattern::Matcher, SourcePos},
+};
+
+use super::{
+	util,
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Create) }
+inventory::submit! { RustFun::from(Extract) }
+inventory::submit! { RustFun::from(List) }
+
+
+/// The supported archive formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+	Tar,
+	TarGz,
+	Zip,
+}
+
+
+impl Format {
+	/// Get the format from the format option, or from the extension of the archive path.
+	fn new(path: &Value, option: Value, pos: SourcePos) -> Result<Self, Panic> {
+		let (name, expected) = match option {
+			Value::Nil => match path {
+				Value::String(ref path) => (path.as_bytes().to_owned(), path.copy().into()),
+				other => return Err(Panic::type_error(other.copy(), "string", pos)),
+			},
+
+			Value::String(ref format) => ([ &b"."[..], format.as_bytes() ].concat(), format.copy().into()),
+
+			other => return Err(Panic::type_error(other, "string", pos)),
+		};
+
+		if name.ends_with(b".tar.gz") || name.ends_with(b".tgz") {
+			Ok(Self::TarGz)
+		} else if name.ends_with(b".tar") {
+			Ok(Self::Tar)
+		} else if name.ends_with(b".zip") {
+			Ok(Self::Zip)
+		} else {
+			Err(Panic::value_error(expected, "tar, tar.gz, tgz or zip archive", pos))
+		}
+	}
+}
+
+
+/// Include and exclude filters for archive entries. Patterns are matched against each
+/// component of the entry path, so that excluding a directory excludes its contents.
+/// Include patterns are matched only against file names, and files which match none of
+/// them are skipped, unless there are no include patterns.
+struct Filter {
+	include: Vec<Matcher>,
+	exclude: Vec<Matcher>,
+}
+
+
+impl Filter {
+	fn new(include: Value, exclude: Value, pos: SourcePos) -> Result<Self, Panic> {
+		let patterns = |value: Value| -> Result<Vec<Matcher>, Panic> {
+			match value {
+				Value::Nil => Ok(Vec::new()),
+
+				Value::Array(ref array) => array
+					.borrow()
+					.iter()
+					.map(
+						|pattern| match pattern {
+							Value::String(ref string) => std::str::from_utf8(string.as_bytes())
+								.ok()
+								.and_then(|pattern| Matcher::new(pattern).ok())
+								.ok_or_else(|| Panic::invalid_pattern(AsRef::<OsStr>::as_ref(string).to_owned(), pos.copy())),
+							other => Err(Panic::type_error(other.copy(), "string", pos.copy())),
+						}
+					)
+					.collect(),
+
+				other => Err(Panic::type_error(other, "array", pos.copy())),
+			}
+		};
+
+		Ok(
+			Self {
+				include: patterns(include)?,
+				exclude: patterns(exclude)?,
+			}
+		)
+	}
+
+
+	/// Whether the entry with the given path should be processed.
+	fn accepts(&self, path: &Path, is_dir: bool) -> bool {
+		let matches = |patterns: &[Matcher], name: &OsStr| {
+			let name = String::from_utf8_lossy(name.as_bytes());
+			patterns.iter().any(|pattern| pattern.matches(&name, true, true))
+		};
+
+		let excluded = path
+			.components()
+			.any(|component| matches(&self.exclude, component.as_os_str()));
+
+		let included = is_dir
+			|| self.include.is_empty()
+			|| path.file_name().map_or(false, |name| matches(&self.include, name));
+
+		!excluded && included
+	}
+}
+
+
+/// Parse the options dict of the archive functions.
+fn options(value: Option<&Value>, path: &Value, pos: SourcePos) -> Result<(Format, Filter), Panic> {
+	let option = |name: &str| -> Result<Value, Panic> {
+		match value {
+			None => Ok(Value::Nil),
+			Some(Value::Dict(dict)) => Ok(dict.get(&name.into()).unwrap_or_default()),
+			Some(other) => Err(Panic::type_error(other.copy(), "dict", pos.copy())),
+		}
+	};
+
+	let format = Format::new(path, option("format")?, pos.copy())?;
+	let filter = Filter::new(option("include")?, option("exclude")?, pos)?;
+
+	Ok((format, filter))
+}
+
+
+/// Get the path of a string value. Relative paths are resolved from the working
+/// directory set by std.with_cwd. Paths denied by the sandbox policy cause a panic.
+fn path<'a>(context: &'a CallContext, value: &'a Value) -> Result<Cow<'a, Path>, Panic> {
+	let path = match value {
+		Value::String(string) => context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(string))),
+		other => return Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+	};
+
+	context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+
+	Ok(path)
+}
+
+
+/// A file to be added to an archive.
+struct Entry {
+	/// The path in the file system.
+	path: PathBuf,
+	/// The path in the archive.
+	name: PathBuf,
+	metadata: fs::Metadata,
+}
+
+
+/// Collect the entries to add to an archive, recursing into directories. Symlinks are
+/// not followed.
+fn entries(sources: &[(PathBuf, PathBuf)], filter: &Filter) -> io::Result<Vec<Entry>> {
+	fn walk(path: PathBuf, name: PathBuf, filter: &Filter, entries: &mut Vec<Entry>) -> io::Result<()> {
+		let metadata = fs::symlink_metadata(&path)?;
+		let is_dir = metadata.is_dir();
+
+		if !filter.accepts(&name, is_dir) {
+			return Ok(());
+		}
+
+		entries.push(Entry { path: path.clone(), name: name.clone(), metadata });
+
+		if is_dir {
+			let mut children = fs::read_dir(&path)?
+				.map(|entry| entry.map(|entry| entry.file_name()))
+				.collect::<io::Result<Vec<_>>>()?;
+			children.sort();
+
+			for child in children {
+				walk(path.join(&child), name.join(&child), filter, entries)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	let mut entries = Vec::new();
+
+	for (path, name) in sources {
+		walk(path.clone(), name.clone(), filter, &mut entries)?;
+	}
+
+	Ok(entries)
+}
+
+
+/// The name of a source in the archive: relative sources are named as given, without
+/// parent components, and absolute sources are named after their file name.
+fn entry_name(source: &Path) -> PathBuf {
+	if source.is_absolute() {
+		return source.file_name().map(PathBuf::from).unwrap_or_default();
+	}
+
+	source
+		.components()
+		.filter(|component| matches!(component, Component::Normal(_)))
+		.collect()
+}
+
+
+/// Write a tar archive.
+fn create_tar<W: Write>(writer: W, entries: &[Entry]) -> io::Result<W> {
+	let mut builder = tar::Builder::new(writer);
+	builder.follow_symlinks(false);
+
+	for entry in entries {
+		builder.append_path_with_name(&entry.path, &entry.name)?;
+	}
+
+	builder.into_inner()
+}
+
+
+/// The permissions for the given Unix permissions. On Windows, only the read-only
+/// attribute is set, when the mode has no write permission.
+#[cfg(unix)]
+fn permissions(_: &Path, mode: u32) -> io::Result<Permissions> {
+	Ok(Permissions::from_mode(mode & 0o7777))
+}
+
+
+/// The permissions for the given Unix permissions. On Windows, only the read-only
+/// attribute is set, when the mode has no write permission.
+#[cfg(windows)]
+fn permissions(path: &Path, mode: u32) -> io::Result<Permissions> {
+	let mut permissions = fs::metadata(path)?.permissions();
+	permissions.set_readonly(mode & 0o222 == 0);
+	Ok(permissions)
+}
+
+
+/// Write a zip archive. Unix permissions are stored, and symlinks are stored as such.
+fn create_zip(file: File, entries: &[Entry]) -> io::Result<()> {
+	let mut zip = ZipWriter::new(BufWriter::new(file));
+
+	for entry in entries {
+		let name = entry.name.to_string_lossy();
+		let options = FileOptions::default()
+			.compression_method(CompressionMethod::Deflated)
+			.unix_permissions(util::mode(&entry.metadata));
+
+		let file_type = entry.metadata.file_type();
+
+		if file_type.is_dir() {
+			zip.add_directory(name, options)?;
+		} else if file_type.is_symlink() {
+			let target = fs::read_link(&entry.path)?;
+			zip.add_symlink(name, target.to_string_lossy(), options)?;
+		} else {
+			zip.start_file(name, options)?;
+			io::copy(&mut File::open(&entry.path)?, &mut zip)?;
+		}
+	}
+
+	zip.finish()?.flush()
+}
+
+
+/// Create an archive.
+fn create(archive: &Path, sources: &[(PathBuf, PathBuf)], format: Format, filter: &Filter) -> io::Result<()> {
+	let entries = entries(sources, filter)?;
+	let file = File::create(archive)?;
+
+	match format {
+		Format::Tar => create_tar(BufWriter::new(file), &entries)?.flush(),
+
+		Format::TarGz => create_tar(GzEncoder::new(BufWriter::new(file), Compression::default()), &entries)?
+			.finish()?
+			.flush(),
+
+		Format::Zip => create_zip(file, &entries),
+	}
+}
+
+
+/// Extract a tar archive, preserving permissions. Entries are never extracted outside
+/// the destination.
+fn extract_tar<R: Read>(reader: R, destination: &Path, filter: &Filter) -> io::Result<()> {
+	let mut archive = tar::Archive::new(reader);
+	archive.set_preserve_permissions(true);
+
+	for entry in archive.entries()? {
+		let mut entry = entry?;
+
+		let path = entry.path()?.into_owned();
+		let is_dir = entry.header().entry_type().is_dir();
+
+		if filter.accepts(&path, is_dir) {
+			entry.unpack_in(destination)?;
+		}
+	}
+
+	Ok(())
+}
+
+
+/// Extract a zip archive, preserving permissions. Entries are never extracted outside
+/// the destination.
+fn extract_zip(file: File, destination: &Path, filter: &Filter) -> io::Result<()> {
+	let mut archive = ZipArchive::new(BufReader::new(file))?;
+
+	for ix in 0 .. archive.len() {
+		let mut file = archive.by_index(ix)?;
+
+		let name = match file.enclosed_name() {
+			Some(name) => name.to_owned(),
+			None => continue,
+		};
+
+		if !filter.accepts(&name, file.is_dir()) {
+			continue;
+		}
+
+		let path = destination.join(&name);
+
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		if file.unix_mode().map_or(false, |mode| mode & 0o170000 == 0o120000) {
+			let mut target = Vec::new();
+			file.read_to_end(&mut target)?;
+			symlink(&*ffi::os_str(&target), &path)?;
+			continue;
+		}
+
+		if file.is_dir() {
+			fs::create_dir_all(&path)?;
+		} else {
+			io::copy(&mut file, &mut File::create(&path)?)?;
+		}
+
+		if let Some(mode) = file.unix_mode() {
+			fs::set_permissions(&path, permissions(&path, mode)?)?;
+		}
+	}
+
+	Ok(())
+}
+
+
+/// Extract an archive.
+fn extract(archive: &Path, destination: &Path, format: Format, filter: &Filter) -> io::Result<()> {
+	let file = File::open(archive)?;
+	fs::create_dir_all(destination)?;
+
+	match format {
+		Format::Tar => extract_tar(BufReader::new(file), destination, filter),
+		Format::TarGz => extract_tar(GzDecoder::new(BufReader::new(file)), destination, filter),
+		Format::Zip => extract_zip(file, destination, filter),
+	}
+}
+
+
+/// List the entry names of an archive.
+fn list(archive: &Path, format: Format) -> io::Result<Vec<Value>> {
+	fn list_tar<R: Read>(reader: R) -> io::Result<Vec<Value>> {
+		let mut archive = tar::Archive::new(reader);
+
+		archive
+			.entries()?
+			.map(|entry| -> io::Result<Value> { Ok(Str::from(entry?.path()?.into_owned()).into()) })
+			.collect()
+	}
+
+	let file = File::open(archive)?;
+
+	match format {
+		Format::Tar => list_tar(BufReader::new(file)),
+		Format::TarGz => list_tar(GzDecoder::new(BufReader::new(file))),
+		Format::Zip => {
+			let mut archive = ZipArchive::new(BufReader::new(file))?;
+
+			(0 .. archive.len())
+				.map(|ix| -> io::Result<Value> { Ok(archive.by_index_raw(ix)?.name().into()) })
+				.collect()
+		}
+	}
+}
+
+
+/// Create an archive from an array of files and directories, which are added recursively.
+/// The format is given by the extension of the archive path, or by the format option.
+/// Options are include and exclude, which are arrays of file name patterns. IO errors
+/// are returned as error values.
+#[derive(Trace, Finalize)]
+struct Create;
+
+impl NativeFun for Create {
+	fn name(&self) -> &'static str { "std.archive.create" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (archive, sources, opts) = match context.args() {
+			[ archive, sources, opts @ .. ] if opts.len() <= 1 => (archive, sources, opts.first()),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos.copy()))
+		};
+
+		let sources = match sources {
+			Value::Array(sources) => sources
+				.borrow()
+				.iter()
+				.map(
+					|source| match source {
+						Value::String(ref string) => {
+							let source = Path::new(AsRef::<OsStr>::as_ref(string));
+							let path = context.runtime.options.path(source).into_owned();
+							context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+							Ok((path, entry_name(source)))
+						}
+						other => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+					}
+				)
+				.collect::<Result<Vec<_>, _>>()?,
+
+			other => return Err(Panic::type_error(other.copy(), "array", context.pos.copy())),
+		};
+
+		let (format, filter) = options(opts, archive, context.pos.copy())?;
+		let archive_path = path(&context, archive)?;
+
+		Ok(
+			create(&archive_path, &sources, format, &filter)
+				.map_err(|error| Error::new(error.to_string().into(), archive.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// Extract an archive into a directory, which is created if needed. The format is given
+/// by the extension of the archive path, or by the format option. Options are include
+/// and exclude, which are arrays of file name patterns. Permissions are preserved. IO
+/// errors are returned as error values.
+#[derive(Trace, Finalize)]
+struct Extract;
+
+impl NativeFun for Extract {
+	fn name(&self) -> &'static str { "std.archive.extract" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (archive, destination, opts) = match context.args() {
+			[ archive, destination, opts @ .. ] if opts.len() <= 1 => (archive, destination, opts.first()),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos.copy()))
+		};
+
+		let (format, filter) = options(opts, archive, context.pos.copy())?;
+		let archive_path = path(&context, archive)?;
+		let destination = path(&context, destination)?;
+
+		Ok(
+			extract(&archive_path, &destination, format, &filter)
+				.map_err(|error| Error::new(error.to_string().into(), archive.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// List the entry names of an archive. The format is given by the extension of the
+/// archive path, or by the format option. IO errors are returned as error values.
+#[derive(Trace, Finalize)]
+struct List;
+
+impl NativeFun for List {
+	fn name(&self) -> &'static str { "std.archive.list" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (archive, opts) = match context.args() {
+			[ archive, opts @ .. ] if opts.len() <= 1 => (archive, opts.first()),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+		};
+
+		let (format, _) = options(opts, archive, context.pos.copy())?;
+		let archive_path = path(&context, archive)?;
+
+		Ok(
+			list(&archive_path, format)
+				.map_err(|error| Error::new(error.to_string().into(), archive.copy()))
+				.into()
+		)
+	}
+}
@@ -11,6 +11,8 @@ use super::{
 
 inventory::submit! { RustFun::from(Args) }
 
+/// The arguments of the script, as given after the script path in the command line, in an
+/// array of strings.
 #[derive(Trace, Finalize)]
 struct Args;
 
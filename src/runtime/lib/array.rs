@@ -0,0 +1,466 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	util,
+	Array,
+	CallContext,
+	Function,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Map) }
+inventory::submit! { RustFun::from(Filter) }
+inventory::submit! { RustFun::from(Retain) }
+inventory::submit! { RustFun::from(Reduce) }
+inventory::submit! { RustFun::from(Fold) }
+inventory::submit! { RustFun::from(Any) }
+inventory::submit! { RustFun::from(All) }
+inventory::submit! { RustFun::from(Find) }
+inventory::submit! { RustFun::from(FindIndex) }
+inventory::submit! { RustFun::from(Zip) }
+inventory::submit! { RustFun::from(Flatten) }
+inventory::submit! { RustFun::from(Chunk) }
+inventory::submit! { RustFun::from(Unique) }
+inventory::submit! { RustFun::from(Reverse) }
+inventory::submit! { RustFun::from(Sort) }
+inventory::submit! { RustFun::from(SortBy) }
+
+
+/// Get the arguments of functions taking an array and a function. The elements are
+/// copied, so that the function may modify the array while it's being processed.
+fn array_fun(context: &CallContext) -> Result<(Array, Vec<Value>, Function), Panic> {
+	match context.args() {
+		[ Value::Array(ref array), Value::Function(ref fun) ] => {
+			let values = array.borrow().iter().map(Value::copy).collect();
+			Ok((array.copy(), values, fun.copy()))
+		}
+
+		[ Value::Array(_), other ] => Err(Panic::type_error(other.copy(), "function", context.pos.copy())),
+		[ other, _ ] => Err(Panic::type_error(other.copy(), "array", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	}
+}
+
+
+/// Get the array argument of functions taking only an array.
+fn array(context: &CallContext) -> Result<Array, Panic> {
+	match context.args() {
+		[ Value::Array(ref array) ] => Ok(array.copy()),
+
+		[ other ] => Err(Panic::type_error(other.copy(), "array", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+	}
+}
+
+
+/// Produce a new array with the results of calling a function on each element.
+#[derive(Trace, Finalize)]
+struct Map;
+
+impl NativeFun for Map {
+	fn name(&self) -> &'static str { "std.array.map" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (_, values, fun) = array_fun(&context)?;
+
+		let mut result = Vec::with_capacity(values.len());
+		for value in values {
+			result.push(util::call(&mut context, &fun, [ value ])?);
+		}
+
+		Ok(result.into())
+	}
+}
+
+
+/// Produce a new array with the elements for which the predicate returns true.
+#[derive(Trace, Finalize)]
+struct Filter;
+
+impl NativeFun for Filter {
+	fn name(&self) -> &'static str { "std.array.filter" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (_, values, fun) = array_fun(&context)?;
+
+		let mut result = Vec::new();
+		for value in values {
+			if util::test(&mut context, &fun, [ value.copy() ])? {
+				result.push(value);
+			}
+		}
+
+		Ok(result.into())
+	}
+}
+
+
+/// Keep only the elements for which the predicate returns true, in place.
+#[derive(Trace, Finalize)]
+struct Retain;
+
+impl NativeFun for Retain {
+	fn name(&self) -> &'static str { "std.array.retain" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (array, values, fun) = array_fun(&context)?;
+
+		let mut result = Vec::new();
+		for value in values {
+			if util::test(&mut context, &fun, [ value.copy() ])? {
+				result.push(value);
+			}
+		}
+
+		*array.borrow_mut() = result;
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Combine the elements with a function of the accumulator and the element, starting
+/// with the first element. Panics if the array is empty.
+#[derive(Trace, Finalize)]
+struct Reduce;
+
+impl NativeFun for Reduce {
+	fn name(&self) -> &'static str { "std.array.reduce" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (_, values, fun) = array_fun(&context)?;
+
+		let mut values = values.into_iter();
+		let mut accumulator = values
+			.next()
+			.ok_or_else(|| Panic::empty_collection(context.pos.copy()))?;
+
+		for value in values {
+			accumulator = util::call(&mut context, &fun, [ accumulator, value ])?;
+		}
+
+		Ok(accumulator)
+	}
+}
+
+
+/// Combine the elements with a function of the accumulator and the element, starting
+/// with the given initial value.
+#[derive(Trace, Finalize)]
+struct Fold;
+
+impl NativeFun for Fold {
+	fn name(&self) -> &'static str { "std.array.fold" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (values, mut accumulator, fun) = match context.args() {
+			[ Value::Array(ref array), initial, Value::Function(ref fun) ] => (
+				array.borrow().iter().map(Value::copy).collect::<Vec<_>>(),
+				initial.copy(),
+				fun.copy(),
+			),
+
+			[ Value::Array(_), _, other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			[ other, _, _ ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		for value in values {
+			accumulator = util::call(&mut context, &fun, [ accumulator, value ])?;
+		}
+
+		Ok(accumulator)
+	}
+}
+
+
+/// Whether the predicate returns true for any element. Stops at the first one.
+#[derive(Trace, Finalize)]
+struct Any;
+
+impl NativeFun for Any {
+	fn name(&self) -> &'static str { "std.array.any" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (_, values, fun) = array_fun(&context)?;
+
+		for value in values {
+			if util::test(&mut context, &fun, [ value ])? {
+				return Ok(true.into());
+			}
+		}
+
+		Ok(false.into())
+	}
+}
+
+
+/// Whether the predicate returns true for all elements. Stops at the first false.
+#[derive(Trace, Finalize)]
+struct All;
+
+impl NativeFun for All {
+	fn name(&self) -> &'static str { "std.array.all" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (_, values, fun) = array_fun(&context)?;
+
+		for value in values {
+			if !util::test(&mut context, &fun, [ value ])? {
+				return Ok(false.into());
+			}
+		}
+
+		Ok(true.into())
+	}
+}
+
+
+/// The first element for which the predicate returns true, or nil if there is none.
+#[derive(Trace, Finalize)]
+struct Find;
+
+impl NativeFun for Find {
+	fn name(&self) -> &'static str { "std.array.find" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (_, values, fun) = array_fun(&context)?;
+
+		for value in values {
+			if util::test(&mut context, &fun, [ value.copy() ])? {
+				return Ok(value);
+			}
+		}
+
+		Ok(Value::default())
+	}
+}
+
+
+/// The index of the first element for which the predicate returns true, or nil if there
+/// is none.
+#[derive(Trace, Finalize)]
+struct FindIndex;
+
+impl NativeFun for FindIndex {
+	fn name(&self) -> &'static str { "std.array.find_index" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (_, values, fun) = array_fun(&context)?;
+
+		for (ix, value) in values.into_iter().enumerate() {
+			if util::test(&mut context, &fun, [ value ])? {
+				return Ok(Value::Int(ix as i64));
+			}
+		}
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Pair the elements of two arrays, producing an array of two-element arrays. The result
+/// is as long as the shortest array.
+#[derive(Trace, Finalize)]
+struct Zip;
+
+impl NativeFun for Zip {
+	fn name(&self) -> &'static str { "std.array.zip" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref left), Value::Array(ref right) ] => Ok(
+				left
+					.borrow()
+					.iter()
+					.zip(right.borrow().iter())
+					.map(|(left, right)| Value::from(vec![ left.copy(), right.copy() ]))
+					.collect::<Vec<_>>()
+					.into()
+			),
+
+			[ Value::Array(_), other ] | [ other, _ ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Flatten one level of nesting. Elements which are not arrays are kept as they are.
+#[derive(Trace, Finalize)]
+struct Flatten;
+
+impl NativeFun for Flatten {
+	fn name(&self) -> &'static str { "std.array.flatten" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let array = array(&context)?;
+
+		let mut result = Vec::new();
+		for value in array.borrow().iter() {
+			match value {
+				Value::Array(inner) => result.extend(inner.borrow().iter().map(Value::copy)),
+				value => result.push(value.copy()),
+			}
+		}
+
+		Ok(result.into())
+	}
+}
+
+
+/// Split into arrays of the given size. The last one may be shorter.
+#[derive(Trace, Finalize)]
+struct Chunk;
+
+impl NativeFun for Chunk {
+	fn name(&self) -> &'static str { "std.array.chunk" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref array), Value::Int(size) ] if *size > 0 => Ok(
+				array
+					.borrow()
+					.chunks(*size as usize)
+					.map(|chunk| Value::from(chunk.iter().map(Value::copy).collect::<Vec<_>>()))
+					.collect::<Vec<_>>()
+					.into()
+			),
+
+			[ Value::Array(_), size @ Value::Int(_) ] => Err(Panic::value_error(size.copy(), "positive integer", context.pos)),
+			[ Value::Array(_), other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Remove duplicate elements, keeping the first occurrence of each.
+#[derive(Trace, Finalize)]
+struct Unique;
+
+impl NativeFun for Unique {
+	fn name(&self) -> &'static str { "std.array.unique" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let array = array(&context)?;
+		let array = array.borrow();
+
+		let mut seen = HashSet::new();
+		let result: Vec<Value> = array
+			.iter()
+			.filter(|value| seen.insert(*value))
+			.map(Value::copy)
+			.collect();
+
+		Ok(result.into())
+	}
+}
+
+
+/// Reverse the elements, in place.
+#[derive(Trace, Finalize)]
+struct Reverse;
+
+impl NativeFun for Reverse {
+	fn name(&self) -> &'static str { "std.array.reverse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		array(&context)?.borrow_mut().reverse();
+		Ok(Value::default())
+	}
+}
+
+
+/// Sort the elements in place. The sort is stable, and uses the natural order of values,
+/// or the given function, which receives two elements and returns whether the first is
+/// less than the second.
+#[derive(Trace, Finalize)]
+struct Sort;
+
+impl NativeFun for Sort {
+	fn name(&self) -> &'static str { "std.array.sort" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (array, mut values, fun) = match context.args() {
+			[ Value::Array(_) ] => {
+				array(&context)?.borrow_mut().sort();
+				return Ok(Value::default());
+			}
+
+			[ _, _ ] => array_fun(&context)?,
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		// The sort can't be interrupted, so further comparisons are skipped after a panic.
+		let mut panic = None;
+
+		values.sort_by(
+			|left, right| {
+				if panic.is_some() {
+					return Ordering::Equal;
+				}
+
+				let ordering = match util::test(&mut context, &fun, [ left.copy(), right.copy() ]) {
+					Ok(true) => Ok(Ordering::Less),
+					Ok(false) => util::test(&mut context, &fun, [ right.copy(), left.copy() ])
+						.map(|greater| if greater { Ordering::Greater } else { Ordering::Equal }),
+					Err(panic) => Err(panic),
+				};
+
+				ordering.unwrap_or_else(
+					|error| {
+						panic = Some(error);
+						Ordering::Equal
+					}
+				)
+			}
+		);
+
+		match panic {
+			Some(panic) => Err(panic),
+			None => {
+				*array.borrow_mut() = values;
+				Ok(Value::default())
+			}
+		}
+	}
+}
+
+
+/// Sort the elements in place by the result of the given function, which is called once
+/// for each element. The sort is stable.
+#[derive(Trace, Finalize)]
+struct SortBy;
+
+impl NativeFun for SortBy {
+	fn name(&self) -> &'static str { "std.array.sort_by" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (array, values, fun) = array_fun(&context)?;
+
+		let mut keyed = Vec::with_capacity(values.len());
+		for value in values {
+			let key = util::call(&mut context, &fun, [ value.copy() ])?;
+			keyed.push((key, value));
+		}
+
+		keyed.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+		*array.borrow_mut() = keyed
+			.into_iter()
+			.map(|(_, value)| value)
+			.collect();
+
+		Ok(Value::default())
+	}
+}
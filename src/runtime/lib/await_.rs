@@ -0,0 +1,78 @@
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Await) }
+
+/// Wait for asynchronous values, such as asynchronous command blocks, timers, socket reads
+/// and threads, by calling their join method. Given an array, waits for all of the values,
+/// and returns an array of the results, in order. Command blocks, timers and socket reads
+/// are waited concurrently by a single thread, without blocking the script until awaited.
+/// This merely calls the join methods.
+#[derive(Trace, Finalize)]
+struct Await;
+
+impl Await {
+	fn join(context: &mut CallContext, value: &Value, pos: &SourcePos) -> Result<Value, Panic> {
+		let join = match value {
+			Value::Dict(dict) => dict.get(&"join".into()).ok(),
+			_ => None,
+		};
+
+		match join {
+			Some(Value::Function(ref join)) => util::call(context, join, []),
+			_ => Err(Panic::type_error(value.copy(), "dict with a join method, or array", pos.copy())),
+		}
+	}
+}
+
+impl NativeFun for Await {
+	fn name(&self) -> &'static str { "std.await" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let value = match context.args() {
+			[ value ] => value.copy(),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let pos = context.pos.copy();
+
+		match value {
+			Value::Array(ref array) => {
+				let values: Vec<Value> = array
+					.borrow()
+					.iter()
+					.map(Value::copy)
+					.collect();
+
+				let mut results = Vec::with_capacity(values.len());
+				// Values are awaited even after a panic, so that none are left running.
+				let mut panic = None;
+
+				for value in values {
+					match Self::join(&mut context, &value, &pos) {
+						Ok(result) => results.push(result),
+						Err(error) => { panic.get_or_insert(error); }
+					}
+				}
+
+				match panic {
+					Some(panic) => Err(panic),
+					None => Ok(results.into()),
+				}
+			}
+
+			value => Self::join(&mut context, &value, &pos),
+		}
+	}
+}
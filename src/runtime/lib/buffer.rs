@@ -0,0 +1,464 @@
+use std::{
+	convert::{TryFrom, TryInto},
+	ffi::OsStr,
+	fs,
+	path::Path,
+};
+
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	Buffer,
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(New) }
+inventory::submit! { RustFun::from(FromValue) }
+inventory::submit! { RustFun::from(ToStr) }
+inventory::submit! { RustFun::from(Slice) }
+inventory::submit! { RustFun::from(Find) }
+inventory::submit! { RustFun::from(Push) }
+inventory::submit! { RustFun::from(ExtendWith) }
+inventory::submit! { RustFun::from(Pack) }
+inventory::submit! { RustFun::from(Unpack) }
+inventory::submit! { RustFun::from(ReadFile) }
+
+
+/// Get a byte from an int between 0 and 255 or a char.
+fn byte(value: &Value, pos: SourcePos) -> Result<u8, Panic> {
+	match value {
+		Value::Byte(byte) => Ok(*byte),
+		Value::Int(int) => u8::try_from(*int)
+			.map_err(|_| Panic::value_error(value.copy(), "integer between 0 and 255", pos)),
+		other => Err(Panic::type_error(other.copy(), "int or char", pos)),
+	}
+}
+
+
+/// Get an index into a buffer, which may be equal to its length.
+fn offset(value: &Value, buffer: &Buffer, pos: SourcePos) -> Result<usize, Panic> {
+	match value {
+		Value::Int(int) => usize::try_from(*int)
+			.ok()
+			.filter(|offset| *offset <= buffer.borrow().len())
+			.ok_or_else(|| Panic::index_out_of_bounds(value.copy(), pos)),
+		other => Err(Panic::type_error(other.copy(), "int", pos)),
+	}
+}
+
+
+/// Create a buffer with the given size, filled with zeros. The size defaults to zero.
+#[derive(Trace, Finalize)]
+struct New;
+
+impl NativeFun for New {
+	fn name(&self) -> &'static str { "std.buffer.new" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(Buffer::new(Vec::new()).into()),
+
+			[ size @ Value::Int(int) ] => usize::try_from(*int)
+				.map(|size| Buffer::new(vec![0; size]).into())
+				.map_err(|_| Panic::value_error(size.copy(), "non-negative integer", context.pos.copy())),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Create a buffer with a copy of the bytes of a string, a buffer, or an array of bytes,
+/// which may be either chars or ints.
+#[derive(Trace, Finalize)]
+struct FromValue;
+
+impl NativeFun for FromValue {
+	fn name(&self) -> &'static str { "std.buffer.from" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => {
+				if let Some(bytes) = util::bytes(value) {
+					return Ok(Buffer::new(bytes.into_owned()).into());
+				}
+
+				match value {
+					Value::Array(array) => Ok(
+						Buffer::new(
+							array
+								.borrow()
+								.iter()
+								.map(|value| byte(value, context.pos.copy()))
+								.collect::<Result<_, _>>()?
+						).into()
+					),
+
+					other => Err(Panic::type_error(other.copy(), "string, buffer or array", context.pos)),
+				}
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Convert a buffer to a string, copying its bytes.
+#[derive(Trace, Finalize)]
+struct ToStr;
+
+impl NativeFun for ToStr {
+	fn name(&self) -> &'static str { "std.buffer.to_string" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Buffer(ref buffer) ] => Ok(Str::from(buffer.borrow().as_slice()).into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "buffer", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Copy a range of a buffer to a new buffer. The end is exclusive, and defaults to the
+/// end of the buffer.
+#[derive(Trace, Finalize)]
+struct Slice;
+
+impl NativeFun for Slice {
+	fn name(&self) -> &'static str { "std.buffer.slice" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (buffer, start, end) = match context.args() {
+			[ Value::Buffer(ref buffer), start ] => {
+				let start = offset(start, buffer, context.pos.copy())?;
+				(buffer, start, buffer.borrow().len())
+			}
+
+			[ Value::Buffer(ref buffer), start, end ] => {
+				let start = offset(start, buffer, context.pos.copy())?;
+				let end = offset(end, buffer, context.pos.copy())?;
+				(buffer, start, end)
+			}
+
+			[ other, _ ] | [ other, _, _ ] => return Err(Panic::type_error(other.copy(), "buffer", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let slice = buffer
+			.borrow()
+			.get(start .. end)
+			.map(|slice| slice.to_vec());
+
+		match slice {
+			Some(slice) => Ok(Buffer::new(slice).into()),
+			None => Err(Panic::index_out_of_bounds(Value::Int(end as i64), context.pos)),
+		}
+	}
+}
+
+
+/// Find the first occurrence of a sequence of bytes, starting at the given offset, which
+/// defaults to zero. Returns the index, or nil if not found.
+#[derive(Trace, Finalize)]
+struct Find;
+
+impl NativeFun for Find {
+	fn name(&self) -> &'static str { "std.buffer.find" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (buffer, needle, start) = match context.args() {
+			[ Value::Buffer(ref buffer), needle ] => (buffer, needle, 0),
+
+			[ Value::Buffer(ref buffer), needle, start ] => (buffer, needle, offset(start, buffer, context.pos.copy())?),
+
+			[ other, _ ] | [ other, _, _ ] => return Err(Panic::type_error(other.copy(), "buffer", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let needle = util::bytes(needle)
+			.ok_or_else(|| Panic::type_error(needle.copy(), "string, buffer or byte array", context.pos.copy()))?;
+
+		Ok(
+			buffer.borrow()[start ..]
+				.find(&needle)
+				.map(|ix| Value::Int((start + ix) as i64))
+				.unwrap_or_default()
+		)
+	}
+}
+
+
+/// Append a byte, given as an int or a char.
+#[derive(Trace, Finalize)]
+struct Push;
+
+impl NativeFun for Push {
+	fn name(&self) -> &'static str { "std.buffer.push" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Buffer(ref buffer), value ] => {
+				let byte = byte(value, context.pos.copy())?;
+				buffer.borrow_mut().push(byte);
+				Ok(Value::default())
+			}
+
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "buffer", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Append the bytes of a string, a buffer, or an array of bytes.
+#[derive(Trace, Finalize)]
+struct ExtendWith;
+
+impl NativeFun for ExtendWith {
+	fn name(&self) -> &'static str { "std.buffer.extend" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Buffer(ref buffer), data ] => {
+				let data = util::bytes(data)
+					.ok_or_else(|| Panic::type_error(data.copy(), "string, buffer or byte array", context.pos.copy()))?;
+
+				buffer.borrow_mut().extend_from_slice(&data);
+
+				Ok(Value::default())
+			}
+
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "buffer", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// The kind of number in a binary format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+	Signed,
+	Unsigned,
+	Float,
+}
+
+
+/// A binary number format, such as u16le or f64be. The endianness defaults to big endian,
+/// as in network protocols, and may be omitted for single bytes.
+#[derive(Debug, Clone, Copy)]
+struct Format {
+	kind: Kind,
+	/// Size in bytes.
+	size: usize,
+	little_endian: bool,
+}
+
+
+impl Format {
+	fn parse(value: &Value, pos: SourcePos) -> Result<Self, Panic> {
+		let format = match value {
+			Value::String(string) => string.as_bytes(),
+			other => return Err(Panic::type_error(other.copy(), "string", pos)),
+		};
+
+		let invalid = || Panic::value_error(value.copy(), "number format, such as u8, i32le or f64be", pos.copy());
+
+		let (kind, rest) = match format.split_first() {
+			Some((b'i', rest)) => (Kind::Signed, rest),
+			Some((b'u', rest)) => (Kind::Unsigned, rest),
+			Some((b'f', rest)) => (Kind::Float, rest),
+			_ => return Err(invalid()),
+		};
+
+		let (bits, little_endian) = if let Some(bits) = rest.strip_suffix(b"le") {
+			(bits, true)
+		} else if let Some(bits) = rest.strip_suffix(b"be") {
+			(bits, false)
+		} else {
+			(rest, false)
+		};
+
+		let size = match (kind, bits) {
+			(Kind::Signed, b"8") | (Kind::Unsigned, b"8") => 1,
+			(Kind::Signed, b"16") | (Kind::Unsigned, b"16") => 2,
+			(_, b"32") => 4,
+			(_, b"64") => 8,
+			_ => return Err(invalid()),
+		};
+
+		Ok(Self { kind, size, little_endian })
+	}
+
+
+	/// Encode a number in this format.
+	fn encode(&self, value: &Value, pos: SourcePos) -> Result<Vec<u8>, Panic> {
+		let mut bytes = match (self.kind, value) {
+			(Kind::Float, Value::Float(float)) if self.size == 4 => (float.0 as f32).to_le_bytes().to_vec(),
+			(Kind::Float, Value::Int(int)) if self.size == 4 => (*int as f32).to_le_bytes().to_vec(),
+			(Kind::Float, Value::Float(float)) => float.0.to_le_bytes().to_vec(),
+			(Kind::Float, Value::Int(int)) => (*int as f64).to_le_bytes().to_vec(),
+			(Kind::Float, other) => return Err(Panic::type_error(other.copy(), "int or float", pos)),
+
+			(kind, Value::Int(int)) => {
+				let bits = self.size as u32 * 8;
+
+				let (min, max) = match kind {
+					Kind::Signed => (i64::MIN >> (64 - bits), i64::MAX >> (64 - bits)),
+					_ => (0, (u64::MAX >> (64 - bits)).min(i64::MAX as u64) as i64),
+				};
+
+				if *int < min || *int > max {
+					return Err(Panic::value_error(value.copy(), "integer in the range of the format", pos));
+				}
+
+				int.to_le_bytes()[.. self.size].to_vec()
+			}
+
+			(_, other) => return Err(Panic::type_error(other.copy(), "int", pos)),
+		};
+
+		if !self.little_endian {
+			bytes.reverse();
+		}
+
+		Ok(bytes)
+	}
+
+
+	/// Decode a number in this format. The bytes must have the format's size.
+	fn decode(&self, bytes: &[u8], pos: SourcePos) -> Result<Value, Panic> {
+		let mut bytes = bytes.to_vec();
+		if !self.little_endian {
+			bytes.reverse();
+		}
+
+		match self.kind {
+			Kind::Float if self.size == 4 => {
+				let bytes = bytes.try_into().expect("invalid f32 size");
+				Ok(f64::from(f32::from_le_bytes(bytes)).into())
+			}
+
+			Kind::Float => {
+				let bytes = bytes.try_into().expect("invalid f64 size");
+				Ok(f64::from_le_bytes(bytes).into())
+			}
+
+			kind => {
+				let mut raw = [0; 8];
+				raw[.. self.size].copy_from_slice(&bytes);
+				let raw = u64::from_le_bytes(raw);
+
+				let bits = self.size as u32 * 8;
+
+				match kind {
+					// Shift back and forth to sign extend.
+					Kind::Signed => Ok(Value::Int(((raw << (64 - bits)) as i64) >> (64 - bits))),
+
+					_ => i64::try_from(raw)
+						.map(Value::Int)
+						.map_err(|_| Panic::value_error(Value::Float((raw as f64).into()), "integer within int range", pos)),
+				}
+			}
+		}
+	}
+}
+
+
+/// Append a number, encoded in the given binary format.
+#[derive(Trace, Finalize)]
+struct Pack;
+
+impl NativeFun for Pack {
+	fn name(&self) -> &'static str { "std.buffer.pack" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Buffer(ref buffer), format, value ] => {
+				let format = Format::parse(format, context.pos.copy())?;
+				let bytes = format.encode(value, context.pos.copy())?;
+
+				buffer.borrow_mut().extend(bytes);
+
+				Ok(Value::default())
+			}
+
+			[ other, _, _ ] => Err(Panic::type_error(other.copy(), "buffer", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
+
+
+/// Decode a number in the given binary format, at the given offset.
+#[derive(Trace, Finalize)]
+struct Unpack;
+
+impl NativeFun for Unpack {
+	fn name(&self) -> &'static str { "std.buffer.unpack" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Buffer(ref buffer), format, start ] => {
+				let format = Format::parse(format, context.pos.copy())?;
+				let start = offset(start, buffer, context.pos.copy())?;
+
+				let bytes = buffer
+					.borrow()
+					.get(start .. start + format.size)
+					.map(<[u8]>::to_vec)
+					.ok_or_else(|| Panic::index_out_of_bounds(Value::Int((start + format.size) as i64), context.pos.copy()))?;
+
+				format.decode(&bytes, context.pos)
+			}
+
+			[ other, _, _ ] => Err(Panic::type_error(other.copy(), "buffer", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
+
+
+/// Read a file into a buffer. Relative paths are resolved from the working directory set
+/// by std.with_cwd. IO errors are returned as error values. Buffers may be written to
+/// files with std.fs.write_file.
+#[derive(Trace, Finalize)]
+struct ReadFile;
+
+impl NativeFun for ReadFile {
+	fn name(&self) -> &'static str { "std.buffer.read_file" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::String(ref path) ] => {
+				let path = context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path)));
+				context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+
+				Ok(
+					fs::read(&path)
+						.map(|data| Value::from(Buffer::new(data)))
+						.unwrap_or_else(|error| Error::new(error.to_string().into(), value.copy()).into())
+				)
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,425 @@
+use std::{
+	cell::RefCell,
+	collections::BTreeMap,
+	ffi::OsStr,
+	fs,
+	io,
+	path::{Path, PathBuf},
+	rc::Rc,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	store::write_atomic,
+	util,
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(New) }
+
+
+/// The current time, in milliseconds since the epoch. Wall clock time is used so that
+/// expiration times remain meaningful in persisted caches.
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|time| time.as_millis() as u64)
+		.unwrap_or(0)
+}
+
+
+/// A cached value. Values are kept as JSON, so that they hold no garbage-collected
+/// references, and so that they may be persisted.
+struct Entry {
+	value: serde_json::Value,
+	/// The expiration time, in milliseconds since the epoch.
+	expires: Option<u64>,
+}
+
+
+impl Entry {
+	fn is_expired(&self, now: u64) -> bool {
+		self.expires.map_or(false, |expires| expires <= now)
+	}
+}
+
+
+struct Cache {
+	/// The file where the cache is persisted, if any.
+	path: Option<PathBuf>,
+	/// The default time to live of entries.
+	ttl: Option<Duration>,
+	entries: BTreeMap<String, Entry>,
+}
+
+
+impl Cache {
+	/// Load the entries of a persisted cache, dropping the expired ones. A missing file is
+	/// an empty cache.
+	fn load(path: &Path) -> io::Result<BTreeMap<String, Entry>> {
+		let data = match fs::read(path) {
+			Ok(data) => data,
+			Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+			Err(error) => return Err(error),
+		};
+
+		let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid cache file");
+
+		let entries: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&data)?;
+		let now = now();
+
+		let mut loaded = BTreeMap::new();
+
+		for (key, entry) in entries {
+			let mut entry = match entry {
+				serde_json::Value::Object(entry) => entry,
+				_ => return Err(invalid()),
+			};
+
+			let expires = match entry.get("expires") {
+				None | Some(serde_json::Value::Null) => None,
+				Some(expires) => Some(expires.as_u64().ok_or_else(invalid)?),
+			};
+
+			let entry = Entry {
+				value: entry.remove("value").ok_or_else(invalid)?,
+				expires,
+			};
+
+			if !entry.is_expired(now) {
+				loaded.insert(key, entry);
+			}
+		}
+
+		Ok(loaded)
+	}
+
+
+	/// Write the cache to disk, if persisted.
+	fn save(&self) -> io::Result<()> {
+		let path = match &self.path {
+			Some(path) => path,
+			None => return Ok(()),
+		};
+
+		let entries: serde_json::Map<String, serde_json::Value> = self.entries
+			.iter()
+			.map(
+				|(key, entry)| {
+					let mut json = serde_json::Map::new();
+					json.insert("value".into(), entry.value.clone());
+					json.insert("expires".into(), entry.expires.map_or(serde_json::Value::Null, Into::into));
+					(key.clone(), json.into())
+				}
+			)
+			.collect();
+
+		write_atomic(path, &entries)
+	}
+
+
+	/// Get an entry which hasn't expired.
+	fn get(&mut self, key: &str) -> Option<Value> {
+		let now = now();
+
+		if self.entries.get(key)?.is_expired(now) {
+			self.entries.remove(key);
+			return None;
+		}
+
+		self.entries
+			.get(key)
+			.map(|entry| serde_json::from_value(entry.value.clone()).expect("cached values are always valid"))
+	}
+
+
+	/// Set an entry, and save the cache. The ttl defaults to the one of the cache.
+	fn set(&mut self, key: String, value: serde_json::Value, ttl: Option<Duration>) -> io::Result<()> {
+		let expires = ttl
+			.or(self.ttl)
+			.map(|ttl| now().saturating_add(ttl.as_millis() as u64));
+
+		self.entries.insert(key, Entry { value, expires });
+
+		self.save()
+	}
+}
+
+
+/// A cache, shared by the methods of a cache value.
+type Shared = Rc<RefCell<Cache>>;
+
+
+/// Get a key argument.
+fn key(value: &Value, pos: SourcePos) -> Result<String, Panic> {
+	match value {
+		Value::String(string) => std::str::from_utf8(string.as_bytes())
+			.map(str::to_owned)
+			.map_err(|_| Panic::value_error(value.copy(), "valid UTF-8", pos)),
+		other => Err(Panic::type_error(other.copy(), "string", pos)),
+	}
+}
+
+
+/// Get an optional time to live argument, in seconds.
+fn ttl(value: Option<&Value>, pos: SourcePos) -> Result<Option<Duration>, Panic> {
+	match value {
+		None | Some(Value::Nil) => Ok(None),
+		Some(value) => util::duration(value, pos).map(Some),
+	}
+}
+
+
+/// Convert a value to JSON, so that it can be cached.
+fn json(value: &Value, pos: SourcePos) -> Result<serde_json::Value, Panic> {
+	serde_json::to_value(value)
+		.map_err(|_| Panic::value_error(value.copy(), "nil, bool, byte, int, float, string, array or dict", pos))
+}
+
+
+/// The error value of a failed save.
+fn error(cache: &Cache, error: io::Error) -> Value {
+	let path = cache.path.clone().map(Str::from);
+	Error::new(error.to_string().into(), path.into()).into()
+}
+
+
+/// Create a cache of values by string keys, whose entries expire after a time to live.
+/// Values are anything that can be encoded as JSON. Options:
+/// - ttl: the default time to live of entries, in seconds. Entries don't expire if nil.
+/// - path: a file where the cache is persisted, so that it's kept between runs. Every
+///   change is written to the file.
+/// Failing to load or save a persisted cache produces an error value.
+#[derive(Trace, Finalize)]
+struct New;
+
+impl NativeFun for New {
+	fn name(&self) -> &'static str { "std.cache.new" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let options = match context.args() {
+			[] => Dict::default(),
+			[ Value::Dict(options) ] => options.copy(),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let ttl = ttl(options.get(&"ttl".into()).ok().as_ref(), context.pos.copy())?;
+
+		let path = match options.get(&"path".into()) {
+			Ok(Value::Nil) | Err(_) => None,
+			Ok(Value::String(ref path)) => Some(
+				context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path))).into_owned()
+			),
+			Ok(other) => return Err(Panic::type_error(other, "string", context.pos)),
+		};
+
+		if let Some(path) = &path {
+			context.runtime.options.policy.check_path(path, context.pos.copy())?;
+		}
+
+		let entries = match &path {
+			Some(path) => match Cache::load(path) {
+				Ok(entries) => entries,
+				Err(error) => {
+					let path = Value::from(Str::from(path.clone()));
+					return Ok(Error::new(error.to_string().into(), path).into());
+				}
+			},
+			None => BTreeMap::new(),
+		};
+
+		let cache: Shared = Rc::new(RefCell::new(Cache { path, ttl, entries }));
+
+		Ok(
+			util::dict([
+				("get", GetImpl(cache.clone()).into()),
+				("set", SetImpl(cache.clone()).into()),
+				("fetch", FetchImpl(cache.clone()).into()),
+				("delete", DeleteImpl(cache.clone()).into()),
+				("clear", ClearImpl(cache).into()),
+			])
+		)
+	}
+}
+
+
+/// Get the value of a key, or the default, which is nil if not given, if the key is
+/// missing or expired.
+#[derive(Finalize)]
+struct GetImpl(Shared);
+
+impl NativeFun for GetImpl {
+	fn name(&self) -> &'static str { "std.cache<get>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (key, default) = match context.args() {
+			[ value, default @ .. ] if default.len() <= 1 => (key(value, context.pos.copy())?, default.first()),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		Ok(
+			self.0
+				.borrow_mut()
+				.get(&key)
+				.unwrap_or_else(|| default.map(Value::copy).unwrap_or_default())
+		)
+	}
+}
+
+
+/// Set the value of a key, with an optional time to live in seconds, which defaults to
+/// the one of the cache.
+#[derive(Finalize)]
+struct SetImpl(Shared);
+
+impl NativeFun for SetImpl {
+	fn name(&self) -> &'static str { "std.cache<set>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (key, value, ttl) = match context.args() {
+			[ key_value, value, ttl_value @ .. ] if ttl_value.len() <= 1 => (
+				key(key_value, context.pos.copy())?,
+				json(value, context.pos.copy())?,
+				ttl(ttl_value.first(), context.pos.copy())?,
+			),
+
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let mut cache = self.0.borrow_mut();
+
+		Ok(
+			match cache.set(key, value, ttl) {
+				Ok(()) => Value::default(),
+				Err(err) => error(&cache, err),
+			}
+		)
+	}
+}
+
+
+/// Get the value of a key, or call the given function to compute it if the key is
+/// missing or expired. The result is cached, with an optional time to live in seconds,
+/// unless it's an error.
+#[derive(Finalize)]
+struct FetchImpl(Shared);
+
+impl NativeFun for FetchImpl {
+	fn name(&self) -> &'static str { "std.cache<fetch>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (key, fun, ttl) = match context.args() {
+			[ key_value, Value::Function(fun), ttl_value @ .. ] if ttl_value.len() <= 1 => (
+				key(key_value, context.pos.copy())?,
+				fun.copy(),
+				ttl(ttl_value.first(), context.pos.copy())?,
+			),
+
+			[ _, other, .. ] if context.args().len() <= 3 => {
+				return Err(Panic::type_error(other.copy(), "function", context.pos))
+			}
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		if let Some(value) = self.0.borrow_mut().get(&key) {
+			return Ok(value);
+		}
+
+		// The cache must not be borrowed during the call, as the function may use it.
+		let value = util::call(&mut context, &fun, [])?;
+
+		if let Value::Error(_) = value {
+			return Ok(value);
+		}
+
+		let json = json(&value, context.pos.copy())?;
+
+		let mut cache = self.0.borrow_mut();
+
+		Ok(
+			match cache.set(key, json, ttl) {
+				Ok(()) => value,
+				Err(err) => error(&cache, err),
+			}
+		)
+	}
+}
+
+
+/// Delete a key. Returns whether the key was present.
+#[derive(Finalize)]
+struct DeleteImpl(Shared);
+
+impl NativeFun for DeleteImpl {
+	fn name(&self) -> &'static str { "std.cache<delete>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let key = match context.args() {
+			[ value ] => key(value, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let mut cache = self.0.borrow_mut();
+
+		if cache.get(&key).is_none() {
+			return Ok(false.into());
+		}
+
+		cache.entries.remove(&key);
+
+		Ok(
+			match cache.save() {
+				Ok(()) => true.into(),
+				Err(err) => error(&cache, err),
+			}
+		)
+	}
+}
+
+
+/// Delete all entries.
+#[derive(Finalize)]
+struct ClearImpl(Shared);
+
+impl NativeFun for ClearImpl {
+	fn name(&self) -> &'static str { "std.cache<clear>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let mut cache = self.0.borrow_mut();
+		cache.entries.clear();
+
+		Ok(
+			match cache.save() {
+				Ok(()) => Value::default(),
+				Err(err) => error(&cache, err),
+			}
+		)
+	}
+}
+
+
+// The methods have no garbage-collected fields.
+unsafe impl Trace for GetImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for SetImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for FetchImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for DeleteImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ClearImpl { gc::unsafe_empty_trace!(); }
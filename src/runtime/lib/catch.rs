@@ -41,10 +41,13 @@ impl NativeFun for Catch {
 		match result {
 			Ok(value) => Ok(value),
 
+			Err(exit @ Panic::Exit { .. }) => Err(exit),
+			Err(limit @ Panic::LimitExceeded { .. }) => Err(limit),
+
 			Err(panic) => {
 				let description = format!(
 					"caught panic: {}",
-					fmt::Show(panic, context.interner()),
+					fmt::Show(panic.untraced(), context.interner()),
 				);
 
 				Ok(
@@ -2,6 +2,8 @@ use std::ffi::OsStr;
 
 use gc::{Finalize, Trace};
 
+use crate::io;
+
 use super::{
 	CallContext,
 	NativeFun,
@@ -22,8 +24,9 @@ impl NativeFun for Cd {
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
 			[ Value::String(ref string) ] => Ok(
-				std::env
-					::set_current_dir(AsRef::<OsStr>::as_ref(string))
+				io
+					::cd_target(Some(AsRef::<OsStr>::as_ref(string)))
+					.and_then(io::change_dir)
 					.into()
 			),
 
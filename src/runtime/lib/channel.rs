@@ -0,0 +1,351 @@
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Condvar, Mutex, MutexGuard},
+	time::Instant,
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	transfer::Transfer,
+	util,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	Function,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(NewChannel) }
+inventory::submit! { RustFun::from(Select) }
+
+
+/// Signaled whenever a value is sent to a channel or a channel is closed, so that
+/// receivers may wait on several channels at once. The mutex must be held while checking
+/// the channels before waiting, so that signals are not missed.
+static SIGNAL: (Mutex<()>, Condvar) = (Mutex::new(()), Condvar::new());
+
+
+/// A queue of values sent between threads.
+#[derive(Debug, Default)]
+pub struct Channel(Mutex<State>);
+
+
+#[derive(Debug, Default)]
+struct State {
+	queue: VecDeque<Transfer>,
+	closed: bool,
+}
+
+
+impl Channel {
+	fn state(&self) -> MutexGuard<State> {
+		// Values are transferred outside of the lock, so a poisoned state is still consistent.
+		self.0
+			.lock()
+			.unwrap_or_else(|error| error.into_inner())
+	}
+
+
+	/// Queue a value, failing if the channel is closed.
+	fn send(&self, value: Transfer) -> Result<(), ()> {
+		let mut state = self.state();
+
+		if state.closed {
+			return Err(());
+		}
+
+		state.queue.push_back(value);
+		drop(state);
+
+		Self::signal();
+
+		Ok(())
+	}
+
+
+	/// Close the channel. Queued values may still be received.
+	fn close(&self) {
+		self.state().closed = true;
+		Self::signal();
+	}
+
+
+	/// Take the next value. Returns None if the channel is empty and open.
+	fn try_recv(&self) -> Option<Result<Transfer, Closed>> {
+		let mut state = self.state();
+
+		match state.queue.pop_front() {
+			Some(value) => Some(Ok(value)),
+			None if state.closed => Some(Err(Closed)),
+			None => None,
+		}
+	}
+
+
+	fn signal() {
+		let _lock = SIGNAL.0
+			.lock()
+			.unwrap_or_else(|error| error.into_inner());
+
+		SIGNAL.1.notify_all();
+	}
+}
+
+
+/// A channel which is closed and empty.
+#[derive(Debug)]
+struct Closed;
+
+
+/// Wait for a value from any of the channels, in order, returning the index of the
+/// channel. Returns None on timeout.
+fn recv(
+	channels: &[Arc<Channel>],
+	deadline: Option<Instant>,
+) -> Option<(usize, Result<Transfer, Closed>)> {
+	let mut lock = SIGNAL.0
+		.lock()
+		.unwrap_or_else(|error| error.into_inner());
+
+	loop {
+		let mut closed = 0;
+
+		for (ix, channel) in channels.iter().enumerate() {
+			match channel.try_recv() {
+				Some(Ok(value)) => return Some((ix, Ok(value))),
+				Some(Err(Closed)) => closed += 1,
+				None => (),
+			}
+		}
+
+		// Report closing only once all channels are closed, so that the others may still be
+		// received from.
+		if closed == channels.len() {
+			return Some((0, Err(Closed)));
+		}
+
+		lock = match deadline {
+			None => SIGNAL.1
+				.wait(lock)
+				.unwrap_or_else(|error| error.into_inner()),
+
+			Some(deadline) => {
+				let timeout = deadline.checked_duration_since(Instant::now())?;
+
+				SIGNAL.1
+					.wait_timeout(lock, timeout)
+					.unwrap_or_else(|error| error.into_inner())
+					.0
+			}
+		};
+	}
+}
+
+
+/// Get the channel of a channel value.
+fn channel(value: &Value) -> Option<Arc<Channel>> {
+	let dict = match value {
+		Value::Dict(dict) => dict,
+		_ => return None,
+	};
+
+	match dict.get(&"recv".into()) {
+		Ok(Value::Function(Function::Rust(ref fun))) => fun
+			.downcast_ref::<ChannelImpl>()
+			.map(|ChannelImpl(channel, _)| channel.clone()),
+		_ => None,
+	}
+}
+
+
+/// The value received from a channel, or an error if all channels are closed.
+fn received(context: &CallContext, value: Result<Transfer, Closed>) -> Value {
+	match value {
+		Ok(value) => value.into_value(&context.runtime.std),
+		Err(Closed) => Error::new("channel is closed".into(), Value::Nil).into(),
+	}
+}
+
+
+/// Create a channel, to send values between threads. Returns a dict with the methods:
+/// - send(value): queue a copy of the value. Mutable collections are deep copied, and
+///   functions are sent with their captured variables. Values which hold resources, such
+///   as sockets and child processes, can't be sent. Returns an error if the channel is
+///   closed.
+/// - recv(): wait for the next value. Returns an error if the channel is closed and
+///   empty.
+/// - close(): close the channel. Values that were already sent may still be received.
+/// Channels may be sent to other channels and captured by threads.
+#[derive(Trace, Finalize)]
+struct NewChannel;
+
+impl NativeFun for NewChannel {
+	fn name(&self) -> &'static str { "std.channel" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				let channel = Arc::new(Channel::default());
+
+				let mut dict = DictMap::default();
+				for method in [Method::Send, Method::Recv, Method::Close] {
+					dict.insert(method.key().into(), ChannelImpl(channel.clone(), method).into());
+				}
+
+				Ok(Dict::new(dict).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// Wait for a value from any of the given channels, with an optional timeout in seconds.
+/// Returns a dict with the index of the channel and the received value, or nil on
+/// timeout. If there are values in multiple channels, the first channel is preferred.
+/// Returns an error once all channels are closed and empty.
+#[derive(Trace, Finalize)]
+struct Select;
+
+impl Select {
+	fn channels(value: &Value, pos: SourcePos) -> Result<Vec<Arc<Channel>>, Panic> {
+		let array = match value {
+			Value::Array(array) => array,
+			other => return Err(Panic::type_error(other.copy(), "array", pos)),
+		};
+
+		let channels = array
+			.borrow()
+			.iter()
+			.map(|value| channel(value).ok_or_else(|| Panic::type_error(value.copy(), "channel", pos.copy())))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		if channels.is_empty() {
+			return Err(Panic::value_error(value.copy(), "non-empty array", pos));
+		}
+
+		Ok(channels)
+	}
+}
+
+impl NativeFun for Select {
+	fn name(&self) -> &'static str { "std.select" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (channels, deadline) = match context.args() {
+			[ channels ] => (Self::channels(channels, context.pos.copy())?, None),
+
+			[ channels, timeout ] => (
+				Self::channels(channels, context.pos.copy())?,
+				Some(Instant::now() + util::duration(timeout, context.pos.copy())?),
+			),
+
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		Ok(
+			match recv(&channels, deadline) {
+				None => Value::Nil,
+
+				Some((_, Err(Closed))) => received(&context, Err(Closed)),
+
+				Some((ix, value)) => {
+					let mut dict = DictMap::default();
+					dict.insert("index".into(), Value::Int(ix as i64));
+					dict.insert("value".into(), received(&context, value));
+
+					Dict::new(dict).into()
+				}
+			}
+		)
+	}
+}
+
+
+/// A method of a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+	Send,
+	Recv,
+	Close,
+}
+
+
+impl Method {
+	fn key(&self) -> &'static str {
+		match self {
+			Self::Send => "send",
+			Self::Recv => "recv",
+			Self::Close => "close",
+		}
+	}
+}
+
+
+/// The methods of a channel, which are recognized when channels are sent to other
+/// threads.
+#[derive(Finalize)]
+pub struct ChannelImpl(pub Arc<Channel>, pub Method);
+
+impl NativeFun for ChannelImpl {
+	fn name(&self) -> &'static str {
+		match self.1 {
+			Method::Send => "std.channel<send>",
+			Method::Recv => "std.channel<recv>",
+			Method::Close => "std.channel<close>",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let ChannelImpl(channel, method) = self;
+
+		match (method, context.args()) {
+			(Method::Send, [ value ]) => {
+				let value = Transfer::new(value, &context.runtime.std, context.pos.copy())?;
+
+				Ok(
+					match channel.send(value) {
+						Ok(()) => Value::Nil,
+						Err(()) => Error::new("channel is closed".into(), Value::Nil).into(),
+					}
+				)
+			}
+
+			(Method::Recv, []) => {
+				let channels = [ channel.clone() ];
+
+				match recv(&channels, None) {
+					Some((_, value)) => Ok(received(&context, value)),
+					None => unreachable!("recv without deadline should not time out"),
+				}
+			}
+
+			(Method::Close, []) => {
+				channel.close();
+				Ok(Value::Nil)
+			}
+
+			(Method::Send, args) => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+			(_, args) => Err(Panic::invalid_args(args.len() as u32, 0, context.pos)),
+		}
+	}
+
+	fn as_any(&self) -> Option<&dyn std::any::Any> {
+		Some(self)
+	}
+}
+
+
+/// ChannelImpl has no garbage-collected fields.
+unsafe impl Trace for ChannelImpl {
+	gc::unsafe_empty_trace!();
+}
@@ -0,0 +1,36 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(ShallowClone) }
+
+/// Copy a value, so that the result doesn't alias the original. Unlike std.deep_copy,
+/// only the outer array, dict or buffer is copied, and the items are shared, which makes
+/// it cheap to take a snapshot of a collection before passing it to code which might
+/// modify it. The contents are copied on write, so cloning takes constant time, and the
+/// copy is made when either collection is first changed. Other values are immutable, and
+/// are shared.
+#[derive(Trace, Finalize)]
+struct ShallowClone;
+
+impl NativeFun for ShallowClone {
+	fn name(&self) -> &'static str { "std.clone" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(array) ] => Ok(array.shallow_clone().into()),
+			[ Value::Dict(dict) ] => Ok(dict.shallow_clone().into()),
+			[ Value::Buffer(buffer) ] => Ok(buffer.shallow_clone().into()),
+
+			[ value ] => Ok(value.copy()),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
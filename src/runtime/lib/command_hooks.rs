@@ -0,0 +1,59 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Function,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(BeforeCommand) }
+inventory::submit! { RustFun::from(AfterCommand) }
+
+
+/// Parse the hook argument, which may be a function or nil.
+fn hook(context: &CallContext) -> Result<Option<Function>, Panic> {
+	match context.args() {
+		[ Value::Function(ref hook) ] => Ok(Some(hook.copy())),
+		[ Value::Nil ] => Ok(None),
+		[ other ] => Err(Panic::type_error(other.copy(), "function or nil", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct BeforeCommand;
+
+impl NativeFun for BeforeCommand {
+	fn name(&self) -> &'static str { "std.before_command" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let hook = hook(&context)?;
+
+		// The hook runs before each spawned command, receiving it's argv, cwd and env.
+		let previous = std::mem::replace(&mut context.runtime.before_command, hook);
+
+		Ok(previous.map(Value::Function).unwrap_or_default())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct AfterCommand;
+
+impl NativeFun for AfterCommand {
+	fn name(&self) -> &'static str { "std.after_command" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let hook = hook(&context)?;
+
+		// The hook runs after each spawned command, receiving also the status and duration.
+		let previous = std::mem::replace(&mut context.runtime.after_command, hook);
+
+		Ok(previous.map(Value::Function).unwrap_or_default())
+	}
+}
@@ -0,0 +1,284 @@
+use std::{
+	borrow::Cow,
+	ffi::OsStr,
+	fs::File,
+	io::{self, BufRead, BufReader, BufWriter, Read, Write},
+	ops::RangeInclusive,
+	path::Path,
+};
+
+use flate2::{bufread::MultiGzDecoder, write::GzEncoder, Compression};
+use gc::{Finalize, Trace};
+use xz2::{bufread::XzDecoder, write::XzEncoder};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	Buffer,
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Compress) }
+inventory::submit! { RustFun::from(Decompress) }
+inventory::submit! { RustFun::from(CompressFile) }
+inventory::submit! { RustFun::from(DecompressFile) }
+
+
+/// The supported compression formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+	Gzip,
+	Zstd,
+	Xz,
+}
+
+
+impl Codec {
+	fn new(value: &Value, pos: SourcePos) -> Result<Self, Panic> {
+		match value {
+			Value::String(ref format) => match format.as_bytes() {
+				b"gzip" => Ok(Self::Gzip),
+				b"zstd" => Ok(Self::Zstd),
+				b"xz" => Ok(Self::Xz),
+				_ => Err(Panic::value_error(value.copy(), "gzip, zstd or xz", pos)),
+			},
+
+			other => Err(Panic::type_error(other.copy(), "string", pos)),
+		}
+	}
+
+
+	/// The valid compression levels.
+	fn levels(self) -> RangeInclusive<i64> {
+		match self {
+			Self::Gzip | Self::Xz => 0 ..= 9,
+			Self::Zstd => 1 ..= 22,
+		}
+	}
+
+
+	/// The compression level used when none is given.
+	fn default_level(self) -> i64 {
+		match self {
+			Self::Gzip | Self::Xz => 6,
+			Self::Zstd => 3,
+		}
+	}
+
+
+	/// Get the compression level argument, which must be in the codec's range.
+	fn level(self, value: Option<&Value>, pos: SourcePos) -> Result<i64, Panic> {
+		match value {
+			None => Ok(self.default_level()),
+			Some(Value::Int(level)) if self.levels().contains(level) => Ok(*level),
+
+			Some(value @ Value::Int(_)) => {
+				let levels = self.levels();
+				let expected = format!("level between {} and {}", levels.start(), levels.end());
+				Err(Panic::value_error(value.copy(), expected, pos))
+			}
+
+			Some(other) => Err(Panic::type_error(other.copy(), "int", pos)),
+		}
+	}
+
+
+	/// Compress all data from the reader into the writer.
+	fn compress<R: Read, W: Write>(self, reader: &mut R, writer: W, level: i64) -> io::Result<W> {
+		match self {
+			Self::Gzip => {
+				let mut encoder = GzEncoder::new(writer, Compression::new(level as u32));
+				io::copy(reader, &mut encoder)?;
+				encoder.finish()
+			}
+
+			Self::Zstd => {
+				let mut encoder = zstd::Encoder::new(writer, level as i32)?;
+				io::copy(reader, &mut encoder)?;
+				encoder.finish()
+			}
+
+			Self::Xz => {
+				let mut encoder = XzEncoder::new(writer, level as u32);
+				io::copy(reader, &mut encoder)?;
+				encoder.finish()
+			}
+		}
+	}
+
+
+	/// Decompress all data from the reader into the writer. Concatenated streams are
+	/// decompressed as a whole.
+	fn decompress<R: BufRead, W: Write>(self, reader: R, writer: &mut W) -> io::Result<()> {
+		match self {
+			Self::Gzip => io::copy(&mut MultiGzDecoder::new(reader), writer),
+			Self::Zstd => io::copy(&mut zstd::Decoder::with_buffer(reader)?, writer),
+			Self::Xz => io::copy(&mut XzDecoder::new_multi_decoder(reader), writer),
+		}?;
+
+		Ok(())
+	}
+}
+
+
+/// Get the data argument.
+fn data<'a>(value: &'a Value, pos: SourcePos) -> Result<Cow<'a, [u8]>, Panic> {
+	util::bytes(value).ok_or_else(|| Panic::type_error(value.copy(), "string, buffer or byte array", pos))
+}
+
+
+/// Get a path argument. Relative paths are resolved from the working directory set by
+/// std.with_cwd. Paths denied by the sandbox policy cause a panic.
+fn path<'a>(context: &'a CallContext, value: &'a Value) -> Result<Cow<'a, Path>, Panic> {
+	let path = match value {
+		Value::String(string) => context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(string))),
+		other => return Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+	};
+
+	context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+
+	Ok(path)
+}
+
+
+/// Compress a string, buffer or byte array, producing a buffer. Formats are gzip, zstd
+/// and xz. The compression level is optional, and defaults to the format's default.
+#[derive(Trace, Finalize)]
+struct Compress;
+
+impl NativeFun for Compress {
+	fn name(&self) -> &'static str { "std.compress.compress" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, format, level @ .. ] if level.len() <= 1 => {
+				let codec = Codec::new(format, context.pos.copy())?;
+				let level = codec.level(level.first(), context.pos.copy())?;
+				let data = data(value, context.pos.copy())?;
+
+				let output = codec
+					.compress(&mut data.as_ref(), Vec::new(), level)
+					.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+				Ok(Buffer::new(output).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
+
+
+/// Decompress a string, buffer or byte array, producing a buffer. Formats are gzip, zstd
+/// and xz. Returns an error if the data is not valid.
+#[derive(Trace, Finalize)]
+struct Decompress;
+
+impl NativeFun for Decompress {
+	fn name(&self) -> &'static str { "std.compress.decompress" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, format ] => {
+				let codec = Codec::new(format, context.pos.copy())?;
+				let data = data(value, context.pos.copy())?;
+
+				let mut output = Vec::new();
+
+				Ok(
+					match codec.decompress(data.as_ref(), &mut output) {
+						Ok(()) => Buffer::new(output).into(),
+						Err(error) => Error::new(error.to_string().into(), format.copy()).into(),
+					}
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Compress a file into another file, without loading it into memory. Formats are gzip,
+/// zstd and xz. The compression level is optional. IO errors are returned as error values.
+#[derive(Trace, Finalize)]
+struct CompressFile;
+
+impl CompressFile {
+	fn compress(source: &Path, destination: &Path, codec: Codec, level: i64) -> io::Result<()> {
+		let mut reader = BufReader::new(File::open(source)?);
+		let writer = BufWriter::new(File::create(destination)?);
+
+		codec
+			.compress(&mut reader, writer, level)?
+			.flush()
+	}
+}
+
+impl NativeFun for CompressFile {
+	fn name(&self) -> &'static str { "std.compress.compress_file" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ source, destination, format, level @ .. ] if level.len() <= 1 => {
+				let codec = Codec::new(format, context.pos.copy())?;
+				let level = codec.level(level.first(), context.pos.copy())?;
+				let source_path = path(&context, source)?;
+				let destination = path(&context, destination)?;
+
+				Ok(
+					Self::compress(&source_path, &destination, codec, level)
+						.map_err(|error| Error::new(error.to_string().into(), source.copy()))
+						.into()
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 4, context.pos))
+		}
+	}
+}
+
+
+/// Decompress a file into another file, without loading it into memory. Formats are
+/// gzip, zstd and xz. IO errors and invalid data are returned as error values.
+#[derive(Trace, Finalize)]
+struct DecompressFile;
+
+impl DecompressFile {
+	fn decompress(source: &Path, destination: &Path, codec: Codec) -> io::Result<()> {
+		let reader = BufReader::new(File::open(source)?);
+		let mut writer = BufWriter::new(File::create(destination)?);
+
+		codec.decompress(reader, &mut writer)?;
+		writer.flush()
+	}
+}
+
+impl NativeFun for DecompressFile {
+	fn name(&self) -> &'static str { "std.compress.decompress_file" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ source, destination, format ] => {
+				let codec = Codec::new(format, context.pos.copy())?;
+				let source_path = path(&context, source)?;
+				let destination = path(&context, destination)?;
+
+				Ok(
+					Self::decompress(&source_path, &destination, codec)
+						.map_err(|error| Error::new(error.to_string().into(), source.copy()))
+						.into()
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
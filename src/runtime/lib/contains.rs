@@ -26,7 +26,11 @@ impl NativeFun for Contains {
 			[ Value::String(ref string), Value::Byte(byte) ] => Ok(string.contains(*byte).into()),
 			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "char", context.pos)),
 
-			[ other, _ ] => Err(Panic::type_error(other.copy(), "string ,array or dict", context.pos)),
+			[ Value::Buffer(ref buffer), Value::Byte(byte) ] => Ok(buffer.borrow().contains(byte).into()),
+			[ Value::Buffer(ref buffer), Value::Int(int) ] => Ok(buffer.borrow().iter().any(|byte| i64::from(*byte) == *int).into()),
+			[ Value::Buffer(_), other ] => Err(Panic::type_error(other.copy(), "int or char", context.pos)),
+
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string, array, dict or buffer", context.pos)),
 			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
 	}
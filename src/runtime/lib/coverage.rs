@@ -0,0 +1,62 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Coverage) }
+
+/// Get the execution counts of statements, when running with coverage enabled. Returns a
+/// dict from each source path to a dict from line number to the amount of executions of
+/// the statements in that line, or nil if coverage is disabled.
+#[derive(Trace, Finalize)]
+struct Coverage;
+
+impl NativeFun for Coverage {
+	fn name(&self) -> &'static str { "std.coverage" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let coverage = match context.runtime.coverage() {
+			Some(coverage) => coverage,
+			None => return Ok(Value::Nil),
+		};
+
+		let interner = context.runtime.interner();
+
+		let mut files: DictMap = coverage
+			.files()
+			.map(
+				|(path, lines)| {
+					let path: Value = interner
+						.resolve(path)
+						.unwrap_or_default()
+						.into();
+
+					let lines: Dict = lines
+						.iter()
+						.map(|(&line, &count)| (Value::Int(line.into()), Value::Int(count as i64)))
+						.collect();
+
+					(path, lines.into())
+				}
+			)
+			.collect();
+
+		// Files are sorted by path, as in the lcov report.
+		files.sort_keys();
+
+		Ok(Dict::new(files).into())
+	}
+}
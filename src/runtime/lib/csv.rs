@@ -0,0 +1,350 @@
+use std::{
+	cell::RefCell,
+	ffi::OsStr,
+	fs::File,
+	io,
+	path::Path,
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	keys,
+	CallContext,
+	Dict,
+	DictMap,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Read) }
+inventory::submit! { RustFun::from(Dump) }
+
+
+/// The options for reading and writing CSV.
+struct Options {
+	/// The field delimiter, which is a comma by default.
+	delimiter: u8,
+	/// When reading, whether the first row contains the column names, in which case rows
+	/// are produced as dicts. When writing, the column names, which are written as the
+	/// first row, and which determine the order of the fields of dict rows.
+	headers: Headers,
+}
+
+
+enum Headers {
+	None,
+	Read,
+	Write(Vec<Value>),
+}
+
+
+impl Options {
+	/// Build the options from the optional options dict.
+	fn new(options: Option<&Dict>, pos: SourcePos) -> Result<Self, Panic> {
+		let option = |name: &str| options
+			.and_then(|options| options.get(&name.into()).ok())
+			.unwrap_or_default();
+
+		let delimiter = match option("delimiter") {
+			Value::Nil => b',',
+			Value::String(ref delimiter) if delimiter.len() == 1 => delimiter.as_bytes()[0],
+			Value::Byte(delimiter) => delimiter,
+			value @ Value::String(_) => return Err(Panic::value_error(value, "single character", pos)),
+			value => return Err(Panic::type_error(value, "string", pos)),
+		};
+
+		let headers = match option("headers") {
+			Value::Nil | Value::Bool(false) => Headers::None,
+			Value::Bool(true) => Headers::Read,
+			Value::Array(ref headers) => Headers::Write(headers.borrow().iter().map(Value::copy).collect()),
+			value => return Err(Panic::type_error(value, "bool or array", pos)),
+		};
+
+		Ok(Self { delimiter, headers })
+	}
+
+
+	fn reader<R: io::Read>(&self, reader: R) -> csv::Reader<R> {
+		csv::ReaderBuilder::new()
+			.delimiter(self.delimiter)
+			.has_headers(matches!(self.headers, Headers::Read))
+			.flexible(true)
+			.from_reader(reader)
+	}
+}
+
+
+/// Read all rows.
+fn read_all<R: io::Read>(mut reader: Rows<R>) -> Result<Value, csv::Error> {
+	let mut rows = Vec::new();
+
+	while let Some(row) = reader.next()? {
+		rows.push(row);
+	}
+
+	Ok(rows.into())
+}
+
+
+/// A reader producing rows as arrays, or as dicts if there are column names.
+struct Rows<R: io::Read> {
+	reader: csv::Reader<R>,
+	headers: Option<Vec<Box<[u8]>>>,
+	record: csv::ByteRecord,
+}
+
+
+impl<R: io::Read> Rows<R> {
+	fn new(options: &Options, reader: R) -> Result<Self, csv::Error> {
+		let mut reader = options.reader(reader);
+
+		let headers = match options.headers {
+			Headers::Read => Some(
+				reader
+					.byte_headers()?
+					.iter()
+					.map(Into::into)
+					.collect()
+			),
+			_ => None,
+		};
+
+		Ok(Self { reader, headers, record: csv::ByteRecord::new() })
+	}
+
+
+	fn next(&mut self) -> Result<Option<Value>, csv::Error> {
+		if !self.reader.read_byte_record(&mut self.record)? {
+			return Ok(None);
+		}
+
+		let fields = self.record
+			.iter()
+			.map(|field| Value::from(Str::from(field)));
+
+		Ok(
+			Some(
+				match &self.headers {
+					// Missing fields are nil, and extra fields are ignored.
+					Some(headers) => {
+						let mut fields = fields;
+						let dict: DictMap = headers
+							.iter()
+							.map(|header| (Str::from(&header[..]).into(), fields.next().unwrap_or_default()))
+							.collect();
+
+						Dict::new(dict).into()
+					}
+
+					None => fields.collect::<Vec<_>>().into(),
+				}
+			)
+		)
+	}
+}
+
+
+/// Convert a CSV error to a panic.
+fn panic(error: csv::Error, pos: SourcePos) -> Panic {
+	let error = match error.into_kind() {
+		csv::ErrorKind::Io(error) => error,
+		kind => io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", kind)),
+	};
+
+	Panic::io(error, pos)
+}
+
+
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.csv.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (string, options) = match context.args() {
+			[ Value::String(ref string) ] => (string, Options::new(None, context.pos.copy())?),
+			[ Value::String(ref string), Value::Dict(ref options) ] => {
+				(string, Options::new(Some(options), context.pos.copy())?)
+			}
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		Rows::new(&options, string.as_bytes())
+			.and_then(read_all)
+			.map_err(|error| panic(error, context.pos.copy()))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Read;
+
+impl NativeFun for Read {
+	fn name(&self) -> &'static str { "std.csv.read" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (path, options) = match context.args() {
+			[ Value::String(ref path) ] => (path, Options::new(None, context.pos.copy())?),
+			[ Value::String(ref path), Value::Dict(ref options) ] => {
+				(path, Options::new(Some(options), context.pos.copy())?)
+			}
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let path = context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path)));
+		context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+
+		let file = File::open(path)
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		let rows = Rows::new(&options, io::BufReader::new(file))
+			.map_err(|error| panic(error, context.pos.copy()))?;
+
+		Ok(ReadImpl(RefCell::new(rows)).into())
+	}
+}
+
+
+/// The iterator over the rows of a file, which are read as needed.
+#[derive(Finalize)]
+struct ReadImpl(RefCell<Rows<io::BufReader<File>>>);
+
+/// ReadImpl has no garbage-collected fields.
+unsafe impl Trace for ReadImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for ReadImpl {
+	fn name(&self) -> &'static str { "std.csv.read<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let next = self.0
+			.borrow_mut()
+			.next()
+			.map_err(|error| panic(error, context.pos.copy()))?;
+
+		let mut iteration = DictMap::default();
+
+		keys::FINISHED.with(
+			|finished| iteration.insert(finished.copy(), next.is_none().into())
+		);
+
+		if let Some(next) = next {
+			keys::VALUE.with(
+				|value| iteration.insert(value.copy(), next)
+			);
+		}
+
+		Ok(Dict::new(iteration).into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Dump;
+
+impl Dump {
+	/// Convert a value to a field. Nil is an empty field.
+	fn field(value: Value, pos: SourcePos) -> Result<Vec<u8>, Panic> {
+		match value {
+			Value::Nil => Ok(Vec::new()),
+			Value::String(ref string) => Ok(string.as_bytes().to_owned()),
+			Value::Byte(byte) => Ok(vec![byte]),
+			Value::Bool(bool) => Ok(bool.to_string().into_bytes()),
+			Value::Int(int) => Ok(int.to_string().into_bytes()),
+			Value::Float(ref float) => Ok(float.to_string().into_bytes()),
+			value => Err(Panic::type_error(value, "nil, bool, byte, int, float or string", pos)),
+		}
+	}
+
+
+	/// Get the fields of a row, in the order of the headers for dicts.
+	fn row(row: &Value, headers: &[Value], pos: SourcePos) -> Result<Vec<Vec<u8>>, Panic> {
+		match row {
+			Value::Array(ref fields) => fields
+				.borrow()
+				.iter()
+				.map(|field| Self::field(field.copy(), pos.copy()))
+				.collect(),
+
+			Value::Dict(ref fields) if !headers.is_empty() => headers
+				.iter()
+				.map(|header| Self::field(fields.get(header).unwrap_or_default(), pos.copy()))
+				.collect(),
+
+			Value::Dict(_) => Err(Panic::value_error(row.copy(), "array, or dict with headers", pos)),
+
+			other => Err(Panic::type_error(other.copy(), "array or dict", pos)),
+		}
+	}
+}
+
+impl NativeFun for Dump {
+	fn name(&self) -> &'static str { "std.csv.dump" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (rows, options) = match context.args() {
+			[ Value::Array(ref rows) ] => (rows, Options::new(None, context.pos.copy())?),
+			[ Value::Array(ref rows), Value::Dict(ref options) ] => {
+				(rows, Options::new(Some(options), context.pos.copy())?)
+			}
+
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let headers = match options.headers {
+			Headers::Write(headers) => headers,
+			_ => Vec::new(),
+		};
+
+		let mut writer = csv::WriterBuilder::new()
+			.delimiter(options.delimiter)
+			.flexible(true)
+			.from_writer(Vec::new());
+
+		let write = |writer: &mut csv::Writer<Vec<u8>>, fields: Vec<Vec<u8>>| writer
+			.write_record(fields)
+			.map_err(|error| panic(error, context.pos.copy()));
+
+		if !headers.is_empty() {
+			let fields = headers
+				.iter()
+				.map(|header| Self::field(header.copy(), context.pos.copy()))
+				.collect::<Result<_, _>>()?;
+
+			write(&mut writer, fields)?;
+		}
+
+		for row in rows.borrow().iter() {
+			write(&mut writer, Self::row(row, &headers, context.pos.copy())?)?;
+		}
+
+		let output = writer
+			.into_inner()
+			.map_err(|error| Panic::io(error.into_error(), context.pos.copy()))?;
+
+		Ok(Str::from(output).into())
+	}
+}
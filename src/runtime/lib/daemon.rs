@@ -0,0 +1,160 @@
+use std::{
+	ffi::OsStr,
+	fs::{File, OpenOptions},
+	io,
+	path::Path,
+	process,
+	thread,
+};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::{command::{env, Options}, SourcePos};
+
+use super::{
+	CallContext,
+	Dict,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Daemon) }
+
+#[derive(Trace, Finalize)]
+struct Daemon;
+
+impl Daemon {
+	/// Open the file for the given stdio stream, from the options dict. Missing options
+	/// mean the null device. Output files are appended to.
+	fn stdio(options: &Dict, name: &str, pos: SourcePos) -> Result<Option<Box<Path>>, Panic> {
+		match options.get(&name.into()) {
+			Ok(Value::String(ref path)) => Ok(Some(Path::new(AsRef::<OsStr>::as_ref(path)).into())),
+			Ok(Value::Nil) | Err(_) => Ok(None),
+			Ok(other) => Err(Panic::type_error(other, "string or nil", pos)),
+		}
+	}
+
+
+	fn open(path: Option<Box<Path>>, output: bool, options: &Options) -> io::Result<File> {
+		let path = match &path {
+			Some(path) => options.path(path),
+			None => Path::new(crate::io::NULL).into(),
+		};
+
+		if output {
+			OpenOptions::new()
+				.create(true)
+				.append(true)
+				.open(path)
+		} else {
+			File::open(path)
+		}
+	}
+
+
+	/// Spawn the process in a new session, so that it's detached from the shell's
+	/// terminal and process group. On Windows, the process is detached from the console
+	/// instead.
+	fn spawn(argv: &[Box<OsStr>], stdio: [Option<Box<Path>>; 3], options: &Options) -> io::Result<u32> {
+		let [stdin, stdout, stderr] = stdio;
+
+		let mut command = process::Command::new(&argv[0]);
+		command
+			.args(&argv[1..])
+			.stdin(Self::open(stdin, false, options)?)
+			.stdout(Self::open(stdout, true, options)?)
+			.stderr(Self::open(stderr, true, options)?);
+
+		if let Some(cwd) = &options.cwd {
+			command.current_dir(cwd);
+		}
+
+		env::apply(&mut command);
+		options.limits.apply(&mut command);
+
+		// Safety: setsid is async signal safe.
+		#[cfg(unix)]
+		unsafe {
+			command.pre_exec(
+				|| if libc::setsid() < 0 {
+					Err(io::Error::last_os_error())
+				} else {
+					Ok(())
+				}
+			);
+		}
+
+		#[cfg(windows)]
+		{
+			use windows_sys::Win32::System::Threading::{CREATE_NEW_PROCESS_GROUP, DETACHED_PROCESS};
+
+			command.creation_flags(
+				DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP | options.limits.creation_flags()
+			);
+		}
+
+		let mut child = command.spawn()?;
+		let pid = child.id();
+
+		// The process is not tracked, but it must be reaped once it finishes.
+		thread::spawn(move || child.wait());
+
+		Ok(pid)
+	}
+}
+
+impl NativeFun for Daemon {
+	fn name(&self) -> &'static str { "std.daemon" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (argv, options) = match context.args() {
+			[ Value::Array(ref argv) ] => (argv.copy(), Dict::default()),
+			[ Value::Array(ref argv), Value::Dict(ref options) ] => (argv.copy(), options.copy()),
+
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let argv = argv
+			.borrow()
+			.iter()
+			.map(
+				|arg| match arg {
+					Value::String(ref arg) => Ok(AsRef::<OsStr>::as_ref(arg).into()),
+					other => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+				}
+			)
+			.collect::<Result<Vec<Box<OsStr>>, Panic>>()?;
+
+		if argv.is_empty() {
+			return Err(Panic::value_error(Value::from(Vec::<Value>::new()), "non-empty array", context.pos));
+		}
+
+		let policy = &context.runtime.options.policy;
+		policy.check_command(&argv[0], context.pos.copy())?;
+
+		let stdio = [
+			Self::stdio(&options, "stdin", context.pos.copy())?,
+			Self::stdio(&options, "stdout", context.pos.copy())?,
+			Self::stdio(&options, "stderr", context.pos.copy())?,
+		];
+
+		for path in stdio.iter().flatten() {
+			policy.check_path(&context.runtime.options.path(path), context.pos.copy())?;
+		}
+
+		Ok(
+			Self::spawn(&argv, stdio, &context.runtime.options)
+				.map(|pid| Value::Int(pid.into()))
+				.into()
+		)
+	}
+}
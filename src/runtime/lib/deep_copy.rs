@@ -0,0 +1,67 @@
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	Array,
+	CallContext,
+	Dict,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(DeepCopy) }
+
+/// Copy a value, recursively copying arrays and dicts, so that the result shares no
+/// mutable state with the original. Other values are immutable, and are shared.
+#[derive(Trace, Finalize)]
+struct DeepCopy;
+
+impl DeepCopy {
+	/// Nesting deeper than this is most likely a cyclic value.
+	const MAX_DEPTH: usize = 1024;
+
+	fn copy(value: &Value, depth: usize, pos: &SourcePos) -> Result<Value, Panic> {
+		if depth > Self::MAX_DEPTH {
+			return Err(Panic::stack_overflow(pos.copy()));
+		}
+
+		match value {
+			Value::Array(array) => {
+				let values = array
+					.borrow()
+					.iter()
+					.map(|value| Self::copy(value, depth + 1, pos))
+					.collect::<Result<_, _>>()?;
+
+				Ok(Array::new(values).into())
+			}
+
+			Value::Dict(dict) => {
+				let entries = dict
+					.borrow()
+					.iter()
+					.map(|(key, value)| Ok((key.copy(), Self::copy(value, depth + 1, pos)?)))
+					.collect::<Result<_, Panic>>()?;
+
+				Ok(Dict::new(entries).into())
+			}
+
+			value => Ok(value.copy()),
+		}
+	}
+}
+
+impl NativeFun for DeepCopy {
+	fn name(&self) -> &'static str { "std.deep_copy" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => Self::copy(value, 0, &context.pos),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,303 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	keys,
+	util,
+	CallContext,
+	Dict,
+	DictMap,
+	Function,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Keys) }
+inventory::submit! { RustFun::from(Values) }
+inventory::submit! { RustFun::from(Entries) }
+inventory::submit! { RustFun::from(Merge) }
+inventory::submit! { RustFun::from(DeepMerge) }
+inventory::submit! { RustFun::from(Get) }
+inventory::submit! { RustFun::from(Pop) }
+inventory::submit! { RustFun::from(Invert) }
+inventory::submit! { RustFun::from(Filter) }
+inventory::submit! { RustFun::from(Map) }
+
+
+/// The entries of a dict, in insertion order. This is the iteration order of dicts.
+pub fn entries(dict: &Dict) -> Vec<(Value, Value)> {
+	dict
+		.borrow()
+		.iter()
+		.map(|(key, value)| (key.copy(), value.copy()))
+		.collect()
+}
+
+
+/// Shallow copy of the entries of a dict.
+fn copy(dict: &Dict) -> DictMap {
+	dict
+		.borrow()
+		.iter()
+		.map(|(key, value)| (key.copy(), value.copy()))
+		.collect()
+}
+
+
+/// Get the dict argument of functions taking only a dict.
+fn dict(context: &CallContext) -> Result<Dict, Panic> {
+	match context.args() {
+		[ Value::Dict(ref dict) ] => Ok(dict.copy()),
+
+		[ other ] => Err(Panic::type_error(other.copy(), "dict", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+	}
+}
+
+
+/// Get the arguments of functions taking a dict and a function.
+fn dict_fun(context: &CallContext) -> Result<(Dict, Function), Panic> {
+	match context.args() {
+		[ Value::Dict(ref dict), Value::Function(ref fun) ] => Ok((dict.copy(), fun.copy())),
+
+		[ Value::Dict(_), other ] => Err(Panic::type_error(other.copy(), "function", context.pos.copy())),
+		[ other, _ ] => Err(Panic::type_error(other.copy(), "dict", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	}
+}
+
+
+/// Get the two dict arguments of the merge functions.
+fn dicts(context: &CallContext) -> Result<(Dict, Dict), Panic> {
+	match context.args() {
+		[ Value::Dict(ref left), Value::Dict(ref right) ] => Ok((left.copy(), right.copy())),
+
+		[ Value::Dict(_), other ] | [ other, _ ] => Err(Panic::type_error(other.copy(), "dict", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	}
+}
+
+
+/// The keys of a dict, in insertion order.
+#[derive(Trace, Finalize)]
+struct Keys;
+
+impl NativeFun for Keys {
+	fn name(&self) -> &'static str { "std.dict.keys" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let keys: Vec<Value> = entries(&dict(&context)?)
+			.into_iter()
+			.map(|(key, _)| key)
+			.collect();
+
+		Ok(keys.into())
+	}
+}
+
+
+/// The values of a dict, in the insertion order of their keys.
+#[derive(Trace, Finalize)]
+struct Values;
+
+impl NativeFun for Values {
+	fn name(&self) -> &'static str { "std.dict.values" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let values: Vec<Value> = entries(&dict(&context)?)
+			.into_iter()
+			.map(|(_, value)| value)
+			.collect();
+
+		Ok(values.into())
+	}
+}
+
+
+/// The entries of a dict, as dicts with key and value fields, in insertion order.
+#[derive(Trace, Finalize)]
+struct Entries;
+
+impl NativeFun for Entries {
+	fn name(&self) -> &'static str { "std.dict.entries" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let entries: Vec<Value> = entries(&dict(&context)?)
+			.into_iter()
+			.map(
+				|(key, value)| {
+					let mut entry = DictMap::default();
+					keys::KEY.with(|name| entry.insert(name.copy(), key));
+					keys::VALUE.with(|name| entry.insert(name.copy(), value));
+					Dict::new(entry).into()
+				}
+			)
+			.collect();
+
+		Ok(entries.into())
+	}
+}
+
+
+/// Produce a new dict with the entries of both dicts. Entries of the second dict take
+/// precedence.
+#[derive(Trace, Finalize)]
+struct Merge;
+
+impl NativeFun for Merge {
+	fn name(&self) -> &'static str { "std.dict.merge" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (left, right) = dicts(&context)?;
+
+		let mut result = copy(&left);
+		result.extend(copy(&right));
+
+		Ok(Dict::from(result).into())
+	}
+}
+
+
+/// Produce a new dict with the entries of both dicts, recursively merging values which
+/// are dicts in both. Otherwise, entries of the second dict take precedence.
+#[derive(Trace, Finalize)]
+struct DeepMerge;
+
+impl DeepMerge {
+	fn merge(left: &Dict, right: &Dict) -> Dict {
+		let mut result = copy(left);
+
+		for (key, value) in right.borrow().iter() {
+			let merged = match (result.get(key), value) {
+				(Some(Value::Dict(left)), Value::Dict(right)) => Self::merge(left, right).into(),
+				(_, value) => value.copy(),
+			};
+
+			result.insert(key.copy(), merged);
+		}
+
+		Dict::from(result)
+	}
+}
+
+impl NativeFun for DeepMerge {
+	fn name(&self) -> &'static str { "std.dict.deep_merge" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (left, right) = dicts(&context)?;
+		Ok(Self::merge(&left, &right).into())
+	}
+}
+
+
+/// Get the value for a key, or the given default if the key is not present.
+#[derive(Trace, Finalize)]
+struct Get;
+
+impl NativeFun for Get {
+	fn name(&self) -> &'static str { "std.dict.get" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Dict(ref dict), key, default ] => Ok(dict.get(key).unwrap_or_else(|_| default.copy())),
+
+			[ other, _, _ ] => Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
+
+
+/// Remove a key, returning its value. If the key is not present, the given default is
+/// returned, or a panic is raised if there is no default.
+#[derive(Trace, Finalize)]
+struct Pop;
+
+impl NativeFun for Pop {
+	fn name(&self) -> &'static str { "std.dict.pop" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Dict(ref dict), key ] => dict
+				.borrow_mut()
+				.shift_remove(key)
+				.ok_or_else(|| Panic::index_out_of_bounds(key.copy(), context.pos.copy())),
+
+			[ Value::Dict(ref dict), key, default ] => Ok(
+				dict
+					.borrow_mut()
+					.shift_remove(key)
+					.unwrap_or_else(|| default.copy())
+			),
+
+			[ other, _ ] | [ other, _, _ ] => Err(Panic::type_error(other.copy(), "dict", context.pos.copy())),
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos.copy()))
+		}
+	}
+}
+
+
+/// Produce a new dict mapping values to keys. If several keys have the same value, the
+/// last key is kept.
+#[derive(Trace, Finalize)]
+struct Invert;
+
+impl NativeFun for Invert {
+	fn name(&self) -> &'static str { "std.dict.invert" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let inverted: Dict = entries(&dict(&context)?)
+			.into_iter()
+			.map(|(key, value)| (value, key))
+			.collect();
+
+		Ok(inverted.into())
+	}
+}
+
+
+/// Produce a new dict with the entries for which the predicate, called with the key and
+/// the value, returns true.
+#[derive(Trace, Finalize)]
+struct Filter;
+
+impl NativeFun for Filter {
+	fn name(&self) -> &'static str { "std.dict.filter" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (dict, fun) = dict_fun(&context)?;
+
+		let mut result = DictMap::default();
+		for (key, value) in entries(&dict) {
+			if util::test(&mut context, &fun, [ key.copy(), value.copy() ])? {
+				result.insert(key, value);
+			}
+		}
+
+		Ok(Dict::from(result).into())
+	}
+}
+
+
+/// Produce a new dict with the same keys, and values replaced by the result of the given
+/// function, called with the key and the value.
+#[derive(Trace, Finalize)]
+struct Map;
+
+impl NativeFun for Map {
+	fn name(&self) -> &'static str { "std.dict.map" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (dict, fun) = dict_fun(&context)?;
+
+		let mut result = DictMap::default();
+		for (key, value) in entries(&dict) {
+			let value = util::call(&mut context, &fun, [ key.copy(), value ])?;
+			result.insert(key, value);
+		}
+
+		Ok(Dict::from(result).into())
+	}
+}
@@ -0,0 +1,190 @@
+use std::{
+	ffi::OsStr,
+	fs,
+	path::Path,
+};
+
+use gc::{Finalize, Trace};
+use similar::{ChangeTag, TextDiff};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Diff) }
+
+
+/// Diff options.
+struct Options {
+	/// Lines of unchanged context around changes.
+	context: usize,
+	/// Whether the arguments are file paths instead of texts.
+	files: bool,
+}
+
+
+impl Options {
+	fn new(value: Option<&Value>, pos: SourcePos) -> Result<Self, Panic> {
+		let option = |name: &str| -> Result<Value, Panic> {
+			match value {
+				None => Ok(Value::Nil),
+				Some(Value::Dict(dict)) => Ok(dict.get(&name.into()).unwrap_or_default()),
+				Some(other) => Err(Panic::type_error(other.copy(), "dict", pos.copy())),
+			}
+		};
+
+		let context = match option("context")? {
+			Value::Nil => 3,
+			Value::Int(context) if context >= 0 => context as usize,
+			value @ Value::Int(_) => return Err(Panic::value_error(value, "non-negative context", pos)),
+			other => return Err(Panic::type_error(other, "int", pos)),
+		};
+
+		let files = match option("files")? {
+			Value::Nil => false,
+			Value::Bool(files) => files,
+			other => return Err(Panic::type_error(other, "bool", pos)),
+		};
+
+		Ok(Self { context, files })
+	}
+}
+
+
+/// Compare two texts by lines. Returns a dict with whether they differ (changed), the
+/// differences in unified diff format (unified), and the hunks of the diff, each with
+/// the line ranges it covers (old_start, old_lines, new_start, new_lines) and it's lines,
+/// each with a kind (context, delete or insert) and the text. Line numbers start at 1.
+/// Options:
+/// - context: the lines of unchanged context around changes, 3 by default.
+/// - files: whether the arguments are paths of files to compare. Failing to read a file
+///   returns an error value.
+#[derive(Trace, Finalize)]
+struct Diff;
+
+impl Diff {
+	fn diff(old: &str, new: &str, labels: (&str, &str), context: usize) -> Value {
+		let diff = TextDiff::from_lines(old, new);
+
+		let unified = diff
+			.unified_diff()
+			.context_radius(context)
+			.header(labels.0, labels.1)
+			.to_string();
+
+		let hunks: Vec<Value> = diff
+			.grouped_ops(context)
+			.iter()
+			.map(
+				|ops| {
+					let (first, last) = match (ops.first(), ops.last()) {
+						(Some(first), Some(last)) => (first, last),
+						_ => unreachable!("hunks are never empty"),
+					};
+
+					let old_range = first.old_range().start .. last.old_range().end;
+					let new_range = first.new_range().start .. last.new_range().end;
+
+					let lines: Vec<Value> = ops
+						.iter()
+						.flat_map(|op| diff.iter_changes(op))
+						.map(
+							|change| {
+								let kind = match change.tag() {
+									ChangeTag::Equal => "context",
+									ChangeTag::Delete => "delete",
+									ChangeTag::Insert => "insert",
+								};
+
+								let text = change.value();
+								let text = text.strip_suffix('\n').unwrap_or(text);
+
+								util::dict([
+									("kind", kind.into()),
+									("text", text.into()),
+								])
+							}
+						)
+						.collect();
+
+					util::dict([
+						("old_start", (old_range.start as i64 + 1).into()),
+						("old_lines", (old_range.len() as i64).into()),
+						("new_start", (new_range.start as i64 + 1).into()),
+						("new_lines", (new_range.len() as i64).into()),
+						("lines", lines.into()),
+					])
+				}
+			)
+			.collect();
+
+		util::dict([
+			("changed", (!hunks.is_empty()).into()),
+			("unified", unified.into()),
+			("hunks", hunks.into()),
+		])
+	}
+}
+
+impl NativeFun for Diff {
+	fn name(&self) -> &'static str { "std.diff" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (old, new, options) = match context.args() {
+			[ old @ Value::String(_), new @ Value::String(_) ] => (old, new, None),
+			[ old @ Value::String(_), new @ Value::String(_), options ] => (old, new, Some(options)),
+
+			[ Value::String(_), other ] | [ Value::String(_), other, _ ] => {
+				return Err(Panic::type_error(other.copy(), "string", context.pos))
+			}
+			[ other, _ ] | [ other, _, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let options = Options::new(options, context.pos.copy())?;
+
+		// Both arguments are known to be strings.
+		let string = |value: &Value| match value {
+			Value::String(string) => String::from_utf8_lossy(string.as_bytes()).into_owned(),
+			_ => unreachable!(),
+		};
+
+		if !options.files {
+			return Ok(Self::diff(&string(old), &string(new), ("a", "b"), options.context));
+		}
+
+		for value in [old, new] {
+			if let Value::String(path) = value {
+				let path = context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path)));
+				context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+			}
+		}
+
+		let read = |value: &Value| -> Result<String, Value> {
+			let path = match value {
+				Value::String(path) => context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path))),
+				_ => unreachable!(),
+			};
+
+			fs::read(path)
+				.map(|data| String::from_utf8_lossy(&data).into_owned())
+				.map_err(|error| Error::new(error.to_string().into(), value.copy()).into())
+		};
+
+		match (read(old), read(new)) {
+			(Ok(old_text), Ok(new_text)) => Ok(
+				Self::diff(&old_text, &new_text, (&string(old), &string(new)), options.context)
+			),
+			(Err(error), _) | (_, Err(error)) => Ok(error),
+		}
+	}
+}
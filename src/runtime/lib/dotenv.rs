@@ -0,0 +1,262 @@
+use std::{ffi::OsStr, fs, path::Path};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::{command::env, Policy, SourcePos};
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Load) }
+
+
+/// Expand `${NAME}` references with earlier variables of the file, or the environment.
+/// Unknown variables, and environment variables denied by the policy, expand to nothing.
+fn expand(value: &str, vars: &[(String, String)], policy: &Policy) -> String {
+	let mut expanded = String::with_capacity(value.len());
+	let mut rest = value;
+
+	while let Some(start) = rest.find("${") {
+		let end = match rest[start ..].find('}') {
+			Some(end) => start + end,
+			None => break,
+		};
+
+		expanded.push_str(&rest[.. start]);
+
+		let name = &rest[start + 2 .. end];
+		let var = vars
+			.iter()
+			.rev()
+			.find(|(var, _)| var == name)
+			.map(|(_, value)| value.clone())
+			.or_else(
+				|| Some(OsStr::new(name))
+					.filter(|name| policy.allows_env(name))
+					.and_then(env::get)
+					.map(|value| value.to_string_lossy().into_owned())
+			);
+
+		expanded.push_str(&var.unwrap_or_default());
+		rest = &rest[end + 1 ..];
+	}
+
+	expanded.push_str(rest);
+	expanded
+}
+
+
+/// Parse the value of a variable, after the equals sign.
+fn value(value: &str, vars: &[(String, String)], policy: &Policy) -> Option<String> {
+	let value = value.trim();
+
+	if let Some(quoted) = value.strip_prefix('\'') {
+		// Single quoted values are literal.
+		let end = quoted.find('\'')?;
+		Some(quoted[.. end].to_owned())
+	} else if let Some(quoted) = value.strip_prefix('"') {
+		let mut unescaped = String::new();
+		let mut chars = quoted.chars();
+
+		loop {
+			match chars.next()? {
+				'"' => break,
+				'\\' => match chars.next()? {
+					'n' => unescaped.push('\n'),
+					't' => unescaped.push('\t'),
+					c => unescaped.push(c),
+				},
+				c => unescaped.push(c),
+			}
+		}
+
+		Some(expand(&unescaped, vars, policy))
+	} else {
+		// Unquoted values end at a comment.
+		let value = match value.find(" #") {
+			Some(comment) => &value[.. comment],
+			None => value,
+		};
+
+		Some(expand(value.trim_end(), vars, policy))
+	}
+}
+
+
+/// Parse the contents of a .env file into the variables, in order. Malformed lines
+/// produce an error value with the line number.
+fn parse(dotenv: &str, policy: &Policy) -> Result<Vec<(String, String)>, Value> {
+	let mut vars: Vec<(String, String)> = Vec::new();
+
+	for (number, line) in dotenv.lines().enumerate() {
+		let line = line.trim();
+
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let line = line.strip_prefix("export ").unwrap_or(line);
+
+		let var = line
+			.split_once('=')
+			.map(|(name, value)| (name.trim(), value))
+			.filter(
+				|(name, _)| !name.is_empty() && name
+					.chars()
+					.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+			)
+			.and_then(|(name, text)| Some((name.to_owned(), value(text, &vars, policy)?)));
+
+		match var {
+			Some(var) => vars.push(var),
+			None => {
+				let description = format!("invalid line in .env file: {}", line);
+				return Err(Error::new(description.into(), Value::Int(number as i64 + 1)).into());
+			}
+		}
+	}
+
+	Ok(vars)
+}
+
+
+/// Build a dict from the variables.
+fn to_dict(vars: &[(String, String)]) -> Value {
+	let dict: DictMap = vars
+		.iter()
+		.map(|(name, value)| (name.as_str().into(), value.as_str().into()))
+		.collect();
+
+	Dict::new(dict).into()
+}
+
+
+/// Parse the contents of a .env file into a dict. Lines are `NAME=value`, optionally
+/// prefixed by `export`. Values may be single quoted, which are literal, or double quoted,
+/// which may contain `\n`, `\t` and `\"` escapes. Unquoted values end at ` #`, which
+/// starts a comment. Unquoted and double quoted values expand `${NAME}` with earlier
+/// variables, or the environment.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.dotenv.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::String(ref string) ] => {
+				let dotenv = std::str::from_utf8(string.as_bytes())
+					.map_err(|_| Panic::value_error(value.copy(), "valid utf-8", context.pos.copy()))?;
+
+				Ok(
+					parse(dotenv, &context.runtime.options.policy)
+						.map(|vars| to_dict(&vars))
+						.unwrap_or_else(|error| error)
+				)
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Load options.
+struct Options {
+	/// Whether to export the variables into the environment.
+	export: bool,
+	/// Whether exported variables replace variables which are already defined.
+	override_: bool,
+}
+
+
+impl Options {
+	fn new(value: Option<&Value>, pos: SourcePos) -> Result<Self, Panic> {
+		let option = |name: &str| -> Result<bool, Panic> {
+			let value = match value {
+				None => Value::Nil,
+				Some(Value::Dict(dict)) => dict.get(&name.into()).unwrap_or_default(),
+				Some(other) => return Err(Panic::type_error(other.copy(), "dict", pos.copy())),
+			};
+
+			match value {
+				Value::Nil => Ok(false),
+				Value::Bool(value) => Ok(value),
+				other => Err(Panic::type_error(other, "bool", pos.copy())),
+			}
+		};
+
+		Ok(
+			Self {
+				export: option("export")?,
+				override_: option("override")?,
+			}
+		)
+	}
+}
+
+
+/// Read a .env file, in the format of std.dotenv.parse, into a dict. Failing to read or
+/// parse the file returns an error value.
+/// Options:
+/// - export: whether to export the variables into the environment, false by default.
+/// - override: whether exported variables replace variables which are already defined,
+///   false by default.
+#[derive(Trace, Finalize)]
+struct Load;
+
+impl NativeFun for Load {
+	fn name(&self) -> &'static str { "std.dotenv.load" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (path, options) = match context.args() {
+			[ Value::String(path) ] => (path, None),
+			[ Value::String(path), options ] => (path, Some(options)),
+
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let options = Options::new(options, context.pos.copy())?;
+
+		context.runtime.options.policy.check_path(Path::new(&path), context.pos.copy())?;
+
+		let dotenv = match fs::read(Path::new(&path)) {
+			Ok(dotenv) => dotenv,
+			Err(error) => return Ok(Error::new(error.to_string().into(), Value::String(path.copy())).into()),
+		};
+
+		let vars = match parse(&String::from_utf8_lossy(&dotenv), &context.runtime.options.policy) {
+			Ok(vars) => vars,
+			Err(error) => return Ok(error),
+		};
+
+		if options.export {
+			// Check all names first, so that no variable is exported if any is denied.
+			for (name, _) in &vars {
+				context.runtime.options.policy.check_env(OsStr::new(name), context.pos.copy())?;
+			}
+
+			for (name, value) in &vars {
+				let name = OsStr::new(name);
+
+				if options.override_ || env::get(name).is_none() {
+					env::export(name, Some(OsStr::new(value)));
+				}
+			}
+		}
+
+		Ok(to_dict(&vars))
+	}
+}
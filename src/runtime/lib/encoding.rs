@@ -0,0 +1,275 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	util,
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Base64Encode) }
+inventory::submit! { RustFun::from(Base64Decode) }
+inventory::submit! { RustFun::from(Base64UrlEncode) }
+inventory::submit! { RustFun::from(Base64UrlDecode) }
+inventory::submit! { RustFun::from(HexEncode) }
+inventory::submit! { RustFun::from(HexDecode) }
+inventory::submit! { RustFun::from(UrlEncode) }
+inventory::submit! { RustFun::from(UrlDecode) }
+inventory::submit! { RustFun::from(Base64EncodeAlias) }
+inventory::submit! { RustFun::from(Base64DecodeAlias) }
+inventory::submit! { RustFun::from(HexEncodeAlias) }
+inventory::submit! { RustFun::from(HexDecodeAlias) }
+
+
+/// Encode a string or byte array.
+fn encode(context: CallContext, encode: fn(&[u8]) -> Vec<u8>) -> Result<Value, Panic> {
+	match context.args() {
+		[ value ] => match util::bytes(value) {
+			Some(bytes) => Ok(Str::from(encode(&bytes)).into()),
+			None => Err(Panic::type_error(value.copy(), "string or byte array", context.pos)),
+		},
+
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+	}
+}
+
+
+/// Decode a string or byte array, producing an error value if the input is malformed.
+fn decode(context: CallContext, decode: fn(&[u8]) -> Result<Vec<u8>, String>) -> Result<Value, Panic> {
+	match context.args() {
+		[ value ] => match util::bytes(value) {
+			Some(bytes) => Ok(
+				match decode(&bytes) {
+					Ok(decoded) => Str::from(decoded).into(),
+					Err(error) => Error::new(error.into(), value.copy()).into(),
+				}
+			),
+			None => Err(Panic::type_error(value.copy(), "string or byte array", context.pos)),
+		},
+
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Base64Encode;
+
+impl NativeFun for Base64Encode {
+	fn name(&self) -> &'static str { "std.encoding.base64.encode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		encode(context, |bytes| base64::encode(bytes).into_bytes())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Base64Decode;
+
+impl NativeFun for Base64Decode {
+	fn name(&self) -> &'static str { "std.encoding.base64.decode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		decode(context, |bytes| base64::decode(bytes).map_err(|error| error.to_string()))
+	}
+}
+
+
+/// URL-safe base64 is encoded without padding, as in tokens and URLs.
+#[derive(Trace, Finalize)]
+struct Base64UrlEncode;
+
+impl NativeFun for Base64UrlEncode {
+	fn name(&self) -> &'static str { "std.encoding.base64url.encode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		encode(
+			context,
+			|bytes| base64::encode_config(bytes, base64::URL_SAFE_NO_PAD).into_bytes()
+		)
+	}
+}
+
+
+/// URL-safe base64 is decoded with or without padding.
+#[derive(Trace, Finalize)]
+struct Base64UrlDecode;
+
+impl NativeFun for Base64UrlDecode {
+	fn name(&self) -> &'static str { "std.encoding.base64url.decode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		decode(
+			context,
+			|bytes| {
+				let bytes = bytes.strip_suffix(b"==")
+					.or_else(|| bytes.strip_suffix(b"="))
+					.unwrap_or(bytes);
+
+				base64::decode_config(bytes, base64::URL_SAFE_NO_PAD)
+					.map_err(|error| error.to_string())
+			}
+		)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct HexEncode;
+
+impl NativeFun for HexEncode {
+	fn name(&self) -> &'static str { "std.encoding.hex.encode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		encode(context, |bytes| hex::encode(bytes).into_bytes())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct HexDecode;
+
+impl NativeFun for HexDecode {
+	fn name(&self) -> &'static str { "std.encoding.hex.decode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		decode(context, |bytes| hex::decode(bytes).map_err(|error| error.to_string()))
+	}
+}
+
+
+/// Percent-encoding, as in RFC 3986. Only unreserved characters are kept as is.
+#[derive(Trace, Finalize)]
+struct UrlEncode;
+
+impl UrlEncode {
+	fn encode(bytes: &[u8]) -> Vec<u8> {
+		const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+		let mut encoded = Vec::with_capacity(bytes.len());
+
+		for &byte in bytes {
+			match byte {
+				b'A' ..= b'Z' | b'a' ..= b'z' | b'0' ..= b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte),
+				_ => encoded.extend_from_slice(
+					&[ b'%', DIGITS[(byte >> 4) as usize], DIGITS[(byte & 0xF) as usize] ]
+				),
+			}
+		}
+
+		encoded
+	}
+}
+
+impl NativeFun for UrlEncode {
+	fn name(&self) -> &'static str { "std.encoding.url.encode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		encode(context, Self::encode)
+	}
+}
+
+
+/// Percent-decoding. Plus signs are kept as is, as they only mean spaces in forms.
+#[derive(Trace, Finalize)]
+struct UrlDecode;
+
+impl UrlDecode {
+	fn decode(bytes: &[u8]) -> Result<Vec<u8>, String> {
+		let mut decoded = Vec::with_capacity(bytes.len());
+		let mut iter = bytes.iter().enumerate();
+
+		while let Some((ix, &byte)) = iter.next() {
+			if byte != b'%' {
+				decoded.push(byte);
+				continue;
+			}
+
+			let digits = bytes
+				.get(ix + 1 ..= ix + 2)
+				.filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+				.and_then(|digits| std::str::from_utf8(digits).ok())
+				.and_then(|digits| u8::from_str_radix(digits, 16).ok());
+
+			match digits {
+				Some(byte) => {
+					decoded.push(byte);
+					iter.nth(1);
+				}
+				None => return Err(format!("invalid percent-encoding at offset {}", ix)),
+			}
+		}
+
+		Ok(decoded)
+	}
+}
+
+impl NativeFun for UrlDecode {
+	fn name(&self) -> &'static str { "std.encoding.url.decode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		decode(context, Self::decode)
+	}
+}
+
+
+// std.encoding is the canonical module for encodings. std.base64 and std.hex predate it,
+// and are kept as aliases of std.encoding.base64 and std.encoding.hex.
+
+
+/// Alias of std.encoding.base64.encode.
+#[derive(Trace, Finalize)]
+struct Base64EncodeAlias;
+
+impl NativeFun for Base64EncodeAlias {
+	fn name(&self) -> &'static str { "std.base64.encode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Base64Encode.call(context)
+	}
+}
+
+
+/// Alias of std.encoding.base64.decode.
+#[derive(Trace, Finalize)]
+struct Base64DecodeAlias;
+
+impl NativeFun for Base64DecodeAlias {
+	fn name(&self) -> &'static str { "std.base64.decode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Base64Decode.call(context)
+	}
+}
+
+
+/// Alias of std.encoding.hex.encode.
+#[derive(Trace, Finalize)]
+struct HexEncodeAlias;
+
+impl NativeFun for HexEncodeAlias {
+	fn name(&self) -> &'static str { "std.hex.encode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		HexEncode.call(context)
+	}
+}
+
+
+/// Alias of std.encoding.hex.decode.
+#[derive(Trace, Finalize)]
+struct HexDecodeAlias;
+
+impl NativeFun for HexDecodeAlias {
+	fn name(&self) -> &'static str { "std.hex.decode" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		HexDecode.call(context)
+	}
+}
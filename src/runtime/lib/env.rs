@@ -1,33 +1,135 @@
 use gc::{Finalize, Trace};
 
-use crate::runtime::value::Error;
+use crate::runtime::{command::env, value::Error};
 
 use super::{
 	CallContext,
+	Dict,
+	DictMap,
 	RustFun,
 	NativeFun,
 	Panic,
+	Str,
 	Value,
 };
 
 
 inventory::submit! { RustFun::from(Env) }
+inventory::submit! { RustFun::from(Get) }
+inventory::submit! { RustFun::from(Set) }
+inventory::submit! { RustFun::from(Unset) }
 inventory::submit! { RustFun::from(Export) }
+inventory::submit! { RustFun::from(IsExported) }
+inventory::submit! { RustFun::from(Vars) }
+inventory::submit! { RustFun::from(LegacyExport) }
 
+
+/// Check that the variable name and value can be placed in the environment, producing an
+/// error value otherwise.
+fn validate(key: &Str, value: Option<&Str>) -> Result<(), Value> {
+	if key.is_empty() || key.contains(b'=') || key.contains(b'\0') {
+		return Err(Error::new("invalid variable name".into(), Value::String(key.copy())).into());
+	}
+
+	match value {
+		Some(value) if value.contains(b'\0') => Err(
+			Error::new("invalid variable value".into(), Value::String(value.copy())).into()
+		),
+		_ => Ok(()),
+	}
+}
+
+
+/// Get a variable, either local or exported.
+fn get(context: CallContext) -> Result<Value, Panic> {
+	match context.args() {
+		[ Value::String(ref name) ] => {
+			context.runtime.options.policy.check_env(name.as_ref(), context.pos.copy())?;
+			Ok(env::get(name.as_ref()).into())
+		}
+
+		[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+	}
+}
+
+
+/// The env module may also be called, as a shorthand for std.env.get.
 #[derive(Trace, Finalize)]
 struct Env;
 
 impl NativeFun for Env {
 	fn name(&self) -> &'static str { "std.env" }
 
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		get(context)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Get;
+
+impl NativeFun for Get {
+	fn name(&self) -> &'static str { "std.env.get" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		get(context)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Set;
+
+impl NativeFun for Set {
+	fn name(&self) -> &'static str { "std.env.set" }
+
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ Value::String(ref string) ] => Ok(
-				std::env
-					::var_os(string)
-					.map(Value::from)
-					.unwrap_or(Value::Nil)
-			),
+			[ Value::String(ref name), Value::String(ref value) ] => {
+				context.runtime.options.policy.check_env(name.as_ref(), context.pos.copy())?;
+
+				Ok(
+					match validate(name, Some(value)) {
+						Ok(()) => {
+							env::set(name.as_ref(), value.as_ref());
+							Value::default()
+						}
+						Err(error) => error,
+					}
+				)
+			}
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Unset;
+
+impl NativeFun for Unset {
+	fn name(&self) -> &'static str { "std.env.unset" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref name) ] => {
+				context.runtime.options.policy.check_env(name.as_ref(), context.pos.copy())?;
+
+				Ok(
+					match validate(name, None) {
+						Ok(()) => {
+							env::unset(name.as_ref());
+							Value::default()
+						}
+						Err(error) => error,
+					}
+				)
+			}
 
 			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
@@ -35,32 +137,107 @@ impl NativeFun for Env {
 	}
 }
 
+
 #[derive(Trace, Finalize)]
 struct Export;
 
 impl NativeFun for Export {
-	fn name(&self) -> &'static str { "std.export" }
+	fn name(&self) -> &'static str { "std.env.export" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (name, value) = match context.args() {
+			[ Value::String(ref name) ] => (name, None),
+			[ Value::String(ref name), Value::String(ref value) ] => (name, Some(value)),
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		context.runtime.options.policy.check_env(name.as_ref(), context.pos.copy())?;
+
+		if let Err(error) = validate(name, value) {
+			return Ok(error);
+		}
+
+		if env::export(name.as_ref(), value.map(AsRef::as_ref)) {
+			Ok(Value::default())
+		} else {
+			Ok(Error::new("variable not defined".into(), Value::String(name.copy())).into())
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct IsExported;
+
+impl NativeFun for IsExported {
+	fn name(&self) -> &'static str { "std.env.is_exported" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ k @ Value::String(ref key), v @ Value::String(ref value) ] => {
-				let ret = match () {
-					() if key.contains(b'=') || key.contains(b'\0') => {
-						Error::new("invalid export key".into(), k.copy()).into()
-					}
+			[ Value::String(ref name) ] => {
+				context.runtime.options.policy.check_env(name.as_ref(), context.pos.copy())?;
+				Ok(env::is_exported(name.as_ref()).into())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
 
-					() if value.contains(b'\0') => {
-						Error::new("invalid export value".into(), v.copy()).into()
-					}
 
-					() => {
-						std::env::set_var(key, value);
-						Value::default()
-					},
-				};
+#[derive(Trace, Finalize)]
+struct Vars;
 
-				Ok(ret)
-			},
+impl NativeFun for Vars {
+	fn name(&self) -> &'static str { "std.env.vars" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		// Local variables take precedence, as in env::get. Variables denied by the sandbox
+		// policy are left out.
+		let policy = &context.runtime.options.policy;
+		let vars: DictMap = env::vars()
+			.into_iter()
+			.chain(env::locals())
+			.filter(|(name, _)| policy.allows_env(name))
+			.map(|(name, value)| (name.into(), value.into()))
+			.collect();
+
+		Ok(Dict::new(vars).into())
+	}
+}
+
+
+/// The former interface for exporting variables, kept for compatibility.
+#[derive(Trace, Finalize)]
+struct LegacyExport;
+
+impl NativeFun for LegacyExport {
+	fn name(&self) -> &'static str { "std.export" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref name), Value::String(ref value) ] => {
+				context.runtime.options.policy.check_env(name.as_ref(), context.pos.copy())?;
+
+				Ok(
+					match validate(name, Some(value)) {
+						Ok(()) => {
+							env::export(name.as_ref(), Some(value.as_ref()));
+							Value::default()
+						}
+						Err(error) => error,
+					}
+				)
+			}
 
 			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
 			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
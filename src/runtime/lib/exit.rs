@@ -13,6 +13,9 @@ use super::{
 
 inventory::submit!{ RustFun::from(Exit) }
 
+/// Terminate the program with the given status. Rather than exiting the process right
+/// away, the program is unwound, so that open resources are released first. std.catch
+/// does not intercept exits.
 #[derive(Trace, Finalize)]
 struct Exit;
 
@@ -25,7 +28,7 @@ impl NativeFun for Exit {
 				let code = u8::try_from(*i)
 					.map_err(|_| Panic::value_error(val.copy(), "valid exit code", context.pos.copy()))?;
 
-				std::process::exit(code.into())
+				Err(Panic::exit(code))
 			}
 
 			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
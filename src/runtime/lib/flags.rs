@@ -0,0 +1,66 @@
+use gc::{Finalize, Trace};
+
+use crate::runtime::{command::FlagStyle, SourcePos};
+
+use super::{
+	CallContext,
+	Dict,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Flags) }
+
+#[derive(Trace, Finalize)]
+struct Flags;
+
+impl Flags {
+	/// Build the flag style from the options dict. Missing options keep the default.
+	fn style(options: &Dict, pos: SourcePos) -> Result<FlagStyle, Panic> {
+		let option = |name: &str| -> Result<Option<Box<[u8]>>, Panic> {
+			match options.get(&name.into()) {
+				Ok(Value::String(ref string)) => Ok(Some(AsRef::<[u8]>::as_ref(string).into())),
+				Ok(Value::Nil) | Err(_) => Ok(None),
+				Ok(other) => Err(Panic::type_error(other, "string or nil", pos.copy())),
+			}
+		};
+
+		let mut style = FlagStyle::default();
+
+		if let Some(prefix) = option("prefix")? {
+			style.prefix = prefix;
+		}
+		style.separator = option("separator")?;
+		style.negation = option("negation")?;
+
+		Ok(style)
+	}
+}
+
+impl NativeFun for Flags {
+	fn name(&self) -> &'static str { "std.flags" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (dict, style) = match context.args() {
+			[ Value::Dict(ref dict) ] => (dict.copy(), FlagStyle::default()),
+			[ Value::Dict(ref dict), Value::Dict(ref options) ] => {
+				(dict.copy(), Self::style(options, context.pos.copy())?)
+			}
+
+			[ Value::Dict(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let flags: Vec<Value> = style
+			.flags(&dict, context.pos)?
+			.into_iter()
+			.map(|flag| Value::from(flag.into_boxed_slice()))
+			.collect();
+
+		Ok(flags.into())
+	}
+}
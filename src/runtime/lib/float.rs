@@ -30,7 +30,7 @@ impl NativeFun for Float {
 			[ value @ Value::String(ref string) ] => {
 				let parse_error = || Panic::value_error(
 					value.copy(),
-					"valid integer",
+					"valid float",
 					context.pos.copy()
 				);
 
@@ -0,0 +1,233 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Function,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Identity) }
+inventory::submit! { RustFun::from(Constant) }
+inventory::submit! { RustFun::from(Partial) }
+inventory::submit! { RustFun::from(Compose) }
+inventory::submit! { RustFun::from(Curry) }
+
+
+/// Call a function with the given arguments, followed by the arguments of the current
+/// call.
+fn call_with(context: &mut CallContext, fun: &Function, args: &[Value]) -> Result<Value, Panic> {
+	let current: Vec<Value> = context.args().iter().map(Value::copy).collect();
+
+	let args_start = context.runtime.arguments.len();
+	context.runtime.arguments.extend(args.iter().map(Value::copy));
+	context.runtime.arguments.extend(current);
+
+	context.call(Value::default(), fun, args_start)
+}
+
+
+/// Return the argument.
+#[derive(Trace, Finalize)]
+struct Identity;
+
+impl NativeFun for Identity {
+	fn name(&self) -> &'static str { "std.fn.identity" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => Ok(value.copy()),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Make a function which ignores it's arguments, and always returns the given value.
+#[derive(Trace, Finalize)]
+struct Constant;
+
+impl NativeFun for Constant {
+	fn name(&self) -> &'static str { "std.fn.constant" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => Ok(ConstantImpl(value.copy()).into()),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct ConstantImpl(Value);
+
+impl NativeFun for ConstantImpl {
+	fn name(&self) -> &'static str { "std.fn.constant<impl>" }
+
+	fn call(&self, _: CallContext) -> Result<Value, Panic> {
+		Ok(self.0.copy())
+	}
+}
+
+
+/// Make a function which calls the given one with the given leading arguments, followed
+/// by the arguments it's called with. For example, `std.fn.partial(f, 1)(2)` is `f(1, 2)`.
+#[derive(Trace, Finalize)]
+struct Partial;
+
+impl NativeFun for Partial {
+	fn name(&self) -> &'static str { "std.fn.partial" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Function(fun), args @ .. ] => Ok(
+				PartialImpl {
+					function: fun.copy(),
+					args: args.iter().map(Value::copy).collect(),
+				}.into()
+			),
+
+			[ other, .. ] => Err(Panic::type_error(other.copy(), "function", context.pos)),
+			[] => Err(Panic::invalid_args(0, 1, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct PartialImpl {
+	function: Function,
+	args: Vec<Value>,
+}
+
+impl NativeFun for PartialImpl {
+	fn name(&self) -> &'static str { "std.fn.partial<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		call_with(&mut context, &self.function, &self.args)
+	}
+}
+
+
+/// Compose functions, from right to left. For example, `std.fn.compose(f, g)(x)` is
+/// `f(g(x))`. The rightmost function receives all arguments, and the others receive the
+/// result of the previous function.
+#[derive(Trace, Finalize)]
+struct Compose;
+
+impl NativeFun for Compose {
+	fn name(&self) -> &'static str { "std.fn.compose" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let functions = context
+			.args()
+			.iter()
+			.map(
+				|value| match value {
+					Value::Function(fun) => Ok(fun.copy()),
+					other => Err(Panic::type_error(other.copy(), "function", context.pos.copy())),
+				}
+			)
+			.collect::<Result<Vec<_>, _>>()?;
+
+		if functions.is_empty() {
+			return Err(Panic::invalid_args(0, 1, context.pos));
+		}
+
+		Ok(ComposeImpl(functions).into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct ComposeImpl(Vec<Function>);
+
+impl NativeFun for ComposeImpl {
+	fn name(&self) -> &'static str { "std.fn.compose<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (last, rest) = self.0.split_last().expect("compose requires a function");
+
+		let mut value = call_with(&mut context, last, &[])?;
+
+		for fun in rest.iter().rev() {
+			let args_start = context.runtime.arguments.len();
+			context.runtime.arguments.push(value);
+			value = context.call(Value::default(), fun, args_start)?;
+		}
+
+		Ok(value)
+	}
+}
+
+
+/// Make a curried version of a function, which collects arguments over successive calls,
+/// and calls the function once the given number of arguments is reached. The number of
+/// arguments defaults to the parameter count of the function, and is required for native
+/// functions. For example, `std.fn.curry(f)(1)(2, 3)` is `f(1, 2, 3)`.
+#[derive(Trace, Finalize)]
+struct Curry;
+
+impl NativeFun for Curry {
+	fn name(&self) -> &'static str { "std.fn.curry" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (fun, arity) = match context.args() {
+			[ Value::Function(fun @ Function::Hush(hush)) ] => (fun.copy(), hush.params as usize),
+			[ Value::Function(fun), Value::Int(arity) ] if *arity >= 0 => (fun.copy(), *arity as usize),
+
+			[ value @ Value::Function(_) ] => {
+				return Err(Panic::value_error(value.copy(), "function with known parameter count", context.pos))
+			}
+			[ Value::Function(_), arity @ Value::Int(_) ] => {
+				return Err(Panic::value_error(arity.copy(), "non-negative integer", context.pos))
+			}
+			[ Value::Function(_), other ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		Ok(
+			CurryImpl {
+				function: fun,
+				arity,
+				args: Vec::new(),
+			}.into()
+		)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct CurryImpl {
+	function: Function,
+	arity: usize,
+	/// The arguments collected so far.
+	args: Vec<Value>,
+}
+
+impl NativeFun for CurryImpl {
+	fn name(&self) -> &'static str { "std.fn.curry<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		if self.args.len() + context.args().len() >= self.arity {
+			return call_with(&mut context, &self.function, &self.args);
+		}
+
+		Ok(
+			CurryImpl {
+				function: self.function.copy(),
+				arity: self.arity,
+				args: self.args
+					.iter()
+					.chain(context.args())
+					.map(Value::copy)
+					.collect(),
+			}.into()
+		)
+	}
+}
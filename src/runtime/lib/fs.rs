@@ -0,0 +1,989 @@
+use std::{
+	borrow::Cow,
+	cell::RefCell,
+	collections::VecDeque,
+	ffi::OsStr,
+	fs::{self, File, Metadata, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::mpsc,
+};
+#[cfg(unix)]
+use std::{
+	ffi::CString,
+	os::unix::{
+		ffi::OsStrExt,
+		fs::{MetadataExt, PermissionsExt},
+		io::AsRawFd,
+	},
+};
+
+use gc::{Finalize, Trace};
+use notify::{
+	event::{EventKind, ModifyKind},
+	RecommendedWatcher,
+	RecursiveMode,
+	Watcher,
+};
+
+#[cfg(unix)]
+use crate::runtime::SourcePos;
+
+use super::{
+	keys,
+	util,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(ReadFile) }
+inventory::submit! { RustFun::from(WriteFile) }
+inventory::submit! { RustFun::from(Append) }
+inventory::submit! { RustFun::from(Exists) }
+inventory::submit! { RustFun::from(IsDir) }
+inventory::submit! { RustFun::from(IsFile) }
+inventory::submit! { RustFun::from(MkdirP) }
+inventory::submit! { RustFun::from(Remove) }
+inventory::submit! { RustFun::from(RemoveAll) }
+inventory::submit! { RustFun::from(CopyFile) }
+inventory::submit! { RustFun::from(Rename) }
+inventory::submit! { RustFun::from(HardLink) }
+inventory::submit! { RustFun::from(Symlink) }
+inventory::submit! { RustFun::from(ReadLink) }
+inventory::submit! { RustFun::from(Stat) }
+inventory::submit! { RustFun::from(Lstat) }
+inventory::submit! { RustFun::from(Chmod) }
+#[cfg(unix)]
+inventory::submit! { RustFun::from(Chown) }
+inventory::submit! { RustFun::from(Walk) }
+inventory::submit! { RustFun::from(Lock) }
+inventory::submit! { RustFun::from(TryLock) }
+inventory::submit! { RustFun::from(Watch) }
+
+
+/// Get the path of a string value. Relative paths are resolved from the working
+/// directory set by std.with_cwd. Paths denied by the sandbox policy cause a panic.
+fn path<'a>(context: &'a CallContext, value: &'a Value) -> Result<Cow<'a, Path>, Panic> {
+	let options = &context.runtime.options;
+
+	let path = match value {
+		Value::String(string) => options.path(Path::new(AsRef::<OsStr>::as_ref(string))),
+		_ => return Err(Panic::type_error(value.copy(), "string", context.pos.copy())),
+	};
+
+	options.policy.check_path(&path, context.pos.copy())?;
+
+	Ok(path)
+}
+
+
+/// Call a function taking a single path. IO errors are converted to error values.
+fn unary<T, F>(context: CallContext, fun: F) -> Result<Value, Panic>
+where
+	T: Into<Value>,
+	F: FnOnce(&Path) -> io::Result<T>,
+{
+	match context.args() {
+		[ value ] => {
+			let path = path(&context, value)?;
+			Ok(fun(&path).map_err(|error| io_error(error, value)).into())
+		}
+
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+	}
+}
+
+
+/// Call a function taking a source and a destination paths.
+fn binary<T, F>(context: CallContext, fun: F) -> Result<Value, Panic>
+where
+	T: Into<Value>,
+	F: FnOnce(&Path, &Path) -> io::Result<T>,
+{
+	match context.args() {
+		[ source, destination ] => {
+			let source_path = path(&context, source)?;
+			let destination = path(&context, destination)?;
+
+			Ok(
+				fun(&source_path, &destination)
+					.map_err(|error| io_error(error, source))
+					.into()
+			)
+		}
+
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	}
+}
+
+
+/// Convert an IO error to an error value, with the path as context.
+fn io_error(error: io::Error, path: &Value) -> Error {
+	Error::new(error.to_string().into(), path.copy())
+}
+
+
+/// Write data to a file, either truncating or appending.
+fn write(context: CallContext, append: bool) -> Result<Value, Panic> {
+	match context.args() {
+		[ value, data ] => {
+			let path = path(&context, value)?;
+
+			let data = util::bytes(data)
+				.ok_or_else(|| Panic::type_error(data.copy(), "string or byte array", context.pos.copy()))?;
+
+			let result = OpenOptions::new()
+				.write(true)
+				.create(true)
+				.append(append)
+				.truncate(!append)
+				.open(&path)
+				.and_then(|mut file| file.write_all(&data));
+
+			Ok(result.map_err(|error| io_error(error, value)).into())
+		}
+
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	}
+}
+
+
+/// Convert file metadata to a dict.
+fn stat(metadata: Metadata) -> Value {
+	let file_type = metadata.file_type();
+
+	let kind = if file_type.is_dir() {
+		"dir"
+	} else if file_type.is_file() {
+		"file"
+	} else if file_type.is_symlink() {
+		"symlink"
+	} else {
+		"other"
+	};
+
+	let time = |seconds: i64, nanos: i64| Value::from(seconds as f64 + nanos as f64 / 1e9);
+
+	#[cfg(unix)]
+	let fields: [(&str, Value); 11] = [
+		("type", kind.into()),
+		("size", (metadata.size() as i64).into()),
+		("mode", ((metadata.mode() & 0o7777) as i64).into()),
+		("uid", (metadata.uid() as i64).into()),
+		("gid", (metadata.gid() as i64).into()),
+		("owner", crate::io::user_name(metadata.uid()).into()),
+		("group", crate::io::group_name(metadata.gid()).into()),
+		("nlink", (metadata.nlink() as i64).into()),
+		("atime", time(metadata.atime(), metadata.atime_nsec())),
+		("mtime", time(metadata.mtime(), metadata.mtime_nsec())),
+		("ctime", time(metadata.ctime(), metadata.ctime_nsec())),
+	];
+
+	// Windows has no owners, groups or inode change times. The creation time is reported
+	// as ctime instead.
+	#[cfg(windows)]
+	let fields: [(&str, Value); 6] = {
+		let system_time = |system_time: io::Result<std::time::SystemTime>| system_time
+			.ok()
+			.and_then(|system_time| system_time.duration_since(std::time::UNIX_EPOCH).ok())
+			.map(|duration| time(duration.as_secs() as i64, duration.subsec_nanos().into()))
+			.unwrap_or_default();
+
+		[
+			("type", kind.into()),
+			("size", (metadata.len() as i64).into()),
+			("mode", (util::mode(&metadata) as i64).into()),
+			("atime", system_time(metadata.accessed())),
+			("mtime", system_time(metadata.modified())),
+			("ctime", system_time(metadata.created())),
+		]
+	};
+
+	let dict: DictMap = IntoIterator::into_iter(fields)
+		.map(|(name, value)| (name.into(), value))
+		.collect();
+
+	Dict::new(dict).into()
+}
+
+
+#[derive(Trace, Finalize)]
+struct ReadFile;
+
+impl NativeFun for ReadFile {
+	fn name(&self) -> &'static str { "std.fs.read_file" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(context, |path| fs::read(path).map(Str::from))
+	}
+}
+
+
+/// Write to a file, replacing it's contents.
+#[derive(Trace, Finalize)]
+struct WriteFile;
+
+impl NativeFun for WriteFile {
+	fn name(&self) -> &'static str { "std.fs.write_file" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		write(context, false)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Append;
+
+impl NativeFun for Append {
+	fn name(&self) -> &'static str { "std.fs.append" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		write(context, true)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Exists;
+
+impl NativeFun for Exists {
+	fn name(&self) -> &'static str { "std.fs.exists" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(context, |path| Ok(path.exists()))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct IsDir;
+
+impl NativeFun for IsDir {
+	fn name(&self) -> &'static str { "std.fs.is_dir" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(context, |path| Ok(path.is_dir()))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct IsFile;
+
+impl NativeFun for IsFile {
+	fn name(&self) -> &'static str { "std.fs.is_file" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(context, |path| Ok(path.is_file()))
+	}
+}
+
+
+/// Create a directory along with it's parents, succeeding if it already exists.
+#[derive(Trace, Finalize)]
+struct MkdirP;
+
+impl NativeFun for MkdirP {
+	fn name(&self) -> &'static str { "std.fs.mkdir_p" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(context, |path| fs::create_dir_all(path))
+	}
+}
+
+
+/// Remove a file, or an empty directory.
+#[derive(Trace, Finalize)]
+struct Remove;
+
+impl NativeFun for Remove {
+	fn name(&self) -> &'static str { "std.fs.remove" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(
+			context,
+			|path| if fs::symlink_metadata(path)?.is_dir() {
+				fs::remove_dir(path)
+			} else {
+				fs::remove_file(path)
+			}
+		)
+	}
+}
+
+
+/// Remove a file, or a directory with all it's contents. Symlinks are not followed.
+#[derive(Trace, Finalize)]
+struct RemoveAll;
+
+impl NativeFun for RemoveAll {
+	fn name(&self) -> &'static str { "std.fs.remove_all" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(
+			context,
+			|path| if fs::symlink_metadata(path)?.is_dir() {
+				fs::remove_dir_all(path)
+			} else {
+				fs::remove_file(path)
+			}
+		)
+	}
+}
+
+
+/// Copy the contents and permissions of a file.
+#[derive(Trace, Finalize)]
+struct CopyFile;
+
+impl NativeFun for CopyFile {
+	fn name(&self) -> &'static str { "std.fs.copy" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		binary(context, |source, destination| fs::copy(source, destination).map(|_| ()))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Rename;
+
+impl NativeFun for Rename {
+	fn name(&self) -> &'static str { "std.fs.rename" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		binary(context, |source, destination| fs::rename(source, destination))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct HardLink;
+
+impl NativeFun for HardLink {
+	fn name(&self) -> &'static str { "std.fs.hard_link" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		binary(context, |source, destination| fs::hard_link(source, destination))
+	}
+}
+
+
+/// Create a symlink. The target is stored as given, so relative targets are resolved from
+/// the directory of the link.
+#[derive(Trace, Finalize)]
+struct Symlink;
+
+impl NativeFun for Symlink {
+	fn name(&self) -> &'static str { "std.fs.symlink" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref target), link ] => {
+				let path = path(&context, link)?;
+
+				#[cfg(unix)]
+				let result = std::os::unix::fs::symlink(AsRef::<OsStr>::as_ref(target), &path);
+
+				// Windows distinguishes links to directories, which are resolved from the
+				// directory of the link, as the target.
+				#[cfg(windows)]
+				let result = {
+					let target = Path::new(AsRef::<OsStr>::as_ref(target));
+					let is_dir = path
+						.parent()
+						.map_or(Cow::Borrowed(target), |parent| Cow::Owned(parent.join(target)))
+						.is_dir();
+
+					if is_dir {
+						std::os::windows::fs::symlink_dir(target, &path)
+					} else {
+						std::os::windows::fs::symlink_file(target, &path)
+					}
+				};
+
+				Ok(
+					result
+						.map_err(|error| io_error(error, link))
+						.into()
+				)
+			}
+
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct ReadLink;
+
+impl NativeFun for ReadLink {
+	fn name(&self) -> &'static str { "std.fs.readlink" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(context, |path| fs::read_link(path).map(Str::from))
+	}
+}
+
+
+/// Get the metadata of a file, following symlinks.
+#[derive(Trace, Finalize)]
+struct Stat;
+
+impl NativeFun for Stat {
+	fn name(&self) -> &'static str { "std.fs.stat" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(context, |path| fs::metadata(path).map(stat))
+	}
+}
+
+
+/// Get the metadata of a file, without following symlinks.
+#[derive(Trace, Finalize)]
+struct Lstat;
+
+impl NativeFun for Lstat {
+	fn name(&self) -> &'static str { "std.fs.lstat" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		unary(context, |path| fs::symlink_metadata(path).map(stat))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Chmod;
+
+impl NativeFun for Chmod {
+	fn name(&self) -> &'static str { "std.fs.chmod" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, mode ] => {
+				let path = path(&context, value)?;
+
+				let mode = util::file_mode(mode.copy(), 0o7777, context.pos.copy())?;
+
+				#[cfg(unix)]
+				let permissions = Ok(fs::Permissions::from_mode(mode));
+
+				// Only the read-only attribute may be set on Windows.
+				#[cfg(windows)]
+				let permissions = fs::metadata(&path).map(
+					|metadata| {
+						let mut permissions = metadata.permissions();
+						permissions.set_readonly(mode & 0o222 == 0);
+						permissions
+					}
+				);
+
+				Ok(
+					permissions
+						.and_then(|permissions| fs::set_permissions(&path, permissions))
+						.map_err(|error| io_error(error, value))
+						.into()
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+		}
+	}
+}
+
+
+/// Change the owner and group of a file, which may be given by name or id. Nil leaves
+/// them unchanged. Not supported on Windows.
+#[cfg(unix)]
+#[derive(Trace, Finalize)]
+struct Chown;
+
+#[cfg(unix)]
+impl Chown {
+	/// Get a user or group id. Unknown names produce nil.
+	fn id(value: &Value, lookup: fn(&[u8]) -> Option<u32>, pos: SourcePos) -> Result<Option<u32>, Panic> {
+		match value {
+			Value::Nil => Ok(Some(u32::MAX)), // Unchanged, as -1 in chown(2).
+			Value::Int(id) if (0 .. u32::MAX as i64).contains(id) => Ok(Some(*id as u32)),
+			Value::Int(_) => Err(Panic::value_error(value.copy(), "valid id", pos)),
+			Value::String(name) => Ok(lookup(name.as_bytes())),
+			other => Err(Panic::type_error(other.copy(), "nil, int or string", pos)),
+		}
+	}
+}
+
+#[cfg(unix)]
+impl NativeFun for Chown {
+	fn name(&self) -> &'static str { "std.fs.chown" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let nil = Value::Nil;
+
+		let (value, user, group) = match context.args() {
+			[ value, user ] => (value, user, &nil),
+			[ value, user, group ] => (value, user, group),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos.copy()))
+		};
+
+		let path = path(&context, value)?;
+
+		let uid = match Self::id(user, crate::io::user_id, context.pos.copy())? {
+			Some(uid) => uid,
+			None => return Ok(Error::new("unknown user".into(), user.copy()).into()),
+		};
+
+		let gid = match Self::id(group, crate::io::group_id, context.pos.copy())? {
+			Some(gid) => gid,
+			None => return Ok(Error::new("unknown group".into(), group.copy()).into()),
+		};
+
+		let result = CString::new(path.as_os_str().as_bytes())
+			.map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+			.and_then(
+				|path| {
+					// Safety: the path is a valid nul terminated string.
+					if unsafe { libc::chown(path.as_ptr(), uid, gid) } == 0 {
+						Ok(())
+					} else {
+						Err(io::Error::last_os_error())
+					}
+				}
+			);
+
+		Ok(result.map_err(|error| io_error(error, value)).into())
+	}
+}
+
+
+/// Iterate over all files below a directory, recursively. Directories are produced before
+/// their contents, entries are sorted by name, and symlinks are not followed. Directories
+/// which can't be read produce an error value.
+#[derive(Trace, Finalize)]
+struct Walk;
+
+impl NativeFun for Walk {
+	fn name(&self) -> &'static str { "std.fs.walk" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => {
+				let path = path(&context, value)?;
+
+				let mut walk = WalkState { pending: Vec::new() };
+				walk.push_dir(&path);
+
+				Ok(WalkImpl(RefCell::new(walk)).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+		}
+	}
+}
+
+
+/// The pending entries of a walk, in reverse order.
+struct WalkState {
+	pending: Vec<io::Result<PathBuf>>,
+}
+
+
+impl WalkState {
+	/// Queue the entries of a directory.
+	fn push_dir(&mut self, dir: &Path) {
+		let entries = fs::read_dir(dir)
+			.and_then(
+				|entries| entries
+					.map(|entry| entry.map(|entry| entry.path()))
+					.collect::<io::Result<Vec<_>>>()
+			);
+
+		match entries {
+			Ok(mut entries) => {
+				entries.sort();
+				self.pending.extend(entries.into_iter().rev().map(Ok));
+			}
+
+			Err(error) => self.pending.push(Err(error)),
+		}
+	}
+
+
+	fn next(&mut self) -> Option<Value> {
+		let entry = match self.pending.pop()? {
+			Ok(entry) => entry,
+			Err(error) => return Some(Error::from(error).into()),
+		};
+
+		let is_dir = fs::symlink_metadata(&entry)
+			.map(|metadata| metadata.is_dir())
+			.unwrap_or(false);
+
+		if is_dir {
+			self.push_dir(&entry);
+		}
+
+		Some(Str::from(entry).into())
+	}
+}
+
+
+#[derive(Finalize)]
+struct WalkImpl(RefCell<WalkState>);
+
+/// WalkImpl has no garbage-collected fields.
+unsafe impl Trace for WalkImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for WalkImpl {
+	fn name(&self) -> &'static str { "std.fs.walk<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let next = self.0.borrow_mut().next();
+
+		let mut iteration = DictMap::default();
+
+		keys::FINISHED.with(
+			|finished| iteration.insert(finished.copy(), next.is_none().into())
+		);
+
+		if let Some(next) = next {
+			keys::VALUE.with(
+				|value| iteration.insert(value.copy(), next)
+			);
+		}
+
+		Ok(Dict::new(iteration).into())
+	}
+}
+
+
+/// Lock a whole file, returning false if it's locked elsewhere and blocking is disabled.
+#[cfg(unix)]
+fn acquire(file: &File, exclusive: bool, block: bool) -> io::Result<bool> {
+	let mut operation = if exclusive { libc::LOCK_EX } else { libc::LOCK_SH };
+	if !block {
+		operation |= libc::LOCK_NB;
+	}
+
+	loop {
+		// Safety: the file descriptor is valid while the file is open.
+		if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+			return Ok(true);
+		}
+
+		let error = io::Error::last_os_error();
+		match error.kind() {
+			io::ErrorKind::Interrupted => continue,
+			io::ErrorKind::WouldBlock => return Ok(false),
+			_ => return Err(error),
+		}
+	}
+}
+
+
+/// Lock a whole file, returning false if it's locked elsewhere and blocking is disabled.
+/// Unlike on Unix, the lock is mandatory for other processes.
+#[cfg(windows)]
+fn acquire(file: &File, exclusive: bool, block: bool) -> io::Result<bool> {
+	use std::os::windows::io::AsRawHandle;
+	use windows_sys::Win32::{
+		Foundation::ERROR_LOCK_VIOLATION,
+		Storage::FileSystem::{LockFileEx, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY},
+		System::IO::OVERLAPPED,
+	};
+
+	let mut flags = 0;
+	if exclusive {
+		flags |= LOCKFILE_EXCLUSIVE_LOCK;
+	}
+	if !block {
+		flags |= LOCKFILE_FAIL_IMMEDIATELY;
+	}
+
+	// Safety: OVERLAPPED is a plain C struct, for which zero is a valid bit pattern. The
+	// zero offset locks from the start of the file.
+	let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+
+	// Safety: the handle is valid while the file is open, and the pointer is valid.
+	let result = unsafe {
+		LockFileEx(file.as_raw_handle() as _, flags, 0, u32::MAX, u32::MAX, &mut overlapped)
+	};
+
+	if result != 0 {
+		return Ok(true);
+	}
+
+	let error = io::Error::last_os_error();
+	if error.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) {
+		Ok(false)
+	} else {
+		Err(error)
+	}
+}
+
+
+/// Acquire an advisory lock on a file, which is created if it doesn't exist. Returns the
+/// lock, which has a release method, or nil if the lock is held elsewhere and blocking is
+/// disabled. The lock is also released when garbage collected.
+fn lock(context: CallContext, block: bool) -> Result<Value, Panic> {
+	let (value, exclusive) = match context.args() {
+		[ value ] => (value, true),
+		[ value, Value::Bool(exclusive) ] => (value, *exclusive),
+
+		[ _, other ] => return Err(Panic::type_error(other.copy(), "bool", context.pos.copy())),
+		args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	};
+
+	let path = path(&context, value)?;
+
+	let file = OpenOptions::new()
+		.read(true)
+		.write(true)
+		.create(true)
+		.open(&path)
+		.and_then(
+			|file| Ok(if acquire(&file, exclusive, block)? { Some(file) } else { None })
+		);
+
+	let handle = |file| {
+		let mut dict = DictMap::default();
+		dict.insert("path".into(), value.copy());
+		dict.insert("release".into(), LockImpl(RefCell::new(Some(file))).into());
+		Value::from(Dict::new(dict))
+	};
+
+	Ok(
+		file
+			.map(|file| file.map(handle))
+			.map_err(|error| io_error(error, value))
+			.into()
+	)
+}
+
+
+/// Acquire a lock, waiting until it's available.
+#[derive(Trace, Finalize)]
+struct Lock;
+
+impl NativeFun for Lock {
+	fn name(&self) -> &'static str { "std.fs.lock" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		lock(context, true)
+	}
+}
+
+
+/// Acquire a lock if it's available, returning nil otherwise.
+#[derive(Trace, Finalize)]
+struct TryLock;
+
+impl NativeFun for TryLock {
+	fn name(&self) -> &'static str { "std.fs.try_lock" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		lock(context, false)
+	}
+}
+
+
+/// Release a lock, by closing the locked file. Releasing twice has no effect.
+#[derive(Finalize)]
+struct LockImpl(RefCell<Option<File>>);
+
+/// LockImpl has no garbage-collected fields.
+unsafe impl Trace for LockImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for LockImpl {
+	fn name(&self) -> &'static str { "std.fs.lock<release>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		self.0.borrow_mut().take();
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Watch files and directories for changes, recursively. Events are dicts with the kind,
+/// which is create, modify, rename, delete or other, and the path. If a function is given,
+/// it's called with each event until it returns false. Otherwise, an iterator over the
+/// events is returned, which waits for events as needed.
+#[derive(Trace, Finalize)]
+struct Watch;
+
+impl Watch {
+	/// Start watching the given paths.
+	fn watcher(context: &CallContext, paths: &Value) -> Result<Result<WatchState, Error>, Panic> {
+		let paths: Vec<Value> = match paths {
+			Value::String(_) => vec![ paths.copy() ],
+			Value::Array(ref array) => array.borrow().iter().map(Value::copy).collect(),
+			other => return Err(Panic::type_error(other.copy(), "string or array", context.pos.copy())),
+		};
+
+		let (sender, receiver) = mpsc::channel();
+
+		let mut watcher = match notify::recommended_watcher(sender) {
+			Ok(watcher) => watcher,
+			Err(error) => return Ok(Err(Error::new(error.to_string().into(), Value::default()))),
+		};
+
+		for value in &paths {
+			let path = path(context, value)?;
+
+			if let Err(error) = watcher.watch(&path, RecursiveMode::Recursive) {
+				return Ok(Err(Error::new(error.to_string().into(), value.copy())));
+			}
+		}
+
+		Ok(Ok(WatchState { _watcher: watcher, receiver, pending: VecDeque::new() }))
+	}
+}
+
+impl NativeFun for Watch {
+	fn name(&self) -> &'static str { "std.fs.watch" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (paths, fun) = match context.args() {
+			[ paths ] => (paths.copy(), None),
+			[ paths, Value::Function(ref fun) ] => (paths.copy(), Some(fun.copy())),
+
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let mut state = match Self::watcher(&context, &paths)? {
+			Ok(state) => state,
+			Err(error) => return Ok(error.into()),
+		};
+
+		let fun = match fun {
+			Some(fun) => fun,
+			None => return Ok(WatchImpl(RefCell::new(state)).into()),
+		};
+
+		loop {
+			let event = match state.next() {
+				Some(event) => event,
+				None => return Ok(Value::default()), // The watcher stopped.
+			};
+
+			let args_start = context.runtime.arguments.len();
+			context.runtime.arguments.push(event);
+
+			if context.call(Value::default(), &fun, args_start)? == Value::Bool(false) {
+				return Ok(Value::default());
+			}
+		}
+	}
+}
+
+
+/// The state of a watch. Events may refer to several paths, so they are queued and
+/// produced one path at a time.
+struct WatchState {
+	/// The watcher, which stops when dropped.
+	_watcher: RecommendedWatcher,
+	receiver: mpsc::Receiver<notify::Result<notify::Event>>,
+	pending: VecDeque<Result<(&'static str, PathBuf), notify::Error>>,
+}
+
+
+impl WatchState {
+	/// Wait for the next event. Access events are ignored.
+	fn next(&mut self) -> Option<Value> {
+		while self.pending.is_empty() {
+			let event = match self.receiver.recv().ok()? {
+				Ok(event) => event,
+				Err(error) => {
+					self.pending.push_back(Err(error));
+					break;
+				}
+			};
+
+			let kind = match event.kind {
+				EventKind::Access(_) => continue,
+				EventKind::Create(_) => "create",
+				EventKind::Modify(ModifyKind::Name(_)) => "rename",
+				EventKind::Modify(_) => "modify",
+				EventKind::Remove(_) => "delete",
+				EventKind::Any | EventKind::Other => "other",
+			};
+
+			self.pending.extend(event.paths.into_iter().map(|path| Ok((kind, path))));
+		}
+
+		let event = match self.pending.pop_front()? {
+			Ok((kind, path)) => {
+				let mut dict = DictMap::default();
+				dict.insert("kind".into(), kind.into());
+				dict.insert("path".into(), Str::from(path).into());
+				Dict::new(dict).into()
+			}
+
+			Err(error) => Error::new(error.to_string().into(), Value::default()).into(),
+		};
+
+		Some(event)
+	}
+}
+
+
+#[derive(Finalize)]
+struct WatchImpl(RefCell<WatchState>);
+
+/// WatchImpl has no garbage-collected fields.
+unsafe impl Trace for WatchImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for WatchImpl {
+	fn name(&self) -> &'static str { "std.fs.watch<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let next = self.0.borrow_mut().next();
+
+		let mut iteration = DictMap::default();
+
+		keys::FINISHED.with(
+			|finished| iteration.insert(finished.copy(), next.is_none().into())
+		);
+
+		if let Some(next) = next {
+			keys::VALUE.with(
+				|value| iteration.insert(value.copy(), next)
+			);
+		}
+
+		Ok(Dict::new(iteration).into())
+	}
+}
@@ -0,0 +1,130 @@
+use std::{
+	cell::Cell,
+	time::{Duration, Instant},
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::{finalize, heap};
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Collect) }
+inventory::submit! { RustFun::from(Stats) }
+inventory::submit! { RustFun::from(OnCollect) }
+
+
+thread_local! {
+	/// The amount of collections forced by std.gc.collect, and the time spent in them.
+	static STATS: Cell<(u64, Duration)> = Cell::new((0, Duration::from_secs(0)));
+}
+
+
+/// Collect garbage right away, including values which are only reachable through
+/// reference cycles, such as a dict which contains itself or a closure which captures
+/// itself. Collection also happens automatically as values are allocated, so this is
+/// only useful to release memory at a specific point, such as between the iterations of
+/// a long running loop. Returns the elapsed time in seconds.
+#[derive(Trace, Finalize)]
+struct Collect;
+
+impl NativeFun for Collect {
+	fn name(&self) -> &'static str { "std.gc.collect" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				// Make sure the collection is counted.
+				heap::arm();
+
+				let start = Instant::now();
+				gc::force_collect();
+				let elapsed = start.elapsed();
+
+				STATS.with(
+					|stats| {
+						let (collections, time) = stats.get();
+						stats.set((collections + 1, time + elapsed));
+					}
+				);
+
+				Ok(elapsed.as_secs_f64().into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// Get statistics of the garbage collector, as a dict with:
+/// - collections: the amount of collections, either automatic or forced.
+/// - forced: the amount of collections forced by std.gc.collect.
+/// - time: the time spent in forced collections, in seconds. Automatic collections
+///   happen within allocations, and aren't timed.
+/// - objects: the amount of arrays, dicts, strings and buffers which are allocated.
+/// - bytes: the amount of bytes allocated for them.
+///
+/// Values which are garbage count as allocated until they are collected.
+#[derive(Trace, Finalize)]
+struct Stats;
+
+impl NativeFun for Stats {
+	fn name(&self) -> &'static str { "std.gc.stats" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				let (forced, time) = STATS.with(Cell::get);
+
+				let mut dict = DictMap::default();
+				dict.insert("collections".into(), Value::Int(heap::collections() as i64));
+				dict.insert("forced".into(), Value::Int(forced as i64));
+				dict.insert("time".into(), time.as_secs_f64().into());
+				dict.insert("objects".into(), Value::Int(heap::objects() as i64));
+				dict.insert("bytes".into(), Value::Int(heap::used() as i64));
+
+				Ok(Dict::new(dict).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// Register a function to be called without arguments after an array or dict is
+/// collected, such as to release the resources associated with it. Finalizers run at the
+/// next function call after the collection, and not at all if the script finishes first.
+/// The function must not capture the value, or it will never be collected.
+#[derive(Trace, Finalize)]
+struct OnCollect;
+
+impl NativeFun for OnCollect {
+	fn name(&self) -> &'static str { "std.gc.on_collect" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (id, finalizer) = match context.args() {
+			[ Value::Array(array), Value::Function(finalizer) ] => (array.id(), finalizer.copy()),
+			[ Value::Dict(dict), Value::Function(finalizer) ] => (dict.id(), finalizer.copy()),
+			[ Value::Array(_), other ] | [ Value::Dict(_), other ] => {
+				return Err(Panic::type_error(other.copy(), "function", context.pos))
+			}
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "array or dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		finalize::on_collect(id, finalizer);
+
+		Ok(Value::Nil)
+	}
+}
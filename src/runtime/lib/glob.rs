@@ -1,10 +1,16 @@
+use std::path::Path;
+
 use gc::{Finalize, Trace};
 
+use crate::runtime::{pattern, Policy, SourcePos};
+
 use super::{
 	CallContext,
+	Dict,
 	RustFun,
 	NativeFun,
 	Panic,
+	Str,
 	Value,
 	Error,
 };
@@ -12,19 +18,75 @@ use super::{
 
 inventory::submit! { RustFun::from(Glob) }
 
+/// Expand a pattern with the same semantics as in commands, returning the sorted array
+/// of matching paths. Relative patterns are expanded in the working directory of
+/// commands, and produce relative paths. The pattern options default to the shell
+/// options, and may be overridden with a dict with `dotfiles`, `case_sensitive` and
+/// `max_depth`.
 #[derive(Trace, Finalize)]
 struct Glob;
 
 impl Glob {
-	fn glob(pattern: &[u8]) -> Result<Value, Error> {
-		let pattern = std::str::from_utf8(pattern).map_err(|_| Error::new("Invalid pattern".into(), Value::default()))?;
-		let paths = glob::glob(pattern).map_err(|error| Error::new("Invalid pattern".into(), error.msg.into()))?;
+	/// Override the shell pattern options.
+	fn options(mut options: pattern::Options, dict: &Dict, pos: SourcePos) -> Result<pattern::Options, Panic> {
+		let option = |name: &str| dict.get(&name.into()).unwrap_or_default();
+
+		match option("dotfiles") {
+			Value::Nil => (),
+			Value::Bool(dotfiles) => options.dotfiles = dotfiles,
+			value => return Err(Panic::type_error(value, "bool", pos)),
+		}
+
+		match option("case_sensitive") {
+			Value::Nil => (),
+			Value::Bool(case_sensitive) => options.case_sensitive = case_sensitive,
+			value => return Err(Panic::type_error(value, "bool", pos)),
+		}
+
+		match option("max_depth") {
+			Value::Nil => (),
+			Value::Int(depth) if (0 ..= u32::MAX as i64).contains(&depth) => options.max_depth = depth as u32,
+			value @ Value::Int(_) => return Err(Panic::value_error(value, "non-negative integer", pos)),
+			value => return Err(Panic::type_error(value, "int", pos)),
+		}
+
+		Ok(options)
+	}
+
+
+	/// Expand the pattern, checking the searched directory and the matches against the
+	/// sandbox policy. Matches are checked as well, as they may be reached through links.
+	fn glob(
+		pattern: &Str,
+		dir: &Path,
+		options: pattern::Options,
+		policy: &Policy,
+		pos: SourcePos,
+	) -> Result<Value, Panic> {
+		let invalid = || Error::new("invalid pattern".into(), Value::String(pattern.copy())).into();
+
+		let pattern = match std::str::from_utf8(pattern.as_bytes()) {
+			Ok(pattern) => pattern,
+			Err(_) => return Ok(invalid()),
+		};
+
+		policy.check_path(&dir.join(pattern::base_dir(pattern)), pos.copy())?;
+
+		let mut paths = match pattern::expand_in(dir, pattern, options) {
+			Ok(paths) => paths,
+			Err(_) => return Ok(invalid()),
+		};
+		paths.sort();
+
+		for path in &paths {
+			policy.check_path(&dir.join(path), pos.copy())?;
+		}
+
 		let paths: Vec<Value> = paths
-			.map(|result| result
-				 .map(|path| Value::String(path.into()))
-				 .map_err(|error| error.into_error().into())
-			)
-			.collect::<Result<_, Error>>()?;
+			.into_iter()
+			.map(|path| Str::from(path).into())
+			.collect();
+
 		Ok(paths.into())
 	}
 }
@@ -33,13 +95,21 @@ impl NativeFun for Glob {
 	fn name(&self) -> &'static str { "std.glob" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
-		match context.args() {
-		    [ Value::String(ref string) ] => {
-				let result = Self::glob(string.as_ref());
-				Ok(result.unwrap_or_else(Into::into))
-			},
-		    [ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-		    args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
-		}
+		let shell_options = context.runtime.options.pattern();
+
+		let (pattern, options) = match context.args() {
+			[ Value::String(ref pattern) ] => (pattern, shell_options),
+			[ Value::String(ref pattern), Value::Dict(ref options) ] => {
+				(pattern, Self::options(shell_options, options, context.pos.copy())?)
+			}
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let dir = context.runtime.options.cwd.as_deref().unwrap_or_else(|| Path::new(""));
+
+		Self::glob(pattern, dir, options, &context.runtime.options.policy, context.pos.copy())
 	}
 }
@@ -0,0 +1,278 @@
+use std::{
+	ffi::OsStr,
+	fs::File,
+	io::{self, Write},
+	path::Path,
+};
+
+use gc::{Finalize, Trace};
+use hmac::{digest::KeyInit, Hmac, Mac};
+use sha2::Digest;
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	CallContext,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Md5) }
+inventory::submit! { RustFun::from(Sha1) }
+inventory::submit! { RustFun::from(Sha256) }
+inventory::submit! { RustFun::from(Sha512) }
+inventory::submit! { RustFun::from(Blake3) }
+inventory::submit! { RustFun::from(HashFile) }
+inventory::submit! { RustFun::from(HashHmac) }
+
+
+/// The supported hash algorithms.
+#[derive(Debug, Clone, Copy)]
+enum Algorithm {
+	Md5,
+	Sha1,
+	Sha256,
+	Sha512,
+	Blake3,
+}
+
+
+impl Algorithm {
+	/// Get the algorithm by name.
+	fn parse(value: &Value, pos: SourcePos) -> Result<Self, Panic> {
+		match value {
+			Value::String(name) => match name.as_bytes() {
+				b"md5" => Ok(Self::Md5),
+				b"sha1" => Ok(Self::Sha1),
+				b"sha256" => Ok(Self::Sha256),
+				b"sha512" => Ok(Self::Sha512),
+				b"blake3" => Ok(Self::Blake3),
+				_ => Err(Panic::value_error(value.copy(), "md5, sha1, sha256, sha512 or blake3", pos)),
+			},
+
+			other => Err(Panic::type_error(other.copy(), "string", pos)),
+		}
+	}
+}
+
+
+/// An incremental hasher for any of the algorithms.
+enum Hasher {
+	Md5(md5::Md5),
+	Sha1(sha1::Sha1),
+	Sha256(sha2::Sha256),
+	Sha512(sha2::Sha512),
+	Blake3(Box<blake3::Hasher>),
+}
+
+
+impl Hasher {
+	fn new(algorithm: Algorithm) -> Self {
+		match algorithm {
+			Algorithm::Md5 => Self::Md5(md5::Md5::new()),
+			Algorithm::Sha1 => Self::Sha1(sha1::Sha1::new()),
+			Algorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+			Algorithm::Sha512 => Self::Sha512(sha2::Sha512::new()),
+			Algorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+		}
+	}
+
+
+	fn update(&mut self, data: &[u8]) {
+		match self {
+			Self::Md5(hasher) => Digest::update(hasher, data),
+			Self::Sha1(hasher) => Digest::update(hasher, data),
+			Self::Sha256(hasher) => Digest::update(hasher, data),
+			Self::Sha512(hasher) => Digest::update(hasher, data),
+			Self::Blake3(hasher) => { hasher.update(data); }
+		}
+	}
+
+
+	/// Get the digest as a lowercase hex string.
+	fn finalize(self) -> String {
+		match self {
+			Self::Md5(hasher) => hex::encode(hasher.finalize()),
+			Self::Sha1(hasher) => hex::encode(hasher.finalize()),
+			Self::Sha256(hasher) => hex::encode(hasher.finalize()),
+			Self::Sha512(hasher) => hex::encode(hasher.finalize()),
+			Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+		}
+	}
+}
+
+
+/// Allows streaming with io::copy.
+impl Write for Hasher {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.update(buf);
+		Ok(buf.len())
+	}
+
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+
+/// Hash a string or byte array.
+fn digest(context: CallContext, algorithm: Algorithm) -> Result<Value, Panic> {
+	match context.args() {
+		[ value ] => match util::bytes(value) {
+			Some(data) => {
+				let mut hasher = Hasher::new(algorithm);
+				hasher.update(&data);
+				Ok(hasher.finalize().into())
+			}
+			None => Err(Panic::type_error(value.copy(), "string or byte array", context.pos)),
+		},
+
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Md5;
+
+impl NativeFun for Md5 {
+	fn name(&self) -> &'static str { "std.hash.md5" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		digest(context, Algorithm::Md5)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Sha1;
+
+impl NativeFun for Sha1 {
+	fn name(&self) -> &'static str { "std.hash.sha1" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		digest(context, Algorithm::Sha1)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Sha256;
+
+impl NativeFun for Sha256 {
+	fn name(&self) -> &'static str { "std.hash.sha256" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		digest(context, Algorithm::Sha256)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Sha512;
+
+impl NativeFun for Sha512 {
+	fn name(&self) -> &'static str { "std.hash.sha512" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		digest(context, Algorithm::Sha512)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Blake3;
+
+impl NativeFun for Blake3 {
+	fn name(&self) -> &'static str { "std.hash.blake3" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		digest(context, Algorithm::Blake3)
+	}
+}
+
+
+/// Hash the contents of a file, which is read in chunks.
+#[derive(Trace, Finalize)]
+struct HashFile;
+
+impl NativeFun for HashFile {
+	fn name(&self) -> &'static str { "std.hash.file" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (algorithm, path) = match context.args() {
+			[ algorithm, Value::String(ref path) ] => (Algorithm::parse(algorithm, context.pos.copy())?, path),
+
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let path = context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path)));
+		context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+
+		let mut hasher = Hasher::new(algorithm);
+
+		File::open(path)
+			.and_then(|mut file| io::copy(&mut file, &mut hasher))
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		Ok(hasher.finalize().into())
+	}
+}
+
+
+/// Keyed message authentication, as used for signing webhook payloads.
+#[derive(Trace, Finalize)]
+struct HashHmac;
+
+impl HashHmac {
+	fn mac<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> String {
+		let mut mac = <M as KeyInit>::new_from_slice(key)
+			.expect("HMAC accepts keys of any length");
+
+		Mac::update(&mut mac, data);
+
+		hex::encode(mac.finalize().into_bytes())
+	}
+}
+
+impl NativeFun for HashHmac {
+	fn name(&self) -> &'static str { "std.hash.hmac" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (algorithm, key, data) = match context.args() {
+			[ algorithm, key, data ] => {
+				let algorithm = Algorithm::parse(algorithm, context.pos.copy())?;
+
+				let key = util::bytes(key)
+					.ok_or_else(|| Panic::type_error(key.copy(), "string or byte array", context.pos.copy()))?;
+
+				let data = util::bytes(data)
+					.ok_or_else(|| Panic::type_error(data.copy(), "string or byte array", context.pos.copy()))?;
+
+				(algorithm, key, data)
+			}
+
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let mac = match algorithm {
+			Algorithm::Md5 => Self::mac::<Hmac<md5::Md5>>(&key, &data),
+			Algorithm::Sha1 => Self::mac::<Hmac<sha1::Sha1>>(&key, &data),
+			Algorithm::Sha256 => Self::mac::<Hmac<sha2::Sha256>>(&key, &data),
+			Algorithm::Sha512 => Self::mac::<Hmac<sha2::Sha512>>(&key, &data),
+
+			// Blake3 has its own keyed mode, which is not HMAC.
+			Algorithm::Blake3 => return Err(
+				Panic::value_error(context.args()[0].copy(), "md5, sha1, sha256 or sha512", context.pos)
+			),
+		};
+
+		Ok(mac.into())
+	}
+}
@@ -0,0 +1,557 @@
+use std::{
+	io::Read,
+	sync::Arc,
+	time::Duration,
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::{Permission, SourcePos};
+
+use super::{
+	util,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Request) }
+inventory::submit! { RustFun::from(Get) }
+inventory::submit! { RustFun::from(Post) }
+inventory::submit! { RustFun::from(Put) }
+inventory::submit! { RustFun::from(Delete) }
+inventory::submit! { RustFun::from(Serve) }
+
+
+/// The options of a request.
+struct Options {
+	/// Header names and values.
+	headers: Vec<(String, String)>,
+	/// Query parameters, which are appended to the url.
+	query: Vec<(String, String)>,
+	/// The request body, from either the body or the json options.
+	body: Option<Vec<u8>>,
+	/// The timeout of the whole request, including the body.
+	timeout: Option<Duration>,
+	/// The maximum number of redirects that are followed.
+	redirects: u32,
+	/// Whether TLS certificates and host names are verified.
+	verify: bool,
+}
+
+
+impl Options {
+	/// Build the options from the optional options dict.
+	fn new(options: Option<&Dict>, pos: SourcePos) -> Result<Self, Panic> {
+		let option = |name: &str| options
+			.and_then(|options| options.get(&name.into()).ok())
+			.unwrap_or_default();
+
+		let mut headers = Self::pairs(option("headers"), pos.copy())?;
+		let query = Self::pairs(option("query"), pos.copy())?;
+
+		let body = match (option("body"), option("json")) {
+			(Value::Nil, Value::Nil) => None,
+
+			(body, Value::Nil) => Some(
+				util::bytes(&body)
+					.ok_or_else(|| Panic::type_error(body.copy(), "string or byte array", pos.copy()))?
+					.into_owned()
+			),
+
+			(Value::Nil, json) => {
+				let body = serde_json::to_vec(&json)
+					.map_err(
+						|_| Panic::value_error(
+							json.copy(),
+							"nil, bool, byte, int, float, string, array or dict",
+							pos.copy()
+						)
+					)?;
+
+				let has_content_type = headers
+					.iter()
+					.any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+
+				if !has_content_type {
+					headers.push(("Content-Type".into(), "application/json".into()));
+				}
+
+				Some(body)
+			}
+
+			(_, json) => return Err(Panic::value_error(json, "either body or json", pos)),
+		};
+
+		let timeout = match option("timeout") {
+			Value::Nil => None,
+			value => Some(util::duration(&value, pos.copy())?),
+		};
+
+		let redirects = match option("redirects") {
+			Value::Nil => 5,
+			Value::Int(redirects) if (0 ..= u32::MAX as i64).contains(&redirects) => redirects as u32,
+			value @ Value::Int(_) => return Err(Panic::value_error(value, "non-negative integer", pos)),
+			value => return Err(Panic::type_error(value, "int", pos)),
+		};
+
+		let verify = match option("verify") {
+			Value::Nil => true,
+			Value::Bool(verify) => verify,
+			value => return Err(Panic::type_error(value, "bool", pos)),
+		};
+
+		Ok(Self { headers, query, body, timeout, redirects, verify })
+	}
+
+
+	/// Get the name and value pairs of a dict. Values may be strings or numbers.
+	fn pairs(value: Value, pos: SourcePos) -> Result<Vec<(String, String)>, Panic> {
+		let dict = match value {
+			Value::Nil => return Ok(Vec::new()),
+			Value::Dict(ref dict) => dict.copy(),
+			value => return Err(Panic::type_error(value, "dict", pos)),
+		};
+
+		let text = |value: &Value| match value {
+			Value::String(string) => std::str::from_utf8(string.as_bytes())
+				.map(ToOwned::to_owned)
+				.map_err(|_| Panic::value_error(value.copy(), "valid utf-8", pos.copy())),
+			Value::Int(int) => Ok(int.to_string()),
+			Value::Float(float) => Ok(float.to_string()),
+			Value::Bool(flag) => Ok(flag.to_string()),
+			other => Err(Panic::type_error(other.copy(), "string or number", pos.copy())),
+		};
+
+		let mut pairs = dict
+			.borrow()
+			.iter()
+			.map(|(name, value)| Ok((text(name)?, text(value)?)))
+			.collect::<Result<Vec<_>, Panic>>()?;
+
+		// Dicts are unordered, so sort for a deterministic request.
+		pairs.sort();
+
+		Ok(pairs)
+	}
+
+
+	fn agent(&self) -> Result<ureq::Agent, native_tls::Error> {
+		let connector = native_tls::TlsConnector::builder()
+			.danger_accept_invalid_certs(!self.verify)
+			.danger_accept_invalid_hostnames(!self.verify)
+			.build()?;
+
+		let mut agent = ureq::AgentBuilder::new()
+			.redirects(self.redirects)
+			.tls_connector(Arc::new(connector));
+
+		if let Some(timeout) = self.timeout {
+			agent = agent.timeout(timeout);
+		}
+
+		Ok(agent.build())
+	}
+}
+
+
+/// Convert a response to a dict with the status, headers, body and final url. Header
+/// names are lowercase, and repeated headers are joined with commas.
+fn response(response: ureq::Response) -> std::io::Result<Value> {
+	let status = response.status();
+	let url = response.get_url().to_owned();
+
+	let mut headers = DictMap::default();
+	for name in response.headers_names() {
+		let value = response.all(&name).join(", ");
+		headers.insert(Value::from(name.to_ascii_lowercase()), Value::from(value));
+	}
+
+	let mut body = Vec::new();
+	response.into_reader().read_to_end(&mut body)?;
+	let body: Box<[u8]> = body.into_boxed_slice();
+
+	let fields: [(&str, Value); 5] = [
+		("status", Value::Int(status.into())),
+		("url", url.into()),
+		("headers", Dict::new(headers).into()),
+		("body", Str::from(&body[..]).into()),
+		("json", JsonImpl(body).into()),
+	];
+
+	let dict: DictMap = IntoIterator::into_iter(fields)
+		.map(|(name, field)| (name.into(), field))
+		.collect();
+
+	Ok(Dict::new(dict).into())
+}
+
+
+/// Perform a request. Responses with error statuses are not errors, but failures to
+/// connect or to read the response are.
+fn request(method: &str, url: &Value, options: Options) -> Value {
+	let url_str = match url {
+		Value::String(url) => String::from_utf8_lossy(url.as_bytes()).into_owned(),
+		_ => unreachable!("url must be a string"),
+	};
+
+	let error = |description: String| -> Value {
+		Error::new(description.into(), url.copy()).into()
+	};
+
+	let agent = match options.agent() {
+		Ok(agent) => agent,
+		Err(err) => return error(err.to_string()),
+	};
+
+	let mut request = agent.request(method, &url_str);
+
+	for (name, value) in &options.query {
+		request = request.query(name, value);
+	}
+
+	for (name, value) in &options.headers {
+		request = request.set(name, value);
+	}
+
+	let result = match &options.body {
+		Some(body) => request.send_bytes(body),
+		None => request.call(),
+	};
+
+	match result {
+		Ok(response) | Err(ureq::Error::Status(_, response)) => self::response(response)
+			.unwrap_or_else(|err| error(err.to_string())),
+
+		Err(ureq::Error::Transport(transport)) => error(transport.to_string()),
+	}
+}
+
+
+/// Parse the arguments of a request with a fixed method, which are the url and the
+/// optional options.
+fn method(context: CallContext, method: &str) -> Result<Value, Panic> {
+	let (url, options) = match context.args() {
+		[ url @ Value::String(_) ] => (url, None),
+		[ url @ Value::String(_), Value::Dict(ref options) ] => (url, Some(options)),
+
+		[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos.copy())),
+		[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	};
+
+	let mut options = Options::new(options, context.pos.copy())?;
+	check_url(&context, url, &mut options)?;
+
+	Ok(request(method, url, options))
+}
+
+
+/// Panic unless the sandbox policy allows requests to the host of the url. Under a
+/// restricted policy, redirects are not followed, as they could lead to other hosts.
+fn check_url(context: &CallContext, url: &Value, options: &mut Options) -> Result<(), Panic> {
+	let policy = &context.runtime.options.policy;
+
+	if let Permission::Allow = policy.network {
+		return Ok(());
+	}
+
+	let host = match url {
+		Value::String(url) => url::Url::parse(&String::from_utf8_lossy(url.as_bytes()))
+			.ok()
+			.and_then(|url| url.host_str().map(|host| host.trim_matches(&['[', ']'][..]).to_owned()))
+			.unwrap_or_default(),
+		_ => unreachable!("url must be a string"),
+	};
+
+	options.redirects = 0;
+
+	policy.check_host(&host, context.pos.copy())
+}
+
+
+/// Perform an HTTP request with the given method. The options are `headers` and `query`
+/// (dicts), `body` (string or byte array), `json` (any value, which is encoded as the
+/// body), `timeout` (seconds), `redirects` (maximum number followed, 5 by default) and
+/// `verify` (whether TLS certificates are verified, true by default).
+#[derive(Trace, Finalize)]
+struct Request;
+
+impl NativeFun for Request {
+	fn name(&self) -> &'static str { "std.http.request" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (method, url, options) = match context.args() {
+			[ Value::String(ref method), url @ Value::String(_) ] => (method, url, None),
+			[ Value::String(ref method), url @ Value::String(_), Value::Dict(ref options) ] => (method, url, Some(options)),
+
+			[ Value::String(_), Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ Value::String(_), other ] | [ Value::String(_), other, _ ] => {
+				return Err(Panic::type_error(other.copy(), "string", context.pos))
+			}
+			[ other, _ ] | [ other, _, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let method = std::str::from_utf8(method.as_bytes())
+			.ok()
+			.filter(|method| !method.is_empty() && method.bytes().all(|byte| byte.is_ascii_alphabetic()))
+			.ok_or_else(|| Panic::value_error(Value::String(method.copy()), "http method", context.pos.copy()))?
+			.to_ascii_uppercase();
+
+		let mut options = Options::new(options, context.pos.copy())?;
+		check_url(&context, url, &mut options)?;
+
+		Ok(request(&method, url, options))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Get;
+
+impl NativeFun for Get {
+	fn name(&self) -> &'static str { "std.http.get" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		method(context, "GET")
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Post;
+
+impl NativeFun for Post {
+	fn name(&self) -> &'static str { "std.http.post" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		method(context, "POST")
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Put;
+
+impl NativeFun for Put {
+	fn name(&self) -> &'static str { "std.http.put" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		method(context, "PUT")
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Delete;
+
+impl NativeFun for Delete {
+	fn name(&self) -> &'static str { "std.http.delete" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		method(context, "DELETE")
+	}
+}
+
+
+/// Serve HTTP requests on an address such as `127.0.0.1:8080`, one at a time. The handler
+/// receives a request dict with the method, path, query (dict), headers (dict, with
+/// lowercase names), body and remote address. It returns either a string, which is sent
+/// with status 200, nil, which is sent as an empty 204 response, or a response dict with
+/// `status`, `headers`, and either `body` or `json`. Serving stops when the response dict
+/// has `stop` set to true, or when the handler panics.
+#[derive(Trace, Finalize)]
+struct Serve;
+
+impl Serve {
+	/// Convert a request to a dict, reading it's body.
+	fn request(request: &mut tiny_http::Request) -> std::io::Result<Value> {
+		let url = request.url().to_owned();
+		let (path, query) = match url.split_once('?') {
+			Some((path, query)) => (path, query),
+			None => (url.as_str(), ""),
+		};
+
+		let query: DictMap = url::form_urlencoded::parse(query.as_bytes())
+			.map(|(name, value)| (Value::from(&*name), Value::from(&*value)))
+			.collect();
+
+		let mut headers: DictMap = DictMap::default();
+		for header in request.headers() {
+			let name = Value::from(header.field.as_str().as_str().to_ascii_lowercase());
+			let value = header.value.as_str();
+
+			// Repeated headers are joined with commas, as in responses of std.http.request.
+			let value = match headers.get(&name) {
+				Some(Value::String(previous)) => format!("{}, {}", String::from_utf8_lossy(previous.as_bytes()), value),
+				_ => value.to_owned(),
+			};
+
+			headers.insert(name, value.into());
+		}
+
+		let remote = request
+			.remote_addr()
+			.map(|address| address.to_string());
+
+		let mut body = Vec::new();
+		request.as_reader().read_to_end(&mut body)?;
+
+		let fields: [(&str, Value); 6] = [
+			("method", request.method().as_str().into()),
+			("path", path.into()),
+			("query", Dict::new(query).into()),
+			("headers", Dict::new(headers).into()),
+			("body", Str::from(body).into()),
+			("remote", remote.into()),
+		];
+
+		let dict: DictMap = IntoIterator::into_iter(fields)
+			.map(|(name, field)| (name.into(), field))
+			.collect();
+
+		Ok(Dict::new(dict).into())
+	}
+
+
+	/// Convert the result of the handler to a response, and whether to stop serving.
+	fn response(value: Value, pos: SourcePos) -> Result<(tiny_http::Response<std::io::Cursor<Vec<u8>>>, bool), Panic> {
+		let dict = match value {
+			Value::Nil => return Ok((tiny_http::Response::from_data(Vec::new()).with_status_code(204), false)),
+			Value::String(ref body) => return Ok((tiny_http::Response::from_data(body.as_bytes().to_vec()), false)),
+			Value::Dict(ref dict) => dict.copy(),
+			value => return Err(Panic::type_error(value, "nil, string or dict", pos)),
+		};
+
+		let option = |name: &str| dict.get(&name.into()).unwrap_or_default();
+
+		let status = match option("status") {
+			Value::Nil => 200,
+			Value::Int(status) if (100 ..= 999).contains(&status) => status as u16,
+			value @ Value::Int(_) => return Err(Panic::value_error(value, "http status", pos)),
+			value => return Err(Panic::type_error(value, "int", pos)),
+		};
+
+		let stop = match option("stop") {
+			Value::Nil => false,
+			Value::Bool(stop) => stop,
+			value => return Err(Panic::type_error(value, "bool", pos)),
+		};
+
+		// The response options are the same as the request ones.
+		let options = Options::new(Some(&dict), pos.copy())?;
+
+		let mut response = tiny_http::Response::from_data(options.body.unwrap_or_default())
+			.with_status_code(status);
+
+		for (name, value) in options.headers {
+			let header = tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes())
+				.map_err(|()| Panic::value_error(Value::from(name), "http header", pos.copy()))?;
+
+			response.add_header(header);
+		}
+
+		Ok((response, stop))
+	}
+}
+
+impl NativeFun for Serve {
+	fn name(&self) -> &'static str { "std.http.serve" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (address, handler) = match context.args() {
+			[ address @ Value::String(_), Value::Function(ref handler) ] => (address.copy(), handler.copy()),
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		if let Value::String(string) = &address {
+			context.runtime.options.policy.check_address(&String::from_utf8_lossy(string.as_bytes()), context.pos.copy())?;
+		}
+
+		let server = match &address {
+			Value::String(string) => std::str::from_utf8(string.as_bytes())
+				.map_err(|error| error.to_string())
+				.and_then(|string| tiny_http::Server::http(string).map_err(|error| error.to_string())),
+			_ => unreachable!("address must be a string"),
+		};
+
+		let server = match server {
+			Ok(server) => server,
+			Err(error) => return Ok(Error::new(error.into(), address).into()),
+		};
+
+		loop {
+			let mut request = match server.recv() {
+				Ok(request) => request,
+				Err(error) => return Ok(Error::new(error.to_string().into(), address).into()),
+			};
+
+			// Requests whose body fails to be read are dropped.
+			let value = match Self::request(&mut request) {
+				Ok(value) => value,
+				Err(_) => continue,
+			};
+
+			let args_start = context.runtime.arguments.len();
+			context.runtime.arguments.push(value);
+
+			let result = context
+				.call(Value::default(), &handler, args_start)
+				.and_then(|value| Self::response(value, context.pos.copy()));
+
+			let (response, stop) = match result {
+				Ok(response) => response,
+				Err(panic) => {
+					let _ = request.respond(tiny_http::Response::empty(500));
+					return Err(panic);
+				}
+			};
+
+			// Failing to respond only affects the client.
+			let _ = request.respond(response);
+
+			if stop {
+				return Ok(Value::default());
+			}
+		}
+	}
+}
+
+
+/// Decode the body of a response as JSON.
+#[derive(Finalize)]
+struct JsonImpl(Box<[u8]>);
+
+/// JsonImpl has no garbage-collected fields.
+unsafe impl Trace for JsonImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for JsonImpl {
+	fn name(&self) -> &'static str { "std.http.response<json>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			serde_json::from_slice(&self.0)
+				.unwrap_or_else(
+					|error| Error::new(error.to_string().into(), Value::default()).into()
+				)
+		)
+	}
+}
@@ -1,13 +1,12 @@
 use std::{
 	io,
 	path::{Path, PathBuf},
-	ffi::OsStr,
-	os::unix::ffi::OsStrExt,
 };
 
 use gc::{Finalize, Trace};
 
 use crate::{
+	ffi::{self, OsStrExt},
 	fmt,
 	syntax,
 	semantic,
@@ -42,6 +41,15 @@ impl Import {
 		match context.runtime.modules.get(&path) {
 			Some(module) => Ok(module.copy()), // Don't reload module if cached.
 			None => {
+				let resolved = context.runtime.interner()
+					.resolve(path)
+					.expect("failed to resolve symbol");
+
+				context.runtime.options.policy.check_path(
+					Path::new(&ffi::os_str(resolved)),
+					context.pos.copy(),
+				)?;
+
 				let module = Self::load(path, &mut context)?;
 				context.runtime.modules.insert(path, module.copy());
 				Ok(module)
@@ -56,11 +64,11 @@ impl Import {
 		interner: &mut symbol::Interner,
 	) -> io::Result<Symbol> {
 		let mut path_buf = PathBuf::from(
-			OsStr::from_bytes(
+			ffi::os_str(
 				interner
 					.resolve(current_path)
 					.expect("failed to resolve symbol")
-			).to_owned()
+			).into_owned()
 		);
 		path_buf.pop(); // Remove the file name.
 		path_buf.push(target_path);
@@ -88,9 +96,11 @@ impl Import {
 			)?;
 
 		// Syntax.
+		let arena = syntax::Arena::new();
 		let syntactic_analysis = syntax::Analysis::analyze(
 			&source,
-			context.runtime.interner_mut()
+			context.runtime.interner_mut(),
+			&arena,
 		);
 		let has_syntax_errors = !syntactic_analysis.is_ok();
 
@@ -124,6 +134,9 @@ impl Import {
 				}
 			)?;
 
+		// The program doesn't refer to the AST, which may then be released before running.
+		drop(arena);
+
 		// Eval.
 		let program = Box::leak(Box::new(program));
 		context.runtime.eval(program)
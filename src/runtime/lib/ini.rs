@@ -0,0 +1,111 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+
+
+/// Remove matching surrounding quotes from a value.
+fn unquote(value: &str) -> &str {
+	for quote in [ '"', '\'' ] {
+		if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+			return &value[1 .. value.len() - 1];
+		}
+	}
+
+	value
+}
+
+
+/// Parse an INI file into a dict. Keys before the first section are placed in the top
+/// level, and each section is a nested dict. Values are strings, with surrounding
+/// whitespace and quotes removed. Keys may be separated from values by `=` or `:`, and
+/// lines starting with `;` or `#` are comments. Repeated sections are merged, and later
+/// keys replace earlier ones. Malformed lines produce an error value with the line number.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl Parse {
+	fn parse(ini: &str) -> Result<Value, Value> {
+		let mut root: DictMap = DictMap::default();
+		let mut sections: Vec<(String, DictMap)> = Vec::new();
+
+		for (number, line) in ini.lines().enumerate() {
+			let line = line.trim();
+
+			if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+				continue;
+			}
+
+			let error = |description: &str| -> Value {
+				Error::new(description.into(), Value::Int(number as i64 + 1)).into()
+			};
+
+			if let Some(section) = line.strip_prefix('[') {
+				let name = section
+					.strip_suffix(']')
+					.map(str::trim)
+					.filter(|name| !name.is_empty())
+					.ok_or_else(|| error("invalid section header"))?;
+
+				sections.push((name.to_owned(), DictMap::default()));
+				continue;
+			}
+
+			let (key, value) = line
+				.split_once(|c| c == '=' || c == ':')
+				.map(|(key, value)| (key.trim(), unquote(value.trim())))
+				.filter(|(key, _)| !key.is_empty())
+				.ok_or_else(|| error("expected key and value"))?;
+
+			let entries = match sections.last_mut() {
+				Some((_, entries)) => entries,
+				None => &mut root,
+			};
+
+			entries.insert(key.into(), value.into());
+		}
+
+		for (name, entries) in sections {
+			let name: Value = name.into();
+
+			match root.get(&name) {
+				Some(Value::Dict(section)) => section.borrow_mut().extend(entries),
+
+				_ => {
+					root.insert(name, Dict::new(entries).into());
+				}
+			}
+		}
+
+		Ok(Dict::new(root).into())
+	}
+}
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.ini.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::String(ref string) ] => {
+				let ini = std::str::from_utf8(string.as_bytes())
+					.map_err(|_| Panic::value_error(value.copy(), "valid utf-8", context.pos.copy()))?;
+
+				Ok(Self::parse(ini).unwrap_or_else(|error| error))
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
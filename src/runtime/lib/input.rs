@@ -0,0 +1,322 @@
+use std::io::{self, BufRead, Write};
+#[cfg(unix)]
+use std::mem;
+
+use gc::{Finalize, Trace};
+
+use crate::fmt::FmtString;
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Line) }
+inventory::submit! { RustFun::from(Password) }
+inventory::submit! { RustFun::from(Confirm) }
+inventory::submit! { RustFun::from(Select) }
+
+
+/// Write a prompt to stderr, so that it's shown even if the script's output is redirected.
+fn prompt(prompt: &[u8]) -> io::Result<()> {
+	let stderr = io::stderr();
+	let mut stderr = stderr.lock();
+
+	stderr.write_all(prompt)?;
+	stderr.flush()
+}
+
+
+/// Read a line from stdin, without the line terminator. Returns None on end of file.
+fn read_line() -> io::Result<Option<String>> {
+	let mut line = String::new();
+
+	if io::stdin().lock().read_line(&mut line)? == 0 {
+		return Ok(None);
+	}
+
+	if line.ends_with('\n') {
+		line.pop();
+		if line.ends_with('\r') {
+			line.pop();
+		}
+	}
+
+	Ok(Some(line))
+}
+
+
+/// Get the optional prompt argument.
+fn prompt_arg<'a>(context: &'a CallContext) -> Result<&'a [u8], Panic> {
+	match context.args() {
+		[] => Ok(b""),
+		[ Value::String(ref string) ] => Ok(string.as_bytes()),
+
+		[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+	}
+}
+
+
+/// Disables echoing of stdin while alive. Nothing is done if stdin is not a terminal.
+#[cfg(unix)]
+struct NoEcho(Option<libc::termios>);
+
+
+#[cfg(unix)]
+impl NoEcho {
+	fn new() -> io::Result<Self> {
+		// Safety: isatty, tcgetattr and tcsetattr only access the given termios struct,
+		// which is valid when zeroed.
+		unsafe {
+			if libc::isatty(libc::STDIN_FILENO) == 0 {
+				return Ok(Self(None));
+			}
+
+			let mut termios: libc::termios = mem::zeroed();
+			if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) < 0 {
+				return Err(io::Error::last_os_error());
+			}
+
+			// Keep echoing the newline, so that the cursor moves past the prompt.
+			let mut silent = termios;
+			silent.c_lflag &= !libc::ECHO;
+			silent.c_lflag |= libc::ECHONL;
+
+			if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &silent) < 0 {
+				return Err(io::Error::last_os_error());
+			}
+
+			Ok(Self(Some(termios)))
+		}
+	}
+}
+
+
+#[cfg(unix)]
+impl Drop for NoEcho {
+	fn drop(&mut self) {
+		if let Some(termios) = &self.0 {
+			// Safety: see NoEcho::new.
+			unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, termios) };
+		}
+	}
+}
+
+
+/// Disables echoing of stdin while alive, through the console mode. Nothing is done if
+/// stdin is not a console.
+#[cfg(windows)]
+struct NoEcho(Option<u32>);
+
+
+#[cfg(windows)]
+impl NoEcho {
+	fn new() -> io::Result<Self> {
+		use windows_sys::Win32::System::Console::{
+			GetConsoleMode,
+			GetStdHandle,
+			SetConsoleMode,
+			ENABLE_ECHO_INPUT,
+			STD_INPUT_HANDLE,
+		};
+
+		let mut mode = 0;
+
+		// Safety: the pointer is valid. GetConsoleMode fails if stdin is not a console.
+		unsafe {
+			let stdin = GetStdHandle(STD_INPUT_HANDLE);
+
+			if GetConsoleMode(stdin, &mut mode) == 0 {
+				return Ok(Self(None));
+			}
+
+			if SetConsoleMode(stdin, mode & !ENABLE_ECHO_INPUT) == 0 {
+				return Err(io::Error::last_os_error());
+			}
+		}
+
+		Ok(Self(Some(mode)))
+	}
+}
+
+
+#[cfg(windows)]
+impl Drop for NoEcho {
+	fn drop(&mut self) {
+		use windows_sys::Win32::System::Console::{GetStdHandle, SetConsoleMode, STD_INPUT_HANDLE};
+
+		if let Some(mode) = self.0 {
+			// Safety: SetConsoleMode has no memory safety requirements.
+			unsafe { SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), mode) };
+		}
+	}
+}
+
+
+/// Prompt for a line of input. Returns the line without the line terminator, or nil on end
+/// of input.
+#[derive(Trace, Finalize)]
+struct Line;
+
+impl NativeFun for Line {
+	fn name(&self) -> &'static str { "std.input.line" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let text = prompt_arg(&context)?;
+
+		prompt(text)
+			.and_then(|()| read_line())
+			.map(Value::from)
+			.map_err(|error| Panic::io(error, context.pos))
+	}
+}
+
+
+/// Prompt for a password, which is not echoed if stdin is a terminal. Returns nil on end
+/// of input.
+#[derive(Trace, Finalize)]
+struct Password;
+
+impl NativeFun for Password {
+	fn name(&self) -> &'static str { "std.input.password" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let text = prompt_arg(&context)?;
+
+		prompt(text)
+			.and_then(|()| NoEcho::new())
+			.and_then(
+				|no_echo| {
+					let line = read_line();
+					drop(no_echo);
+					line
+				}
+			)
+			.map(Value::from)
+			.map_err(|error| Panic::io(error, context.pos))
+	}
+}
+
+
+/// Ask a yes or no question, until a valid answer is given. If a default is given, an
+/// empty answer selects it. On end of input, returns the default, or false if none.
+#[derive(Trace, Finalize)]
+struct Confirm;
+
+impl Confirm {
+	fn confirm(text: &[u8], default: Option<bool>) -> io::Result<bool> {
+		let hint: &[u8] = match default {
+			None => b" [y/n] ",
+			Some(true) => b" [Y/n] ",
+			Some(false) => b" [y/N] ",
+		};
+
+		loop {
+			prompt(&[ text, hint ].concat())?;
+
+			let answer = match read_line()? {
+				Some(answer) => answer.trim().to_lowercase(),
+				None => return Ok(default.unwrap_or(false)),
+			};
+
+			match (answer.as_str(), default) {
+				("y" | "yes", _) => return Ok(true),
+				("n" | "no", _) => return Ok(false),
+				("", Some(default)) => return Ok(default),
+				_ => continue,
+			}
+		}
+	}
+}
+
+impl NativeFun for Confirm {
+	fn name(&self) -> &'static str { "std.input.confirm" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (text, default) = match context.args() {
+			[ Value::String(ref string) ] => (string.as_bytes(), None),
+			[ Value::String(ref string), Value::Bool(default) ] => (string.as_bytes(), Some(*default)),
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "bool", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		Self::confirm(text, default)
+			.map(Value::from)
+			.map_err(|error| Panic::io(error, context.pos))
+	}
+}
+
+
+/// Show a numbered menu of options, and ask for one of them until a valid number is given.
+/// Returns the selected option, or nil on end of input.
+#[derive(Trace, Finalize)]
+struct Select;
+
+impl Select {
+	fn select(options: &[String], text: &[u8]) -> io::Result<Option<usize>> {
+		let mut menu = Vec::new();
+		for (ix, option) in options.iter().enumerate() {
+			writeln!(menu, "{:>3}) {}", ix + 1, option)?;
+		}
+		menu.extend_from_slice(text);
+
+		loop {
+			prompt(&menu)?;
+
+			let answer = match read_line()? {
+				Some(answer) => answer,
+				None => return Ok(None),
+			};
+
+			match answer.trim().parse::<usize>() {
+				Ok(number) if (1 ..= options.len()).contains(&number) => return Ok(Some(number - 1)),
+				_ => continue,
+			}
+		}
+	}
+}
+
+impl NativeFun for Select {
+	fn name(&self) -> &'static str { "std.input.select" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (options, text) = match context.args() {
+			[ Value::Array(ref options) ] => (options.copy(), &b"> "[..]),
+			[ Value::Array(ref options), Value::String(ref string) ] => (options.copy(), string.as_bytes()),
+
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		if options.is_empty() {
+			return Err(Panic::value_error(Value::Array(options), "non-empty array", context.pos));
+		}
+
+		let options = options.borrow();
+
+		let labels: Vec<String> = options
+			.iter()
+			.map(
+				|option| match option {
+					Value::String(string) => String::from_utf8_lossy(string.as_bytes()).into_owned(),
+					option => option.fmt_string(context.interner()),
+				}
+			)
+			.collect();
+
+		Ok(
+			Self::select(&labels, text)
+				.map_err(|error| Panic::io(error, context.pos.copy()))?
+				.map(|ix| options[ix].copy())
+				.unwrap_or_default()
+		)
+	}
+}
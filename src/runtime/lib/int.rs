@@ -1,19 +1,45 @@
 use gc::{Finalize, Trace};
 
+use crate::runtime::SourcePos;
+
 use super::{
 	CallContext,
 	NativeFun,
 	RustFun,
 	Panic,
+	Str,
 	Value,
 };
 
 
 inventory::submit!{ RustFun::from(Int) }
 
+/// Convert a value to an int. Floats are truncated towards zero, and must be finite and
+/// within the int range. Strings are parsed in the given radix, which defaults to 10 and
+/// must be between 2 and 36.
 #[derive(Trace, Finalize)]
 struct Int;
 
+impl Int {
+	fn parse(value: &Value, string: &Str, radix: u32, pos: SourcePos) -> Result<Value, Panic> {
+		let parse_error = || Panic::value_error(
+			value.copy(),
+			"valid integer",
+			pos.copy()
+		);
+
+		let slice = std::str
+			::from_utf8(string.as_bytes())
+			.map_err(|_| parse_error())?;
+
+		let int = i64
+			::from_str_radix(slice, radix)
+			.map_err(|_| parse_error())?;
+
+		Ok(Value::from(int))
+	}
+}
+
 impl NativeFun for Int {
 	fn name(&self) -> &'static str { "std.int" }
 
@@ -23,30 +49,28 @@ impl NativeFun for Int {
 				Value::Int(*i)
 			),
 
-			[ Value::Float(f) ] => Ok(
+			// The range check is exclusive, as the upper bound is not representable.
+			[ Value::Float(f) ] if f.0 >= i64::MIN as f64 && f.0 < i64::MAX as f64 => Ok(
 				Value::Int(f.into())
 			),
 
-			[ value @ Value::String(ref string) ] => {
-				let parse_error = || Panic::value_error(
-					value.copy(),
-					"valid integer",
-					context.pos.copy()
-				);
-
-				let slice = std::str
-					::from_utf8(string.as_bytes())
-					.map_err(|_| parse_error())?;
+			[ value @ Value::Float(_) ] => Err(
+				Panic::value_error(value.copy(), "finite float within int range", context.pos)
+			),
 
-				let int: i64 = slice
-					.parse()
-					.map_err(|_| parse_error())?;
+			[ value @ Value::String(ref string) ] => Self::parse(value, string, 10, context.pos.copy()),
 
-				Ok(Value::from(int))
+			[ value @ Value::String(ref string), Value::Int(radix) ] if (2 ..= 36).contains(radix) => {
+				Self::parse(value, string, *radix as u32, context.pos.copy())
 			}
 
-			[ other ] => Err(Panic::type_error(other.copy(), "int, float or string", context.pos)),
-			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+			[ Value::String(_), radix @ Value::Int(_) ] => Err(
+				Panic::value_error(radix.copy(), "radix between 2 and 36", context.pos)
+			),
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ other ] | [ other, _ ] => Err(Panic::type_error(other.copy(), "int, float or string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
 	}
 }
@@ -0,0 +1,317 @@
+use std::{
+	cell::RefCell,
+	ffi::OsStr,
+	fs::{File, OpenOptions},
+	io::{self, BufRead, BufReader, Read as _, Seek as _, SeekFrom, Write as _},
+	path::Path,
+	rc::Rc,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	util,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Open) }
+
+
+/// An open file, shared by the methods of a handle. The file is closed when all methods
+/// are garbage collected, or when explicitly closed.
+type Handle = Rc<RefCell<Option<BufReader<File>>>>;
+
+
+/// Run an operation on an open handle.
+fn with_file<T, F>(handle: &Handle, operation: F) -> io::Result<T>
+where
+	F: FnOnce(&mut BufReader<File>) -> io::Result<T>,
+{
+	match handle.borrow_mut().as_mut() {
+		Some(file) => operation(file),
+		None => Err(io::Error::new(io::ErrorKind::Other, "file is closed")),
+	}
+}
+
+
+/// Open a file, returning a handle with methods. The mode is as in fopen: r, w, a, r+, w+
+/// or a+, and defaults to r.
+#[derive(Trace, Finalize)]
+struct Open;
+
+impl Open {
+	fn options(mode: &[u8]) -> Option<OpenOptions> {
+		let mut options = OpenOptions::new();
+
+		match mode {
+			b"r" => options.read(true),
+			b"w" => options.write(true).create(true).truncate(true),
+			b"a" => options.append(true).create(true),
+			b"r+" => options.read(true).write(true),
+			b"w+" => options.read(true).write(true).create(true).truncate(true),
+			b"a+" => options.read(true).append(true).create(true),
+			_ => return None,
+		};
+
+		Some(options)
+	}
+
+
+	fn handle(file: File) -> Value {
+		let handle: Handle = Rc::new(RefCell::new(Some(BufReader::new(file))));
+
+		let methods: [(&str, Value); 6] = [
+			("read", ReadImpl(handle.clone()).into()),
+			("read_line", ReadLineImpl(handle.clone()).into()),
+			("write", WriteImpl(handle.clone()).into()),
+			("seek", SeekImpl(handle.clone()).into()),
+			("flush", FlushImpl(handle.clone()).into()),
+			("close", CloseImpl(handle).into()),
+		];
+
+		let dict: DictMap = IntoIterator::into_iter(methods)
+			.map(|(name, method)| (name.into(), method))
+			.collect();
+
+		Dict::new(dict).into()
+	}
+}
+
+impl NativeFun for Open {
+	fn name(&self) -> &'static str { "std.io.open" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, mode) = match context.args() {
+			[ value @ Value::String(_) ] => (value, None),
+			[ value @ Value::String(_), Value::String(ref mode) ] => (value, Some(mode)),
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let options = match mode {
+			None => Self::options(b"r"),
+			Some(mode) => Self::options(mode.as_bytes()),
+		};
+
+		let options = options.ok_or_else(
+			|| Panic::value_error(
+				mode.map(|mode| Value::String(mode.copy())).unwrap_or_default(),
+				"r, w, a, r+, w+ or a+",
+				context.pos.copy()
+			)
+		)?;
+
+		let path = match value {
+			Value::String(path) => context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path))),
+			_ => unreachable!("path must be a string"),
+		};
+
+		context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+
+		Ok(
+			options
+				.open(path)
+				.map(Self::handle)
+				.map_err(|error| Error::new(error.to_string().into(), value.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// Read up to the given number of bytes, or all remaining bytes if no count is given.
+/// Returns nil at the end of the file.
+#[derive(Finalize)]
+struct ReadImpl(Handle);
+
+impl NativeFun for ReadImpl {
+	fn name(&self) -> &'static str { "std.io.open<read>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let count = match context.args() {
+			[] => None,
+			[ Value::Int(count) ] if *count >= 0 => Some(*count as u64),
+
+			[ count @ Value::Int(_) ] => return Err(Panic::value_error(count.copy(), "non-negative integer", context.pos)),
+			[ other ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = with_file(
+			&self.0,
+			|file| {
+				let mut buffer = Vec::new();
+
+				let read = match count {
+					Some(count) => file.by_ref().take(count).read_to_end(&mut buffer)?,
+					None => file.read_to_end(&mut buffer)?,
+				};
+
+				let at_end = read == 0 && count != Some(0);
+
+				Ok(if at_end { None } else { Some(Str::from(buffer)) })
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Read a line, without the line terminator. Returns nil at the end of the file.
+#[derive(Finalize)]
+struct ReadLineImpl(Handle);
+
+impl NativeFun for ReadLineImpl {
+	fn name(&self) -> &'static str { "std.io.open<read_line>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let result = with_file(
+			&self.0,
+			|file| {
+				let mut line = Vec::new();
+
+				if file.read_until(b'\n', &mut line)? == 0 {
+					return Ok(None);
+				}
+
+				if line.last() == Some(&b'\n') {
+					line.pop();
+				}
+
+				Ok(Some(Str::from(line)))
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Write a string or byte array.
+#[derive(Finalize)]
+struct WriteImpl(Handle);
+
+impl NativeFun for WriteImpl {
+	fn name(&self) -> &'static str { "std.io.open<write>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let data = match context.args() {
+			[ value ] => util::bytes(value)
+				.ok_or_else(|| Panic::type_error(value.copy(), "string or byte array", context.pos.copy()))?,
+
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = with_file(
+			&self.0,
+			|file| {
+				// Discard buffered input, so that the data is written at the current position.
+				file.seek(SeekFrom::Current(0))?;
+				file.get_mut().write_all(&data)
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Move to the given offset, relative to the start (the default), the current position or
+/// the end. Returns the new position.
+#[derive(Finalize)]
+struct SeekImpl(Handle);
+
+impl NativeFun for SeekImpl {
+	fn name(&self) -> &'static str { "std.io.open<seek>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let position = match context.args() {
+			[ Value::Int(offset) ] if *offset >= 0 => SeekFrom::Start(*offset as u64),
+			[ Value::Int(offset), Value::String(ref whence) ] => match whence.as_bytes() {
+				b"start" if *offset >= 0 => SeekFrom::Start(*offset as u64),
+				b"current" => SeekFrom::Current(*offset),
+				b"end" => SeekFrom::End(*offset),
+				b"start" => return Err(Panic::value_error(Value::Int(*offset), "non-negative integer", context.pos)),
+				_ => return Err(
+					Panic::value_error(Value::String(whence.copy()), "start, current or end", context.pos)
+				),
+			},
+
+			[ offset @ Value::Int(_) ] => return Err(Panic::value_error(offset.copy(), "non-negative integer", context.pos)),
+			[ Value::Int(_), other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let result = with_file(&self.0, |file| file.seek(position))
+			.map(|position| position as i64);
+
+		Ok(result.into())
+	}
+}
+
+
+#[derive(Finalize)]
+struct FlushImpl(Handle);
+
+impl NativeFun for FlushImpl {
+	fn name(&self) -> &'static str { "std.io.open<flush>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(with_file(&self.0, |file| file.get_mut().flush()).into())
+	}
+}
+
+
+/// Close the file. Further operations produce error values.
+#[derive(Finalize)]
+struct CloseImpl(Handle);
+
+impl NativeFun for CloseImpl {
+	fn name(&self) -> &'static str { "std.io.open<close>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			match self.0.borrow_mut().take() {
+				Some(_) => Value::default(),
+				None => Error::new("file is closed".into(), Value::default()).into(),
+			}
+		)
+	}
+}
+
+
+// The methods have no garbage-collected fields.
+unsafe impl Trace for ReadImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ReadLineImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for WriteImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for SeekImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for FlushImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for CloseImpl { gc::unsafe_empty_trace!(); }
@@ -0,0 +1,376 @@
+use std::{
+	cell::Cell,
+	convert::TryFrom,
+	net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	iter::iteration,
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Cidr) }
+inventory::submit! { RustFun::from(Contains) }
+inventory::submit! { RustFun::from(Hosts) }
+inventory::submit! { RustFun::from(Range) }
+
+
+/// An address as an integer, so that both versions can share the arithmetic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Address {
+	v6: bool,
+	bits: u128,
+}
+
+
+impl Address {
+	fn width(self) -> u32 {
+		if self.v6 { 128 } else { 32 }
+	}
+
+
+	fn ip(self) -> IpAddr {
+		if self.v6 {
+			Ipv6Addr::from(self.bits).into()
+		} else {
+			Ipv4Addr::from(self.bits as u32).into()
+		}
+	}
+
+
+	fn value(self) -> Value {
+		self.ip().to_string().into()
+	}
+}
+
+
+impl From<IpAddr> for Address {
+	fn from(ip: IpAddr) -> Self {
+		match ip {
+			IpAddr::V4(ip) => Self { v6: false, bits: u32::from(ip).into() },
+			IpAddr::V6(ip) => Self { v6: true, bits: ip.into() },
+		}
+	}
+}
+
+
+/// Get an address argument, which must be valid.
+fn address(value: &Value, pos: SourcePos) -> Result<Address, Panic> {
+	util::text(value, pos.copy())?
+		.parse::<IpAddr>()
+		.map(Address::from)
+		.map_err(|_| Panic::value_error(value.copy(), "valid ip address", pos))
+}
+
+
+/// A network in CIDR notation.
+#[derive(Debug, Clone, Copy)]
+struct Network {
+	address: Address,
+	prefix: u32,
+}
+
+
+impl Network {
+	/// Parse a network such as `10.0.0.0/8`. An address without a prefix is a network
+	/// with a single address. The address may have host bits set.
+	fn parse(cidr: &str) -> Result<Self, String> {
+		let (address, prefix) = match cidr.split_once('/') {
+			Some((address, prefix)) => (address, Some(prefix)),
+			None => (cidr, None),
+		};
+
+		let address: Address = address
+			.parse::<IpAddr>()
+			.map_err(|error| error.to_string())?
+			.into();
+
+		let prefix = match prefix {
+			None => address.width(),
+			Some(prefix) => match prefix.parse::<u32>() {
+				Ok(prefix) if prefix <= address.width() => prefix,
+				_ => return Err(format!("invalid prefix length: {}", prefix)),
+			},
+		};
+
+		Ok(Self { address, prefix })
+	}
+
+
+	/// Get a network argument, which must be valid.
+	fn arg(value: &Value, pos: SourcePos) -> Result<Self, Panic> {
+		Self::parse(util::text(value, pos.copy())?)
+			.map_err(|_| Panic::value_error(value.copy(), "valid cidr network", pos))
+	}
+
+
+	/// All bits of addresses of the network's version.
+	fn all(self) -> u128 {
+		if self.address.v6 { u128::MAX } else { u32::MAX.into() }
+	}
+
+
+	fn mask(self) -> u128 {
+		match self.prefix {
+			0 => 0,
+			prefix => (u128::MAX << (self.address.width() - prefix)) & self.all(),
+		}
+	}
+
+
+	fn with_bits(self, bits: u128) -> Address {
+		Address { v6: self.address.v6, bits }
+	}
+
+
+	fn first(self) -> Address {
+		self.with_bits(self.address.bits & self.mask())
+	}
+
+
+	fn last(self) -> Address {
+		self.with_bits(self.first().bits | (!self.mask() & self.all()))
+	}
+
+
+	/// The range of host addresses. In IPv4 networks with more than two addresses, the
+	/// network and broadcast addresses are excluded.
+	fn hosts(self) -> (Address, Address) {
+		let (first, last) = (self.first(), self.last());
+
+		if !self.address.v6 && self.prefix < 31 {
+			(self.with_bits(first.bits + 1), self.with_bits(last.bits - 1))
+		} else {
+			(first, last)
+		}
+	}
+
+
+	fn contains(self, address: Address) -> bool {
+		address.v6 == self.address.v6 && address.bits & self.mask() == self.first().bits
+	}
+
+
+	/// The amount of addresses, which is a float if it doesn't fit an int.
+	fn size(self) -> Value {
+		let size = (self.last().bits - self.first().bits).checked_add(1);
+
+		match size.and_then(|size| i64::try_from(size).ok()) {
+			Some(size) => size.into(),
+			None => 2f64.powi((self.address.width() - self.prefix) as i32).into(),
+		}
+	}
+}
+
+
+/// Parse an IP address into a dict with it's version (4 or 6), the normalized address,
+/// and whether it is a loopback, private, link-local, multicast or unspecified address.
+/// Invalid addresses produce an error value.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl Parse {
+	fn parse(ip: IpAddr) -> Value {
+		let (private, link_local) = match ip {
+			IpAddr::V4(ip) => (ip.is_private(), ip.is_link_local()),
+			IpAddr::V6(ip) => {
+				let segment = ip.segments()[0];
+				(segment & 0xfe00 == 0xfc00, segment & 0xffc0 == 0xfe80)
+			}
+		};
+
+		util::dict([
+			("version", Value::Int(if ip.is_ipv6() { 6 } else { 4 })),
+			("address", ip.to_string().into()),
+			("loopback", ip.is_loopback().into()),
+			("private", private.into()),
+			("link_local", link_local.into()),
+			("multicast", ip.is_multicast().into()),
+			("unspecified", ip.is_unspecified().into()),
+		])
+	}
+}
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.net.ip.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => Ok(
+				util::text(value, context.pos.copy())?
+					.parse::<IpAddr>()
+					.map(Self::parse)
+					.unwrap_or_else(|error| Error::new(error.to_string().into(), value.copy()).into())
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Parse a network in CIDR notation, such as `192.168.0.0/24`, into a dict with it's
+/// version, the given address, the prefix length, the network address, the netmask, the
+/// broadcast address (nil for IPv6), the first and last host addresses, and the amount
+/// of addresses (size). Invalid networks produce an error value.
+#[derive(Trace, Finalize)]
+struct Cidr;
+
+impl Cidr {
+	fn cidr(network: Network) -> Value {
+		let (first, last) = network.hosts();
+		let v6 = network.address.v6;
+
+		util::dict([
+			("version", Value::Int(if v6 { 6 } else { 4 })),
+			("address", network.address.value()),
+			("prefix", i64::from(network.prefix).into()),
+			("network", network.first().value()),
+			("netmask", network.with_bits(network.mask()).value()),
+			("broadcast", if v6 { Value::Nil } else { network.last().value() }),
+			("first", first.value()),
+			("last", last.value()),
+			("size", network.size()),
+		])
+	}
+}
+
+impl NativeFun for Cidr {
+	fn name(&self) -> &'static str { "std.net.ip.cidr" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => Ok(
+				Network::parse(util::text(value, context.pos.copy())?)
+					.map(Self::cidr)
+					.unwrap_or_else(|error| Error::new(error.into(), value.copy()).into())
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Whether a network in CIDR notation contains an address. Addresses of the other IP
+/// version are never contained.
+#[derive(Trace, Finalize)]
+struct Contains;
+
+impl NativeFun for Contains {
+	fn name(&self) -> &'static str { "std.net.ip.contains" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ network, value ] => {
+				let network = Network::arg(network, context.pos.copy())?;
+				let address = address(value, context.pos.copy())?;
+
+				Ok(network.contains(address).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Iterate over the host addresses of a network in CIDR notation. In IPv4 networks with
+/// more than two addresses, the network and broadcast addresses are skipped.
+#[derive(Trace, Finalize)]
+struct Hosts;
+
+impl NativeFun for Hosts {
+	fn name(&self) -> &'static str { "std.net.ip.hosts" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ network ] => {
+				let (first, last) = Network::arg(network, context.pos.copy())?.hosts();
+				Ok(RangeImpl::new(first, last).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Iterate over the addresses from first to last, inclusive, which must be of the same
+/// IP version.
+#[derive(Trace, Finalize)]
+struct Range;
+
+impl NativeFun for Range {
+	fn name(&self) -> &'static str { "std.net.ip.range" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ first, last_value ] => {
+				let first = address(first, context.pos.copy())?;
+				let last = address(last_value, context.pos.copy())?;
+
+				if first.v6 != last.v6 {
+					return Err(Panic::value_error(last_value.copy(), "address of the same ip version", context.pos));
+				}
+
+				Ok(RangeImpl::new(first, last).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+#[derive(Finalize)]
+struct RangeImpl {
+	/// The next address, or None when finished.
+	next: Cell<Option<Address>>,
+	last: Address,
+}
+
+impl RangeImpl {
+	fn new(first: Address, last: Address) -> Self {
+		Self {
+			next: Cell::new(Some(first).filter(|first| first.bits <= last.bits)),
+			last,
+		}
+	}
+}
+
+impl NativeFun for RangeImpl {
+	fn name(&self) -> &'static str { "std.net.ip.range<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let next = self.next.get();
+
+		if let Some(address) = next {
+			let following = Address { bits: address.bits.wrapping_add(1), ..address };
+			self.next.set(Some(following).filter(|_| address.bits < self.last.bits));
+		}
+
+		Ok(iteration(next.map(Address::value)))
+	}
+}
+
+
+// The iterator has no garbage-collected fields.
+unsafe impl Trace for RangeImpl { gc::unsafe_empty_trace!(); }
@@ -25,7 +25,9 @@ impl NativeFun for IsEmpty {
 
 			[ Value::String(ref string) ] => Ok(string.is_empty().into()),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.pos)),
+			[ Value::Buffer(ref buffer) ] => Ok(buffer.is_empty().into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string, array, dict or buffer", context.pos)),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
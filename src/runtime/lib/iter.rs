@@ -1,12 +1,15 @@
-use std::collections::HashMap;
-
 use gc::{Finalize, GcCell, Trace};
 
+use crate::runtime::SourcePos;
+
 use super::{
 	keys,
+	util,
 	Array,
 	CallContext,
 	Dict,
+	DictMap,
+	Function,
 	RustFun,
 	NativeFun,
 	Panic,
@@ -16,7 +19,18 @@ use super::{
 
 
 inventory::submit! { RustFun::from(Iter) }
+inventory::submit! { RustFun::from(Map) }
+inventory::submit! { RustFun::from(Filter) }
+inventory::submit! { RustFun::from(Take) }
+inventory::submit! { RustFun::from(Skip) }
+inventory::submit! { RustFun::from(Enumerate) }
+inventory::submit! { RustFun::from(Zip) }
+inventory::submit! { RustFun::from(Chain) }
+inventory::submit! { RustFun::from(Collect) }
 
+/// Iterate over the elements of an array, the characters of a string, or the entries of
+/// a dict. Dict entries are produced in ascending order of their keys, so that iteration
+/// order is stable across runs.
 #[derive(Trace, Finalize)]
 struct Iter;
 
@@ -25,39 +39,102 @@ impl NativeFun for Iter {
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ Value::Array(ref array) ] => Ok(
-				IterImpl::Array {
-					array: array.copy(),
-					ix: GcCell::new(0),
-				}.into()
-			),
+			[ value @ Value::Function(_) ] => Err(Panic::type_error(value.copy(), "string, array or dict", context.pos)),
 
-			[ Value::Dict(ref dict) ] => Ok(
-				IterImpl::Dict {
-					entries: GcCell::new(
-						dict
-							.borrow()
-							.iter()
-							.map(|(k, v)| (k.copy(), v.copy()))
-							.collect()
-					)
-				}.into()
-			),
+			[ value ] => match iterator(value) {
+				Some(iter) => Ok(iter.into()),
+				None => Err(Panic::type_error(value.copy(), "string, array or dict", context.pos)),
+			},
 
-			[ Value::String(ref string) ] => Ok(
-				IterImpl::String {
-					string: string.copy(),
-					ix: GcCell::new(0),
-				}.into()
-			),
-
-			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.pos)),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
 }
 
 
+/// Get an iterator for a value. Functions are assumed to be iterators already. Arrays,
+/// strings and dicts are iterated as in std.iter. Other values are not iterable.
+pub fn iterator(value: &Value) -> Option<Function> {
+	let iter = match value {
+		Value::Function(fun) => return Some(fun.copy()),
+
+		Value::Array(array) => IterImpl::Array {
+			array: array.copy(),
+			ix: GcCell::new(0),
+		},
+
+		Value::Dict(dict) => {
+			// Entries are popped from the back, so reverse the insertion order.
+			let mut entries = super::dict::entries(dict);
+			entries.reverse();
+
+			IterImpl::Dict {
+				entries: GcCell::new(entries)
+			}
+		}
+
+		Value::String(string) => IterImpl::String {
+			string: string.copy(),
+			ix: GcCell::new(0),
+		},
+
+		_ => return None,
+	};
+
+	Some(iter.into())
+}
+
+
+/// Advance an iterator, following the iteration protocol: each call produces a dict
+/// with a finished field, and a value field if not finished. Returns None when finished.
+pub fn next(context: &mut CallContext, iter: &Function) -> Result<Option<Value>, Panic> {
+	let pos = context.pos.copy();
+
+	match util::call(context, iter, [])? {
+		Value::Dict(ref dict) => {
+			let finished = keys::FINISHED.with(
+				|finished| dict
+					.get(finished)
+					.map_err(|_| Panic::index_out_of_bounds(finished.copy(), pos.copy()))
+			)?;
+
+			match finished {
+				Value::Bool(true) => Ok(None),
+
+				Value::Bool(false) => keys::VALUE.with(
+					|value| dict
+						.get(value)
+						.map(Some)
+						.map_err(|_| Panic::index_out_of_bounds(value.copy(), pos))
+				),
+
+				other => Err(Panic::type_error(other, "bool", pos)),
+			}
+		}
+
+		other => Err(Panic::type_error(other, "dict", pos)),
+	}
+}
+
+
+/// Build the result of an iteration step, following the iteration protocol.
+pub fn iteration(next: Option<Value>) -> Value {
+	let mut iteration = DictMap::default();
+
+	keys::FINISHED.with(
+		|finished| iteration.insert(finished.copy(), next.is_none().into())
+	);
+
+	if let Some(next) = next {
+		keys::VALUE.with(
+			|value| iteration.insert(value.copy(), next)
+		);
+	}
+
+	Dict::new(iteration).into()
+}
+
+
 #[derive(Trace, Finalize)]
 enum IterImpl {
 	Array {
@@ -82,8 +159,6 @@ impl NativeFun for IterImpl {
 			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
 		}
 
-		let mut iteration = HashMap::new();
-
 		let next = match self {
 			IterImpl::Array { array, ix } => {
 				let mut ix = ix.borrow_mut();
@@ -110,7 +185,7 @@ impl NativeFun for IterImpl {
 				.pop()
 				.map(
 					|(k, v)| {
-						let mut entry = HashMap::new();
+						let mut entry = DictMap::default();
 
 						keys::KEY.with(
 							|key| entry.insert(key.copy(), k)
@@ -125,16 +200,385 @@ impl NativeFun for IterImpl {
 				)
 		};
 
-		keys::FINISHED.with(
-			|finished| iteration.insert(finished.copy(), next.is_none().into())
+		Ok(iteration(next))
+	}
+}
+
+
+/// Get an iterator argument. Arrays, dicts and strings are accepted as well.
+fn iterable(value: &Value, pos: SourcePos) -> Result<Function, Panic> {
+	iterator(value).ok_or_else(|| Panic::type_error(value.copy(), "function, array, dict or string", pos))
+}
+
+
+/// Get the arguments of combinators taking an iterator and a function.
+fn iter_fun(context: &CallContext) -> Result<(Function, Function), Panic> {
+	match context.args() {
+		[ iter, Value::Function(ref fun) ] => Ok((iterable(iter, context.pos.copy())?, fun.copy())),
+
+		[ _, other ] => Err(Panic::type_error(other.copy(), "function", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	}
+}
+
+
+/// Get the arguments of combinators taking an iterator and a count.
+fn iter_count(context: &CallContext) -> Result<(Function, i64), Panic> {
+	match context.args() {
+		[ iter, Value::Int(count) ] if *count >= 0 => Ok((iterable(iter, context.pos.copy())?, *count)),
+
+		[ _, count @ Value::Int(_) ] => Err(Panic::value_error(count.copy(), "non-negative integer", context.pos.copy())),
+		[ _, other ] => Err(Panic::type_error(other.copy(), "int", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	}
+}
+
+
+/// Iterator functions take no arguments.
+fn no_args(context: &CallContext) -> Result<(), Panic> {
+	match context.args() {
+		[] => Ok(()),
+		args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos.copy()))
+	}
+}
+
+
+/// Lazily apply a function to each value of an iterator.
+#[derive(Trace, Finalize)]
+struct Map;
+
+impl NativeFun for Map {
+	fn name(&self) -> &'static str { "std.iter.map" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (source, fun) = iter_fun(&context)?;
+		Ok(MapImpl { source, fun }.into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct MapImpl {
+	source: Function,
+	fun: Function,
+}
+
+impl NativeFun for MapImpl {
+	fn name(&self) -> &'static str { "std.iter.map<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		let value = match next(&mut context, &self.source)? {
+			Some(value) => Some(util::call(&mut context, &self.fun, [ value ])?),
+			None => None,
+		};
+
+		Ok(iteration(value))
+	}
+}
+
+
+/// Lazily keep the values of an iterator for which the predicate returns true.
+#[derive(Trace, Finalize)]
+struct Filter;
+
+impl NativeFun for Filter {
+	fn name(&self) -> &'static str { "std.iter.filter" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (source, fun) = iter_fun(&context)?;
+		Ok(FilterImpl { source, fun }.into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct FilterImpl {
+	source: Function,
+	fun: Function,
+}
+
+impl NativeFun for FilterImpl {
+	fn name(&self) -> &'static str { "std.iter.filter<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		while let Some(value) = next(&mut context, &self.source)? {
+			if util::test(&mut context, &self.fun, [ value.copy() ])? {
+				return Ok(iteration(Some(value)));
+			}
+		}
+
+		Ok(iteration(None))
+	}
+}
+
+
+/// Produce at most the given number of values from an iterator. The source iterator is
+/// not advanced further once the count is reached.
+#[derive(Trace, Finalize)]
+struct Take;
+
+impl NativeFun for Take {
+	fn name(&self) -> &'static str { "std.iter.take" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (source, count) = iter_count(&context)?;
+		Ok(TakeImpl { source, remaining: GcCell::new(count) }.into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct TakeImpl {
+	source: Function,
+	remaining: GcCell<i64>,
+}
+
+impl NativeFun for TakeImpl {
+	fn name(&self) -> &'static str { "std.iter.take<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		{
+			let mut remaining = self.remaining.borrow_mut();
+			if *remaining == 0 {
+				return Ok(iteration(None));
+			}
+			*remaining -= 1;
+		}
+
+		let value = next(&mut context, &self.source)?;
+		if value.is_none() {
+			*self.remaining.borrow_mut() = 0;
+		}
+
+		Ok(iteration(value))
+	}
+}
+
+
+/// Skip the given number of values from an iterator. Values are skipped on the first
+/// call, not when the iterator is created.
+#[derive(Trace, Finalize)]
+struct Skip;
+
+impl NativeFun for Skip {
+	fn name(&self) -> &'static str { "std.iter.skip" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (source, count) = iter_count(&context)?;
+		Ok(SkipImpl { source, skip: GcCell::new(count) }.into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct SkipImpl {
+	source: Function,
+	skip: GcCell<i64>,
+}
+
+impl NativeFun for SkipImpl {
+	fn name(&self) -> &'static str { "std.iter.skip<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		let skip = std::mem::take(&mut *self.skip.borrow_mut());
+		for _ in 0 .. skip {
+			if next(&mut context, &self.source)?.is_none() {
+				return Ok(iteration(None));
+			}
+		}
+
+		Ok(iteration(next(&mut context, &self.source)?))
+	}
+}
+
+
+/// Pair each value of an iterator with its index, producing dicts with index and value
+/// fields.
+#[derive(Trace, Finalize)]
+struct Enumerate;
+
+impl NativeFun for Enumerate {
+	fn name(&self) -> &'static str { "std.iter.enumerate" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ iter ] => Ok(
+				EnumerateImpl {
+					source: iterable(iter, context.pos.copy())?,
+					index: GcCell::new(0),
+				}.into()
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct EnumerateImpl {
+	source: Function,
+	index: GcCell<i64>,
+}
+
+impl EnumerateImpl {
+	fn entry(index: i64, value: Value) -> Value {
+		thread_local! {
+			static INDEX: Value = "index".into();
+		}
+
+		let mut entry = DictMap::default();
+		INDEX.with(|key| entry.insert(key.copy(), index.into()));
+		keys::VALUE.with(|key| entry.insert(key.copy(), value));
+
+		Dict::new(entry).into()
+	}
+}
+
+impl NativeFun for EnumerateImpl {
+	fn name(&self) -> &'static str { "std.iter.enumerate<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		let value = next(&mut context, &self.source)?.map(
+			|value| {
+				let mut index = self.index.borrow_mut();
+				let entry = Self::entry(*index, value);
+				*index += 1;
+				entry
+			}
 		);
 
-		if let Some(next) = next {
-			keys::VALUE.with(
-				|value| iteration.insert(value.copy(), next)
-			);
+		Ok(iteration(value))
+	}
+}
+
+
+/// Pair the values of two iterators, producing two-element arrays. Finishes when either
+/// iterator finishes.
+#[derive(Trace, Finalize)]
+struct Zip;
+
+impl NativeFun for Zip {
+	fn name(&self) -> &'static str { "std.iter.zip" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ left, right ] => Ok(
+				ZipImpl {
+					left: iterable(left, context.pos.copy())?,
+					right: iterable(right, context.pos.copy())?,
+				}.into()
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct ZipImpl {
+	left: Function,
+	right: Function,
+}
+
+impl NativeFun for ZipImpl {
+	fn name(&self) -> &'static str { "std.iter.zip<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		let left = match next(&mut context, &self.left)? {
+			Some(value) => value,
+			None => return Ok(iteration(None)),
+		};
+
+		let right = match next(&mut context, &self.right)? {
+			Some(value) => value,
+			None => return Ok(iteration(None)),
+		};
+
+		Ok(iteration(Some(vec![ left, right ].into())))
+	}
+}
+
+
+/// Produce the values of each of the given iterators, one after another.
+#[derive(Trace, Finalize)]
+struct Chain;
+
+impl NativeFun for Chain {
+	fn name(&self) -> &'static str { "std.iter.chain" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let sources = context
+			.args()
+			.iter()
+			.map(|iter| iterable(iter, context.pos.copy()))
+			.collect::<Result<_, _>>()?;
+
+		Ok(ChainImpl { sources, current: GcCell::new(0) }.into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct ChainImpl {
+	sources: Vec<Function>,
+	current: GcCell<usize>,
+}
+
+impl NativeFun for ChainImpl {
+	fn name(&self) -> &'static str { "std.iter.chain<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		loop {
+			let current = *self.current.borrow();
+
+			let source = match self.sources.get(current) {
+				Some(source) => source,
+				None => return Ok(iteration(None)),
+			};
+
+			match next(&mut context, source)? {
+				Some(value) => return Ok(iteration(Some(value))),
+				None => *self.current.borrow_mut() = current + 1,
+			}
+		}
+	}
+}
+
+
+/// Consume an iterator, producing an array with all its values.
+#[derive(Trace, Finalize)]
+struct Collect;
+
+impl NativeFun for Collect {
+	fn name(&self) -> &'static str { "std.iter.collect" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let source = match context.args() {
+			[ iter ] => iterable(iter, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+		};
+
+		let mut values = Vec::new();
+		while let Some(value) = next(&mut context, &source)? {
+			values.push(value);
 		}
 
-		Ok(Dict::new(iteration).into())
+		Ok(values.into())
 	}
 }
@@ -1,4 +1,4 @@
-use std::{fmt, collections::HashMap, convert::TryFrom};
+use std::{fmt, convert::TryFrom};
 
 use gc::{Finalize, Trace};
 use serde::{
@@ -12,6 +12,7 @@ use serde::{
 
 use super::{
 	Dict,
+	DictMap,
 	Error,
 	Float,
 	NativeFun,
@@ -22,9 +23,60 @@ use super::{
 };
 
 
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Dump) }
 inventory::submit! { RustFun::from(Encode) }
 inventory::submit! { RustFun::from(Decode) }
 
+
+/// Convert a value to json. The output is compact, unless the pretty option is given.
+#[derive(Trace, Finalize)]
+struct Dump;
+
+impl Dump {
+	/// Convert the value in the arguments, pretty printing by default if requested.
+	fn dump(context: CallContext, pretty: bool) -> Result<Value, Panic> {
+		let (value, pretty) = match context.args() {
+			[ value ] => (value, pretty),
+			[ value, Value::Dict(ref options) ] => match options.get(&"pretty".into()) {
+				Ok(Value::Bool(pretty)) => (value, pretty),
+				Ok(Value::Nil) | Err(_) => (value, pretty),
+				Ok(other) => return Err(Panic::type_error(other, "bool", context.pos)),
+			},
+
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args_range(args.len() as u32, 1 ..= 2, context.pos))
+		};
+
+		let result =
+			if pretty {
+				serde_json::to_string_pretty(value)
+			} else {
+				serde_json::to_string(value)
+			};
+
+		result
+			.map(Into::into)
+			.map_err(
+				|_| Panic::value_error(
+					value.copy(),
+					"nil, bool, byte, int, float, string, array or dict",
+					context.pos.copy()
+				)
+			)
+	}
+}
+
+impl NativeFun for Dump {
+	fn name(&self) -> &'static str { "std.json.dump" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Self::dump(context, false)
+	}
+}
+
+
+/// Alias of std.json.dump, which pretty prints by default.
 #[derive(Trace, Finalize)]
 struct Encode;
 
@@ -32,22 +84,60 @@ impl NativeFun for Encode {
 	fn name(&self) -> &'static str { "std.json.encode" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Dump::dump(context, true)
+	}
+}
+
+
+/// Convert json to a value. Malformed input results in an error, with it's line and
+/// column in the context.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl Parse {
+	/// Parse the string in the arguments, building the error with the given function.
+	fn parse<F>(context: CallContext, error: F) -> Result<Value, Panic>
+	where
+		F: FnOnce(serde_json::Error, &Value) -> Value,
+	{
 		match context.args() {
-			[ value ] => serde_json::to_string_pretty(value)
-					.map(Into::into)
-					.map_err(
-						|_| Panic::value_error(
-							value.copy(),
-							"nil, bool, byte, int, float, string, array or dict",
-							context.pos.copy()
-						)
-					),
+			[ value @ Value::String(ref string) ] => Ok(
+				serde_json::from_slice(string.as_bytes()).unwrap_or_else(|err| error(err, value))
+			),
 
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
+
+
+	/// Build the error for malformed input, with it's position in the context.
+	fn error(error: serde_json::Error) -> Value {
+		thread_local! {
+			pub static LINE: Value = "line".into();
+			pub static COLUMN: Value = "column".into();
+		}
+
+		let mut context = DictMap::default();
+		LINE.with(|line| context.insert(line.copy(), Value::Int(error.line() as i64)));
+		COLUMN.with(|column| context.insert(column.copy(), Value::Int(error.column() as i64)));
+
+		let description = format!("invalid json: {}", error);
+
+		Error::new(description.into(), Dict::new(context).into()).into()
+	}
+}
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.json.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Self::parse(context, |error, _| Self::error(error))
+	}
 }
 
+
+/// Alias of std.json.parse, where errors have the input string as context.
 #[derive(Trace, Finalize)]
 struct Decode;
 
@@ -55,20 +145,14 @@ impl NativeFun for Decode {
 	fn name(&self) -> &'static str { "std.json.decode" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
-		match context.args() {
-			[ value @ Value::String(ref string) ] => Ok(
-				serde_json::from_slice(string.as_bytes())
-					.unwrap_or_else(
-						|error| Error::new(error.to_string().into(), value.copy()).into()
-					)
-			),
-
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
-		}
+		Parse::parse(
+			context,
+			|error, input| Error::new(error.to_string().into(), input.copy()).into()
+		)
 	}
 }
 
+
 impl<'de> Deserialize<'de> for Value {
 	fn deserialize<D>(deserializer: D) -> Result<Value, D::Error>
 	where
@@ -80,7 +164,7 @@ impl<'de> Deserialize<'de> for Value {
 			type Value = Value;
 
 			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-				formatter.write_str("any valid JSON value")
+				formatter.write_str("any valid value")
 			}
 
 			fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
@@ -149,7 +233,7 @@ impl<'de> Deserialize<'de> for Value {
 			{
 				match visitor.next_key()? {
 					Some(key) => {
-						let mut values = HashMap::new();
+						let mut values = DictMap::default();
 
 						values.insert(key, visitor.next_value()?);
 						while let Some((key, value)) = visitor.next_entry()? {
@@ -192,6 +276,7 @@ impl Serialize for Value {
 				map.end()
 			}
 
+			Value::Buffer(buffer) => serializer.serialize_bytes(&buffer.borrow()),
 			Value::Function(_) => Err(ser::Error::custom("can't serialize function")),
 			Value::Error(_) => Err(ser::Error::custom("can't serialize error")),
 		}
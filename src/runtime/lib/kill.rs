@@ -0,0 +1,148 @@
+use std::{convert::TryFrom, io};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Kill) }
+
+
+/// A process id.
+#[cfg(unix)]
+pub type Pid = libc::pid_t;
+#[cfg(windows)]
+pub type Pid = u32;
+
+
+/// The default signal, which asks the process to terminate.
+#[cfg(unix)]
+pub const TERM: libc::c_int = libc::SIGTERM;
+#[cfg(windows)]
+pub const TERM: libc::c_int = 15;
+
+
+/// Get a signal by name, with or without the SIG prefix.
+#[cfg(unix)]
+pub fn signal(name: &[u8]) -> Option<libc::c_int> {
+	let name = name.strip_prefix(b"SIG").unwrap_or(name);
+
+	match name {
+		b"HUP" => Some(libc::SIGHUP),
+		b"INT" => Some(libc::SIGINT),
+		b"QUIT" => Some(libc::SIGQUIT),
+		b"KILL" => Some(libc::SIGKILL),
+		b"TERM" => Some(libc::SIGTERM),
+		b"USR1" => Some(libc::SIGUSR1),
+		b"USR2" => Some(libc::SIGUSR2),
+		b"ALRM" => Some(libc::SIGALRM),
+		b"PIPE" => Some(libc::SIGPIPE),
+		b"CHLD" => Some(libc::SIGCHLD),
+		b"CONT" => Some(libc::SIGCONT),
+		b"STOP" => Some(libc::SIGSTOP),
+		b"TSTP" => Some(libc::SIGTSTP),
+		b"WINCH" => Some(libc::SIGWINCH),
+		_ => None,
+	}
+}
+
+
+/// Get a signal by name, with or without the SIG prefix. Only the signals that terminate
+/// a process are supported on Windows, with their Unix numbers.
+#[cfg(windows)]
+pub fn signal(name: &[u8]) -> Option<libc::c_int> {
+	let name = name.strip_prefix(b"SIG").unwrap_or(name);
+
+	match name {
+		b"INT" => Some(2),
+		b"KILL" => Some(9),
+		b"TERM" => Some(15),
+		_ => None,
+	}
+}
+
+
+/// Send a signal to a process.
+#[cfg(unix)]
+pub fn send(pid: Pid, signal: libc::c_int) -> io::Result<()> {
+	// Safety: kill has no memory safety requirements.
+	if unsafe { libc::kill(pid, signal) } == 0 {
+		Ok(())
+	} else {
+		Err(io::Error::last_os_error())
+	}
+}
+
+
+/// Send a signal to a process. As Windows has no signals, the process is terminated
+/// with 128 plus the signal number as exit status, like Unix shells report it.
+#[cfg(windows)]
+pub fn send(pid: Pid, signal: libc::c_int) -> io::Result<()> {
+	use windows_sys::Win32::{
+		Foundation::CloseHandle,
+		System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE},
+	};
+
+	if !matches!(signal, 2 | 9 | 15) {
+		return Err(io::ErrorKind::Unsupported.into());
+	}
+
+	// Safety: OpenProcess has no memory safety requirements.
+	let process = unsafe { OpenProcess(PROCESS_TERMINATE, 0, pid) };
+	if process == 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	// Safety: the handle was just opened, and is closed only once.
+	let result = unsafe { TerminateProcess(process, 128 + signal as u32) };
+	let error = io::Error::last_os_error();
+	unsafe { CloseHandle(process) };
+
+	if result == 0 {
+		Err(error)
+	} else {
+		Ok(())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Kill;
+
+impl NativeFun for Kill {
+	fn name(&self) -> &'static str { "std.kill" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (pid, signal) = match context.args() {
+			[ pid @ Value::Int(_), signal ] => (pid.copy(), signal.copy()),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		// Only positive pids are allowed, as the others target multiple processes.
+		let pid_value = match &pid {
+			Value::Int(int) if *int > 0 => Pid::try_from(*int).ok(),
+			_ => None,
+		};
+
+		let signal_value = match &signal {
+			Value::Int(int) => libc::c_int::try_from(*int).ok(),
+			Value::String(name) => self::signal(name.as_bytes()),
+			other => return Err(Panic::type_error(other.copy(), "int or string", context.pos)),
+		};
+
+		let pid = pid_value
+			.ok_or_else(|| Panic::value_error(pid, "valid pid", context.pos.copy()))?;
+
+		let signal = signal_value
+			.ok_or_else(|| Panic::value_error(signal, "valid signal", context.pos.copy()))?;
+
+		Ok(send(pid, signal).into())
+	}
+}
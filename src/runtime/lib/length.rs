@@ -22,7 +22,8 @@ impl NativeFun for Length {
 			[ Value::Array(ref array) ] => Ok(Value::Int(array.len())),
 			[ Value::Dict(ref dict) ] => Ok(Value::Int(dict.len())),
 			[ Value::String(ref string) ] => Ok(Value::Int(string.len() as i64)),
-			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.pos)),
+			[ Value::Buffer(ref buffer) ] => Ok(Value::Int(buffer.len())),
+			[ other ] => Err(Panic::type_error(other.copy(), "string, array, dict or buffer", context.pos)),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
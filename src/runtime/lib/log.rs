@@ -0,0 +1,559 @@
+use std::{
+	cell::RefCell,
+	ffi::OsStr,
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+
+use chrono::{DateTime, Local, SecondsFormat};
+use gc::{Finalize, Trace};
+
+use crate::{ffi::OsStrExt, fmt::FmtString, runtime::command::env, symbol};
+
+use super::{
+	dict::entries,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(New) }
+inventory::submit! { RustFun::from(Log(Level::Debug)) }
+inventory::submit! { RustFun::from(Log(Level::Info)) }
+inventory::submit! { RustFun::from(Log(Level::Warn)) }
+inventory::submit! { RustFun::from(Log(Level::Error)) }
+
+
+/// The environment variable which sets the minimum level of all loggers.
+const LEVEL_VAR: &str = "HUSH_LOG";
+
+
+/// Log levels, in increasing order of severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+	Debug,
+	Info,
+	Warn,
+	Error,
+}
+
+
+impl Level {
+	fn parse(name: &[u8]) -> Option<Self> {
+		match name.to_ascii_lowercase().as_slice() {
+			b"debug" => Some(Self::Debug),
+			b"info" => Some(Self::Info),
+			b"warn" | b"warning" => Some(Self::Warn),
+			b"error" => Some(Self::Error),
+			_ => None,
+		}
+	}
+
+
+	/// Get the minimum level from the environment variable, if set to a valid level.
+	fn from_env() -> Option<Self> {
+		env::get(OsStr::new(LEVEL_VAR)).and_then(|level| Self::parse(level.as_bytes()))
+	}
+
+
+	fn name(self) -> &'static str {
+		match self {
+			Self::Debug => "debug",
+			Self::Info => "info",
+			Self::Warn => "warn",
+			Self::Error => "error",
+		}
+	}
+
+
+	/// The syslog severity of the level.
+	fn severity(self) -> u8 {
+		match self {
+			Self::Debug => 7,
+			Self::Info => 6,
+			Self::Warn => 4,
+			Self::Error => 3,
+		}
+	}
+}
+
+
+/// A log record.
+struct Record {
+	level: Level,
+	timestamp: DateTime<Local>,
+	message: String,
+	/// Fields, in the order of the dict. Values are kept as JSON, so that they may be
+	/// written either as text or as JSON.
+	fields: Vec<(String, serde_json::Value)>,
+}
+
+
+impl Record {
+	fn new(level: Level, message: &Value, fields: Option<&Dict>, interner: &symbol::Interner) -> Self {
+		let text = |value: &Value| match value {
+			Value::String(string) => String::from_utf8_lossy(string.as_bytes()).into_owned(),
+			value => value.fmt_string(interner),
+		};
+
+		let fields = fields
+			.map(entries)
+			.unwrap_or_default()
+			.into_iter()
+			.map(
+				|(key, value)| {
+					let value = serde_json::to_value(&value)
+						.unwrap_or_else(|_| serde_json::Value::String(text(&value)));
+					(text(&key), value)
+				}
+			)
+			.collect();
+
+		Self {
+			level,
+			timestamp: Local::now(),
+			message: text(message),
+			fields,
+		}
+	}
+
+
+	/// The message followed by the fields as `name=value` pairs. Values are quoted only
+	/// when needed.
+	fn message(&self) -> String {
+		let mut line = self.message.clone();
+
+		for (name, value) in &self.fields {
+			let value = match value {
+				serde_json::Value::String(string) if !Self::needs_quotes(string) => string.clone(),
+				value => value.to_string(),
+			};
+
+			line.push(' ');
+			line.push_str(name);
+			line.push('=');
+			line.push_str(&value);
+		}
+
+		line
+	}
+
+
+	fn needs_quotes(string: &str) -> bool {
+		string.is_empty() || string.contains(|c: char| c.is_whitespace() || c == '"' || c == '=')
+	}
+
+
+	/// A line of text, with the timestamp and level.
+	fn text(&self) -> String {
+		format!(
+			"{} {:5} {}\n",
+			self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, false),
+			self.level.name().to_ascii_uppercase(),
+			self.message(),
+		)
+	}
+
+
+	/// A line of JSON, with the fields merged into the top level object.
+	fn json(&self) -> String {
+		let mut object = serde_json::Map::new();
+
+		for (name, value) in &self.fields {
+			object.insert(name.clone(), value.clone());
+		}
+
+		object.insert("time".into(), self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, false).into());
+		object.insert("level".into(), self.level.name().into());
+		object.insert("message".into(), self.message.clone().into());
+
+		let mut line = serde_json::Value::Object(object).to_string();
+		line.push('\n');
+		line
+	}
+
+
+	/// A syslog datagram, with the user facility.
+	#[cfg(unix)]
+	fn syslog(&self, tag: &str) -> String {
+		format!(
+			"<{}>{}[{}]: {}",
+			8 + self.level.severity(),
+			tag,
+			std::process::id(),
+			self.message(),
+		)
+	}
+
+
+	/// A journald datagram in the native protocol. Field names are converted to upper case,
+	/// and other characters than letters, digits and underscores are replaced.
+	#[cfg(unix)]
+	fn journald(&self, tag: &str) -> Vec<u8> {
+		fn field(datagram: &mut Vec<u8>, name: &str, value: &str) {
+			datagram.extend(name.bytes());
+
+			// Values with newlines must be written with an explicit length.
+			if value.contains('\n') {
+				datagram.push(b'\n');
+				datagram.extend(&(value.len() as u64).to_le_bytes());
+			} else {
+				datagram.push(b'=');
+			}
+
+			datagram.extend(value.bytes());
+			datagram.push(b'\n');
+		}
+
+		let mut datagram = Vec::new();
+
+		field(&mut datagram, "MESSAGE", &self.message());
+		field(&mut datagram, "PRIORITY", &self.level.severity().to_string());
+		field(&mut datagram, "SYSLOG_IDENTIFIER", tag);
+
+		for (name, value) in &self.fields {
+			let name: String = name
+				.chars()
+				.map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+				.collect();
+
+			// Names must start with a letter.
+			let name = if name.starts_with(|c: char| c.is_ascii_uppercase()) { name } else { format!("F{}", name) };
+
+			let value = match value {
+				serde_json::Value::String(string) => string.clone(),
+				value => value.to_string(),
+			};
+
+			field(&mut datagram, &name, &value);
+		}
+
+		datagram
+	}
+}
+
+
+/// A log file, rotated when it would exceed the maximum size, if any. Rotated files are
+/// suffixed with `.1`, `.2` and so on, with `.1` being the most recent.
+struct LogFile {
+	path: PathBuf,
+	file: File,
+	size: u64,
+	max_size: Option<u64>,
+	max_files: u32,
+}
+
+
+impl LogFile {
+	fn open(path: PathBuf, max_size: Option<u64>, max_files: u32) -> io::Result<Self> {
+		let file = OpenOptions::new().create(true).append(true).open(&path)?;
+		let size = file.metadata()?.len();
+
+		Ok(Self { path, file, size, max_size, max_files })
+	}
+
+
+	fn rotated(&self, index: u32) -> PathBuf {
+		let mut path = self.path.clone().into_os_string();
+		path.push(format!(".{}", index));
+		path.into()
+	}
+
+
+	fn rotate(&mut self) -> io::Result<()> {
+		if self.max_files == 0 {
+			fs::remove_file(&self.path)?;
+		} else {
+			for index in (1 .. self.max_files).rev() {
+				let from = self.rotated(index);
+				if from.exists() {
+					fs::rename(&from, self.rotated(index + 1))?;
+				}
+			}
+
+			fs::rename(&self.path, self.rotated(1))?;
+		}
+
+		*self = Self::open(self.path.clone(), self.max_size, self.max_files)?;
+
+		Ok(())
+	}
+
+
+	fn write(&mut self, line: &[u8]) -> io::Result<()> {
+		let exceeds = self.max_size.map_or(false, |max_size| self.size + line.len() as u64 > max_size);
+
+		if exceeds && self.size > 0 {
+			self.rotate()?;
+		}
+
+		self.file.write_all(line)?;
+		self.size += line.len() as u64;
+
+		Ok(())
+	}
+}
+
+
+/// Where log records are written. Syslog and journald are not supported on Windows.
+enum Sink {
+	Stderr,
+	File(LogFile),
+	#[cfg(unix)]
+	Syslog(UnixDatagram),
+	#[cfg(unix)]
+	Journald(UnixDatagram),
+}
+
+
+/// A configured logger, shared by the methods of a logger value.
+struct Logger {
+	level: Level,
+	json: bool,
+	tag: String,
+	sink: Sink,
+}
+
+
+impl Logger {
+	/// The logger used by the std.log functions, which writes text to stderr.
+	fn stderr() -> Self {
+		Self {
+			level: Level::from_env().unwrap_or(Level::Info),
+			json: false,
+			tag: "hush".into(),
+			sink: Sink::Stderr,
+		}
+	}
+
+
+	fn write(&mut self, record: &Record) -> io::Result<()> {
+		let json = self.json;
+		let line = || if json { record.json() } else { record.text() };
+
+		match &mut self.sink {
+			Sink::Stderr => io::stderr().write_all(line().as_bytes()),
+			Sink::File(file) => file.write(line().as_bytes()),
+			#[cfg(unix)]
+			Sink::Syslog(socket) => socket.send(record.syslog(&self.tag).as_bytes()).map(drop),
+			#[cfg(unix)]
+			Sink::Journald(socket) => socket.send(&record.journald(&self.tag)).map(drop),
+		}
+	}
+
+
+	/// Log a message, if the level is enabled. IO errors are returned as error values.
+	fn log(&mut self, context: &CallContext, level: Level) -> Result<Value, Panic> {
+		let (message, fields) = match context.args() {
+			[ message ] => (message, None),
+			[ message, Value::Dict(ref fields) ] => (message, Some(fields)),
+
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos.copy())),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+		};
+
+		if level < self.level {
+			return Ok(Value::default());
+		}
+
+		let record = Record::new(level, message, fields, context.interner());
+
+		Ok(
+			self.write(&record)
+				.map_err(|error| Error::new(error.to_string().into(), message.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// Parse the options of std.log.new.
+fn options(context: &CallContext, options: Option<&Dict>) -> Result<Result<Logger, Error>, Panic> {
+	let pos = || context.pos.copy();
+
+	let option = |name: &str| options
+		.and_then(|options| options.get(&name.into()).ok())
+		.unwrap_or_default();
+
+	let string = |name: &str| -> Result<Option<Vec<u8>>, Panic> {
+		match option(name) {
+			Value::Nil => Ok(None),
+			Value::String(ref string) => Ok(Some(string.as_bytes().to_owned())),
+			other => Err(Panic::type_error(other, "string", pos())),
+		}
+	};
+
+	let int = |name: &str| -> Result<Option<u64>, Panic> {
+		match option(name) {
+			Value::Nil => Ok(None),
+			Value::Int(int) if int >= 0 => Ok(Some(int as u64)),
+			value @ Value::Int(_) => Err(Panic::value_error(value, "non-negative integer", pos())),
+			other => Err(Panic::type_error(other, "int", pos())),
+		}
+	};
+
+	let level = match string("level")? {
+		None => Level::Info,
+		Some(name) => Level::parse(&name)
+			.ok_or_else(|| Panic::value_error(option("level"), "debug, info, warn or error", pos()))?,
+	};
+
+	let json = match option("json") {
+		Value::Nil => false,
+		Value::Bool(json) => json,
+		other => return Err(Panic::type_error(other, "bool", pos())),
+	};
+
+	let tag = string("tag")?
+		.map(|tag| String::from_utf8_lossy(&tag).into_owned())
+		.unwrap_or_else(|| "hush".into());
+
+	let sink = match string("sink")?.as_deref() {
+		None | Some(b"stderr") => Ok(Sink::Stderr),
+
+		Some(b"file") => {
+			let path = match option("path") {
+				Value::String(ref path) => context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path))).into_owned(),
+				other => return Err(Panic::type_error(other, "string", pos())),
+			};
+
+			context.runtime.options.policy.check_path(&path, pos())?;
+
+			let max_size = int("max_size")?;
+			let max_files = int("max_files")?.unwrap_or(5).min(u32::MAX as u64) as u32;
+
+			LogFile::open(path, max_size, max_files)
+				.map(Sink::File)
+				.map_err(|error| Error::new(error.to_string().into(), option("path")))
+		}
+
+		#[cfg(windows)]
+		Some(b"syslog" | b"journald") => Err(
+			Error::new(io::Error::from(io::ErrorKind::Unsupported).to_string().into(), option("sink"))
+		),
+
+		#[cfg(unix)]
+		Some(b"syslog") => UnixDatagram::unbound()
+			.and_then(|socket| socket.connect("/dev/log").map(|()| Sink::Syslog(socket)))
+			.map_err(|error| Error::new(error.to_string().into(), "/dev/log".into())),
+
+		#[cfg(unix)]
+		Some(b"journald") => UnixDatagram::unbound()
+			.and_then(|socket| socket.connect("/run/systemd/journal/socket").map(|()| Sink::Journald(socket)))
+			.map_err(|error| Error::new(error.to_string().into(), "/run/systemd/journal/socket".into())),
+
+		Some(_) => return Err(Panic::value_error(option("sink"), "stderr, file, syslog or journald", pos())),
+	};
+
+	Ok(
+		sink.map(
+			|sink| Logger {
+				level: Level::from_env().unwrap_or(level),
+				json,
+				tag,
+				sink,
+			}
+		)
+	)
+}
+
+
+/// Create a logger, returning a value with debug, info, warn and error methods. Each
+/// takes a message and an optional dict of fields. Options are:
+/// - level: the minimum level to log, which defaults to info. The HUSH_LOG environment
+///   variable takes precedence, so that verbosity may be changed without editing scripts.
+/// - sink: stderr (the default), file, syslog or journald. Syslog and journald are not
+///   supported on Windows.
+/// - path, max_size and max_files: the log file, which is rotated when it would exceed
+///   max_size bytes, keeping max_files old files (5 by default).
+/// - json: whether to write lines of JSON instead of text.
+/// - tag: the program name for syslog and journald, which defaults to hush.
+/// Errors opening the sink are returned as error values.
+#[derive(Trace, Finalize)]
+struct New;
+
+impl NativeFun for New {
+	fn name(&self) -> &'static str { "std.log.new" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let opts = match context.args() {
+			[] => None,
+			[ Value::Dict(ref options) ] => Some(options),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let logger = match options(&context, opts)? {
+			Ok(logger) => Rc::new(RefCell::new(logger)),
+			Err(error) => return Ok(error.into()),
+		};
+
+		let mut methods = DictMap::default();
+
+		for level in IntoIterator::into_iter([ Level::Debug, Level::Info, Level::Warn, Level::Error ]) {
+			methods.insert(level.name().into(), LogImpl { logger: logger.clone(), level }.into());
+		}
+
+		Ok(Dict::new(methods).into())
+	}
+}
+
+
+/// Log a message to stderr, with an optional dict of fields. Messages below the level
+/// set by the HUSH_LOG environment variable, or info by default, are ignored.
+#[derive(Finalize)]
+struct Log(Level);
+
+impl NativeFun for Log {
+	fn name(&self) -> &'static str {
+		match self.0 {
+			Level::Debug => "std.log.debug",
+			Level::Info => "std.log.info",
+			Level::Warn => "std.log.warn",
+			Level::Error => "std.log.error",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Logger::stderr().log(&context, self.0)
+	}
+}
+
+
+/// Log a message with the configured logger.
+#[derive(Finalize)]
+struct LogImpl {
+	logger: Rc<RefCell<Logger>>,
+	level: Level,
+}
+
+impl NativeFun for LogImpl {
+	fn name(&self) -> &'static str {
+		match self.level {
+			Level::Debug => "std.log.logger<debug>",
+			Level::Info => "std.log.logger<info>",
+			Level::Warn => "std.log.logger<warn>",
+			Level::Error => "std.log.logger<error>",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		self.logger.borrow_mut().log(&context, self.level)
+	}
+}
+
+
+// These have no garbage-collected fields.
+unsafe impl Trace for Log { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for LogImpl { gc::unsafe_empty_trace!(); }
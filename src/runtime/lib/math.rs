@@ -0,0 +1,336 @@
+use std::convert::TryFrom;
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	CallContext,
+	Constant,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Sqrt) }
+inventory::submit! { RustFun::from(Exp) }
+inventory::submit! { RustFun::from(Sin) }
+inventory::submit! { RustFun::from(Cos) }
+inventory::submit! { RustFun::from(Tan) }
+inventory::submit! { RustFun::from(Asin) }
+inventory::submit! { RustFun::from(Acos) }
+inventory::submit! { RustFun::from(Atan) }
+inventory::submit! { RustFun::from(Atan2) }
+inventory::submit! { RustFun::from(Pow) }
+inventory::submit! { RustFun::from(Log) }
+inventory::submit! { RustFun::from(Floor) }
+inventory::submit! { RustFun::from(Ceil) }
+inventory::submit! { RustFun::from(Round) }
+inventory::submit! { RustFun::from(Abs) }
+inventory::submit! { RustFun::from(Min) }
+inventory::submit! { RustFun::from(Max) }
+inventory::submit! { RustFun::from(Clamp) }
+
+inventory::submit! { Constant { name: "std.math.pi", value: || std::f64::consts::PI.into() } }
+inventory::submit! { Constant { name: "std.math.e", value: || std::f64::consts::E.into() } }
+
+
+/// Get a number argument as a float, accepting ints as well.
+fn float(value: &Value, pos: SourcePos) -> Result<f64, Panic> {
+	match value {
+		Value::Int(int) => Ok(*int as f64),
+		Value::Float(float) => Ok(float.0),
+		other => Err(Panic::type_error(other.copy(), "int or float", pos)),
+	}
+}
+
+
+/// Define a function of a single number, which always produces a float.
+macro_rules! unary {
+	($struct: ident, $name: literal, $fun: expr) => {
+		#[derive(Trace, Finalize)]
+		struct $struct;
+
+		impl NativeFun for $struct {
+			fn name(&self) -> &'static str { $name }
+
+			fn call(&self, context: CallContext) -> Result<Value, Panic> {
+				match context.args() {
+					[ value ] => {
+						let fun: fn(f64) -> f64 = $fun;
+						Ok(fun(float(value, context.pos.copy())?).into())
+					}
+
+					args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+				}
+			}
+		}
+	};
+}
+
+
+unary!(Sqrt, "std.math.sqrt", f64::sqrt);
+unary!(Exp, "std.math.exp", f64::exp);
+unary!(Sin, "std.math.sin", f64::sin);
+unary!(Cos, "std.math.cos", f64::cos);
+unary!(Tan, "std.math.tan", f64::tan);
+unary!(Asin, "std.math.asin", f64::asin);
+unary!(Acos, "std.math.acos", f64::acos);
+unary!(Atan, "std.math.atan", f64::atan);
+
+
+/// The angle of the point (x, y), taking y first as in C.
+#[derive(Trace, Finalize)]
+struct Atan2;
+
+impl NativeFun for Atan2 {
+	fn name(&self) -> &'static str { "std.math.atan2" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ y, x ] => {
+				let y = float(y, context.pos.copy())?;
+				let x = float(x, context.pos.copy())?;
+				Ok(y.atan2(x).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Raise a number to a power. Ints raised to non-negative ints produce ints, panicking on
+/// overflow. Otherwise, the result is a float.
+#[derive(Trace, Finalize)]
+struct Pow;
+
+impl NativeFun for Pow {
+	fn name(&self) -> &'static str { "std.math.pow" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(base), exponent @ Value::Int(int) ] if *int >= 0 => u32::try_from(*int)
+				.ok()
+				.and_then(|int| base.checked_pow(int))
+				.map(Value::Int)
+				.ok_or_else(|| Panic::value_error(exponent.copy(), "exponent within int range", context.pos.copy())),
+
+			[ base, exponent ] => {
+				let base = float(base, context.pos.copy())?;
+				let exponent = float(exponent, context.pos.copy())?;
+				Ok(base.powf(exponent).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// The logarithm of a number, in the given base. Defaults to the natural logarithm.
+#[derive(Trace, Finalize)]
+struct Log;
+
+impl NativeFun for Log {
+	fn name(&self) -> &'static str { "std.math.log" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => Ok(float(value, context.pos.copy())?.ln().into()),
+
+			[ value, base ] => {
+				let value = float(value, context.pos.copy())?;
+				let base = float(base, context.pos.copy())?;
+				Ok(value.log(base).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Round down. Ints are returned unchanged.
+#[derive(Trace, Finalize)]
+struct Floor;
+
+impl NativeFun for Floor {
+	fn name(&self) -> &'static str { "std.math.floor" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(int) ] => Ok(Value::Int(*int)),
+			[ Value::Float(float) ] => Ok(float.0.floor().into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Round up. Ints are returned unchanged.
+#[derive(Trace, Finalize)]
+struct Ceil;
+
+impl NativeFun for Ceil {
+	fn name(&self) -> &'static str { "std.math.ceil" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(int) ] => Ok(Value::Int(*int)),
+			[ Value::Float(float) ] => Ok(float.0.ceil().into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Round to the nearest value with the given number of decimal digits, which defaults to
+/// zero and may be negative. Halfway cases are rounded away from zero. Ints are returned
+/// unchanged.
+#[derive(Trace, Finalize)]
+struct Round;
+
+impl NativeFun for Round {
+	fn name(&self) -> &'static str { "std.math.round" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, digits) = match context.args() {
+			[ value ] => (value, 0),
+			[ value, Value::Int(digits) ] => (value, *digits),
+
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		match value {
+			Value::Int(int) => Ok(Value::Int(*int)),
+			Value::Float(float) if digits == 0 => Ok(float.0.round().into()),
+			Value::Float(float) => {
+				let digits = i32::try_from(digits.clamp(-400, 400)).expect("clamped digits fit in i32");
+				let scale = 10f64.powi(digits);
+				let rounded = (float.0 * scale).round() / scale;
+
+				// Scaling may overflow for large digit counts, in which case the value is
+				// already exact.
+				Ok(if rounded.is_finite() { rounded } else { float.0 }.into())
+			}
+
+			other => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+		}
+	}
+}
+
+
+/// The absolute value, panicking if it doesn't fit in an int.
+#[derive(Trace, Finalize)]
+struct Abs;
+
+impl NativeFun for Abs {
+	fn name(&self) -> &'static str { "std.math.abs" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::Int(int) ] => int
+				.checked_abs()
+				.map(Value::Int)
+				.ok_or_else(|| Panic::value_error(value.copy(), "int with absolute value in range", context.pos.copy())),
+			[ Value::Float(float) ] => Ok(float.0.abs().into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Find the element of a non-empty array of numbers which is preferred by the given
+/// comparison. Ints and floats may be mixed, in which case they are compared as floats.
+fn extreme(context: CallContext, prefer: fn(f64, f64) -> bool) -> Result<Value, Panic> {
+	let array = match context.args() {
+		[ Value::Array(ref array) ] => array.copy(),
+
+		[ other ] => return Err(Panic::type_error(other.copy(), "array", context.pos.copy())),
+		args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+	};
+
+	let array = array.borrow();
+	let mut values = array.iter();
+
+	let mut best = match values.next() {
+		Some(value) => value,
+		None => return Err(Panic::empty_collection(context.pos.copy())),
+	};
+	let mut best_float = float(best, context.pos.copy())?;
+
+	for value in values {
+		let value_float = float(value, context.pos.copy())?;
+
+		if prefer(value_float, best_float) {
+			best = value;
+			best_float = value_float;
+		}
+	}
+
+	Ok(best.copy())
+}
+
+
+/// The smallest number in an array.
+#[derive(Trace, Finalize)]
+struct Min;
+
+impl NativeFun for Min {
+	fn name(&self) -> &'static str { "std.math.min" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		extreme(context, |value, best| value < best)
+	}
+}
+
+
+/// The largest number in an array.
+#[derive(Trace, Finalize)]
+struct Max;
+
+impl NativeFun for Max {
+	fn name(&self) -> &'static str { "std.math.max" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		extreme(context, |value, best| value > best)
+	}
+}
+
+
+/// Restrict a number to the given inclusive range. If any argument is a float, the result
+/// is a float.
+#[derive(Trace, Finalize)]
+struct Clamp;
+
+impl NativeFun for Clamp {
+	fn name(&self) -> &'static str { "std.math.clamp" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, min, max ] => {
+				let numbers = util::Numbers::promote([value.copy(), min.copy(), max.copy()])
+					.map_err(|value| Panic::type_error(value, "int or float", context.pos.copy()))?;
+
+				match numbers {
+					util::Numbers::Ints([ value, min, max ]) if min <= max => Ok(value.clamp(min, max).into()),
+					util::Numbers::Floats([ value, min, max ]) if min.0 <= max.0 => Ok(value.0.clamp(min.0, max.0).into()),
+					_ => Err(Panic::value_error(max.copy(), "maximum not less than the minimum", context.pos)),
+				}
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
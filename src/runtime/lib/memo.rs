@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use gc::{Finalize, GcCell, Trace};
+
+use super::{
+	Array,
+	CallContext,
+	Function,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Memo) }
+
+
+/// Wrap a function so that it's called only once for each distinct set of arguments,
+/// which are compared by value. Later calls return the first result. Panics are not
+/// cached.
+#[derive(Trace, Finalize)]
+struct Memo;
+
+impl NativeFun for Memo {
+	fn name(&self) -> &'static str { "std.memo" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Function(fun) ] => Ok(
+				MemoImpl {
+					function: fun.copy(),
+					results: GcCell::new(HashMap::new()),
+				}.into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct MemoImpl {
+	function: Function,
+	/// Results, by the array of arguments.
+	results: GcCell<HashMap<Value, Value>>,
+}
+
+impl NativeFun for MemoImpl {
+	fn name(&self) -> &'static str { "std.memo<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let key: Value = Array::new(context.args().iter().map(Value::copy).collect()).into();
+
+		if let Some(result) = self.results.borrow().get(&key) {
+			return Ok(result.copy());
+		}
+
+		let result = context.call(Value::default(), &self.function, context.args_start)?;
+
+		self.results.borrow_mut().insert(key, result.copy());
+
+		Ok(result)
+	}
+}
@@ -0,0 +1,1190 @@
+use std::{
+	cell::RefCell,
+	convert::TryFrom,
+	io::{self, BufRead, BufReader, Read, Write},
+	net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket},
+	rc::Rc,
+	time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+	ffi::OsStr,
+	os::unix::{
+		io::AsRawFd,
+		net::{self as unix, UnixDatagram, UnixListener, UnixStream},
+	},
+	path::Path,
+};
+#[cfg(windows)]
+use std::os::windows::io::AsRawSocket;
+
+use gc::{Finalize, Trace};
+use socket2::{Domain, Protocol, Type};
+
+use crate::runtime::{command::Source, SourcePos};
+
+use super::{
+	util,
+	wait::Wait,
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Connect) }
+inventory::submit! { RustFun::from(Listen) }
+inventory::submit! { RustFun::from(Udp) }
+#[cfg(unix)]
+inventory::submit! { RustFun::from(UnixConnect) }
+#[cfg(unix)]
+inventory::submit! { RustFun::from(UnixListen) }
+#[cfg(unix)]
+inventory::submit! { RustFun::from(UnixBind) }
+
+
+/// A connected stream socket.
+enum Stream {
+	Tcp(TcpStream),
+	#[cfg(unix)]
+	Unix(UnixStream),
+}
+
+
+impl Stream {
+	/// Set the timeout of reads and writes.
+	fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+		match self {
+			Self::Tcp(stream) => {
+				stream.set_read_timeout(timeout)?;
+				stream.set_write_timeout(timeout)
+			}
+
+			#[cfg(unix)]
+			Self::Unix(stream) => {
+				stream.set_read_timeout(timeout)?;
+				stream.set_write_timeout(timeout)
+			}
+		}
+	}
+
+
+	/// The timeout of reads.
+	fn timeout(&self) -> io::Result<Option<Duration>> {
+		match self {
+			Self::Tcp(stream) => stream.read_timeout(),
+			#[cfg(unix)]
+			Self::Unix(stream) => stream.read_timeout(),
+		}
+	}
+
+
+	/// The socket, to be waited by the reactor thread.
+	fn source(&self) -> Source {
+		match self {
+			#[cfg(unix)]
+			Self::Tcp(stream) => stream.as_raw_fd(),
+			#[cfg(windows)]
+			Self::Tcp(stream) => stream.as_raw_socket() as Source,
+			#[cfg(unix)]
+			Self::Unix(stream) => stream.as_raw_fd(),
+		}
+	}
+
+
+	fn peer_address(&self) -> io::Result<Value> {
+		match self {
+			Self::Tcp(stream) => stream.peer_addr().map(|address| address.to_string().into()),
+			#[cfg(unix)]
+			Self::Unix(stream) => stream.peer_addr().map(|address| unix_address(&address)),
+		}
+	}
+
+
+	fn local_address(&self) -> io::Result<Value> {
+		match self {
+			Self::Tcp(stream) => stream.local_addr().map(|address| address.to_string().into()),
+			#[cfg(unix)]
+			Self::Unix(stream) => stream.local_addr().map(|address| unix_address(&address)),
+		}
+	}
+}
+
+
+impl Read for Stream {
+	fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+		match self {
+			Self::Tcp(stream) => stream.read(buffer),
+			#[cfg(unix)]
+			Self::Unix(stream) => stream.read(buffer),
+		}
+	}
+}
+
+
+impl Write for Stream {
+	fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+		match self {
+			Self::Tcp(stream) => stream.write(buffer),
+			#[cfg(unix)]
+			Self::Unix(stream) => stream.write(buffer),
+		}
+	}
+
+
+	fn flush(&mut self) -> io::Result<()> {
+		match self {
+			Self::Tcp(stream) => stream.flush(),
+			#[cfg(unix)]
+			Self::Unix(stream) => stream.flush(),
+		}
+	}
+}
+
+
+/// A listening stream socket.
+enum Server {
+	Tcp(TcpListener),
+	#[cfg(unix)]
+	Unix(UnixListener),
+}
+
+
+impl Server {
+	fn accept(&self) -> io::Result<Stream> {
+		match self {
+			Self::Tcp(listener) => listener.accept().map(|(stream, _)| Stream::Tcp(stream)),
+			#[cfg(unix)]
+			Self::Unix(listener) => listener.accept().map(|(stream, _)| Stream::Unix(stream)),
+		}
+	}
+
+
+	/// The socket, to be waited by the reactor thread.
+	fn source(&self) -> Source {
+		match self {
+			#[cfg(unix)]
+			Self::Tcp(listener) => listener.as_raw_fd(),
+			#[cfg(windows)]
+			Self::Tcp(listener) => listener.as_raw_socket() as Source,
+			#[cfg(unix)]
+			Self::Unix(listener) => listener.as_raw_fd(),
+		}
+	}
+
+
+	fn local_address(&self) -> io::Result<Value> {
+		match self {
+			Self::Tcp(listener) => listener.local_addr().map(|address| address.to_string().into()),
+			#[cfg(unix)]
+			Self::Unix(listener) => listener.local_addr().map(|address| unix_address(&address)),
+		}
+	}
+}
+
+
+/// A datagram socket.
+enum Datagram {
+	Udp(UdpSocket),
+	#[cfg(unix)]
+	Unix(UnixDatagram),
+}
+
+
+impl Datagram {
+	fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+		match self {
+			Self::Udp(socket) => {
+				socket.set_read_timeout(timeout)?;
+				socket.set_write_timeout(timeout)
+			}
+
+			#[cfg(unix)]
+			Self::Unix(socket) => {
+				socket.set_read_timeout(timeout)?;
+				socket.set_write_timeout(timeout)
+			}
+		}
+	}
+
+
+	fn local_address(&self) -> io::Result<Value> {
+		match self {
+			Self::Udp(socket) => socket.local_addr().map(|address| address.to_string().into()),
+			#[cfg(unix)]
+			Self::Unix(socket) => socket.local_addr().map(|address| unix_address(&address)),
+		}
+	}
+}
+
+
+/// A connected socket, shared by the methods of a socket value. The socket is closed when
+/// all methods are garbage collected, or when explicitly closed.
+type Socket = Rc<RefCell<Option<BufReader<Stream>>>>;
+
+
+/// A listening socket, shared by the methods of a listener value.
+type Listener = Rc<RefCell<Option<Server>>>;
+
+
+/// A datagram socket, shared by the methods of a datagram socket value.
+type DatagramSocket = Rc<RefCell<Option<Datagram>>>;
+
+
+/// Run an operation on an open socket.
+fn with_socket<S, T, F>(socket: &Rc<RefCell<Option<S>>>, operation: F) -> io::Result<T>
+where
+	F: FnOnce(&mut S) -> io::Result<T>,
+{
+	match socket.borrow_mut().as_mut() {
+		Some(socket) => operation(socket),
+		None => Err(io::Error::new(io::ErrorKind::Other, "socket is closed")),
+	}
+}
+
+
+/// Read up to the given number of bytes, returning as soon as some data is available. If
+/// no count is given, read until the peer closes the connection.
+fn read(stream: &mut BufReader<Stream>, count: Option<usize>) -> io::Result<Option<Str>> {
+	let buffer = match count {
+		Some(count) => {
+			let available = stream.fill_buf()?;
+			let buffer = available[.. available.len().min(count)].to_vec();
+			stream.consume(buffer.len());
+			buffer
+		}
+
+		None => {
+			let mut buffer = Vec::new();
+			stream.read_to_end(&mut buffer)?;
+			buffer
+		}
+	};
+
+	Ok(if buffer.is_empty() { None } else { Some(Str::from(buffer)) })
+}
+
+
+/// Get a positive count of bytes.
+fn count(value: &Value, pos: SourcePos) -> Result<usize, Panic> {
+	match value {
+		Value::Int(count) if *count > 0 => Ok(*count as usize),
+		count @ Value::Int(_) => Err(Panic::value_error(count.copy(), "positive integer", pos)),
+		other => Err(Panic::type_error(other.copy(), "int", pos)),
+	}
+}
+
+
+/// The address of a Unix socket is it's path, or nil if it's unnamed.
+#[cfg(unix)]
+fn unix_address(address: &unix::SocketAddr) -> Value {
+	address
+		.as_pathname()
+		.map(|path| Str::from(path.to_owned()))
+		.into()
+}
+
+
+/// Get an optional timeout, in seconds. Nil means no timeout.
+fn timeout(value: &Value, pos: SourcePos) -> Result<Option<Duration>, Panic> {
+	match value {
+		Value::Nil => Ok(None),
+		value => match util::duration(value, pos.copy())? {
+			duration if duration.is_zero() => Err(Panic::value_error(value.copy(), "positive number", pos)),
+			duration => Ok(Some(duration)),
+		},
+	}
+}
+
+
+/// Create a socket value with methods. Read and write operations time out after the given
+/// duration, if any.
+fn socket(stream: Stream, timeout: Option<Duration>) -> io::Result<Value> {
+	stream.set_timeout(timeout)?;
+
+	let peer = stream.peer_address()?;
+	let local = stream.local_address()?;
+
+	let socket: Socket = Rc::new(RefCell::new(Some(BufReader::new(stream))));
+
+	Ok(
+		util::dict([
+			("peer", peer),
+			("local", local),
+			("read", ReadImpl(socket.clone()).into()),
+			("read_line", ReadLineImpl(socket.clone()).into()),
+			("read_async", ReadAsyncImpl(socket.clone()).into()),
+			("write", WriteImpl(socket.clone()).into()),
+			("set_timeout", SetTimeoutImpl(socket.clone()).into()),
+			("close", CloseImpl(socket).into()),
+		])
+	)
+}
+
+
+/// Create a listener value with methods.
+fn listener(server: Server) -> io::Result<Value> {
+	let address = server.local_address()?;
+
+	let listener: Listener = Rc::new(RefCell::new(Some(server)));
+
+	Ok(
+		util::dict([
+			("address", address),
+			("accept", AcceptImpl(listener.clone()).into()),
+			("accept_async", AcceptAsyncImpl(listener.clone()).into()),
+			("close", CloseListenerImpl(listener).into()),
+		])
+	)
+}
+
+
+/// Create a datagram socket value with methods.
+fn datagram(socket: Datagram) -> io::Result<Value> {
+	let address = socket.local_address()?;
+
+	let socket: DatagramSocket = Rc::new(RefCell::new(Some(socket)));
+
+	Ok(
+		util::dict([
+			("address", address),
+			("send", SendImpl(socket.clone()).into()),
+			("receive", ReceiveImpl(socket.clone()).into()),
+			("set_timeout", SetDatagramTimeoutImpl(socket.clone()).into()),
+			("close", CloseDatagramImpl(socket).into()),
+		])
+	)
+}
+
+
+/// Get a path argument, resolved in the working directory of commands. Paths denied by
+/// the sandbox policy cause a panic.
+#[cfg(unix)]
+fn path<'a>(context: &'a CallContext, value: &'a Value) -> Result<std::borrow::Cow<'a, Path>, Panic> {
+	let path = match value {
+		Value::String(string) => context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(string))),
+		_ => unreachable!("path must be a string"),
+	};
+
+	context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+
+	Ok(path)
+}
+
+
+/// Panic unless the sandbox policy allows network access to the host of the address.
+fn check_address(context: &CallContext, address: &Value) -> Result<(), Panic> {
+	match address {
+		Value::String(address) => context.runtime.options.policy.check_address(
+			&String::from_utf8_lossy(address.as_bytes()),
+			context.pos.copy(),
+		),
+		_ => unreachable!("address must be a string"),
+	}
+}
+
+
+/// Connect to a TCP server, returning a socket with methods. The host may be a name or an
+/// address. The timeout option applies to connecting and to each read or write.
+#[derive(Trace, Finalize)]
+struct Connect;
+
+impl Connect {
+	fn connect(host: &str, port: u16, timeout: Option<Duration>) -> io::Result<TcpStream> {
+		let timeout = match timeout {
+			Some(timeout) => timeout,
+			None => return TcpStream::connect((host, port)),
+		};
+
+		let addresses: Vec<SocketAddr> = (host, port).to_socket_addrs()?.collect();
+
+		let mut error = io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to");
+
+		// As in TcpStream::connect, try each address in order.
+		for address in addresses {
+			match TcpStream::connect_timeout(&address, timeout) {
+				Ok(stream) => return Ok(stream),
+				Err(err) => error = err,
+			}
+		}
+
+		Err(error)
+	}
+}
+
+impl NativeFun for Connect {
+	fn name(&self) -> &'static str { "std.net.connect" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (host, port, options) = match context.args() {
+			[ Value::String(ref host), Value::Int(port) ] => (host, port, None),
+			[ Value::String(ref host), Value::Int(port), Value::Dict(ref options) ] => (host, port, Some(options)),
+
+			[ Value::String(_), Value::Int(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ Value::String(_), other ] | [ Value::String(_), other, _ ] => {
+				return Err(Panic::type_error(other.copy(), "int", context.pos))
+			}
+			[ other, _ ] | [ other, _, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let port = u16::try_from(*port)
+			.map_err(|_| Panic::value_error(Value::Int(*port), "port number", context.pos.copy()))?;
+
+		let timeout = match options {
+			Some(options) => timeout(&options.get(&"timeout".into()).unwrap_or_default(), context.pos.copy())?,
+			None => None,
+		};
+
+		let address = Value::from(format!("{}:{}", String::from_utf8_lossy(host.as_bytes()), port));
+
+		let host = match std::str::from_utf8(host.as_bytes()) {
+			Ok(host) => host,
+			Err(_) => return Ok(Error::new("invalid host".into(), Value::String(host.copy())).into()),
+		};
+
+		context.runtime.options.policy.check_host(host, context.pos.copy())?;
+
+		Ok(
+			Self::connect(host, port, timeout)
+				.and_then(|stream| socket(Stream::Tcp(stream), timeout))
+				.map_err(|error| Error::new(error.to_string().into(), address))
+				.into()
+		)
+	}
+}
+
+
+/// Listen for TCP connections on an address such as `127.0.0.1:8080`, returning a
+/// listener with methods. Port 0 picks a free port, which is available in the address
+/// field. The reuseaddr option defaults to true, and the backlog option to 128.
+#[derive(Trace, Finalize)]
+struct Listen;
+
+impl Listen {
+	fn bind(address: SocketAddr, reuse_address: bool, backlog: i32) -> io::Result<TcpListener> {
+		let socket = socket2::Socket::new(Domain::for_address(address), Type::STREAM, Some(Protocol::TCP))?;
+
+		socket.set_reuse_address(reuse_address)?;
+		socket.bind(&address.into())?;
+		socket.listen(backlog)?;
+
+		Ok(socket.into())
+	}
+
+
+	fn listen(address: &str, reuse_address: bool, backlog: i32) -> io::Result<Value> {
+		let mut error = io::Error::new(io::ErrorKind::InvalidInput, "no addresses to listen on");
+		let mut listener = None;
+
+		// As in TcpListener::bind, try each address in order.
+		for address in address.to_socket_addrs()? {
+			match Self::bind(address, reuse_address, backlog) {
+				Ok(bound) => {
+					listener = Some(bound);
+					break;
+				}
+				Err(err) => error = err,
+			}
+		}
+
+		listener
+			.ok_or(error)
+			.map(Server::Tcp)
+			.and_then(self::listener)
+	}
+}
+
+impl NativeFun for Listen {
+	fn name(&self) -> &'static str { "std.net.listen" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, options) = match context.args() {
+			[ value @ Value::String(_) ] => (value, None),
+			[ value @ Value::String(_), Value::Dict(ref options) ] => (value, Some(options)),
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let option = |name: &str| options
+			.map(|options| options.get(&name.into()).unwrap_or_default())
+			.unwrap_or_default();
+
+		let reuse_address = match option("reuseaddr") {
+			Value::Nil => true,
+			Value::Bool(reuse_address) => reuse_address,
+			other => return Err(Panic::type_error(other, "bool", context.pos)),
+		};
+
+		let backlog = match option("backlog") {
+			Value::Nil => 128,
+			Value::Int(backlog) if (1 ..= i32::MAX as i64).contains(&backlog) => backlog as i32,
+			backlog @ Value::Int(_) => return Err(Panic::value_error(backlog, "positive integer", context.pos)),
+			other => return Err(Panic::type_error(other, "int", context.pos)),
+		};
+
+		check_address(&context, value)?;
+
+		let address = match value {
+			Value::String(address) => std::str::from_utf8(address.as_bytes()),
+			_ => unreachable!("address must be a string"),
+		};
+
+		Ok(
+			address
+				.map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid address"))
+				.and_then(|address| Self::listen(address, reuse_address, backlog))
+				.map_err(|error| Error::new(error.to_string().into(), value.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// Bind a UDP socket to an address, which defaults to `0.0.0.0:0`, returning a datagram
+/// socket with methods.
+#[derive(Trace, Finalize)]
+struct Udp;
+
+impl NativeFun for Udp {
+	fn name(&self) -> &'static str { "std.net.udp" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let value = match context.args() {
+			[] => Value::from("0.0.0.0:0"),
+			[ value @ Value::String(_) ] => value.copy(),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		check_address(&context, &value)?;
+
+		let address = match &value {
+			Value::String(address) => std::str::from_utf8(address.as_bytes()),
+			_ => unreachable!("address must be a string"),
+		};
+
+		Ok(
+			address
+				.map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid address"))
+				.and_then(UdpSocket::bind)
+				.map(Datagram::Udp)
+				.and_then(datagram)
+				.map_err(|error| Error::new(error.to_string().into(), value.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// Connect to a Unix domain stream socket, returning a socket with the same methods as
+/// std.net.connect. The timeout option applies to each read or write.
+#[cfg(unix)]
+#[derive(Trace, Finalize)]
+struct UnixConnect;
+
+#[cfg(unix)]
+impl NativeFun for UnixConnect {
+	fn name(&self) -> &'static str { "std.net.unix.connect" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, options) = match context.args() {
+			[ value @ Value::String(_) ] => (value, None),
+			[ value @ Value::String(_), Value::Dict(ref options) ] => (value, Some(options)),
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let timeout = match options {
+			Some(options) => timeout(&options.get(&"timeout".into()).unwrap_or_default(), context.pos.copy())?,
+			None => None,
+		};
+
+		let path = path(&context, value)?;
+
+		Ok(
+			UnixStream::connect(path)
+				.and_then(|stream| socket(Stream::Unix(stream), timeout))
+				.map_err(|error| Error::new(error.to_string().into(), value.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// Listen for connections on a Unix domain stream socket, returning a listener with the
+/// same methods as std.net.listen. The socket file must not exist, and is not removed
+/// when the listener is closed.
+#[cfg(unix)]
+#[derive(Trace, Finalize)]
+struct UnixListen;
+
+#[cfg(unix)]
+impl NativeFun for UnixListen {
+	fn name(&self) -> &'static str { "std.net.unix.listen" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let value = match context.args() {
+			[ value @ Value::String(_) ] => value,
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let path = path(&context, value)?;
+
+		Ok(
+			UnixListener::bind(path)
+				.map(Server::Unix)
+				.and_then(listener)
+				.map_err(|error| Error::new(error.to_string().into(), value.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// Create a Unix domain datagram socket, returning a datagram socket with the same
+/// methods as std.net.udp. If a path is given, the socket is bound to it, so that it may
+/// receive datagrams. The socket file must not exist.
+#[cfg(unix)]
+#[derive(Trace, Finalize)]
+struct UnixBind;
+
+#[cfg(unix)]
+impl NativeFun for UnixBind {
+	fn name(&self) -> &'static str { "std.net.unix.datagram" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let result = match context.args() {
+			[] => UnixDatagram::unbound()
+				.map(Datagram::Unix)
+				.and_then(datagram)
+				.map_err(Error::from),
+
+			[ value @ Value::String(_) ] => {
+				let path = path(&context, value)?;
+
+				UnixDatagram::bind(path)
+					.map(Datagram::Unix)
+					.and_then(datagram)
+					.map_err(|error| Error::new(error.to_string().into(), value.copy()))
+			}
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Ok(result.into())
+	}
+}
+
+
+/// Wait for a connection, returning a socket with the same methods as std.net.connect.
+/// The optional timeout applies to reads and writes in the returned socket.
+#[derive(Finalize)]
+struct AcceptImpl(Listener);
+
+impl NativeFun for AcceptImpl {
+	fn name(&self) -> &'static str { "std.net.listen<accept>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let timeout = match context.args() {
+			[] => None,
+			[ value ] => timeout(value, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = match self.0.borrow().as_ref() {
+			Some(listener) => listener
+				.accept()
+				.and_then(|stream| socket(stream, timeout)),
+			None => Err(io::Error::new(io::ErrorKind::Other, "listener is closed")),
+		};
+
+		Ok(result.into())
+	}
+}
+
+
+/// Wait for a connection asynchronously, returning an asynchronous value, like an
+/// asynchronous command block. It's join method returns the same as the accept method,
+/// and it's is_finished method checks whether a connection is pending. The listener is
+/// waited by the same thread as command blocks.
+#[derive(Finalize)]
+struct AcceptAsyncImpl(Listener);
+
+impl NativeFun for AcceptAsyncImpl {
+	fn name(&self) -> &'static str { "std.net.listen<accept_async>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let timeout = match context.args() {
+			[] => None,
+			[ value ] => timeout(value, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let wait = match self.0.borrow().as_ref() {
+			Some(listener) => Wait::new(Some(listener.source()), None),
+			None => return Ok(Error::new("listener is closed".into(), Value::default()).into()),
+		};
+
+		Ok(
+			wait.value(
+				AcceptJoinImpl { listener: self.0.clone(), timeout, wait: wait.clone() }.into()
+			)
+		)
+	}
+}
+
+
+#[derive(Finalize)]
+struct AcceptJoinImpl {
+	listener: Listener,
+	timeout: Option<Duration>,
+	wait: Wait,
+}
+
+impl NativeFun for AcceptJoinImpl {
+	fn name(&self) -> &'static str { "std.net.listen<accept_async><join>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		self.wait.join(context.pos)?;
+
+		let result = match self.listener.borrow().as_ref() {
+			Some(listener) => listener
+				.accept()
+				.and_then(|stream| socket(stream, self.timeout)),
+			None => Err(io::Error::new(io::ErrorKind::Other, "listener is closed")),
+		};
+
+		Ok(result.into())
+	}
+}
+
+
+/// Stop listening. Established connections are unaffected.
+#[derive(Finalize)]
+struct CloseListenerImpl(Listener);
+
+impl NativeFun for CloseListenerImpl {
+	fn name(&self) -> &'static str { "std.net.listen<close>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			match self.0.borrow_mut().take() {
+				Some(_) => Value::default(),
+				None => Error::new("listener is closed".into(), Value::default()).into(),
+			}
+		)
+	}
+}
+
+
+/// Read up to the given number of bytes, returning as soon as some data is available. If
+/// no count is given, read until the peer closes the connection. Returns nil at the end
+/// of the stream.
+#[derive(Finalize)]
+struct ReadImpl(Socket);
+
+impl NativeFun for ReadImpl {
+	fn name(&self) -> &'static str { "std.net.socket<read>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let count = match context.args() {
+			[] => None,
+			[ value ] => Some(count(value, context.pos.copy())?),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = with_socket(&self.0, |stream| read(stream, count));
+
+		Ok(result.into())
+	}
+}
+
+
+/// Read up to the given number of bytes asynchronously, returning an asynchronous value,
+/// like an asynchronous command block. It's join method returns the same as the read
+/// method, and it's is_finished method checks whether data is available. The socket is
+/// waited by the same thread as command blocks, so that any amount of sockets may be
+/// read concurrently. The timeout of the socket applies to the wait.
+#[derive(Finalize)]
+struct ReadAsyncImpl(Socket);
+
+impl NativeFun for ReadAsyncImpl {
+	fn name(&self) -> &'static str { "std.net.socket<read_async>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let count = match context.args() {
+			[ value ] => count(value, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let wait = with_socket(
+			&self.0,
+			|stream| {
+				let now = Instant::now();
+
+				// Buffered data is available right away.
+				if !stream.buffer().is_empty() {
+					return Ok(Wait::new(None, Some(now)));
+				}
+
+				let stream = stream.get_ref();
+				let deadline = stream.timeout()?.map(|timeout| now + timeout);
+
+				Ok(Wait::new(Some(stream.source()), deadline))
+			}
+		);
+
+		Ok(
+			match wait {
+				Ok(wait) => wait.value(
+					ReadJoinImpl { socket: self.0.clone(), count, wait: wait.clone() }.into()
+				),
+				Err(error) => Err::<Value, _>(error).into(),
+			}
+		)
+	}
+}
+
+
+#[derive(Finalize)]
+struct ReadJoinImpl {
+	socket: Socket,
+	count: usize,
+	wait: Wait,
+}
+
+impl NativeFun for ReadJoinImpl {
+	fn name(&self) -> &'static str { "std.net.socket<read_async><join>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let ready = self.wait.join(context.pos)?;
+
+		let result = with_socket(
+			&self.socket,
+			|stream| {
+				if !ready && stream.buffer().is_empty() {
+					return Err(io::Error::new(io::ErrorKind::TimedOut, "read timed out"));
+				}
+
+				read(stream, Some(self.count))
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Read a line, without the line terminator. Returns nil at the end of the stream.
+#[derive(Finalize)]
+struct ReadLineImpl(Socket);
+
+impl NativeFun for ReadLineImpl {
+	fn name(&self) -> &'static str { "std.net.socket<read_line>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let result = with_socket(
+			&self.0,
+			|stream| {
+				let mut line = Vec::new();
+
+				if stream.read_until(b'\n', &mut line)? == 0 {
+					return Ok(None);
+				}
+
+				if line.last() == Some(&b'\n') {
+					line.pop();
+				}
+
+				if line.last() == Some(&b'\r') {
+					line.pop();
+				}
+
+				Ok(Some(Str::from(line)))
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Write a string or byte array.
+#[derive(Finalize)]
+struct WriteImpl(Socket);
+
+impl NativeFun for WriteImpl {
+	fn name(&self) -> &'static str { "std.net.socket<write>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let data = match context.args() {
+			[ value ] => util::bytes(value)
+				.ok_or_else(|| Panic::type_error(value.copy(), "string or byte array", context.pos.copy()))?,
+
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = with_socket(&self.0, |stream| stream.get_mut().write_all(&data));
+
+		Ok(result.into())
+	}
+}
+
+
+/// Set the timeout of reads and writes, in seconds. Nil disables the timeout.
+#[derive(Finalize)]
+struct SetTimeoutImpl(Socket);
+
+impl NativeFun for SetTimeoutImpl {
+	fn name(&self) -> &'static str { "std.net.socket<set_timeout>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let timeout = match context.args() {
+			[ value ] => timeout(value, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = with_socket(
+			&self.0,
+			|stream| {
+				stream.get_ref().set_timeout(timeout)
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Close the socket. Further operations produce error values.
+#[derive(Finalize)]
+struct CloseImpl(Socket);
+
+impl NativeFun for CloseImpl {
+	fn name(&self) -> &'static str { "std.net.socket<close>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			match self.0.borrow_mut().take() {
+				Some(_) => Value::default(),
+				None => Error::new("socket is closed".into(), Value::default()).into(),
+			}
+		)
+	}
+}
+
+
+/// Send a string or byte array to an address, which is `host:port` for UDP sockets, or a
+/// path for Unix domain sockets. Returns the number of bytes sent.
+#[derive(Finalize)]
+struct SendImpl(DatagramSocket);
+
+impl NativeFun for SendImpl {
+	fn name(&self) -> &'static str { "std.net.datagram<send>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (data, address) = match context.args() {
+			[ value, address @ Value::String(_) ] => (
+				util::bytes(value)
+					.ok_or_else(|| Panic::type_error(value.copy(), "string or byte array", context.pos.copy()))?,
+				address,
+			),
+
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		// Unix domain sockets send to paths, and UDP sockets to hosts. Closed sockets fail
+		// regardless of the address.
+		#[cfg(unix)]
+		let unix = with_socket(&self.0, |socket| Ok(matches!(socket, Datagram::Unix(_)))).ok();
+		#[cfg(unix)]
+		let path = match unix {
+			Some(true) => Some(path(&context, address)?),
+			Some(false) => {
+				check_address(&context, address)?;
+				None
+			}
+			None => None,
+		};
+
+		#[cfg(windows)]
+		check_address(&context, address)?;
+
+		let result = with_socket(
+			&self.0,
+			|socket| match socket {
+				Datagram::Udp(socket) => {
+					let address = match address {
+						Value::String(address) => std::str::from_utf8(address.as_bytes())
+							.map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid address"))?,
+						_ => unreachable!("address must be a string"),
+					};
+
+					socket.send_to(&data, address)
+				}
+
+				#[cfg(unix)]
+				Datagram::Unix(socket) => {
+					let path = path.as_ref().expect("path of unix socket was resolved");
+					socket.send_to(&data, path)
+				}
+			}
+		);
+
+		Ok(
+			result
+				.map(|sent| sent as i64)
+				.map_err(|error| Error::new(error.to_string().into(), address.copy()))
+				.into()
+		)
+	}
+}
+
+
+/// Receive a datagram of up to the given number of bytes, which defaults to 65536. Larger
+/// datagrams are truncated. Returns a dict with the data and the sender address, which is
+/// nil for unnamed Unix domain sockets.
+#[derive(Finalize)]
+struct ReceiveImpl(DatagramSocket);
+
+impl NativeFun for ReceiveImpl {
+	fn name(&self) -> &'static str { "std.net.datagram<receive>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let size = match context.args() {
+			[] => 65536,
+			[ Value::Int(size) ] if *size > 0 => *size as usize,
+
+			[ size @ Value::Int(_) ] => return Err(Panic::value_error(size.copy(), "positive integer", context.pos)),
+			[ other ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = with_socket(
+			&self.0,
+			|socket| {
+				let mut buffer = vec![0; size];
+
+				let (received, from) = match socket {
+					Datagram::Udp(socket) => socket
+						.recv_from(&mut buffer)
+						.map(|(received, from)| (received, Value::from(from.to_string())))?,
+
+					#[cfg(unix)]
+					Datagram::Unix(socket) => socket
+						.recv_from(&mut buffer)
+						.map(|(received, from)| (received, unix_address(&from)))?,
+				};
+
+				buffer.truncate(received);
+
+				Ok(
+					util::dict([
+						("data", Str::from(buffer).into()),
+						("from", from),
+					])
+				)
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Set the timeout of sends and receives, in seconds. Nil disables the timeout.
+#[derive(Finalize)]
+struct SetDatagramTimeoutImpl(DatagramSocket);
+
+impl NativeFun for SetDatagramTimeoutImpl {
+	fn name(&self) -> &'static str { "std.net.datagram<set_timeout>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let timeout = match context.args() {
+			[ value ] => timeout(value, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Ok(with_socket(&self.0, |socket| socket.set_timeout(timeout)).into())
+	}
+}
+
+
+/// Close the socket. Further operations produce error values.
+#[derive(Finalize)]
+struct CloseDatagramImpl(DatagramSocket);
+
+impl NativeFun for CloseDatagramImpl {
+	fn name(&self) -> &'static str { "std.net.datagram<close>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			match self.0.borrow_mut().take() {
+				Some(_) => Value::default(),
+				None => Error::new("socket is closed".into(), Value::default()).into(),
+			}
+		)
+	}
+}
+
+
+// The methods have no garbage-collected fields.
+unsafe impl Trace for ReadImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ReadLineImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ReadAsyncImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ReadJoinImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for WriteImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for SetTimeoutImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for CloseImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for AcceptImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for AcceptAsyncImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for AcceptJoinImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for CloseListenerImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for SendImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ReceiveImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for SetDatagramTimeoutImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for CloseDatagramImpl { gc::unsafe_empty_trace!(); }
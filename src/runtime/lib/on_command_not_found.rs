@@ -0,0 +1,33 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(OnCommandNotFound) }
+
+#[derive(Trace, Finalize)]
+struct OnCommandNotFound;
+
+impl NativeFun for OnCommandNotFound {
+	fn name(&self) -> &'static str { "std.on_command_not_found" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let handler = match context.args() {
+			[ Value::Function(ref handler) ] => Some(handler.copy()),
+			[ Value::Nil ] => None,
+			[ other ] => return Err(Panic::type_error(other.copy(), "function or nil", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		// The handler runs in place of the command, receiving the program name and arguments.
+		let previous = std::mem::replace(&mut context.runtime.command_not_found, handler);
+
+		Ok(previous.map(Value::Function).unwrap_or_default())
+	}
+}
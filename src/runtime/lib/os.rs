@@ -0,0 +1,308 @@
+use std::{
+	ffi::OsString,
+	io,
+};
+
+use gc::{Finalize, Trace};
+
+#[cfg(unix)]
+use crate::ffi::OsStringExt;
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Hostname) }
+inventory::submit! { RustFun::from(Username) }
+#[cfg(unix)]
+inventory::submit! { RustFun::from(Uid) }
+#[cfg(unix)]
+inventory::submit! { RustFun::from(Gid) }
+inventory::submit! { RustFun::from(Platform) }
+inventory::submit! { RustFun::from(Arch) }
+inventory::submit! { RustFun::from(Cpus) }
+inventory::submit! { RustFun::from(LoadAverage) }
+inventory::submit! { RustFun::from(Memory) }
+inventory::submit! { RustFun::from(Uptime) }
+
+
+/// Call a function taking no arguments.
+fn nullary<T, F>(context: CallContext, fun: F) -> Result<Value, Panic>
+where
+	T: Into<Value>,
+	F: FnOnce() -> T,
+{
+	match context.args() {
+		[] => Ok(fun().into()),
+		args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos.copy()))
+	}
+}
+
+
+/// System statistics, available only on Linux.
+#[cfg(target_os = "linux")]
+fn sysinfo() -> io::Result<libc::sysinfo> {
+	let mut info = std::mem::MaybeUninit::<libc::sysinfo>::uninit();
+
+	// Safety: sysinfo initializes the struct on success.
+	if unsafe { libc::sysinfo(info.as_mut_ptr()) } < 0 {
+		Err(io::Error::last_os_error())
+	} else {
+		Ok(unsafe { info.assume_init() })
+	}
+}
+
+
+/// System statistics, available only on Linux.
+#[cfg(not(target_os = "linux"))]
+fn sysinfo() -> io::Result<()> {
+	Err(io::ErrorKind::Unsupported.into())
+}
+
+
+#[derive(Trace, Finalize)]
+struct Hostname;
+
+impl Hostname {
+	#[cfg(unix)]
+	fn hostname() -> io::Result<OsString> {
+		let mut buffer = vec![0u8; 256];
+
+		// Safety: the buffer is valid for the given length.
+		let result = unsafe {
+			libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len())
+		};
+
+		if result < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		// The name may be truncated without a nul terminator.
+		let len = buffer.iter().position(|&byte| byte == 0).unwrap_or(buffer.len());
+		buffer.truncate(len);
+
+		Ok(OsString::from_vec(buffer))
+	}
+
+
+	#[cfg(windows)]
+	fn hostname() -> io::Result<OsString> {
+		std::env::var_os("COMPUTERNAME")
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "COMPUTERNAME is not set"))
+	}
+}
+
+impl NativeFun for Hostname {
+	fn name(&self) -> &'static str { "std.os.hostname" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(context, Self::hostname)
+	}
+}
+
+
+/// Get the name of the current user from the password database, or nil if it's unknown.
+/// On Windows, the name is taken from the USERNAME variable.
+#[derive(Trace, Finalize)]
+struct Username;
+
+impl NativeFun for Username {
+	fn name(&self) -> &'static str { "std.os.username" }
+
+	#[cfg(unix)]
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		// Safety: getuid has no memory safety requirements, and always succeeds.
+		nullary(context, || crate::io::user_name(unsafe { libc::getuid() }))
+	}
+
+	#[cfg(windows)]
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(context, || std::env::var_os("USERNAME"))
+	}
+}
+
+
+/// Get the user id. Not supported on Windows, which has security identifiers instead.
+#[cfg(unix)]
+#[derive(Trace, Finalize)]
+struct Uid;
+
+#[cfg(unix)]
+impl NativeFun for Uid {
+	fn name(&self) -> &'static str { "std.os.uid" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		// Safety: getuid has no memory safety requirements, and always succeeds.
+		nullary(context, || unsafe { libc::getuid() } as i64)
+	}
+}
+
+
+/// Get the group id. Not supported on Windows.
+#[cfg(unix)]
+#[derive(Trace, Finalize)]
+struct Gid;
+
+#[cfg(unix)]
+impl NativeFun for Gid {
+	fn name(&self) -> &'static str { "std.os.gid" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		// Safety: getgid has no memory safety requirements, and always succeeds.
+		nullary(context, || unsafe { libc::getgid() } as i64)
+	}
+}
+
+
+/// Get the operating system, such as linux or macos.
+#[derive(Trace, Finalize)]
+struct Platform;
+
+impl NativeFun for Platform {
+	fn name(&self) -> &'static str { "std.os.platform" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(context, || std::env::consts::OS)
+	}
+}
+
+
+/// Get the CPU architecture, such as x86_64 or aarch64.
+#[derive(Trace, Finalize)]
+struct Arch;
+
+impl NativeFun for Arch {
+	fn name(&self) -> &'static str { "std.os.arch" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(context, || std::env::consts::ARCH)
+	}
+}
+
+
+/// Get the number of online CPUs.
+#[derive(Trace, Finalize)]
+struct Cpus;
+
+impl NativeFun for Cpus {
+	fn name(&self) -> &'static str { "std.os.cpus" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		#[cfg(unix)]
+		return nullary(
+			context,
+			|| {
+				// Safety: sysconf has no memory safety requirements.
+				let cpus = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+
+				if cpus < 0 {
+					Err(io::Error::last_os_error())
+				} else {
+					Ok(cpus as i64)
+				}
+			}
+		);
+
+		#[cfg(windows)]
+		return nullary(
+			context,
+			|| std::thread::available_parallelism().map(|cpus| cpus.get() as i64)
+		);
+	}
+}
+
+
+/// Get the system load average over the last 1, 5 and 15 minutes. Not supported on
+/// Windows, which has no load average.
+#[derive(Trace, Finalize)]
+struct LoadAverage;
+
+impl NativeFun for LoadAverage {
+	fn name(&self) -> &'static str { "std.os.load_average" }
+
+	#[cfg(windows)]
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(context, || Err::<Value, _>(io::Error::from(io::ErrorKind::Unsupported)))
+	}
+
+	#[cfg(unix)]
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(
+			context,
+			|| {
+				let mut loads = [0.0; 3];
+
+				// Safety: the array is valid for the given number of elements.
+				if unsafe { libc::getloadavg(loads.as_mut_ptr(), 3) } != 3 {
+					return Err(io::Error::new(io::ErrorKind::Other, "load average unavailable"));
+				}
+
+				Ok(
+					loads
+						.iter()
+						.copied()
+						.map(Value::from)
+						.collect::<Vec<_>>()
+				)
+			}
+		)
+	}
+}
+
+
+/// Get the total and free physical memory, in bytes. Only supported on Linux.
+#[derive(Trace, Finalize)]
+struct Memory;
+
+impl NativeFun for Memory {
+	fn name(&self) -> &'static str { "std.os.memory" }
+
+	#[cfg(target_os = "linux")]
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(
+			context,
+			|| sysinfo().map(
+				|info| {
+					let unit = info.mem_unit as u64;
+					let bytes = |amount| (amount as u64).saturating_mul(unit) as i64;
+
+					let mut dict = DictMap::default();
+					dict.insert("total".into(), bytes(info.totalram).into());
+					dict.insert("free".into(), bytes(info.freeram).into());
+
+					Dict::new(dict)
+				}
+			)
+		)
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(context, || sysinfo().map(|()| Dict::new(DictMap::default())))
+	}
+}
+
+
+/// Get the number of seconds since the system booted. Only supported on Linux.
+#[derive(Trace, Finalize)]
+struct Uptime;
+
+impl NativeFun for Uptime {
+	fn name(&self) -> &'static str { "std.os.uptime" }
+
+	#[cfg(target_os = "linux")]
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(context, || sysinfo().map(|info| info.uptime as i64))
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		nullary(context, || sysinfo().map(|()| Value::default()))
+	}
+}
@@ -0,0 +1,277 @@
+// Workers are forked processes, which are not available on Windows.
+#![cfg(unix)]
+
+use std::{
+	collections::VecDeque,
+	io::{Read, Write},
+	panic::AssertUnwindSafe,
+	thread,
+};
+
+use gc::{Finalize, Trace};
+
+use crate::fmt;
+use crate::runtime::{Runtime, SourcePos};
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	Function,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(Parallel) }
+
+#[derive(Trace, Finalize)]
+struct Parallel;
+
+
+thread_local! {
+	pub static VALUE: Value = "value".into();
+	pub static ERROR: Value = "error".into();
+	pub static CONTEXT: Value = "context".into();
+}
+
+
+/// A worker running in a forked process, whose result is read through a pipe.
+struct Worker {
+	pid: libc::pid_t,
+	reader: thread::JoinHandle<std::io::Result<Vec<u8>>>,
+}
+
+
+impl Parallel {
+	/// Get the maximum number of concurrent workers from the options dict.
+	fn jobs(options: Option<&Dict>, pos: SourcePos) -> Result<usize, Panic> {
+		let jobs = options
+			.and_then(|options| options.get(&"jobs".into()).ok())
+			.unwrap_or_default();
+
+		match jobs {
+			Value::Nil => Ok(
+				thread::available_parallelism()
+					.map(usize::from)
+					.unwrap_or(1)
+			),
+			Value::Int(jobs) if jobs > 0 => Ok(jobs as usize),
+			value @ Value::Int(_) => Err(Panic::value_error(value, "positive integer", pos)),
+			value => Err(Panic::type_error(value, "int", pos)),
+		}
+	}
+
+
+	/// Run the worker for the item in a forked process. The values of the interpreter
+	/// can't be shared across threads, hence the separate process.
+	fn spawn(
+		runtime: &mut Runtime,
+		worker: &Function,
+		item: Value,
+		pos: SourcePos,
+	) -> Result<Worker, Panic> {
+		let (mut reader, writer) = os_pipe::pipe()
+			.map_err(|error| Panic::io(error, pos.copy()))?;
+
+		// Output buffered before the fork would be written twice otherwise.
+		crate::io::flush_stdio();
+
+		// Safety: the child process only runs the worker, and then exits without returning.
+		let pid = unsafe { libc::fork() };
+
+		if pid < 0 {
+			return Err(Panic::io(std::io::Error::last_os_error(), pos));
+		}
+
+		if pid == 0 {
+			drop(reader);
+
+			let status = std::panic::catch_unwind(
+				AssertUnwindSafe(|| Self::work(runtime, worker, item, writer, pos))
+			);
+
+			crate::io::flush_stdio();
+
+			// Safety: _exit has no memory safety requirements. Exiting immediately prevents
+			// destructors from touching state shared with the parent process.
+			unsafe { libc::_exit(if matches!(status, Ok(true)) { 0 } else { 1 }) }
+		}
+
+		drop(writer);
+
+		let reader = thread::spawn(move || {
+			let mut data = Vec::new();
+			reader.read_to_end(&mut data)?;
+			Ok(data)
+		});
+
+		Ok(Worker { pid, reader })
+	}
+
+
+	/// Run the worker in the child process, and write it's result as JSON. Values are
+	/// wrapped in a dict, so that errors and panics can be told apart from values. Returns
+	/// whether the result could be written.
+	fn work(
+		runtime: &mut Runtime,
+		worker: &Function,
+		item: Value,
+		mut writer: os_pipe::PipeWriter,
+		pos: SourcePos,
+	) -> bool {
+		let args_start = runtime.arguments.len();
+		runtime.arguments.push(item);
+
+		let result = runtime.call(Value::default(), worker, args_start, pos);
+
+		let mut dict = DictMap::default();
+
+		match result {
+			Ok(Value::Error(ref error)) => {
+				ERROR.with(|key| dict.insert(key.copy(), error.description.copy().into()));
+				CONTEXT.with(|key| dict.insert(key.copy(), error.context.borrow().copy()));
+			}
+
+			Ok(value) => {
+				VALUE.with(|key| dict.insert(key.copy(), value));
+			}
+
+			Err(panic) => {
+				let description = format!("worker panic: {}", fmt::Show(panic, runtime.interner()));
+				ERROR.with(|key| dict.insert(key.copy(), description.into()));
+			}
+		};
+
+		let result = serde_json::to_vec(&Value::from(Dict::new(dict)))
+			.or_else(
+				|_| {
+					let mut dict = DictMap::default();
+					ERROR.with(
+						|key| dict.insert(key.copy(), "worker result is not serializable".into())
+					);
+					serde_json::to_vec(&Value::from(Dict::new(dict)))
+				}
+			);
+
+		match result {
+			Ok(data) => writer.write_all(&data).is_ok(),
+			Err(_) => false,
+		}
+	}
+
+
+	/// Wait for the worker to finish, and decode it's result.
+	fn wait(worker: Worker, pos: SourcePos) -> Result<Value, Panic> {
+		let data = match worker.reader.join() {
+			Err(error) => std::panic::resume_unwind(error),
+			Ok(result) => result.map_err(|error| Panic::io(error, pos.copy()))?,
+		};
+
+		let mut status = 0;
+		loop {
+			// Safety: the pointer is valid.
+			if unsafe { libc::waitpid(worker.pid, &mut status, 0) } >= 0 {
+				break;
+			}
+
+			let error = std::io::Error::last_os_error();
+			if error.kind() != std::io::ErrorKind::Interrupted {
+				return Err(Panic::io(error, pos));
+			}
+		}
+
+		let dict = match serde_json::from_slice(&data) {
+			Ok(Value::Dict(ref dict)) => dict.copy(),
+			_ => return Ok(Error::new("worker failed".into(), Value::Int(status.into())).into()),
+		};
+
+		let get = |key: &'static std::thread::LocalKey<Value>| key.with(|key| dict.get(key).ok());
+
+		Ok(
+			match (get(&VALUE), get(&ERROR)) {
+				(Some(value), _) => value,
+				(None, Some(Value::String(ref description))) => {
+					Error::new(description.copy(), get(&CONTEXT).unwrap_or_default()).into()
+				}
+				_ => Error::new("worker failed".into(), Value::Int(status.into())).into(),
+			}
+		)
+	}
+}
+
+impl NativeFun for Parallel {
+	fn name(&self) -> &'static str { "std.parallel" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (items, worker, jobs) = match context.args() {
+			[ Value::Array(ref items), Value::Function(ref worker) ] => {
+				(items.copy(), worker.copy(), Self::jobs(None, context.pos.copy())?)
+			}
+
+			[ Value::Array(ref items), Value::Function(ref worker), Value::Dict(ref options) ] => {
+				(items.copy(), worker.copy(), Self::jobs(Some(options), context.pos.copy())?)
+			}
+
+			[ Value::Array(_), Value::Function(_), other ] => {
+				return Err(Panic::type_error(other.copy(), "dict", context.pos))
+			}
+			[ Value::Array(_), other ] | [ Value::Array(_), other, _ ] => {
+				return Err(Panic::type_error(other.copy(), "function", context.pos))
+			}
+			[ other, .. ] if context.args().len() <= 3 => {
+				return Err(Panic::type_error(other.copy(), "array", context.pos))
+			}
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let items: Vec<Value> = items
+			.borrow()
+			.iter()
+			.map(Value::copy)
+			.collect();
+
+		let mut results = Vec::with_capacity(items.len());
+		let mut running = VecDeque::with_capacity(jobs);
+		// On panic, the running workers must still be waited.
+		let mut panic = None;
+
+		// Workers are waited in order, so that the results are kept in the order of the items.
+		for item in items {
+			if running.len() == jobs {
+				let worker = running.pop_front().expect("running workers should not be empty");
+
+				match Self::wait(worker, context.pos.copy()) {
+					Ok(result) => results.push(result),
+					Err(error) => {
+						panic = Some(error);
+						break;
+					}
+				}
+			}
+
+			match Self::spawn(context.runtime, &worker, item, context.pos.copy()) {
+				Ok(worker) => running.push_back(worker),
+				Err(error) => {
+					panic = Some(error);
+					break;
+				}
+			}
+		}
+
+		for worker in running {
+			match Self::wait(worker, context.pos.copy()) {
+				Ok(result) => results.push(result),
+				Err(error) => { panic.get_or_insert(error); }
+			}
+		}
+
+		match panic {
+			Some(panic) => Err(panic),
+			None => Ok(results.into()),
+		}
+	}
+}
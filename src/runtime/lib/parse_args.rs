@@ -0,0 +1,589 @@
+use std::{
+	fmt::Write,
+	io::{self, Write as _},
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	dict::entries,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(ParseArgs) }
+
+
+/// The type of option and positional values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+	String,
+	Int,
+	Float,
+}
+
+
+impl Kind {
+	fn parse(&self, arg: &str) -> Option<Value> {
+		match self {
+			Self::String => Some(arg.into()),
+			Self::Int => arg.parse::<i64>().ok().map(Value::from),
+			Self::Float => arg.parse::<f64>().ok().map(Value::from),
+		}
+	}
+}
+
+
+/// A boolean flag, such as `--verbose`.
+struct Flag {
+	name: String,
+	short: Option<char>,
+	help: String,
+}
+
+
+/// An option taking a value, such as `--output file`.
+struct Opt {
+	name: String,
+	short: Option<char>,
+	help: String,
+	metavar: String,
+	kind: Kind,
+	default: Value,
+	required: bool,
+	/// Whether the option may be given multiple times, collecting the values in an array.
+	multiple: bool,
+}
+
+
+/// A positional argument.
+struct Positional {
+	name: String,
+	help: String,
+	kind: Kind,
+	default: Value,
+	required: bool,
+	/// Whether the argument collects all remaining arguments in an array.
+	variadic: bool,
+}
+
+
+/// A declarative description of a script's command line.
+struct Spec {
+	name: String,
+	description: String,
+	flags: Vec<Flag>,
+	options: Vec<Opt>,
+	positionals: Vec<Positional>,
+}
+
+
+/// Accessors for the fields of a spec dict.
+struct Fields<'a> {
+	dict: Option<&'a Dict>,
+	pos: &'a SourcePos,
+}
+
+
+impl<'a> Fields<'a> {
+	fn new(value: &'a Value, pos: &'a SourcePos) -> Result<Self, Panic> {
+		match value {
+			Value::Nil | Value::Bool(true) => Ok(Self { dict: None, pos }),
+			Value::Dict(dict) => Ok(Self { dict: Some(dict), pos }),
+			other => Err(Panic::type_error(other.copy(), "dict", pos.copy())),
+		}
+	}
+
+
+	fn get(&self, name: &str) -> Value {
+		self.dict
+			.and_then(|dict| dict.get(&name.into()).ok())
+			.unwrap_or_default()
+	}
+
+
+	fn string(&self, name: &str) -> Result<Option<String>, Panic> {
+		match self.get(name) {
+			Value::Nil => Ok(None),
+			Value::String(ref string) => Ok(Some(String::from_utf8_lossy(string.as_bytes()).into_owned())),
+			other => Err(Panic::type_error(other, "string", self.pos.copy())),
+		}
+	}
+
+
+	fn bool(&self, name: &str) -> Result<Option<bool>, Panic> {
+		match self.get(name) {
+			Value::Nil => Ok(None),
+			Value::Bool(value) => Ok(Some(value)),
+			other => Err(Panic::type_error(other, "bool", self.pos.copy())),
+		}
+	}
+
+
+	fn short(&self) -> Result<Option<char>, Panic> {
+		let short = match self.string("short")? {
+			Some(short) => short,
+			None => return Ok(None),
+		};
+
+		let mut chars = short.chars();
+
+		match (chars.next(), chars.next()) {
+			(Some(c), None) if c != '-' => Ok(Some(c)),
+			_ => Err(Panic::value_error(self.get("short"), "single character", self.pos.copy())),
+		}
+	}
+
+
+	fn kind(&self) -> Result<Kind, Panic> {
+		match self.string("type")?.as_deref() {
+			None | Some("string") => Ok(Kind::String),
+			Some("int") => Ok(Kind::Int),
+			Some("float") => Ok(Kind::Float),
+			Some(_) => Err(Panic::value_error(self.get("type"), "string, int or float", self.pos.copy())),
+		}
+	}
+}
+
+
+impl Spec {
+	fn new(spec: &Dict, pos: &SourcePos) -> Result<Self, Panic> {
+		let spec_value = Value::Dict(spec.copy());
+		let fields = Fields::new(&spec_value, pos)?;
+
+		let name = fields.string("name")?.unwrap_or_else(|| "script".into());
+		let description = fields.string("description")?.unwrap_or_default();
+
+		let named = |field: &str| -> Result<Vec<(String, Value)>, Panic> {
+			match fields.get(field) {
+				Value::Nil => Ok(Vec::new()),
+				Value::Dict(ref dict) => entries(dict)
+					.into_iter()
+					.map(
+						|(key, value)| match key {
+							Value::String(ref name) => Ok((String::from_utf8_lossy(name.as_bytes()).into_owned(), value)),
+							other => Err(Panic::type_error(other, "string", pos.copy())),
+						}
+					)
+					.collect(),
+				other => Err(Panic::type_error(other, "dict", pos.copy())),
+			}
+		};
+
+		let flags = named("flags")?
+			.into_iter()
+			.map(
+				|(name, value)| {
+					let fields = Fields::new(&value, pos)?;
+					Ok(
+						Flag {
+							short: fields.short()?,
+							help: fields.string("help")?.unwrap_or_default(),
+							name,
+						}
+					)
+				}
+			)
+			.collect::<Result<Vec<_>, Panic>>()?;
+
+		let options = named("options")?
+			.into_iter()
+			.map(
+				|(name, value)| {
+					let fields = Fields::new(&value, pos)?;
+					let multiple = fields.bool("multiple")?.unwrap_or(false);
+					let default = match fields.get("default") {
+						Value::Nil if multiple => Vec::new().into(),
+						default => default,
+					};
+
+					Ok(
+						Opt {
+							short: fields.short()?,
+							help: fields.string("help")?.unwrap_or_default(),
+							metavar: fields.string("metavar")?.unwrap_or_else(|| name.replace('_', "-")),
+							kind: fields.kind()?,
+							required: fields.bool("required")?.unwrap_or(false),
+							default,
+							multiple,
+							name,
+						}
+					)
+				}
+			)
+			.collect::<Result<Vec<_>, Panic>>()?;
+
+		let positionals = match fields.get("positionals") {
+			Value::Nil => Vec::new(),
+			Value::Array(ref array) => array
+				.borrow()
+				.iter()
+				.map(
+					|value| {
+						let fields = Fields::new(value, pos)?;
+						let name = fields
+							.string("name")?
+							.ok_or_else(|| Panic::value_error(value.copy(), "positional with a name", pos.copy()))?;
+						let default = fields.get("default");
+						let variadic = fields.bool("variadic")?.unwrap_or(false);
+
+						Ok(
+							Positional {
+								help: fields.string("help")?.unwrap_or_default(),
+								kind: fields.kind()?,
+								required: fields.bool("required")?.unwrap_or(default == Value::Nil && !variadic),
+								default,
+								variadic,
+								name,
+							}
+						)
+					}
+				)
+				.collect::<Result<Vec<_>, Panic>>()?,
+			other => return Err(Panic::type_error(other, "array", pos.copy())),
+		};
+
+		Ok(Self { name, description, flags, options, positionals })
+	}
+
+
+	/// The command line form of a name: underscores become dashes.
+	fn long(name: &str) -> String {
+		format!("--{}", name.replace('_', "-"))
+	}
+
+
+	/// The usage and help text.
+	fn help(&self) -> String {
+		let mut help = format!("usage: {}", self.name);
+
+		if !self.flags.is_empty() || !self.options.is_empty() {
+			help.push_str(" [options]");
+		}
+
+		for option in self.options.iter().filter(|option| option.required) {
+			let _ = write!(help, " {} <{}>", Self::long(&option.name), option.metavar);
+		}
+
+		for positional in &self.positionals {
+			let name = if positional.variadic { format!("{}...", positional.name) } else { positional.name.clone() };
+
+			if positional.required {
+				let _ = write!(help, " <{}>", name);
+			} else {
+				let _ = write!(help, " [{}]", name);
+			}
+		}
+
+		help.push('\n');
+
+		if !self.description.is_empty() {
+			let _ = write!(help, "\n{}\n", self.description);
+		}
+
+		let with_default = |help: &str, default: &Value| {
+			let default = match default {
+				Value::String(string) => String::from_utf8_lossy(string.as_bytes()).into_owned(),
+				Value::Int(int) => int.to_string(),
+				Value::Float(float) => float.to_string(),
+				_ => return help.to_owned(),
+			};
+
+			if help.is_empty() {
+				format!("(default: {})", default)
+			} else {
+				format!("{} (default: {})", help, default)
+			}
+		};
+
+		let arguments: Vec<(String, String)> = self.positionals
+			.iter()
+			.map(|positional| (positional.name.clone(), with_default(&positional.help, &positional.default)))
+			.collect();
+
+		let mut options: Vec<(String, String)> = Vec::new();
+
+		let left = |short: Option<char>, name: &str| match short {
+			Some(short) => format!("-{}, {}", short, Self::long(name)),
+			None => format!("    {}", Self::long(name)),
+		};
+
+		for flag in &self.flags {
+			options.push((left(flag.short, &flag.name), flag.help.clone()));
+		}
+
+		for option in &self.options {
+			options.push(
+				(
+					format!("{} <{}>", left(option.short, &option.name), option.metavar),
+					with_default(&option.help, &option.default),
+				)
+			);
+		}
+
+		options.push(("-h, --help".into(), "Show this help".into()));
+
+		let width = arguments
+			.iter()
+			.chain(options.iter())
+			.map(|(left, _)| left.chars().count())
+			.max()
+			.unwrap_or(0);
+
+		let mut section = |title: &str, rows: &[(String, String)]| {
+			if rows.is_empty() {
+				return;
+			}
+
+			let _ = write!(help, "\n{}:\n", title);
+
+			for (left, right) in rows {
+				let line = format!("  {:width$}  {}", left, right, width = width);
+				help.push_str(line.trim_end());
+				help.push('\n');
+			}
+		};
+
+		section("arguments", &arguments);
+		section("options", &options);
+
+		help
+	}
+}
+
+
+/// A parse error, which is returned as an error value with the help text as context.
+struct ParseError(String);
+
+
+/// The result of parsing the command line.
+enum Parsed {
+	Values(DictMap),
+	Help,
+}
+
+
+impl Spec {
+	fn parse(&self, args: &[String]) -> Result<Parsed, ParseError> {
+		let mut values = DictMap::default();
+
+		for flag in &self.flags {
+			values.insert(flag.name.as_str().into(), false.into());
+		}
+
+		let mut given: Vec<bool> = vec![false; self.options.len()];
+		let mut option_values: Vec<Vec<Value>> = self.options.iter().map(|_| Vec::new()).collect();
+		let mut positionals = Vec::new();
+
+		let mut args = args.iter();
+		let mut only_positionals = false;
+
+		let value = |option: &Opt, arg: &str, display: &str| {
+			option.kind
+				.parse(arg)
+				.ok_or_else(|| ParseError(format!("invalid value for {}: {}", display, arg)))
+		};
+
+		while let Some(arg) = args.next() {
+			if only_positionals || arg == "-" || !arg.starts_with('-') {
+				positionals.push(arg.as_str());
+			} else if arg == "--" {
+				only_positionals = true;
+			} else if let Some(long) = arg.strip_prefix("--") {
+				let (name, inline) = match long.split_once('=') {
+					Some((name, value)) => (name, Some(value)),
+					None => (long, None),
+				};
+
+				if name == "help" {
+					return Ok(Parsed::Help);
+				}
+
+				let display = format!("--{}", name);
+				let name = name.replace('-', "_");
+
+				if let Some(flag) = self.flags.iter().find(|flag| flag.name == name) {
+					if inline.is_some() {
+						return Err(ParseError(format!("flag {} takes no value", display)));
+					}
+
+					values.insert(flag.name.as_str().into(), true.into());
+				} else if let Some(ix) = self.options.iter().position(|option| option.name == name) {
+					let arg = match inline {
+						Some(arg) => arg,
+						None => args
+							.next()
+							.map(String::as_str)
+							.ok_or_else(|| ParseError(format!("missing value for {}", display)))?,
+					};
+
+					given[ix] = true;
+					option_values[ix].push(value(&self.options[ix], arg, &display)?);
+				} else {
+					return Err(ParseError(format!("unknown option {}", display)));
+				}
+			} else {
+				// A cluster of short flags, possibly ending with a short option.
+				let cluster = &arg[1 ..];
+
+				for (offset, short) in cluster.char_indices() {
+					if short == 'h' && !self.flags.iter().any(|flag| flag.short == Some('h'))
+						&& !self.options.iter().any(|option| option.short == Some('h')) {
+						return Ok(Parsed::Help);
+					}
+
+					let display = format!("-{}", short);
+
+					if let Some(flag) = self.flags.iter().find(|flag| flag.short == Some(short)) {
+						values.insert(flag.name.as_str().into(), true.into());
+					} else if let Some(ix) = self.options.iter().position(|option| option.short == Some(short)) {
+						let rest = &cluster[offset + short.len_utf8() ..];
+
+						let arg = if rest.is_empty() {
+							args
+								.next()
+								.map(String::as_str)
+								.ok_or_else(|| ParseError(format!("missing value for {}", display)))?
+						} else {
+							rest
+						};
+
+						given[ix] = true;
+						option_values[ix].push(value(&self.options[ix], arg, &display)?);
+						break;
+					} else {
+						return Err(ParseError(format!("unknown option {}", display)));
+					}
+				}
+			}
+		}
+
+		for ((option, given), mut option_values) in self.options.iter().zip(given).zip(option_values) {
+			let value = if !given {
+				if option.required {
+					return Err(ParseError(format!("missing required option {}", Self::long(&option.name))));
+				}
+
+				option.default.copy()
+			} else if option.multiple {
+				option_values.into()
+			} else {
+				// The last occurrence wins.
+				option_values.pop().unwrap_or_default()
+			};
+
+			values.insert(option.name.as_str().into(), value);
+		}
+
+		let mut positionals = positionals.into_iter();
+
+		for positional in &self.positionals {
+			let parse = |arg: &str| positional.kind
+				.parse(arg)
+				.ok_or_else(|| ParseError(format!("invalid value for {}: {}", positional.name, arg)));
+
+			let value = if positional.variadic {
+				let rest = positionals
+					.by_ref()
+					.map(parse)
+					.collect::<Result<Vec<_>, _>>()?;
+
+				if rest.is_empty() && positional.required {
+					return Err(ParseError(format!("missing argument {}", positional.name)));
+				}
+
+				if rest.is_empty() && positional.default != Value::Nil {
+					positional.default.copy()
+				} else {
+					rest.into()
+				}
+			} else {
+				match positionals.next() {
+					Some(arg) => parse(arg)?,
+					None if positional.required => {
+						return Err(ParseError(format!("missing argument {}", positional.name)))
+					}
+					None => positional.default.copy(),
+				}
+			};
+
+			values.insert(positional.name.as_str().into(), value);
+		}
+
+		if let Some(arg) = positionals.next() {
+			return Err(ParseError(format!("unexpected argument {}", arg)));
+		}
+
+		Ok(Parsed::Values(values))
+	}
+}
+
+
+/// Parse command line arguments according to a spec, returning a dict of values. The
+/// arguments default to the script's arguments. The spec is a dict with the fields:
+/// - name and description, for the help text.
+/// - flags: a dict of boolean flags, such as `--verbose`. Each may have a short letter
+///   and help text.
+/// - options: a dict of options taking a value, such as `--output file`. Besides short
+///   and help, each may have a type (string, int or float), a default, a metavar, and
+///   may be required, or be given multiple times, collecting the values in an array.
+/// - positionals: an array of positional arguments, each with a name, and optionally
+///   help, type and default. Positionals without a default are required. The last may
+///   be variadic, collecting the remaining arguments in an array.
+/// Underscores in names are written as dashes in the command line. With `--help` or
+/// `-h`, the help text is printed and the script exits successfully. Invalid command
+/// lines produce an error value, with the help text as context.
+#[derive(Trace, Finalize)]
+struct ParseArgs;
+
+impl NativeFun for ParseArgs {
+	fn name(&self) -> &'static str { "std.args.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (spec, args) = match context.args() {
+			[ Value::Dict(ref spec) ] => (spec, context.runtime.args.copy()),
+			[ Value::Dict(ref spec), args @ Value::Array(_) ] => (spec, args.copy()),
+
+			[ Value::Dict(_), other ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let spec = Spec::new(spec, &context.pos)?;
+
+		let args: Vec<String> = match args {
+			Value::Array(ref array) => array
+				.borrow()
+				.iter()
+				.map(
+					|arg| match arg {
+						Value::String(string) => Ok(String::from_utf8_lossy(string.as_bytes()).into_owned()),
+						other => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+					}
+				)
+				.collect::<Result<_, _>>()?,
+			_ => unreachable!("arguments are always an array"),
+		};
+
+		match spec.parse(&args) {
+			Ok(Parsed::Values(values)) => Ok(Dict::new(values).into()),
+
+			Ok(Parsed::Help) => {
+				print!("{}", spec.help());
+				let _ = io::stdout().flush();
+				Err(Panic::exit(0))
+			}
+
+			Err(ParseError(message)) => Ok(Error::new(message.into(), spec.help().into()).into()),
+		}
+	}
+}
@@ -0,0 +1,316 @@
+use std::{
+	ffi::OsStr,
+	path::{Component, Path, PathBuf},
+};
+
+use gc::{Finalize, Trace};
+
+use crate::ffi::OsStrExt;
+use crate::runtime::command::env;
+
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Join) }
+inventory::submit! { RustFun::from(Split) }
+inventory::submit! { RustFun::from(Basename) }
+inventory::submit! { RustFun::from(Dirname) }
+inventory::submit! { RustFun::from(Extension) }
+inventory::submit! { RustFun::from(Canonicalize) }
+inventory::submit! { RustFun::from(RelativeTo) }
+inventory::submit! { RustFun::from(IsAbsolute) }
+inventory::submit! { RustFun::from(HomeDir) }
+
+
+/// Get a path from a string value.
+fn path(value: &Value) -> Option<&Path> {
+	match value {
+		Value::String(string) => Some(Path::new(AsRef::<OsStr>::as_ref(string))),
+		_ => None,
+	}
+}
+
+
+/// Normalize a path without touching the filesystem, removing `.` components, and
+/// resolving `..` components where possible.
+fn normalize(path: &Path) -> Vec<Component> {
+	let mut components = Vec::new();
+
+	for component in path.components() {
+		match component {
+			Component::CurDir => (),
+			Component::ParentDir => match components.last() {
+				Some(Component::Normal(_)) => { components.pop(); }
+				Some(Component::RootDir) => (), // The parent of the root is the root.
+				_ => components.push(component),
+			},
+			component => components.push(component),
+		}
+	}
+
+	components
+}
+
+
+/// Get the relative path from a base to a path, without touching the filesystem. Both
+/// paths must be either absolute or relative.
+fn relative(path: &Path, base: &Path) -> Option<PathBuf> {
+	if path.is_absolute() != base.is_absolute() {
+		return None;
+	}
+
+	let path = normalize(path);
+	let base = normalize(base);
+
+	let common = path
+		.iter()
+		.zip(base.iter())
+		.take_while(|(path, base)| path == base)
+		.count();
+
+	let mut relative = PathBuf::new();
+
+	for component in &base[common..] {
+		match component {
+			Component::Normal(_) => relative.push(".."),
+			_ => return None, // The base is above the current directory.
+		}
+	}
+
+	relative.extend(&path[common..]);
+
+	if relative.as_os_str().is_empty() {
+		relative.push(".");
+	}
+
+	Some(relative)
+}
+
+
+#[derive(Trace, Finalize)]
+struct Join;
+
+impl NativeFun for Join {
+	fn name(&self) -> &'static str { "std.path.join" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let mut joined = PathBuf::new();
+
+		// As in PathBuf::push, absolute paths replace the previous ones.
+		for value in context.args() {
+			match path(value) {
+				Some(path) => joined.push(path),
+				None => return Err(Panic::type_error(value.copy(), "string", context.pos)),
+			}
+		}
+
+		Ok(Str::from(joined).into())
+	}
+}
+
+
+/// Split a path in it's components. The root directory is a component.
+#[derive(Trace, Finalize)]
+struct Split;
+
+impl NativeFun for Split {
+	fn name(&self) -> &'static str { "std.path.split" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => match path(value) {
+				Some(path) => Ok(
+					path
+						.components()
+						.map(|component| Value::from(component.as_os_str().as_bytes()))
+						.collect::<Vec<_>>()
+						.into()
+				),
+				None => Err(Panic::type_error(value.copy(), "string", context.pos)),
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Get the last component of a path, or nil if there is none.
+#[derive(Trace, Finalize)]
+struct Basename;
+
+impl NativeFun for Basename {
+	fn name(&self) -> &'static str { "std.path.basename" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => match path(value) {
+				Some(path) => Ok(path.file_name().map(OsStr::as_bytes).into()),
+				None => Err(Panic::type_error(value.copy(), "string", context.pos)),
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Get a path without it's last component. As in the dirname utility, the parent of a
+/// single component is the current directory, and the root has no parent, in which case
+/// nil is returned.
+#[derive(Trace, Finalize)]
+struct Dirname;
+
+impl NativeFun for Dirname {
+	fn name(&self) -> &'static str { "std.path.dirname" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => match path(value) {
+				Some(path) => Ok(
+					path
+						.parent()
+						.map(
+							|parent| if parent.as_os_str().is_empty() {
+								Path::new(".")
+							} else {
+								parent
+							}
+						)
+						.map(|parent| parent.as_os_str().as_bytes())
+						.into()
+				),
+				None => Err(Panic::type_error(value.copy(), "string", context.pos)),
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Get the extension of the last component, without the dot, or nil if there is none.
+#[derive(Trace, Finalize)]
+struct Extension;
+
+impl NativeFun for Extension {
+	fn name(&self) -> &'static str { "std.path.extension" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => match path(value) {
+				Some(path) => Ok(path.extension().map(OsStr::as_bytes).into()),
+				None => Err(Panic::type_error(value.copy(), "string", context.pos)),
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Get the absolute path, with all symlinks resolved. Unlike the other functions, this
+/// accesses the filesystem, and the path must exist.
+#[derive(Trace, Finalize)]
+struct Canonicalize;
+
+impl NativeFun for Canonicalize {
+	fn name(&self) -> &'static str { "std.path.canonicalize" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => match path(value) {
+				Some(path) => Ok(
+					context.runtime.options
+						.path(path)
+						.canonicalize()
+						.map(Str::from)
+						.map_err(
+							|error| Error::new(error.to_string().into(), value.copy())
+						)
+						.into()
+				),
+				None => Err(Panic::type_error(value.copy(), "string", context.pos)),
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Get the relative path from a base directory to a path, without accessing the
+/// filesystem. Both paths must be either absolute or relative.
+#[derive(Trace, Finalize)]
+struct RelativeTo;
+
+impl NativeFun for RelativeTo {
+	fn name(&self) -> &'static str { "std.path.relative_to" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, base ] => match (path(value), path(base)) {
+				(Some(path), Some(base)) => Ok(
+					match relative(path, base) {
+						Some(relative) => Str::from(relative).into(),
+						None => Error::new("no relative path".into(), value.copy()).into(),
+					}
+				),
+				(Some(_), None) => Err(Panic::type_error(base.copy(), "string", context.pos)),
+				(None, _) => Err(Panic::type_error(value.copy(), "string", context.pos)),
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct IsAbsolute;
+
+impl NativeFun for IsAbsolute {
+	fn name(&self) -> &'static str { "std.path.is_absolute" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => match path(value) {
+				Some(path) => Ok(path.is_absolute().into()),
+				None => Err(Panic::type_error(value.copy(), "string", context.pos)),
+			},
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Get the home directory of the current user, or of the given user from the password
+/// database. Returns nil if it's unknown.
+#[derive(Trace, Finalize)]
+struct HomeDir;
+
+impl NativeFun for HomeDir {
+	fn name(&self) -> &'static str { "std.path.home_dir" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				context.runtime.options.policy.check_env(OsStr::new(crate::io::HOME), context.pos.copy())?;
+				Ok(env::var(OsStr::new(crate::io::HOME)).into())
+			}
+			[ Value::String(ref user) ] => Ok(crate::io::user_home(user.as_bytes()).map(Str::from).into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,250 @@
+use std::{
+	ffi::OsStr,
+	io::{self, Write},
+	path::Path,
+	process::Command,
+};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::command::env;
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Pid) }
+#[cfg(unix)]
+inventory::submit! { RustFun::from(Ppid) }
+inventory::submit! { RustFun::from(Argv) }
+inventory::submit! { RustFun::from(Exec) }
+inventory::submit! { RustFun::from(List) }
+
+
+#[derive(Trace, Finalize)]
+struct Pid;
+
+impl NativeFun for Pid {
+	fn name(&self) -> &'static str { "std.process.pid" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(Value::Int(std::process::id().into())),
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// Get the parent pid. Not supported on Windows, where processes have no parent.
+#[cfg(unix)]
+#[derive(Trace, Finalize)]
+struct Ppid;
+
+#[cfg(unix)]
+impl NativeFun for Ppid {
+	fn name(&self) -> &'static str { "std.process.ppid" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			// Safety: getppid has no memory safety requirements, and always succeeds.
+			[] => Ok(Value::Int(unsafe { libc::getppid() }.into())),
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// Get the command line of the shell process, including the interpreter and the script
+/// path. Use std.args for the script arguments only.
+#[derive(Trace, Finalize)]
+struct Argv;
+
+impl NativeFun for Argv {
+	fn name(&self) -> &'static str { "std.process.argv" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(
+				std::env::args_os()
+					.map(Value::from)
+					.collect::<Vec<_>>()
+					.into()
+			),
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// Replace the shell process with the given program, which is searched in the PATH. The
+/// program runs in the working directory of commands. Returns only on failure, with an
+/// error value. As Windows can't replace a process, the program is run as a child there,
+/// and the shell exits with it's status.
+#[derive(Trace, Finalize)]
+struct Exec;
+
+impl NativeFun for Exec {
+	fn name(&self) -> &'static str { "std.process.exec" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (program, args) = match context.args() {
+			[ program @ Value::String(_) ] => (program, Vec::new()),
+			[ program @ Value::String(_), Value::Array(ref args) ] => {
+				let args = args
+					.borrow()
+					.iter()
+					.map(
+						|arg| match arg {
+							Value::String(arg) => Ok(arg.copy()),
+							other => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+						}
+					)
+					.collect::<Result<Vec<Str>, Panic>>()?;
+
+				(program, args)
+			}
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let mut command = match program {
+			Value::String(program) => {
+				let program = AsRef::<OsStr>::as_ref(program);
+				context.runtime.options.policy.check_command(program, context.pos.copy())?;
+				Command::new(program)
+			}
+			_ => unreachable!("program must be a string"),
+		};
+
+		command.args(args.iter().map(AsRef::<OsStr>::as_ref));
+		env::apply(&mut command);
+
+		if let Some(cwd) = &context.runtime.options.cwd {
+			command.current_dir(cwd);
+		}
+
+		// Output written so far would be lost otherwise.
+		let _ = io::stdout().flush();
+
+		#[cfg(unix)]
+		let error = command.exec();
+
+		#[cfg(windows)]
+		let error = match command.status() {
+			Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+			Err(error) => error,
+		};
+
+		Ok(Error::new(error.to_string().into(), program.copy()).into())
+	}
+}
+
+
+/// List the running processes, with their pid, parent pid, name and command line. Only
+/// supported on Linux.
+#[derive(Trace, Finalize)]
+struct List;
+
+impl List {
+	/// Read the information of a process from procfs.
+	#[cfg(target_os = "linux")]
+	fn process(pid: i64) -> io::Result<Value> {
+		let dir = std::path::Path::new("/proc").join(pid.to_string());
+
+		let mut name = std::fs::read(dir.join("comm"))?;
+		if name.last() == Some(&b'\n') {
+			name.pop();
+		}
+
+		let cmdline = std::fs::read(dir.join("cmdline"))?;
+		let cmdline: Vec<Value> = cmdline
+			.split(|&byte| byte == 0)
+			.filter(|arg| !arg.is_empty())
+			.map(Value::from)
+			.collect();
+
+		// The name in the stat file is in parentheses, and may contain spaces and
+		// parentheses itself, so the fields are after the last parenthesis.
+		let stat = std::fs::read(dir.join("stat"))?;
+		let ppid = stat
+			.iter()
+			.rposition(|&byte| byte == b')')
+			.and_then(
+				|end| std::str::from_utf8(&stat[end + 1 ..])
+					.ok()?
+					.split_whitespace()
+					.nth(1)? // The state comes before the parent pid.
+					.parse::<i64>()
+					.ok()
+			)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid stat file"))?;
+
+		let mut dict = DictMap::default();
+		dict.insert("pid".into(), pid.into());
+		dict.insert("ppid".into(), ppid.into());
+		dict.insert("name".into(), Str::from(name).into());
+		dict.insert("cmdline".into(), cmdline.into());
+
+		Ok(Dict::new(dict).into())
+	}
+
+
+	#[cfg(target_os = "linux")]
+	fn list() -> io::Result<Value> {
+		let mut pids: Vec<i64> = std::fs::read_dir("/proc")?
+			.filter_map(
+				|entry| entry
+					.ok()?
+					.file_name()
+					.to_str()?
+					.parse()
+					.ok()
+			)
+			.collect();
+
+		pids.sort_unstable();
+
+		// Processes may exit while listing, in which case they are skipped.
+		let processes: Vec<Value> = pids
+			.into_iter()
+			.filter_map(|pid| Self::process(pid).ok())
+			.collect();
+
+		Ok(processes.into())
+	}
+
+
+	#[cfg(not(target_os = "linux"))]
+	fn list() -> io::Result<Value> {
+		Err(io::ErrorKind::Unsupported.into())
+	}
+}
+
+impl NativeFun for List {
+	fn name(&self) -> &'static str { "std.process.list" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				context.runtime.options.policy.check_path(Path::new("/proc"), context.pos.copy())?;
+				Ok(Self::list().into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
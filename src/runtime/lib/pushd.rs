@@ -0,0 +1,58 @@
+use std::ffi::OsStr;
+
+use gc::{Finalize, Trace};
+
+use crate::io;
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(Pushd) }
+inventory::submit!{ RustFun::from(Popd) }
+
+#[derive(Trace, Finalize)]
+struct Pushd;
+
+impl NativeFun for Pushd {
+	fn name(&self) -> &'static str { "std.pushd" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				io
+					::push_dir(AsRef::<OsStr>::as_ref(string))
+					.into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+#[derive(Trace, Finalize)]
+struct Popd;
+
+impl NativeFun for Popd {
+	fn name(&self) -> &'static str { "std.popd" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			io
+				::pop_dir()
+				.map(|dir| dir.into_os_string())
+				.into()
+		)
+	}
+}
@@ -0,0 +1,235 @@
+use std::{cell::RefCell, convert::TryFrom};
+
+use gc::{Finalize, Trace};
+use rand::{
+	rngs::{OsRng, StdRng},
+	seq::SliceRandom,
+	Rng,
+	SeedableRng,
+};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+	util,
+};
+
+
+inventory::submit! { RustFun::from(Seed) }
+inventory::submit! { RustFun::from(Int) }
+inventory::submit! { RustFun::from(Float) }
+inventory::submit! { RustFun::from(Shuffle) }
+inventory::submit! { RustFun::from(Choice) }
+inventory::submit! { RustFun::from(Bytes) }
+inventory::submit! { RustFun::from(Token) }
+inventory::submit! { RustFun::from(Uuid) }
+
+
+thread_local! {
+	/// The pseudo-random generator, which may be seeded for reproducible results. It is
+	/// not suitable for secrets, which must be generated from the operating system source.
+	static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+
+/// The default alphabet for tokens.
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+
+#[derive(Trace, Finalize)]
+struct Seed;
+
+impl NativeFun for Seed {
+	fn name(&self) -> &'static str { "std.random.seed" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(seed) ] => {
+				RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(*seed as u64));
+				Ok(Value::default())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// A random integer in the given range, which excludes the upper bound as in std.range.
+#[derive(Trace, Finalize)]
+struct Int;
+
+impl NativeFun for Int {
+	fn name(&self) -> &'static str { "std.random.int" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(from), Value::Int(to) ] if from < to => Ok(
+				RNG.with(|rng| rng.borrow_mut().gen_range(*from .. *to)).into()
+			),
+
+			[ Value::Int(_), to @ Value::Int(_) ] => {
+				Err(Panic::value_error(to.copy(), "upper bound greater than lower bound", context.pos))
+			}
+
+			[ Value::Int(_), other ] | [ other, _ ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// A random float between zero and one, excluding one.
+#[derive(Trace, Finalize)]
+struct Float;
+
+impl NativeFun for Float {
+	fn name(&self) -> &'static str { "std.random.float" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(RNG.with(|rng| rng.borrow_mut().gen::<f64>()).into())
+	}
+}
+
+
+/// Shuffle an array in place.
+#[derive(Trace, Finalize)]
+struct Shuffle;
+
+impl NativeFun for Shuffle {
+	fn name(&self) -> &'static str { "std.random.shuffle" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref array) ] => {
+				RNG.with(|rng| array.borrow_mut().shuffle(&mut *rng.borrow_mut()));
+				Ok(Value::default())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// A random element of an array.
+#[derive(Trace, Finalize)]
+struct Choice;
+
+impl NativeFun for Choice {
+	fn name(&self) -> &'static str { "std.random.choice" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref array) ] => RNG
+				.with(|rng| array.borrow().choose(&mut *rng.borrow_mut()).map(Value::copy))
+				.ok_or_else(|| Panic::empty_collection(context.pos.copy())),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Secure random bytes, from the operating system source.
+#[derive(Trace, Finalize)]
+struct Bytes;
+
+impl NativeFun for Bytes {
+	fn name(&self) -> &'static str { "std.random.bytes" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ length @ Value::Int(int) ] => {
+				let size = usize::try_from(*int).ok();
+
+				let mut bytes = util::alloc(size)
+					.ok_or_else(|| Panic::value_error(length.copy(), "non-negative integer within memory limits", context.pos.copy()))?;
+
+				bytes.resize(size.unwrap_or(0), 0);
+				OsRng.fill(bytes.as_mut_slice());
+				Ok(Str::from(bytes).into())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// A secure random token, such as a password, from the given alphabet, which is
+/// alphanumeric by default.
+#[derive(Trace, Finalize)]
+struct Token;
+
+impl NativeFun for Token {
+	fn name(&self) -> &'static str { "std.random.token" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (length, alphabet) = match context.args() {
+			[ Value::Int(length) ] => (*length, ALPHANUMERIC),
+			[ Value::Int(length), Value::String(ref alphabet) ] if !alphabet.is_empty() => {
+				(*length, alphabet.as_bytes())
+			}
+
+			[ Value::Int(_), alphabet @ Value::String(_) ] => {
+				return Err(Panic::value_error(alphabet.copy(), "non-empty string", context.pos))
+			}
+			[ Value::Int(_), other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		if length < 0 {
+			return Err(Panic::value_error(length.into(), "non-negative integer", context.pos));
+		}
+
+		let token: Vec<u8> = (0 .. length)
+			.map(|_| alphabet[OsRng.gen_range(0 .. alphabet.len())])
+			.collect();
+
+		Ok(Str::from(token).into())
+	}
+}
+
+
+/// A random UUID, version 4, from the operating system source.
+#[derive(Trace, Finalize)]
+struct Uuid;
+
+impl NativeFun for Uuid {
+	fn name(&self) -> &'static str { "std.random.uuid" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let mut bytes = [0u8; 16];
+		OsRng.fill(&mut bytes);
+
+		bytes[6] = (bytes[6] & 0x0F) | 0x40; // Version 4.
+		bytes[8] = (bytes[8] & 0x3F) | 0x80; // RFC 4122 variant.
+
+		let hex = hex::encode(bytes);
+
+		Ok(
+			format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+				.into()
+		)
+	}
+}
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use gc::{Finalize, GcCell, Trace};
 
 use super::{
@@ -7,6 +5,7 @@ use super::{
 	keys,
 	CallContext,
 	Dict,
+	DictMap,
 	RustFun,
 	NativeFun,
 	Panic,
@@ -74,7 +73,7 @@ where
 		}
 
 		let mut from = self.from.borrow_mut();
-		let mut iteration = HashMap::new();
+		let mut iteration = DictMap::default();
 
 		let finished =
 			if self.step > T::default() { // Step is positive.
@@ -1,12 +1,14 @@
-use std::{rc::Rc, collections::HashMap, borrow::Cow};
+use std::{rc::Rc, borrow::Cow};
 
 use gc::{Finalize, Trace};
-use regex::bytes::Regex;
+use regex::bytes::{Captures, Match, Regex};
 
 use super::{
 	Error,
 	CallContext,
 	Dict,
+	DictMap,
+	Function,
 	RustFun,
 	NativeFun,
 	Panic,
@@ -36,9 +38,11 @@ impl StdRegex {
 			pub static MATCH: Value = "match".into();
 			pub static SPLIT: Value = "split".into();
 			pub static REPLACE: Value = "replace".into();
+			pub static FIND: Value = "find".into();
+			pub static FIND_ALL: Value = "find_all".into();
 		}
 
-		let mut dict = HashMap::new();
+		let mut dict = DictMap::default();
 
 		MATCH.with(
 			|name| dict.insert(name.copy(), RegexMatchImpl { pattern: pattern.clone() }.into())
@@ -52,10 +56,66 @@ impl StdRegex {
 			|replace| dict.insert(replace.copy(), RegexReplaceImpl { pattern: pattern.clone() }.into())
 		);
 
+		FIND.with(
+			|find| dict.insert(find.copy(), RegexFindImpl { pattern: pattern.clone() }.into())
+		);
+
+		FIND_ALL.with(
+			|find_all| dict.insert(find_all.copy(), RegexFindAllImpl { pattern: pattern.clone() }.into())
+		);
+
 		Dict::new(dict).into()
 	}
 }
 
+/// Build a match object, with the matched text, it's byte offsets, and the capture groups.
+/// Groups are available by number, in which the group 0 is the whole match, and by name.
+/// Groups that didn't participate in the match are nil.
+fn match_object(pattern: &Regex, captures: &Captures) -> Value {
+	thread_local! {
+		pub static TEXT: Value = "text".into();
+		pub static START: Value = "start".into();
+		pub static END: Value = "end".into();
+		pub static GROUPS: Value = "groups".into();
+		pub static NAMED: Value = "named".into();
+	}
+
+	let group = |group: Match| -> DictMap {
+		let mut dict = DictMap::default();
+
+		TEXT.with(|text| dict.insert(text.copy(), Str::from(group.as_bytes()).into()));
+		START.with(|start| dict.insert(start.copy(), Value::Int(group.start() as i64)));
+		END.with(|end| dict.insert(end.copy(), Value::Int(group.end() as i64)));
+
+		dict
+	};
+
+	let optional_group = |group_match: Option<Match>| -> Value {
+		group_match
+			.map(|group_match| Dict::new(group(group_match)).into())
+			.unwrap_or_default()
+	};
+
+	let groups: Vec<Value> = captures
+		.iter()
+		.map(optional_group)
+		.collect();
+
+	let named: DictMap = pattern
+		.capture_names()
+		.flatten()
+		.map(|name| (name.into(), optional_group(captures.name(name))))
+		.collect();
+
+	let mut dict = group(captures.get(0).expect("the whole match should be present"));
+
+	GROUPS.with(|key| dict.insert(key.copy(), groups.into()));
+	NAMED.with(|key| dict.insert(key.copy(), Dict::new(named).into()));
+
+	Dict::new(dict).into()
+}
+
+
 impl NativeFun for StdRegex {
 	fn name(&self) -> &'static str { "std.regex" }
 
@@ -133,6 +193,40 @@ unsafe impl Trace for RegexReplaceImpl {
 	gc::unsafe_empty_trace!();
 }
 
+impl RegexReplaceImpl {
+	/// Replace all matches with the result of calling the function with the match object.
+	fn replace_with(
+		&self,
+		string: &[u8],
+		fun: &Function,
+		mut context: CallContext,
+	) -> Result<Value, Panic> {
+		let mut result = Vec::with_capacity(string.len());
+		let mut last = 0;
+
+		for captures in self.pattern.captures_iter(string) {
+			let whole = captures.get(0).expect("the whole match should be present");
+
+			let args_start = context.runtime.arguments.len();
+			context.runtime.arguments.push(match_object(&self.pattern, &captures));
+
+			match context.call(Value::default(), fun, args_start)? {
+				Value::String(ref replacement) => {
+					result.extend_from_slice(&string[last .. whole.start()]);
+					result.extend_from_slice(replacement.as_bytes());
+					last = whole.end();
+				}
+
+				other => return Err(Panic::type_error(other, "string", context.pos)),
+			}
+		}
+
+		result.extend_from_slice(&string[last ..]);
+
+		Ok(Str::from(result).into())
+	}
+}
+
 impl NativeFun for RegexReplaceImpl {
 	fn name(&self) -> &'static str { "std.regex<replace>" }
 
@@ -145,7 +239,17 @@ impl NativeFun for RegexReplaceImpl {
 				}
 			),
 
-			[ Value::String(_), other ] | [ other, _ ] => {
+			[ Value::String(ref string), Value::Function(ref fun) ] => {
+				let string = string.copy();
+				let fun = fun.copy();
+				self.replace_with(string.as_bytes(), &fun, context)
+			}
+
+			[ Value::String(_), other ] => {
+				Err(Panic::type_error(other.copy(), "string or function", context.pos))
+			},
+
+			[ other, _ ] => {
 				Err(Panic::type_error(other.copy(), "string", context.pos))
 			},
 
@@ -153,3 +257,60 @@ impl NativeFun for RegexReplaceImpl {
 		}
 	}
 }
+
+#[derive(Finalize)]
+struct RegexFindImpl {
+	pattern: Rc<Regex>,
+}
+
+/// RegexFindImpl has no garbage-collected fields.
+unsafe impl Trace for RegexFindImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for RegexFindImpl {
+	fn name(&self) -> &'static str { "std.regex<find>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				self.pattern
+					.captures(string.as_ref())
+					.map(|captures| match_object(&self.pattern, &captures))
+					.unwrap_or_default()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+#[derive(Finalize)]
+struct RegexFindAllImpl {
+	pattern: Rc<Regex>,
+}
+
+/// RegexFindAllImpl has no garbage-collected fields.
+unsafe impl Trace for RegexFindAllImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for RegexFindAllImpl {
+	fn name(&self) -> &'static str { "std.regex<find_all>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				self.pattern
+					.captures_iter(string.as_ref())
+					.map(|captures| match_object(&self.pattern, &captures))
+					.collect::<Vec<_>>()
+					.into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,144 @@
+use std::{
+	collections::hash_map::RandomState,
+	hash::{BuildHasher, Hasher},
+	time::Duration,
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	CallContext,
+	Dict,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(Retry) }
+
+#[derive(Trace, Finalize)]
+struct Retry;
+
+
+/// The retry policy.
+#[derive(Debug)]
+struct Policy {
+	/// Maximum number of calls.
+	attempts: i64,
+	/// Delay before the first retry, in milliseconds.
+	delay: f64,
+	/// Factor by which the delay is multiplied after each retry.
+	backoff: f64,
+	/// Whether delays are randomly shortened by up to half, so that many scripts retrying
+	/// at once don't do it in lockstep.
+	jitter: bool,
+}
+
+
+impl Default for Policy {
+	fn default() -> Self {
+		Self {
+			attempts: 3,
+			delay: 1000.0,
+			backoff: 2.0,
+			jitter: true,
+		}
+	}
+}
+
+
+impl Retry {
+	/// Build the retry policy from the options dict. Missing options keep the default.
+	fn policy(options: &Dict, pos: SourcePos) -> Result<Policy, Panic> {
+		let option = |name: &str| options.get(&name.into()).unwrap_or_default();
+
+		let number = |value: Value| -> Result<Option<f64>, Panic> {
+			match value {
+				Value::Nil => Ok(None),
+				Value::Int(int) if int >= 0 => Ok(Some(int as f64)),
+				Value::Float(ref float) if float.0 >= 0.0 => Ok(Some(float.0)),
+				value @ (Value::Int(_) | Value::Float(_)) => Err(Panic::value_error(value, "positive number", pos.copy())),
+				value => Err(Panic::type_error(value, "int or float", pos.copy())),
+			}
+		};
+
+		let mut policy = Policy::default();
+
+		match option("attempts") {
+			Value::Nil => (),
+			Value::Int(attempts) if attempts > 0 => policy.attempts = attempts,
+			value @ Value::Int(_) => return Err(Panic::value_error(value, "positive integer", pos)),
+			value => return Err(Panic::type_error(value, "int", pos)),
+		}
+
+		if let Some(delay) = number(option("delay"))? {
+			policy.delay = delay;
+		}
+
+		if let Some(backoff) = number(option("backoff"))? {
+			policy.backoff = backoff;
+		}
+
+		match option("jitter") {
+			Value::Nil => (),
+			Value::Bool(jitter) => policy.jitter = jitter,
+			value => return Err(Panic::type_error(value, "bool", pos)),
+		}
+
+		Ok(policy)
+	}
+
+
+	/// A random number in [0, 1).
+	fn random() -> f64 {
+		// The standard hasher is randomly seeded.
+		let random = RandomState::new().build_hasher().finish();
+
+		(random >> 11) as f64 / (1u64 << 53) as f64
+	}
+}
+
+impl NativeFun for Retry {
+	fn name(&self) -> &'static str { "std.retry" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (policy, fun) = match context.args() {
+			[ Value::Dict(ref options), Value::Function(ref fun) ] => {
+				(Self::policy(options, context.pos.copy())?, fun.copy())
+			}
+
+			[ Value::Dict(_), other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let args_start = context.args_start + 2;
+		let mut delay = policy.delay;
+
+		for _ in 1 .. policy.attempts {
+			let result = context.call(Value::default(), &fun, args_start)?;
+
+			if !matches!(result, Value::Error(_)) {
+				return Ok(result);
+			}
+
+			let sleep =
+				if policy.jitter {
+					delay * (1.0 - Self::random() / 2.0)
+				} else {
+					delay
+				};
+
+			std::thread::sleep(Duration::from_secs_f64(sleep / 1000.0));
+
+			delay *= policy.backoff;
+		}
+
+		// The last attempt, whose result is returned even if it's an error.
+		context.call(Value::default(), &fun, args_start)
+	}
+}
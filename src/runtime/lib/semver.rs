@@ -0,0 +1,143 @@
+use std::cmp::Ordering;
+
+use gc::{Finalize, Trace};
+use semver::{Version, VersionReq};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Compare) }
+inventory::submit! { RustFun::from(Matches) }
+
+
+/// Parse a version, ignoring surrounding whitespace and a `v` prefix, as commonly
+/// printed by tools.
+fn parse(version: &str) -> Result<Version, semver::Error> {
+	let version = version.trim();
+	Version::parse(version.strip_prefix('v').unwrap_or(version))
+}
+
+
+/// Get a version argument, which must be valid.
+fn version(value: &Value, pos: SourcePos) -> Result<Version, Panic> {
+	parse(util::text(value, pos.copy())?)
+		.map_err(|_| Panic::value_error(value.copy(), "valid semantic version", pos))
+}
+
+
+/// Parse a semantic version into a dict with the major, minor and patch numbers, and the
+/// pre-release and build metadata strings, which are nil if absent. Invalid versions
+/// produce an error value.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl Parse {
+	fn parse(version: &Version) -> Value {
+		let optional = |text: &str| Some(text.to_owned()).filter(|text| !text.is_empty());
+
+		let fields: [(&str, Value); 5] = [
+			("major", (version.major as i64).into()),
+			("minor", (version.minor as i64).into()),
+			("patch", (version.patch as i64).into()),
+			("pre", optional(version.pre.as_str()).into()),
+			("build", optional(version.build.as_str()).into()),
+		];
+
+		let dict: DictMap = IntoIterator::into_iter(fields)
+			.map(|(name, field)| (name.into(), field))
+			.collect();
+
+		Dict::new(dict).into()
+	}
+}
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.semver.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => {
+				let version = util::text(value, context.pos.copy())?;
+
+				Ok(
+					parse(version)
+						.map(|version| Self::parse(&version))
+						.unwrap_or_else(|error| Error::new(error.to_string().into(), value.copy()).into())
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Compare two versions by semantic versioning precedence, returning -1, 0 or 1. Build
+/// metadata does not affect precedence.
+#[derive(Trace, Finalize)]
+struct Compare;
+
+impl NativeFun for Compare {
+	fn name(&self) -> &'static str { "std.semver.compare" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ left, right ] => {
+				let left = version(left, context.pos.copy())?;
+				let right = version(right, context.pos.copy())?;
+
+				let ordering = left.cmp_precedence(&right);
+
+				Ok(
+					Value::Int(
+						match ordering {
+							Ordering::Less => -1,
+							Ordering::Equal => 0,
+							Ordering::Greater => 1,
+						}
+					)
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Whether a version matches a range expression, which is a comma separated list of
+/// comparisons, such as `>=1.2, <2`. Comparison operators are `=`, `>`, `>=`, `<`, `<=`,
+/// `~` (patch updates) and `^` (compatible updates, the default).
+#[derive(Trace, Finalize)]
+struct Matches;
+
+impl NativeFun for Matches {
+	fn name(&self) -> &'static str { "std.semver.matches" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, range ] => {
+				let version = version(value, context.pos.copy())?;
+				let range = VersionReq::parse(util::text(range, context.pos.copy())?)
+					.map_err(|_| Panic::value_error(range.copy(), "valid version range", context.pos.copy()))?;
+
+				Ok(range.matches(&version).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
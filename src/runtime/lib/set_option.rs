@@ -0,0 +1,41 @@
+use gc::{Finalize, Trace};
+
+use crate::runtime::command::OptionError;
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(SetOption) }
+
+#[derive(Trace, Finalize)]
+struct SetOption;
+
+impl NativeFun for SetOption {
+	fn name(&self) -> &'static str { "std.set_option" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (name, value) = match context.args() {
+			[ name @ Value::String(_), value ] => (name.copy(), value.copy()),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let result = match &name {
+			Value::String(string) => context.runtime.options.set(string, value.copy()),
+			_ => unreachable!("option name should be a string"),
+		};
+
+		result.map_err(
+			|error| match error {
+				OptionError::InvalidName => Panic::value_error(name, "valid option", context.pos),
+				OptionError::InvalidValue(expected) => Panic::value_error(value, expected, context.pos),
+			}
+		)
+	}
+}
@@ -0,0 +1,506 @@
+use std::{
+	cell::RefCell,
+	convert::TryFrom,
+	ffi::OsStr,
+	io::{self, BufRead, BufReader, Read, Write},
+	path::Path,
+	process::{self, ChildStdin, Stdio},
+	rc::Rc,
+	thread,
+};
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::{command::env, SourcePos};
+
+use super::{
+	kill,
+	util,
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Spawn) }
+
+
+/// A child process. If it's garbage collected before being waited for, it's reaped in
+/// the background, so that it doesn't become a zombie.
+struct Child(Option<process::Child>);
+
+
+impl Child {
+	fn get(&mut self) -> &mut process::Child {
+		self.0.as_mut().expect("child is only taken when dropped")
+	}
+}
+
+
+impl Drop for Child {
+	fn drop(&mut self) {
+		if let Some(mut child) = self.0.take() {
+			if let Ok(None) = child.try_wait() {
+				thread::spawn(move || child.wait());
+			}
+		}
+	}
+}
+
+
+/// The child process, shared by the methods of a child value.
+type Shared = Rc<RefCell<Child>>;
+
+
+/// The stdin pipe of a child, shared by it's methods and by the child's wait method,
+/// which closes it.
+type Input = Rc<RefCell<Option<ChildStdin>>>;
+
+
+/// The stdout or stderr pipe of a child.
+type Output = Rc<RefCell<Option<BufReader<Box<dyn Read>>>>>;
+
+
+/// Run an operation on an open pipe.
+fn with_pipe<S, T, F>(pipe: &Rc<RefCell<Option<S>>>, operation: F) -> io::Result<T>
+where
+	F: FnOnce(&mut S) -> io::Result<T>,
+{
+	match pipe.borrow_mut().as_mut() {
+		Some(pipe) => operation(pipe),
+		None => Err(io::Error::new(io::ErrorKind::Other, "pipe is closed")),
+	}
+}
+
+
+/// The exit status of a process, which is 128 plus the signal number if it was killed
+/// by a signal, as in shells.
+fn status(status: process::ExitStatus) -> Value {
+	#[cfg(unix)]
+	let signal = status.signal();
+	// Processes are not terminated by signals on Windows.
+	#[cfg(windows)]
+	let signal: Option<i32> = None;
+
+	let code = status
+		.code()
+		.or_else(|| signal.map(|signal| 128 + signal))
+		.unwrap_or(1);
+
+	Value::Int(code.into())
+}
+
+
+/// How to set up a standard stream of the child.
+fn stdio(options: &Dict, name: &str, pos: SourcePos) -> Result<Stdio, Panic> {
+	match options.get(&name.into()) {
+		Ok(Value::Nil) | Err(_) => Ok(Stdio::piped()),
+		Ok(Value::String(ref mode)) => match mode.as_bytes() {
+			b"pipe" => Ok(Stdio::piped()),
+			b"inherit" => Ok(Stdio::inherit()),
+			b"null" => Ok(Stdio::null()),
+			_ => Err(Panic::value_error(Value::String(mode.copy()), "pipe, inherit or null", pos)),
+		},
+		Ok(other) => Err(Panic::type_error(other, "string", pos)),
+	}
+}
+
+
+/// Spawn a process from an array with the program and it's arguments, returning a child
+/// with it's pid, the stdin, stdout and stderr pipes, and wait, try_wait and kill methods.
+/// The program is searched in the PATH. Options:
+/// - cwd: the working directory, which defaults to the one of commands.
+/// - env: a dict of additional environment variables.
+/// - stdin, stdout, stderr: pipe (the default), inherit or null. Streams which are not
+///   piped are nil in the child.
+/// Failing to spawn the process produces an error value.
+#[derive(Trace, Finalize)]
+struct Spawn;
+
+impl Spawn {
+	fn spawn(mut command: process::Command) -> io::Result<Value> {
+		let mut child = command.spawn()?;
+
+		let stdin = child.stdin.take().map(|stdin| Rc::new(RefCell::new(Some(stdin))));
+		let stdout = child.stdout.take().map(|stdout| Box::new(stdout) as Box<dyn Read>);
+		let stderr = child.stderr.take().map(|stderr| Box::new(stderr) as Box<dyn Read>);
+
+		let pid = child.id();
+		let shared: Shared = Rc::new(RefCell::new(Child(Some(child))));
+
+		let output = |pipe: Option<Box<dyn Read>>| -> Value {
+			pipe
+				.map(|pipe| -> Output { Rc::new(RefCell::new(Some(BufReader::new(pipe)))) })
+				.map(
+					|pipe| util::dict([
+						("read", ReadImpl(pipe.clone()).into()),
+						("read_line", ReadLineImpl(pipe.clone()).into()),
+						("close", CloseOutputImpl(pipe).into()),
+					])
+				)
+				.into()
+		};
+
+		Ok(
+			util::dict([
+				("pid", Value::Int(pid.into())),
+				(
+					"stdin",
+					stdin
+						.clone()
+						.map(
+							|pipe| util::dict([
+								("write", WriteImpl(pipe.clone()).into()),
+								("close", CloseInputImpl(pipe).into()),
+							])
+						)
+						.into()
+				),
+				("stdout", output(stdout)),
+				("stderr", output(stderr)),
+				("wait", WaitImpl(shared.clone(), stdin).into()),
+				("try_wait", TryWaitImpl(shared.clone()).into()),
+				("kill", KillImpl(shared).into()),
+			])
+		)
+	}
+}
+
+impl NativeFun for Spawn {
+	fn name(&self) -> &'static str { "std.process.spawn" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (argv, options) = match context.args() {
+			[ Value::Array(ref argv) ] => (argv.copy(), Dict::default()),
+			[ Value::Array(ref argv), Value::Dict(ref options) ] => (argv.copy(), options.copy()),
+
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let argv = argv
+			.borrow()
+			.iter()
+			.map(
+				|arg| match arg {
+					Value::String(ref arg) => Ok(AsRef::<OsStr>::as_ref(arg).into()),
+					other => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+				}
+			)
+			.collect::<Result<Vec<Box<OsStr>>, Panic>>()?;
+
+		if argv.is_empty() {
+			return Err(Panic::value_error(Value::from(Vec::<Value>::new()), "non-empty array", context.pos));
+		}
+
+		context.runtime.options.policy.check_command(&argv[0], context.pos.copy())?;
+
+		let mut command = process::Command::new(&argv[0]);
+		command
+			.args(&argv[1..])
+			.stdin(stdio(&options, "stdin", context.pos.copy())?)
+			.stdout(stdio(&options, "stdout", context.pos.copy())?)
+			.stderr(stdio(&options, "stderr", context.pos.copy())?);
+
+		env::apply(&mut command);
+
+		match options.get(&"cwd".into()) {
+			Ok(Value::Nil) | Err(_) => {
+				if let Some(cwd) = &context.runtime.options.cwd {
+					command.current_dir(cwd);
+				}
+			}
+
+			Ok(Value::String(ref cwd)) => {
+				let cwd = context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(cwd)));
+				context.runtime.options.policy.check_path(&cwd, context.pos.copy())?;
+				command.current_dir(cwd);
+			}
+
+			Ok(other) => return Err(Panic::type_error(other, "string", context.pos)),
+		}
+
+		match options.get(&"env".into()) {
+			Ok(Value::Nil) | Err(_) => (),
+
+			Ok(Value::Dict(ref env)) => {
+				for (name, value) in super::dict::entries(env) {
+					match (name, value) {
+						(Value::String(ref name), Value::String(ref value)) => {
+							context.runtime.options.policy.check_env(name.as_ref(), context.pos.copy())?;
+							command.env(AsRef::<OsStr>::as_ref(name), AsRef::<OsStr>::as_ref(value));
+						}
+
+						(Value::String(_), other) | (other, _) => {
+							return Err(Panic::type_error(other, "string", context.pos))
+						}
+					}
+				}
+			}
+
+			Ok(other) => return Err(Panic::type_error(other, "dict", context.pos)),
+		}
+
+		let program = Value::from(argv[0].to_os_string());
+
+		Ok(
+			Self::spawn(command)
+				.unwrap_or_else(|error| Error::new(error.to_string().into(), program).into())
+		)
+	}
+}
+
+
+/// Write a string or byte array to the child's stdin.
+#[derive(Finalize)]
+struct WriteImpl(Input);
+
+impl NativeFun for WriteImpl {
+	fn name(&self) -> &'static str { "std.process.stdin<write>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let data = match context.args() {
+			[ value ] => util::bytes(value)
+				.ok_or_else(|| Panic::type_error(value.copy(), "string or byte array", context.pos.copy()))?,
+
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = with_pipe(
+			&self.0,
+			|stdin| {
+				stdin.write_all(&data)?;
+				stdin.flush()
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Close the child's stdin, signaling the end of input.
+#[derive(Finalize)]
+struct CloseInputImpl(Input);
+
+impl NativeFun for CloseInputImpl {
+	fn name(&self) -> &'static str { "std.process.stdin<close>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			match self.0.borrow_mut().take() {
+				Some(_) => Value::default(),
+				None => Error::new("pipe is closed".into(), Value::default()).into(),
+			}
+		)
+	}
+}
+
+
+/// Read up to the given number of bytes from the child's stdout or stderr, returning as
+/// soon as some data is available. If no count is given, read until the child closes the
+/// stream. Returns nil at the end of the stream.
+#[derive(Finalize)]
+struct ReadImpl(Output);
+
+impl NativeFun for ReadImpl {
+	fn name(&self) -> &'static str { "std.process.output<read>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let count = match context.args() {
+			[] => None,
+			[ Value::Int(count) ] if *count > 0 => Some(*count as usize),
+
+			[ count @ Value::Int(_) ] => return Err(Panic::value_error(count.copy(), "positive integer", context.pos)),
+			[ other ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let result = with_pipe(
+			&self.0,
+			|output| {
+				let buffer = match count {
+					Some(count) => {
+						let available = output.fill_buf()?;
+						let buffer = available[.. available.len().min(count)].to_vec();
+						output.consume(buffer.len());
+						buffer
+					}
+
+					None => {
+						let mut buffer = Vec::new();
+						output.read_to_end(&mut buffer)?;
+						buffer
+					}
+				};
+
+				Ok(if buffer.is_empty() { None } else { Some(Str::from(buffer)) })
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Read a line from the child's stdout or stderr, without the line terminator. Returns
+/// nil at the end of the stream.
+#[derive(Finalize)]
+struct ReadLineImpl(Output);
+
+impl NativeFun for ReadLineImpl {
+	fn name(&self) -> &'static str { "std.process.output<read_line>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let result = with_pipe(
+			&self.0,
+			|output| {
+				let mut line = Vec::new();
+
+				if output.read_until(b'\n', &mut line)? == 0 {
+					return Ok(None);
+				}
+
+				if line.last() == Some(&b'\n') {
+					line.pop();
+				}
+
+				if line.last() == Some(&b'\r') {
+					line.pop();
+				}
+
+				Ok(Some(Str::from(line)))
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Close the child's stdout or stderr. Further output of the child to the stream fails.
+#[derive(Finalize)]
+struct CloseOutputImpl(Output);
+
+impl NativeFun for CloseOutputImpl {
+	fn name(&self) -> &'static str { "std.process.output<close>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			match self.0.borrow_mut().take() {
+				Some(_) => Value::default(),
+				None => Error::new("pipe is closed".into(), Value::default()).into(),
+			}
+		)
+	}
+}
+
+
+/// Wait for the child to finish, returning it's exit status. The child's stdin is closed
+/// first, so that it doesn't wait for input forever.
+#[derive(Finalize)]
+struct WaitImpl(Shared, Option<Input>);
+
+impl NativeFun for WaitImpl {
+	fn name(&self) -> &'static str { "std.process.child<wait>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		if let Some(stdin) = &self.1 {
+			stdin.borrow_mut().take();
+		}
+
+		Ok(self.0.borrow_mut().get().wait().map(status).into())
+	}
+}
+
+
+/// Get the exit status of the child if it has finished, or nil otherwise.
+#[derive(Finalize)]
+struct TryWaitImpl(Shared);
+
+impl NativeFun for TryWaitImpl {
+	fn name(&self) -> &'static str { "std.process.child<try_wait>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(self.0.borrow_mut().get().try_wait().map(|result| result.map(status)).into())
+	}
+}
+
+
+/// Send a signal to the child, given by number or name, which defaults to TERM. Signaling
+/// a child which has already been waited for produces an error value.
+#[derive(Finalize)]
+struct KillImpl(Shared);
+
+impl NativeFun for KillImpl {
+	fn name(&self) -> &'static str { "std.process.child<kill>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let signal = match context.args() {
+			[] => kill::TERM,
+			[ value @ Value::Int(int) ] => libc::c_int::try_from(*int)
+				.map_err(|_| Panic::value_error(value.copy(), "valid signal", context.pos.copy()))?,
+			[ value @ Value::String(name) ] => kill::signal(name.as_bytes())
+				.ok_or_else(|| Panic::value_error(value.copy(), "valid signal", context.pos.copy()))?,
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "int or string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let mut child = self.0.borrow_mut();
+		let child = child.get();
+
+		// The pid may have been reused once the child is reaped.
+		let result = match child.try_wait() {
+			Ok(None) => kill::send(child.id() as kill::Pid, signal),
+			Ok(Some(_)) => Err(io::Error::new(io::ErrorKind::Other, "process has finished")),
+			Err(error) => Err(error),
+		};
+
+		Ok(result.into())
+	}
+}
+
+
+// The methods have no garbage-collected fields.
+unsafe impl Trace for WriteImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for CloseInputImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ReadImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ReadLineImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for CloseOutputImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for WaitImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for TryWaitImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for KillImpl { gc::unsafe_empty_trace!(); }
@@ -0,0 +1,526 @@
+use std::{
+	cell::RefCell,
+	ffi::OsStr,
+	path::Path,
+	rc::Rc,
+};
+
+use gc::{Finalize, Trace};
+use rusqlite::{
+	types::{ToSqlOutput, Value as SqlValue, ValueRef},
+	Connection,
+	Statement,
+	ToSql,
+};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	Buffer,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	Float,
+	Function,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Open) }
+
+
+/// An open database, shared by the methods of a database value and its statements. The
+/// database is closed when all methods are garbage collected, or when explicitly closed.
+type Database = Rc<RefCell<Option<Connection>>>;
+
+
+/// Run an operation on an open database. SQL errors are converted to error values, with
+/// the given context.
+fn with_database<T, F>(database: &Database, context: &Value, operation: F) -> Result<T, Error>
+where
+	F: FnOnce(&Connection) -> rusqlite::Result<T>,
+{
+	match database.borrow().as_ref() {
+		Some(connection) => operation(connection).map_err(|error| Error::new(error.to_string().into(), context.copy())),
+		None => Err(Error::new("database is closed".into(), Value::default())),
+	}
+}
+
+
+/// Get the SQL argument.
+fn sql<'a>(value: &'a Value, pos: SourcePos) -> Result<&'a str, Panic> {
+	match value {
+		Value::String(string) => std::str::from_utf8(string.as_bytes())
+			.map_err(|_| Panic::value_error(value.copy(), "valid UTF-8", pos)),
+		other => Err(Panic::type_error(other.copy(), "string", pos)),
+	}
+}
+
+
+/// A value bound to a statement parameter. Strings which are not valid UTF-8 are bound as
+/// blobs.
+struct Param(SqlValue);
+
+
+impl Param {
+	fn new(value: &Value, pos: SourcePos) -> Result<Self, Panic> {
+		let value = match value {
+			Value::Nil => SqlValue::Null,
+			Value::Bool(flag) => SqlValue::Integer(*flag as i64),
+			Value::Int(int) => SqlValue::Integer(*int),
+			Value::Float(Float(float)) => SqlValue::Real(*float),
+			Value::Byte(byte) => SqlValue::Integer(*byte as i64),
+			Value::String(string) => match std::str::from_utf8(string.as_bytes()) {
+				Ok(text) => SqlValue::Text(text.to_owned()),
+				Err(_) => SqlValue::Blob(string.as_bytes().to_owned()),
+			},
+			Value::Buffer(buffer) => SqlValue::Blob(buffer.borrow().clone()),
+			other => return Err(Panic::type_error(other.copy(), "nil, bool, int, float, string or buffer", pos)),
+		};
+
+		Ok(Self(value))
+	}
+}
+
+
+impl ToSql for Param {
+	fn to_sql(&self) -> rusqlite::Result<ToSqlOutput> {
+		self.0.to_sql()
+	}
+}
+
+
+/// Statement parameters, given either as an array of positional parameters, or as a dict
+/// of named parameters. Names may omit the leading colon.
+enum Params {
+	Positional(Vec<Param>),
+	Named(Vec<(String, Param)>),
+}
+
+
+impl Params {
+	fn new(value: Option<&Value>, pos: SourcePos) -> Result<Self, Panic> {
+		match value {
+			None => Ok(Self::Positional(Vec::new())),
+
+			Some(Value::Array(array)) => array
+				.borrow()
+				.iter()
+				.map(|value| Param::new(value, pos.copy()))
+				.collect::<Result<_, _>>()
+				.map(Self::Positional),
+
+			Some(Value::Dict(dict)) => dict
+				.borrow()
+				.iter()
+				.map(
+					|(key, value)| {
+						let name = match key {
+							Value::String(name) => String::from_utf8_lossy(name.as_bytes()),
+							other => return Err(Panic::type_error(other.copy(), "string", pos.copy())),
+						};
+
+						let name = if name.starts_with(&[':', '@', '$'][..]) {
+							name.into_owned()
+						} else {
+							format!(":{}", name)
+						};
+
+						Ok((name, Param::new(value, pos.copy())?))
+					}
+				)
+				.collect::<Result<_, _>>()
+				.map(Self::Named),
+
+			Some(other) => Err(Panic::type_error(other.copy(), "array or dict", pos)),
+		}
+	}
+
+
+	/// Execute a statement, returning the number of changed rows.
+	fn execute(&self, statement: &mut Statement) -> rusqlite::Result<usize> {
+		match self {
+			Self::Positional(params) => statement.execute(rusqlite::params_from_iter(params)),
+			Self::Named(params) => statement.execute(Self::named(params).as_slice()),
+		}
+	}
+
+
+	/// Run a query, returning the rows as dicts keyed by column name.
+	fn query(&self, statement: &mut Statement) -> rusqlite::Result<Vec<Value>> {
+		let columns: Vec<Value> = statement
+			.column_names()
+			.into_iter()
+			.map(Value::from)
+			.collect();
+
+		let mut rows = match self {
+			Self::Positional(params) => statement.query(rusqlite::params_from_iter(params))?,
+			Self::Named(params) => statement.query(Self::named(params).as_slice())?,
+		};
+
+		let mut result = Vec::new();
+
+		while let Some(row) = rows.next()? {
+			let mut dict = DictMap::with_capacity(columns.len());
+
+			for (ix, column) in columns.iter().enumerate() {
+				dict.insert(column.copy(), from_sql(row.get_ref(ix)?));
+			}
+
+			result.push(Dict::new(dict).into());
+		}
+
+		Ok(result)
+	}
+
+
+	fn named(params: &[(String, Param)]) -> Vec<(&str, &dyn ToSql)> {
+		params
+			.iter()
+			.map(|(name, param)| (name.as_str(), param as &dyn ToSql))
+			.collect()
+	}
+}
+
+
+/// Convert a SQL value. Text which is not valid UTF-8 is kept as is, and blobs are
+/// converted to buffers.
+fn from_sql(value: ValueRef) -> Value {
+	match value {
+		ValueRef::Null => Value::Nil,
+		ValueRef::Integer(int) => int.into(),
+		ValueRef::Real(float) => float.into(),
+		ValueRef::Text(text) => text.into(),
+		ValueRef::Blob(blob) => Buffer::new(blob.to_owned()).into(),
+	}
+}
+
+
+/// Get the SQL and parameters of exec and query.
+fn statement_args<'a>(context: &'a CallContext) -> Result<(&'a Value, &'a str, Params), Panic> {
+	match context.args() {
+		[ value, params @ .. ] if params.len() <= 1 => Ok(
+			(value, sql(value, context.pos.copy())?, Params::new(params.first(), context.pos.copy())?)
+		),
+
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy()))
+	}
+}
+
+
+/// Methods take no arguments.
+fn no_args(context: &CallContext) -> Result<(), Panic> {
+	match context.args() {
+		[] => Ok(()),
+		args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos.copy()))
+	}
+}
+
+
+/// Open a SQLite database, which is created if it doesn't exist, returning a database
+/// value with methods. The path `:memory:` opens a temporary in-memory database. Errors
+/// are returned as error values.
+#[derive(Trace, Finalize)]
+struct Open;
+
+impl NativeFun for Open {
+	fn name(&self) -> &'static str { "std.sqlite.open" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let path = match context.args() {
+			[ Value::String(ref path) ] => path,
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let connection = if path.as_bytes() == b":memory:" {
+			Connection::open_in_memory()
+		} else {
+			let path = context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path)));
+			context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+			Connection::open(path)
+		};
+
+		let database: Database = match connection {
+			Ok(connection) => Rc::new(RefCell::new(Some(connection))),
+			Err(error) => return Ok(Error::new(error.to_string().into(), path.copy().into()).into()),
+		};
+
+		Ok(
+			util::dict([
+				("exec", ExecImpl(database.clone()).into()),
+				("batch", BatchImpl(database.clone()).into()),
+				("query", QueryImpl(database.clone()).into()),
+				("prepare", PrepareImpl(database.clone()).into()),
+				("transaction", TransactionImpl(database.clone()).into()),
+				("last_insert_id", LastInsertIdImpl(database.clone()).into()),
+				("close", CloseImpl(database).into()),
+			])
+		)
+	}
+}
+
+
+/// Execute a single statement with optional parameters, returning the number of changed
+/// rows.
+#[derive(Finalize)]
+struct ExecImpl(Database);
+
+impl NativeFun for ExecImpl {
+	fn name(&self) -> &'static str { "std.sqlite.database<exec>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, sql, params) = statement_args(&context)?;
+
+		let result = with_database(
+			&self.0,
+			value,
+			|connection| params.execute(&mut connection.prepare(sql)?)
+		);
+
+		Ok(result.map(|changes| changes as i64).into())
+	}
+}
+
+
+/// Execute a script of statements separated by semicolons, without parameters.
+#[derive(Finalize)]
+struct BatchImpl(Database);
+
+impl NativeFun for BatchImpl {
+	fn name(&self) -> &'static str { "std.sqlite.database<batch>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, sql) = match context.args() {
+			[ value ] => (value, sql(value, context.pos.copy())?),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Ok(with_database(&self.0, value, |connection| connection.execute_batch(sql)).into())
+	}
+}
+
+
+/// Run a query with optional parameters, returning an array of dicts keyed by column
+/// name.
+#[derive(Finalize)]
+struct QueryImpl(Database);
+
+impl NativeFun for QueryImpl {
+	fn name(&self) -> &'static str { "std.sqlite.database<query>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, sql, params) = statement_args(&context)?;
+
+		let result = with_database(
+			&self.0,
+			value,
+			|connection| params.query(&mut connection.prepare(sql)?)
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Prepare a statement, returning a statement value with exec and query methods, which
+/// take optional parameters. Prepared statements are cached by the database, so that
+/// repeated executions skip parsing the SQL.
+#[derive(Finalize)]
+struct PrepareImpl(Database);
+
+impl NativeFun for PrepareImpl {
+	fn name(&self) -> &'static str { "std.sqlite.database<prepare>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let value = match context.args() {
+			[ value ] => value,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let sql = sql(value, context.pos.copy())?;
+
+		// Prepare the statement now, so that syntax errors are reported early.
+		let result = with_database(&self.0, value, |connection| connection.prepare_cached(sql).map(drop));
+
+		if let Err(error) = result {
+			return Ok(error.into());
+		}
+
+		let statement = Rc::new(Prepared { database: self.0.clone(), sql: sql.to_owned() });
+
+		Ok(
+			util::dict([
+				("sql", value.copy()),
+				("exec", StatementExecImpl(statement.clone()).into()),
+				("query", StatementQueryImpl(statement).into()),
+			])
+		)
+	}
+}
+
+
+/// A prepared statement, which is fetched from the database's statement cache on each
+/// use.
+struct Prepared {
+	database: Database,
+	sql: String,
+}
+
+
+impl Prepared {
+	fn run<T, F>(&self, context: &CallContext, operation: F) -> Result<Value, Panic>
+	where
+		T: Into<Value>,
+		F: FnOnce(&Params, &mut Statement) -> rusqlite::Result<T>,
+	{
+		let params = match context.args() {
+			[ params @ .. ] if params.len() <= 1 => Params::new(params.first(), context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+		};
+
+		let result = with_database(
+			&self.database,
+			&self.sql.as_str().into(),
+			|connection| {
+				let mut statement = connection.prepare_cached(&self.sql)?;
+				operation(&params, &mut statement)
+			}
+		);
+
+		Ok(result.into())
+	}
+}
+
+
+/// Execute the prepared statement with optional parameters, returning the number of
+/// changed rows.
+#[derive(Finalize)]
+struct StatementExecImpl(Rc<Prepared>);
+
+impl NativeFun for StatementExecImpl {
+	fn name(&self) -> &'static str { "std.sqlite.statement<exec>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		self.0.run(&context, |params, statement| params.execute(statement).map(|changes| changes as i64))
+	}
+}
+
+
+/// Run the prepared query with optional parameters, returning an array of dicts keyed by
+/// column name.
+#[derive(Finalize)]
+struct StatementQueryImpl(Rc<Prepared>);
+
+impl NativeFun for StatementQueryImpl {
+	fn name(&self) -> &'static str { "std.sqlite.statement<query>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		self.0.run(&context, |params, statement| params.query(statement))
+	}
+}
+
+
+/// Run a function in a transaction, returning its result. The transaction is committed
+/// if the function returns normally, and rolled back if it returns an error value or
+/// panics. Transactions may be nested.
+#[derive(Finalize)]
+struct TransactionImpl(Database);
+
+impl TransactionImpl {
+	fn run(&self, sql: &str) -> Result<(), Error> {
+		with_database(&self.0, &sql.into(), |connection| connection.execute_batch(sql))
+	}
+}
+
+impl NativeFun for TransactionImpl {
+	fn name(&self) -> &'static str { "std.sqlite.database<transaction>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let fun: Function = match context.args() {
+			[ Value::Function(ref fun) ] => fun.copy(),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		if let Err(error) = self.run("SAVEPOINT hush_transaction") {
+			return Ok(error.into());
+		}
+
+		let result = util::call(&mut context, &fun, []);
+
+		let end = match result {
+			Ok(Value::Error(_)) | Err(_) => self
+				.run("ROLLBACK TO hush_transaction")
+				.and_then(|()| self.run("RELEASE hush_transaction")),
+
+			Ok(_) => self.run("RELEASE hush_transaction"),
+		};
+
+		match (result, end) {
+			(Err(panic), _) => Err(panic),
+			(Ok(_), Err(error)) => Ok(error.into()),
+			(Ok(value), Ok(())) => Ok(value),
+		}
+	}
+}
+
+
+/// The id of the row most recently inserted.
+#[derive(Finalize)]
+struct LastInsertIdImpl(Database);
+
+impl NativeFun for LastInsertIdImpl {
+	fn name(&self) -> &'static str { "std.sqlite.database<last_insert_id>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		Ok(with_database(&self.0, &Value::default(), |connection| Ok(connection.last_insert_rowid())).into())
+	}
+}
+
+
+/// Close the database. Further operations produce error values.
+#[derive(Finalize)]
+struct CloseImpl(Database);
+
+impl NativeFun for CloseImpl {
+	fn name(&self) -> &'static str { "std.sqlite.database<close>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		no_args(&context)?;
+
+		let connection = match self.0.borrow_mut().take() {
+			Some(connection) => connection,
+			None => return Ok(Error::new("database is closed".into(), Value::default()).into()),
+		};
+
+		Ok(
+			connection
+				.close()
+				.map_err(|(_, error)| Error::new(error.to_string().into(), Value::default()))
+				.into()
+		)
+	}
+}
+
+
+// The methods have no garbage-collected fields.
+unsafe impl Trace for ExecImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for BatchImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for QueryImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for PrepareImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for StatementExecImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for StatementQueryImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for TransactionImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for LastInsertIdImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for CloseImpl { gc::unsafe_empty_trace!(); }
@@ -0,0 +1,122 @@
+use std::{ffi::OsStr, sync::Arc};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::{command::Remote, SourcePos};
+
+use super::{
+	CallContext,
+	Dict,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(Ssh) }
+
+#[derive(Trace, Finalize)]
+struct Ssh;
+
+impl Ssh {
+	/// Build the remote from the options dict, which must contain the host.
+	fn remote(options: &Dict, pos: SourcePos) -> Result<Remote, Panic> {
+		let option = |name: &str| options.get(&name.into()).unwrap_or_default();
+
+		let mut destination = match option("host") {
+			Value::String(ref host) => AsRef::<OsStr>::as_ref(host).to_owned(),
+			value => return Err(Panic::type_error(value, "string", pos)),
+		};
+
+		match option("user") {
+			Value::Nil => (),
+			Value::String(ref user) => {
+				let mut user = AsRef::<OsStr>::as_ref(user).to_owned();
+				user.push("@");
+				user.push(destination);
+				destination = user;
+			}
+			value => return Err(Panic::type_error(value, "string", pos)),
+		}
+
+		let program = match option("program") {
+			Value::Nil => OsStr::new("ssh").into(),
+			Value::String(ref program) => AsRef::<OsStr>::as_ref(program).into(),
+			value => return Err(Panic::type_error(value, "string", pos)),
+		};
+
+		let mut args: Vec<Box<OsStr>> = Vec::new();
+
+		match option("port") {
+			Value::Nil => (),
+			Value::Int(port) if (1 ..= 65535).contains(&port) => {
+				args.push(OsStr::new("-p").into());
+				args.push(OsStr::new(&port.to_string()).into());
+			}
+			value @ Value::Int(_) => return Err(Panic::value_error(value, "port number", pos)),
+			value => return Err(Panic::type_error(value, "int", pos)),
+		}
+
+		match option("args") {
+			Value::Nil => (),
+			Value::Array(ref array) => {
+				for arg in array.borrow().iter() {
+					match arg {
+						Value::String(arg) => args.push(AsRef::<OsStr>::as_ref(arg).into()),
+						other => return Err(Panic::type_error(other.copy(), "string", pos)),
+					}
+				}
+			}
+			value => return Err(Panic::type_error(value, "array", pos)),
+		}
+
+		Ok(
+			Remote {
+				program,
+				destination: destination.into(),
+				args: args.into(),
+			}
+		)
+	}
+}
+
+impl NativeFun for Ssh {
+	fn name(&self) -> &'static str { "std.ssh" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (remote, fun) = match context.args() {
+			[ Value::String(ref host), Value::Function(ref fun) ] => {
+				let remote = Remote {
+					program: OsStr::new("ssh").into(),
+					destination: AsRef::<OsStr>::as_ref(host).into(),
+					args: Box::default(),
+				};
+
+				(remote, fun.copy())
+			}
+
+			[ Value::Dict(ref options), Value::Function(ref fun) ] => {
+				(Self::remote(options, context.pos.copy())?, fun.copy())
+			}
+
+			[ Value::String(_) | Value::Dict(_), other ] => {
+				return Err(Panic::type_error(other.copy(), "function", context.pos))
+			}
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "string or dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		// Commands in the remote host are run through the local ssh program.
+		context.runtime.options.policy.check_command(&remote.program, context.pos.copy())?;
+
+		let previous = std::mem::replace(&mut context.runtime.options.remote, Some(Arc::new(remote)));
+
+		let args_start = context.args_start + 2;
+		let result = context.call(Value::default(), &fun, args_start);
+
+		context.runtime.options.remote = previous;
+
+		result
+	}
+}
@@ -0,0 +1,303 @@
+use std::{
+	cell::RefCell,
+	collections::BTreeMap,
+	ffi::OsStr,
+	fs::{self, File},
+	io::{self, BufWriter, Write},
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	util,
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Open) }
+
+
+/// Write data as JSON to a file. The data is written to a temporary file which then
+/// replaces the file, so that a crash never leaves a partially written file.
+pub fn write_atomic<T: serde::Serialize>(path: &Path, data: &T) -> io::Result<()> {
+	let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+	temp_name.push(format!(".tmp-{}", std::process::id()));
+	let temp_path = path.with_file_name(temp_name);
+
+	let result = (|| -> io::Result<()> {
+		let mut writer = BufWriter::new(File::create(&temp_path)?);
+		serde_json::to_writer(&mut writer, data)?;
+		writer.flush()?;
+		writer.get_ref().sync_all()?;
+
+		fs::rename(&temp_path, path)?;
+
+		// Make the rename itself durable.
+		let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+		File::open(parent.unwrap_or_else(|| Path::new(".")))?.sync_all()
+	})();
+
+	if result.is_err() {
+		let _ = fs::remove_file(&temp_path);
+	}
+
+	result
+}
+
+
+/// The entries of a store, kept in memory and written to disk on every change. Values
+/// are kept as JSON, so that they hold no garbage-collected references.
+struct Store {
+	path: PathBuf,
+	entries: BTreeMap<String, serde_json::Value>,
+}
+
+
+impl Store {
+	/// Load a store from disk. A missing file is an empty store.
+	fn load(path: PathBuf) -> io::Result<Self> {
+		let entries = match fs::read(&path) {
+			Ok(data) => serde_json::from_slice(&data)?,
+			Err(error) if error.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+			Err(error) => return Err(error),
+		};
+
+		Ok(Self { path, entries })
+	}
+
+
+	/// Write the store to disk.
+	fn save(&self) -> io::Result<()> {
+		write_atomic(&self.path, &self.entries)
+	}
+
+
+	/// Change an entry and save the store. If saving fails, the change is undone.
+	fn update(&mut self, key: String, value: Option<serde_json::Value>) -> io::Result<Option<serde_json::Value>> {
+		let previous = match value {
+			Some(value) => self.entries.insert(key.clone(), value),
+			None => self.entries.remove(&key),
+		};
+
+		match self.save() {
+			Ok(()) => Ok(previous),
+			Err(error) => {
+				match previous {
+					Some(previous) => self.entries.insert(key, previous),
+					None => self.entries.remove(&key),
+				};
+
+				Err(error)
+			}
+		}
+	}
+}
+
+
+/// An open store, shared by the methods of a store value.
+type Shared = Rc<RefCell<Store>>;
+
+
+/// Get a key argument.
+fn key(context: &CallContext, value: &Value) -> Result<String, Panic> {
+	match value {
+		Value::String(string) => std::str::from_utf8(string.as_bytes())
+			.map(str::to_owned)
+			.map_err(|_| Panic::value_error(value.copy(), "valid UTF-8", context.pos.copy())),
+		other => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+	}
+}
+
+
+/// The error value of a failed save.
+fn error(store: &Store, error: io::Error) -> Value {
+	Error::new(error.to_string().into(), Str::from(store.path.clone()).into()).into()
+}
+
+
+/// Open a persistent key-value store in a single file, which is created on the first
+/// change. Keys are strings, and values are anything that can be encoded as JSON. Every
+/// change is written to disk atomically, so that a crash never corrupts the store. Errors
+/// are returned as error values.
+#[derive(Trace, Finalize)]
+struct Open;
+
+impl NativeFun for Open {
+	fn name(&self) -> &'static str { "std.store.open" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, path) = match context.args() {
+			[ value @ Value::String(ref path) ] => (value, path),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let path = context.runtime.options.path(Path::new(AsRef::<OsStr>::as_ref(path)));
+		context.runtime.options.policy.check_path(&path, context.pos.copy())?;
+
+		let store: Shared = match Store::load(path.into_owned()) {
+			Ok(store) => Rc::new(RefCell::new(store)),
+			Err(error) => return Ok(Error::new(error.to_string().into(), value.copy()).into()),
+		};
+
+		Ok(
+			util::dict([
+				("path", value.copy()),
+				("get", GetImpl(store.clone()).into()),
+				("set", SetImpl(store.clone()).into()),
+				("delete", DeleteImpl(store.clone()).into()),
+				("contains", ContainsImpl(store.clone()).into()),
+				("keys", KeysImpl(store).into()),
+			])
+		)
+	}
+}
+
+
+/// Get the value of a key, or the default, which is nil if not given.
+#[derive(Finalize)]
+struct GetImpl(Shared);
+
+impl NativeFun for GetImpl {
+	fn name(&self) -> &'static str { "std.store<get>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (key, default) = match context.args() {
+			[ value, default @ .. ] if default.len() <= 1 => (key(&context, value)?, default.first()),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let store = self.0.borrow();
+
+		Ok(
+			match store.entries.get(&key) {
+				Some(json) => serde_json::from_value(json.clone())
+					.expect("stored values are always valid"),
+				None => default.map(Value::copy).unwrap_or_default(),
+			}
+		)
+	}
+}
+
+
+/// Set the value of a key, and save the store.
+#[derive(Finalize)]
+struct SetImpl(Shared);
+
+impl NativeFun for SetImpl {
+	fn name(&self) -> &'static str { "std.store<set>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (key, value) = match context.args() {
+			[ key_value, value ] => (key(&context, key_value)?, value),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let json = serde_json::to_value(value)
+			.map_err(
+				|_| Panic::value_error(
+					value.copy(),
+					"nil, bool, byte, int, float, string, array or dict",
+					context.pos.copy()
+				)
+			)?;
+
+		let mut store = self.0.borrow_mut();
+
+		Ok(
+			match store.update(key, Some(json)) {
+				Ok(_) => Value::default(),
+				Err(err) => error(&store, err),
+			}
+		)
+	}
+}
+
+
+/// Delete a key, and save the store. Returns whether the key was present.
+#[derive(Finalize)]
+struct DeleteImpl(Shared);
+
+impl NativeFun for DeleteImpl {
+	fn name(&self) -> &'static str { "std.store<delete>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let key = match context.args() {
+			[ value ] => key(&context, value)?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let mut store = self.0.borrow_mut();
+
+		if !store.entries.contains_key(&key) {
+			return Ok(false.into());
+		}
+
+		Ok(
+			match store.update(key, None) {
+				Ok(_) => true.into(),
+				Err(err) => error(&store, err),
+			}
+		)
+	}
+}
+
+
+/// Whether the store has a key.
+#[derive(Finalize)]
+struct ContainsImpl(Shared);
+
+impl NativeFun for ContainsImpl {
+	fn name(&self) -> &'static str { "std.store<contains>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let key = match context.args() {
+			[ value ] => key(&context, value)?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Ok(self.0.borrow().entries.contains_key(&key).into())
+	}
+}
+
+
+/// The keys of the store, in sorted order.
+#[derive(Finalize)]
+struct KeysImpl(Shared);
+
+impl NativeFun for KeysImpl {
+	fn name(&self) -> &'static str { "std.store<keys>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(
+				self.0
+					.borrow()
+					.entries
+					.keys()
+					.map(|key| Value::from(key.as_str()))
+					.collect::<Vec<_>>()
+					.into()
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+// The methods have no garbage-collected fields.
+unsafe impl Trace for GetImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for SetImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for DeleteImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for ContainsImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for KeysImpl { gc::unsafe_empty_trace!(); }
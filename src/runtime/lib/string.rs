@@ -0,0 +1,484 @@
+use std::convert::TryFrom;
+
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	Buffer,
+	CallContext,
+	Dict,
+	DictMap,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+	util,
+};
+
+
+inventory::submit! { RustFun::from(PadLeft) }
+inventory::submit! { RustFun::from(PadRight) }
+inventory::submit! { RustFun::from(Center) }
+inventory::submit! { RustFun::from(Upper) }
+inventory::submit! { RustFun::from(Lower) }
+inventory::submit! { RustFun::from(Title) }
+inventory::submit! { RustFun::from(TrimStart) }
+inventory::submit! { RustFun::from(TrimEnd) }
+inventory::submit! { RustFun::from(StartsWith) }
+inventory::submit! { RustFun::from(EndsWith) }
+inventory::submit! { RustFun::from(Repeat) }
+inventory::submit! { RustFun::from(Reverse) }
+inventory::submit! { RustFun::from(Builder) }
+
+
+/// Get the bytes of a string argument.
+fn string(value: &Value, pos: SourcePos) -> Result<&[u8], Panic> {
+	match value {
+		Value::String(string) => Ok(string.as_bytes()),
+		other => Err(Panic::type_error(other.copy(), "string", pos)),
+	}
+}
+
+
+/// Get a set of characters to trim. Defaults to whitespace.
+pub fn char_set(value: Option<&Value>, pos: SourcePos) -> Result<Option<Vec<char>>, Panic> {
+	value
+		.map(|value| string(value, pos).map(|chars| chars.chars().collect()))
+		.transpose()
+}
+
+
+/// Trim characters in the set, or whitespace if there is none.
+pub fn trim_with<'a, F>(string: &'a [u8], chars: &Option<Vec<char>>, trim: F) -> &'a [u8]
+where
+	F: FnOnce(&'a [u8], &dyn Fn(char) -> bool) -> &'a [u8],
+{
+	match chars {
+		Some(chars) => trim(string, &|c: char| chars.contains(&c)),
+		None => trim(string, &char::is_whitespace),
+	}
+}
+
+
+/// Pad a string to the given width, in characters, with the given fill character, which
+/// defaults to a space. The padding is split between the start and end according to the
+/// given function, which receives the total padding. Strings which are already as wide
+/// are returned unchanged.
+fn pad(context: CallContext, split: fn(usize) -> (usize, usize)) -> Result<Value, Panic> {
+	let (value, width, fill) = match context.args() {
+		[ value, Value::Int(width) ] => (value, *width, ' '),
+
+		[ value, Value::Int(width), fill ] => {
+			let fill_str = string(fill, context.pos.copy())?;
+			let mut chars = fill_str.chars();
+
+			match (chars.next(), chars.next()) {
+				(Some(c), None) => (value, *width, c),
+				_ => return Err(Panic::value_error(fill.copy(), "single character", context.pos)),
+			}
+		}
+
+		[ _, other ] | [ _, other, _ ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+		args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+	};
+
+	let string = string(value, context.pos.copy())?;
+
+	let length = string.chars().count() as i64;
+	if width <= length {
+		return Ok(value.copy());
+	}
+
+	let padding = usize::try_from(width - length).ok();
+	let (start, end) = split(padding.unwrap_or(0));
+
+	let mut fill_bytes = [0; 4];
+	let fill = fill.encode_utf8(&mut fill_bytes).as_bytes();
+
+	let size = padding
+		.and_then(|padding| padding.checked_mul(fill.len()))
+		.and_then(|padding| padding.checked_add(string.len()));
+
+	let mut result = util::alloc(size)
+		.ok_or_else(|| Panic::value_error(Value::Int(width), "width within memory limits", context.pos.copy()))?;
+
+	for _ in 0 .. start {
+		result.extend_from_slice(fill);
+	}
+	result.extend_from_slice(string);
+	for _ in 0 .. end {
+		result.extend_from_slice(fill);
+	}
+
+	Ok(result.into_boxed_slice().into())
+}
+
+
+/// Pad a string at the start, aligning it to the right.
+#[derive(Trace, Finalize)]
+struct PadLeft;
+
+impl NativeFun for PadLeft {
+	fn name(&self) -> &'static str { "std.string.pad_left" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		pad(context, |padding| (padding, 0))
+	}
+}
+
+
+/// Pad a string at the end, aligning it to the left.
+#[derive(Trace, Finalize)]
+struct PadRight;
+
+impl NativeFun for PadRight {
+	fn name(&self) -> &'static str { "std.string.pad_right" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		pad(context, |padding| (0, padding))
+	}
+}
+
+
+/// Pad a string at both ends, centering it. When the padding is odd, the extra character
+/// goes at the end.
+#[derive(Trace, Finalize)]
+struct Center;
+
+impl NativeFun for Center {
+	fn name(&self) -> &'static str { "std.string.center" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		pad(context, |padding| (padding / 2, padding - padding / 2))
+	}
+}
+
+
+/// Apply a conversion to a single string argument.
+fn convert(context: CallContext, fun: fn(&[u8]) -> Vec<u8>) -> Result<Value, Panic> {
+	match context.args() {
+		[ value ] => {
+			let string = string(value, context.pos.copy())?;
+			Ok(fun(string).into_boxed_slice().into())
+		}
+
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+	}
+}
+
+
+/// Convert a string to upper case, according to Unicode.
+#[derive(Trace, Finalize)]
+struct Upper;
+
+impl NativeFun for Upper {
+	fn name(&self) -> &'static str { "std.string.upper" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		convert(context, |string| string.to_uppercase())
+	}
+}
+
+
+/// Convert a string to lower case, according to Unicode.
+#[derive(Trace, Finalize)]
+struct Lower;
+
+impl NativeFun for Lower {
+	fn name(&self) -> &'static str { "std.string.lower" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		convert(context, |string| string.to_lowercase())
+	}
+}
+
+
+/// Convert a string to title case: the first letter of each word is converted to upper
+/// case, and the remaining letters to lower case. Words are separated by whitespace.
+#[derive(Trace, Finalize)]
+struct Title;
+
+impl Title {
+	fn title(string: &[u8]) -> Vec<u8> {
+		let mut result = Vec::with_capacity(string.len());
+		let mut word_start = true;
+
+		for (start, end, c) in string.char_indices() {
+			let bytes = &string[start .. end];
+
+			if c.is_whitespace() {
+				word_start = true;
+				result.extend(bytes);
+			} else if word_start {
+				word_start = false;
+				result.extend(bytes.to_uppercase());
+			} else {
+				result.extend(bytes.to_lowercase());
+			}
+		}
+
+		result
+	}
+}
+
+impl NativeFun for Title {
+	fn name(&self) -> &'static str { "std.string.title" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		convert(context, Self::title)
+	}
+}
+
+
+/// Trim characters from the start of a string. The characters to trim may be given as a
+/// string, and default to whitespace.
+#[derive(Trace, Finalize)]
+struct TrimStart;
+
+impl NativeFun for TrimStart {
+	fn name(&self) -> &'static str { "std.string.trim_start" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, chars @ .. ] if chars.len() <= 1 => {
+				let string = string(value, context.pos.copy())?;
+				let chars = char_set(chars.first(), context.pos.copy())?;
+
+				Ok(trim_with(string, &chars, |string, trim| string.trim_start_with(trim)).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Trim characters from the end of a string. The characters to trim may be given as a
+/// string, and default to whitespace.
+#[derive(Trace, Finalize)]
+struct TrimEnd;
+
+impl NativeFun for TrimEnd {
+	fn name(&self) -> &'static str { "std.string.trim_end" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, chars @ .. ] if chars.len() <= 1 => {
+				let string = string(value, context.pos.copy())?;
+				let chars = char_set(chars.first(), context.pos.copy())?;
+
+				Ok(trim_with(string, &chars, |string, trim| string.trim_end_with(trim)).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Whether a string starts with the given prefix.
+#[derive(Trace, Finalize)]
+struct StartsWith;
+
+impl NativeFun for StartsWith {
+	fn name(&self) -> &'static str { "std.string.starts_with" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, prefix ] => {
+				let prefix = string(prefix, context.pos.copy())?;
+				let string = string(value, context.pos.copy())?;
+				Ok(string.starts_with(prefix).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Whether a string ends with the given suffix.
+#[derive(Trace, Finalize)]
+struct EndsWith;
+
+impl NativeFun for EndsWith {
+	fn name(&self) -> &'static str { "std.string.ends_with" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, suffix ] => {
+				let suffix = string(suffix, context.pos.copy())?;
+				let string = string(value, context.pos.copy())?;
+				Ok(string.ends_with(suffix).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Repeat a string the given number of times.
+#[derive(Trace, Finalize)]
+struct Repeat;
+
+impl NativeFun for Repeat {
+	fn name(&self) -> &'static str { "std.string.repeat" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value, count @ Value::Int(times) ] => {
+				let string = string(value, context.pos.copy())?;
+
+				let times = usize::try_from(*times).ok();
+				let size = times.and_then(|times| string.len().checked_mul(times));
+
+				let mut result = util::alloc(size)
+					.ok_or_else(|| Panic::value_error(count.copy(), "non-negative count within memory limits", context.pos.copy()))?;
+
+				for _ in 0 .. times.unwrap_or(0) {
+					result.extend_from_slice(string);
+				}
+
+				Ok(result.into_boxed_slice().into())
+			}
+
+			[ _, other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Reverse the characters of a string. Invalid UTF-8 bytes are kept as they are.
+#[derive(Trace, Finalize)]
+struct Reverse;
+
+impl NativeFun for Reverse {
+	fn name(&self) -> &'static str { "std.string.reverse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		convert(
+			context,
+			|string| string
+				.char_indices()
+				.rev()
+				.flat_map(|(start, end, _)| &string[start .. end])
+				.copied()
+				.collect()
+		)
+	}
+}
+
+
+/// Create a string builder, which appends strings in amortized constant time, unlike
+/// repeated concatenation, which copies the whole string every time. The builder is a
+/// dict with the following methods:
+/// - push(string or char): append to the string.
+/// - build(): get the string built so far. The builder may still be used afterwards.
+/// - len(): get the length of the string built so far.
+/// - clear(): discard the string built so far.
+#[derive(Trace, Finalize)]
+struct Builder;
+
+impl NativeFun for Builder {
+	fn name(&self) -> &'static str { "std.string.builder" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				let buffer = Buffer::new(Vec::new());
+
+				let mut methods = DictMap::default();
+				methods.insert("push".into(), BuilderPush { buffer: buffer.copy() }.into());
+				methods.insert("build".into(), BuilderBuild { buffer: buffer.copy() }.into());
+				methods.insert("len".into(), BuilderLen { buffer: buffer.copy() }.into());
+				methods.insert("clear".into(), BuilderClear { buffer }.into());
+
+				Ok(Dict::new(methods).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct BuilderPush {
+	buffer: Buffer,
+}
+
+impl NativeFun for BuilderPush {
+	fn name(&self) -> &'static str { "std.string.builder<push>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(string) ] => self.buffer.borrow_mut().extend_from_slice(string.as_bytes()),
+			[ Value::Byte(byte) ] => self.buffer.borrow_mut().push(*byte),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "string or char", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Ok(Value::Nil)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct BuilderBuild {
+	buffer: Buffer,
+}
+
+impl NativeFun for BuilderBuild {
+	fn name(&self) -> &'static str { "std.string.builder<build>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(self.buffer.borrow().as_slice().into()),
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct BuilderLen {
+	buffer: Buffer,
+}
+
+impl NativeFun for BuilderLen {
+	fn name(&self) -> &'static str { "std.string.builder<len>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(Value::Int(self.buffer.len())),
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct BuilderClear {
+	buffer: Buffer,
+}
+
+impl NativeFun for BuilderClear {
+	fn name(&self) -> &'static str { "std.string.builder<clear>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				self.buffer.borrow_mut().clear();
+				Ok(Value::Nil)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
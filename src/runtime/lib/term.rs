@@ -0,0 +1,308 @@
+use std::collections::BTreeSet;
+
+use gc::{Finalize, Trace};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+	fmt::FmtString,
+	runtime::SourcePos,
+	symbol,
+	term::color::{self, Color},
+};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Table) }
+
+
+/// Table rendering options.
+struct Options {
+	/// The selected columns: keys for dict rows, indexes for array rows.
+	columns: Option<Vec<Value>>,
+	header: Option<bool>,
+	max_width: Option<usize>,
+	color: bool,
+}
+
+
+impl Options {
+	fn new(value: Option<&Value>, pos: SourcePos) -> Result<Self, Panic> {
+		let option = |name: &str| -> Result<Value, Panic> {
+			match value {
+				None => Ok(Value::Nil),
+				Some(Value::Dict(dict)) => Ok(dict.get(&name.into()).unwrap_or_default()),
+				Some(other) => Err(Panic::type_error(other.copy(), "dict", pos.copy())),
+			}
+		};
+
+		let columns = match option("columns")? {
+			Value::Nil => None,
+			Value::Array(ref columns) => Some(columns.borrow().iter().map(Value::copy).collect()),
+			other => return Err(Panic::type_error(other, "array", pos)),
+		};
+
+		let header = match option("header")? {
+			Value::Nil => None,
+			Value::Bool(header) => Some(header),
+			other => return Err(Panic::type_error(other, "bool", pos)),
+		};
+
+		let max_width = match option("max_width")? {
+			Value::Nil => None,
+			Value::Int(width) if width >= 1 => Some(width as usize),
+			value @ Value::Int(_) => return Err(Panic::value_error(value, "positive width", pos)),
+			other => return Err(Panic::type_error(other, "int", pos)),
+		};
+
+		let color = match option("color")? {
+			Value::Nil => false,
+			Value::Bool(color) => color,
+			other => return Err(Panic::type_error(other, "bool", pos)),
+		};
+
+		Ok(Self { columns, header, max_width, color })
+	}
+}
+
+
+/// A table cell.
+struct Cell {
+	text: String,
+	/// Numbers are aligned to the right.
+	numeric: bool,
+}
+
+
+impl Cell {
+	fn new(value: &Value, max_width: Option<usize>, interner: &symbol::Interner) -> Self {
+		let text = match value {
+			Value::Nil => String::new(),
+			Value::String(string) => String::from_utf8_lossy(string.as_bytes()).into_owned(),
+			value => value.fmt_string(interner),
+		};
+
+		// Line breaks would break the layout.
+		let text = text.replace('\n', " ");
+
+		Self {
+			text: match max_width {
+				Some(width) => truncate(text, width),
+				None => text,
+			},
+			numeric: matches!(value, Value::Int(_) | Value::Float(_)),
+		}
+	}
+
+
+	fn width(&self) -> usize {
+		self.text.width()
+	}
+}
+
+
+/// Truncate a string to the given display width, marking the truncation with an ellipsis.
+fn truncate(text: String, width: usize) -> String {
+	if text.width() <= width {
+		return text;
+	}
+
+	let mut truncated = String::new();
+	let mut truncated_width = 0;
+
+	for c in text.chars() {
+		let char_width = c.width().unwrap_or(0);
+		if truncated_width + char_width > width - 1 {
+			break;
+		}
+
+		truncated.push(c);
+		truncated_width += char_width;
+	}
+
+	truncated.push('…');
+	truncated
+}
+
+
+/// Render rows as an aligned table. Rows may be dicts, with a header of their keys, or
+/// arrays, without a header by default. Options:
+/// - columns: the selected columns, in order. Defaults to all keys, sorted, or all
+///   indexes.
+/// - header: whether to include the header row.
+/// - max_width: the maximum width of cells, which are truncated beyond it.
+/// - color: whether to paint the header with terminal escape codes.
+/// Numbers are aligned to the right, and missing cells are left blank.
+#[derive(Trace, Finalize)]
+struct Table;
+
+impl Table {
+	fn render(header: Option<Vec<Cell>>, rows: Vec<Vec<Cell>>, color: bool) -> String {
+		let columns = rows
+			.iter()
+			.chain(&header)
+			.map(Vec::len)
+			.max()
+			.unwrap_or(0);
+
+		let mut widths = vec![0; columns];
+		for row in rows.iter().chain(&header) {
+			for (width, cell) in widths.iter_mut().zip(row) {
+				*width = (*width).max(cell.width());
+			}
+		}
+
+		let line = |row: &[Cell]| -> String {
+			let mut line = String::new();
+
+			for (ix, (cell, width)) in row.iter().zip(&widths).enumerate() {
+				if ix > 0 {
+					line.push_str("  ");
+				}
+
+				let padding = " ".repeat(width - cell.width());
+				if cell.numeric {
+					line.push_str(&padding);
+					line.push_str(&cell.text);
+				} else {
+					line.push_str(&cell.text);
+					line.push_str(&padding);
+				}
+			}
+
+			line.truncate(line.trim_end().len());
+			line
+		};
+
+		let mut table = String::new();
+
+		if let Some(header) = &header {
+			let separator = widths
+				.iter()
+				.map(|&width| "-".repeat(width))
+				.collect::<Vec<_>>()
+				.join("  ");
+
+			if color {
+				table.push_str(color::BOLD);
+				table.push_str(color::Yellow::FG);
+				table.push_str(&line(header));
+				table.push_str(color::RESET);
+			} else {
+				table.push_str(&line(header));
+			}
+			table.push('\n');
+
+			table.push_str(&separator);
+			table.push('\n');
+		}
+
+		for row in &rows {
+			table.push_str(&line(row));
+			table.push('\n');
+		}
+
+		table
+	}
+}
+
+impl NativeFun for Table {
+	fn name(&self) -> &'static str { "std.term.table" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (rows, options) = match context.args() {
+			[ Value::Array(ref rows) ] => (rows.copy(), None),
+			[ Value::Array(ref rows), options ] => (rows.copy(), Some(options)),
+
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let options = Options::new(options, context.pos.copy())?;
+
+		let rows = rows.borrow();
+		let interner = context.interner();
+
+		let is_dict = match rows.first() {
+			None | Some(Value::Dict(_)) => true,
+			Some(Value::Array(_)) => false,
+			Some(other) => return Err(Panic::type_error(other.copy(), "dict or array", context.pos.copy())),
+		};
+
+		// Default to all keys or indexes.
+		let columns: Vec<Value> = match options.columns {
+			Some(ref columns) => columns.iter().map(Value::copy).collect(),
+
+			None if is_dict => {
+				let mut keys = BTreeSet::new();
+				for row in rows.iter() {
+					if let Value::Dict(dict) = row {
+						keys.extend(dict.borrow().keys().map(Value::copy));
+					}
+				}
+
+				keys.into_iter().collect()
+			}
+
+			None => {
+				let len = rows
+					.iter()
+					.map(
+						|row| match row {
+							Value::Array(array) => array.len(),
+							_ => 0,
+						}
+					)
+					.max()
+					.unwrap_or(0);
+
+				(0 .. len).map(Value::Int).collect()
+			}
+		};
+
+		let mut cells = Vec::with_capacity(rows.len());
+
+		for row in rows.iter() {
+			let row: Vec<Cell> = match (row, is_dict) {
+				(Value::Dict(dict), true) => columns
+					.iter()
+					.map(|column| Cell::new(&dict.get(column).unwrap_or_default(), options.max_width, interner))
+					.collect(),
+
+				(Value::Array(array), false) => columns
+					.iter()
+					.map(
+						|column| match column {
+							Value::Int(ix) => Ok(Cell::new(&array.index(*ix).unwrap_or_default(), options.max_width, interner)),
+							other => Err(Panic::type_error(other.copy(), "int", context.pos.copy())),
+						}
+					)
+					.collect::<Result<_, _>>()?,
+
+				(other, true) => return Err(Panic::type_error(other.copy(), "dict", context.pos.copy())),
+				(other, false) => return Err(Panic::type_error(other.copy(), "array", context.pos.copy())),
+			};
+
+			cells.push(row);
+		}
+
+		let header = if options.header.unwrap_or(is_dict) && !columns.is_empty() {
+			Some(
+				columns
+					.iter()
+					.map(|column| Cell { numeric: false, ..Cell::new(column, options.max_width, interner) })
+					.collect()
+			)
+		} else {
+			None
+		};
+
+		Ok(Self::render(header, cells, options.color).into())
+	}
+}
@@ -0,0 +1,281 @@
+use std::fmt::Write;
+
+use gc::{Finalize, Trace};
+use regex::bytes::Regex;
+use similar::{ChangeTag, TextDiff};
+
+use crate::{fmt, runtime::SourcePos, symbol};
+
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(AssertEq) }
+inventory::submit! { RustFun::from(AssertNe) }
+inventory::submit! { RustFun::from(AssertError) }
+inventory::submit! { RustFun::from(AssertMatches) }
+
+
+/// Render a value for failure messages. Non-empty arrays and dicts span multiple lines,
+/// with one item per line, so that nested values can be diffed by lines. Dict entries are
+/// sorted, so that equal dicts render equally.
+fn render(value: &Value, interner: &symbol::Interner, indent: usize, out: &mut String) {
+	let item_indent = "  ".repeat(indent + 1);
+
+	match value {
+		Value::Array(array) if !array.is_empty() => {
+			out.push_str("[\n");
+
+			for item in array.borrow().iter() {
+				out.push_str(&item_indent);
+				render(item, interner, indent + 1, out);
+				out.push_str(",\n");
+			}
+
+			out.push_str(&"  ".repeat(indent));
+			out.push(']');
+		}
+
+		Value::Dict(dict) if !dict.is_empty() => {
+			out.push_str("@[\n");
+
+			for (key, item) in super::dict::entries(dict) {
+				let _ = write!(out, "{}{}: ", item_indent, fmt::Show(&key, interner));
+				render(&item, interner, indent + 1, out);
+				out.push_str(",\n");
+			}
+
+			out.push_str(&"  ".repeat(indent));
+			out.push(']');
+		}
+
+		other => {
+			let _ = write!(out, "{}", fmt::Show(other, interner));
+		}
+	}
+}
+
+
+/// Describe two values which were expected to be equal. Multi-line values are followed by
+/// a line diff, where removed lines are from the expected value, and added lines are from
+/// the actual value.
+fn difference(actual: &Value, expected: &Value, interner: &symbol::Interner) -> String {
+	let (mut actual_text, mut expected_text) = (String::new(), String::new());
+	render(actual, interner, 0, &mut actual_text);
+	render(expected, interner, 0, &mut expected_text);
+
+	let mut message = String::new();
+
+	if !actual_text.contains('\n') && !expected_text.contains('\n') {
+		let _ = write!(message, "\n  expected: {}\n    actual: {}", expected_text, actual_text);
+		return message;
+	}
+
+	message.push_str("\n  --- expected\n  +++ actual");
+
+	for change in TextDiff::from_lines(&expected_text, &actual_text).iter_all_changes() {
+		let sign = match change.tag() {
+			ChangeTag::Equal => ' ',
+			ChangeTag::Delete => '-',
+			ChangeTag::Insert => '+',
+		};
+
+		let _ = write!(message, "\n  {} {}", sign, change.value().trim_end_matches('\n'));
+	}
+
+	message
+}
+
+
+/// Build a failure message, with an optional user message.
+fn failure(summary: &str, message: Option<&Value>, pos: SourcePos) -> Result<String, Panic> {
+	match message {
+		None => Ok(summary.to_owned()),
+		Some(Value::String(message)) => Ok(
+			format!("{}: {}", String::from_utf8_lossy(message.as_bytes()), summary)
+		),
+		Some(other) => Err(Panic::type_error(other.copy(), "string", pos)),
+	}
+}
+
+
+/// Assert that two values are equal, with an optional message. On failure, the panic
+/// includes both values, and a line diff of them when they span multiple lines.
+#[derive(Trace, Finalize)]
+struct AssertEq;
+
+impl NativeFun for AssertEq {
+	fn name(&self) -> &'static str { "std.test.assert_eq" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (actual, expected, message) = match context.args() {
+			[ actual, expected ] => (actual, expected, None),
+			[ actual, expected, message ] => (actual, expected, Some(message)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		if actual == expected {
+			return Ok(Value::default());
+		}
+
+		let summary = format!("values differ{}", difference(actual, expected, context.interner()));
+
+		Err(
+			Panic::assertion_failed_with(
+				failure(&summary, message, context.pos.copy())?,
+				context.pos.copy(),
+			)
+		)
+	}
+}
+
+
+/// Assert that two values are different, with an optional message.
+#[derive(Trace, Finalize)]
+struct AssertNe;
+
+impl NativeFun for AssertNe {
+	fn name(&self) -> &'static str { "std.test.assert_ne" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (actual, unexpected, message) = match context.args() {
+			[ actual, unexpected ] => (actual, unexpected, None),
+			[ actual, unexpected, message ] => (actual, unexpected, Some(message)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		if actual != unexpected {
+			return Ok(Value::default());
+		}
+
+		let summary = format!(
+			"values are equal\n  value: {}",
+			fmt::Show(actual, context.interner())
+		);
+
+		Err(
+			Panic::assertion_failed_with(
+				failure(&summary, message, context.pos.copy())?,
+				context.pos.copy(),
+			)
+		)
+	}
+}
+
+
+/// Assert that a value is an error, optionally with the given description, and return
+/// it. If the value is a function, it is called without arguments, and it's result is
+/// checked instead. Panics in the function count as errors, and are returned like in
+/// std.catch.
+#[derive(Trace, Finalize)]
+struct AssertError;
+
+impl NativeFun for AssertError {
+	fn name(&self) -> &'static str { "std.test.assert_error" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (value, description) = match context.args() {
+			[ value ] => (value.copy(), None),
+			[ value, Value::String(description) ] => (value.copy(), Some(description.copy())),
+
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let value = match value {
+			Value::Function(ref fun) => {
+				let args_start = context.runtime.arguments.len();
+
+				match context.call(Value::default(), fun, args_start) {
+					Ok(value) => value,
+
+					Err(exit @ Panic::Exit { .. }) => return Err(exit),
+					Err(limit @ Panic::LimitExceeded { .. }) => return Err(limit),
+
+					Err(panic) => {
+						let description = format!(
+							"caught panic: {}",
+							fmt::Show(panic.untraced(), context.interner()),
+						);
+
+						Error::new(description.into(), "panic".into()).into()
+					}
+				}
+			}
+
+			value => value,
+		};
+
+		let error = match &value {
+			Value::Error(error) => error,
+			other => {
+				let summary = format!(
+					"expected an error\n  actual: {}",
+					fmt::Show(other, context.interner())
+				);
+				return Err(Panic::assertion_failed_with(summary, context.pos));
+			}
+		};
+
+		match description {
+			Some(description) if error.description != description => {
+				let summary = format!(
+					"error description differs\n  expected: {}\n    actual: {}",
+					description,
+					error.description,
+				);
+				Err(Panic::assertion_failed_with(summary, context.pos))
+			}
+
+			_ => Ok(value),
+		}
+	}
+}
+
+
+/// Assert that a string matches a regex, with an optional message.
+#[derive(Trace, Finalize)]
+struct AssertMatches;
+
+impl NativeFun for AssertMatches {
+	fn name(&self) -> &'static str { "std.test.assert_matches" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (string, pattern, message) = match context.args() {
+			[ Value::String(string), Value::String(pattern) ] => (string, pattern, None),
+			[ Value::String(string), Value::String(pattern), message ] => (string, pattern, Some(message)),
+
+			[ Value::String(_), other, .. ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other, _ ] | [ other, _, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let regex = std::str::from_utf8(pattern.as_bytes())
+			.ok()
+			.and_then(|pattern| Regex::new(pattern).ok())
+			.ok_or_else(|| Panic::value_error(pattern.copy().into(), "valid regex", context.pos.copy()))?;
+
+		if regex.is_match(string.as_bytes()) {
+			return Ok(Value::default());
+		}
+
+		let summary = format!(
+			"string does not match pattern\n  pattern: {}\n   string: {}",
+			pattern,
+			string,
+		);
+
+		Err(
+			Panic::assertion_failed_with(
+				failure(&summary, message, context.pos.copy())?,
+				context.pos.copy(),
+			)
+		)
+	}
+}
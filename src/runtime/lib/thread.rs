@@ -0,0 +1,182 @@
+use std::{
+	cell::RefCell,
+	rc::Rc,
+	thread::{self, JoinHandle},
+};
+
+use gc::{Finalize, Trace};
+
+use crate::{fmt, runtime::{Runtime, SourcePos}};
+
+use super::{
+	transfer::Transfer,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Spawn) }
+
+
+/// The native stack size of threads, the same as the usual size for the main thread, so
+/// that threads may nest as many calls.
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+
+/// The result of a thread, or the description of it's panic.
+type Outcome = Result<Transfer, Box<str>>;
+
+
+/// The thread, shared by the methods of a thread value. It's taken when joined.
+type Shared = Rc<RefCell<Option<JoinHandle<Outcome>>>>;
+
+
+/// Run a function in a separate OS thread, with it's own interpreter state. The function
+/// is sent to the thread as in channels, so captured variables are copied, and values may
+/// be shared only through channels. The thread inherits the options, the policy and the
+/// budget of the runtime. Returns a dict with the methods:
+/// - join(): wait for the thread to finish, and get the result of the function. Panics in
+///   the thread produce an error value.
+/// - is_finished(): check whether the thread has finished, without waiting.
+/// Threads which are still running when the script finishes are terminated.
+#[derive(Trace, Finalize)]
+struct Spawn;
+
+impl Spawn {
+	/// Run the function in the thread's runtime.
+	fn run(mut runtime: Runtime, function: Transfer, pos: SourcePos) -> Outcome {
+		let function = match function.into_value(&runtime.std) {
+			Value::Function(ref function) => function.copy(),
+			_ => unreachable!("thread function was checked"),
+		};
+
+		let args_start = runtime.arguments.len();
+		let result = runtime
+			.call(Value::default(), &function, args_start, pos.copy())
+			.and_then(|value| Transfer::new(&value, &runtime.std, pos));
+
+		result.map_err(
+			|panic| format!("thread panic: {}", fmt::Show(panic, runtime.interner()))
+				.into_boxed_str()
+		)
+	}
+}
+
+impl NativeFun for Spawn {
+	fn name(&self) -> &'static str { "std.thread.spawn" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let function = match context.args() {
+			[ function @ Value::Function(_) ] => {
+				Transfer::new(function, &context.runtime.std, context.pos.copy())?
+			}
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let args: Vec<Box<[u8]>> = match &context.runtime.args {
+			Value::Array(args) => args
+				.borrow()
+				.iter()
+				.filter_map(
+					|arg| match arg {
+						Value::String(arg) => Some(arg.as_bytes().into()),
+						_ => None,
+					}
+				)
+				.collect(),
+			_ => Vec::new(),
+		};
+
+		let interner = context.runtime.interner.clone();
+		let options = context.runtime.options.clone();
+		let budget = context.runtime.meter.budget();
+		let pos = context.pos.copy();
+
+		let result = thread::Builder::new()
+			.stack_size(STACK_SIZE)
+			.spawn(
+				move || {
+					let mut runtime = Runtime::new(args, interner);
+					runtime.options = options;
+					runtime.set_budget(budget);
+
+					Self::run(runtime, function, pos)
+				}
+			);
+
+		let handle = match result {
+			Ok(handle) => handle,
+			Err(error) => return Ok(Error::new(error.to_string().into(), Value::Nil).into()),
+		};
+
+		let shared: Shared = Rc::new(RefCell::new(Some(handle)));
+
+		let mut dict = DictMap::default();
+		dict.insert("join".into(), JoinImpl(shared.clone()).into());
+		dict.insert("is_finished".into(), IsFinishedImpl(shared).into());
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+/// Wait for the thread to finish, and get it's result.
+#[derive(Finalize)]
+struct JoinImpl(Shared);
+
+impl NativeFun for JoinImpl {
+	fn name(&self) -> &'static str { "std.thread<join>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let handle = match self.0.borrow_mut().take() {
+			Some(handle) => handle,
+			None => return Ok(Error::new("thread was already joined".into(), Value::Nil).into()),
+		};
+
+		match handle.join() {
+			Ok(Ok(value)) => Ok(value.into_value(&context.runtime.std)),
+			Ok(Err(description)) => Ok(Error::new(description.into(), Value::Nil).into()),
+			Err(error) => std::panic::resume_unwind(error),
+		}
+	}
+}
+
+
+/// Check whether the thread has finished.
+#[derive(Finalize)]
+struct IsFinishedImpl(Shared);
+
+impl NativeFun for IsFinishedImpl {
+	fn name(&self) -> &'static str { "std.thread<is_finished>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let finished = self.0
+			.borrow()
+			.as_ref()
+			.map_or(true, JoinHandle::is_finished);
+
+		Ok(finished.into())
+	}
+}
+
+
+unsafe impl Trace for JoinImpl { gc::unsafe_empty_trace!(); }
+unsafe impl Trace for IsFinishedImpl { gc::unsafe_empty_trace!(); }
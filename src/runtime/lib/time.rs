@@ -0,0 +1,629 @@
+use std::{
+	str::FromStr,
+	time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{
+	format::{Item, StrftimeItems},
+	DateTime,
+	Datelike,
+	Days,
+	FixedOffset,
+	Local,
+	Months,
+	NaiveDate,
+	NaiveDateTime,
+	Offset,
+	TimeZone,
+	Timelike,
+};
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	wait::Wait,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Now) }
+inventory::submit! { RustFun::from(Monotonic) }
+inventory::submit! { RustFun::from(ToDate) }
+inventory::submit! { RustFun::from(FromDate) }
+inventory::submit! { RustFun::from(Format) }
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Duration) }
+inventory::submit! { RustFun::from(Add) }
+inventory::submit! { RustFun::from(Sleep) }
+inventory::submit! { RustFun::from(After) }
+inventory::submit! { RustFun::from(Stopwatch) }
+
+
+thread_local! {
+	/// The reference point of the monotonic clock.
+	static START: Instant = Instant::now();
+}
+
+
+/// Timestamps are floats, in seconds since the Unix epoch.
+fn to_timestamp(date: &DateTime<FixedOffset>) -> f64 {
+	date.timestamp() as f64 + date.timestamp_subsec_nanos() as f64 / 1e9
+}
+
+
+/// Convert a timestamp to a date in UTC.
+fn from_timestamp(timestamp: f64) -> Option<NaiveDateTime> {
+	if !timestamp.is_finite() {
+		return None;
+	}
+
+	let seconds = timestamp.floor();
+	let nanos = ((timestamp - seconds) * 1e9).round().min(999_999_999.0);
+
+	DateTime::from_timestamp(seconds as i64, nanos as u32)
+		.map(|date| date.naive_utc())
+}
+
+
+/// A time zone, which is either the local time zone, UTC, a fixed offset, or a named time
+/// zone from the IANA database.
+enum Zone {
+	Local,
+	Utc,
+	Fixed(FixedOffset),
+	Named(chrono_tz::Tz),
+}
+
+
+impl Zone {
+	/// Parse a time zone. Nil is the local time zone.
+	fn parse(value: Option<&Value>, pos: SourcePos) -> Result<Self, Panic> {
+		let name = match value {
+			None | Some(Value::Nil) => return Ok(Self::Local),
+			Some(Value::String(name)) => name,
+			Some(other) => return Err(Panic::type_error(other.copy(), "string", pos)),
+		};
+
+		let zone = match name.as_bytes() {
+			b"local" => Some(Self::Local),
+			b"UTC" | b"utc" | b"Z" => Some(Self::Utc),
+			offset @ [ b'+' | b'-', .. ] => Self::parse_offset(offset).map(Self::Fixed),
+			name => std::str::from_utf8(name)
+				.ok()
+				.and_then(|name| chrono_tz::Tz::from_str(name).ok())
+				.map(Self::Named),
+		};
+
+		zone.ok_or_else(|| Panic::value_error(Value::String(name.copy()), "valid time zone", pos))
+	}
+
+
+	/// Parse an offset in the +HH:MM or +HHMM forms.
+	fn parse_offset(offset: &[u8]) -> Option<FixedOffset> {
+		let (sign, offset) = match offset.split_first()? {
+			(b'+', offset) => (1, offset),
+			(b'-', offset) => (-1, offset),
+			_ => return None,
+		};
+
+		let digits: Vec<u8> = offset
+			.iter()
+			.copied()
+			.filter(|&byte| byte != b':')
+			.collect();
+
+		if digits.len() != 4 || !digits.iter().all(u8::is_ascii_digit) {
+			return None;
+		}
+
+		let number = |digits: &[u8]| (digits[0] - b'0') as i32 * 10 + (digits[1] - b'0') as i32;
+		let (hours, minutes) = (number(&digits[..2]), number(&digits[2..]));
+
+		if minutes >= 60 {
+			return None;
+		}
+
+		FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+	}
+
+
+	/// Get the offset at the given instant.
+	fn offset_at_utc(&self, date: &NaiveDateTime) -> FixedOffset {
+		match self {
+			Self::Local => Local.offset_from_utc_datetime(date).fix(),
+			Self::Utc => FixedOffset::east_opt(0).expect("zero offset"),
+			Self::Fixed(offset) => *offset,
+			Self::Named(zone) => zone.offset_from_utc_datetime(date).fix(),
+		}
+	}
+
+
+	/// Get the offset at the given local time. Ambiguous times resolve to the earliest
+	/// instant, and times which are skipped in the time zone have no offset.
+	fn offset_at_local(&self, date: &NaiveDateTime) -> Option<FixedOffset> {
+		match self {
+			Self::Local => Local.offset_from_local_datetime(date).earliest().map(|offset| offset.fix()),
+			Self::Utc | Self::Fixed(_) => Some(self.offset_at_utc(date)),
+			Self::Named(zone) => zone.offset_from_local_datetime(date).earliest().map(|offset| offset.fix()),
+		}
+	}
+
+
+	/// Convert a timestamp to a date in this time zone.
+	fn date(&self, timestamp: f64) -> Option<DateTime<FixedOffset>> {
+		let utc = from_timestamp(timestamp)?;
+		Some(self.offset_at_utc(&utc).from_utc_datetime(&utc))
+	}
+
+
+	/// Convert a local date in this time zone to a date.
+	fn local(&self, date: &NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+		self
+			.offset_at_local(date)?
+			.from_local_datetime(date)
+			.single()
+	}
+}
+
+
+/// Get a float argument, accepting ints as well.
+fn float(value: &Value, pos: SourcePos) -> Result<f64, Panic> {
+	match value {
+		Value::Int(int) => Ok(*int as f64),
+		Value::Float(float) => Ok(float.0),
+		other => Err(Panic::type_error(other.copy(), "int or float", pos)),
+	}
+}
+
+
+/// Get an optional int field of a dict.
+fn field(dict: &Dict, name: &str, default: i64, pos: SourcePos) -> Result<i64, Panic> {
+	match dict.get(&name.into()) {
+		Err(_) | Ok(Value::Nil) => Ok(default),
+		Ok(Value::Int(int)) => Ok(int),
+		Ok(other) => Err(Panic::type_error(other, "int", pos)),
+	}
+}
+
+
+/// Units of elapsed time, in seconds.
+const TIME_UNITS: [(&str, f64); 5] = [
+	("hours", 3600.0),
+	("minutes", 60.0),
+	("seconds", 1.0),
+	("milliseconds", 1e-3),
+	("microseconds", 1e-6),
+];
+
+
+/// Sum the given components of a duration, in seconds.
+fn seconds(components: &Dict, units: &[(&str, f64)], pos: SourcePos) -> Result<f64, Panic> {
+	let mut duration = 0.0;
+
+	for &(name, seconds) in units {
+		match components.get(&name.into()) {
+			Err(_) | Ok(Value::Nil) => (),
+			Ok(value) => duration += float(&value, pos.copy())? * seconds,
+		}
+	}
+
+	Ok(duration)
+}
+
+
+/// Get the current wall-clock time.
+#[derive(Trace, Finalize)]
+struct Now;
+
+impl NativeFun for Now {
+	fn name(&self) -> &'static str { "std.time.now" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|duration| duration.as_secs_f64())
+			.unwrap_or_default();
+
+		Ok(now.into())
+	}
+}
+
+
+/// Get the monotonic time, in seconds from an arbitrary point. Unlike the wall-clock
+/// time, it's never adjusted, and should be used to measure durations.
+#[derive(Trace, Finalize)]
+struct Monotonic;
+
+impl NativeFun for Monotonic {
+	fn name(&self) -> &'static str { "std.time.monotonic" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(START.with(|start| start.elapsed().as_secs_f64()).into())
+	}
+}
+
+
+/// Convert a timestamp to its broken-down date in a time zone.
+#[derive(Trace, Finalize)]
+struct ToDate;
+
+impl NativeFun for ToDate {
+	fn name(&self) -> &'static str { "std.time.to_date" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (timestamp, zone) = match context.args() {
+			[ timestamp ] => (float(timestamp, context.pos.copy())?, Zone::parse(None, context.pos.copy())?),
+			[ timestamp, zone ] => (
+				float(timestamp, context.pos.copy())?,
+				Zone::parse(Some(zone), context.pos.copy())?
+			),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let date = zone
+			.date(timestamp)
+			.ok_or_else(|| Panic::value_error(timestamp.into(), "valid timestamp", context.pos.copy()))?;
+
+		let fields: [(&str, i64); 10] = [
+			("year", date.year() as i64),
+			("month", date.month() as i64),
+			("day", date.day() as i64),
+			("hour", date.hour() as i64),
+			("minute", date.minute() as i64),
+			("second", date.second() as i64),
+			("nanosecond", date.nanosecond() as i64),
+			("weekday", date.weekday().number_from_monday() as i64),
+			("yearday", date.ordinal() as i64),
+			("offset", date.offset().local_minus_utc() as i64),
+		];
+
+		let dict: DictMap = fields
+			.iter()
+			.map(|&(name, value)| (name.into(), value.into()))
+			.collect();
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+/// Convert a broken-down date to a timestamp. The offset field, if present, takes
+/// precedence over the time zone.
+#[derive(Trace, Finalize)]
+struct FromDate;
+
+impl NativeFun for FromDate {
+	fn name(&self) -> &'static str { "std.time.from_date" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (date, zone) = match context.args() {
+			[ Value::Dict(ref date) ] => (date, Zone::parse(None, context.pos.copy())?),
+			[ Value::Dict(ref date), zone ] => (date, Zone::parse(Some(zone), context.pos.copy())?),
+
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		let pos = context.pos.copy();
+		let get = |name, default| field(date, name, default, pos.copy());
+
+		let zone = match date.get(&"offset".into()) {
+			Err(_) | Ok(Value::Nil) => zone,
+			Ok(Value::Int(offset)) => FixedOffset::east_opt(offset as i32)
+				.filter(|_| offset.abs() < 86_400)
+				.map(Zone::Fixed)
+				.ok_or_else(|| Panic::value_error(offset.into(), "valid offset", pos.copy()))?,
+			Ok(other) => return Err(Panic::type_error(other, "int", pos.copy())),
+		};
+
+		let (year, month, day) = (get("year", 1970)?, get("month", 1)?, get("day", 1)?);
+		let (hour, minute, second) = (get("hour", 0)?, get("minute", 0)?, get("second", 0)?);
+		let nanosecond = get("nanosecond", 0)?;
+
+		let naive = NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32)
+			.and_then(|date| date.and_hms_nano_opt(hour as u32, minute as u32, second as u32, nanosecond as u32));
+
+		let timestamp = naive
+			.and_then(|naive| zone.local(&naive))
+			.map(|date| to_timestamp(&date));
+
+		Ok(
+			match timestamp {
+				Some(timestamp) => timestamp.into(),
+				None => Error::new("invalid date".into(), Value::Dict(date.copy())).into(),
+			}
+		)
+	}
+}
+
+
+/// Format a timestamp with a strftime-like format.
+#[derive(Trace, Finalize)]
+struct Format;
+
+impl NativeFun for Format {
+	fn name(&self) -> &'static str { "std.time.format" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (timestamp, format, zone) = match context.args() {
+			[ timestamp, Value::String(ref format) ] => (timestamp, format, None),
+			[ timestamp, Value::String(ref format), zone ] => (timestamp, format, Some(zone)),
+
+			[ _, other ] | [ _, other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let timestamp = float(timestamp, context.pos.copy())?;
+		let zone = Zone::parse(zone, context.pos.copy())?;
+
+		let format_str = std::str::from_utf8(format.as_bytes())
+			.map_err(|_| Panic::value_error(Value::String(format.copy()), "valid utf-8", context.pos.copy()))?;
+
+		let items: Vec<Item> = StrftimeItems::new(format_str).collect();
+		if items.iter().any(|item| matches!(item, Item::Error)) {
+			return Err(Panic::value_error(Value::String(format.copy()), "valid format", context.pos));
+		}
+
+		let date = zone
+			.date(timestamp)
+			.ok_or_else(|| Panic::value_error(timestamp.into(), "valid timestamp", context.pos.copy()))?;
+
+		Ok(date.format_with_items(items.into_iter()).to_string().into())
+	}
+}
+
+
+/// Parse a date with a strftime-like format, producing a timestamp. If the format has no
+/// offset, the date is interpreted in the given time zone. If it has no time, midnight is
+/// assumed.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.time.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, format, zone) = match context.args() {
+			[ value @ Value::String(_), Value::String(ref format) ] => (value, format, None),
+			[ value @ Value::String(_), Value::String(ref format), zone ] => (value, format, Some(zone)),
+
+			[ Value::String(_), other ] | [ Value::String(_), other, _ ] => {
+				return Err(Panic::type_error(other.copy(), "string", context.pos))
+			}
+			[ other, _ ] | [ other, _, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let zone = Zone::parse(zone, context.pos.copy())?;
+
+		let string = match value {
+			Value::String(string) => String::from_utf8_lossy(string.as_bytes()),
+			_ => unreachable!("value must be a string"),
+		};
+		let format = String::from_utf8_lossy(format.as_bytes());
+
+		let date = DateTime::parse_from_str(&string, &format)
+			.or_else(
+				|_| NaiveDateTime::parse_from_str(&string, &format)
+					.or_else(
+						|error| NaiveDate::parse_from_str(&string, &format)
+							.map(|date| date.and_hms_opt(0, 0, 0).expect("midnight"))
+							.map_err(|_| error)
+					)
+					.map_err(|error| error.to_string())
+					.and_then(
+						|naive| zone
+							.local(&naive)
+							.ok_or_else(|| "date does not exist in the time zone".to_owned())
+					)
+			);
+
+		Ok(
+			match date {
+				Ok(date) => to_timestamp(&date).into(),
+				Err(error) => Error::new(error.into(), value.copy()).into(),
+			}
+		)
+	}
+}
+
+
+/// Build a duration, in seconds, from its components.
+#[derive(Trace, Finalize)]
+struct Duration;
+
+impl NativeFun for Duration {
+	fn name(&self) -> &'static str { "std.time.duration" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let components = match context.args() {
+			[ Value::Dict(ref components) ] => components,
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let pos = context.pos.copy();
+		let days = seconds(components, &[ ("weeks", 604_800.0), ("days", 86_400.0) ], pos.copy())?;
+		let duration = days + seconds(components, &TIME_UNITS, pos)?;
+
+		Ok(duration.into())
+	}
+}
+
+
+/// Add calendar units to a timestamp. Years, months, weeks and days are added to the date
+/// in the given time zone, clamping the day to the end of the month, and the remaining
+/// units are added as elapsed time.
+#[derive(Trace, Finalize)]
+struct Add;
+
+impl NativeFun for Add {
+	fn name(&self) -> &'static str { "std.time.add" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (timestamp, components, zone) = match context.args() {
+			[ timestamp, Value::Dict(ref components) ] => (timestamp, components, None),
+			[ timestamp, Value::Dict(ref components), zone ] => (timestamp, components, Some(zone)),
+
+			[ _, other ] | [ _, other, _ ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		};
+
+		let pos = context.pos.copy();
+		let timestamp = float(timestamp, pos.copy())?;
+		let zone = Zone::parse(zone, pos.copy())?;
+
+		let months = field(components, "years", 0, pos.copy())? * 12 + field(components, "months", 0, pos.copy())?;
+		let days = field(components, "weeks", 0, pos.copy())? * 7 + field(components, "days", 0, pos.copy())?;
+
+		let elapsed = seconds(components, &TIME_UNITS, pos.copy())?;
+
+		let date = zone
+			.date(timestamp)
+			.map(|date| date.naive_local())
+			.and_then(
+				|date| if months >= 0 {
+					date.checked_add_months(Months::new(months as u32))
+				} else {
+					date.checked_sub_months(Months::new(months.unsigned_abs() as u32))
+				}
+			)
+			.and_then(
+				|date| if days >= 0 {
+					date.checked_add_days(Days::new(days as u64))
+				} else {
+					date.checked_sub_days(Days::new(days.unsigned_abs()))
+				}
+			)
+			.and_then(|date| zone.local(&date));
+
+		match date {
+			Some(date) => Ok((to_timestamp(&date) + elapsed).into()),
+			None => Err(Panic::value_error(timestamp.into(), "valid timestamp", pos)),
+		}
+	}
+}
+
+
+/// Sleep for the given number of seconds, with sub-second precision. This is the same as
+/// awaiting std.time.after.
+#[derive(Trace, Finalize)]
+struct Sleep;
+
+impl NativeFun for Sleep {
+	fn name(&self) -> &'static str { "std.time.sleep" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let duration = match context.args() {
+			[ seconds ] => util::duration(seconds, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Wait::new(None, Some(Instant::now() + duration)).join(context.pos)?;
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Start a timer for the given number of seconds, returning an asynchronous value, like
+/// an asynchronous command block. It's join method waits for the timer and returns nil,
+/// and it's is_finished method checks whether it has expired. Timers are waited by the
+/// same thread as command blocks, so that any amount of them may be pending.
+#[derive(Trace, Finalize)]
+struct After;
+
+impl NativeFun for After {
+	fn name(&self) -> &'static str { "std.time.after" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let duration = match context.args() {
+			[ seconds ] => util::duration(seconds, context.pos.copy())?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let wait = Wait::new(None, Some(Instant::now() + duration));
+
+		Ok(wait.value(AfterJoinImpl(wait.clone()).into()))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct AfterJoinImpl(Wait);
+
+impl NativeFun for AfterJoinImpl {
+	fn name(&self) -> &'static str { "std.time.after<join>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		self.0.join(context.pos)?;
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Start a stopwatch, which is a function returning the seconds elapsed since it was
+/// started, measured with the monotonic clock.
+#[derive(Trace, Finalize)]
+struct Stopwatch;
+
+impl NativeFun for Stopwatch {
+	fn name(&self) -> &'static str { "std.time.stopwatch" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(StopwatchImpl(Instant::now()).into())
+	}
+}
+
+
+#[derive(Finalize)]
+struct StopwatchImpl(Instant);
+
+/// StopwatchImpl has no garbage-collected fields.
+unsafe impl Trace for StopwatchImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for StopwatchImpl {
+	fn name(&self) -> &'static str { "std.time.stopwatch<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(self.0.elapsed().as_secs_f64().into())
+	}
+}
@@ -0,0 +1,150 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	Dict,
+	DictMap,
+	Error,
+	Float,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+	CallContext,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Dump) }
+
+
+/// Convert a TOML value. Dates and times are converted to strings.
+fn from_toml(value: toml::Value) -> Value {
+	match value {
+		toml::Value::String(string) => string.into(),
+		toml::Value::Integer(int) => Value::Int(int),
+		toml::Value::Float(float) => Value::Float(Float(float)),
+		toml::Value::Boolean(b) => Value::Bool(b),
+		toml::Value::Datetime(datetime) => datetime.to_string().into(),
+		toml::Value::Array(array) => array
+			.into_iter()
+			.map(from_toml)
+			.collect::<Vec<_>>()
+			.into(),
+		toml::Value::Table(table) => {
+			let dict: DictMap = table
+				.into_iter()
+				.map(|(key, value)| (key.into(), from_toml(value)))
+				.collect();
+
+			Dict::new(dict).into()
+		}
+	}
+}
+
+
+/// Convert a value to TOML, which has no representation for nil or functions, and only
+/// supports string keys. Returns the offending value on failure.
+fn to_toml(value: &Value) -> Result<toml::Value, Value> {
+	let string = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+
+	match value {
+		Value::Bool(b) => Ok(toml::Value::Boolean(*b)),
+		Value::Int(int) => Ok(toml::Value::Integer(*int)),
+		Value::Float(Float(float)) => Ok(toml::Value::Float(*float)),
+		Value::Byte(byte) => Ok(toml::Value::String(string(&[*byte]))),
+		Value::String(s) => Ok(toml::Value::String(string(s.as_bytes()))),
+		Value::Array(array) => array
+			.borrow()
+			.iter()
+			.map(to_toml)
+			.collect::<Result<_, _>>()
+			.map(toml::Value::Array),
+		Value::Dict(dict) => dict
+			.borrow()
+			.iter()
+			.map(
+				|(key, value)| match key {
+					Value::String(key) => Ok((string(key.as_bytes()), to_toml(value)?)),
+					other => Err(other.copy()),
+				}
+			)
+			.collect::<Result<_, _>>()
+			.map(toml::Value::Table),
+		other => Err(other.copy()),
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl Parse {
+	/// Build the error for malformed input, with it's position in the context, if any.
+	fn error(error: toml::de::Error) -> Value {
+		thread_local! {
+			pub static LINE: Value = "line".into();
+			pub static COLUMN: Value = "column".into();
+		}
+
+		let context = match error.line_col() {
+			Some((line, column)) => {
+				let mut context = DictMap::default();
+				LINE.with(|key| context.insert(key.copy(), Value::Int(line as i64 + 1)));
+				COLUMN.with(|key| context.insert(key.copy(), Value::Int(column as i64 + 1)));
+				Dict::new(context).into()
+			}
+
+			None => Value::Nil,
+		};
+
+		let description = format!("invalid toml: {}", error);
+
+		Error::new(description.into(), context).into()
+	}
+}
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.toml.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				toml::from_slice(string.as_bytes())
+					.map(from_toml)
+					.unwrap_or_else(Self::error)
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+#[derive(Trace, Finalize)]
+struct Dump;
+
+impl NativeFun for Dump {
+	fn name(&self) -> &'static str { "std.toml.dump" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::Dict(_) ] => {
+				let table = to_toml(value)
+					.map_err(
+						|value| Panic::value_error(
+							value,
+							"bool, byte, int, float, string, array or dict with string keys",
+							context.pos.copy()
+						)
+					)?;
+
+				toml::to_string(&table)
+					.map(Into::into)
+					.map_err(|_| Panic::value_error(value.copy(), "valid toml document", context.pos.copy()))
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,333 @@
+use std::{collections::HashMap, sync::Arc};
+
+use gc::{Gc, GcCell};
+
+use crate::{
+	runtime::{
+		mem,
+		program,
+		trace,
+		value::{HushFun, HushFunData},
+		SourcePos,
+	},
+	symbol::Symbol,
+};
+
+use super::{
+	channel::{Channel, ChannelImpl, Method},
+	Array,
+	Buffer,
+	Dict,
+	Error,
+	Function,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+/// Nesting deeper than this is most likely a cyclic value.
+const MAX_DEPTH: usize = 1024;
+
+
+/// A value which may be sent to another thread. The values of the interpreter are garbage
+/// collected per thread, and therefore must be copied: mutable collections are deep
+/// copied, and functions are transferred with their captured variables. Native functions
+/// which hold resources, such as sockets and child processes, can't be transferred,
+/// except for the methods of channels.
+#[derive(Debug)]
+pub struct Transfer {
+	value: Portable,
+	functions: Vec<PortableFun>,
+	/// Captured variables, which are kept shared by the transferred functions.
+	cells: Vec<Portable>,
+}
+
+
+#[derive(Debug)]
+enum Portable {
+	Nil,
+	Bool(bool),
+	Byte(u8),
+	Int(i64),
+	Float(f64),
+	String(Box<[u8]>),
+	Array(Box<[Portable]>),
+	Dict(Box<[(Portable, Portable)]>),
+	Buffer(Box<[u8]>),
+	/// An index in the transferred functions.
+	Function(usize),
+	/// A function of the standard library, by name.
+	Builtin(&'static str),
+	Channel(Arc<Channel>, Method),
+	Error {
+		description: Box<[u8]>,
+		context: Box<Portable>,
+		trace: Box<[trace::Frame]>,
+	},
+	/// The standard library, which is not copied.
+	Std,
+}
+
+
+#[derive(Debug)]
+struct PortableFun {
+	name: Option<Symbol>,
+	params: u32,
+	frame_info: &'static program::mem::FrameInfo,
+	body: &'static program::Block,
+	/// The indices of the captured variables.
+	context: Box<[(usize, mem::SlotIx)]>,
+	pos: SourcePos,
+}
+
+
+impl Transfer {
+	/// Copy a value so that it may be sent to another thread. The given stdlib is
+	/// transferred as the stdlib of the receiving runtime.
+	pub fn new(value: &Value, std: &Value, pos: SourcePos) -> Result<Self, Panic> {
+		let mut encoder = Encoder {
+			std,
+			functions: Vec::new(),
+			function_ids: HashMap::new(),
+			cells: Vec::new(),
+			cell_ids: HashMap::new(),
+			pos,
+		};
+
+		let value = encoder.encode(value, 0)?;
+
+		Ok(
+			Self {
+				value,
+				functions: encoder.functions,
+				cells: encoder.cells,
+			}
+		)
+	}
+
+
+	/// Rebuild the value in the receiving runtime, given it's stdlib.
+	pub fn into_value(self, std: &Value) -> Value {
+		let cells: Vec<Gc<GcCell<Value>>> = self.cells
+			.iter()
+			.map(|_| Gc::new(GcCell::new(Value::Nil)))
+			.collect();
+
+		let functions: Vec<HushFun> = self.functions
+			.into_iter()
+			.map(
+				|fun| HushFun::new(
+					fun.name,
+					fun.params,
+					fun.frame_info,
+					fun.body,
+					fun.context
+						.into_vec() // Use vec's owned iterator.
+						.into_iter()
+						.map(|(cell, slot_ix)| (cells[cell].clone(), slot_ix))
+						.collect(),
+					fun.pos,
+				)
+			)
+			.collect();
+
+		let decoder = Decoder { std, functions: &functions };
+
+		for (cell, value) in cells.iter().zip(self.cells) {
+			*cell.borrow_mut() = decoder.decode(value);
+		}
+
+		decoder.decode(self.value)
+	}
+}
+
+
+struct Encoder<'a> {
+	std: &'a Value,
+	functions: Vec<PortableFun>,
+	function_ids: HashMap<*const HushFunData, usize>,
+	cells: Vec<Portable>,
+	cell_ids: HashMap<*const GcCell<Value>, usize>,
+	pos: SourcePos,
+}
+
+
+impl<'a> Encoder<'a> {
+	fn encode(&mut self, value: &Value, depth: usize) -> Result<Portable, Panic> {
+		if depth > MAX_DEPTH {
+			return Err(Panic::stack_overflow(self.pos.copy()));
+		}
+
+		Ok(
+			match value {
+				Value::Nil => Portable::Nil,
+				Value::Bool(b) => Portable::Bool(*b),
+				Value::Byte(byte) => Portable::Byte(*byte),
+				Value::Int(int) => Portable::Int(*int),
+				Value::Float(float) => Portable::Float(float.0),
+				Value::String(string) => Portable::String(string.as_bytes().into()),
+				Value::Buffer(buffer) => Portable::Buffer(buffer.borrow().as_slice().into()),
+
+				Value::Array(array) => Portable::Array(
+					array
+						.borrow()
+						.iter()
+						.map(|value| self.encode(value, depth + 1))
+						.collect::<Result<_, _>>()?
+				),
+
+				Value::Dict(dict) if matches!(self.std, Value::Dict(std) if dict.ptr_eq(std)) => Portable::Std,
+
+				Value::Dict(dict) => Portable::Dict(
+					dict
+						.borrow()
+						.iter()
+						.map(|(key, value)| Ok((self.encode(key, depth + 1)?, self.encode(value, depth + 1)?)))
+						.collect::<Result<_, Panic>>()?
+				),
+
+				Value::Function(Function::Hush(fun)) => Portable::Function(self.function(fun, depth)?),
+
+				Value::Function(Function::Rust(fun)) => self.native(fun)?,
+
+				Value::Error(error) => Portable::Error {
+					description: error.description.as_bytes().into(),
+					context: Box::new(self.encode(&error.context.borrow(), depth + 1)?),
+					trace: error.trace.clone(),
+				},
+			}
+		)
+	}
+
+
+	/// Encode a function, returning it's index. Functions are encoded only once, as they
+	/// may capture themselves.
+	fn function(&mut self, fun: &HushFun, depth: usize) -> Result<usize, Panic> {
+		let ptr = &**fun as *const HushFunData;
+
+		if let Some(&id) = self.function_ids.get(&ptr) {
+			return Ok(id);
+		}
+
+		let id = self.functions.len();
+		self.function_ids.insert(ptr, id);
+		self.functions.push(
+			PortableFun {
+				name: fun.name,
+				params: fun.params,
+				frame_info: fun.frame_info,
+				body: fun.body,
+				context: Box::default(),
+				pos: fun.pos.copy(),
+			}
+		);
+
+		let context = fun.context
+			.iter()
+			.map(|(cell, slot_ix)| Ok((self.cell(cell, depth)?, slot_ix.copy())))
+			.collect::<Result<_, Panic>>()?;
+
+		self.functions[id].context = context;
+
+		Ok(id)
+	}
+
+
+	/// Encode a captured variable, returning it's index.
+	fn cell(&mut self, cell: &Gc<GcCell<Value>>, depth: usize) -> Result<usize, Panic> {
+		let ptr = &**cell as *const GcCell<Value>;
+
+		if let Some(&id) = self.cell_ids.get(&ptr) {
+			return Ok(id);
+		}
+
+		let id = self.cells.len();
+		self.cell_ids.insert(ptr, id);
+		self.cells.push(Portable::Nil);
+
+		self.cells[id] = self.encode(&cell.borrow(), depth + 1)?;
+
+		Ok(id)
+	}
+
+
+	/// Encode a native function, which must be either stateless or a channel method.
+	fn native(&self, fun: &RustFun) -> Result<Portable, Panic> {
+		if let Some(ChannelImpl(channel, method)) = fun.downcast_ref() {
+			return Ok(Portable::Channel(channel.clone(), *method));
+		}
+
+		let is_builtin = inventory::iter::<RustFun>
+			.into_iter()
+			.any(|builtin| builtin.name() == fun.name());
+
+		if is_builtin {
+			Ok(Portable::Builtin(fun.name()))
+		} else {
+			Err(
+				Panic::value_error(
+					Value::Function(fun.copy().into()),
+					"function that may be sent to another thread",
+					self.pos.copy(),
+				)
+			)
+		}
+	}
+}
+
+
+struct Decoder<'a> {
+	std: &'a Value,
+	functions: &'a [HushFun],
+}
+
+
+impl<'a> Decoder<'a> {
+	fn decode(&self, value: Portable) -> Value {
+		match value {
+			Portable::Nil => Value::Nil,
+			Portable::Bool(b) => b.into(),
+			Portable::Byte(byte) => byte.into(),
+			Portable::Int(int) => int.into(),
+			Portable::Float(float) => float.into(),
+			Portable::String(string) => string.into(),
+			Portable::Buffer(buffer) => Buffer::new(buffer.into()).into(),
+
+			Portable::Array(array) => Array::new(
+				array
+					.into_vec() // Use vec's owned iterator.
+					.into_iter()
+					.map(|value| self.decode(value))
+					.collect()
+			).into(),
+
+			Portable::Dict(dict) => {
+				let dict: Dict = dict
+					.into_vec() // Use vec's owned iterator.
+					.into_iter()
+					.map(|(key, value)| (self.decode(key), self.decode(value)))
+					.collect();
+
+				dict.into()
+			}
+
+			Portable::Function(id) => self.functions[id].copy().into(),
+
+			Portable::Builtin(name) => inventory::iter::<RustFun>
+				.into_iter()
+				.find(|builtin| builtin.name() == name)
+				.expect("builtin function should be registered")
+				.copy()
+				.into(),
+
+			Portable::Channel(channel, method) => ChannelImpl(channel, method).into(),
+
+			Portable::Error { description, context, trace } => {
+				Error::with_trace(description.into(), self.decode(*context), trace).into()
+			}
+
+			Portable::Std => self.std.copy(),
+		}
+	}
+}
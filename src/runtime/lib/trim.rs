@@ -28,9 +28,22 @@ impl NativeFun for Trim {
 					.into()
 			),
 
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-
-			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+			// Trim the given set of characters, instead of whitespace.
+			[ Value::String(ref string), chars ] => {
+				let chars = super::string::char_set(Some(chars), context.pos.copy())?;
+
+				Ok(
+					super::string::trim_with(
+						string.as_bytes(),
+						&chars,
+						|string, trim| string.trim_start_with(trim).trim_end_with(trim)
+					).into()
+				)
+			}
+
+			[ other ] | [ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
 	}
 }
@@ -28,6 +28,7 @@ impl StdType {
 			pub static STRING: Value = Type::String.display().into();
 			pub static ARRAY: Value = Type::Array.display().into();
 			pub static DICT: Value = Type::Dict.display().into();
+			pub static BUFFER: Value = Type::Buffer.display().into();
 			pub static FUNCTION: Value = Type::Function.display().into();
 			pub static ERROR: Value = Type::Error.display().into();
 		}
@@ -41,6 +42,7 @@ impl StdType {
 			Value::String(_) => &STRING,
 			Value::Array(_) => &ARRAY,
 			Value::Dict(_) => &DICT,
+			Value::Buffer(_) => &BUFFER,
 			Value::Function(_) => &FUNCTION,
 			Value::Error(_) => &ERROR,
 		};
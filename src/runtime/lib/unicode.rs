@@ -0,0 +1,133 @@
+use bstr::ByteSlice;
+use caseless::Caseless;
+use unicode_normalization::UnicodeNormalization;
+use unicode_width::UnicodeWidthStr;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Graphemes) }
+inventory::submit! { RustFun::from(Length) }
+inventory::submit! { RustFun::from(Nfc) }
+inventory::submit! { RustFun::from(Nfd) }
+inventory::submit! { RustFun::from(FoldCase) }
+inventory::submit! { RustFun::from(Width) }
+
+
+/// Get the single string argument.
+fn string<'a>(context: &'a CallContext) -> Result<&'a [u8], Panic> {
+	match context.args() {
+		[ Value::String(ref string) ] => Ok(string.as_bytes()),
+
+		[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy()))
+	}
+}
+
+
+/// Get the single string argument, which must be valid UTF-8.
+fn text<'a>(context: &'a CallContext) -> Result<&'a str, Panic> {
+	let string = string(context)?;
+
+	std::str::from_utf8(string)
+		.map_err(|_| Panic::value_error(context.args()[0].copy(), "valid utf-8", context.pos.copy()))
+}
+
+
+/// Split a string into grapheme clusters, which are what users perceive as characters.
+/// Invalid UTF-8 bytes are kept as they are.
+#[derive(Trace, Finalize)]
+struct Graphemes;
+
+impl NativeFun for Graphemes {
+	fn name(&self) -> &'static str { "std.unicode.graphemes" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let string = string(&context)?;
+
+		let graphemes: Vec<Value> = string
+			.grapheme_indices()
+			.map(|(start, end, _)| string[start .. end].into())
+			.collect();
+
+		Ok(graphemes.into())
+	}
+}
+
+
+/// The number of characters in a string, as opposed to std.len, which counts bytes. Each
+/// invalid UTF-8 sequence counts as a single character.
+#[derive(Trace, Finalize)]
+struct Length;
+
+impl NativeFun for Length {
+	fn name(&self) -> &'static str { "std.unicode.length" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Ok(Value::Int(string(&context)?.chars().count() as i64))
+	}
+}
+
+
+/// Normalize a string to the canonical composed form (NFC).
+#[derive(Trace, Finalize)]
+struct Nfc;
+
+impl NativeFun for Nfc {
+	fn name(&self) -> &'static str { "std.unicode.nfc" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Ok(text(&context)?.nfc().collect::<String>().into())
+	}
+}
+
+
+/// Normalize a string to the canonical decomposed form (NFD).
+#[derive(Trace, Finalize)]
+struct Nfd;
+
+impl NativeFun for Nfd {
+	fn name(&self) -> &'static str { "std.unicode.nfd" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Ok(text(&context)?.nfd().collect::<String>().into())
+	}
+}
+
+
+/// Fold the case of a string, for caseless comparison. This is more thorough than
+/// converting to lower case, e.g. ß is folded to ss.
+#[derive(Trace, Finalize)]
+struct FoldCase;
+
+impl NativeFun for FoldCase {
+	fn name(&self) -> &'static str { "std.unicode.fold_case" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Ok(text(&context)?.chars().default_case_fold().collect::<String>().into())
+	}
+}
+
+
+/// The number of columns a string takes when displayed in a terminal, where East Asian
+/// wide characters take two columns and combining characters take none. Useful to align
+/// tables.
+#[derive(Trace, Finalize)]
+struct Width;
+
+impl NativeFun for Width {
+	fn name(&self) -> &'static str { "std.unicode.width" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		Ok(Value::Int(text(&context)?.width() as i64))
+	}
+}
+
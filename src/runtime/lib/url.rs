@@ -0,0 +1,278 @@
+use std::convert::TryFrom;
+
+use gc::{Finalize, Trace};
+use url::{form_urlencoded, Url};
+
+use crate::runtime::SourcePos;
+
+use super::{
+	util,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Build) }
+inventory::submit! { RustFun::from(Join) }
+inventory::submit! { RustFun::from(EncodeQuery) }
+inventory::submit! { RustFun::from(DecodeQuery) }
+
+
+/// Convert a query value to text. Numbers and bools are formatted.
+fn query_value(value: &Value, pos: SourcePos) -> Result<String, Panic> {
+	match value {
+		Value::String(_) => util::text(value, pos).map(ToOwned::to_owned),
+		Value::Int(int) => Ok(int.to_string()),
+		Value::Float(float) => Ok(float.to_string()),
+		Value::Bool(flag) => Ok(flag.to_string()),
+		other => Err(Panic::type_error(other.copy(), "string or number", pos)),
+	}
+}
+
+
+/// Encode a dict as a query string. Keys are sorted, as dicts are unordered, and arrays
+/// produce a pair for each element.
+fn encode_query(dict: &Dict, pos: SourcePos) -> Result<String, Panic> {
+	let mut pairs = Vec::new();
+
+	for (key, value) in dict.borrow().iter() {
+		let key = query_value(key, pos.copy())?;
+
+		match value {
+			Value::Array(array) => {
+				for value in array.borrow().iter() {
+					pairs.push((key.clone(), query_value(value, pos.copy())?));
+				}
+			}
+
+			value => pairs.push((key, query_value(value, pos.copy())?)),
+		}
+	}
+
+	// Stable, so that the order of array elements is kept.
+	pairs.sort_by(|(key, _), (other, _)| key.cmp(other));
+
+	Ok(
+		form_urlencoded::Serializer::new(String::new())
+			.extend_pairs(pairs)
+			.finish()
+	)
+}
+
+
+/// Decode a query string to a dict. Repeated keys keep the last value.
+fn decode_query(query: &str) -> Value {
+	let dict: DictMap = form_urlencoded::parse(query.as_bytes())
+		.map(|(key, value)| (Value::from(&*key), Value::from(&*value)))
+		.collect();
+
+	Dict::new(dict).into()
+}
+
+
+/// Parse an URL into a dict with the scheme, username, password, host, port, path, query,
+/// which is decoded to a dict, and fragment. Missing components are nil. The port defaults
+/// to the one of the scheme, if known.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl Parse {
+	fn parse(url: &Url) -> Value {
+		let username = Some(url.username()).filter(|username| !username.is_empty());
+
+		let fields: [(&str, Value); 9] = [
+			("scheme", url.scheme().into()),
+			("username", username.into()),
+			("password", url.password().into()),
+			("host", url.host_str().into()),
+			("port", url.port_or_known_default().map(i64::from).into()),
+			("path", url.path().into()),
+			("query", url.query().map(decode_query).into()),
+			("raw_query", url.query().into()),
+			("fragment", url.fragment().into()),
+		];
+
+		let dict: DictMap = IntoIterator::into_iter(fields)
+			.map(|(name, field)| (name.into(), field))
+			.collect();
+
+		Dict::new(dict).into()
+	}
+}
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.url.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => {
+				let url = util::text(value, context.pos.copy())?;
+
+				Ok(
+					Url::parse(url)
+						.map(|url| Self::parse(&url))
+						.unwrap_or_else(|error| Error::new(error.to_string().into(), value.copy()).into())
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Build an URL from a dict with the same fields as produced by std.url.parse. The scheme
+/// and host are required. The query may be either a dict or an already encoded string.
+#[derive(Trace, Finalize)]
+struct Build;
+
+impl Build {
+	fn build(dict: &Dict, pos: SourcePos) -> Result<Result<Url, Error>, Panic> {
+		let field = |name: &str| dict.get(&name.into()).unwrap_or_default();
+		let invalid = |error: &dyn std::fmt::Display| Error::new(error.to_string().into(), Value::Dict(dict.copy()));
+
+		let scheme = field("scheme");
+		let host = field("host");
+
+		let mut url = match Url::parse(&format!("{}://{}", util::text(&scheme, pos.copy())?, util::text(&host, pos.copy())?)) {
+			Ok(url) => url,
+			Err(error) => return Ok(Err(invalid(&error))),
+		};
+
+		match field("port") {
+			Value::Nil => (),
+			Value::Int(port) => {
+				let result = u16::try_from(port).ok().map(|port| url.set_port(Some(port)));
+				if result != Some(Ok(())) {
+					return Ok(Err(invalid(&"invalid port")));
+				}
+			}
+			other => return Err(Panic::type_error(other, "int", pos)),
+		}
+
+		match field("username") {
+			Value::Nil => (),
+			username => if url.set_username(util::text(&username, pos.copy())?).is_err() {
+				return Ok(Err(invalid(&"invalid username")));
+			},
+		}
+
+		match field("password") {
+			Value::Nil => (),
+			password => if url.set_password(Some(util::text(&password, pos.copy())?)).is_err() {
+				return Ok(Err(invalid(&"invalid password")));
+			},
+		}
+
+		match field("path") {
+			Value::Nil => (),
+			path => url.set_path(util::text(&path, pos.copy())?),
+		}
+
+		match field("query") {
+			Value::Nil => (),
+			Value::Dict(ref query) => url.set_query(Some(&encode_query(query, pos.copy())?)),
+			query @ Value::String(_) => url.set_query(Some(util::text(&query, pos.copy())?)),
+			other => return Err(Panic::type_error(other, "dict or string", pos)),
+		}
+
+		match field("fragment") {
+			Value::Nil => (),
+			fragment => url.set_fragment(Some(util::text(&fragment, pos.copy())?)),
+		}
+
+		Ok(Ok(url))
+	}
+}
+
+impl NativeFun for Build {
+	fn name(&self) -> &'static str { "std.url.build" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Dict(ref dict) ] => Ok(
+				Self::build(dict, context.pos.copy())?
+					.map(String::from)
+					.into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Resolve a relative URL against a base URL, as a browser would.
+#[derive(Trace, Finalize)]
+struct Join;
+
+impl NativeFun for Join {
+	fn name(&self) -> &'static str { "std.url.join" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ base, relative ] => {
+				let base_url = util::text(base, context.pos.copy())?;
+				let relative = util::text(relative, context.pos.copy())?;
+
+				Ok(
+					Url::parse(base_url)
+						.and_then(|url| url.join(relative))
+						.map(|url| String::from(url).into())
+						.unwrap_or_else(|error| Error::new(error.to_string().into(), base.copy()).into())
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Encode a dict as a query string, in the application/x-www-form-urlencoded format.
+/// Values may be strings, numbers or arrays of those, which produce repeated keys. Keys
+/// are sorted, so that the result is deterministic.
+#[derive(Trace, Finalize)]
+struct EncodeQuery;
+
+impl NativeFun for EncodeQuery {
+	fn name(&self) -> &'static str { "std.url.encode_query" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Dict(ref dict) ] => Ok(encode_query(dict, context.pos.copy())?.into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Decode a query string to a dict. A leading question mark is ignored. Repeated keys
+/// keep the last value.
+#[derive(Trace, Finalize)]
+struct DecodeQuery;
+
+impl NativeFun for DecodeQuery {
+	fn name(&self) -> &'static str { "std.url.decode_query" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => {
+				let query = util::text(value, context.pos.copy())?;
+				Ok(decode_query(query.strip_prefix('?').unwrap_or(query)))
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
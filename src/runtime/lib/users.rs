@@ -0,0 +1,161 @@
+// The password and group databases are only available on Unix.
+#![cfg(unix)]
+
+use std::{convert::TryFrom, path::Path};
+
+use gc::{Finalize, Trace};
+
+use crate::io::{self, Group, User};
+
+use super::{
+	util,
+	CallContext,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(GetUser) }
+inventory::submit! { RustFun::from(GetGroup) }
+inventory::submit! { RustFun::from(All) }
+inventory::submit! { RustFun::from(Groups) }
+
+
+/// The user database, which must be readable according to the sandbox policy.
+const PASSWD: &str = "/etc/passwd";
+
+/// The group database, which must be readable according to the sandbox policy.
+const GROUP: &str = "/etc/group";
+
+
+/// Convert a user to a dict with it's name, uid, gid, gecos, home and shell.
+fn user(user: User) -> Value {
+	util::dict([
+		("name", user.name.into()),
+		("uid", i64::from(user.uid).into()),
+		("gid", i64::from(user.gid).into()),
+		("gecos", user.gecos.into()),
+		("home", user.home.into_os_string().into()),
+		("shell", user.shell.into()),
+	])
+}
+
+
+/// Convert a group to a dict with it's name, gid and members.
+fn group(group: Group) -> Value {
+	let members: Vec<Value> = group.members
+		.into_iter()
+		.map(Into::into)
+		.collect();
+
+	util::dict([
+		("name", group.name.into()),
+		("gid", i64::from(group.gid).into()),
+		("members", members.into()),
+	])
+}
+
+
+/// Look up a user by name or uid, returning a dict with it's name, uid, gid, gecos
+/// (usually the full name), home and shell, or nil if there is no such user.
+#[derive(Trace, Finalize)]
+struct GetUser;
+
+impl NativeFun for GetUser {
+	fn name(&self) -> &'static str { "std.os.users.user" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		context.runtime.options.policy.check_path(Path::new(PASSWD), context.pos.copy())?;
+
+		let entry = match context.args() {
+			[ Value::String(name) ] => io::user(Ok(name.as_bytes())),
+			[ value @ Value::Int(uid) ] => io::user(
+				Err(
+					libc::uid_t::try_from(*uid)
+						.map_err(|_| Panic::value_error(value.copy(), "valid uid", context.pos.copy()))?
+				)
+			),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "string or int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Ok(entry.map(user).into())
+	}
+}
+
+
+/// Look up a group by name or gid, returning a dict with it's name, gid and members, or
+/// nil if there is no such group. Members are the users which have it as a supplementary
+/// group, and not the ones which have it as their primary group.
+#[derive(Trace, Finalize)]
+struct GetGroup;
+
+impl NativeFun for GetGroup {
+	fn name(&self) -> &'static str { "std.os.users.group" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		context.runtime.options.policy.check_path(Path::new(GROUP), context.pos.copy())?;
+
+		let entry = match context.args() {
+			[ Value::String(name) ] => io::group(Ok(name.as_bytes())),
+			[ value @ Value::Int(gid) ] => io::group(
+				Err(
+					libc::gid_t::try_from(*gid)
+						.map_err(|_| Panic::value_error(value.copy(), "valid gid", context.pos.copy()))?
+				)
+			),
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "string or int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		Ok(entry.map(group).into())
+	}
+}
+
+
+/// Get all users of the password database, in the format of std.os.users.user.
+#[derive(Trace, Finalize)]
+struct All;
+
+impl NativeFun for All {
+	fn name(&self) -> &'static str { "std.os.users.all" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		context.runtime.options.policy.check_path(Path::new(PASSWD), context.pos.copy())?;
+
+		match context.args() {
+			[] => {
+				let users: Vec<Value> = io::users().into_iter().map(user).collect();
+				Ok(users.into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// Get all groups of the group database, in the format of std.os.users.group.
+#[derive(Trace, Finalize)]
+struct Groups;
+
+impl NativeFun for Groups {
+	fn name(&self) -> &'static str { "std.os.users.groups" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		context.runtime.options.policy.check_path(Path::new(GROUP), context.pos.copy())?;
+
+		match context.args() {
+			[] => {
+				let groups: Vec<Value> = io::groups().into_iter().map(group).collect();
+				Ok(groups.into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
@@ -1,4 +1,135 @@
-use super::{Float, Value};
+use std::{borrow::Cow, convert::TryFrom, fs::Metadata, time::Duration};
+
+use crate::runtime::SourcePos;
+
+use super::{CallContext, Dict, DictMap, Float, Function, Panic, Value};
+
+
+/// Call a function with the given arguments.
+pub fn call<const N: usize>(context: &mut CallContext, fun: &Function, args: [Value; N]) -> Result<Value, Panic> {
+	let args_start = context.runtime.arguments.len();
+	context.runtime.arguments.extend(IntoIterator::into_iter(args));
+	context.call(Value::default(), fun, args_start)
+}
+
+
+/// Call a predicate, which must return a bool.
+pub fn test<const N: usize>(context: &mut CallContext, fun: &Function, args: [Value; N]) -> Result<bool, Panic> {
+	match call(context, fun, args)? {
+		Value::Bool(result) => Ok(result),
+		other => Err(Panic::invalid_condition(other, context.pos.copy())),
+	}
+}
+
+
+/// Build a dict from fields.
+pub fn dict<const N: usize>(fields: [(&str, Value); N]) -> Value {
+	let dict: DictMap = IntoIterator::into_iter(fields)
+		.map(|(name, field)| (name.into(), field))
+		.collect();
+
+	Dict::new(dict).into()
+}
+
+
+/// Get the text of a string argument.
+pub fn text(value: &Value, pos: SourcePos) -> Result<&str, Panic> {
+	match value {
+		Value::String(string) => std::str::from_utf8(string.as_bytes())
+			.map_err(|_| Panic::value_error(value.copy(), "valid utf-8", pos)),
+		other => Err(Panic::type_error(other.copy(), "string", pos)),
+	}
+}
+
+
+/// Allocate an empty byte vector with room for the given amount of bytes, which is
+/// usually computed from arguments. Sizes which overflowed, which are past isize::MAX or
+/// which can't be allocated yield None, so that they may be reported as a panic instead
+/// of aborting the process.
+pub fn alloc(size: Option<usize>) -> Option<Vec<u8>> {
+	let size = size.filter(|&size| isize::try_from(size).is_ok())?;
+
+	let mut bytes = Vec::new();
+	bytes.try_reserve_exact(size).ok()?;
+
+	Some(bytes)
+}
+
+
+/// Get the bytes of a string, a buffer, or an array of bytes.
+pub fn bytes(value: &Value) -> Option<Cow<[u8]>> {
+	match value {
+		Value::String(string) => Some(Cow::Borrowed(string.as_bytes())),
+
+		Value::Buffer(buffer) => Some(Cow::Owned(buffer.borrow().clone())),
+
+		Value::Array(array) => array
+			.borrow()
+			.iter()
+			.map(
+				|value| match value {
+					Value::Byte(byte) => Some(*byte),
+					_ => None,
+				}
+			)
+			.collect::<Option<Vec<u8>>>()
+			.map(Cow::Owned),
+
+		_ => None,
+	}
+}
+
+
+/// The permissions of a file, as a Unix file mode. On Windows, the mode is derived from
+/// the read-only attribute.
+pub fn mode(metadata: &Metadata) -> u32 {
+	#[cfg(unix)]
+	return std::os::unix::fs::MetadataExt::mode(metadata) & 0o7777;
+
+	#[cfg(windows)]
+	return {
+		let mode = if metadata.is_dir() { 0o755 } else { 0o644 };
+
+		if metadata.permissions().readonly() {
+			mode & !0o222
+		} else {
+			mode
+		}
+	};
+}
+
+
+/// Get a file mode, up to the given maximum. As there are no octal literals, the mode may
+/// be given as a string of octal digits.
+pub fn file_mode(value: Value, max: u32, pos: SourcePos) -> Result<u32, Panic> {
+	let mode = match &value {
+		Value::Int(mode) => u32::try_from(*mode).ok(),
+		Value::String(mode) => std::str::from_utf8(mode.as_bytes())
+			.ok()
+			.and_then(|mode| u32::from_str_radix(mode, 8).ok()),
+		_ => return Err(Panic::type_error(value, "int or string", pos)),
+	};
+
+	mode
+		.filter(|&mode| mode <= max)
+		.ok_or_else(|| Panic::value_error(value, "file mode", pos))
+}
+
+
+/// Get a duration from a non-negative number of seconds, with sub-second precision.
+pub fn duration(value: &Value, pos: SourcePos) -> Result<Duration, Panic> {
+	let seconds = match value {
+		Value::Int(int) => *int as f64,
+		Value::Float(float) => float.0,
+		other => return Err(Panic::type_error(other.copy(), "int or float", pos)),
+	};
+
+	if !seconds.is_finite() || seconds < 0.0 {
+		return Err(Panic::value_error(value.copy(), "non-negative number", pos));
+	}
+
+	Ok(Duration::from_secs_f64(seconds))
+}
 
 
 /// A triple of numbers promoted to the same type.
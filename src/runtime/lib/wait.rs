@@ -0,0 +1,72 @@
+use std::{sync::Arc, time::Instant};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::{
+	command::{self, Source, WaitHandle},
+	SourcePos,
+};
+
+use super::{
+	util,
+	CallContext,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+/// A wait for a socket or a deadline in the reactor thread, which runs asynchronous
+/// command blocks. It's shared by the methods of an asynchronous value.
+#[derive(Clone, Finalize)]
+pub struct Wait(Arc<WaitHandle>);
+
+/// Wait has no garbage-collected fields.
+unsafe impl Trace for Wait {
+	gc::unsafe_empty_trace!();
+}
+
+impl Wait {
+	/// Wait until the socket becomes readable, or until the deadline.
+	pub fn new(source: Option<Source>, deadline: Option<Instant>) -> Self {
+		Self(command::wait(source, deadline))
+	}
+
+
+	/// Block until the wait finishes, returning whether the socket became readable. Waits
+	/// may only be joined once.
+	pub fn join(&self, pos: SourcePos) -> Result<bool, Panic> {
+		match self.0.join() {
+			Some(Ok(ready)) => Ok(ready),
+			Some(Err(error)) => Err(Panic::io(error, pos)),
+			None => Err(Panic::invalid_join(pos)),
+		}
+	}
+
+
+	/// Build an asynchronous value, with the given join method and an is_finished method.
+	pub fn value(&self, join: Value) -> Value {
+		util::dict([
+			("join", join),
+			("is_finished", IsFinishedImpl(self.clone()).into()),
+		])
+	}
+}
+
+
+/// Check whether the wait has finished, without blocking.
+#[derive(Trace, Finalize)]
+struct IsFinishedImpl(Wait);
+
+impl NativeFun for IsFinishedImpl {
+	fn name(&self) -> &'static str { "<wait>.is_finished" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(self.0.0.is_finished().into())
+	}
+}
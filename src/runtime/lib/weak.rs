@@ -0,0 +1,86 @@
+use std::{
+	cell::Cell,
+	rc::Rc,
+};
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::finalize;
+
+use super::{
+	CallContext,
+	Dict,
+	DictMap,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Weak) }
+
+
+/// Create a weak reference to an array or dict, which doesn't keep it alive, such as for
+/// caches of large values. Returns a dict with the method:
+/// - get(): the value, or nil if it has been collected.
+/// Values are collected some time after they become unreachable, or right away with
+/// std.gc.collect.
+#[derive(Trace, Finalize)]
+struct Weak;
+
+impl NativeFun for Weak {
+	fn name(&self) -> &'static str { "std.weak" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, id): (Value, _) = match context.args() {
+			[ Value::Array(array) ] => (array.copy().into(), array.id()),
+			[ Value::Dict(dict) ] => (dict.copy().into(), dict.id()),
+			[ other ] => return Err(Panic::type_error(other.copy(), "array or dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let alive = finalize::watch(id);
+
+		// Unrooted values are not kept alive by the garbage collector.
+		unsafe { Trace::unroot(&value); }
+
+		let mut dict = DictMap::default();
+		dict.insert("get".into(), GetImpl { value, alive }.into());
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+/// Get the referenced value, if it's still alive.
+#[derive(Finalize)]
+struct GetImpl {
+	/// An unrooted copy of the value, which must not be used once the value is collected.
+	value: Value,
+	alive: Rc<Cell<bool>>,
+}
+
+impl NativeFun for GetImpl {
+	fn name(&self) -> &'static str { "std.weak<get>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		if self.alive.get() {
+			Ok(self.value.copy())
+		} else {
+			Ok(Value::Nil)
+		}
+	}
+}
+
+
+/// GetImpl must not trace the value, or it would be kept alive. The value is unrooted, so
+/// dropping it doesn't access the collected value.
+unsafe impl Trace for GetImpl {
+	gc::unsafe_empty_trace!();
+}
@@ -0,0 +1,36 @@
+use std::ffi::OsStr;
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::command::lookup;
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Which) }
+
+#[derive(Trace, Finalize)]
+struct Which;
+
+impl NativeFun for Which {
+	fn name(&self) -> &'static str { "std.which" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref name) ] => Ok(
+				lookup::find(AsRef::<OsStr>::as_ref(name))
+					.map(|path| path.into_path_buf().into_os_string())
+					.into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,57 @@
+use std::{ffi::OsStr, io, path::Path};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(WithCwd) }
+
+#[derive(Trace, Finalize)]
+struct WithCwd;
+
+impl NativeFun for WithCwd {
+	fn name(&self) -> &'static str { "std.with_cwd" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (dir, fun) = match context.args() {
+			[ Value::String(ref dir), Value::Function(ref fun) ] => {
+				(Path::new(AsRef::<OsStr>::as_ref(dir)).to_owned(), fun.copy())
+			}
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		// Relative directories are resolved in the working directory of commands, and the
+		// result is absolute, so that it's unaffected by later changes of the current
+		// directory.
+		let dir = match &context.runtime.options.cwd {
+			Some(cwd) => cwd.join(dir),
+			None => std::env::current_dir()
+				.map_err(|error| Panic::io(error, context.pos.copy()))?
+				.join(dir),
+		};
+
+		if !dir.is_dir() {
+			let error = io::Error::new(io::ErrorKind::NotFound, "not a directory");
+			return Err(Panic::io(error, context.pos));
+		}
+
+		let previous = std::mem::replace(&mut context.runtime.options.cwd, Some(dir.into()));
+
+		let args_start = context.args_start + 2;
+		let result = context.call(Value::default(), &fun, args_start);
+
+		context.runtime.options.cwd = previous;
+
+		result
+	}
+}
@@ -0,0 +1,114 @@
+use std::io;
+
+use gc::{Finalize, Trace};
+
+use crate::runtime::{
+	command::{IoClass, IoPriority, Limits},
+	SourcePos,
+};
+
+use super::{
+	util,
+	CallContext,
+	Dict,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(WithLimits) }
+
+#[derive(Trace, Finalize)]
+struct WithLimits;
+
+impl WithLimits {
+	/// Build the limits from the options dict. Missing options are left unset.
+	fn limits(options: &Dict, pos: SourcePos) -> Result<Limits, Panic> {
+		let option = |name: &str| options.get(&name.into()).unwrap_or_default();
+
+		let mut limits = Limits::default();
+
+		match option("nice") {
+			Value::Nil => (),
+			Value::Int(nice) if (-20 ..= 19).contains(&nice) => limits.nice = Some(nice as i32),
+			value @ Value::Int(_) => return Err(Panic::value_error(value, "integer from -20 to 19", pos)),
+			value => return Err(Panic::type_error(value, "int", pos)),
+		}
+
+		match option("ionice") {
+			Value::Nil => (),
+			Value::Dict(_) if cfg!(windows) => return Err(Self::unsupported(pos)),
+			Value::Dict(ref ionice) => limits.ionice = Some(Self::io_priority(ionice, pos.copy())?),
+			value => return Err(Panic::type_error(value, "dict", pos)),
+		}
+
+		match option("umask") {
+			Value::Nil => (),
+			_ if cfg!(windows) => return Err(Self::unsupported(pos)),
+			umask => limits.umask = Some(util::file_mode(umask, 0o777, pos)?),
+		}
+
+		Ok(limits)
+	}
+
+
+	/// The error for limits which are not supported on Windows.
+	fn unsupported(pos: SourcePos) -> Panic {
+		Panic::io(io::ErrorKind::Unsupported.into(), pos)
+	}
+
+
+	/// Build the io priority from a dict with the class name and the level.
+	fn io_priority(ionice: &Dict, pos: SourcePos) -> Result<IoPriority, Panic> {
+		let class = match ionice.get(&"class".into()) {
+			Ok(Value::String(ref name)) => IoClass::parse(name.as_bytes()).ok_or_else(
+				|| Panic::value_error(
+					Value::String(name.copy()),
+					"realtime, best-effort or idle",
+					pos.copy()
+				)
+			)?,
+			Ok(value) => return Err(Panic::type_error(value, "string", pos)),
+			Err(_) => return Err(Panic::value_error(Value::Dict(ionice.copy()), "dict with class", pos)),
+		};
+
+		// The default level of ionice(1).
+		let level = match ionice.get(&"level".into()) {
+			Ok(Value::Nil) | Err(_) => 4,
+			Ok(Value::Int(level)) if (0 ..= 7).contains(&level) => level as u8,
+			Ok(value @ Value::Int(_)) => return Err(Panic::value_error(value, "integer from 0 to 7", pos)),
+			Ok(value) => return Err(Panic::type_error(value, "int", pos)),
+		};
+
+		Ok(IoPriority { class, level })
+	}
+}
+
+impl NativeFun for WithLimits {
+	fn name(&self) -> &'static str { "std.with_limits" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (limits, fun) = match context.args() {
+			[ Value::Dict(ref options), Value::Function(ref fun) ] => {
+				(Self::limits(options, context.pos.copy())?, fun.copy())
+			}
+
+			[ Value::Dict(_), other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		};
+
+		// Nested calls only override the limits they set.
+		let previous = context.runtime.options.limits;
+		context.runtime.options.limits.merge(limits);
+
+		let args_start = context.args_start + 2;
+		let result = context.call(Value::default(), &fun, args_start);
+
+		context.runtime.options.limits = previous;
+
+		result
+	}
+}
@@ -0,0 +1,474 @@
+use gc::{Finalize, Trace};
+use roxmltree::{Document, Node, ParsingOptions};
+
+use super::{
+	util,
+	CallContext,
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Select) }
+
+
+/// Parse an XML document into it's root element. Elements are dicts with the tag name
+/// (without namespace prefix), the attributes dict, the children array, which contains
+/// elements and non-blank text strings, and the text content of all descendants. Comments
+/// and processing instructions are dropped. Malformed documents produce an error value.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl Parse {
+	fn element(node: Node) -> Value {
+		let attributes: DictMap = node
+			.attributes()
+			.map(|attribute| (attribute.name().into(), attribute.value().into()))
+			.collect();
+
+		let children: Vec<Value> = node
+			.children()
+			.filter_map(
+				|child| if child.is_element() {
+					Some(Self::element(child))
+				} else {
+					child
+						.text()
+						.filter(|text| child.is_text() && !text.trim().is_empty())
+						.map(Into::into)
+				}
+			)
+			.collect();
+
+		let text: String = node
+			.descendants()
+			.filter(Node::is_text)
+			.filter_map(|node| node.text())
+			.collect();
+
+		util::dict([
+			("tag", node.tag_name().name().into()),
+			("attributes", Dict::new(attributes).into()),
+			("children", children.into()),
+			("text", text.into()),
+		])
+	}
+}
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.xml.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => {
+				let xml = util::text(value, context.pos.copy())?;
+
+				// Tools such as nmap emit a doctype.
+				let options = ParsingOptions { allow_dtd: true, ..ParsingOptions::default() };
+
+				Ok(
+					match Document::parse_with_options(xml, options) {
+						Ok(document) => Self::element(document.root_element()),
+						Err(error) => Error::new(error.to_string().into(), value.copy()).into(),
+					}
+				)
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Get a field of an element, or nil if the value is not an element.
+fn field(element: &Value, name: &str) -> Value {
+	match element {
+		Value::Dict(dict) => dict.get(&name.into()).unwrap_or_default(),
+		_ => Value::Nil,
+	}
+}
+
+
+/// Get an attribute of an element, if it has one.
+fn attribute(element: &Value, name: &str) -> Option<Value> {
+	match field(element, "attributes") {
+		Value::Dict(ref attributes) => attributes.get(&name.into()).ok(),
+		_ => None,
+	}
+}
+
+
+/// How a step relates to the element matched by the previous step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+	Child,
+	Descendant,
+}
+
+
+/// A condition on an element's attribute.
+#[derive(Debug)]
+struct Condition {
+	name: String,
+	/// The required value, or None if the attribute must only be present.
+	value: Option<String>,
+}
+
+
+/// A step of a selector, matching elements by tag and attributes.
+#[derive(Debug)]
+struct Step {
+	combinator: Combinator,
+	/// The tag name, or None for any tag.
+	tag: Option<String>,
+	/// Class names, which must all be present in the class attribute.
+	classes: Vec<String>,
+	conditions: Vec<Condition>,
+}
+
+
+impl Step {
+	fn new(combinator: Combinator) -> Self {
+		Self {
+			combinator,
+			tag: None,
+			classes: Vec::new(),
+			conditions: Vec::new(),
+		}
+	}
+
+
+	fn matches(&self, element: &Value) -> bool {
+		let tag_matches = match (&self.tag, field(element, "tag")) {
+			(None, Value::String(_)) => true,
+			(Some(tag), Value::String(ref name)) => tag.as_bytes() == name.as_bytes(),
+			_ => false,
+		};
+
+		let has_classes = || match attribute(element, "class") {
+			_ if self.classes.is_empty() => true,
+			Some(Value::String(ref class)) => {
+				let class = String::from_utf8_lossy(class.as_bytes()).into_owned();
+				self.classes.iter().all(|name| class.split_whitespace().any(|class| class == name))
+			}
+			_ => false,
+		};
+
+		let meets_conditions = || self.conditions.iter().all(
+			|condition| match (attribute(element, &condition.name), &condition.value) {
+				(Some(_), None) => true,
+				(Some(Value::String(ref actual)), Some(value)) => actual.as_bytes() == value.as_bytes(),
+				_ => false,
+			}
+		);
+
+		tag_matches && has_classes() && meets_conditions()
+	}
+}
+
+
+/// A parser for selectors.
+struct Parser<'a> {
+	input: &'a [u8],
+	offset: usize,
+	/// Whether the selector is CSS, where dots start class names rather than being part
+	/// of names.
+	css: bool,
+}
+
+
+impl<'a> Parser<'a> {
+	fn new(input: &'a str) -> Self {
+		Self { input: input.as_bytes(), offset: 0, css: false }
+	}
+
+
+	/// Parse a selector into it's steps. Selectors starting with a slash are XPath,
+	/// otherwise they are CSS.
+	fn parse(mut self) -> Option<Vec<Step>> {
+		self.skip_whitespace();
+
+		let steps = if self.peek() == Some(b'/') {
+			self.xpath()?
+		} else {
+			self.css = true;
+			self.css()?
+		};
+
+		Some(steps).filter(|steps| !steps.is_empty())
+	}
+
+
+	fn peek(&self) -> Option<u8> {
+		self.input.get(self.offset).copied()
+	}
+
+
+	fn eat(&mut self, byte: u8) -> bool {
+		let matches = self.peek() == Some(byte);
+		if matches {
+			self.offset += 1;
+		}
+		matches
+	}
+
+
+	fn skip_whitespace(&mut self) -> bool {
+		let start = self.offset;
+		while self.peek().map_or(false, |byte| byte.is_ascii_whitespace()) {
+			self.offset += 1;
+		}
+		self.offset > start
+	}
+
+
+	/// A word of name characters.
+	fn word(&mut self) -> Option<&'a str> {
+		let start = self.offset;
+		let separators: &[u8] = if self.css { b"-_:" } else { b"-_:." };
+
+		while self
+			.peek()
+			.map_or(false, |byte| byte.is_ascii_alphanumeric() || separators.contains(&byte) || byte >= 0x80)
+		{
+			self.offset += 1;
+		}
+
+		std::str::from_utf8(&self.input[start .. self.offset])
+			.ok()
+			.filter(|word| !word.is_empty())
+	}
+
+
+	/// A tag or attribute name. Namespace prefixes are dropped, as they are not kept when
+	/// parsing.
+	fn name(&mut self) -> Option<String> {
+		let name = self.word()?.rsplit(':').next()?;
+		Some(name.to_owned()).filter(|name| !name.is_empty())
+	}
+
+
+	/// An attribute value, either quoted or a plain name.
+	fn value(&mut self) -> Option<String> {
+		match self.peek() {
+			Some(quote @ (b'"' | b'\'')) => {
+				self.offset += 1;
+				let start = self.offset;
+
+				while self.peek()? != quote {
+					self.offset += 1;
+				}
+
+				let value = std::str::from_utf8(&self.input[start .. self.offset]).ok()?;
+				self.offset += 1;
+
+				Some(value.to_owned())
+			}
+
+			_ => self.word().map(str::to_owned),
+		}
+	}
+
+
+	/// An attribute condition, after the opening bracket, with an optional prefix before
+	/// the name (`@` in XPath).
+	fn condition(&mut self, prefix: Option<u8>) -> Option<Condition> {
+		self.skip_whitespace();
+
+		if let Some(prefix) = prefix {
+			if !self.eat(prefix) {
+				return None;
+			}
+		}
+
+		let name = self.name()?;
+		self.skip_whitespace();
+
+		let value = if self.eat(b'=') {
+			self.skip_whitespace();
+			Some(self.value()?)
+		} else {
+			None
+		};
+
+		self.skip_whitespace();
+
+		if self.eat(b']') {
+			Some(Condition { name, value })
+		} else {
+			None
+		}
+	}
+
+
+	/// A CSS selector, with descendant and child combinators, and compound selectors of
+	/// tag, id, class and attribute conditions.
+	fn css(&mut self) -> Option<Vec<Step>> {
+		let mut steps = Vec::new();
+		let mut combinator = Combinator::Descendant;
+
+		loop {
+			let mut step = Step::new(combinator);
+			let start = self.offset;
+
+			if self.eat(b'*') {
+				// Any tag.
+			} else if self.peek().map_or(false, |byte| !b"#.[".contains(&byte)) {
+				step.tag = Some(self.name()?);
+			}
+
+			loop {
+				if self.eat(b'#') {
+					let id = self.name()?;
+					step.conditions.push(Condition { name: "id".into(), value: Some(id) });
+				} else if self.eat(b'.') {
+					step.classes.push(self.name()?);
+				} else if self.eat(b'[') {
+					step.conditions.push(self.condition(None)?);
+				} else {
+					break;
+				}
+			}
+
+			// Steps can't be empty, such as after a trailing combinator.
+			if self.offset == start {
+				return None;
+			}
+
+			steps.push(step);
+
+			let whitespace = self.skip_whitespace();
+
+			if self.peek().is_none() {
+				return Some(steps);
+			}
+
+			combinator = if self.eat(b'>') {
+				self.skip_whitespace();
+				Combinator::Child
+			} else if whitespace {
+				Combinator::Descendant
+			} else {
+				return None;
+			};
+		}
+	}
+
+
+	/// An XPath location path, with child and descendant steps, and attribute
+	/// predicates.
+	fn xpath(&mut self) -> Option<Vec<Step>> {
+		let mut steps = Vec::new();
+
+		while self.eat(b'/') {
+			let combinator = if self.eat(b'/') {
+				Combinator::Descendant
+			} else {
+				Combinator::Child
+			};
+
+			let mut step = Step::new(combinator);
+
+			if !self.eat(b'*') {
+				step.tag = Some(self.name()?);
+			}
+
+			while self.eat(b'[') {
+				step.conditions.push(self.condition(Some(b'@'))?);
+			}
+
+			steps.push(step);
+		}
+
+		self.skip_whitespace();
+
+		if self.peek().is_none() {
+			Some(steps)
+		} else {
+			None
+		}
+	}
+}
+
+
+/// Whether the last element of the path matches the steps. The path is the chain of
+/// elements from the queried element, which is the first one.
+fn matches(steps: &[Step], path: &[Value]) -> bool {
+	let ((step, steps), (element, ancestors)) = match (steps.split_last(), path.split_last()) {
+		(Some(steps), Some(path)) => (steps, path),
+		_ => return false,
+	};
+
+	if !step.matches(element) {
+		return false;
+	}
+
+	match (step.combinator, steps.is_empty()) {
+		// The first step is relative to the queried element's parent.
+		(Combinator::Child, true) => ancestors.is_empty(),
+		(Combinator::Descendant, true) => true,
+
+		(Combinator::Child, false) => matches(steps, ancestors),
+		(Combinator::Descendant, false) => (1 ..= ancestors.len())
+			.rev()
+			.any(|len| matches(steps, &ancestors[.. len])),
+	}
+}
+
+
+/// Collect the elements matching the steps, in document order.
+fn select(element: &Value, steps: &[Step], path: &mut Vec<Value>, selected: &mut Vec<Value>) {
+	path.push(element.copy());
+
+	if matches(steps, path) {
+		selected.push(element.copy());
+	}
+
+	if let Value::Array(ref children) = field(element, "children") {
+		for child in children.borrow().iter() {
+			if let Value::Dict(_) = child {
+				select(child, steps, path, selected);
+			}
+		}
+	}
+
+	path.pop();
+}
+
+
+/// Select the elements matching a selector in an element and it's descendants, in
+/// document order. Selectors are either a CSS subset, such as `project > version`,
+/// `a.external[href]` or `#main`, with descendant and child combinators, or an XPath
+/// subset, such as `//host/address[@addrtype='ipv4']`, with child and descendant steps
+/// and attribute predicates. The queried element is matched as a child of an implicit
+/// parent, so that it may be the first step of the selector.
+#[derive(Trace, Finalize)]
+struct Select;
+
+impl NativeFun for Select {
+	fn name(&self) -> &'static str { "std.xml.select" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ element @ Value::Dict(_), selector ] => {
+				let steps = Parser::new(util::text(selector, context.pos.copy())?)
+					.parse()
+					.ok_or_else(|| Panic::value_error(selector.copy(), "valid selector", context.pos.copy()))?;
+
+				let mut selected = Vec::new();
+				select(element, &steps, &mut Vec::new(), &mut selected);
+
+				Ok(selected.into())
+			}
+
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
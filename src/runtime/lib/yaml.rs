@@ -0,0 +1,82 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	Dict,
+	DictMap,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+	CallContext,
+};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Dump) }
+
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl Parse {
+	/// Build the error for malformed input, with it's position in the context, if any.
+	fn error(error: serde_yaml::Error) -> Value {
+		thread_local! {
+			pub static LINE: Value = "line".into();
+			pub static COLUMN: Value = "column".into();
+		}
+
+		let context = match error.location() {
+			Some(location) => {
+				let mut context = DictMap::default();
+				LINE.with(|line| context.insert(line.copy(), Value::Int(location.line() as i64)));
+				COLUMN.with(|column| context.insert(column.copy(), Value::Int(location.column() as i64)));
+				Dict::new(context).into()
+			}
+
+			None => Value::Nil,
+		};
+
+		let description = format!("invalid yaml: {}", error);
+
+		Error::new(description.into(), context).into()
+	}
+}
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.yaml.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				serde_yaml::from_slice(string.as_bytes()).unwrap_or_else(Self::error)
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+#[derive(Trace, Finalize)]
+struct Dump;
+
+impl NativeFun for Dump {
+	fn name(&self) -> &'static str { "std.yaml.dump" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => serde_yaml::to_string(value)
+				.map(Into::into)
+				.map_err(
+					|_| Panic::value_error(
+						value.copy(),
+						"nil, bool, byte, int, float, string, array or dict",
+						context.pos.copy()
+					)
+				),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -2,45 +2,66 @@
 #![allow(clippy::mutable_key_type)]
 
 
+mod budget;
 mod command;
-mod flow;
+mod coverage;
+mod finalize;
+mod heap;
 mod lib;
 mod mem;
 mod panic;
+mod pattern;
+mod policy;
+mod profile;
 mod source;
+mod trace;
 pub mod value;
+mod vm;
 #[cfg(test)]
 mod tests;
 
-use std::{collections::HashMap, ops::Deref};
+use std::{collections::HashMap, convert::TryFrom, ops::Deref, rc::Rc, sync::Arc};
 
 use crate::symbol::{self, Symbol};
 use super::semantic::program;
 use value::{
 	keys,
 	Array,
+	Buffer,
 	CallContext,
 	Dict,
+	DictMap,
 	Error,
 	Float,
 	Function,
 	HushFun,
+	HushFunData,
 	RustFun,
 	NativeFun,
 	Str,
 	Value,
 	Type,
 };
+pub use budget::{Budget, Limit};
+pub use command::env;
+pub use coverage::Coverage;
 pub use panic::Panic;
+pub use policy::{Policy, Permission};
+pub use profile::Profiler;
 pub use source::SourcePos;
-use flow::Flow;
 use mem::Stack;
+use vm::Chunk;
+use command::Options;
 
 
 /// A runtime instance to execute Hush programs.
 #[derive(Debug)]
 pub struct Runtime {
 	stack: Stack,
+	/// Operands of the bytecode virtual machine.
+	operands: Vec<Value>,
+	/// Compiled function bodies, by their address.
+	chunks: HashMap<*const program::Block, Rc<Chunk>>,
 	/// Function arguments.
 	arguments: Vec<Value>,
 	std: Value,
@@ -48,6 +69,20 @@ pub struct Runtime {
 	modules: HashMap<Symbol, Value>,
 	/// Command line arguments.
 	args: Value,
+	/// Command block execution options.
+	options: Options,
+	/// Handler for programs that are not found in command blocks.
+	command_not_found: Option<Function>,
+	/// Hook called before spawning each command.
+	before_command: Option<Function>,
+	/// Hook called after each spawned command finishes.
+	after_command: Option<Function>,
+	/// The resources used by the script, checked against it's budget.
+	meter: budget::Meter,
+	/// The execution counts of statements, if enabled.
+	coverage: Option<Coverage>,
+	/// The profiler of the call stack, if enabled.
+	profiler: Option<Profiler>,
 }
 
 
@@ -66,11 +101,20 @@ impl Runtime {
 
 		Self {
 			stack: Stack::default(),
+			operands: Vec::new(),
+			chunks: HashMap::new(),
 			arguments: Vec::new(),
 			interner,
 			std: lib::new(),
 			modules: HashMap::new(),
 			args: args.into(),
+			options: Options::default(),
+			command_not_found: None,
+			before_command: None,
+			after_command: None,
+			meter: budget::Meter::default(),
+			coverage: None,
+			profiler: None,
 		}
 	}
 
@@ -87,485 +131,83 @@ impl Runtime {
 	}
 
 
-	/// Execute the given program.
-	pub fn eval(&mut self, program: &'static program::Program) -> Result<Value, Panic> {
-		// Global variables.
-		let slots: mem::SlotIx = program.root_slots.into();
-
-		let initial_args_len = self.arguments.len();
-		let initial_stack_len = self.stack.len();
-
-		self.stack
-			.extend(slots.copy())
-			.map_err(|_| Panic::stack_overflow(SourcePos::file(program.source)))?;
-
-		// Stdlib.
-		self.stack.store(mem::SlotIx(0), self.std.copy());
-
-		// Execute the program.
-		let value = match self.eval_block(&program.statements)? {
-			Flow::Regular(value) => value,
-			flow => panic!("invalid flow in root state: {:#?}", flow)
-		};
-
-		// Drop global variables.
-		self.stack.shrink(slots);
-
-		debug_assert_eq!(self.stack.len(), initial_stack_len);
-		debug_assert_eq!(self.arguments.len(), initial_args_len);
-
-		Ok(value)
+	/// Get a mutable reference to the command block execution options.
+	pub fn options_mut(&mut self) -> &mut Options {
+		&mut self.options
 	}
 
 
-	/// Execute a block, returning the value of the last statement, or the corresponding
-	/// control flow if returns or breaks are reached.
-	fn eval_block(&mut self, block: &'static program::Block) -> Result<Flow, Panic> {
-		self.eval_tail_block(block, |_| ())
+	/// Restrict the resources the script may access.
+	pub fn set_policy(&mut self, policy: Policy) {
+		self.options.policy = Arc::new(policy);
 	}
 
 
-	/// Execute a block, returning the value of the last statement, or the corresponding
-	/// control flow if returns or breaks are reached.
-	fn eval_tail_block<F>(
-		&mut self,
-		block: &'static program::Block,
-		tail_call: F,
-	) -> Result<Flow, Panic>
-	where
-		F: FnOnce(&mut Self),
-	{
-		let mut iter = block.0.iter();
-
-		let last_statement = iter.next_back();
-
-		for statement in iter {
-			match self.eval_statement(statement)? {
-				Flow::Regular(_) => (),
-				flow => return Ok(flow),
-			}
-		}
-
-		let flow =
-			if let Some(last_statement) = last_statement {
-				self.eval_tail_statement(last_statement, tail_call)?
-			} else {
-				Flow::Regular(Value::default())
-			};
-
-		Ok(flow)
+	/// Limit the resources used by the script. The time limit is measured from this call.
+	pub fn set_budget(&mut self, budget: Budget) {
+		self.meter = budget::Meter::new(budget);
 	}
 
 
-	/// Execute a literal.
-	/// For trivial types, this basically instatiates a corresponding value.
-	/// For compound types, sub-expressions are evaluated.
-	/// For function types, closed-over variables are captured, if any.
-	/// For identifiers, their string is resolved.
-	fn eval_literal(
-		&mut self,
-		literal: &'static program::Literal,
-		pos: program::SourcePos
-	) -> Result<Flow, Panic> {
-		match literal {
-			// Nil.
-			program::Literal::Nil => Ok(Flow::Regular(Value::Nil)),
-
-			// Bool.
-			program::Literal::Bool(b) => Ok(Flow::Regular((*b).into())),
-
-			// Int.
-			program::Literal::Int(int) => Ok(Flow::Regular((*int).into())),
-
-			// Float.
-			program::Literal::Float(float) => Ok(Flow::Regular((*float).into())),
-
-			// Byte.
-			program::Literal::Byte(byte) => Ok(Flow::Regular((*byte).into())),
-
-			// String.
-			program::Literal::String(string) => Ok(Flow::Regular(string.as_ref().into())),
-
-			// Array.
-			program::Literal::Array(exprs) => {
-				let mut array = Vec::new();
-
-				for expr in exprs.iter() {
-					match self.eval_expr(expr)?.0 {
-						Flow::Regular(value) => array.push(value),
-						flow => return Ok(flow),
-					}
-				}
-
-				Ok(Flow::Regular(Array::new(array).into()))
-			},
-
-			// Dict.
-			program::Literal::Dict(exprs) => {
-				let mut dict = HashMap::new();
-
-				for (symbol, expr) in exprs.iter() {
-					let key: Value = self.interner
-						.resolve(*symbol)
-						.expect("unresolved symbol")
-						.into();
-
-					match self.eval_expr(expr)?.0 {
-						Flow::Regular(value) => dict.insert(key, value),
-						flow => return Ok(flow),
-					};
-				}
-
-				Ok(Flow::Regular(Dict::new(dict).into()))
-			}
-
-			// Function.
-			program::Literal::Function { params, frame_info, body } => {
-				let context = frame_info
-					.captures
-					.iter()
-					.map(
-						|capture| (
-							self.stack.capture(capture.from.into()),
-							capture.to.into(),
-						)
-					)
-					.collect();
-
-				Ok(
-					Flow::Regular(
-						HushFun::new(*params, frame_info, body, context, pos.into()).into()
-					)
-				)
-			},
-
-			// Identifier.
-			program::Literal::Identifier(symbol) => Ok(
-				Flow::Regular(
-					self.interner
-						.resolve(*symbol)
-						.expect("unresolved symbol")
-						.into()
-				)
-			),
-		}
+	/// Count the execution of statements. Only programs and functions which are compiled
+	/// afterwards are counted, so this should be enabled before executing.
+	pub fn enable_coverage(&mut self) {
+		self.coverage.get_or_insert_with(Coverage::default);
 	}
 
 
-	/// Execute an expression.
-	/// Returns a triple of (flow, expr pos, self value) or panic.
-	fn eval_expr(
-		&mut self,
-		expr: &'static program::Expr
-	) -> Result<(Flow, SourcePos, Value), Panic> {
-		self.eval_tail_expr(expr, |_| ())
+	/// Get the execution counts of statements, if enabled.
+	pub fn coverage(&self) -> Option<&Coverage> {
+		self.coverage.as_ref()
 	}
 
 
-	/// Execute an expression.
-	/// Returns a triple of (flow, expr pos, self value) or panic.
-	fn eval_tail_expr<F>(
-		&mut self,
-		expr: &'static program::Expr,
-		tail_call: F,
-	) -> Result<(Flow, SourcePos, Value), Panic>
-	where
-		F: FnOnce(&mut Self),
-	{
-		macro_rules! regular_expr {
-			($expr: expr, $pos: expr) => {
-				match self.eval_expr($expr)? {
-					(Flow::Regular(value), pos, _) => (value, pos),
-					(flow, _, _) => return Ok((flow, $pos, Value::default()))
-				}
-			}
-		}
-
-		match expr {
-			// Identifier.
-			program::Expr::Identifier { slot_ix, pos } => {
-				let value = self.stack.fetch(slot_ix.into());
-				Ok((Flow::Regular(value), pos.into(), Value::default()))
-			},
-
-			// Literal.
-			program::Expr::Literal { literal, pos } => {
-				let flow = self.eval_literal(literal, *pos)?;
-				Ok((flow, pos.into(), Value::default()))
-			},
-
-			// UnaryOp.
-			program::Expr::UnaryOp { op, operand, pos } => {
-				let pos = pos.into();
-
-				let flow = self.unary_op(op, operand)?;
-
-				Ok((flow, pos, Value::default()))
-			}
-
-			// BinaryOp.
-			program::Expr::BinaryOp { left, op, right, pos } => {
-				let pos = pos.into();
-
-				let flow = self.binary_op(left, op, right, &pos)?;
-
-				Ok((flow, pos, Value::default()))
-			}
-
-			// If.
-			program::Expr::If { condition, then, otherwise, pos } => {
-				let pos = pos.into();
-
-				let condition = match self.eval_expr(condition)? {
-					(Flow::Regular(Value::Bool(b)), _, _) => b,
-					(Flow::Regular(value), pos, _) => return Err(Panic::invalid_condition(value, pos)),
-					(flow, _, _) => return Ok((flow, pos, Value::default()))
-				};
-
-				let value = if condition {
-					self.eval_block(then)
-				} else {
-					self.eval_block(otherwise)
-				}?;
-
-				Ok((value, pos, Value::default()))
-			}
-
-			// Access.
-			program::Expr::Access { object, field, pos } => {
-				let pos = pos.into();
-
-				let (obj, obj_pos) = regular_expr!(object, pos);
-				let (field, field_pos) = regular_expr!(field, pos);
-
-				let value = match (&obj, field) {
-					(Value::Dict(ref dict), field) => dict
-						.get(&field)
-						.map_err(|_| Panic::index_out_of_bounds(field, field_pos)),
-
-					(Value::Array(ref array), Value::Int(ix)) => array
-						.index(ix)
-						.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
-
-					(Value::Array(_), field) => Err(Panic::type_error(field, "int", field_pos)),
-
-					(Value::String(ref string), Value::Int(ix)) => string
-						.index(ix)
-						.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
-
-					(Value::String(_), field) => Err(Panic::type_error(field, "int", field_pos)),
-
-					(Value::Error(ref error), field) => error
-						.get(&field)
-						.map_err(|_| Panic::index_out_of_bounds(field, field_pos)),
-
-					(_, _) => return Err(Panic::type_error(obj, "string, array, dict or error", obj_pos)),
-				}?;
-
-				Ok((Flow::Regular(value), pos, obj))
-			}
-
-			// Call.
-			program::Expr::Call { function, args, pos } => {
-				let pos = pos.into();
-
-				// Eval function.
-				let (function, obj) = match self.eval_expr(function)? {
-					(Flow::Regular(Value::Function(ref fun)), _, obj) => (fun.copy(), obj),
-					(Flow::Regular(value), pos, _) => return Err(Panic::invalid_call(value, pos)),
-					(flow, _, _) => return Ok((flow, pos, Value::default())),
-				};
-
-				// Eval arguments.
-				// While evaluating arguments, we may need to call other functions, so we must
-				// keep track of when our arguments start.
-				let args_start = self.arguments.len();
-
-				for expr in args.iter() {
-					match self.eval_expr(expr)? {
-						(Flow::Regular(value), _, _) => self.arguments.push(value),
-						(flow, _, _) => {
-							self.arguments.truncate(args_start);
-							return Ok((flow, pos, Value::default()));
-						}
-					}
-				}
-
-				tail_call(self);
-
-				let value = self.call(obj, &function, args_start, pos.copy())?;
-
-				Ok((Flow::Regular(value), pos, Value::default()))
-			}
-
-			// CommandBlock.
-			program::Expr::CommandBlock { block, pos } => {
-				let value = self.eval_command_block(block, pos.into())?;
-				Ok((Flow::Regular(value), pos.into(), Value::default()))
-			}
-		}
+	/// Sample the call stack periodically, to find where time is spent.
+	pub fn enable_profiling(&mut self) {
+		self.profiler.get_or_insert_with(Profiler::new);
 	}
 
 
-	/// Execute a statement.
-	fn eval_statement(&mut self, statement: &'static program::Statement) -> Result<Flow, Panic> {
-		self.eval_tail_statement(statement, |_| ())
+	/// Get the profiler, if enabled.
+	pub fn profiler(&self) -> Option<&Profiler> {
+		self.profiler.as_ref()
 	}
 
 
-	/// Execute a statement.
-	fn eval_tail_statement<F>(
-		&mut self,
-		statement: &'static program::Statement,
-		tail_call: F,
-	) -> Result<Flow, Panic>
-	where
-		F: FnOnce(&mut Self),
-	{
-		match statement {
-			// Assign.
-			program::Statement::Assign { left, right } => {
-				let value = match self.eval_expr(right)?.0 {
-					Flow::Regular(value) => value,
-					flow => return Ok(flow),
-				};
-
-				match left {
-					program::Lvalue::Identifier { slot_ix, .. } => self.stack.store(slot_ix.into(), value),
-
-					program::Lvalue::Access { object, field, pos } => {
-						let (obj, obj_pos) = match self.eval_expr(object)? {
-							(Flow::Regular(obj), pos, _) => (obj, pos),
-							(flow, _, _) => return Ok(flow),
-						};
-
-						let (field, field_pos) = match self.eval_expr(field)? {
-							(Flow::Regular(field), pos, _) => (field, pos),
-							(flow, _, _) => return Ok(flow),
-						};
-
-						match (obj, field) {
-							// Note that strings are immutable.
-
-							(Value::Dict(ref dict), field) => dict.insert(field, value),
-
-							(Value::Array(ref array), Value::Int(ix)) if ix >= array.len() => return Err(
-								Panic::index_out_of_bounds(Value::Int(ix), field_pos)
-							),
-
-							(Value::Array(ref array), Value::Int(ix)) => array
-								.deref()
-								.set(ix, value)
-								.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), pos.into()))?,
-
-							(Value::Array(_), field) => return Err(Panic::type_error(field, "int", field_pos)),
-
-							(Value::Error(_), field) => return Err(Panic::assign_to_readonly_field(field, field_pos)),
-
-							(obj, _) => return Err(Panic::type_error(obj, "array, dict or error", obj_pos)),
-						};
-					}
-				}
-
-				Ok(Flow::Regular(Value::default()))
-			}
-
-			// Return.
-			program::Statement::Return { expr } => {
-				match self.eval_tail_expr(expr, tail_call)?.0 {
-					Flow::Regular(value) => Ok(Flow::Return(value)),
-					flow => Ok(flow),
-				}
-			}
-
-			// Break.
-			program::Statement::Break => Ok(Flow::Break),
+	/// Execute the given program.
+	pub fn eval(&mut self, program: &'static program::Program) -> Result<Value, Panic> {
+		// Global variables.
+		let slots: mem::SlotIx = program.root_slots.into();
 
-			// While.
-			program::Statement::While { condition, block } => {
-				loop {
-					let condition = match self.eval_expr(condition)? {
-						(Flow::Regular(Value::Bool(b)), _, _) => b,
-						(Flow::Regular(value), pos, _) => return Err(Panic::invalid_condition(value, pos)),
-						(flow, _, _) => return Ok(flow)
-					};
+		let initial_args_len = self.arguments.len();
+		let initial_stack_len = self.stack.len();
 
-					if !condition {
-						break;
-					}
+		self.stack
+			.extend(slots.copy())
+			.map_err(|_| Panic::stack_overflow(SourcePos::file(program.source)))?;
 
-					match self.eval_block(block)? {
-						Flow::Regular(_) => (),
-						flow @ Flow::Return(_) => return Ok(flow),
-						Flow::Break => break,
-					}
-				}
+		// Stdlib.
+		self.stack.store(mem::SlotIx(0), self.std.copy());
 
-				Ok(Flow::Regular(Value::default()))
-			}
+		if let Some(coverage) = &mut self.coverage {
+			coverage.register(program);
+		}
 
-			// For.
-			program::Statement::For { slot_ix, expr, block } => {
-				let slot_ix: mem::SlotIx = slot_ix.into();
+		// Execute the program.
+		let chunk = Chunk::program(&program.statements, self.coverage.is_some(), &self.interner);
+		let result = self.run(&chunk, |_| ());
 
-				let (iter, pos) = match self.eval_expr(expr)? {
-					(Flow::Regular(Value::Function(ref iter)), pos, _) => (iter.copy(), pos),
-					(Flow::Regular(value), pos, _) => return Err(Panic::type_error(value, "function", pos)),
-					(flow, _, _) => return Ok(flow)
-				};
+		// Drop global variables.
+		self.stack.shrink(slots);
 
-				loop {
-					// While evaluating arguments, we may need to call other functions, so we must
-					// keep track of when our arguments start.
-					let args_start = self.arguments.len();
-					match self.call(Value::default(), &iter, args_start, pos.copy())? {
-						Value::Dict(ref dict) => {
-							let finished = keys::FINISHED.with(
-								|finished| dict
-									.get(finished)
-									.map_err(|_| Panic::index_out_of_bounds(finished.copy(), pos.copy()))
-							)?;
-
-							match finished {
-								Value::Bool(false) => {
-									let value = keys::VALUE.with(
-										|value| dict
-											.get(value)
-											.map_err(|_| Panic::index_out_of_bounds(value.copy(), pos.copy()))
-									)?;
-
-									self.stack.store(slot_ix.copy(), value);
-								},
-
-								Value::Bool(true) => break,
-
-								other => return Err(Panic::type_error(other, "bool", pos))
-							}
-
-							Value::Nil
-						},
-
-						other => return Err(Panic::type_error(other, "dict", pos)),
-					};
-
-					match self.eval_block(block)? {
-						Flow::Regular(_) => (),
-						flow @ Flow::Return(_) => return Ok(flow),
-						Flow::Break => break,
-					}
-				}
+		let value = result?;
 
-				Ok(Flow::Regular(Value::default()))
-			}
+		debug_assert_eq!(self.stack.len(), initial_stack_len);
+		debug_assert_eq!(self.arguments.len(), initial_args_len);
 
-			// Expr.
-			program::Statement::Expr(expr) => self
-				.eval_tail_expr(expr, tail_call)
-				.map(|(flow, _, _)| flow)
-		}
+		Ok(value)
 	}
 
-
 	/// Call the given function.
 	/// The arguments are expected to be on the self.arguments vector.
 	fn call(
@@ -577,19 +219,30 @@ impl Runtime {
 	) -> Result<Value, Panic> {
 
 		let value = match function {
-			Function::Hush(HushFun { params, frame_info, body, context, .. }) => {
-				let args_count = (self.arguments.len() - args_start) as u32;
+			Function::Hush(fun) => {
+				let HushFunData { name, params, frame_info, body, context, .. } = fun.deref();
 
-				// Make sure we clean the arguments vector even when early returning.
-				let arguments = self.arguments.drain(args_start..);
+				/// The minimum amount of native stack, in bytes, to call a function. Calls nest
+				/// in the native stack, which would abort the process when exhausted.
+				const NATIVE_STACK_RED_ZONE: usize = 1024 * 1024;
+
+				let args_count = (self.arguments.len() - args_start) as u32;
 
 				if args_count != *params {
+					self.arguments.truncate(args_start);
 					return Err(Panic::invalid_args(args_count, *params, pos));
 				}
 
+				let native_stack_exhausted = stacker::remaining_stack()
+					.map_or(false, |remaining| remaining < NATIVE_STACK_RED_ZONE);
+
 				let slots: mem::SlotIx = frame_info.slots.into();
-				self.stack.extend(slots.copy())
-					.map_err(|_| Panic::stack_overflow(pos))?;
+				if native_stack_exhausted || self.stack.extend(slots.copy()).is_err() {
+					self.arguments.truncate(args_start);
+					return Err(self.stack_overflow(pos));
+				}
+
+				let arguments = self.arguments.drain(args_start..);
 
 				// Place arguments
 				for (ix, value) in arguments.enumerate() {
@@ -606,10 +259,14 @@ impl Runtime {
 					self.stack.store(slot_ix.into(), obj);
 				}
 
+				let chunk = self.function_chunk(body);
+
+				trace::enter(trace::Callee::Hush(*name), pos.copy());
+
 				let mut shrinked = false;
 
-				let result = self.eval_tail_block(
-					body,
+				let result = self.run(
+					&chunk,
 					|runtime| { // Shrink stack before tail calling.
 						runtime.stack.shrink(slots.copy());
 						shrinked = true;
@@ -621,16 +278,17 @@ impl Runtime {
 					self.stack.shrink(slots);
 				}
 
-				let flow = result?;
+				// Attach the calls before leaving, so that the innermost one is included.
+				let result = result.map_err(Panic::traced);
 
-				match flow {
-					Flow::Regular(value) => value,
-					Flow::Return(value) => value,
-					Flow::Break => panic!("break outside loop"),
-				}
+				trace::leave();
+
+				result?
 			}
 
 			Function::Rust(fun) => {
+				trace::enter(trace::Callee::Rust(fun.name()), pos.copy());
+
 				let result = fun.call(
 					CallContext {
 						runtime: self,
@@ -640,6 +298,16 @@ impl Runtime {
 					}
 				);
 
+				// Native functions are sampled before leaving the call stack, as they execute no
+				// instructions.
+				if let Some(profiler) = &mut self.profiler {
+					profiler.poll();
+				}
+
+				let result = result.map_err(Panic::traced);
+
+				trace::leave();
+
 				self.arguments.truncate(args_start);
 
 				result?
@@ -650,114 +318,176 @@ impl Runtime {
 	}
 
 
-	/// Execute a unary operator expression.
+	/// Build a stack overflow panic, with the innermost calls.
+	fn stack_overflow(&self, pos: SourcePos) -> Panic {
+		Panic::stack_overflow_with(pos, trace::capture(), trace::depth())
+	}
+
+
+	/// Apply an unary operator to a value. The try operator is handled by the virtual machine.
 	fn unary_op(
-		&mut self,
-		op: &'static program::UnaryOp,
-		operand: &'static program::Expr,
-	) -> Result<Flow, Panic> {
+		op: &program::UnaryOp,
+		value: Value,
+		operand_pos: SourcePos,
+	) -> Result<Value, Panic> {
 		use program::UnaryOp::{Minus, Not, Try};
 
-		let (value, operand_pos) = match self.eval_expr(operand)? {
-			(Flow::Regular(value), pos, _) => (value, pos),
-			(flow, _, _) => return Ok(flow),
-		};
-
 		match (op, value) {
-			(Minus, Value::Float(ref f)) => Ok(Flow::Regular((-f).into())),
-			(Minus, Value::Int(i)) => Ok(Flow::Regular((-i).into())),
+			(Minus, Value::Float(ref f)) => Ok((-f).into()),
+			(Minus, Value::Int(i)) => Ok((-i).into()),
 			(Minus, value) => Err(Panic::type_error(value, "int or float", operand_pos)),
 
-			(Not, Value::Bool(b)) => Ok(Flow::Regular((!b).into())),
+			(Not, Value::Bool(b)) => Ok((!b).into()),
 			(Not, value) => Err(Panic::type_error(value, "bool", operand_pos)),
 
-			(Try, value @ Value::Error(_)) => Ok(Flow::Return(value)),
-			(Try, value) => Ok(Flow::Regular(value)),
+			(Try, _) => unreachable!("try operator is not a value operator"),
 		}
 	}
 
 
-	/// Execute a binary operator expression.
+	/// Apply a binary operator to values. Logical operators are short-circuited by the
+	/// virtual machine.
 	fn binary_op(
-		&mut self,
-		left: &'static program::Expr,
 		op: &'static program::BinaryOp,
-		right: &'static program::Expr,
-		pos: &SourcePos,
-	) -> Result<Flow, Panic> {
+		(left, left_pos): (Value, SourcePos),
+		(right, right_pos): (Value, SourcePos),
+		pos: SourcePos,
+	) -> Result<Value, Panic> {
 		use program::BinaryOp::*;
 
-		macro_rules! regular_expr {
-			($expr: expr) => {
-				match self.eval_expr($expr)? {
-					(Flow::Regular(value), pos, _) => (value, pos),
-					(flow, _, _) => return Ok(flow)
+		let value = match op {
+			And | Or => unreachable!("logical operators are short-circuited"),
+
+			Plus | Minus | Times | Div | Mod => Self::arithmetic_op(left, left_pos, op, &pos, right, right_pos)?,
+
+			Greater | GreaterEquals | Lower | LowerEquals => Self::ord_op(left, left_pos, op, right, right_pos)?,
+
+			Equals => Value::Bool(left == right),
+			NotEquals => Value::Bool(left != right),
+
+			Concat => match (left, right) {
+				(Value::String(ref str1), Value::String(ref str2)) => {
+					let string =
+						[
+							AsRef::<[u8]>::as_ref(str1),
+							AsRef::<[u8]>::as_ref(str2),
+						]
+						.concat::<u8>();
+
+					string.into_boxed_slice().into()
 				}
-			}
-		}
 
-		let (left, left_pos) = regular_expr!(left);
+				(Value::String(_), right) => return Err(Panic::type_error(right, "string", right_pos)),
 
-		let value = match op {
-			And | Or => match (left, op) {
-				(Value::Bool(false), And) => Value::Bool(false),
-				(Value::Bool(true), Or) => Value::Bool(true),
-
-				(Value::Bool(_), _) => {
-					let (right, right_pos) = regular_expr!(right);
-					match right {
-						right @ Value::Bool(_) => right,
-						right => return Err(Panic::type_error(right, "bool", right_pos)),
-					}
+				(Value::Buffer(ref buf1), Value::Buffer(ref buf2)) => {
+					let buffer = [ buf1.borrow().as_slice(), buf2.borrow().as_slice() ].concat();
+					Buffer::new(buffer).into()
 				}
 
-				(left, _) => return Err(Panic::type_error(left, "bool", left_pos)),
+				(Value::Buffer(_), right) => return Err(Panic::type_error(right, "buffer", right_pos)),
+				(left, _) => return Err(Panic::type_error(left, "string or buffer", left_pos)),
 			}
+		};
 
-			Plus | Minus | Times | Div | Mod => {
-				let (right, right_pos) = regular_expr!(right);
+		Ok(value)
+	}
 
-				self.arithmetic_op(left, left_pos, op, pos, right, right_pos)?
-			}
 
-			Greater | GreaterEquals | Lower | LowerEquals => {
-				let (right, right_pos) = regular_expr!(right);
+	/// Get the value of a field of an object.
+	fn access(
+		&self,
+		(obj, obj_pos): (&Value, SourcePos),
+		(field, field_pos): (Value, SourcePos),
+	) -> Result<Value, Panic> {
+		match (obj, field) {
+			(Value::Dict(ref dict), field) => dict
+				.get(&field)
+				.map_err(|_| Panic::index_out_of_bounds(field, field_pos)),
 
-				self.ord_op(left, left_pos, op, right, right_pos)?
-			}
+			(Value::Array(ref array), Value::Int(ix)) => array
+				.index(ix)
+				.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
 
-			Equals => Value::Bool(left == regular_expr!(right).0),
-			NotEquals => Value::Bool(left != regular_expr!(right).0),
+			(Value::Array(_), field) => Err(Panic::type_error(field, "int", field_pos)),
 
-			Concat => {
-				let (right, right_pos) = regular_expr!(right);
+			(Value::String(ref string), Value::Int(ix)) => string
+				.index(ix)
+				.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
 
-				match (left, right) {
-					(Value::String(ref str1), Value::String(ref str2)) => {
-						let string =
-							[
-								AsRef::<[u8]>::as_ref(str1),
-								AsRef::<[u8]>::as_ref(str2),
-							]
-							.concat::<u8>();
-
-						string.into_boxed_slice().into()
-					}
+			(Value::String(_), field) => Err(Panic::type_error(field, "int", field_pos)),
 
-					(Value::String(_), right) => return Err(Panic::type_error(right, "string", right_pos)),
-					(left, _) => return Err(Panic::type_error(left, "string", left_pos)),
-				}
+			(Value::Buffer(ref buffer), Value::Int(ix)) => buffer
+				.index(ix)
+				.map(|byte| Value::Int(byte.into()))
+				.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos)),
+
+			(Value::Buffer(_), field) => Err(Panic::type_error(field, "int", field_pos)),
+
+			(Value::Error(ref error), field) => error
+				.get(&field, &self.interner)
+				.map_err(|_| Panic::index_out_of_bounds(field, field_pos)),
+
+			// Callable modules of the stdlib, such as std.env.
+			(Value::Function(Function::Rust(ref fun)), field) => match fun.downcast_ref::<lib::Module>() {
+				Some(module) => module
+					.get(&field)
+					.ok_or_else(|| Panic::index_out_of_bounds(field, field_pos)),
+				None => Err(Panic::type_error(obj.copy(), "string, array, dict, buffer or error", obj_pos)),
+			},
+
+			(obj, _) => Err(Panic::type_error(obj.copy(), "string, array, dict, buffer or error", obj_pos)),
+		}
+	}
+
+
+	/// Assign a value to a field of an object. Note that strings are immutable.
+	fn assign(
+		&mut self,
+		(obj, obj_pos): (Value, SourcePos),
+		(field, field_pos): (Value, SourcePos),
+		value: Value,
+		pos: SourcePos,
+	) -> Result<(), Panic> {
+		match (obj, field) {
+			(Value::Dict(ref dict), field) => dict.insert(field, value),
+
+			(Value::Array(ref array), Value::Int(ix)) if ix >= array.len() => return Err(
+				Panic::index_out_of_bounds(Value::Int(ix), field_pos)
+			),
+
+			(Value::Array(ref array), Value::Int(ix)) => array
+				.deref()
+				.set(ix, value)
+				.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), pos))?,
+
+			(Value::Array(_), field) => return Err(Panic::type_error(field, "int", field_pos)),
+
+			(Value::Buffer(ref buffer), Value::Int(ix)) => {
+				let byte = match value {
+					Value::Int(int) => u8::try_from(int)
+						.map_err(|_| Panic::value_error(Value::Int(int), "integer between 0 and 255", pos.copy()))?,
+					Value::Byte(byte) => byte,
+					other => return Err(Panic::type_error(other, "int or char", pos)),
+				};
+
+				buffer
+					.set(ix, byte)
+					.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), field_pos))?
 			}
+
+			(Value::Buffer(_), field) => return Err(Panic::type_error(field, "int", field_pos)),
+
+			(Value::Error(_), field) => return Err(Panic::assign_to_readonly_field(field, field_pos)),
+
+			(obj, _) => return Err(Panic::type_error(obj, "array, dict, buffer or error", obj_pos)),
 		};
 
-		Ok(Flow::Regular(value))
+		Ok(())
 	}
 
-
 	/// Execute a binary arithmetic operator expression.
 	/// Panics if op is not arithmetic (+, -, *, /, %).
 	fn arithmetic_op(
-		&mut self,
 		left: Value,
 		left_pos: SourcePos,
 		op: &'static program::BinaryOp,
@@ -834,7 +564,6 @@ impl Runtime {
 	/// Execute a binary ord operator expression.
 	/// Panics if op is not ord (<, <=, >, >=).
 	fn ord_op(
-		&mut self,
 		left: Value,
 		left_pos: SourcePos,
 		op: &'static program::BinaryOp,
@@ -850,6 +579,8 @@ impl Runtime {
 					| (left @ Value::Float(_), right @ Value::Float(_))
 					| (left @ Value::Byte(_), right @ Value::Byte(_))
 					| (left @ Value::String(_), right @ Value::String(_))
+					| (left @ Value::Array(_), right @ Value::Array(_))
+					| (left @ Value::Dict(_), right @ Value::Dict(_))
 					=> Ok(
 						Value::Bool(
 							order(left.cmp(&right))
@@ -860,9 +591,11 @@ impl Runtime {
 				(Value::Float(_), right) => Err(Panic::type_error(right, "float", right_pos)),
 				(Value::Byte(_), right) => Err(Panic::type_error(right, "char", right_pos)),
 				(Value::String(_), right) => Err(Panic::type_error(right, "string", right_pos)),
+				(Value::Array(_), right) => Err(Panic::type_error(right, "array", right_pos)),
+				(Value::Dict(_), right) => Err(Panic::type_error(right, "dict", right_pos)),
 
 				// ? + ?
-				(left, _) => Err(Panic::type_error(left, "int, float, byte or string", left_pos)),
+				(left, _) => Err(Panic::type_error(left, "int, float, byte, string, array or dict", left_pos)),
 			}
 		};
 
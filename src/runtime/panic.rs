@@ -1,4 +1,4 @@
-use std::{borrow::Cow, io, ffi::OsString};
+use std::{borrow::Cow, io, ffi::OsString, ops::RangeInclusive};
 
 use crate::{
 	fmt::{self, Display},
@@ -6,14 +6,20 @@ use crate::{
 	term::color,
 	symbol::{self, Symbol},
 };
-use super::{Value, SourcePos};
+use super::{trace, Limit, Value, SourcePos};
 
 
 /// A panic is an irrecoverable error in Hush.
 #[derive(Debug)]
 pub enum Panic {
 	/// Attempt to increase the stack past it's maximum size.
-	StackOverflow { pos: SourcePos },
+	StackOverflow {
+		pos: SourcePos,
+		/// The innermost calls, if the overflow was caused by nesting function calls.
+		traceback: Box<[trace::Frame]>,
+		/// The amount of nested calls.
+		depth: usize,
+	},
 	/// Integer overflow.
 	IntegerOverflow { pos: SourcePos },
 	/// Integer division by zero.
@@ -33,7 +39,7 @@ pub enum Panic {
 	/// Ammount of supplied arguments in function call is different than expected.
 	InvalidArgs {
 		supplied: u32,
-		expected: u32,
+		expected: RangeInclusive<u32>,
 		pos: SourcePos
 	},
 	/// Conditional expression is not a boolean.
@@ -79,8 +85,18 @@ pub enum Panic {
 		pattern: OsString,
 		pos: SourcePos,
 	},
-	/// Assertion failed.
-	AssertionFailed { pos: SourcePos },
+	/// Pattern had no matches, and the failglob option is set.
+	NoMatches {
+		pattern: OsString,
+		pos: SourcePos,
+	},
+	/// Functions can't be used as commands in asynchronous command blocks.
+	AsyncFunctionCommand { pos: SourcePos },
+	/// Assertion failed, optionally with a description of the failure.
+	AssertionFailed {
+		message: Option<String>,
+		pos: SourcePos,
+	},
 	/// Failed to import module.
 	ImportFailed {
 		pos: SourcePos,
@@ -88,24 +104,57 @@ pub enum Panic {
 	},
 	/// Attempt to call <command>.join more than once.
 	InvalidJoin { pos: SourcePos },
+	/// Access denied by the sandbox policy.
+	PolicyViolation {
+		access: &'static str,
+		target: Value,
+		pos: SourcePos,
+	},
 	/// std.panic.
 	User {
 		context: Value,
 		pos: SourcePos,
 	},
+	/// The script exceeded a limit of it's budget. This can't be caught, so that the
+	/// script is aborted.
+	LimitExceeded { limit: Limit },
+	/// std.exit. This is not an error, but it unwinds the program like a panic, so that
+	/// pending cleanup runs before the process terminates with the given status.
+	Exit { code: u8 },
+	/// A panic raised inside a function call, with the calls that were active when it was
+	/// raised.
+	Traced {
+		panic: Box<Panic>,
+		/// The innermost calls, innermost first.
+		traceback: Box<[trace::Frame]>,
+		/// The amount of nested calls.
+		depth: usize,
+	},
 }
 
 
 impl Panic {
 	/// Attempt to increase the stack past it's maximum size.
 	pub fn stack_overflow(pos: SourcePos) -> Self {
-		Self::StackOverflow { pos }
+		Self::StackOverflow { pos, traceback: Box::default(), depth: 0 }
+	}
+
+
+	/// Attempt to nest function calls past the maximum depth, with the innermost calls.
+	pub fn stack_overflow_with(pos: SourcePos, traceback: Box<[trace::Frame]>, depth: usize) -> Self {
+		Self::StackOverflow { pos, traceback, depth }
 	}
 
 
 	/// Assertion failed.
 	pub fn assertion_failed(pos: SourcePos) -> Self {
-		Self::AssertionFailed { pos }
+		Self::AssertionFailed { message: None, pos }
+	}
+
+
+	/// Assertion failed, with a description of the failure.
+	pub fn assertion_failed_with(message: String, pos: SourcePos) -> Self {
+		Self::AssertionFailed { message: Some(message), pos }
 	}
 
 
@@ -141,6 +190,13 @@ impl Panic {
 
 	/// Ammount of supplied arguments in function call is different than expected.
 	pub fn invalid_args(supplied: u32, expected: u32, pos: SourcePos) -> Self {
+		Self::InvalidArgs { supplied, expected: expected ..= expected, pos }
+	}
+
+
+	/// Ammount of supplied arguments in function call is out of the expected range, for
+	/// functions with optional parameters.
+	pub fn invalid_args_range(supplied: u32, expected: RangeInclusive<u32>, pos: SourcePos) -> Self {
 		Self::InvalidArgs { supplied, expected, pos }
 	}
 
@@ -199,6 +255,17 @@ impl Panic {
 		Self::InvalidPattern { pattern, pos }
 	}
 
+	/// Pattern had no matches, and the failglob option is set.
+	pub fn no_matches(pattern: OsString, pos: SourcePos) -> Self {
+		Self::NoMatches { pattern, pos }
+	}
+
+
+	/// Functions can't be used as commands in asynchronous command blocks.
+	pub fn async_function_command(pos: SourcePos) -> Self {
+		Self::AsyncFunctionCommand { pos }
+	}
+
 
 	/// Attempt to assign a readonly field value.
 	pub fn assign_to_readonly_field(field: Value, pos: SourcePos) -> Self {
@@ -215,10 +282,52 @@ impl Panic {
 		Self::InvalidJoin { pos }
 	}
 
+	/// Access denied by the sandbox policy.
+	pub fn policy_violation(access: &'static str, target: Value, pos: SourcePos) -> Self {
+		Self::PolicyViolation { access, target, pos }
+	}
+
 	/// std.panic
 	pub fn user(context: Value, pos: SourcePos) -> Self {
 		Self::User { context, pos }
 	}
+
+	/// The script exceeded a limit of it's budget.
+	pub fn limit_exceeded(limit: Limit) -> Self {
+		Self::LimitExceeded { limit }
+	}
+
+	/// std.exit
+	pub fn exit(code: u8) -> Self {
+		Self::Exit { code }
+	}
+
+
+	/// Attach the active calls to the panic, unless it already carries them. This must be
+	/// called while the call in which the panic was raised is still active.
+	pub fn traced(self) -> Self {
+		match self {
+			Self::Traced { .. }
+			| Self::StackOverflow { .. }
+			| Self::LimitExceeded { .. }
+			| Self::Exit { .. } => self,
+
+			panic => Self::Traced {
+				panic: Box::new(panic),
+				traceback: trace::capture(),
+				depth: trace::depth(),
+			},
+		}
+	}
+
+
+	/// The panic without the calls attached to it, if any.
+	pub fn untraced(&self) -> &Self {
+		match self {
+			Self::Traced { panic, .. } => panic,
+			panic => panic,
+		}
+	}
 }
 
 
@@ -229,8 +338,19 @@ impl<'a> Display<'a> for Panic {
 		let panic = color::Fg(color::Red, "Panic");
 
 		match self {
-			Self::StackOverflow { pos } =>
-				write!(f, "{} in {}: stack overflow", panic, fmt::Show(pos, context)),
+			Self::StackOverflow { pos, traceback, depth } => {
+				write!(f, "{} in {}: stack overflow", panic, fmt::Show(pos, context))?;
+
+				for frame in traceback.iter() {
+					write!(f, "\n  {}", fmt::Show(frame, context))?;
+				}
+
+				if *depth > traceback.len() {
+					write!(f, "\n  ... {} more calls", depth - traceback.len())?;
+				}
+
+				Ok(())
+			}
 
 			Self::IntegerOverflow { pos } =>
 				write!(f, "{} in {}: integer overflow", panic, fmt::Show(pos, context)),
@@ -259,14 +379,25 @@ impl<'a> Display<'a> for Panic {
 					color::Fg(color::Yellow, fmt::Show(function, context))
 				),
 
-			Self::InvalidArgs { supplied, expected, pos } =>
+			Self::InvalidArgs { supplied, expected, pos } if expected.start() == expected.end() =>
 				write!(
 					f,
 					"{} in {}: incorrect amount of function parameters -- supplied {}, expected {}",
 					panic,
 					fmt::Show(pos, context),
 					supplied,
-					expected
+					expected.start()
+				),
+
+			Self::InvalidArgs { supplied, expected, pos } =>
+				write!(
+					f,
+					"{} in {}: incorrect amount of function parameters -- supplied {}, expected {} to {}",
+					panic,
+					fmt::Show(pos, context),
+					supplied,
+					expected.start(),
+					expected.end()
 				),
 
 			Self::InvalidCondition { value, pos } =>
@@ -329,6 +460,23 @@ impl<'a> Display<'a> for Panic {
 					color::Fg(color::Yellow, pattern)
 				),
 
+			Self::NoMatches { pattern, pos } =>
+				write!(
+					f,
+					"{} in {}: no matches for pattern ({:?})",
+					panic,
+					fmt::Show(pos, context),
+					color::Fg(color::Yellow, pattern)
+				),
+
+			Self::AsyncFunctionCommand { pos } =>
+				write!(
+					f,
+					"{} in {}: function used as command in asynchronous block",
+					panic,
+					fmt::Show(pos, context),
+				),
+
 			Self::AssignToReadonlyField { field, pos } => write!(
 					f,
 					"{} in {}: attempt to assign field ({}), which is readonly",
@@ -337,9 +485,12 @@ impl<'a> Display<'a> for Panic {
 					color::Fg(color::Yellow, fmt::Show(field, context))
 				),
 
-			Self::AssertionFailed { pos } =>
+			Self::AssertionFailed { message: None, pos } =>
 				write!(f, "{} in {}: assertion failed", panic, fmt::Show(pos, context)),
 
+			Self::AssertionFailed { message: Some(message), pos } =>
+				write!(f, "{} in {}: assertion failed: {}", panic, fmt::Show(pos, context), message),
+
 			Self::ImportFailed { path, pos } =>
 				write!(
 					f,
@@ -352,14 +503,55 @@ impl<'a> Display<'a> for Panic {
 			Self::InvalidJoin { pos } =>
 				write!(f, "{} in {}: attempt to call join more than once", panic, fmt::Show(pos, context)),
 
-			Self::User { context: value, pos } =>
+			Self::PolicyViolation { access, target, pos } =>
+				write!(
+					f,
+					"{} in {}: {} ({}) denied by policy",
+					panic,
+					fmt::Show(pos, context),
+					access,
+					color::Fg(color::Yellow, fmt::Show(target, context))
+				),
+
+			Self::User { context: value, pos } => {
 				write!(
 					f,
 					"{} in {}: std.panic({})",
 					panic,
 					fmt::Show(pos, context),
 					color::Fg(color::Yellow, fmt::Show(value, context))
-				),
+				)?;
+
+				// Errors carry the calls that were active when they were created.
+				if let Value::Error(error) = value {
+					write!(f, "\n  error created:")?;
+
+					for frame in error.trace.iter() {
+						write!(f, "\n  {}", fmt::Show(frame, context))?;
+					}
+				}
+
+				Ok(())
+			}
+
+			Self::LimitExceeded { limit } => write!(f, "{}: {} exceeded", panic, limit),
+
+			Self::Exit { code } =>
+				write!(f, "std.exit({})", color::Fg(color::Yellow, code)),
+
+			Self::Traced { panic, traceback, depth } => {
+				panic.fmt(f, context)?;
+
+				for frame in traceback.iter() {
+					write!(f, "\n  {}", fmt::Show(frame, context))?;
+				}
+
+				if *depth > traceback.len() {
+					write!(f, "\n  ... {} more calls", depth - traceback.len())?;
+				}
+
+				Ok(())
+			}
 		}
 	}
 }
@@ -0,0 +1,239 @@
+use super::InvalidPattern;
+
+
+/// A pattern for a single path component. Besides the usual wildcards (`*`, `?` and
+/// `[...]`), extended globs are supported: `@(a|b)` matches any of the given patterns, and
+/// `!(a|b)` matches anything except the given patterns.
+#[derive(Debug)]
+pub struct Matcher {
+	tokens: Box<[Token]>,
+}
+
+
+#[derive(Debug)]
+enum Token {
+	/// A literal character.
+	Char(char),
+	/// Any single character (`?`).
+	Any,
+	/// Any sequence of characters (`*`).
+	Star,
+	/// A character class (`[...]`), composed of inclusive ranges.
+	Class { negated: bool, ranges: Box<[(char, char)]> },
+	/// Any of the patterns (`@(...)`).
+	OneOf(Box<[Box<[Token]>]>),
+	/// Anything but the patterns (`!(...)`).
+	NoneOf(Box<[Box<[Token]>]>),
+}
+
+
+impl Matcher {
+	pub fn new(pattern: &str) -> Result<Self, InvalidPattern> {
+		let chars: Vec<char> = pattern.chars().collect();
+		let mut parser = Parser { chars: &chars, ix: 0 };
+
+		let tokens = parser.sequence(false)?;
+
+		if parser.ix < chars.len() {
+			return Err(InvalidPattern);
+		}
+
+		Ok(Self { tokens })
+	}
+
+
+	/// Check if the given file name matches the pattern.
+	/// When dotfiles is false, names starting with a dot are only matched by patterns
+	/// starting with a literal dot.
+	pub fn matches(&self, name: &str, case_sensitive: bool, dotfiles: bool) -> bool {
+		if !dotfiles && name.starts_with('.') && !matches!(self.tokens.first(), Some(Token::Char('.'))) {
+			return false;
+		}
+
+		let name: Vec<char> = name.chars().collect();
+
+		match_tokens(&self.tokens, &name, case_sensitive)
+	}
+}
+
+
+/// Backtracking matcher. File names are short, so this is fast enough.
+fn match_tokens(tokens: &[Token], text: &[char], case_sensitive: bool) -> bool {
+	let any_of = |patterns: &[Box<[Token]>], text: &[char]| {
+		patterns
+			.iter()
+			.any(|pattern| match_tokens(pattern, text, case_sensitive))
+	};
+
+	match tokens.split_first() {
+		None => text.is_empty(),
+
+		Some((Token::Char(c), rest)) => match text.split_first() {
+			Some((t, text)) => char_eq(*c, *t, case_sensitive) && match_tokens(rest, text, case_sensitive),
+			None => false,
+		},
+
+		Some((Token::Any, rest)) => match text.split_first() {
+			Some((_, text)) => match_tokens(rest, text, case_sensitive),
+			None => false,
+		},
+
+		Some((Token::Star, rest)) => (0 ..= text.len())
+			.any(|ix| match_tokens(rest, &text[ix ..], case_sensitive)),
+
+		Some((Token::Class { negated, ranges }, rest)) => match text.split_first() {
+			Some((t, text)) => {
+				let in_class = ranges
+					.iter()
+					.any(|&(from, to)| in_range(*t, from, to, case_sensitive));
+
+				in_class != *negated && match_tokens(rest, text, case_sensitive)
+			}
+			None => false,
+		},
+
+		Some((Token::OneOf(patterns), rest)) => (0 ..= text.len())
+			.any(
+				|ix| any_of(patterns, &text[.. ix])
+					&& match_tokens(rest, &text[ix ..], case_sensitive)
+			),
+
+		Some((Token::NoneOf(patterns), rest)) => (0 ..= text.len())
+			.any(
+				|ix| !any_of(patterns, &text[.. ix])
+					&& match_tokens(rest, &text[ix ..], case_sensitive)
+			),
+	}
+}
+
+
+fn char_eq(a: char, b: char, case_sensitive: bool) -> bool {
+	if case_sensitive {
+		a == b
+	} else {
+		a == b || a.to_lowercase().eq(b.to_lowercase())
+	}
+}
+
+
+fn in_range(c: char, from: char, to: char, case_sensitive: bool) -> bool {
+	let check = |c: char| from <= c && c <= to;
+
+	if case_sensitive {
+		check(c)
+	} else {
+		check(c) || c.to_lowercase().any(check) || c.to_uppercase().any(check)
+	}
+}
+
+
+/// Recursive descent parser for component patterns.
+struct Parser<'a> {
+	chars: &'a [char],
+	ix: usize,
+}
+
+
+impl<'a> Parser<'a> {
+	/// Parse a sequence of tokens. Inside extended globs, the sequence ends before the
+	/// pattern separator or the closing parenthesis.
+	fn sequence(&mut self, nested: bool) -> Result<Box<[Token]>, InvalidPattern> {
+		let mut tokens = Vec::new();
+
+		while let Some(&c) = self.chars.get(self.ix) {
+			let next = self.chars.get(self.ix + 1).copied();
+
+			let token = match (c, next) {
+				('|', _) | (')', _) if nested => break,
+
+				('*', _) => {
+					self.ix += 1;
+					Token::Star
+				}
+
+				('?', _) => {
+					self.ix += 1;
+					Token::Any
+				}
+
+				('[', _) => self.class()?,
+
+				('@', Some('(')) => {
+					self.ix += 2;
+					Token::OneOf(self.alternatives()?)
+				}
+
+				('!', Some('(')) => {
+					self.ix += 2;
+					Token::NoneOf(self.alternatives()?)
+				}
+
+				(c, _) => {
+					self.ix += 1;
+					Token::Char(c)
+				}
+			};
+
+			tokens.push(token);
+		}
+
+		Ok(tokens.into_boxed_slice())
+	}
+
+
+	/// Parse the patterns of an extended glob, after the open parenthesis.
+	fn alternatives(&mut self) -> Result<Box<[Box<[Token]>]>, InvalidPattern> {
+		let mut patterns = Vec::new();
+
+		loop {
+			patterns.push(self.sequence(true)?);
+
+			let separator = self.chars.get(self.ix).copied();
+			self.ix += 1;
+
+			match separator {
+				Some('|') => continue,
+				Some(')') => break,
+				_ => return Err(InvalidPattern),
+			}
+		}
+
+		Ok(patterns.into_boxed_slice())
+	}
+
+
+	/// Parse a character class, starting at the open bracket.
+	/// A closing bracket right after the open bracket is a literal.
+	fn class(&mut self) -> Result<Token, InvalidPattern> {
+		self.ix += 1;
+
+		let negated = matches!(self.chars.get(self.ix), Some('!' | '^'));
+		if negated {
+			self.ix += 1;
+		}
+
+		let mut ranges = Vec::new();
+		let mut first = true;
+
+		loop {
+			let c = *self.chars.get(self.ix).ok_or(InvalidPattern)?;
+			self.ix += 1;
+
+			match c {
+				']' if !first => break,
+
+				c => match self.chars.get(self.ix ..= self.ix + 1) {
+					Some(&['-', to]) if to != ']' => {
+						self.ix += 2;
+						ranges.push((c, to));
+					}
+					_ => ranges.push((c, c)),
+				},
+			}
+
+			first = false;
+		}
+
+		Ok(Token::Class { negated, ranges: ranges.into_boxed_slice() })
+	}
+}
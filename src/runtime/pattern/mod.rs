@@ -0,0 +1,307 @@
+mod matcher;
+
+use std::{
+	collections::HashSet,
+	fs,
+	path::{Path, PathBuf},
+};
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+
+use crate::ffi::OsStrExt;
+
+pub use matcher::Matcher;
+
+
+/// Options for file name pattern expansion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+	/// Maximum amount of directory levels traversed by the recursive wildcard (`**`).
+	pub max_depth: u32,
+	/// Whether wildcards match file names starting with a dot.
+	pub dotfiles: bool,
+	/// Whether matching is case sensitive.
+	pub case_sensitive: bool,
+}
+
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			max_depth: 64,
+			dotfiles: true,
+			case_sensitive: true,
+		}
+	}
+}
+
+
+/// The pattern is not valid.
+#[derive(Debug)]
+pub struct InvalidPattern;
+
+
+/// A path component of a pattern.
+#[derive(Debug)]
+enum Component {
+	/// A component with no wildcards.
+	Literal(String),
+	/// A component with wildcards, matched against directory entries.
+	Pattern(Matcher),
+	/// The recursive wildcard (`**`), which matches zero or more directories.
+	Recursive,
+}
+
+
+impl Component {
+	fn parse(component: &str) -> Result<Self, InvalidPattern> {
+		let has_wildcards = component.contains(|c| matches!(c, '*' | '?' | '['))
+			|| component.contains("!(")
+			|| component.contains("@(");
+
+		if component == "**" {
+			Ok(Self::Recursive)
+		} else if has_wildcards {
+			// Recursive wildcards that do not form a whole component are plain wildcards.
+			let mut component = component.to_owned();
+			while component.contains("**") {
+				component = component.replace("**", "*");
+			}
+
+			Matcher::new(&component).map(Self::Pattern)
+		} else {
+			Ok(Self::Literal(component.to_owned()))
+		}
+	}
+}
+
+
+/// Expand a pattern to the matching paths, in lexicographic order for each directory.
+/// Relative patterns are expanded in the current directory, and produce relative paths.
+pub fn expand(pattern: &str, options: Options) -> Result<Vec<PathBuf>, InvalidPattern> {
+	expand_in(Path::new(""), pattern, options)
+}
+
+
+/// Expand a pattern like `expand`, but with relative patterns expanded in the given
+/// directory. The produced paths are still relative to that directory.
+pub fn expand_in(dir: &Path, pattern: &str, options: Options) -> Result<Vec<PathBuf>, InvalidPattern> {
+	let (root, pattern, relative) = match pattern.strip_prefix('/') {
+		Some(pattern) => (PathBuf::from("/"), pattern, false),
+		None => (dir.to_owned(), pattern, true),
+	};
+
+	// A trailing slash requires the last component to be a directory.
+	let dirs_only = pattern.ends_with('/');
+
+	let components = pattern
+		.split('/')
+		.filter(|component| !component.is_empty())
+		.map(Component::parse)
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mut paths = vec![root];
+
+	for (ix, component) in components.iter().enumerate() {
+		let files = !dirs_only && ix == components.len() - 1;
+		let mut matches = Vec::new();
+
+		for path in paths {
+			match component {
+				Component::Literal(literal) => {
+					let path = path.join(literal);
+
+					if (files && fs::symlink_metadata(&path).is_ok()) || path.is_dir() {
+						matches.push(path);
+					}
+				}
+
+				Component::Pattern(pattern) => {
+					for (entry, is_dir) in read_dir(&path) {
+						let is_match = entry
+							.file_name()
+							.and_then(|name| name.to_str())
+							.map(|name| pattern.matches(name, options.case_sensitive, options.dotfiles))
+							.unwrap_or(false);
+
+						if is_match && (files || is_dir) {
+							matches.push(entry);
+						}
+					}
+				}
+
+				Component::Recursive => {
+					let mut visited = HashSet::new();
+					if let Some(id) = dir_id(&path) {
+						visited.insert(id);
+					}
+
+					// The recursive wildcard may match no directories at all.
+					if !files {
+						matches.push(path.clone());
+					}
+
+					walk(&path, 0, files, options, &mut visited, &mut matches);
+				}
+			}
+		}
+
+		paths = matches;
+	}
+
+	if relative && !dir.as_os_str().is_empty() {
+		for path in paths.iter_mut() {
+			if let Ok(stripped) = path.strip_prefix(dir) {
+				*path = stripped.to_owned();
+			}
+		}
+	}
+
+	Ok(paths)
+}
+
+
+/// Get the directory in which the pattern is expanded, which is formed by it's leading
+/// components with no wildcards. Relative patterns produce relative directories.
+pub fn base_dir(pattern: &str) -> PathBuf {
+	let mut dir = PathBuf::from(if pattern.starts_with('/') { "/" } else { "" });
+
+	let literals = pattern
+		.split('/')
+		.filter(|component| !component.is_empty())
+		.map_while(
+			|component| match Component::parse(component) {
+				Ok(Component::Literal(literal)) => Some(literal),
+				_ => None,
+			}
+		);
+
+	dir.extend(literals);
+
+	dir
+}
+
+
+/// Recursively collect all directories, and possibly files, below the given directory.
+/// Directories are not visited twice, which prevents infinite loops caused by symlinks.
+fn walk(
+	dir: &Path,
+	depth: u32,
+	files: bool,
+	options: Options,
+	visited: &mut HashSet<DirId>,
+	matches: &mut Vec<PathBuf>,
+) {
+	if depth >= options.max_depth {
+		return;
+	}
+
+	for (entry, is_dir) in read_dir(dir) {
+		let hidden = entry
+			.file_name()
+			.map(|name| name.as_bytes().starts_with(b"."))
+			.unwrap_or(false);
+
+		if hidden && !options.dotfiles {
+			continue;
+		}
+
+		if is_dir {
+			let first_visit = dir_id(&entry)
+				.map(|id| visited.insert(id))
+				.unwrap_or(false);
+
+			matches.push(entry.clone());
+
+			if first_visit {
+				walk(&entry, depth + 1, files, options, visited, matches);
+			}
+		} else if files {
+			matches.push(entry);
+		}
+	}
+}
+
+
+/// Read the directory entries in lexicographic order, along with whether each entry is a
+/// directory. The empty path is the current directory. Errors are ignored.
+fn read_dir(dir: &Path) -> Vec<(PathBuf, bool)> {
+	let read_path =
+		if dir.as_os_str().is_empty() {
+			Path::new(".")
+		} else {
+			dir
+		};
+
+	let mut entries: Vec<(PathBuf, bool)> = match fs::read_dir(read_path) {
+		Ok(entries) => entries
+			.filter_map(Result::ok)
+			.map(
+				|entry| {
+					let path = dir.join(entry.file_name());
+					let is_dir = path.is_dir(); // Follows symlinks.
+					(path, is_dir)
+				}
+			)
+			.collect(),
+
+		Err(_) => Vec::new(),
+	};
+
+	entries.sort();
+
+	entries
+}
+
+
+/// An unique identifier for a directory: the device and inode numbers, or the canonical
+/// path on Windows.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(windows)]
+type DirId = PathBuf;
+
+
+/// Get an unique identifier for the given directory, following symlinks.
+fn dir_id(path: &Path) -> Option<DirId> {
+	let path =
+		if path.as_os_str().is_empty() {
+			Path::new(".")
+		} else {
+			path
+		};
+
+	#[cfg(unix)]
+	return fs::metadata(path)
+		.ok()
+		.map(|metadata| (metadata.dev(), metadata.ino()));
+
+	#[cfg(windows)]
+	return fs::canonicalize(path).ok();
+}
+
+
+/// Remove the escaping of special characters, which are escaped as `[c]`.
+pub fn unescape(pattern: &str) -> String {
+	let bytes = pattern.as_bytes();
+	let mut unescaped = Vec::with_capacity(bytes.len());
+
+	let mut ix = 0;
+	while ix < bytes.len() {
+		match &bytes[ix ..] {
+			[ b'[', c, b']', .. ] if matches!(c, b'?' | b'*' | b'[' | b']' | b'(') => {
+				unescaped.push(*c);
+				ix += 3;
+			}
+
+			[ c, .. ] => {
+				unescaped.push(*c);
+				ix += 1;
+			}
+
+			[] => unreachable!("index should be in bounds"),
+		}
+	}
+
+	String::from_utf8(unescaped).expect("unescaping should preserve UTF-8")
+}
@@ -0,0 +1,189 @@
+use std::{
+	ffi::OsStr,
+	path::{Component, Path, PathBuf},
+};
+
+use crate::ffi::OsStrExt;
+use super::{Panic, SourcePos, Value};
+
+
+/// Restrictions on the resources a script may access, so that semi-trusted scripts, such
+/// as plugins and user hooks, may be run safely. Denied accesses cause a panic. The
+/// default policy allows everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Policy {
+	/// The programs that may be executed in command blocks, as written in the command.
+	pub commands: Permission<Box<OsStr>>,
+	/// The paths that may be accessed by the standard library and by redirections.
+	/// Allowing a directory allows everything below it.
+	pub paths: Permission<PathBuf>,
+	/// The hosts to which network requests may be made.
+	pub network: Permission<Box<str>>,
+	/// The environment variables that may be read or written. Writes are restricted as
+	/// well, as variables such as PATH or LD_PRELOAD could hijack the allowed commands.
+	pub env: Permission<Box<OsStr>>,
+}
+
+
+/// Whether a kind of resource may be accessed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Permission<T> {
+	/// Everything may be accessed.
+	Allow,
+	/// Nothing may be accessed.
+	Deny,
+	/// Only the listed resources may be accessed.
+	Only(Box<[T]>),
+}
+
+
+impl<T> Permission<T> {
+	/// Check whether any of the listed resources satisfies the predicate.
+	fn allows<F>(&self, predicate: F) -> bool
+	where
+		F: FnMut(&T) -> bool,
+	{
+		match self {
+			Self::Allow => true,
+			Self::Deny => false,
+			Self::Only(allowed) => allowed.iter().any(predicate),
+		}
+	}
+
+
+	/// Convert the listed resources.
+	pub fn map<U, F>(self, f: F) -> Permission<U>
+	where
+		F: FnMut(T) -> U,
+	{
+		match self {
+			Self::Allow => Permission::Allow,
+			Self::Deny => Permission::Deny,
+			Self::Only(allowed) => Permission::Only(
+				allowed
+					.into_vec() // Use vec's owned iterator.
+					.into_iter()
+					.map(f)
+					.collect()
+			),
+		}
+	}
+}
+
+
+impl<T> Default for Permission<T> {
+	fn default() -> Self {
+		Self::Allow
+	}
+}
+
+
+impl Policy {
+	/// Check whether the given program may be executed.
+	pub fn allows_command(&self, program: &OsStr) -> bool {
+		self.commands.allows(|allowed| &**allowed == program)
+	}
+
+
+	/// Check whether the given path may be accessed. Relative paths are resolved from the
+	/// current directory, and symbolic links are resolved as far as the path exists, so
+	/// that neither `..` nor links escape the allowed directories.
+	pub fn allows_path(&self, path: &Path) -> bool {
+		if let Permission::Allow = self.paths {
+			return true;
+		}
+
+		let path = normalize(path);
+		self.paths.allows(|allowed| path.starts_with(normalize(allowed)))
+	}
+
+
+	/// Check whether network requests may be made to the given host.
+	pub fn allows_host(&self, host: &str) -> bool {
+		self.network.allows(|allowed| allowed.eq_ignore_ascii_case(host))
+	}
+
+
+	/// Check whether the given environment variable may be read or written.
+	pub fn allows_env(&self, name: &OsStr) -> bool {
+		self.env.allows(|allowed| &**allowed == name)
+	}
+
+
+	/// Panic unless the given program may be executed.
+	pub fn check_command(&self, program: &OsStr, pos: SourcePos) -> Result<(), Panic> {
+		if self.allows_command(program) {
+			Ok(())
+		} else {
+			Err(Panic::policy_violation("command", Value::from(program.as_bytes()), pos))
+		}
+	}
+
+
+	/// Panic unless the given path may be accessed.
+	pub fn check_path(&self, path: &Path, pos: SourcePos) -> Result<(), Panic> {
+		if self.allows_path(path) {
+			Ok(())
+		} else {
+			Err(Panic::policy_violation("path", path.as_os_str().as_bytes().into(), pos))
+		}
+	}
+
+
+	/// Panic unless network requests may be made to the given host.
+	pub fn check_host(&self, host: &str, pos: SourcePos) -> Result<(), Panic> {
+		if self.allows_host(host) {
+			Ok(())
+		} else {
+			Err(Panic::policy_violation("network access to", host.into(), pos))
+		}
+	}
+
+
+	/// Panic unless network requests may be made to the host of an address such as
+	/// `example.com:80` or `[::1]:80`.
+	pub fn check_address(&self, address: &str, pos: SourcePos) -> Result<(), Panic> {
+		let host = address
+			.rsplit_once(':')
+			.map_or(address, |(host, _)| host)
+			.trim_start_matches('[')
+			.trim_end_matches(']');
+
+		self.check_host(host, pos)
+	}
+
+
+	/// Panic unless the given environment variable may be read or written.
+	pub fn check_env(&self, name: &OsStr, pos: SourcePos) -> Result<(), Panic> {
+		if self.allows_env(name) {
+			Ok(())
+		} else {
+			Err(Panic::policy_violation("env variable", Value::from(name.as_bytes()), pos))
+		}
+	}
+}
+
+
+/// Make a path absolute, without `.` or `..` components, and resolve symbolic links in
+/// it's existing prefixes. Links are resolved before applying the following components,
+/// so that `..` after a link refers to the parent of it's target, as in the kernel.
+fn normalize(path: &Path) -> PathBuf {
+	let mut absolute = std::env::current_dir().unwrap_or_default();
+
+	for component in path.components() {
+		match component {
+			Component::Prefix(_) | Component::RootDir => absolute = PathBuf::from("/"),
+			Component::CurDir => (),
+			Component::ParentDir => { absolute.pop(); }
+			Component::Normal(name) => {
+				absolute.push(name);
+
+				if let Ok(canonical) = absolute.canonicalize() {
+					absolute = canonical;
+				}
+			}
+		}
+	}
+
+	absolute
+}
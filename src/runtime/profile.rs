@@ -0,0 +1,203 @@
+use std::{
+	collections::HashMap,
+	io::{self, Write},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	thread,
+	time::{Duration, Instant},
+};
+
+use crate::{fmt, symbol};
+use super::{trace, SourcePos};
+
+
+/// The interval between samples of the call stack.
+const INTERVAL: Duration = Duration::from_millis(1);
+
+
+/// The name of the root of all call stacks, which is the script itself.
+const ROOT: &str = "<main>";
+
+
+/// A frame of a sampled call stack.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+	Call(trace::Callee),
+	/// A command block, which is where time is spent in external commands.
+	Command(SourcePos),
+}
+
+
+/// A sampling profiler of the script's call stack. A timer thread periodically requests a
+/// sample, which the interpreter takes at the next instruction, attributing the time since
+/// the previous sample to the current call stack. Native functions and command blocks
+/// don't execute instructions, so they are sampled when they return, before they leave
+/// the call stack.
+#[derive(Debug)]
+pub struct Profiler {
+	/// Set by the timer thread when a sample is due.
+	due: Arc<AtomicBool>,
+	last: Instant,
+	/// The time spent in each call stack, outermost first.
+	stacks: HashMap<Box<[Node]>, Duration>,
+}
+
+
+impl Profiler {
+	/// Start profiling. The timer thread stops when the profiler is dropped.
+	pub fn new() -> Self {
+		let due = Arc::new(AtomicBool::new(false));
+		let timer = Arc::downgrade(&due);
+
+		thread::spawn(
+			move || loop {
+				thread::sleep(INTERVAL);
+
+				match timer.upgrade() {
+					Some(due) => due.store(true, Ordering::Relaxed),
+					None => return,
+				}
+			}
+		);
+
+		Self {
+			due,
+			last: Instant::now(),
+			stacks: HashMap::new(),
+		}
+	}
+
+
+	/// Take a sample if one is due.
+	#[inline]
+	pub fn poll(&mut self) {
+		if self.due.swap(false, Ordering::Relaxed) {
+			self.sample(None);
+		}
+	}
+
+
+	/// Attribute the time since the last sample to the current call stack, and to the
+	/// given command block within it, if any.
+	pub fn sample(&mut self, command: Option<SourcePos>) {
+		let now = Instant::now();
+		let elapsed = now - self.last;
+		self.last = now;
+
+		let stack: Box<[Node]> = trace::callees()
+			.into_iter()
+			.map(Node::Call)
+			.chain(command.map(Node::Command))
+			.collect();
+
+		*self.stacks.entry(stack).or_default() += elapsed;
+	}
+
+
+	/// Write the call stacks in the collapsed format used by flamegraph tools, with the time
+	/// spent in each stack in microseconds.
+	pub fn write_folded<W: Write>(&self, interner: &symbol::Interner, mut writer: W) -> io::Result<()> {
+		let mut lines: Vec<(String, u128)> = self.stacks
+			.iter()
+			.map(
+				|(stack, time)| {
+					let stack = std::iter::once(ROOT.to_owned())
+						.chain(stack.iter().map(|node| Self::name(node, interner)))
+						.collect::<Vec<_>>()
+						.join(";");
+
+					(stack, time.as_micros())
+				}
+			)
+			.filter(|(_, micros)| *micros > 0)
+			.collect();
+
+		lines.sort();
+
+		for (stack, micros) in lines {
+			writeln!(writer, "{} {}", stack, micros)?;
+		}
+
+		writer.flush()
+	}
+
+
+	/// Write the total time spent in each function, including the functions it called, and
+	/// the time spent in the function itself, sorted by total time.
+	pub fn write_report<W: Write>(&self, interner: &symbol::Interner, mut writer: W) -> io::Result<()> {
+		let mut functions: HashMap<String, (Duration, Duration)> = HashMap::new();
+		let mut elapsed = Duration::default();
+
+		for (stack, &time) in self.stacks.iter() {
+			elapsed += time;
+
+			let mut names: Vec<String> = std::iter::once(ROOT.to_owned())
+				.chain(stack.iter().map(|node| Self::name(node, interner)))
+				.collect();
+
+			let innermost = names.pop().expect("call stack should have a root");
+			functions.entry(innermost.clone()).or_default().1 += time;
+
+			// Recursive functions appear multiple times in the stack, but are counted once.
+			names.push(innermost);
+			names.sort();
+			names.dedup();
+
+			for name in names {
+				functions.entry(name).or_default().0 += time;
+			}
+		}
+
+		let mut functions: Vec<(String, (Duration, Duration))> = functions.into_iter().collect();
+		functions.sort_by(
+			|(name, (total, _)), (other, (other_total, _))| other_total
+				.cmp(total)
+				.then_with(|| name.cmp(other))
+		);
+
+		let percent = |time: Duration| {
+			if elapsed.is_zero() {
+				0.0
+			} else {
+				100.0 * time.as_secs_f64() / elapsed.as_secs_f64()
+			}
+		};
+
+		writeln!(writer, "{:>12} {:>7} {:>12} {:>7}  function", "total", "", "self", "")?;
+
+		for (name, (total, own)) in functions {
+			writeln!(
+				writer,
+				"{:>11.3}s {:>6.2}% {:>11.3}s {:>6.2}%  {}",
+				total.as_secs_f64(),
+				percent(total),
+				own.as_secs_f64(),
+				percent(own),
+				name,
+			)?;
+		}
+
+		writer.flush()
+	}
+
+
+	/// The name of a frame. Semicolons separate frames in the collapsed format, and are
+	/// therefore replaced.
+	fn name(node: &Node, interner: &symbol::Interner) -> String {
+		let name = match node {
+			Node::Call(callee) => fmt::Show(*callee, interner).to_string(),
+			Node::Command(pos) => format!("<command block at {}>", fmt::Show(pos, interner)),
+		};
+
+		name.replace(';', ",")
+	}
+}
+
+
+impl Default for Profiler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -1,25 +1,37 @@
-use std::{
-	io,
-	path::Path,
-	os::unix::ffi::OsStrExt,
-};
+use std::{fs::File, io, path::Path};
+#[cfg(unix)]
+use std::ffi::OsStr;
 
 use serial_test::serial;
 
 use crate::{
+	ffi::OsStrExt,
 	fmt,
 	semantic::{self, ErrorsDisplayContext},
 	symbol,
 	syntax::{self, AnalysisDisplayContext},
 	tests,
 };
-use super::{Runtime, Value, Panic};
+use super::{Budget, Runtime, Value, Panic};
+#[cfg(unix)]
+use super::{Permission, Policy};
 
 
-fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
+fn test_dir<P, F>(path: P, check: F) -> io::Result<()>
 where
 	P: AsRef<Path>,
 	F: FnMut(&Result<Value, Panic>) -> bool,
+{
+	test_dir_with(path, |_| (), check)
+}
+
+
+/// Test the files in a directory, calling setup on the runtime before each file.
+fn test_dir_with<P, S, F>(path: P, mut setup: S, mut check: F) -> io::Result<()>
+where
+	P: AsRef<Path>,
+	S: FnMut(&mut Runtime),
+	F: FnMut(&Result<Value, Panic>) -> bool,
 {
 	let interner = symbol::Interner::new();
 	let args = std::iter::empty::<&str>();
@@ -28,49 +40,9 @@ where
 	tests::util::test_dir(
 		path,
 		move |path, file| {
-			let path_symbol = runtime
-				.interner_mut()
-				.get_or_intern(path.as_os_str().as_bytes());
-			let source = syntax::Source::from_reader(path_symbol, file)?;
-			let syntactic_analysis = syntax::Analysis::analyze(
-				&source,
-				runtime.interner_mut()
-			);
-
-			if !syntactic_analysis.errors.is_empty() {
-				panic!(
-					"{}",
-					fmt::Show(
-						syntactic_analysis,
-						AnalysisDisplayContext {
-							max_errors: None,
-							interner: runtime.interner(),
-						}
-					)
-				);
-			}
+			setup(&mut runtime);
 
-			let semantic_analysis = semantic::Analyzer::analyze(
-				syntactic_analysis.ast,
-				runtime.interner_mut()
-			);
-			let program = match semantic_analysis {
-				Ok(program) => program,
-				Err(errors) => panic!(
-					"{}",
-					fmt::Show(
-						errors,
-						ErrorsDisplayContext {
-							max_errors: None,
-							interner: runtime.interner(),
-						}
-					)
-				),
-			};
-
-			let program = Box::leak(Box::new(program));
-
-			let result = runtime.eval(program);
+			let result = eval(&mut runtime, path, file)?;
 
 			if !check(&result) {
 				match result {
@@ -89,6 +61,73 @@ where
 }
 
 
+/// Analyze and evaluate a source file.
+fn eval(runtime: &mut Runtime, path: &Path, file: File) -> io::Result<Result<Value, Panic>> {
+	let path_symbol = runtime
+		.interner_mut()
+		.get_or_intern(path.as_os_str().as_bytes());
+	let source = syntax::Source::from_reader(path_symbol, file)?;
+	let arena = syntax::Arena::new();
+	let syntactic_analysis = syntax::Analysis::analyze(
+		&source,
+		runtime.interner_mut(),
+		&arena,
+	);
+
+	if !syntactic_analysis.errors.is_empty() {
+		panic!(
+			"{}",
+			fmt::Show(
+				syntactic_analysis,
+				AnalysisDisplayContext {
+					max_errors: None,
+					interner: runtime.interner(),
+				}
+			)
+		);
+	}
+
+	let semantic_analysis = semantic::Analyzer::analyze(
+		syntactic_analysis.ast,
+		runtime.interner_mut()
+	);
+	let program = match semantic_analysis {
+		Ok(program) => program,
+		Err(errors) => panic!(
+			"{}",
+			fmt::Show(
+				errors,
+				ErrorsDisplayContext {
+					max_errors: None,
+					interner: runtime.interner(),
+				}
+			)
+		),
+	};
+
+	let program = Box::leak(Box::new(program));
+
+	Ok(runtime.eval(program))
+}
+
+
+/// Analyze and evaluate a source file, given it's path relative to the crate.
+fn eval_file(runtime: &mut Runtime, path: &str) -> io::Result<Result<Value, Panic>> {
+	let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+	let file = File::open(&path)?;
+
+	eval(runtime, &path, file)
+}
+
+
+/// Check that Value is not too big, because it gets moved around and stored in arrays a
+/// lot. Scalars and pointers fit in a single word besides the discriminant.
+#[test]
+fn test_value_size() {
+	assert_eq!(std::mem::size_of::<Value>(), 16);
+}
+
+
 // As our garbage collector is not thread safe, we must *not* run the following tests in
 // parallel.
 
@@ -118,6 +157,179 @@ fn test_negative() -> io::Result<()> {
 fn test_asserts() -> io::Result<()> {
 	test_dir(
 		"src/runtime/tests/data/negative/asserts",
-		|result| matches!(result, Err(Panic::AssertionFailed { .. }))
+		|result| matches!(result.as_ref().map_err(Panic::untraced), Err(Panic::AssertionFailed { .. }))
+	)
+}
+
+
+#[test]
+#[serial]
+fn test_exits() -> io::Result<()> {
+	test_dir(
+		"src/runtime/tests/data/negative/exit",
+		|result| matches!(result, Err(Panic::Exit { code: 3 }))
+	)
+}
+
+
+// The policy scripts use symlinks and Unix commands.
+#[cfg(unix)]
+#[test]
+#[serial]
+fn test_policy() -> io::Result<()> {
+	let policy = Policy {
+		commands: Permission::Only(vec![ OsStr::new("true").into() ].into_boxed_slice()),
+		paths: Permission::Only(vec![ concat!(env!("CARGO_MANIFEST_DIR"), "/src/runtime/tests/data").into() ].into_boxed_slice()),
+		network: Permission::Deny,
+		env: Permission::Only(vec![ OsStr::new("HOME").into() ].into_boxed_slice()),
+	};
+
+	// A link to the root directory, used to check that links can't escape the allowed
+	// directories. It's kept out of the scanned directories, as those follow links.
+	let link = concat!(env!("CARGO_MANIFEST_DIR"), "/src/runtime/tests/data/root-link");
+	let _ = std::fs::remove_file(link);
+	std::os::unix::fs::symlink("/", link)?;
+
+	let result = test_dir_with(
+		"src/runtime/tests/data/policy",
+		|runtime| runtime.set_policy(policy.clone()),
+		|result| matches!(result.as_ref().map_err(Panic::untraced), Err(Panic::PolicyViolation { .. }))
+	);
+
+	std::fs::remove_file(link)?;
+
+	result
+}
+
+
+#[test]
+#[serial]
+fn test_limits() -> io::Result<()> {
+	let budget = Budget {
+		instructions: Some(100_000),
+		heap: Some(1 << 20),
+		..Budget::default()
+	};
+
+	test_dir_with(
+		"src/runtime/tests/data/limits",
+		|runtime| runtime.set_budget(budget),
+		|result| matches!(result, Err(Panic::LimitExceeded { .. }))
 	)
 }
+
+
+#[test]
+#[serial]
+fn test_coverage() -> io::Result<()> {
+	test_dir_with(
+		"src/runtime/tests/data/coverage",
+		|runtime| runtime.enable_coverage(),
+		Result::is_ok
+	)
+}
+
+
+/// The lcov report should have a record for the script, with the count of each line.
+#[test]
+#[serial]
+fn test_coverage_lcov() -> io::Result<()> {
+	let path = "src/runtime/tests/data/coverage/counts.hsh";
+
+	let mut runtime = Runtime::new(std::iter::empty::<&str>(), symbol::Interner::new());
+	runtime.enable_coverage();
+
+	if let Err(panic) = eval_file(&mut runtime, path)? {
+		panic!("{}", fmt::Show(panic, runtime.interner()));
+	}
+
+	let coverage = runtime.coverage().expect("coverage should be enabled");
+
+	let mut lcov = Vec::new();
+	coverage.write_lcov(runtime.interner(), &mut lcov)?;
+	let lcov = String::from_utf8(lcov).expect("lcov report should be utf-8");
+	let lines: Vec<&str> = lcov.lines().collect();
+
+	let source = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+	assert!(lines.contains(&format!("SF:{}", source.display()).as_str()), "{}", lcov);
+
+	for line in [ "DA:3,0", "DA:6,1", "DA:8,3" ] {
+		assert!(lines.contains(&line), "{}", lcov);
+	}
+
+	assert_eq!(lines.last(), Some(&"end_of_record"), "{}", lcov);
+
+	Ok(())
+}
+
+
+#[test]
+#[serial]
+fn test_profile() -> io::Result<()> {
+	test_dir_with(
+		"src/runtime/tests/data/profile",
+		|runtime| runtime.enable_profiling(),
+		Result::is_ok
+	)
+}
+
+
+/// The recursive calls of fib should be sampled as nested frames.
+#[test]
+#[serial]
+fn test_profile_output() -> io::Result<()> {
+	let mut runtime = Runtime::new(std::iter::empty::<&str>(), symbol::Interner::new());
+	runtime.enable_profiling();
+
+	if let Err(panic) = eval_file(&mut runtime, "src/runtime/tests/data/profile/calls.hsh")? {
+		panic!("{}", fmt::Show(panic, runtime.interner()));
+	}
+
+	let profiler = runtime.profiler().expect("profiling should be enabled");
+
+	let mut folded = Vec::new();
+	profiler.write_folded(runtime.interner(), &mut folded)?;
+	let folded = String::from_utf8(folded).expect("folded stacks should be utf-8");
+
+	assert!(
+		folded.lines().any(|line| line.starts_with("<main>;fib;fib")),
+		"{}",
+		folded
+	);
+
+	let mut report = Vec::new();
+	profiler.write_report(runtime.interner(), &mut report)?;
+	let report = String::from_utf8(report).expect("report should be utf-8");
+
+	assert!(report.lines().any(|line| line.ends_with("  fib")), "{}", report);
+	assert!(report.lines().any(|line| line.ends_with("  <main>")), "{}", report);
+
+	Ok(())
+}
+
+
+/// Panics raised inside functions should carry the calls that were active.
+#[test]
+#[serial]
+fn test_traceback() -> io::Result<()> {
+	let mut runtime = Runtime::new(std::iter::empty::<&str>(), symbol::Interner::new());
+
+	let panic = match eval_file(&mut runtime, "src/runtime/tests/data/traceback/type-error.hsh")? {
+		Ok(value) => panic!("expected panic, got {}", fmt::Show(value, runtime.interner())),
+		Err(panic) => panic,
+	};
+
+	assert!(matches!(panic.untraced(), Panic::TypeError { .. }));
+
+	let message = fmt::Show(&panic, runtime.interner()).to_string();
+	let lines: Vec<&str> = message.lines().collect();
+
+	assert_eq!(lines.len(), 3, "{}", message);
+	assert!(lines[0].contains("type-error.hsh (line 2, column 5)"), "{}", message);
+	assert!(lines[1].starts_with("  in f, called from "), "{}", message);
+	assert!(lines[1].ends_with("type-error.hsh (line 6, column 2)"), "{}", message);
+	assert!(lines[2].starts_with("  in g, called from "), "{}", message);
+	assert!(lines[2].ends_with("type-error.hsh (line 9, column 1)"), "{}", message);
+
+	Ok(())
+}
@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+
+use gc::{Finalize, Trace};
+
+use crate::{
+	fmt::{self, Display},
+	symbol::{self, Symbol},
+};
+use super::SourcePos;
+
+
+/// The maximum amount of calls kept in a stack trace.
+const MAX_FRAMES: usize = 32;
+
+
+thread_local! {
+	/// The active function calls, innermost last. The call stack is kept per thread instead
+	/// of in the runtime, so that errors may capture it when created.
+	static CALLS: RefCell<Vec<Frame>> = RefCell::new(Vec::new());
+}
+
+
+/// The function of a call in a stack trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Callee {
+	/// A function implemented in Hush code, and the name it was declared with, if any.
+	Hush(Option<Symbol>),
+	/// A native function.
+	Rust(&'static str),
+}
+
+
+/// A function call in a stack trace.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Finalize)]
+pub struct Frame {
+	pub callee: Callee,
+	/// The position of the call.
+	pub pos: SourcePos,
+}
+
+
+/// Frame has no garbage-collected fields.
+unsafe impl Trace for Frame {
+	gc::unsafe_empty_trace!();
+}
+
+
+/// Push a call to the call stack.
+pub fn enter(callee: Callee, pos: SourcePos) {
+	CALLS.with(
+		|calls| calls
+			.borrow_mut()
+			.push(Frame { callee, pos })
+	);
+}
+
+
+/// Pop the innermost call from the call stack.
+pub fn leave() {
+	CALLS.with(
+		|calls| {
+			calls.borrow_mut().pop();
+		}
+	);
+}
+
+
+/// The amount of active calls.
+pub fn depth() -> usize {
+	CALLS.with(|calls| calls.borrow().len())
+}
+
+
+/// The functions of all active calls, outermost first.
+pub fn callees() -> Vec<Callee> {
+	CALLS.with(
+		|calls| calls
+			.borrow()
+			.iter()
+			.map(|frame| frame.callee)
+			.collect()
+	)
+}
+
+
+/// Capture the innermost calls, innermost first.
+pub fn capture() -> Box<[Frame]> {
+	CALLS.with(
+		|calls| calls
+			.borrow()
+			.iter()
+			.rev()
+			.take(MAX_FRAMES)
+			.cloned()
+			.collect()
+	)
+}
+
+
+impl<'a> Display<'a> for Callee {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::Hush(Some(name)) => name.fmt(f, context),
+			Self::Hush(None) => write!(f, "<anonymous function>"),
+			Self::Rust(name) => write!(f, "{}", name),
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Frame {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		write!(
+			f,
+			"in {}, called from {}",
+			fmt::Show(self.callee, context),
+			fmt::Show(&self.pos, context)
+		)
+	}
+}
@@ -1,24 +1,55 @@
 use std::{
+	cmp::Ordering,
 	convert::TryInto,
 	hash::{Hash, Hasher},
-	ops::Deref,
 };
 
-use gc::{Gc, GcCell, GcCellRef, GcCellRefMut, Finalize, Trace};
+use gc::{Gc, GcCell, GcCellRef, Finalize, Trace};
 
-use super::{EmptyCollection, IndexOutOfBounds, Value};
+use super::{cycle, finalize, heap, EmptyCollection, IndexOutOfBounds, Storage, Value};
 
 
-/// An array in the language.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// The items of an array, which notify the finalizers when collected. The identity is
+/// kept outside the cell, so that it's available even while the items are borrowed.
+#[derive(Debug)]
+#[derive(Trace)]
+struct Items {
+	id: finalize::Id,
+	items: GcCell<Storage<Vec<Value>>>,
+	bytes: heap::Bytes,
+}
+
+
+impl Finalize for Items {
+	fn finalize(&self) {
+		finalize::collected(self.id);
+	}
+}
+
+
+/// An array in the language. Arrays are compared by their items, in order.
+#[derive(Debug)]
 #[derive(Trace, Finalize)]
-pub struct Array(Gc<GcCell<Vec<Value>>>);
+pub struct Array(Gc<Items>);
 
 
 impl Array {
 	/// Crate a new empty array.
 	pub fn new(vec: Vec<Value>) -> Self {
-		Self(Gc::new(GcCell::new(vec)))
+		Self::with_storage(Storage::Owned(vec))
+	}
+
+
+	fn with_storage(items: Storage<Vec<Value>>) -> Self {
+		Self(
+			Gc::new(
+				Items {
+					id: finalize::new_id(),
+					bytes: heap::Bytes::new::<Items, _>(&items),
+					items: GcCell::new(items),
+				}
+			)
+		)
 	}
 
 
@@ -28,27 +59,40 @@ impl Array {
 	}
 
 
+	/// Copy the array, so that the result doesn't alias the original. The items are shared
+	/// until either array is changed, so this takes constant time.
+	pub fn shallow_clone(&self) -> Self {
+		Self::with_storage(Storage::clone(&self.0.items, &self.0.bytes))
+	}
+
+
+	/// The identity of the array, for watching it's collection.
+	pub fn id(&self) -> finalize::Id {
+		self.0.id
+	}
+
+
 	/// Borrow the inner Vec.
 	pub fn borrow(&self) -> GcCellRef<Vec<Value>> {
-		self.0.deref().borrow()
+		Storage::borrow(&self.0.items)
 	}
 
 
 	/// Borrow the inner Vec mutably.
-	pub fn borrow_mut(&self) -> GcCellRefMut<Vec<Value>> {
-		self.0.deref().borrow_mut()
+	pub fn borrow_mut(&self) -> heap::RefMut<Vec<Value>> {
+		heap::RefMut::new(Storage::borrow_mut(&self.0.items), &self.0.bytes)
 	}
 
 
 	/// Push a value into the array.
 	pub fn push(&mut self, value: Value) {
-		self.0.borrow_mut().push(value)
+		self.borrow_mut().push(value)
 	}
 
 
 	/// Pop a value from the back of the array.
 	pub fn pop(&mut self) -> Result<Value, EmptyCollection> {
-		self.0
+		self
 			.borrow_mut()
 			.pop()
 			.ok_or(EmptyCollection)
@@ -113,10 +157,52 @@ impl Array {
 }
 
 
-// GcCell does not implement Eq because `borrow` might panic.
-#[allow(clippy::derive_hash_xor_eq)]
+impl PartialEq for Array {
+	fn eq(&self, other: &Self) -> bool {
+		cycle::compare(
+			self.id(),
+			other.id(),
+			true,
+			|| *self.borrow() == *other.borrow(),
+		)
+	}
+}
+
+
+impl Eq for Array { }
+
+
+impl PartialOrd for Array {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+
+/// Arrays are ordered lexicographically.
+impl Ord for Array {
+	fn cmp(&self, other: &Self) -> Ordering {
+		cycle::compare(
+			self.id(),
+			other.id(),
+			Ordering::Equal,
+			|| self.borrow().as_slice().cmp(other.borrow().as_slice()),
+		)
+	}
+}
+
+
 impl Hash for Array {
 	fn hash<H: Hasher>(&self, state: &mut H) {
-		self.borrow().hash(state)
+		let items = self.borrow();
+
+		items.len().hash(state);
+		cycle::hash_contents(
+			|| {
+				for item in items.iter() {
+					item.hash(state);
+				}
+			}
+		);
 	}
 }
@@ -0,0 +1,144 @@
+use std::{
+	cmp::Ordering,
+	convert::TryInto,
+	hash::{Hash, Hasher},
+};
+
+use gc::{Gc, GcCell, GcCellRef, Finalize, Trace};
+
+use super::{heap, IndexOutOfBounds, Storage};
+
+
+/// The contents of a buffer, along the bytes accounted for them.
+#[derive(Debug)]
+#[derive(Trace, Finalize)]
+struct Contents {
+	vec: GcCell<Storage<Vec<u8>>>,
+	bytes: heap::Bytes,
+}
+
+
+/// A mutable byte buffer in the language.
+#[derive(Debug)]
+#[derive(Trace, Finalize)]
+pub struct Buffer(Gc<Contents>);
+
+
+impl Buffer {
+	/// Crate a new buffer with the given contents.
+	pub fn new(vec: Vec<u8>) -> Self {
+		Self::with_storage(Storage::Owned(vec))
+	}
+
+
+	fn with_storage(vec: Storage<Vec<u8>>) -> Self {
+		Self(
+			Gc::new(
+				Contents {
+					bytes: heap::Bytes::new::<Contents, _>(&vec),
+					vec: GcCell::new(vec),
+				}
+			)
+		)
+	}
+
+
+	/// Shallow copy.
+	pub fn copy(&self) -> Self {
+		Self(self.0.clone())
+	}
+
+
+	/// Copy the buffer, so that the result doesn't alias the original. The bytes are
+	/// shared until either buffer is changed, so this takes constant time.
+	pub fn shallow_clone(&self) -> Self {
+		Self::with_storage(Storage::clone(&self.0.vec, &self.0.bytes))
+	}
+
+
+	/// Borrow the inner Vec.
+	pub fn borrow(&self) -> GcCellRef<Vec<u8>> {
+		Storage::borrow(&self.0.vec)
+	}
+
+
+	/// Borrow the inner Vec mutably.
+	pub fn borrow_mut(&self) -> heap::RefMut<Vec<u8>> {
+		heap::RefMut::new(Storage::borrow_mut(&self.0.vec), &self.0.bytes)
+	}
+
+
+	/// Get the byte at a given index.
+	pub fn index(&self, index: i64) -> Result<u8, IndexOutOfBounds> {
+		let index: usize = index
+			.try_into()
+			.map_err(|_| IndexOutOfBounds)?;
+
+		self
+			.borrow()
+			.get(index)
+			.copied()
+			.ok_or(IndexOutOfBounds)
+	}
+
+
+	/// Assign a byte to the given index.
+	pub fn set(&self, index: i64, byte: u8) -> Result<(), IndexOutOfBounds> {
+		let index: usize = index
+			.try_into()
+			.map_err(|_| IndexOutOfBounds)?;
+
+		let mut buffer = self.borrow_mut();
+
+		let val = buffer
+			.get_mut(index)
+			.ok_or(IndexOutOfBounds)?;
+
+		*val = byte;
+
+		Ok(())
+	}
+
+
+	/// Get the buffer length.
+	pub fn len(&self) -> i64 {
+		self.borrow().len() as i64
+	}
+
+
+	/// Whether the buffer is empty.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+
+impl PartialEq for Buffer {
+	fn eq(&self, other: &Self) -> bool {
+		*self.borrow() == *other.borrow()
+	}
+}
+
+
+impl Eq for Buffer { }
+
+
+impl PartialOrd for Buffer {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+
+impl Ord for Buffer {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.borrow().cmp(&other.borrow())
+	}
+}
+
+
+impl Hash for Buffer {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.borrow().hash(state)
+	}
+}
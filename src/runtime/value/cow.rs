@@ -0,0 +1,138 @@
+use std::mem;
+
+use gc::{Finalize, Gc, GcCell, GcCellRef, GcCellRefMut, Trace};
+
+use super::{heap, DictMap, Value};
+
+
+/// Contents which can be duplicated, so that a shared copy may be changed.
+pub trait Duplicate: Default {
+	fn duplicate(&self) -> Self;
+}
+
+
+impl Duplicate for Vec<Value> {
+	fn duplicate(&self) -> Self {
+		self.iter().map(Value::copy).collect()
+	}
+}
+
+
+impl Duplicate for DictMap {
+	fn duplicate(&self) -> Self {
+		self
+			.iter()
+			.map(|(key, value)| (key.copy(), value.copy()))
+			.collect()
+	}
+}
+
+
+impl Duplicate for Vec<u8> {
+	fn duplicate(&self) -> Self {
+		self.clone()
+	}
+}
+
+
+/// Contents shared by clones, which are accounted once for all of them.
+#[derive(Debug)]
+#[derive(Trace, Finalize)]
+pub struct Shared<T: Trace + 'static> {
+	contents: T,
+	bytes: heap::Bytes,
+}
+
+
+/// The contents of a collection, which are copied on write after being cloned. This way,
+/// std.clone takes constant time, and the copy is only made if either collection is
+/// changed afterwards.
+#[derive(Debug)]
+#[derive(Trace, Finalize)]
+pub enum Storage<T: Trace + 'static> {
+	Owned(T),
+	Shared(Gc<Shared<T>>),
+}
+
+
+impl<T: Trace + Duplicate + heap::Contents + 'static> Storage<T> {
+	/// Borrow the contents.
+	pub fn borrow(cell: &GcCell<Self>) -> GcCellRef<'_, T> {
+		GcCellRef::map(cell.borrow(), Self::get)
+	}
+
+
+	/// Borrow the contents mutably, copying them if they are shared.
+	pub fn borrow_mut(cell: &GcCell<Self>) -> GcCellRefMut<'_, Self, T> {
+		GcCellRefMut::map(cell.borrow_mut(), Self::get_mut)
+	}
+
+
+	/// Clone the contents, sharing them until either copy is changed. The contents are
+	/// copied right away if they are borrowed.
+	pub fn clone(cell: &GcCell<Self>, bytes: &heap::Bytes) -> Self {
+		match cell.try_borrow_mut() {
+			Ok(mut storage) => {
+				let shared = storage.share();
+				bytes.update(&*storage);
+				Self::Shared(shared)
+			}
+
+			Err(_) => Self::Owned(cell.borrow().get().duplicate()),
+		}
+	}
+
+
+	fn get(&self) -> &T {
+		match self {
+			Self::Owned(contents) => contents,
+			Self::Shared(shared) => &shared.contents,
+		}
+	}
+
+
+	fn get_mut(&mut self) -> &mut T {
+		if let Self::Shared(shared) = self {
+			*self = Self::Owned(shared.contents.duplicate());
+		}
+
+		match self {
+			Self::Owned(contents) => contents,
+			Self::Shared(_) => unreachable!("contents should be owned"),
+		}
+	}
+
+
+	/// Move the contents to a shared box, if they are owned, and get another reference to
+	/// it.
+	fn share(&mut self) -> Gc<Shared<T>> {
+		if let Self::Owned(contents) = self {
+			let contents = mem::take(contents);
+
+			*self = Self::Shared(
+				Gc::new(
+					Shared {
+						bytes: heap::Bytes::new::<Shared<T>, _>(&contents),
+						contents,
+					}
+				)
+			);
+		}
+
+		match self {
+			Self::Shared(shared) => shared.clone(),
+			Self::Owned(_) => unreachable!("contents should be shared"),
+		}
+	}
+}
+
+
+/// Shared contents are accounted by the shared box.
+impl<T: Trace + heap::Contents + 'static> heap::Contents for Storage<T> {
+	fn heap_size(&self) -> usize {
+		match self {
+			Self::Owned(contents) => contents.heap_size(),
+			Self::Shared(_) => 0,
+		}
+	}
+}
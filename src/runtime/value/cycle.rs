@@ -0,0 +1,52 @@
+use std::{
+	cell::{Cell, RefCell},
+	collections::HashSet,
+};
+
+
+thread_local! {
+	/// The pairs of values which are being compared, by address.
+	static COMPARING: RefCell<HashSet<(usize, usize)>> = RefCell::new(HashSet::new());
+	/// Whether a collection is being hashed.
+	static HASHING: Cell<bool> = Cell::new(false);
+}
+
+
+/// Compare two values which may be part of a cycle, given their addresses. If the pair is
+/// already being compared, it has been reached through a cycle, and is assumed to be
+/// equal, so that the result depends only on the rest of the contents.
+pub fn compare<T, F>(left: usize, right: usize, equal: T, compare: F) -> T
+where
+	F: FnOnce() -> T,
+{
+	if left == right {
+		return equal;
+	}
+
+	let pair = (left, right);
+
+	let entered = COMPARING.with(|comparing| comparing.borrow_mut().insert(pair));
+	if !entered {
+		return equal;
+	}
+
+	let result = compare();
+
+	COMPARING.with(|comparing| comparing.borrow_mut().remove(&pair));
+
+	result
+}
+
+
+/// Hash the contents of a collection, unless it is nested in another collection which is
+/// being hashed. Nested collections are then hashed by their length only, which is
+/// consistent with equality and avoids cycles.
+pub fn hash_contents<F: FnOnce()>(hash: F) {
+	if HASHING.with(|hashing| hashing.replace(true)) {
+		return;
+	}
+
+	hash();
+
+	HASHING.with(|hashing| hashing.set(false));
+}
@@ -1,13 +1,15 @@
 use std::{
 	cmp::Ordering,
-	collections::{HashMap, BTreeMap},
+	collections::BTreeMap,
 	hash::{Hash, Hasher},
-	ops::Deref,
+	iter::FromIterator,
+	ops::{Deref, DerefMut},
 };
 
-use gc::{Gc, GcCell, GcCellRef, GcCellRefMut, Finalize, Trace};
+use gc::{custom_trace, Gc, GcCell, GcCellRef, Finalize, Trace};
+use indexmap::IndexMap;
 
-use super::{IndexOutOfBounds, Value};
+use super::{cycle, finalize, heap, IndexOutOfBounds, Storage, Value};
 
 
 /// Common dict keys
@@ -25,16 +27,102 @@ pub mod keys {
 }
 
 
-/// A dict in the language.
+/// The entries of a dict, which are kept in insertion order. Replacing the value of a
+/// key keeps it's position, and removing a key preserves the order of the others.
 #[derive(Debug, Default, PartialEq, Eq)]
+pub struct DictMap(IndexMap<Value, Value>);
+
+
+impl Deref for DictMap {
+	type Target = IndexMap<Value, Value>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+
+impl DerefMut for DictMap {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.0
+	}
+}
+
+
+impl DictMap {
+	/// Create an empty map with room for the given amount of entries.
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self(IndexMap::with_capacity(capacity))
+	}
+}
+
+
+impl FromIterator<(Value, Value)> for DictMap {
+	fn from_iter<I: IntoIterator<Item = (Value, Value)>>(iter: I) -> Self {
+		Self(iter.into_iter().collect())
+	}
+}
+
+
+impl IntoIterator for DictMap {
+	type Item = (Value, Value);
+	type IntoIter = indexmap::map::IntoIter<Value, Value>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+
+impl Finalize for DictMap { }
+
+
+unsafe impl Trace for DictMap {
+	custom_trace!(this, {
+		for (key, value) in this.0.iter() {
+			mark(key);
+			mark(value);
+		}
+	});
+}
+
+
+/// The entries of a dict, which notify the finalizers when collected. The identity is
+/// kept outside the cell, so that it's available even while the entries are borrowed.
+#[derive(Debug)]
+#[derive(Trace)]
+struct Entries {
+	id: finalize::Id,
+	map: GcCell<Storage<DictMap>>,
+	bytes: heap::Bytes,
+}
+
+
+impl Finalize for Entries {
+	fn finalize(&self) {
+		finalize::collected(self.id);
+	}
+}
+
+
+/// A dict in the language. Iteration follows insertion order, so that it is
+/// deterministic. Dicts are compared by their entries, regardless of order.
+#[derive(Debug)]
 #[derive(Trace, Finalize)]
-pub struct Dict(Gc<GcCell<HashMap<Value, Value>>>);
+pub struct Dict(Gc<Entries>);
+
+
+impl Default for Dict {
+	fn default() -> Self {
+		DictMap::default().into()
+	}
+}
 
 
 impl Dict {
-	/// Crate a new empty dict.
-	pub fn new(dict: HashMap<Value, Value>) -> Self {
-		Self(Gc::new(GcCell::new(dict)))
+	/// Create a new dict from it's entries.
+	pub fn new(dict: DictMap) -> Self {
+		dict.into()
 	}
 
 
@@ -44,15 +132,47 @@ impl Dict {
 	}
 
 
-	/// Borrow the hashmap.
-	pub fn borrow(&self) -> GcCellRef<HashMap<Value, Value>> {
-		self.0.deref().borrow()
+	fn with_storage(map: Storage<DictMap>) -> Self {
+		Self(
+			Gc::new(
+				Entries {
+					id: finalize::new_id(),
+					bytes: heap::Bytes::new::<Entries, _>(&map),
+					map: GcCell::new(map),
+				}
+			)
+		)
+	}
+
+
+	/// Copy the dict, so that the result doesn't alias the original. The entries are
+	/// shared until either dict is changed, so this takes constant time.
+	pub fn shallow_clone(&self) -> Self {
+		Self::with_storage(Storage::clone(&self.0.map, &self.0.bytes))
+	}
+
+
+	/// Check whether both values are the same dict, instead of comparing the entries.
+	pub fn ptr_eq(&self, other: &Self) -> bool {
+		std::ptr::eq(self.0.deref(), other.0.deref())
+	}
+
+
+	/// The identity of the dict, for watching it's collection.
+	pub fn id(&self) -> finalize::Id {
+		self.0.id
+	}
+
+
+	/// Borrow the entries.
+	pub fn borrow(&self) -> GcCellRef<DictMap> {
+		Storage::borrow(&self.0.map)
 	}
 
 
-	/// Borrow the hashmap mutably.
-	pub fn borrow_mut(&self) -> GcCellRefMut<HashMap<Value, Value>> {
-		self.0.deref().borrow_mut()
+	/// Borrow the entries mutably.
+	pub fn borrow_mut(&self) -> heap::RefMut<DictMap> {
+		heap::RefMut::new(Storage::borrow_mut(&self.0.map), &self.0.bytes)
 	}
 
 
@@ -93,6 +213,38 @@ impl Dict {
 }
 
 
+impl From<DictMap> for Dict {
+	fn from(dict: DictMap) -> Self {
+		Self::with_storage(Storage::Owned(dict))
+	}
+}
+
+
+impl FromIterator<(Value, Value)> for Dict {
+	fn from_iter<I: IntoIterator<Item = (Value, Value)>>(iter: I) -> Self {
+		iter
+			.into_iter()
+			.collect::<DictMap>()
+			.into()
+	}
+}
+
+
+impl PartialEq for Dict {
+	fn eq(&self, other: &Self) -> bool {
+		cycle::compare(
+			self.id(),
+			other.id(),
+			true,
+			|| *self.borrow() == *other.borrow(),
+		)
+	}
+}
+
+
+impl Eq for Dict { }
+
+
 /// We need PartialOrd in order to be able to store dicts as keys in other dicts.
 impl PartialOrd for Dict {
 	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
@@ -101,30 +253,44 @@ impl PartialOrd for Dict {
 }
 
 
-/// We need Ord in order to be able to store dicts as keys in other dicts.
+/// We need Ord in order to be able to store dicts as keys in other dicts. Dicts are
+/// ordered by their entries sorted by key.
 impl Ord for Dict {
 	fn cmp(&self, other: &Self) -> Ordering {
-		// This is very expensive, but there is no better way to correctly compare.
-		let _self = self.borrow();
-		let _self: BTreeMap<&Value, &Value> = _self.iter().collect();
-
-		let _other = other.borrow();
-		let _other: BTreeMap<&Value, &Value> = _other.iter().collect();
-
-		_self.cmp(&_other)
+		cycle::compare(
+			self.id(),
+			other.id(),
+			Ordering::Equal,
+			|| {
+				// This is very expensive, but there is no better way to correctly compare.
+				let _self = self.borrow();
+				let _self: BTreeMap<&Value, &Value> = _self.iter().collect();
+
+				let _other = other.borrow();
+				let _other: BTreeMap<&Value, &Value> = _other.iter().collect();
+
+				_self.cmp(&_other)
+			}
+		)
 	}
 }
 
 
 /// We need Hash in order to be able to store dicts as keys in other dicts.
-// GcCell does not implement Eq because `borrow` might panic.
-#[allow(clippy::derive_hash_xor_eq)]
 impl Hash for Dict {
 	fn hash<H: Hasher>(&self, state: &mut H) {
-		// This is very expensive, but there is no better way to correctly compare.
-		let _self = self.borrow();
-		let _self: BTreeMap<&Value, &Value> = _self.iter().collect();
-
-		_self.hash(state)
+		let entries = self.borrow();
+
+		entries.len().hash(state);
+		cycle::hash_contents(
+			|| {
+				// This is very expensive, but there is no better way to correctly compare.
+				let entries: BTreeMap<&Value, &Value> = entries.iter().collect();
+
+				for entry in entries {
+					entry.hash(state);
+				}
+			}
+		);
 	}
 }
@@ -1,4 +1,5 @@
 use std::{
+	cmp::Ordering,
 	hash::{Hash, Hasher},
 	io,
 	ops::Deref,
@@ -6,41 +7,69 @@ use std::{
 
 use gc::{Gc, GcCell, Finalize, Trace};
 
-use super::{IndexOutOfBounds, Value, Str};
+use crate::{fmt::FmtString, symbol};
+use super::{trace, Dict, IndexOutOfBounds, Value, Str};
 
 
-/// Strings in Hush are immutable.
-#[derive(Debug, Eq, PartialOrd, Ord)]
+/// Error values. Errors are shared, so that values which hold errors remain small.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[derive(Trace, Finalize)]
-pub struct Error {
+pub struct Error(Gc<ErrorData>);
+
+
+/// The contents of an error value.
+#[derive(Debug)]
+#[derive(Trace, Finalize)]
+pub struct ErrorData {
 	pub description: Str,
-	pub context: Gc<GcCell<Value>>,
+	pub context: GcCell<Value>,
+	/// The function calls that were active when the error was created, innermost first.
+	#[unsafe_ignore_trace]
+	pub trace: Box<[trace::Frame]>,
 }
 
 
 impl Error {
-	/// Create a new error instance.
+	/// Create a new error instance, capturing the active function calls.
 	pub fn new(description: Str, context: Value) -> Self {
-		Self {
-			description,
-			context: Gc::new(GcCell::new(context)),
-		}
+		Self(
+			Gc::new(
+				ErrorData {
+					description,
+					context: GcCell::new(context),
+					trace: trace::capture(),
+				}
+			)
+		)
+	}
+
+	/// Create a new error instance with the given stack trace, such as the one of an error
+	/// received from another thread.
+	pub fn with_trace(description: Str, context: Value, trace: Box<[trace::Frame]>) -> Self {
+		Self(
+			Gc::new(
+				ErrorData {
+					description,
+					context: GcCell::new(context),
+					trace,
+				}
+			)
+		)
 	}
 
 	/// Shallow copy.
 	pub fn copy(&self) -> Self {
-		Self {
-			description: self.description.copy(),
-			context: self.context.clone(),
-		}
+		Self(self.0.clone())
 	}
 
 
-	/// Get the given property.
-	pub fn get(&self, key: &Value) -> Result<Value, IndexOutOfBounds> {
+	/// Get the given property. The interner is used to resolve the function names in the
+	/// stack trace.
+	pub fn get(&self, key: &Value, interner: &symbol::Interner) -> Result<Value, IndexOutOfBounds> {
 		thread_local! {
 			pub static DESCRIPTION: Value = "description".into();
 			pub static CONTEXT: Value = "context".into();
+			pub static TRACE: Value = "trace".into();
 		}
 
 		match key {
@@ -52,21 +81,78 @@ impl Error {
 
 			key if CONTEXT.with(|ctx| key == ctx) => Ok(
 				self.context
-					.deref()
 					.borrow()
 					.copy()
 			),
 
+			key if TRACE.with(|trace| key == trace) => Ok(self.trace_value(interner)),
+
 			_ => Err(IndexOutOfBounds)
 		}
 	}
+
+
+	/// Build the stack trace value, an array of dicts with the function name and the
+	/// position of each call, innermost first.
+	fn trace_value(&self, interner: &symbol::Interner) -> Value {
+		let frames: Vec<Value> = self.trace
+			.iter()
+			.map(
+				|frame| {
+					let path = interner
+						.resolve(frame.pos.path)
+						.unwrap_or_default();
+
+					let dict: Dict = vec![
+						("name".into(), frame.callee.fmt_string(interner).into()),
+						("path".into(), path.into()),
+						("line".into(), Value::Int(frame.pos.line.into())),
+						("column".into(), Value::Int(frame.pos.column.into())),
+					]
+					.into_iter()
+					.collect();
+
+					dict.into()
+				}
+			)
+			.collect();
+
+		frames.into()
+	}
+}
+
+
+impl Deref for Error {
+	type Target = ErrorData;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
 }
 
 
-impl PartialEq for Error {
+/// The stack trace is not considered in comparisons, so that errors with the same
+/// description and context are equal regardless of where they were created.
+impl PartialEq for ErrorData {
 	fn eq(&self, other: &Self) -> bool {
-		self.description == other.description
-			&& *self.context.deref().borrow() == *other.context.deref().borrow()
+		self.description == other.description && self.context == other.context
+	}
+}
+
+
+impl Eq for ErrorData { }
+
+
+impl PartialOrd for ErrorData {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+
+impl Ord for ErrorData {
+	fn cmp(&self, other: &Self) -> Ordering {
+		(&self.description, &self.context).cmp(&(&other.description, &other.context))
 	}
 }
 
@@ -74,7 +160,7 @@ impl PartialEq for Error {
 impl Hash for Error {
 	fn hash<H: Hasher>(&self, state: &mut H) {
 		self.description.hash(state);
-		self.context.deref().borrow().hash(state);
+		self.context.borrow().hash(state);
 	}
 }
 
@@ -1,10 +1,8 @@
-use std::ops::Deref;
-
 use crate::{
 	fmt::{self, Display},
 	symbol,
 };
-use super::{Array, Dict, Error, Float, Function, HushFun, RustFun, Str, Value};
+use super::{Array, Buffer, Dict, Error, Float, Function, HushFun, RustFun, Str, Value};
 
 
 impl std::fmt::Display for RustFun {
@@ -103,6 +101,19 @@ impl std::fmt::Display for Str {
 }
 
 
+impl std::fmt::Display for Buffer {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str("b\"")?;
+
+		for byte in self.borrow().iter() {
+			write!(f, "{}", std::ascii::escape_default(*byte))?;
+		}
+
+		f.write_str("\"")
+	}
+}
+
+
 impl<'a> Display<'a> for Error {
 	type Context = &'a symbol::Interner;
 
@@ -111,7 +122,7 @@ impl<'a> Display<'a> for Error {
 			f,
 			"error: {} ({})",
 			self.description,
-			fmt::Show(self.context.deref().borrow().copy(), context)
+			fmt::Show(self.context.borrow().copy(), context)
 		)
 	}
 }
@@ -130,6 +141,7 @@ impl<'a> Display<'a> for Value {
 			Self::String(string) => write!(f, "{}", string),
 			Self::Array(array) => write!(f, "{}", fmt::Show(array, context)),
 			Self::Dict(dict) => write!(f, "{}", fmt::Show(dict, context)),
+			Self::Buffer(buffer) => write!(f, "{}", buffer),
 			Self::Function(fun) => write!(f, "{}", fmt::Show(fun, context)),
 			Self::Error(error) => write!(f, "{}", fmt::Show(error, context)),
 		}
@@ -1,13 +1,16 @@
 use std::{
+	any::Any,
 	cmp::Ordering,
 	fmt::{self, Debug},
 	hash::{Hash, Hasher},
+	ops::Deref,
 };
 
 use gc::{Gc, GcCell, Finalize, Trace};
 
-use crate::symbol;
+use crate::symbol::{self, Symbol};
 use super::{
+	cycle,
 	mem,
 	program,
 	Panic,
@@ -60,48 +63,67 @@ impl<T: NativeFun> From<T> for Function {
 
 
 /// A function object implemented in Hush code.
-/// May contain captured variables.
+/// May contain captured variables. The function is shared, so that values which hold
+/// functions remain small.
 #[derive(Debug)]
 #[derive(Trace, Finalize)]
-pub struct HushFun {
+pub struct HushFun(Gc<HushFunData>);
+
+
+/// The contents of a function implemented in Hush code.
+#[derive(Debug)]
+#[derive(Trace, Finalize)]
+pub struct HushFunData {
+	/// The name of the variable or dict key the function was declared in, if any.
+	#[unsafe_ignore_trace]
+	pub name: Option<Symbol>,
 	/// How many parameters the function expects.
 	pub params: u32,
 	pub frame_info: &'static program::mem::FrameInfo,
 	pub body: &'static program::Block,
 	/// Captured variables, if any.
 	#[allow(clippy::type_complexity)]
-	pub context: Gc<Box<[(Gc<GcCell<Value>>, mem::SlotIx)]>>,
+	pub context: Box<[(Gc<GcCell<Value>>, mem::SlotIx)]>,
 	pub pos: SourcePos,
 }
 
 
 impl HushFun {
 	pub fn new (
+		name: Option<Symbol>,
 		params: u32,
 		frame_info: &'static program::mem::FrameInfo,
 		body: &'static program::Block,
 		context: Box<[(Gc<GcCell<Value>>, mem::SlotIx)]>,
 		pos: SourcePos,
 	) -> Self {
-		Self {
-			params,
-			frame_info,
-			body,
-			context: Gc::new(context),
-			pos,
-		}
+		Self(
+			Gc::new(
+				HushFunData {
+					name,
+					params,
+					frame_info,
+					body,
+					context,
+					pos,
+				}
+			)
+		)
 	}
 
 
 	/// Shallow copy.
 	pub fn copy(&self) -> Self {
-		Self {
-			params: self.params,
-			frame_info: self.frame_info,
-			body: self.body,
-			context: self.context.clone(),
-			pos: self.pos.copy(),
-		}
+		Self(self.0.clone())
+	}
+}
+
+
+impl Deref for HushFun {
+	type Target = HushFunData;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
 	}
 }
 
@@ -109,9 +131,14 @@ impl HushFun {
 impl PartialEq for HushFun {
 	fn eq(&self, other: &Self) -> bool {
 		// As the functions are defined in the source code, two functions can't share the same
-		// body.
+		// body. Functions may capture themselves, so their context may be cyclic.
 		std::ptr::eq(self.body, other.body)
-			&& self.context == other.context
+			&& cycle::compare(
+				self.0.deref() as *const HushFunData as usize,
+				other.0.deref() as *const HushFunData as usize,
+				true,
+				|| self.context == other.context,
+			)
 	}
 }
 
@@ -197,6 +224,11 @@ pub trait NativeFun: Trace + Finalize + 'static {
 	fn name(&self) -> &'static str;
 	/// Invoke the function.
 	fn call(&self, context: CallContext) -> Result<Value, Panic>;
+	/// Get the function as Any, for native functions which must be recognized by the
+	/// standard library, such as the methods of channels.
+	fn as_any(&self) -> Option<&dyn Any> {
+		None
+	}
 }
 
 
@@ -222,6 +254,14 @@ impl RustFun {
 	pub fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		self.0.call(context)
 	}
+
+
+	/// Get the implementation of the function, if it's of the given type.
+	pub fn downcast_ref<T: NativeFun>(&self) -> Option<&T> {
+		self.0
+			.as_any()?
+			.downcast_ref()
+	}
 }
 
 
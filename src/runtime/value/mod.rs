@@ -1,6 +1,9 @@
 #[macro_use]
 mod ops;
 mod array;
+mod buffer;
+mod cow;
+mod cycle;
 mod dict;
 mod error;
 mod errors;
@@ -14,16 +17,21 @@ use std::{ffi::OsString, fmt::Display};
 use gc::{Finalize, Trace};
 
 use super::{
+	finalize,
+	heap,
 	program,
 	mem,
+	trace,
 	Panic,
 	Runtime,
 	SourcePos,
 };
 pub use array::Array;
-pub use dict::{keys, Dict};
+pub use buffer::Buffer;
+pub use cow::Storage;
+pub use dict::{keys, Dict, DictMap};
 pub use error::Error;
-pub use function::{CallContext, Function, HushFun, RustFun, NativeFun};
+pub use function::{CallContext, Function, HushFun, HushFunData, RustFun, NativeFun};
 pub use float::Float;
 pub use errors::{EmptyCollection, IndexOutOfBounds};
 pub use string::Str;
@@ -41,6 +49,7 @@ pub enum Type {
 	String,
 	Array,
 	Dict,
+	Buffer,
 	Function,
 	Error,
 }
@@ -61,6 +70,7 @@ impl Type {
 			b"string" => Some(Self::String),
 			b"array" => Some(Self::Array),
 			b"dict" => Some(Self::Dict),
+			b"buffer" => Some(Self::Buffer),
 			b"function" => Some(Self::Function),
 			b"error" => Some(Self::Error),
 			_ => None,
@@ -79,6 +89,7 @@ impl Type {
 			Self::String => "string",
 			Self::Array => "array",
 			Self::Dict => "dict",
+			Self::Buffer => "buffer",
 			Self::Function => "function",
 			Self::Error => "error",
 		}
@@ -113,6 +124,8 @@ pub enum Value {
 	String(Str),
 	Array(Array),
 	Dict(Dict),
+	/// Buffers are mutable byte strings.
+	Buffer(Buffer),
 	Function(Function),
 	Error(Error),
 }
@@ -130,6 +143,7 @@ impl Value {
 			Self::String(string) => Self::String(string.copy()),
 			Self::Array(array) => Self::Array(array.copy()),
 			Self::Dict(dict) => Self::Dict(dict.copy()),
+			Self::Buffer(buffer) => Self::Buffer(buffer.copy()),
 			Self::Function(fun) => Self::Function(fun.copy()),
 			Self::Error(error) => Self::Error(error.copy())
 		}
@@ -147,6 +161,7 @@ impl Value {
 			Self::String(_) => Type::String,
 			Self::Array(_) => Type::Array,
 			Self::Dict(_) => Type::Dict,
+			Self::Buffer(_) => Type::Buffer,
 			Self::Function(_) => Type::Function,
 			Self::Error(_) => Type::Error,
 		}
@@ -180,6 +195,7 @@ from_variant!(Byte, u8);
 from_variant!(String, Str);
 from_variant!(Array, Array);
 from_variant!(Dict, Dict);
+from_variant!(Buffer, Buffer);
 from_variant!(Function, Function);
 from_variant!(Error, Error);
 
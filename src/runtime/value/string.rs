@@ -1,20 +1,37 @@
+#[cfg(windows)]
+use std::borrow::Cow;
 use std::{
+    cmp::Ordering,
     convert::TryInto,
     ffi::{OsString, OsStr},
-    ops::Deref,
-    os::unix::ffi::{OsStringExt, OsStrExt},
+    hash::{Hash, Hasher},
     path::PathBuf,
 };
 
 use gc::{Gc, Finalize, Trace};
 
-use super::{IndexOutOfBounds, Value};
+use crate::ffi::{self, OsStringExt};
+use super::{heap, IndexOutOfBounds, Value};
+
+
+/// The contents of a string, along the bytes accounted for them.
+#[derive(Debug)]
+#[derive(Trace, Finalize)]
+struct Contents {
+	string: Box<[u8]>,
+	bytes: heap::Bytes,
+	/// The lossy conversion to an operating system string, for strings which are not
+	/// valid WTF-8.
+	#[cfg(windows)]
+	#[unsafe_ignore_trace]
+	os_string: std::cell::OnceCell<OsString>,
+}
 
 
 /// Strings in Hush are immutable.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug)]
 #[derive(Trace, Finalize)]
-pub struct Str(Gc<Box<[u8]>>);
+pub struct Str(Gc<Contents>);
 
 
 impl Str {
@@ -36,7 +53,8 @@ impl Str {
 			.try_into()
 			.map_err(|_| IndexOutOfBounds)?;
 
-		self.0
+		self
+			.as_bytes()
 			.get(index)
 			.copied()
 			.map(Value::Byte)
@@ -46,13 +64,13 @@ impl Str {
 
 	/// Check if the collections contains the given value
 	pub fn contains(&self, byte: u8) -> bool {
-		self.0.contains(&byte)
+		self.as_bytes().contains(&byte)
 	}
 
 
 	/// Get the string length.
 	pub fn len(&self) -> usize {
-		self.0.len()
+		self.as_bytes().len()
 	}
 
 
@@ -65,23 +83,61 @@ impl Str {
 
 impl AsRef<[u8]> for Str {
 	fn as_ref(&self) -> &[u8] {
-		self.0.deref().deref()
+		&self.0.string
+	}
+}
+
+
+impl PartialEq for Str {
+	fn eq(&self, other: &Self) -> bool {
+		self.as_bytes() == other.as_bytes()
+	}
+}
+
+
+impl Eq for Str { }
+
+
+impl PartialOrd for Str {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+
+impl Ord for Str {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.as_bytes().cmp(other.as_bytes())
+	}
+}
+
+
+impl Hash for Str {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.as_bytes().hash(state)
 	}
 }
 
 
 impl AsRef<OsStr> for Str {
+	#[cfg(unix)]
+	fn as_ref(&self) -> &OsStr {
+		ffi::OsStrExt::from_bytes(self.as_bytes())
+	}
+
+	#[cfg(windows)]
 	fn as_ref(&self) -> &OsStr {
-		OsStr::from_bytes(self.as_ref())
+		match ffi::os_str(self.as_bytes()) {
+			Cow::Borrowed(string) => string,
+			Cow::Owned(string) => self.0.os_string.get_or_init(|| string),
+		}
 	}
 }
 
 
 impl<'a> From<&'a [u8]> for Str {
 	fn from(string: &'a [u8]) -> Self {
-		Self(
-			Gc::new(string.into())
-		)
+		Box::<[u8]>::from(string).into()
 	}
 }
 
@@ -89,7 +145,14 @@ impl<'a> From<&'a [u8]> for Str {
 impl From<Box<[u8]>> for Str {
 	fn from(string: Box<[u8]>) -> Self {
 		Self(
-			Gc::new(string)
+			Gc::new(
+				Contents {
+					bytes: heap::Bytes::new::<Contents, _>(&string),
+					string,
+					#[cfg(windows)]
+					os_string: Default::default(),
+				}
+			)
 		)
 	}
 }
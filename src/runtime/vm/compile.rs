@@ -0,0 +1,523 @@
+use crate::symbol;
+use super::{
+	super::Runtime,
+	program,
+	Chunk,
+	Instr,
+	Label,
+	Pos,
+	SourcePos,
+	Value,
+};
+
+
+/// The position of an expression, which is where panics in it's evaluation are reported.
+fn expr_pos(expr: &program::Expr) -> &program::SourcePos {
+	match expr {
+		program::Expr::Identifier { pos, .. } => pos,
+		program::Expr::Literal { pos, .. } => pos,
+		program::Expr::UnaryOp { pos, .. } => pos,
+		program::Expr::BinaryOp { pos, .. } => pos,
+		program::Expr::If { pos, .. } => pos,
+		program::Expr::Access { pos, .. } => pos,
+		program::Expr::Call { pos, .. } => pos,
+		program::Expr::CommandBlock { pos, .. } => pos,
+	}
+}
+
+
+/// The position of a statement, which is where it's execution is counted for coverage.
+pub fn statement_pos(statement: &program::Statement) -> Option<&program::SourcePos> {
+	match statement {
+		program::Statement::Assign { left: program::Lvalue::Identifier { pos, .. }, .. } => Some(pos),
+		program::Statement::Assign { left: program::Lvalue::Access { pos, .. }, .. } => Some(pos),
+		program::Statement::Return { expr } => Some(expr_pos(expr)),
+		program::Statement::Break => None,
+		program::Statement::While { condition, .. } => Some(expr_pos(condition)),
+		program::Statement::For { expr, .. } => Some(expr_pos(expr)),
+		program::Statement::Expr(expr) => Some(expr_pos(expr)),
+	}
+}
+
+
+/// A loop being compiled.
+#[derive(Debug)]
+struct Loop {
+	/// The amount of operands when the loop's body starts.
+	depth: u32,
+	/// The offsets of the break instructions, which jump to the end of the loop.
+	breaks: Vec<usize>,
+}
+
+
+/// A compiler of blocks to chunks. Every statement leaves exactly one value on the operand
+/// stack, so that the value of a block is the value of it's last statement.
+/// Expressions of constant operands are folded, and branches of constant conditions are
+/// only compiled if taken.
+#[derive(Debug)]
+pub struct Compiler<'a> {
+	interner: &'a symbol::Interner,
+	chunk: Chunk,
+	/// The amount of operands on the stack after the last instruction.
+	depth: u32,
+	loops: Vec<Loop>,
+	/// Whether the last statement may tail call.
+	tail: bool,
+	/// Whether to count the execution of statements.
+	coverage: bool,
+}
+
+
+impl<'a> Compiler<'a> {
+	pub fn new(tail: bool, coverage: bool, interner: &'a symbol::Interner) -> Self {
+		Self {
+			interner,
+			chunk: Chunk::default(),
+			depth: 0,
+			loops: Vec::new(),
+			tail,
+			coverage,
+		}
+	}
+
+
+	/// Compile a block, returning it's value.
+	pub fn compile(mut self, block: &'static program::Block) -> Chunk {
+		self.block(block, self.tail);
+		self.emit(Instr::Return, 0);
+
+		debug_assert_eq!(self.depth, 1);
+		debug_assert!(self.loops.is_empty());
+
+		self.chunk
+	}
+
+
+	/// The offset of the next instruction.
+	fn label(&self) -> Label {
+		self.chunk.code.len() as Label
+	}
+
+
+	/// Append an instruction, with the given effect on the amount of operands. Returns the
+	/// instruction's offset.
+	fn emit(&mut self, instr: Instr, effect: i32) -> usize {
+		self.chunk.code.push(instr);
+		self.depth = (self.depth as i32 + effect) as u32;
+		self.chunk.code.len() - 1
+	}
+
+
+	/// Point the jump at the given offset to the label.
+	fn patch(&mut self, offset: usize, label: Label) {
+		let instr = &mut self.chunk.code[offset];
+
+		match instr.target_mut() {
+			Some(target) => *target = label,
+			None => unreachable!("patching non-jump instruction: {:?}", instr),
+		}
+	}
+
+
+	/// Add a value to the constants table, returning it's index.
+	fn constant(&mut self, value: Value) -> u32 {
+		self.chunk.constants.push(value);
+		(self.chunk.constants.len() - 1) as u32
+	}
+
+
+	/// Push a value, which is added to the constants table unless it fits in an
+	/// instruction.
+	fn push(&mut self, value: Value) {
+		let instr = match value {
+			Value::Nil => Instr::Nil,
+			Value::Bool(b) => Instr::Bool(b),
+			Value::Int(int) => Instr::Int(int),
+			Value::Float(ref float) => Instr::Float(float.0),
+			Value::Byte(byte) => Instr::Byte(byte),
+			value => Instr::Constant(self.constant(value)),
+		};
+
+		self.emit(instr, 1);
+	}
+
+
+	/// The string of a symbol.
+	fn symbol(&self, symbol: symbol::Symbol) -> Value {
+		self.interner
+			.resolve(symbol)
+			.expect("unresolved symbol")
+			.into()
+	}
+
+
+	/// Evaluate an expression at compile time, if it's operands are constant. Operations
+	/// which would panic are left to the runtime, which reports them.
+	fn fold(&self, expr: &'static program::Expr) -> Option<Value> {
+		match expr {
+			program::Expr::Literal { literal, .. } => match literal {
+				program::Literal::Nil => Some(Value::Nil),
+				program::Literal::Bool(b) => Some(Value::Bool(*b)),
+				program::Literal::Int(int) => Some(Value::Int(*int)),
+				program::Literal::Float(float) => Some((*float).into()),
+				program::Literal::Byte(byte) => Some(Value::Byte(*byte)),
+				program::Literal::String(string) => Some(string.as_ref().into()),
+				program::Literal::Identifier(symbol) => Some(self.symbol(*symbol)),
+				// Arrays, dicts and functions are mutable, and must be created on every evaluation.
+				_ => None,
+			},
+
+			program::Expr::UnaryOp { op: program::UnaryOp::Try, .. } => None,
+
+			program::Expr::UnaryOp { op, operand, .. } => {
+				let value = self.fold(operand)?;
+				Runtime::unary_op(op, value, expr_pos(operand).into()).ok()
+			}
+
+			program::Expr::BinaryOp { left, op, right, pos } => match op {
+				program::BinaryOp::And | program::BinaryOp::Or => {
+					let and = matches!(op, program::BinaryOp::And);
+
+					match self.fold(left)? {
+						// The right operand is not evaluated.
+						Value::Bool(b) if b != and => Some(Value::Bool(b)),
+						Value::Bool(_) => match self.fold(right)? {
+							right @ Value::Bool(_) => Some(right),
+							_ => None,
+						},
+						_ => None,
+					}
+				}
+
+				_ => {
+					let left_value = self.fold(left)?;
+					let right_value = self.fold(right)?;
+
+					Runtime::binary_op(
+						op,
+						(left_value, expr_pos(left).into()),
+						(right_value, expr_pos(right).into()),
+						pos.into(),
+					).ok()
+				}
+			},
+
+			_ => None,
+		}
+	}
+
+
+	/// Evaluate a condition at compile time, if it's constant.
+	fn fold_condition(&self, condition: &'static program::Expr) -> Option<bool> {
+		match self.fold(condition) {
+			Some(Value::Bool(b)) => Some(b),
+			_ => None,
+		}
+	}
+
+
+	/// Add positions to the table, returning the index of the first.
+	fn positions<const N: usize>(&mut self, positions: [&program::SourcePos; N]) -> Pos {
+		let pos = self.chunk.positions.len() as Pos;
+
+		self.chunk.positions.extend(
+			IntoIterator::into_iter(positions).map(SourcePos::from)
+		);
+
+		pos
+	}
+
+
+	fn block(&mut self, block: &'static program::Block, tail: bool) {
+		match block.0.split_last() {
+			Some((last, statements)) => {
+				for statement in statements.iter() {
+					self.statement(statement, false);
+					self.emit(Instr::Pop, -1);
+				}
+
+				self.statement(last, tail);
+			}
+
+			None => {
+				self.emit(Instr::Nil, 1);
+			}
+		}
+	}
+
+
+	fn statement(&mut self, statement: &'static program::Statement, tail: bool) {
+		if self.coverage {
+			if let Some(pos) = statement_pos(statement) {
+				let pos = self.positions([ pos ]);
+				self.emit(Instr::Count(pos), 0);
+			}
+		}
+
+		match statement {
+			program::Statement::Assign { left, right } => {
+				self.expr(right, false);
+
+				match left {
+					program::Lvalue::Identifier { slot_ix, .. } => {
+						self.emit(Instr::Store(slot_ix.0), -1);
+					}
+
+					program::Lvalue::Access { object, field, pos } => {
+						self.expr(object, false);
+						self.expr(field, false);
+						let pos = self.positions([ pos, expr_pos(object), expr_pos(field) ]);
+						self.emit(Instr::Assign(pos), -3);
+					}
+				}
+
+				self.emit(Instr::Nil, 1);
+			}
+
+			program::Statement::Return { expr } => {
+				self.expr(expr, tail);
+				self.emit(Instr::Return, 0);
+			}
+
+			program::Statement::Break => {
+				let depth = self.loops
+					.last()
+					.expect("break outside loop")
+					.depth;
+
+				// The break leaves no value, but the following code is unreachable.
+				let offset = self.emit(Instr::Break { target: 0, depth }, 1);
+
+				if let Some(current) = self.loops.last_mut() {
+					current.breaks.push(offset);
+				}
+			}
+
+			program::Statement::While { condition, block } => {
+				match self.fold_condition(condition) {
+					Some(false) => (),
+
+					Some(true) => {
+						let start = self.label();
+						self.loop_body(block, start, None);
+					}
+
+					None => {
+						let start = self.label();
+
+						self.expr(condition, false);
+						let pos = self.positions([ expr_pos(condition) ]);
+						let exit = self.emit(Instr::JumpUnless(0, pos), -1);
+
+						self.loop_body(block, start, Some(exit));
+					}
+				}
+
+				self.emit(Instr::Nil, 1);
+			}
+
+			program::Statement::For { slot_ix, expr, block } => {
+				self.expr(expr, false);
+				let pos = self.positions([ expr_pos(expr) ]);
+				self.emit(Instr::Iterate(pos), 0);
+
+				let start = self.label();
+				let exit = self.emit(Instr::Next { slot: slot_ix.0, target: 0, pos }, 0);
+
+				self.loop_body(block, start, Some(exit));
+
+				// Discard the iterator.
+				self.emit(Instr::Pop, -1);
+				self.emit(Instr::Nil, 1);
+			}
+
+			program::Statement::Expr(expr) => self.expr(expr, tail),
+		}
+	}
+
+
+	/// Compile the body of a loop, which jumps back to the start. The exit jump and breaks
+	/// are pointed to the end of the loop.
+	fn loop_body(&mut self, block: &'static program::Block, start: Label, exit: Option<usize>) {
+		self.loops.push(Loop { depth: self.depth, breaks: Vec::new() });
+
+		self.block(block, false);
+		self.emit(Instr::Pop, -1);
+		self.emit(Instr::Jump(start), 0);
+
+		let end = self.label();
+		let current = self.loops.pop().expect("loop was pushed");
+
+		if let Some(exit) = exit {
+			self.patch(exit, end);
+		}
+
+		for offset in current.breaks {
+			self.patch(offset, end);
+		}
+	}
+
+
+	fn literal(&mut self, literal: &'static program::Literal, pos: &program::SourcePos) {
+		match literal {
+			program::Literal::Nil => self.emit(Instr::Nil, 1),
+			program::Literal::Bool(b) => self.emit(Instr::Bool(*b), 1),
+			program::Literal::Int(int) => self.emit(Instr::Int(*int), 1),
+			program::Literal::Float(float) => self.emit(Instr::Float(*float), 1),
+			program::Literal::Byte(byte) => self.emit(Instr::Byte(*byte), 1),
+			program::Literal::String(string) => {
+				let ix = self.constant(string.as_ref().into());
+				self.emit(Instr::Constant(ix), 1)
+			}
+
+			program::Literal::Array(exprs) => {
+				for expr in exprs.iter() {
+					self.expr(expr, false);
+				}
+
+				self.emit(Instr::Array(exprs.len() as u32), 1 - exprs.len() as i32)
+			}
+
+			program::Literal::Dict(entries) => {
+				for (_, expr) in entries.iter() {
+					self.expr(expr, false);
+				}
+
+				// Keys are resolved once, at compile time.
+				let keys = self.chunk.constants.len() as u32;
+				for (symbol, _) in entries.iter() {
+					let key = self.symbol(*symbol);
+					self.constant(key);
+				}
+
+				let len = entries.len() as u32;
+				self.emit(Instr::Dict { keys, len }, 1 - len as i32)
+			}
+
+			program::Literal::Function { .. } => {
+				let pos = self.positions([ pos ]);
+				self.emit(Instr::Function(literal, pos), 1)
+			}
+
+			program::Literal::Identifier(symbol) => {
+				let ix = self.constant(self.symbol(*symbol));
+				self.emit(Instr::Constant(ix), 1)
+			}
+		};
+	}
+
+
+	fn expr(&mut self, expr: &'static program::Expr, tail: bool) {
+		if let program::Expr::UnaryOp { .. } | program::Expr::BinaryOp { .. } = expr {
+			if let Some(value) = self.fold(expr) {
+				self.push(value);
+				return;
+			}
+		}
+
+		match expr {
+			program::Expr::Identifier { slot_ix, .. } => {
+				self.emit(Instr::Load(slot_ix.0), 1);
+			}
+
+			program::Expr::Literal { literal, pos } => self.literal(literal, pos),
+
+			program::Expr::UnaryOp { op, operand, .. } => {
+				self.expr(operand, false);
+				let pos = self.positions([ expr_pos(operand) ]);
+				self.emit(Instr::Unary(op, pos), 0);
+			}
+
+			program::Expr::BinaryOp { left, op, right, pos } => match op {
+				program::BinaryOp::And | program::BinaryOp::Or => {
+					let and = matches!(op, program::BinaryOp::And);
+
+					self.expr(left, false);
+					let left_pos = self.positions([ expr_pos(left) ]);
+					let logic = self.emit(Instr::Logic { and, target: 0, pos: left_pos }, -1);
+
+					self.expr(right, false);
+					let right_pos = self.positions([ expr_pos(right) ]);
+					self.emit(Instr::CheckBool(right_pos), 0);
+
+					let end = self.label();
+					self.patch(logic, end);
+				}
+
+				_ => {
+					self.expr(left, false);
+					self.expr(right, false);
+					let pos = self.positions([ pos, expr_pos(left), expr_pos(right) ]);
+					self.emit(Instr::Binary(op, pos), -1);
+				}
+			},
+
+			program::Expr::If { condition, then, otherwise, .. } => {
+				if let Some(condition) = self.fold_condition(condition) {
+					self.block(if condition { then } else { otherwise }, false);
+					return;
+				}
+
+				self.expr(condition, false);
+				let pos = self.positions([ expr_pos(condition) ]);
+				let otherwise_jump = self.emit(Instr::JumpUnless(0, pos), -1);
+
+				self.block(then, false);
+				let end_jump = self.emit(Instr::Jump(0), 0);
+
+				// Only one of the branches is executed.
+				self.depth -= 1;
+
+				let otherwise_label = self.label();
+				self.patch(otherwise_jump, otherwise_label);
+
+				self.block(otherwise, false);
+
+				let end = self.label();
+				self.patch(end_jump, end);
+			}
+
+			program::Expr::Access { object, field, pos } => {
+				self.expr(object, false);
+				self.expr(field, false);
+				let pos = self.positions([ pos, expr_pos(object), expr_pos(field) ]);
+				self.emit(Instr::Access(pos), -1);
+			}
+
+			program::Expr::Call { function, args, pos } => {
+				match &**function {
+					// Method calls receive the object as self.
+					program::Expr::Access { object, field, pos } => {
+						self.expr(object, false);
+						self.expr(field, false);
+						let pos = self.positions([ pos, expr_pos(object), expr_pos(field) ]);
+						self.emit(Instr::Method(pos), 0);
+					}
+
+					function => {
+						self.emit(Instr::Nil, 1);
+						self.expr(function, false);
+					}
+				}
+
+				let function_pos = self.positions([ expr_pos(function) ]);
+				self.emit(Instr::Callable(function_pos), 0);
+
+				for arg in args.iter() {
+					self.expr(arg, false);
+				}
+
+				let pos = self.positions([ pos ]);
+				self.emit(
+					Instr::Call { args: args.len() as u32, pos, tail },
+					-(args.len() as i32 + 1)
+				);
+			}
+
+			program::Expr::CommandBlock { block, pos } => {
+				let pos = self.positions([ pos ]);
+				self.emit(Instr::CommandBlock(block, pos), 1);
+			}
+		}
+	}
+}
@@ -0,0 +1,355 @@
+use std::rc::Rc;
+
+use super::{
+	super::{
+		finalize,
+		lib,
+		mem,
+		value::keys,
+		Array,
+		Dict,
+		HushFun,
+		Panic,
+		Runtime,
+		SourcePos,
+		Value,
+	},
+	program,
+	Chunk,
+	Instr,
+	Pos,
+};
+
+
+impl Runtime {
+	/// Get the compiled body of a function, compiling it on the first call.
+	pub(in crate::runtime) fn function_chunk(&mut self, body: &'static program::Block) -> Rc<Chunk> {
+		let interner = &self.interner;
+		let coverage = self.coverage.is_some();
+
+		self.chunks
+			.entry(body as *const program::Block)
+			.or_insert_with(|| Rc::new(Chunk::function(body, coverage, interner)))
+			.clone()
+	}
+
+
+	/// Execute a chunk, returning it's value. The given function is called right before the
+	/// tail call, if any.
+	pub(in crate::runtime) fn run<F>(&mut self, chunk: &Chunk, tail_call: F) -> Result<Value, Panic>
+	where
+		F: FnOnce(&mut Self),
+	{
+		let base = self.operands.len();
+
+		let result = self.execute(chunk, base, tail_call);
+
+		// Operands remain on the stack when returning early or panicking.
+		self.operands.truncate(base);
+
+		result
+	}
+
+
+	fn pop(&mut self) -> Value {
+		self.operands.pop().expect("operand stack underflow")
+	}
+
+
+	/// Run the finalizers of the values collected so far. Values may be collected at any
+	/// allocation, so finalizers are deferred to the next function call.
+	fn finalize(&mut self, pos: &SourcePos) -> Result<(), Panic> {
+		for finalizer in finalize::pending() {
+			let args_start = self.arguments.len();
+			self.call(Value::default(), &finalizer, args_start, pos.copy())?;
+		}
+
+		Ok(())
+	}
+
+
+	fn execute<F>(&mut self, chunk: &Chunk, base: usize, tail_call: F) -> Result<Value, Panic>
+	where
+		F: FnOnce(&mut Self),
+	{
+		let mut tail_call = Some(tail_call);
+		let pos = |pos: Pos| chunk.positions[pos as usize].copy();
+		let mut pc = 0;
+
+		loop {
+			self.meter.tick().map_err(Panic::limit_exceeded)?;
+
+			if let Some(profiler) = &mut self.profiler {
+				profiler.poll();
+			}
+
+			let instr = &chunk.code[pc];
+			pc += 1;
+
+			match instr {
+				Instr::Nil => self.operands.push(Value::Nil),
+				Instr::Bool(b) => self.operands.push((*b).into()),
+				Instr::Int(int) => self.operands.push((*int).into()),
+				Instr::Float(float) => self.operands.push((*float).into()),
+				Instr::Byte(byte) => self.operands.push((*byte).into()),
+				Instr::Constant(ix) => self.operands.push(chunk.constants[*ix as usize].copy()),
+
+				Instr::Array(len) => {
+					let items = self.operands.split_off(self.operands.len() - *len as usize);
+					self.operands.push(Array::new(items).into());
+				}
+
+				Instr::Dict { keys, len } => {
+					let keys = &chunk.constants[*keys as usize .. (*keys + *len) as usize];
+					let values = self.operands.split_off(self.operands.len() - *len as usize);
+
+					let dict: Dict = keys
+						.iter()
+						.map(Value::copy)
+						.zip(values)
+						.collect();
+
+					self.operands.push(dict.into());
+				}
+
+				Instr::Function(literal, function_pos) => {
+					let (name, params, frame_info, body) = match *literal {
+						program::Literal::Function { name, params, frame_info, body } => (name, params, frame_info, body),
+						_ => unreachable!("function instruction with non-function literal"),
+					};
+
+					let context = frame_info
+						.captures
+						.iter()
+						.map(
+							|capture| (
+								self.stack.capture(capture.from.into()),
+								capture.to.into(),
+							)
+						)
+						.collect();
+
+					let function = HushFun::new(*name, *params, frame_info, body, context, pos(*function_pos));
+
+					self.operands.push(function.into());
+				}
+
+				Instr::Load(slot_ix) => {
+					let value = self.stack.fetch(mem::SlotIx(*slot_ix));
+					self.operands.push(value);
+				}
+
+				Instr::Store(slot_ix) => {
+					let value = self.pop();
+					self.stack.store(mem::SlotIx(*slot_ix), value);
+				}
+
+				Instr::Pop => {
+					self.pop();
+				}
+
+				Instr::Unary(program::UnaryOp::Try, _) => {
+					if let Some(Value::Error(_)) = self.operands.last() {
+						return Ok(self.pop());
+					}
+				}
+
+				Instr::Unary(op, operand_pos) => {
+					let operand = self.pop();
+					let value = Self::unary_op(op, operand, pos(*operand_pos))?;
+					self.operands.push(value);
+				}
+
+				Instr::Binary(op, positions) => {
+					let right = self.pop();
+					let left = self.pop();
+
+					let value = Self::binary_op(
+						op,
+						(left, pos(*positions + 1)),
+						(right, pos(*positions + 2)),
+						pos(*positions),
+					)?;
+
+					self.operands.push(value);
+				}
+
+				Instr::Logic { and, target, pos: left_pos } => match (self.pop(), *and) {
+					(Value::Bool(false), true) | (Value::Bool(true), false) => {
+						self.operands.push(Value::Bool(!*and));
+						pc = *target as usize;
+					}
+
+					(Value::Bool(_), _) => (),
+
+					(left, _) => return Err(Panic::type_error(left, "bool", pos(*left_pos))),
+				},
+
+				Instr::CheckBool(right_pos) => {
+					if !matches!(self.operands.last(), Some(Value::Bool(_))) {
+						let right = self.pop();
+						return Err(Panic::type_error(right, "bool", pos(*right_pos)));
+					}
+				}
+
+				Instr::Jump(target) => pc = *target as usize,
+
+				Instr::JumpUnless(target, condition_pos) => match self.pop() {
+					Value::Bool(true) => (),
+					Value::Bool(false) => pc = *target as usize,
+					value => return Err(Panic::invalid_condition(value, pos(*condition_pos))),
+				},
+
+				Instr::Access(positions) => {
+					let field = self.pop();
+					let obj = self.pop();
+
+					let value = self.access(
+						(&obj, pos(*positions + 1)),
+						(field, pos(*positions + 2)),
+					)?;
+
+					self.operands.push(value);
+				}
+
+				Instr::Method(positions) => {
+					let field = self.pop();
+					let obj = self.pop();
+
+					let value = self.access(
+						(&obj, pos(*positions + 1)),
+						(field, pos(*positions + 2)),
+					)?;
+
+					self.operands.push(obj);
+					self.operands.push(value);
+				}
+
+				Instr::Assign(positions) => {
+					let field = self.pop();
+					let obj = self.pop();
+					let value = self.pop();
+
+					self.assign(
+						(obj, pos(*positions + 1)),
+						(field, pos(*positions + 2)),
+						value,
+						pos(*positions),
+					)?;
+				}
+
+				Instr::Callable(function_pos) => {
+					if !matches!(self.operands.last(), Some(Value::Function(_))) {
+						let value = self.pop();
+						return Err(Panic::invalid_call(value, pos(*function_pos)));
+					}
+				}
+
+				Instr::Call { args, pos: call_pos, tail } => {
+					let call_pos = pos(*call_pos);
+					self.finalize(&call_pos)?;
+
+					let args_start = self.arguments.len();
+					let args_offset = self.operands.len() - *args as usize;
+					self.arguments.extend(self.operands.drain(args_offset ..));
+
+					let function = match self.pop() {
+						Value::Function(ref function) => function.copy(),
+						_ => unreachable!("callable was checked"),
+					};
+
+					let obj = self.pop();
+
+					if *tail {
+						if let Some(tail_call) = tail_call.take() {
+							tail_call(self);
+						}
+					}
+
+					let value = self.call(obj, &function, args_start, call_pos)?;
+					self.operands.push(value);
+				}
+
+				Instr::CommandBlock(block, block_pos) => {
+					let block_pos = pos(*block_pos);
+
+					// Command blocks are sampled precisely, so that the time spent in external
+					// commands is attributed to the block.
+					if let Some(profiler) = &mut self.profiler {
+						profiler.sample(None);
+					}
+
+					let result = self.eval_command_block(*block, block_pos.copy());
+
+					if let Some(profiler) = &mut self.profiler {
+						profiler.sample(Some(block_pos));
+					}
+
+					self.operands.push(result?);
+				}
+
+				Instr::Iterate(expr_pos) => {
+					// Besides iterator functions, arrays, dicts and strings may be iterated directly.
+					let value = self.pop();
+
+					match lib::iterator(&value) {
+						Some(iter) => self.operands.push(Value::Function(iter)),
+						None => return Err(
+							Panic::type_error(value, "function, array, dict or string", pos(*expr_pos))
+						),
+					}
+				}
+
+				Instr::Next { slot, target, pos: expr_pos } => {
+					let iter = match self.operands.last() {
+						Some(Value::Function(iter)) => iter.copy(),
+						_ => unreachable!("iterator was checked"),
+					};
+
+					let iter_pos = pos(*expr_pos);
+
+					let args_start = self.arguments.len();
+					let dict = match self.call(Value::default(), &iter, args_start, iter_pos.copy())? {
+						Value::Dict(ref dict) => dict.copy(),
+						other => return Err(Panic::type_error(other, "dict", iter_pos)),
+					};
+
+					let finished = keys::FINISHED.with(
+						|finished| dict
+							.get(finished)
+							.map_err(|_| Panic::index_out_of_bounds(finished.copy(), iter_pos.copy()))
+					)?;
+
+					match finished {
+						Value::Bool(false) => {
+							let value = keys::VALUE.with(
+								|value| dict
+									.get(value)
+									.map_err(|_| Panic::index_out_of_bounds(value.copy(), iter_pos.copy()))
+							)?;
+
+							self.stack.store(mem::SlotIx(*slot), value);
+						}
+
+						Value::Bool(true) => pc = *target as usize,
+
+						other => return Err(Panic::type_error(other, "bool", iter_pos)),
+					}
+				}
+
+				Instr::Break { target, depth } => {
+					self.operands.truncate(base + *depth as usize);
+					pc = *target as usize;
+				}
+
+				Instr::Return => return Ok(self.pop()),
+
+				Instr::Count(count_pos) => {
+					if let Some(coverage) = &mut self.coverage {
+						coverage.hit(&chunk.positions[*count_pos as usize]);
+					}
+				}
+			}
+		}
+	}
+}
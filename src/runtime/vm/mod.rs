@@ -0,0 +1,155 @@
+//! The bytecode virtual machine. Blocks of the semantically checked program are compiled
+//! to a flat sequence of instructions, which operate on a stack of operands. Local
+//! variables remain in the runtime's slot stack, so that closures and command blocks are
+//! unaffected.
+
+mod compile;
+mod exec;
+mod peephole;
+
+use crate::symbol;
+use super::{program, SourcePos, Value};
+pub use compile::statement_pos;
+
+
+/// An index into the positions table of a chunk. Instructions which may panic at multiple
+/// positions refer to consecutive entries.
+pub type Pos = u32;
+
+
+/// An instruction offset in a chunk.
+pub type Label = u32;
+
+
+/// A bytecode instruction.
+#[derive(Debug)]
+pub enum Instr {
+	/// Push nil.
+	Nil,
+	/// Push a bool.
+	Bool(bool),
+	/// Push an int.
+	Int(i64),
+	/// Push a float.
+	Float(f64),
+	/// Push a byte.
+	Byte(u8),
+	/// Push a value of the constants table. Strings, identifiers and folded expressions are
+	/// constants.
+	Constant(u32),
+	/// Pop the given amount of values, and push an array of them.
+	Array(u32),
+	/// Pop a value for each key, and push a dict of them. The keys are consecutive entries
+	/// of the constants table, starting at the given index.
+	Dict { keys: u32, len: u32 },
+	/// Push a function, capturing it's closed-over variables.
+	Function(&'static program::Literal, Pos),
+	/// Push the value of a slot.
+	Load(u32),
+	/// Pop a value into a slot.
+	Store(u32),
+	/// Discard the top value.
+	Pop,
+	/// Apply an unary operator to the top value. The position is the operand's. The try
+	/// operator returns errors from the function.
+	Unary(&'static program::UnaryOp, Pos),
+	/// Pop the right and left operands, and push the result of the operator. The positions
+	/// are the operator's, the left operand's and the right operand's.
+	Binary(&'static program::BinaryOp, Pos),
+	/// Check the left operand of a logical operator, which is popped. If it determines the
+	/// result, push it and jump to the label.
+	Logic { and: bool, target: Label, pos: Pos },
+	/// Check that the top value, the right operand of a logical operator, is a bool.
+	CheckBool(Pos),
+	/// Jump to the label.
+	Jump(Label),
+	/// Pop a condition, and jump to the label if it's false.
+	JumpUnless(Label, Pos),
+	/// Pop the field and the object, and push the field's value. The positions are the
+	/// access', the object's and the field's.
+	Access(Pos),
+	/// Like access, but push the object before the field's value, to be used as self in a
+	/// method call.
+	Method(Pos),
+	/// Pop the field, the object and the value, and assign the value to the field. The
+	/// positions are the l-value's, the object's and the field's.
+	Assign(Pos),
+	/// Check that the top value is a function, so that it can be called.
+	Callable(Pos),
+	/// Pop the arguments, the function and self, and push the result of the call. Tail calls
+	/// release the frame of the current function before calling.
+	Call { args: u32, pos: Pos, tail: bool },
+	/// Execute a command block, and push it's result.
+	CommandBlock(&'static program::CommandBlock, Pos),
+	/// Replace the top value by it's iterator function.
+	Iterate(Pos),
+	/// Call the iterator on the top of the stack, storing the next value in the slot, or
+	/// jumping to the label when finished.
+	Next { slot: u32, target: Label, pos: Pos },
+	/// Discard the operands of the current loop, and jump to the label.
+	Break { target: Label, depth: u32 },
+	/// Return the top value.
+	Return,
+	/// Count the execution of the statement at the position, for coverage.
+	Count(Pos),
+}
+
+
+impl Instr {
+	/// Get the label of jump instructions.
+	fn target_mut(&mut self) -> Option<&mut Label> {
+		match self {
+			Instr::Logic { target, .. }
+				| Instr::JumpUnless(target, _)
+				| Instr::Jump(target)
+				| Instr::Next { target, .. }
+				| Instr::Break { target, .. } => Some(target),
+
+			_ => None,
+		}
+	}
+}
+
+
+/// A compiled block.
+#[derive(Debug, Default)]
+pub struct Chunk {
+	code: Vec<Instr>,
+	positions: Vec<SourcePos>,
+	constants: Vec<Value>,
+}
+
+
+impl Chunk {
+	/// Compile the body of a function, in which the last call may be a tail call. With
+	/// coverage, the execution of statements is counted.
+	pub fn function(
+		body: &'static program::Block,
+		coverage: bool,
+		interner: &symbol::Interner,
+	) -> Self {
+		Self::compile(body, true, coverage, interner)
+	}
+
+
+	/// Compile the statements of a program.
+	pub fn program(
+		statements: &'static program::Block,
+		coverage: bool,
+		interner: &symbol::Interner,
+	) -> Self {
+		Self::compile(statements, false, coverage, interner)
+	}
+
+
+	fn compile(
+		block: &'static program::Block,
+		tail: bool,
+		coverage: bool,
+		interner: &symbol::Interner,
+	) -> Self {
+		let mut chunk = compile::Compiler::new(tail, coverage, interner).compile(block);
+		peephole::optimize(&mut chunk);
+		chunk
+	}
+}
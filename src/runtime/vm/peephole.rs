@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+
+use super::{Chunk, Instr, Label};
+
+
+/// The maximum amount of jumps followed when threading, which bounds infinite loops of
+/// jumps.
+const MAX_THREADING: usize = 16;
+
+
+/// Apply peephole optimizations to a compiled chunk:
+/// - Jumps to unconditional jumps are redirected to their final label.
+/// - Values which are pushed and immediately discarded are not pushed at all. This is the
+///   case for the nil value of non-last statements, such as assignments.
+pub fn optimize(chunk: &mut Chunk) {
+	thread_jumps(chunk);
+	remove_discarded(chunk);
+}
+
+
+/// Redirect jumps to unconditional jumps to the latter's label.
+fn thread_jumps(chunk: &mut Chunk) {
+	for ix in 0 .. chunk.code.len() {
+		let mut label = match chunk.code[ix].target_mut() {
+			Some(target) => *target,
+			None => continue,
+		};
+
+		for _ in 0 .. MAX_THREADING {
+			match chunk.code.get(label as usize) {
+				Some(Instr::Jump(next)) if *next != label => label = *next,
+				_ => break,
+			}
+		}
+
+		if let Some(target) = chunk.code[ix].target_mut() {
+			*target = label;
+		}
+	}
+}
+
+
+/// Remove instructions which push a value that is immediately discarded, along with the
+/// pop instruction. Pops which are the target of jumps must be preserved, as the value
+/// to be discarded may be pushed elsewhere.
+fn remove_discarded(chunk: &mut Chunk) {
+	let targets: HashSet<Label> = chunk.code
+		.iter_mut()
+		.filter_map(|instr| instr.target_mut().map(|target| *target))
+		.collect();
+
+	let mut removed = vec![false; chunk.code.len()];
+	let mut ix = 0;
+
+	while ix + 1 < chunk.code.len() {
+		let pure = matches!(
+			chunk.code[ix],
+			Instr::Nil
+				| Instr::Bool(_)
+				| Instr::Int(_)
+				| Instr::Float(_)
+				| Instr::Byte(_)
+				| Instr::Constant(_)
+				| Instr::Load(_)
+		);
+
+		let pop = matches!(chunk.code[ix + 1], Instr::Pop)
+			&& !targets.contains(&((ix + 1) as Label));
+
+		if pure && pop {
+			removed[ix] = true;
+			removed[ix + 1] = true;
+			ix += 2;
+		} else {
+			ix += 1;
+		}
+	}
+
+	if !removed.contains(&true) {
+		return;
+	}
+
+	// The new offset of each instruction. Removed instructions are mapped to the next
+	// instruction, which is equivalent as they have no effect.
+	let mut offsets = Vec::with_capacity(chunk.code.len() + 1);
+	let mut offset = 0;
+	for removed in &removed {
+		offsets.push(offset);
+		if !removed {
+			offset += 1;
+		}
+	}
+	offsets.push(offset);
+
+	let code = std::mem::take(&mut chunk.code);
+	chunk.code = code
+		.into_iter()
+		.zip(removed)
+		.filter(|(_, removed)| !removed)
+		.map(|(mut instr, _)| {
+			if let Some(target) = instr.target_mut() {
+				*target = offsets[*target as usize];
+			}
+			instr
+		})
+		.collect();
+}
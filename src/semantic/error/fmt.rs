@@ -52,6 +52,8 @@ impl<'a> Display<'a> for ErrorKind {
 			Self::InvalidAssignment => write!(f, "invalid assignment"),
 
 			Self::AsyncBuiltin => write!(f, "use of built-in command in async context"),
+
+			Self::InvalidSplice => write!(f, "array splice must be a whole argument"),
 		}
 	}
 }
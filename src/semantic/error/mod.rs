@@ -27,6 +27,8 @@ pub enum ErrorKind {
 	/// Built-in command used in async context.
 	/// Async contexts include pipes, redirections and capture or async blocks.
 	AsyncBuiltin,
+	/// Array splice (${name...}) combined with other parts in a single argument.
+	InvalidSplice,
 }
 
 
@@ -120,6 +122,15 @@ impl Error {
 			pos
 		}
 	}
+
+
+	/// Array splice combined with other parts in a single argument.
+	pub fn invalid_splice(pos: SourcePos) -> Self {
+		Self {
+			kind: ErrorKind::InvalidSplice,
+			pos
+		}
+	}
 }
 
 
@@ -32,7 +32,7 @@ use program::{
 	RedirectionTarget,
 	Statement,
 };
-pub use error::{Error, ErrorKind, Errors, ErrorsDisplayContext};
+pub use error::{Error, Errors, ErrorsDisplayContext};
 
 
 /// Static semantic analyzer.
@@ -67,7 +67,7 @@ impl<'a> Analyzer<'a> {
 
 		let (result, root_frame) = {
 			let mut analyzer = Analyzer::new(interner, &mut scope, &mut dict_keys, &mut errors);
-			let result = analyzer.analyze_block(ast.statements);
+			let result = analyzer.analyze_block(&ast.statements);
 			let root_frame = analyzer.exit_frame();
 			(result, root_frame)
 			// Drop analyzer before proceeding, making sure everything is clean.
@@ -89,14 +89,14 @@ impl<'a> Analyzer<'a> {
 
 	/// Analyze a block.
 	/// None is returned if any error is detected.
-	fn analyze_block(&mut self, block: ast::Block) -> Option<Block> {
-		match block {
+	fn analyze_block(&mut self, block: &ast::Block) -> Option<Block> {
+		match *block {
 			ast::Block::IllFormed => None,
 
 			ast::Block::Block(block) => {
 				let block = self.analyze_items(
 					Self::analyze_statement,
-					block.into_vec(), // Use vec's owned iterator.
+					block.iter(),
 				)?;
 
 				Some(block.into())
@@ -107,10 +107,10 @@ impl<'a> Analyzer<'a> {
 
 	/// Analyze a statement.
 	/// None is returned if any error is detected.
-	fn analyze_statement(&mut self, statement: ast::Statement) -> Option<Statement> {
-		match statement {
+	fn analyze_statement(&mut self, statement: &ast::Statement) -> Option<Statement> {
+		match *statement {
 			// Let.
-			ast::Statement::Let { identifier, init, pos } => {
+			ast::Statement::Let { identifier, ref init, pos } => {
 				let slot_ix = self.scope
 					.declare(identifier, pos)
 					.map_err(
@@ -118,7 +118,7 @@ impl<'a> Analyzer<'a> {
 					)
 					.ok();
 
-				let init = self.analyze_expr(init);
+				let init = self.analyze_binding(init, identifier);
 
 				let (slot_ix, right) = slot_ix.zip(init)?;
 
@@ -128,7 +128,7 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// Assign.
-			ast::Statement::Assign { left, right, pos } => {
+			ast::Statement::Assign { ref left, ref right, pos } => {
 				let left = self
 					.analyze_lvalue(left)
 					.map_err(
@@ -146,7 +146,7 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// Return.
-			ast::Statement::Return { expr, pos } => {
+			ast::Statement::Return { ref expr, pos } => {
 				let ret =
 					if self.in_function {
 						Some(())
@@ -173,7 +173,7 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// While.
-			ast::Statement::While { condition, block, .. } => {
+			ast::Statement::While { ref condition, ref block, .. } => {
 				let condition = self.analyze_expr(condition);
 				let block = {
 					self.enter_loop().analyze_block(block)
@@ -185,7 +185,7 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// For.
-			ast::Statement::For { identifier, expr, block, pos } => {
+			ast::Statement::For { identifier, ref expr, ref block, pos } => {
 				let expr = self.analyze_expr(expr);
 				let id_block = {
 					let mut analyzer = self.enter_loop();
@@ -213,7 +213,7 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// Expr.
-			ast::Statement::Expr(expr) => {
+			ast::Statement::Expr(ref expr) => {
 				let expr = self.analyze_expr(expr)?;
 				Some(Statement::Expr(expr))
 			}
@@ -226,8 +226,8 @@ impl<'a> Analyzer<'a> {
 
 	/// Analyze an expression.
 	/// None is returned if any error is detected.
-	fn analyze_expr(&mut self, expr: ast::Expr) -> Option<Expr> {
-		match expr {
+	fn analyze_expr(&mut self, expr: &ast::Expr) -> Option<Expr> {
+		match *expr {
 			// Self
 			ast::Expr::Self_ { pos } => {
 				if self.in_function {
@@ -257,14 +257,14 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// Literal.
-			ast::Expr::Literal { literal, pos } => {
-				let literal = self.analyze_literal(literal)?;
+			ast::Expr::Literal { ref literal, pos } => {
+				let literal = self.analyze_literal(literal, None)?;
 				Some(Expr::Literal { literal, pos })
 			}
 
 			// UnaryOp.
 			ast::Expr::UnaryOp { op, operand, pos } => {
-				let operand = self.analyze_expr(*operand)?;
+				let operand = self.analyze_expr(operand)?;
 
 				match op {
 					UnaryOp::Try if !self.in_function => {
@@ -285,8 +285,8 @@ impl<'a> Analyzer<'a> {
 
 			// BinaryOp.
 			ast::Expr::BinaryOp { left, op, right, pos } => {
-				let left = self.analyze_expr(*left);
-				let right = self.analyze_expr(*right);
+				let left = self.analyze_expr(left);
+				let right = self.analyze_expr(right);
 
 				let (left, right) = left.zip(right)?;
 
@@ -301,8 +301,8 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// If.
-			ast::Expr::If { condition, then, otherwise, pos } => {
-				let condition = self.analyze_expr(*condition);
+			ast::Expr::If { condition, ref then, ref otherwise, pos } => {
+				let condition = self.analyze_expr(condition);
 				let then = {
 					self.enter_block().analyze_block(then)
 				};
@@ -324,8 +324,8 @@ impl<'a> Analyzer<'a> {
 
 			// Access.
 			ast::Expr::Access { object, field, pos } => {
-				let object = self.analyze_expr(*object);
-				let field = self.analyze_expr(*field);
+				let object = self.analyze_expr(object);
+				let field = self.analyze_expr(field);
 
 				let (object, field) = object.zip(field)?;
 
@@ -340,11 +340,11 @@ impl<'a> Analyzer<'a> {
 
 			// Call.
 			ast::Expr::Call { function, args, pos } => {
-				let function = self.analyze_expr(*function);
+				let function = self.analyze_expr(function);
 
 				let args = self.analyze_items(
 					Self::analyze_expr,
-					args.into_vec(), // Use vec's owned iterator.
+					args.iter(),
 				);
 
 				let (function, args) = function.zip(args)?;
@@ -359,7 +359,7 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// Command block.
-			ast::Expr::CommandBlock { block, pos } => {
+			ast::Expr::CommandBlock { ref block, pos } => {
 				let block = self.analyze_command_block(block)?;
 
 				Some(Expr::CommandBlock { block, pos })
@@ -371,11 +371,26 @@ impl<'a> Analyzer<'a> {
 	}
 
 
+	/// Analyze an expression which is bound to a name, either by a let statement or as a
+	/// dict value. Function literals are named after it.
+	/// None is returned if any error is detected.
+	fn analyze_binding(&mut self, expr: &ast::Expr, name: Symbol) -> Option<Expr> {
+		match *expr {
+			ast::Expr::Literal { literal: ref literal @ ast::Literal::Function { .. }, pos } => {
+				let literal = self.analyze_literal(literal, Some(name))?;
+				Some(Expr::Literal { literal, pos })
+			}
+
+			_ => self.analyze_expr(expr),
+		}
+	}
+
+
 	/// Analyze an l-value expression.
 	/// Err is returned if any error is detected. The boolean indicates if the expression is
 	/// a valid l-value.
-	fn analyze_lvalue(&mut self, expr: ast::Expr) -> Result<Lvalue, bool> {
-		match expr {
+	fn analyze_lvalue(&mut self, expr: &ast::Expr) -> Result<Lvalue, bool> {
+		match *expr {
 			// Identifier.
 			ast::Expr::Identifier { identifier, pos } => {
 				let slot_ix =
@@ -397,8 +412,8 @@ impl<'a> Analyzer<'a> {
 
 			// Access.
 			ast::Expr::Access { object, field, pos } => {
-				let object = self.analyze_expr(*object);
-				let field = self.analyze_expr(*field);
+				let object = self.analyze_expr(object);
+				let field = self.analyze_expr(field);
 
 				let (object, field) = object
 					.zip(field)
@@ -422,10 +437,10 @@ impl<'a> Analyzer<'a> {
 	}
 
 
-	/// Analyze a literal.
+	/// Analyze a literal. Function literals are given the name, if any.
 	/// None is returned if any error is detected.
-	fn analyze_literal(&mut self, literal: ast::Literal) -> Option<Literal> {
-		match literal {
+	fn analyze_literal(&mut self, literal: &ast::Literal, name: Option<Symbol>) -> Option<Literal> {
+		match *literal {
 			// Nil.
 			ast::Literal::Nil => Some(Literal::Nil),
 
@@ -442,13 +457,13 @@ impl<'a> Analyzer<'a> {
 			ast::Literal::Byte(b) => Some(Literal::Byte(b)),
 
 			// String.
-			ast::Literal::String(s) => Some(Literal::String(s)),
+			ast::Literal::String(s) => Some(Literal::String(s.into())),
 
 			// Array.
 			ast::Literal::Array(array) => {
 				let array = self.analyze_items(
 					Self::analyze_expr,
-					array.into_vec(), // Use vec's owned iterator.
+					array.iter(),
 				)?;
 
 				Some(Literal::Array(array))
@@ -459,31 +474,31 @@ impl<'a> Analyzer<'a> {
 				self.dict_keys.clear();
 
 				let items = self.analyze_items(
-					|analyzer, ((symbol, pos), expr)| {
+					|analyzer, &((key, pos), ref expr)| {
 						let symbol =
-							if symbol.is_ill_formed() {
+							if key.is_ill_formed() {
 								None
-							} else if analyzer.dict_keys.insert(symbol) {
-								Some(symbol)
+							} else if analyzer.dict_keys.insert(key) {
+								Some(key)
 							} else { // Duplicate symbol.
-								analyzer.report(Error::duplicate_key(symbol, pos));
+								analyzer.report(Error::duplicate_key(key, pos));
 								None
 							};
 
-						let expr = analyzer.analyze_expr(expr);
+						let expr = analyzer.analyze_binding(expr, key);
 
 						let (symbol, expr) = symbol.zip(expr)?;
 
 						Some((symbol, expr))
 					},
-					items.into_vec(), // Use vec's owned iterator.
+					items.iter(),
 				)?;
 
 				Some(Literal::Dict(items))
 			}
 
 			// Function.
-			ast::Literal::Function { params, body } => {
+			ast::Literal::Function { params, ref body } => {
 				let mut analyzer = self.enter_frame();
 
 				let params_result = params
@@ -515,6 +530,7 @@ impl<'a> Analyzer<'a> {
 
 				Some(
 					Literal::Function {
+						name,
 						params: params.len() as u32,
 						frame_info,
 						body
@@ -536,13 +552,13 @@ impl<'a> Analyzer<'a> {
 
 	/// Analyze a command block.
 	/// None is returned if any error is detected.
-	fn analyze_command_block(&mut self, block: ast::CommandBlock) -> Option<CommandBlock> {
+	fn analyze_command_block(&mut self, block: &ast::CommandBlock) -> Option<CommandBlock> {
 		let in_async = !block.kind.is_sync();
 
-		let head = self.analyze_command(block.head, in_async);
+		let head = self.analyze_command(&block.head, in_async);
 		let tail = self.analyze_items(
 			move |analyzer, cmd| analyzer.analyze_command(cmd, in_async),
-			block.tail.into_vec(), // Use vec's owned iterator.
+			block.tail.iter(),
 		);
 
 		let (head, tail) = head.zip(tail)?;
@@ -559,7 +575,7 @@ impl<'a> Analyzer<'a> {
 
 	/// Analyze a command.
 	/// None is returned if any error is detected.
-	fn analyze_command(&mut self, command: ast::Command, in_async: bool) -> Option<Command> {
+	fn analyze_command(&mut self, command: &ast::Command, in_async: bool) -> Option<Command> {
 		match command::Builtin::try_from(&command.head.program) {
 			Ok(_)
 				if in_async // Block is async.
@@ -573,7 +589,7 @@ impl<'a> Analyzer<'a> {
 			Ok(builtin) => {
 				let arguments = self.analyze_items(
 					Self::analyze_argument,
-					command.head.arguments.into_vec(), // Use vec's owned iterator.
+					command.head.arguments.iter(),
 				)?;
 
 				Some(
@@ -587,11 +603,11 @@ impl<'a> Analyzer<'a> {
 			}
 
 			Err(_) => {
-				let head = self.analyze_basic_command(command.head);
+				let head = self.analyze_basic_command(&command.head);
 
 				let tail = self.analyze_items(
 					Self::analyze_basic_command,
-					command.tail.into_vec(), // Use vec's owned iterator.
+					command.tail.iter(),
 				);
 
 				let (head, tail) = head.zip(tail)?;
@@ -604,24 +620,32 @@ impl<'a> Analyzer<'a> {
 
 	/// Analyze a basic command.
 	/// None is returned if any error is detected.
-	fn analyze_basic_command(&mut self, command: ast::BasicCommand) -> Option<BasicCommand> {
+	fn analyze_basic_command(&mut self, command: &ast::BasicCommand) -> Option<BasicCommand> {
 		if command::Builtin::try_from(&command.program).is_ok() {
 			self.report(Error::async_builtin(command.pos));
 			return None;
 		};
 
-		let program = self.analyze_argument(command.program);
+		// Whether the variable holds a function is only known at runtime.
+		let function = match command.program.parts {
+			[ ast::ArgPart::Unit(ast::ArgUnit::Literal(name)) ] => self.interner
+				.get(name)
+				.and_then(|symbol| self.scope.resolve(symbol, command.pos, self.interner).ok()),
+			_ => None,
+		};
+
+		let program = self.analyze_argument(&command.program);
 
 		let env = self.analyze_env(command.env);
 
 		let arguments = self.analyze_items(
 			Self::analyze_argument,
-			command.arguments.into_vec(), // Use vec's owned iterator.
+			command.arguments.iter(),
 		);
 
 		let redirections = self.analyze_items(
 			Self::analyze_redirection,
-			command.redirections.into_vec(), // Use vec's owned iterator.
+			command.redirections.iter(),
 		);
 
 		let (program, (env, (arguments, redirections))) = program.zip(env.zip(arguments.zip(redirections)))?;
@@ -629,6 +653,7 @@ impl<'a> Analyzer<'a> {
 		Some(
 			BasicCommand {
 				program,
+				function,
 				env,
 				arguments,
 				redirections,
@@ -640,7 +665,7 @@ impl<'a> Analyzer<'a> {
 
 	fn analyze_env(
 		&mut self,
-		env: Box<[(ast::ArgUnit, ast::Argument)]>
+		env: &[(ast::ArgUnit, ast::Argument)]
 	) -> Option<Box<[(ArgUnit, Argument)]>> {
 		self.analyze_items(
 			|analyzer, (key, value)| {
@@ -648,19 +673,27 @@ impl<'a> Analyzer<'a> {
 				let value = analyzer.analyze_argument(value)?;
 				Some((key, value))
 			},
-			env.into_vec() // Use vec's owned iterator.
+			env.iter()
 		)
 	}
 
 	/// Analyze a command argument.
 	/// None is returned if any error is detected.
-	fn analyze_argument(&mut self, argument: ast::Argument) -> Option<Argument> {
+	fn analyze_argument(&mut self, argument: &ast::Argument) -> Option<Argument> {
+		let has_splice = argument
+			.parts
+			.iter()
+			.any(|part| matches!(part, ast::ArgPart::Splice { .. }));
+
 		if argument.is_ill_formed() {
 			None
+		} else if has_splice && argument.parts.len() > 1 {
+			self.report(Error::invalid_splice(argument.pos));
+			None
 		} else {
 			let parts = self.analyze_items(
 				Self::analyze_arg_part,
-				argument.parts.into_vec(), // Use vec's owned iterator.
+				argument.parts.iter(),
 			)?;
 
 			Some(
@@ -675,42 +708,69 @@ impl<'a> Analyzer<'a> {
 
 	/// Analyze a command argument part.
 	/// None is returned if any error is detected.
-	fn analyze_arg_part(&mut self, part: ast::ArgPart) -> Option<ArgPart> {
-		match part {
-			ast::ArgPart::Unit(unit) => self
+	fn analyze_arg_part(&mut self, part: &ast::ArgPart) -> Option<ArgPart> {
+		match *part {
+			ast::ArgPart::Unit(ref unit) => self
 				.analyze_arg_unit(unit)
 				.map(ArgPart::Unit),
-			ast::ArgPart::Expansion(unit) => self.analyze_arg_expansion(unit),
+			ast::ArgPart::Expansion(ref unit) => self.analyze_arg_expansion(unit),
+			ast::ArgPart::Splice { symbol, pos } => {
+				if symbol.is_ill_formed() {
+					None
+				} else {
+					let slot_ix = self.scope
+						.resolve(symbol, pos, self.interner)
+						.map_err(
+							|error| self.report(error)
+						)
+						.ok()?;
+
+					Some(ArgPart::Splice { slot_ix, pos })
+				}
+			}
 		}
 	}
 
 
 	/// Analyze a command argument expansion.
 	/// None is returned if any error is detected.
-	fn analyze_arg_expansion(&mut self, expansion: ast::ArgExpansion) -> Option<ArgPart> {
-		match expansion {
+	fn analyze_arg_expansion(&mut self, expansion: &ast::ArgExpansion) -> Option<ArgPart> {
+		let patterns = |patterns: &[&[u8]]| -> Box<[Box<[u8]>]> {
+			patterns
+				.iter()
+				.map(|&pattern| pattern.into())
+				.collect()
+		};
+
+		match *expansion {
 			ast::ArgExpansion::Home => Some(ArgPart::Home),
-			ast::ArgExpansion::Range(from, to) => Some(ArgPart::Range(from, to)),
+			ast::ArgExpansion::UserHome(user) => Some(ArgPart::UserHome(user.into())),
+			ast::ArgExpansion::WorkingDir => Some(ArgPart::WorkingDir),
+			ast::ArgExpansion::PreviousDir => Some(ArgPart::PreviousDir),
+			ast::ArgExpansion::Range(range) => Some(ArgPart::Range(range)),
 			ast::ArgExpansion::Collection(items) => {
 				let items = self.analyze_items(
 					Self::analyze_arg_unit,
-					items.into_vec() // Use vec's owned iterator.
+					items.iter(),
 				)?;
 
 				Some(ArgPart::Collection(items))
 			},
 			ast::ArgExpansion::Star => Some(ArgPart::Star),
+			ast::ArgExpansion::DoubleStar => Some(ArgPart::DoubleStar),
 			ast::ArgExpansion::Percent => Some(ArgPart::Percent),
-			ast::ArgExpansion::CharClass(chars) => Some(ArgPart::CharClass(chars)),
+			ast::ArgExpansion::CharClass(chars) => Some(ArgPart::CharClass(chars.into())),
+			ast::ArgExpansion::Negation(negated) => Some(ArgPart::Negation(patterns(negated))),
+			ast::ArgExpansion::Alternation(alternatives) => Some(ArgPart::Alternation(patterns(alternatives))),
 		}
 	}
 
 
 	/// Analyze a command argument unit.
 	/// None is returned if any error is detected.
-	fn analyze_arg_unit(&mut self, unit: ast::ArgUnit) -> Option<ArgUnit> {
-		match unit {
-			ast::ArgUnit::Literal(lit) => Some(ArgUnit::Literal(lit)),
+	fn analyze_arg_unit(&mut self, unit: &ast::ArgUnit) -> Option<ArgUnit> {
+		match *unit {
+			ast::ArgUnit::Literal(lit) => Some(ArgUnit::Literal(lit.into())),
 			ast::ArgUnit::Dollar { symbol, pos } => {
 				if symbol.is_ill_formed() {
 					None
@@ -731,19 +791,23 @@ impl<'a> Analyzer<'a> {
 
 	/// Analyze a redirection.
 	/// None is returned if any error is detected.
-	fn analyze_redirection(&mut self, redirection: ast::Redirection) -> Option<Redirection> {
-		match redirection {
+	fn analyze_redirection(&mut self, redirection: &ast::Redirection) -> Option<Redirection> {
+		match *redirection {
 			ast::Redirection::IllFormed => None,
 
-			ast::Redirection::Output { source, target } => {
-				let target = match target {
+			ast::Redirection::Output { source, ref target } => {
+				let target = match *target {
 					ast::RedirectionTarget::Fd(fd) => Some(RedirectionTarget::Fd(fd)),
 
-					ast::RedirectionTarget::Overwrite(arg) => self
+					ast::RedirectionTarget::Overwrite(ref arg) => self
 						.analyze_argument(arg)
 						.map(RedirectionTarget::Overwrite),
 
-					ast::RedirectionTarget::Append(arg) => self
+					ast::RedirectionTarget::Clobber(ref arg) => self
+						.analyze_argument(arg)
+						.map(RedirectionTarget::Clobber),
+
+					ast::RedirectionTarget::Append(ref arg) => self
 						.analyze_argument(arg)
 						.map(RedirectionTarget::Append),
 				}?;
@@ -751,7 +815,7 @@ impl<'a> Analyzer<'a> {
 				Some(Redirection::Output { source, target })
 			},
 
-			ast::Redirection::Input { literal, source } => {
+			ast::Redirection::Input { literal, ref source } => {
 				let source = self.analyze_argument(source)?;
 
 				Some(Redirection::Input { literal, source })
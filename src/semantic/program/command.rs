@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 
 use crate::io::FileDescriptor;
-use super::{ast, mem, SourcePos};
+use super::{ast, lexer, mem, SourcePos};
 
 
 /// The most basic part of an argument.
@@ -19,16 +19,26 @@ pub enum ArgUnit {
 #[derive(Debug)]
 pub enum ArgPart {
 	Unit(ArgUnit),
+	Splice { // ${name...}
+		slot_ix: mem::SlotIx,
+		pos: SourcePos,
+	},
 
 	// Literal expansions:
 	Home, // ~/
-	Range(i64, i64), // {x..y}
+	UserHome(Box<[u8]>), // ~user/
+	WorkingDir, // ~+/
+	PreviousDir, // ~-/
+	Range(lexer::ArgRange), // {x..y..z}
 	Collection(Box<[ArgUnit]>), // {a,b,c}
 
 	// File expansions:
 	Star, // *
+	DoubleStar, // **
 	Percent, // %
 	CharClass(Box<[u8]>), // [...]
+	Negation(Box<[Box<[u8]>]>), // !(a|b)
+	Alternation(Box<[Box<[u8]>]>), // @(a|b)
 }
 
 
@@ -47,6 +57,8 @@ pub enum RedirectionTarget {
 	Fd(FileDescriptor),
 	/// Overwrite a file.
 	Overwrite(Argument),
+	/// Overwrite a file, even if noclobber is set.
+	Clobber(Argument),
 	/// Append to a file.
 	Append(Argument),
 }
@@ -74,6 +86,12 @@ pub enum Redirection {
 pub enum Builtin {
 	Alias,
 	Cd,
+	Hash,
+	Pwd,
+	Pushd,
+	Popd,
+	Set,
+	Unalias,
 }
 
 
@@ -91,18 +109,24 @@ impl<'a> TryFrom<&'a [u8]> for Builtin {
 		match value {
 			b"alias" => Ok(Self::Alias),
 			b"cd" => Ok(Self::Cd),
+			b"hash" => Ok(Self::Hash),
+			b"pwd" => Ok(Self::Pwd),
+			b"pushd" => Ok(Self::Pushd),
+			b"popd" => Ok(Self::Popd),
+			b"set" => Ok(Self::Set),
+			b"unalias" => Ok(Self::Unalias),
 			_ => Err(InvalidBuiltin)
 		}
 	}
 }
 
 
-impl<'a> TryFrom<&'a ast::Argument> for Builtin {
+impl<'a, 'ast> TryFrom<&'a ast::Argument<'ast>> for Builtin {
 	type Error = InvalidBuiltin;
 
-	fn try_from(arg: &'a ast::Argument) -> Result<Self, Self::Error> {
-		match arg.parts.as_ref() {
-			[ ast::ArgPart::Unit(ast::ArgUnit::Literal(ref lit)) ] => Self::try_from(lit.as_ref()),
+	fn try_from(arg: &'a ast::Argument<'ast>) -> Result<Self, Self::Error> {
+		match arg.parts {
+			[ ast::ArgPart::Unit(ast::ArgUnit::Literal(lit)) ] => Self::try_from(*lit),
 			_ => Err(InvalidBuiltin),
 		}
 	}
@@ -113,6 +137,9 @@ impl<'a> TryFrom<&'a ast::Argument> for Builtin {
 #[derive(Debug)]
 pub struct BasicCommand {
 	pub program: Argument,
+	/// The variable named after the program, if any. If it holds a function at runtime,
+	/// the function is called instead of the program.
+	pub function: Option<mem::SlotIx>,
 	/// Key-value pairs of environment variables.
 	pub env: Box<[(ArgUnit, Argument)]>,
 	pub arguments: Box<[Argument]>,
@@ -153,6 +180,7 @@ pub enum CommandBlockKind {
 	Synchronous,  // {}
 	Asynchronous, // &{}
 	Capture,      // ${}
+	Isolated,     // !{}
 }
 
 
@@ -162,6 +190,7 @@ impl From<ast::CommandBlockKind> for CommandBlockKind {
 			ast::CommandBlockKind::Synchronous => CommandBlockKind::Synchronous,
 			ast::CommandBlockKind::Asynchronous => CommandBlockKind::Asynchronous,
 			ast::CommandBlockKind::Capture => CommandBlockKind::Capture,
+			ast::CommandBlockKind::Isolated => CommandBlockKind::Isolated,
 		}
 	}
 }
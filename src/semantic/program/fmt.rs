@@ -149,7 +149,7 @@ impl<'a> Display<'a> for Literal {
 				"]".fmt(f)
 			},
 
-			Self::Function { params, frame_info, body } => {
+			Self::Function { params, frame_info, body, .. } => {
 				let step = if context.indentation.is_some() { "\n" } else { " " };
 
 				Keyword::Function.fmt(f)?;
@@ -430,15 +430,28 @@ impl std::fmt::Display for ArgPart {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		match self {
 			Self::Unit(unit) => unit.fmt(f),
+			Self::Splice { slot_ix, .. } => {
+				"${".fmt(f)?;
+				slot_ix.fmt(f)?;
+				"...}".fmt(f)
+			},
 
 			Self::Home => color::Fg(color::Yellow, "~/").fmt(f),
-			Self::Range(start, end) => {
-				color::Fg(color::Yellow, "{").fmt(f)?;
-				start.fmt(f)?;
-				color::Fg(color::Yellow, "..").fmt(f)?;
-				end.fmt(f)?;
-				color::Fg(color::Yellow, "}").fmt(f)
+			Self::UserHome(user) => {
+				color::Fg(color::Yellow, "~").fmt(f)?;
+
+				color
+					::Fg(
+						color::Yellow,
+						String::from_utf8_lossy(user).escape_debug()
+					)
+					.fmt(f)?;
+
+				color::Fg(color::Yellow, "/").fmt(f)
 			},
+			Self::WorkingDir => color::Fg(color::Yellow, "~+/").fmt(f),
+			Self::PreviousDir => color::Fg(color::Yellow, "~-/").fmt(f),
+			Self::Range(range) => range.fmt(f),
 			Self::Collection(items) => {
 				color::Fg(color::Yellow, "{").fmt(f)?;
 
@@ -453,6 +466,7 @@ impl std::fmt::Display for ArgPart {
 			},
 
 			Self::Star => color::Fg(color::Yellow, "*").fmt(f),
+			Self::DoubleStar => color::Fg(color::Yellow, "**").fmt(f),
 			Self::Percent => color::Fg(color::Yellow, "%").fmt(f),
 			Self::CharClass(chars) => {
 				color::Fg(color::Yellow, "[").fmt(f)?;
@@ -466,6 +480,44 @@ impl std::fmt::Display for ArgPart {
 
 				color::Fg(color::Yellow, "]").fmt(f)
 			},
+			Self::Negation(patterns) => {
+				color::Fg(color::Yellow, "!(").fmt(f)?;
+
+				fmt::sep_by(
+					patterns.iter(),
+					f,
+					|pattern, f| {
+						color
+							::Fg(
+								color::Yellow,
+								String::from_utf8_lossy(pattern).escape_debug()
+							)
+							.fmt(f)
+					},
+					color::Fg(color::Yellow, "|")
+				)?;
+
+				color::Fg(color::Yellow, ")").fmt(f)
+			},
+			Self::Alternation(patterns) => {
+				color::Fg(color::Yellow, "@(").fmt(f)?;
+
+				fmt::sep_by(
+					patterns.iter(),
+					f,
+					|pattern, f| {
+						color
+							::Fg(
+								color::Yellow,
+								String::from_utf8_lossy(pattern).escape_debug()
+							)
+							.fmt(f)
+					},
+					color::Fg(color::Yellow, "|")
+				)?;
+
+				color::Fg(color::Yellow, ")").fmt(f)
+			},
 		}
 	}
 }
@@ -494,6 +546,11 @@ impl std::fmt::Display for RedirectionTarget {
 				arg.fmt(f)
 			}
 
+			Self::Clobber(arg) => {
+				">|".fmt(f)?;
+				arg.fmt(f)
+			}
+
 			Self::Append(arg) => {
 				">>".fmt(f)?;
 				arg.fmt(f)
@@ -530,6 +587,12 @@ impl std::fmt::Display for command::Builtin {
 		let command = match self {
 			command::Builtin::Alias => "alias",
 			command::Builtin::Cd => "cd",
+			command::Builtin::Hash => "hash",
+			command::Builtin::Pwd => "pwd",
+			command::Builtin::Pushd => "pushd",
+			command::Builtin::Popd => "popd",
+			command::Builtin::Set => "set",
+			command::Builtin::Unalias => "unalias",
 		};
 
 		color::Fg(color::Green, command).fmt(f)
@@ -615,6 +678,7 @@ impl std::fmt::Display for CommandBlockKind {
 			Self::Synchronous => "{",
 			Self::Asynchronous => "&{",
 			Self::Capture => "${",
+			Self::Isolated => "!{",
 		}.fmt(f)
 	}
 }
@@ -7,6 +7,7 @@ pub use crate::{
 	syntax::SourcePos,
 	symbol::Symbol,
 };
+pub use lexer::ArgRange;
 pub use command::{
 	ArgPart,
 	ArgUnit,
@@ -45,6 +46,8 @@ pub enum Literal {
 	Array(Box<[Expr]>),
 	Dict(Box<[(Symbol, Expr)]>),
 	Function {
+		/// The name of the variable or dict key the function was declared in, if any.
+		name: Option<Symbol>,
 		/// The number of parameters.
 		params: u32,
 		frame_info: mem::FrameInfo,
@@ -1,10 +1,9 @@
 use std::{
 	io,
 	path::Path,
-	os::unix::ffi::OsStrExt,
 };
 
-use crate::{fmt, semantic::ErrorsDisplayContext, symbol, syntax::{self, AnalysisDisplayContext}, tests};
+use crate::{ffi::OsStrExt, fmt, semantic::ErrorsDisplayContext, symbol, syntax::{self, AnalysisDisplayContext}, tests};
 use super::{program, Analyzer, Program, Errors};
 
 
@@ -20,7 +19,8 @@ where
 		move |path, file| {
 			let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
 			let source = syntax::Source::from_reader(path_symbol, file)?;
-			let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+			let arena = syntax::Arena::new();
+			let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner, &arena);
 
 			if !syntactic_analysis.errors.is_empty() {
 				panic!(
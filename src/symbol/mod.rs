@@ -41,8 +41,7 @@ impl Interner {
 	}
 
 
-	/// Get the symbol for a value.
-	#[cfg(test)]
+	/// Get the symbol for a value, if it has been interned.
 	pub fn get<T>(&self, value: T) -> Option<Symbol>
 	where
 		T: AsRef<[u8]>,
@@ -81,3 +80,20 @@ impl Interner {
 		self.0.len() - 1
 	}
 }
+
+
+/// Cloning interns the same values in the same order, so that symbols are valid for both
+/// interners. This allows programs to be shared by runtimes in different threads.
+impl Clone for Interner {
+	fn clone(&self) -> Self {
+		let mut interner = SymbolTable::with_capacity(self.0.len());
+
+		for (_, value) in self.0.iter() {
+			interner
+				.intern(value.to_owned())
+				.expect("failed to intern symbol");
+		}
+
+		Self(interner)
+	}
+}
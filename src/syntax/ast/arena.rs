@@ -0,0 +1,44 @@
+use bumpalo::{collections::Vec, Bump};
+
+
+/// The arena in which AST nodes, argument parts and literal buffers are allocated. The
+/// whole AST is released at once when the arena is dropped, instead of node by node.
+/// Values in the arena are never dropped, and therefore must not own heap memory.
+#[derive(Debug, Default)]
+pub struct Arena(Bump);
+
+
+impl Arena {
+	/// Create an empty arena.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+
+	/// Allocate a single value.
+	pub fn alloc<T>(&self, value: T) -> &T {
+		self.0.alloc(value)
+	}
+
+
+	/// Allocate a copy of a byte buffer.
+	pub fn bytes(&self, bytes: &[u8]) -> &[u8] {
+		self.0.alloc_slice_copy(bytes)
+	}
+
+
+	/// Allocate the items of an iterator as a slice.
+	pub fn slice<T, I>(&self, items: I) -> &[T]
+	where
+		I: IntoIterator<Item = T>,
+	{
+		Vec::from_iter_in(items, &self.0).into_bump_slice()
+	}
+
+
+	/// Create a growable vector in the arena. It may be converted to a slice with
+	/// `into_bump_slice`, without copying the items.
+	pub fn vec<T>(&self) -> Vec<'_, T> {
+		Vec::new_in(&self.0)
+	}
+}
@@ -1,11 +1,11 @@
 use crate::{io::FileDescriptor, symbol::Symbol};
-use super::{lexer, IllFormed, SourcePos};
+use super::{lexer, Arena, IllFormed, SourcePos};
 
 
 /// The most basic part of an argument.
 #[derive(Debug)]
-pub enum ArgUnit {
-	Literal(Box<[u8]>),
+pub enum ArgUnit<'a> {
+	Literal(&'a [u8]),
 	Dollar {
 		symbol: Symbol,
 		pos: SourcePos,
@@ -13,10 +13,11 @@ pub enum ArgUnit {
 }
 
 
-impl From<lexer::ArgUnit> for ArgUnit {
-	fn from(unit: lexer::ArgUnit) -> Self {
+impl<'a> ArgUnit<'a> {
+	/// Convert an argument unit token, allocating literals in the arena.
+	pub fn from_token(unit: lexer::ArgUnit, arena: &'a Arena) -> Self {
 		match unit {
-			lexer::ArgUnit::Literal(lit) => Self::Literal(lit),
+			lexer::ArgUnit::Literal(lit) => Self::Literal(arena.bytes(&lit)),
 			lexer::ArgUnit::Dollar { symbol, pos } => Self::Dollar { symbol, pos }
 		}
 	}
@@ -25,32 +26,52 @@ impl From<lexer::ArgUnit> for ArgUnit {
 
 /// An argument expansion.
 #[derive(Debug)]
-pub enum ArgExpansion {
+pub enum ArgExpansion<'a> {
 	Home, // ~/
-	Range(i64, i64), // {x..y}
-	Collection(Box<[ArgUnit]>), // {a,b,c}
+	UserHome(&'a [u8]), // ~user/
+	WorkingDir, // ~+/
+	PreviousDir, // ~-/
+	Range(lexer::ArgRange), // {x..y..z}
+	Collection(&'a [ArgUnit<'a>]), // {a,b,c}
 
 	Star, // *
+	DoubleStar, // **
 	Percent, // %
-	CharClass(Box<[u8]>), // [...]
+	CharClass(&'a [u8]), // [...]
+	Negation(&'a [&'a [u8]]), // !(a|b)
+	Alternation(&'a [&'a [u8]]), // @(a|b)
 }
 
 
-impl From<lexer::ArgExpansion> for ArgExpansion {
-	fn from(expansion: lexer::ArgExpansion) -> Self {
+impl<'a> ArgExpansion<'a> {
+	/// Convert an argument expansion token, allocating it's contents in the arena.
+	pub fn from_token(expansion: lexer::ArgExpansion, arena: &'a Arena) -> Self {
+		let patterns = |patterns: Box<[Box<[u8]>]>| arena.slice(
+			patterns
+				.iter()
+				.map(|pattern| arena.bytes(pattern))
+		);
+
 		match expansion {
 			lexer::ArgExpansion::Home => Self::Home,
-			lexer::ArgExpansion::Range(from ,to) => Self::Range(from ,to),
+			lexer::ArgExpansion::UserHome(user) => Self::UserHome(arena.bytes(&user)),
+			lexer::ArgExpansion::WorkingDir => Self::WorkingDir,
+			lexer::ArgExpansion::PreviousDir => Self::PreviousDir,
+			lexer::ArgExpansion::Range(range) => Self::Range(range),
 			lexer::ArgExpansion::Collection(items) => Self::Collection(
-				items
-					.into_vec() // Use vec's owned iterator.
-					.into_iter()
-					.map(Into::into)
-					.collect()
+				arena.slice(
+					items
+						.into_vec() // Use vec's owned iterator.
+						.into_iter()
+						.map(|item| ArgUnit::from_token(item, arena))
+				)
 			),
 			lexer::ArgExpansion::Star => Self::Star,
+			lexer::ArgExpansion::DoubleStar => Self::DoubleStar,
 			lexer::ArgExpansion::Percent => Self::Percent,
-			lexer::ArgExpansion::CharClass(class) => Self::CharClass(class),
+			lexer::ArgExpansion::CharClass(class) => Self::CharClass(arena.bytes(&class)),
+			lexer::ArgExpansion::Negation(negated) => Self::Negation(patterns(negated)),
+			lexer::ArgExpansion::Alternation(alternatives) => Self::Alternation(patterns(alternatives)),
 		}
 	}
 }
@@ -58,21 +79,25 @@ impl From<lexer::ArgExpansion> for ArgExpansion {
 
 /// The most basic part of an argument.
 #[derive(Debug)]
-pub enum ArgPart {
-	Unit(ArgUnit),
-	Expansion(ArgExpansion),
+pub enum ArgPart<'a> {
+	Unit(ArgUnit<'a>),
+	Expansion(ArgExpansion<'a>),
+	Splice { // ${name...}
+		symbol: Symbol,
+		pos: SourcePos,
+	},
 }
 
 
 /// An argument may consist of several argument parts.
 #[derive(Debug)]
-pub struct Argument {
-	pub parts: Box<[ArgPart]>,
+pub struct Argument<'a> {
+	pub parts: &'a [ArgPart<'a>],
 	pub pos: SourcePos,
 }
 
 
-impl IllFormed for Argument {
+impl<'a> IllFormed for Argument<'a> {
 	fn ill_formed() -> Self {
 		Self {
 			parts: Default::default(),
@@ -88,36 +113,38 @@ impl IllFormed for Argument {
 
 /// The target of a redirection operation.
 #[derive(Debug)]
-pub enum RedirectionTarget {
+pub enum RedirectionTarget<'a> {
 	/// Redirect to a file descriptor.
 	Fd(FileDescriptor),
 	/// Overwrite a file.
-	Overwrite(Argument),
+	Overwrite(Argument<'a>),
+	/// Overwrite a file, even if noclobber is set.
+	Clobber(Argument<'a>),
 	/// Append to a file.
-	Append(Argument),
+	Append(Argument<'a>),
 }
 
 
 /// Redirection operation.
 #[derive(Debug)]
-pub enum Redirection {
+pub enum Redirection<'a> {
 	/// An ill-formed redirection, produced by a parse error.
 	IllFormed,
 	/// Redirect output to a file or file descriptor.
 	Output {
 		source: FileDescriptor,
-		target: RedirectionTarget,
+		target: RedirectionTarget<'a>,
 	},
 	/// Redirect input from a file or literal.
 	Input {
 		/// Whether the source is the input or the file path.
 		literal: bool,
-		source: Argument,
+		source: Argument<'a>,
 	},
 }
 
 
-impl IllFormed for Redirection {
+impl<'a> IllFormed for Redirection<'a> {
 	fn ill_formed() -> Self {
 		Self::IllFormed
 	}
@@ -130,18 +157,18 @@ impl IllFormed for Redirection {
 
 /// A single command, including possible redirections and try operator.
 #[derive(Debug)]
-pub struct BasicCommand {
-	pub program: Argument,
+pub struct BasicCommand<'a> {
+	pub program: Argument<'a>,
 	/// Key-value pairs of environment variables.
-	pub env: Box<[(ArgUnit, Argument)]>,
-	pub arguments: Box<[Argument]>,
-	pub redirections: Box<[Redirection]>,
+	pub env: &'a [(ArgUnit<'a>, Argument<'a>)],
+	pub arguments: &'a [Argument<'a>],
+	pub redirections: &'a [Redirection<'a>],
 	pub abort_on_error: bool,
 	pub pos: SourcePos,
 }
 
 
-impl IllFormed for BasicCommand {
+impl<'a> IllFormed for BasicCommand<'a> {
 	fn ill_formed() -> Self {
 		Self {
 			program: Argument::ill_formed(),
@@ -161,13 +188,13 @@ impl IllFormed for BasicCommand {
 
 /// Commands may be pipelines, or a single BasicCommand.
 #[derive(Debug)]
-pub struct Command {
-	pub head: BasicCommand,
-	pub tail: Box<[BasicCommand]>,
+pub struct Command<'a> {
+	pub head: BasicCommand<'a>,
+	pub tail: &'a [BasicCommand<'a>],
 }
 
 
-impl IllFormed for Command {
+impl<'a> IllFormed for Command<'a> {
 	fn ill_formed() -> Self {
 		Self {
 			head: BasicCommand::ill_formed(),
@@ -183,14 +210,14 @@ impl IllFormed for Command {
 
 /// A command block.
 #[derive(Debug)]
-pub struct CommandBlock {
+pub struct CommandBlock<'a> {
 	pub kind: CommandBlockKind,
-	pub head: Command,
-	pub tail: Box<[Command]>,
+	pub head: Command<'a>,
+	pub tail: &'a [Command<'a>],
 }
 
 
-impl IllFormed for CommandBlock {
+impl<'a> IllFormed for CommandBlock<'a> {
 	fn ill_formed() -> Self {
 		Self {
 			kind: CommandBlockKind::Synchronous,
@@ -206,11 +233,12 @@ impl IllFormed for CommandBlock {
 
 
 /// The kinds of command blocks.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum CommandBlockKind {
 	Synchronous,  // {}
 	Asynchronous, // &{}
 	Capture,      // ${}
+	Isolated,     // !{}
 }
 
 
@@ -220,6 +248,7 @@ impl CommandBlockKind {
 			lexer::TokenKind::Command => Some(Self::Synchronous),
 			lexer::TokenKind::AsyncCommand => Some(Self::Asynchronous),
 			lexer::TokenKind::CaptureCommand => Some(Self::Capture),
+			lexer::TokenKind::IsolatedCommand => Some(Self::Isolated),
 			_ => None,
 		}
 	}
@@ -227,6 +256,6 @@ impl CommandBlockKind {
 
 	/// Check whether the command block should be executed synchronously.
 	pub fn is_sync(&self) -> bool {
-		matches!(self, Self::Synchronous)
+		matches!(self, Self::Synchronous | Self::Isolated)
 	}
 }
@@ -64,7 +64,7 @@ impl<'a> From<&'a symbol::Interner> for Context<'a> {
 }
 
 
-impl<'a> Display<'a> for Block {
+impl<'a, 'ast> Display<'a> for Block<'ast> {
 	type Context = Context<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -89,7 +89,7 @@ impl<'a> Display<'a> for Block {
 }
 
 
-impl<'a> Display<'a> for Literal {
+impl<'a, 'ast> Display<'a> for Literal<'ast> {
 	type Context = Context<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -219,7 +219,7 @@ impl std::fmt::Display for BinaryOp {
 }
 
 
-impl<'a> Display<'a> for Expr {
+impl<'a, 'ast> Display<'a> for Expr<'ast> {
 	type Context = Context<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -297,7 +297,7 @@ impl<'a> Display<'a> for Expr {
 			}
 
 			Self::Access { object, field, .. }
-			if matches!(field.as_ref(), Self::Literal { literal: Literal::Identifier(..), .. }) => {
+			if matches!(**field, Self::Literal { literal: Literal::Identifier(..), .. }) => {
 				object.fmt(f, context.inlined())?;
 				".".fmt(f)?;
 				field.fmt(f, context.inlined())
@@ -330,7 +330,7 @@ impl<'a> Display<'a> for Expr {
 }
 
 
-impl<'a> Display<'a> for Statement {
+impl<'a, 'ast> Display<'a> for Statement<'ast> {
 	type Context = Context<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -413,7 +413,7 @@ impl<'a> Display<'a> for Statement {
 }
 
 
-impl<'a> Display<'a> for ArgUnit {
+impl<'a, 'ast> Display<'a> for ArgUnit<'ast> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -430,19 +430,27 @@ impl<'a> Display<'a> for ArgUnit {
 }
 
 
-impl<'a> Display<'a> for ArgExpansion {
+impl<'a, 'ast> Display<'a> for ArgExpansion<'ast> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		match self {
 			Self::Home => color::Fg(color::Yellow, "~/").fmt(f),
-			Self::Range(start, end) => {
-				color::Fg(color::Yellow, "{").fmt(f)?;
-				start.fmt(f)?;
-				color::Fg(color::Yellow, "..").fmt(f)?;
-				end.fmt(f)?;
-				color::Fg(color::Yellow, "}").fmt(f)
+			Self::UserHome(user) => {
+				color::Fg(color::Yellow, "~").fmt(f)?;
+
+				color
+					::Fg(
+						color::Yellow,
+						String::from_utf8_lossy(user).escape_debug()
+					)
+					.fmt(f)?;
+
+				color::Fg(color::Yellow, "/").fmt(f)
 			},
+			Self::WorkingDir => color::Fg(color::Yellow, "~+/").fmt(f),
+			Self::PreviousDir => color::Fg(color::Yellow, "~-/").fmt(f),
+			Self::Range(range) => range.fmt(f),
 			Self::Collection(items) => {
 				color::Fg(color::Yellow, "{").fmt(f)?;
 
@@ -457,6 +465,7 @@ impl<'a> Display<'a> for ArgExpansion {
 			},
 
 			Self::Star => color::Fg(color::Yellow, "*").fmt(f),
+			Self::DoubleStar => color::Fg(color::Yellow, "**").fmt(f),
 			Self::Percent => color::Fg(color::Yellow, "%").fmt(f),
 			Self::CharClass(chars) => {
 				color::Fg(color::Yellow, "[").fmt(f)?;
@@ -470,24 +479,67 @@ impl<'a> Display<'a> for ArgExpansion {
 
 				color::Fg(color::Yellow, "]").fmt(f)
 			},
+			Self::Negation(patterns) => {
+				color::Fg(color::Yellow, "!(").fmt(f)?;
+
+				fmt::sep_by(
+					patterns.iter(),
+					f,
+					|pattern, f| {
+						color
+							::Fg(
+								color::Yellow,
+								String::from_utf8_lossy(pattern).escape_debug()
+							)
+							.fmt(f)
+					},
+					color::Fg(color::Yellow, "|")
+				)?;
+
+				color::Fg(color::Yellow, ")").fmt(f)
+			},
+			Self::Alternation(patterns) => {
+				color::Fg(color::Yellow, "@(").fmt(f)?;
+
+				fmt::sep_by(
+					patterns.iter(),
+					f,
+					|pattern, f| {
+						color
+							::Fg(
+								color::Yellow,
+								String::from_utf8_lossy(pattern).escape_debug()
+							)
+							.fmt(f)
+					},
+					color::Fg(color::Yellow, "|")
+				)?;
+
+				color::Fg(color::Yellow, ")").fmt(f)
+			},
 		}
 	}
 }
 
 
-impl<'a> Display<'a> for ArgPart {
+impl<'a, 'ast> Display<'a> for ArgPart<'ast> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		match self {
 			Self::Unit(unit) => unit.fmt(f, context),
 			Self::Expansion(expansion) => expansion.fmt(f, context),
+			Self::Splice { symbol, .. } => {
+				"${".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"...}".fmt(f)
+			}
 		}
 	}
 }
 
 
-impl<'a> Display<'a> for Argument {
+impl<'a, 'ast> Display<'a> for Argument<'ast> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -506,7 +558,7 @@ impl<'a> Display<'a> for Argument {
 }
 
 
-impl<'a> Display<'a> for RedirectionTarget {
+impl<'a, 'ast> Display<'a> for RedirectionTarget<'ast> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -518,6 +570,11 @@ impl<'a> Display<'a> for RedirectionTarget {
 				arg.fmt(f, context)
 			}
 
+			Self::Clobber(arg) => {
+				">|".fmt(f)?;
+				arg.fmt(f, context)
+			}
+
 			Self::Append(arg) => {
 				">>".fmt(f)?;
 				arg.fmt(f, context)
@@ -527,7 +584,7 @@ impl<'a> Display<'a> for RedirectionTarget {
 }
 
 
-impl<'a> Display<'a> for Redirection {
+impl<'a, 'ast> Display<'a> for Redirection<'ast> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -553,7 +610,7 @@ impl<'a> Display<'a> for Redirection {
 }
 
 
-impl<'a> Display<'a> for BasicCommand {
+impl<'a, 'ast> Display<'a> for BasicCommand<'ast> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -586,7 +643,7 @@ impl<'a> Display<'a> for BasicCommand {
 }
 
 
-impl<'a> Display<'a> for Command {
+impl<'a, 'ast> Display<'a> for Command<'ast> {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -610,12 +667,13 @@ impl std::fmt::Display for CommandBlockKind {
 			Self::Synchronous => "{",
 			Self::Asynchronous => "&{",
 			Self::Capture => "${",
+			Self::Isolated => "!{",
 		}.fmt(f)
 	}
 }
 
 
-impl<'a> Display<'a> for CommandBlock {
+impl<'a, 'ast> Display<'a> for CommandBlock<'ast> {
 	type Context = Context<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
@@ -640,7 +698,7 @@ impl<'a> Display<'a> for CommandBlock {
 }
 
 
-impl<'a> Display<'a> for Ast {
+impl<'a, 'ast> Display<'a> for Ast<'ast> {
 	type Context = Context<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
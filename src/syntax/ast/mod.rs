@@ -1,8 +1,10 @@
+mod arena;
 mod command;
 pub mod fmt;
 
 use super::{lexer, SourcePos};
 pub use crate::symbol::Symbol;
+pub use arena::Arena;
 pub use command::{
 	ArgPart,
 	ArgExpansion,
@@ -77,34 +79,34 @@ impl IllFormed for Symbol {
 
 /// A block is a list of statements, constituting a new scope.
 #[derive(Debug)]
-pub enum Block {
+pub enum Block<'a> {
 	IllFormed,
-	Block(Box<[Statement]>),
+	Block(&'a [Statement<'a>]),
 }
 
 
-impl Block {
+impl<'a> Block<'a> {
 	pub fn is_empty(&self) -> bool {
 		matches!(self, Self::Block(block) if block.is_empty())
 	}
 }
 
 
-impl Default for Block {
+impl<'a> Default for Block<'a> {
 	fn default() -> Self {
 		Self::Block(Default::default())
 	}
 }
 
 
-impl From<Box<[Statement]>> for Block {
-	fn from(block: Box<[Statement]>) -> Self {
+impl<'a> From<&'a [Statement<'a>]> for Block<'a> {
+	fn from(block: &'a [Statement<'a>]) -> Self {
 		Self::Block(block)
 	}
 }
 
 
-impl IllFormed for Block {
+impl<'a> IllFormed for Block<'a> {
 	fn ill_formed() -> Self {
 		Self::IllFormed
 	}
@@ -118,19 +120,19 @@ impl IllFormed for Block {
 /// Literals of all types in the language.
 /// Note that there are no literals for the error type.
 #[derive(Debug)]
-pub enum Literal {
+pub enum Literal<'a> {
 	Nil,
 	Bool(bool),
 	Int(i64),
 	Float(f64),
 	Byte(u8),
-	String(Box<[u8]>),
-	Array(Box<[Expr]>),
-	Dict(Box<[((Symbol, SourcePos), Expr)]>),
+	String(&'a [u8]),
+	Array(&'a [Expr<'a>]),
+	Dict(&'a [((Symbol, SourcePos), Expr<'a>)]),
 	Function {
 		/// A list of parameters (identifiers).
-		params: Box<[(Symbol, SourcePos)]>,
-		body: Block,
+		params: &'a [(Symbol, SourcePos)],
+		body: Block<'a>,
 	},
 	/// For the dot access operator, we want to be able to have identifiers as literal
 	/// strings instead of names for variables. This variant should only be used in such
@@ -139,15 +141,16 @@ pub enum Literal {
 }
 
 
-impl Default for Literal {
+impl<'a> Default for Literal<'a> {
 	fn default() -> Self {
 		Self::Nil
 	}
 }
 
 
-impl From<lexer::Literal> for Literal {
-	fn from(lit: lexer::Literal) -> Self {
+impl<'a> Literal<'a> {
+	/// Convert a literal token, allocating string literals in the arena.
+	pub fn from_token(lit: lexer::Literal, arena: &'a Arena) -> Self {
 		match lit {
 			lexer::Literal::Nil => Literal::Nil,
 			lexer::Literal::True => Literal::Bool(true),
@@ -155,14 +158,14 @@ impl From<lexer::Literal> for Literal {
 			lexer::Literal::Int(int) => Literal::Int(int),
 			lexer::Literal::Float(float) => Literal::Float(float),
 			lexer::Literal::Byte(byte) => Literal::Byte(byte),
-			lexer::Literal::String(string) => Literal::String(string),
+			lexer::Literal::String(string) => Literal::String(arena.bytes(&string)),
 		}
 	}
 }
 
 
 /// Unary operators.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum UnaryOp {
 	Minus, // -
 	Not,   // not
@@ -193,7 +196,7 @@ impl From<lexer::Operator> for UnaryOp {
 /// Binary operators.
 /// Assignment/Access are not represented as operators, but directly as
 /// statements/expressions instead.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum BinaryOp {
 	Plus,  // +
 	Minus, // -
@@ -241,7 +244,7 @@ impl From<lexer::Operator> for BinaryOp {
 
 /// Expressions of all kinds in the language.
 #[derive(Debug)]
-pub enum Expr {
+pub enum Expr<'a> {
 	/// An ill-formed expr, produced by a parse error.
 	IllFormed,
 	/// The `self` keyword.
@@ -253,47 +256,47 @@ pub enum Expr {
 		pos: SourcePos,
 	},
 	Literal {
-		literal: Literal,
+		literal: Literal<'a>,
 		pos: SourcePos,
 	},
 	UnaryOp {
 		op: UnaryOp,
-		operand: Box<Expr>,
+		operand: &'a Expr<'a>,
 		pos: SourcePos,
 	},
 	BinaryOp {
-		left: Box<Expr>,
+		left: &'a Expr<'a>,
 		op: BinaryOp,
-		right: Box<Expr>,
+		right: &'a Expr<'a>,
 		pos: SourcePos,
 	},
 	/// If-else expression.
 	If {
-		condition: Box<Expr>,
-		then: Block,
-		otherwise: Block,
+		condition: &'a Expr<'a>,
+		then: Block<'a>,
+		otherwise: Block<'a>,
 		pos: SourcePos,
 	},
 	/// Field access ([]) operator.
 	Access {
-		object: Box<Expr>,
-		field: Box<Expr>,
+		object: &'a Expr<'a>,
+		field: &'a Expr<'a>,
 		pos: SourcePos,
 	},
 	/// Function call (()) operator.
 	Call {
-		function: Box<Expr>,
-		args: Box<[Expr]>,
+		function: &'a Expr<'a>,
+		args: &'a [Expr<'a>],
 		pos: SourcePos,
 	},
 	CommandBlock {
-		block: CommandBlock,
+		block: CommandBlock<'a>,
 		pos: SourcePos,
 	},
 }
 
 
-impl IllFormed for Expr {
+impl<'a> IllFormed for Expr<'a> {
 	fn ill_formed() -> Self {
 		Self::IllFormed
 	}
@@ -306,22 +309,22 @@ impl IllFormed for Expr {
 
 /// Statements of all kinds in the language.
 #[derive(Debug)]
-pub enum Statement {
+pub enum Statement<'a> {
 	/// An ill-formed statement, produced by a parse error.
 	IllFormed,
 	/// Introduces an identifier.
 	Let {
 		identifier: Symbol,
-		init: Expr,
+		init: Expr<'a>,
 		pos: SourcePos,
 	},
 	Assign {
-		left: Expr,
-		right: Expr,
+		left: Expr<'a>,
+		right: Expr<'a>,
 		pos: SourcePos,
 	},
 	Return {
-		expr: Expr,
+		expr: Expr<'a>,
 		pos: SourcePos,
 	},
 	Break {
@@ -329,22 +332,22 @@ pub enum Statement {
 	},
 	/// While loop.
 	While {
-		condition: Expr,
-		block: Block,
+		condition: Expr<'a>,
+		block: Block<'a>,
 		pos: SourcePos,
 	},
 	/// For loop. Also introduces an identifier.
 	For {
 		identifier: Symbol,
-		expr: Expr,
-		block: Block,
+		expr: Expr<'a>,
+		block: Block<'a>,
 		pos: SourcePos,
 	},
-	Expr(Expr),
+	Expr(Expr<'a>),
 }
 
 
-impl IllFormed for Statement {
+impl<'a> IllFormed for Statement<'a> {
 	fn ill_formed() -> Self {
 		Self::IllFormed
 	}
@@ -355,11 +358,12 @@ impl IllFormed for Statement {
 }
 
 
-/// The abstract syntax tree for a source file.
+/// The abstract syntax tree for a source file. The nodes are allocated in an arena, which
+/// must outlive the tree.
 #[derive(Debug)]
-pub struct Ast {
+pub struct Ast<'a> {
 	/// The source path. May be something fictional, like "<stdin>".
 	pub source: Symbol,
 	/// The program.
-	pub statements: Block,
+	pub statements: Block<'a>,
 }
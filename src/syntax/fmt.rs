@@ -15,7 +15,7 @@ pub struct AnalysisDisplayContext<'a> {
 }
 
 
-impl<'a> Display<'a> for Analysis {
+impl<'a, 'ast> Display<'a> for Analysis<'ast> {
 	type Context = AnalysisDisplayContext<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
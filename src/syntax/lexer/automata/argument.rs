@@ -143,6 +143,8 @@ pub(super) trait DollarContext {
 	fn resume(self, symbol: Symbol, pos: SourcePos) -> Transition;
 	/// Non-consuming variant of error.
 	fn resume_error(self, error: Error) -> Transition;
+	/// The transition to make when an array splice (${name...}) has been consumed.
+	fn splice(self, symbol: Symbol, pos: SourcePos) -> Transition;
 }
 
 
@@ -166,6 +168,12 @@ impl DollarContext for Argument {
 	fn resume_error(self, error: Error) -> Transition {
 		Transition::resume_error(self, error)
 	}
+
+	fn splice(mut self, symbol: Symbol, pos: SourcePos) -> Transition {
+		self.parts.push(ArgPart::Splice { symbol, pos });
+
+		Transition::step(self)
+	}
 }
 
 
@@ -189,6 +197,10 @@ impl DollarContext for DoubleQuoted {
 	fn resume_error(self, error: Error) -> Transition {
 		Transition::resume_error(self, error)
 	}
+
+	fn splice(self, _: Symbol, pos: SourcePos) -> Transition {
+		Transition::error(self, Error::quoted_splice(pos))
+	}
 }
 
 
@@ -201,6 +213,9 @@ pub(super) struct Dollar<C> {
 	braces: Option<bool>,
 	/// Whether the identifier is invalid.
 	error: bool,
+	/// The number of dots after the identifier, which make an array splice when enclosed
+	/// in braces (${name...}).
+	dots: usize,
 	/// The position of the dollar.
 	pos: SourcePos,
 	/// The argument context.
@@ -218,6 +233,7 @@ where
 			start_offset: None,
 			braces: None,
 			error: false,
+			dots: 0,
 			pos: cursor.pos(),
 			context,
 		}
@@ -229,16 +245,20 @@ where
 			($consume:expr) => {{
 				// If no characters have been read, the identifier is empty, which is an error.
 				let offset = self.start_offset.unwrap_or(cursor.offset());
-				let identifier = &cursor.slice()[offset .. cursor.offset()];
+				let full_identifier = &cursor.slice()[offset .. cursor.offset()];
+				let identifier = &full_identifier[.. full_identifier.len() - self.dots];
+				let splice = self.dots == 3;
 
-				if identifier.is_empty() || self.error {
+				if identifier.is_empty() || self.error || (self.dots != 0 && !splice) {
 					return self.context
-						.error(Error::invalid_identifier(identifier, self.pos))
+						.error(Error::invalid_identifier(full_identifier, self.pos))
 				}
 
 				match word::to_token(identifier, interner) {
 					TokenKind::Identifier(symbol) => {
-						if $consume {
+						if splice {
+							self.context.splice(symbol, self.pos)
+						} else if $consume {
 							self.context.produce(symbol, self.pos)
 						} else {
 							self.context.resume(symbol, self.pos)
@@ -292,7 +312,9 @@ where
 
 			// Tail character when braces
 			(&Self { start_offset: Some(_), .. }, Some(c)) => {
-				if !c.is_word() {
+				if c == b'.' {
+					self.dots += 1;
+				} else if !c.is_word() || self.dots != 0 {
 					self.error = true;
 				}
 
@@ -630,6 +652,14 @@ impl ExpansionContext for Argument {
 		Transition::step(self)
 	}
 
+	fn resume(mut self, expansion: crate::syntax::lexer::ArgExpansion) -> Transition {
+		self.parts.push(
+			ArgPart::Expansion(expansion)
+		);
+
+		Transition::resume(self)
+	}
+
 	fn rollback(self, checkpoint: Checkpoint) -> Transition {
 		// If expansion parsing fails, handle it like a word.
 		Transition::rollback(checkpoint, Word::from(self))
@@ -656,6 +686,20 @@ impl ExpansionContext for Word<Argument> {
 		Transition::step(argument_state)
 	}
 
+	fn resume(self, expansion: crate::syntax::lexer::ArgExpansion) -> Transition {
+		let mut argument_state = self.context;
+
+		argument_state.parts.push(ArgPart::Unquoted(ArgUnit::Literal(
+			self.value.into_boxed_slice(),
+		)));
+
+		argument_state.parts.push(
+			ArgPart::Expansion(expansion)
+		);
+
+		Transition::resume(argument_state)
+	}
+
 	fn rollback(mut self, checkpoint: Checkpoint) -> Transition {
 		self.allow_expansion_start = false;
 		// If expansion parsing fails, handle it like a word.
@@ -33,6 +33,11 @@ impl Command {
 				Token { kind: TokenKind::CloseCommand, pos: cursor.pos() },
 			),
 
+			// Redirection of all outputs. Otherwise, the ampersand is part of an argument.
+			Some(b'&') if cursor.peek_second() == Some(b'>') => {
+				Transition::step(CommandSymbol::from_first(b'&', cursor))
+			}
+
 			// Argument or operator.
 			Some(c) => match CommandSymbolChar::from_first(c) {
 				// Argument.
@@ -43,7 +48,7 @@ impl Command {
 					Transition::produce(self, Token { kind: token, pos: cursor.pos() })
 				}
 
-				// >, >>, <, <<.
+				// >, >>, >|, <, <<.
 				CommandSymbolChar::Double { first } => {
 					Transition::step(CommandSymbol::from_first(first, cursor))
 				}
@@ -2,6 +2,7 @@ use super::{
 	argument,
 	Argument,
 	ArgExpansion,
+	ArgRange,
 	Cursor,
 	Checkpoint,
 	State,
@@ -17,6 +18,8 @@ use super::{
 pub(super) trait ExpansionContext {
 	/// The transition to make when a expansion has been produced.
 	fn produce(self, expansion: ArgExpansion) -> Transition;
+	/// Non-consuming variant of produce.
+	fn resume(self, expansion: ArgExpansion) -> Transition;
 	/// The transition to make when no expansion could be parsed.
 	/// Yield and rollback to the given checkpoint.
 	fn rollback(self, checkpoint: Checkpoint) -> Transition;
@@ -31,8 +34,16 @@ pub(super) struct Expansion<C> {
 	start: Checkpoint,
 	/// Whether to allow recognition of the home expansion.
 	allow_home: bool,
-	/// Whether the tilde has been consumed for the home expansion.
-	tilde_consumed: bool,
+	/// The user name after the tilde, if the tilde has been consumed for the home
+	/// expansion.
+	tilde: Option<Vec<u8>>,
+	/// Whether a star has been consumed, which may be followed by another for the
+	/// recursive expansion.
+	star_consumed: bool,
+	/// The extended glob being parsed, if any.
+	ext_glob: Option<ExtGlob>,
+	/// The contents of the brace expansion being parsed, if any.
+	brace: Option<Vec<u8>>,
 	/// The argument context.
 	context: C,
 }
@@ -47,7 +58,10 @@ where
 		Self {
 			start: cursor.checkpoint(),
 			allow_home,
-			tilde_consumed: false,
+			tilde: None,
+			star_consumed: false,
+			ext_glob: None,
+			brace: None,
 			context,
 		}
 	}
@@ -58,24 +72,39 @@ where
 		let allow_home = self.allow_home;
 		self.allow_home = false;
 
+		if let Some(ext_glob) = self.ext_glob.take() {
+			return self.visit_ext_glob(ext_glob, cursor);
+		}
+
+		if let Some(brace) = self.brace.take() {
+			return self.visit_brace(brace, cursor);
+		}
+
+		if let Some(tilde) = self.tilde.take() {
+			return self.visit_tilde(tilde, cursor);
+		}
+
 		match cursor.peek() {
 			// Home expansion start.
 			Some(b'~') if allow_home => {
-				self.tilde_consumed = true;
+				self.tilde = Some(Vec::new());
 				Transition::step(self)
 			}
 
-			// Home expansion end.
-			Some(b'/') if self.tilde_consumed => {
-				self.context.produce(ArgExpansion::Home)
+			// Double star.
+			Some(b'*') if self.star_consumed => {
+				self.context.produce(ArgExpansion::DoubleStar)
 			}
 
-			// Home expansion missing tilde.
-			Some(_) if self.tilde_consumed => self.context.rollback(self.start),
+			// Star, which must not consume the following character.
+			_ if self.star_consumed => {
+				self.context.resume(ArgExpansion::Star)
+			}
 
-			// Star.
+			// Star start.
 			Some(b'*') => {
-				self.context.produce(ArgExpansion::Star)
+				self.star_consumed = true;
+				Transition::step(self)
 			}
 
 			// Percent.
@@ -83,18 +112,223 @@ where
 				self.context.produce(ArgExpansion::Percent)
 			}
 
+			// Extended glob start, which must be followed by an open parenthesis.
+			Some(b'!') => {
+				self.ext_glob = Some(ExtGlob::from(true));
+				Transition::step(self)
+			}
+
+			Some(b'@') => {
+				self.ext_glob = Some(ExtGlob::from(false));
+				Transition::step(self)
+			}
+
 			Some(b'[') => {
 				todo!() // char class.
 			}
 
+			// Brace expansion start.
 			Some(b'{') => {
-				todo!() // range, collection
+				self.brace = Some(Vec::new());
+				Transition::step(self)
 			}
 
 			// Failed to parse expansion.
 			_ => self.context.rollback(self.start)
 		}
 	}
+
+
+	fn visit_tilde(mut self, mut tilde: Vec<u8>, cursor: &Cursor) -> Transition {
+		match cursor.peek() {
+			// Home expansion end.
+			Some(b'/') => self.context.produce(
+				match tilde.as_slice() {
+					b"" => ArgExpansion::Home,
+					b"+" => ArgExpansion::WorkingDir,
+					b"-" => ArgExpansion::PreviousDir,
+					_ => ArgExpansion::UserHome(tilde.into_boxed_slice()),
+				}
+			),
+
+			// User name.
+			Some(c) if c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b'-' | b'+') => {
+				tilde.push(c);
+				self.tilde = Some(tilde);
+				Transition::step(self)
+			}
+
+			// Home expansion missing slash.
+			_ => self.context.rollback(self.start),
+		}
+	}
+
+
+	fn visit_brace(mut self, mut brace: Vec<u8>, cursor: &Cursor) -> Transition {
+		match cursor.peek() {
+			// Brace expansion end. Collections are not supported yet, and are therefore
+			// handled as literals.
+			Some(b'}') => match parse_range(&brace) {
+				Some(range) => self.context.produce(ArgExpansion::Range(range)),
+				None => self.context.rollback(self.start),
+			},
+
+			Some(c) if C::is_expansion_word(c) => {
+				brace.push(c);
+				self.brace = Some(brace);
+				Transition::step(self)
+			}
+
+			// Unterminated brace expansion.
+			_ => self.context.rollback(self.start),
+		}
+	}
+
+
+	fn visit_ext_glob(mut self, mut ext_glob: ExtGlob, cursor: &Cursor) -> Transition {
+		match (ext_glob.open, cursor.peek()) {
+			// Open parenthesis after the extended glob starter.
+			(false, Some(b'(')) => {
+				ext_glob.open = true;
+			}
+
+			// Missing open parenthesis.
+			(false, _) => return self.context.rollback(self.start),
+
+			// Extended glob end.
+			(true, Some(b')')) if ext_glob.depth == 0 => {
+				let negated = ext_glob.negated;
+				let patterns = ext_glob.finish();
+
+				return self.context.produce(
+					if negated {
+						ArgExpansion::Negation(patterns)
+					} else {
+						ArgExpansion::Alternation(patterns)
+					}
+				);
+			}
+
+			// Pattern separator.
+			(true, Some(b'|')) if ext_glob.depth == 0 => ext_glob.separate(),
+
+			// Nested extended globs are kept as part of the pattern.
+			(true, Some(c @ b'(')) => {
+				ext_glob.depth += 1;
+				ext_glob.current.push(c);
+			}
+
+			(true, Some(c @ b')')) => {
+				ext_glob.depth -= 1;
+				ext_glob.current.push(c);
+			}
+
+			(true, Some(c)) if C::is_expansion_word(c) || matches!(c, b'?' | b'|') => {
+				ext_glob.current.push(c);
+			}
+
+			// Unterminated extended glob.
+			(true, _) => return self.context.rollback(self.start),
+		}
+
+		self.ext_glob = Some(ext_glob);
+		Transition::step(self)
+	}
+}
+
+
+/// Parse the contents of a brace range expansion: `x..y` or `x..y..step`, where the
+/// bounds are either integers or single ASCII letters. Integer bounds with leading zeros
+/// produce zero-padded items.
+fn parse_range(contents: &[u8]) -> Option<ArgRange> {
+	let contents = std::str::from_utf8(contents).ok()?;
+	let mut parts = contents.split("..");
+
+	let from = parts.next()?;
+	let to = parts.next()?;
+	let step = match parts.next() {
+		Some(step) => step.parse::<i64>().ok()?.unsigned_abs().max(1),
+		None => 1,
+	};
+
+	if parts.next().is_some() {
+		return None;
+	}
+
+	match (from.as_bytes(), to.as_bytes()) {
+		(&[from], &[to]) if from.is_ascii_alphabetic() && to.is_ascii_alphabetic() => {
+			Some(ArgRange { from: from.into(), to: to.into(), step, width: 0, chars: true })
+		}
+
+		_ => {
+			let padded = |bound: &str| {
+				let digits = bound.trim_start_matches('-');
+				digits.len() > 1 && digits.starts_with('0')
+			};
+
+			let width =
+				if padded(from) || padded(to) {
+					from.len().max(to.len())
+				} else {
+					0
+				};
+
+			Some(
+				ArgRange {
+					from: from.parse().ok()?,
+					to: to.parse().ok()?,
+					step,
+					width,
+					chars: false,
+				}
+			)
+		}
+	}
+}
+
+
+/// The state of an extended glob, such as `!(a|b)` or `@(a|b)`.
+#[derive(Debug)]
+struct ExtGlob {
+	/// Whether the glob is negated (`!`) instead of an alternation (`@`).
+	negated: bool,
+	/// Whether the open parenthesis has been consumed.
+	open: bool,
+	/// The nesting level of parenthesis inside the glob.
+	depth: u32,
+	/// The finished patterns.
+	patterns: Vec<Box<[u8]>>,
+	/// The pattern being parsed.
+	current: Vec<u8>,
+}
+
+
+impl ExtGlob {
+	/// Finish the current pattern, and start a new one.
+	fn separate(&mut self) {
+		let current = std::mem::take(&mut self.current);
+		self.patterns.push(current.into_boxed_slice());
+	}
+
+
+	/// Finish the glob, producing all it's patterns.
+	fn finish(mut self) -> Box<[Box<[u8]>]> {
+		self.separate();
+		self.patterns.into_boxed_slice()
+	}
+}
+
+
+impl From<bool> for ExtGlob {
+	fn from(negated: bool) -> Self {
+		Self {
+			negated,
+			open: false,
+			depth: 0,
+			patterns: Vec::new(),
+			current: Vec::new(),
+		}
+	}
 }
 
 
@@ -114,5 +348,5 @@ impl From<Expansion<argument::Word<Argument>>> for State {
 
 /// Whether a character is an expansion starter.
 pub fn is_start(c: u8) -> bool {
-	b"{[~*%".contains(&c)
+	b"{[~*%!@".contains(&c)
 }
@@ -22,6 +22,7 @@ use self::{
 use super::{
 	ArgPart,
 	ArgExpansion,
+	ArgRange,
 	ArgUnit,
 	CommandOperator,
 	Cursor,
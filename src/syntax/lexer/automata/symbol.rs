@@ -48,6 +48,7 @@ impl Symbol {
 			(b'=', _) => skip_produce(operator(Operator::Assign)),
 
 			(b'!', Some(b'=')) => Transition::produce(Root, operator(Operator::NotEquals)),
+			(b'!', Some(b'{')) => Transition::produce(Command, token(TokenKind::IsolatedCommand)),
 			(b'!', _) => unexpected(self.first),
 
 			(b'@', Some(b'[')) => Transition::produce(Root, token(TokenKind::OpenDict)),
@@ -74,17 +75,19 @@ impl From<Symbol> for State {
 }
 
 
-/// The state for lexing two-character symbols in command blocks.
+/// The state for lexing multi-character symbols in command blocks.
 #[derive(Debug)]
 pub(super) struct CommandSymbol {
 	first: u8,
+	/// Whether the symbol is prefixed by an ampersand, which redirects all outputs.
+	all: bool,
 	pos: SourcePos,
 }
 
 
 impl CommandSymbol {
 	pub fn from_first(first: u8, cursor: &Cursor) -> Self {
-		Self { first, pos: cursor.pos() }
+		Self { first, all: false, pos: cursor.pos() }
 	}
 
 
@@ -95,10 +98,21 @@ impl CommandSymbol {
 		let produce = |token| Transition::produce(Command, token);
 		let skip_produce = |output| Transition::resume_produce(Command, output);
 
+		if self.all {
+			return match cursor.peek() {
+				Some(b'>') => produce(operator(CommandOperator::OutputAll { append: true })),
+				_ => skip_produce(operator(CommandOperator::OutputAll { append: false })),
+			};
+		}
+
 		match (self.first, cursor.peek()) {
+			// The ampersand is only lexed as a symbol when followed by a greater sign.
+			(b'&', _) => Transition::step(Self { first: b'>', all: true, ..self }),
+
 			(b'>', Some(b'>')) => produce(operator(CommandOperator::Output {
 				append: true,
 			})),
+			(b'>', Some(b'|')) => produce(operator(CommandOperator::Clobber)),
 			(b'>', _) => skip_produce(operator(CommandOperator::Output {
 				append: false,
 			})),
@@ -31,6 +31,12 @@ impl<'a> Cursor<'a> {
 	}
 
 
+	/// Peek the character after the next one.
+	pub fn peek_second(&self) -> Option<u8> {
+		self.input.get(self.offset + 1).copied()
+	}
+
+
 	pub fn slice(&self) -> &'a [u8] {
 		self.input
 	}
@@ -29,6 +29,8 @@ impl std::fmt::Display for ErrorKind {
 			Self::InvalidIdentifier(ident) => {
 				write!(f, "invalid identifier '{}'", String::from_utf8_lossy(ident))?;
 			}
+
+			Self::QuotedSplice => "array splice inside double quotes".fmt(f)?,
 		};
 
 		Ok(())
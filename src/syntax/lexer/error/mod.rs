@@ -18,6 +18,8 @@ pub enum ErrorKind {
 	InvalidNumber(Box<[u8]>),
 	/// Invalid identifier, only possible in dollar braces (${}).
 	InvalidIdentifier(Box<[u8]>),
+	/// Array splice (${name...}) inside double quotes.
+	QuotedSplice,
 }
 
 
@@ -65,4 +67,8 @@ impl Error {
 			pos,
 		}
 	}
+
+	pub fn quoted_splice(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::QuotedSplice, pos }
+	}
 }
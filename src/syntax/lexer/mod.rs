@@ -14,6 +14,7 @@ pub use token::{
 	ArgPart,
 	ArgUnit,
 	ArgExpansion,
+	ArgRange,
 	CommandOperator,
 	Keyword,
 	Literal,
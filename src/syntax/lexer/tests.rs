@@ -470,3 +470,284 @@ fn test_expansions() {
 			}
 	);
 }
+
+
+#[test]
+fn test_recursive_expansion() {
+	let input = r#"
+		{
+			ls src/**/*.rs;
+			ls **;
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	let unquoted = ArgPart::Unquoted;
+	let expansion = ArgPart::Expansion;
+
+	let literal = |lit: &str| ArgUnit::Literal(lit.as_bytes().into());
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(args0)),
+			token!(TokenKind::Argument(args1)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Argument(args2)),
+			token!(TokenKind::Argument(args3)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::CloseCommand),
+		]
+			=> {
+				assert_eq!(args0.as_ref(), &[unquoted(literal("ls"))]);
+				assert_eq!(
+					args1.as_ref(),
+					&[
+						unquoted(literal("src/")),
+						expansion(ArgExpansion::DoubleStar),
+						unquoted(literal("/")),
+						expansion(ArgExpansion::Star),
+						unquoted(literal(".rs")),
+					]
+				);
+
+				assert_eq!(args2.as_ref(), &[unquoted(literal("ls"))]);
+				assert_eq!(args3.as_ref(), &[expansion(ArgExpansion::DoubleStar)]);
+			}
+	);
+}
+
+
+#[test]
+fn test_extended_glob_expansion() {
+	let input = r#"
+		{
+			ls !(*.rs|*.toml);
+			ls src/@(a|b(c))x;
+			ls foo!bar;
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	let unquoted = ArgPart::Unquoted;
+	let expansion = ArgPart::Expansion;
+
+	let literal = |lit: &str| ArgUnit::Literal(lit.as_bytes().into());
+	let patterns = |patterns: &[&str]| -> Box<[Box<[u8]>]> {
+		patterns
+			.iter()
+			.map(|pattern| pattern.as_bytes().into())
+			.collect()
+	};
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(args0)),
+			token!(TokenKind::Argument(args1)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Argument(args2)),
+			token!(TokenKind::Argument(args3)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Argument(args4)),
+			token!(TokenKind::Argument(args5)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::CloseCommand),
+		]
+			=> {
+				assert_eq!(args0.as_ref(), &[unquoted(literal("ls"))]);
+				assert_eq!(
+					args1.as_ref(),
+					&[expansion(ArgExpansion::Negation(patterns(&["*.rs", "*.toml"])))]
+				);
+
+				assert_eq!(args2.as_ref(), &[unquoted(literal("ls"))]);
+				assert_eq!(
+					args3.as_ref(),
+					&[
+						unquoted(literal("src/")),
+						expansion(ArgExpansion::Alternation(patterns(&["a", "b(c)"]))),
+						unquoted(literal("x")),
+					]
+				);
+
+				assert_eq!(args4.as_ref(), &[unquoted(literal("ls"))]);
+				assert_eq!(args5.as_ref(), &[unquoted(literal("foo!bar"))]);
+			}
+	);
+}
+
+
+#[test]
+fn test_range_expansion() {
+	let input = r#"
+		{
+			echo {1..3} x{10..0..5} {01..12} {a..f};
+			echo {1..x {a..b..c;
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	let unquoted = ArgPart::Unquoted;
+	let expansion = ArgPart::Expansion;
+
+	let literal = |lit: &str| ArgUnit::Literal(lit.as_bytes().into());
+	let range = |from, to, step, width, chars| ArgExpansion::Range(
+		ArgRange { from, to, step, width, chars }
+	);
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(args0)),
+			token!(TokenKind::Argument(args1)),
+			token!(TokenKind::Argument(args2)),
+			token!(TokenKind::Argument(args3)),
+			token!(TokenKind::Argument(args4)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Argument(args5)),
+			token!(TokenKind::Argument(args6)),
+			token!(TokenKind::Argument(args7)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::CloseCommand),
+		]
+			=> {
+				assert_eq!(args0.as_ref(), &[unquoted(literal("echo"))]);
+				assert_eq!(args1.as_ref(), &[expansion(range(1, 3, 1, 0, false))]);
+				assert_eq!(
+					args2.as_ref(),
+					&[unquoted(literal("x")), expansion(range(10, 0, 5, 0, false))]
+				);
+				assert_eq!(args3.as_ref(), &[expansion(range(1, 12, 1, 2, false))]);
+				assert_eq!(
+					args4.as_ref(),
+					&[expansion(range(b'a'.into(), b'f'.into(), 1, 0, true))]
+				);
+
+				assert_eq!(args5.as_ref(), &[unquoted(literal("echo"))]);
+				assert_eq!(args6.as_ref(), &[unquoted(literal("{1..x"))]);
+				assert_eq!(args7.as_ref(), &[unquoted(literal("{a..b..c"))]);
+			}
+	);
+}
+
+
+#[test]
+fn test_home_expansion() {
+	let input = r#"
+		{
+			ls ~/ ~root/bin ~+/ ~-/ ~foo ~a$b/;
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	let unquoted = ArgPart::Unquoted;
+	let expansion = ArgPart::Expansion;
+
+	let literal = |lit: &str| ArgUnit::Literal(lit.as_bytes().into());
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(args0)),
+			token!(TokenKind::Argument(args1)),
+			token!(TokenKind::Argument(args2)),
+			token!(TokenKind::Argument(args3)),
+			token!(TokenKind::Argument(args4)),
+			token!(TokenKind::Argument(args5)),
+			token!(TokenKind::Argument(args6)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::CloseCommand),
+		]
+			=> {
+				assert_eq!(args0.as_ref(), &[unquoted(literal("ls"))]);
+				assert_eq!(args1.as_ref(), &[expansion(ArgExpansion::Home)]);
+				assert_eq!(
+					args2.as_ref(),
+					&[
+						expansion(ArgExpansion::UserHome(b"root".as_ref().into())),
+						unquoted(literal("bin")),
+					]
+				);
+				assert_eq!(args3.as_ref(), &[expansion(ArgExpansion::WorkingDir)]);
+				assert_eq!(args4.as_ref(), &[expansion(ArgExpansion::PreviousDir)]);
+				assert_eq!(args5.as_ref(), &[unquoted(literal("~foo"))]);
+				assert_matches!(
+					args6.as_ref(),
+					[ArgPart::Unquoted(ArgUnit::Literal(lit)), ArgPart::Unquoted(ArgUnit::Dollar { .. }), _]
+						=> assert_eq!(lit.as_ref(), b"~a")
+				);
+			}
+	);
+}
+
+
+#[test]
+fn test_splice() {
+	let input = r#"
+		{
+			echo ${args...} ${a.b} "${args...}";
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(args)),
+			error!(ErrorKind::InvalidIdentifier(ident)),
+			..
+		]
+			=> {
+				assert_matches!(args.as_ref(), [ArgPart::Splice { .. }]);
+				assert_eq!(ident.as_ref(), b"a.b");
+			}
+	);
+
+	assert!(
+		tokens
+			.iter()
+			.any(|token| matches!(token, error!(ErrorKind::QuotedSplice)))
+	);
+}
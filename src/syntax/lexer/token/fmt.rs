@@ -3,6 +3,7 @@ use std::fmt::Display as _;
 use super::{
 	ArgPart,
 	ArgExpansion,
+	ArgRange,
 	ArgUnit,
 	CommandOperator,
 	Keyword,
@@ -105,19 +106,52 @@ impl<'a> Display<'a> for ArgUnit {
 }
 
 
+impl std::fmt::Display for ArgRange {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let bound = |value: i64| {
+			if self.chars {
+				(value as u8 as char).to_string()
+			} else {
+				format!("{:0width$}", value, width = self.width)
+			}
+		};
+
+		color::Fg(color::Yellow, "{").fmt(f)?;
+		bound(self.from).fmt(f)?;
+		color::Fg(color::Yellow, "..").fmt(f)?;
+		bound(self.to).fmt(f)?;
+
+		if self.step != 1 {
+			color::Fg(color::Yellow, "..").fmt(f)?;
+			self.step.fmt(f)?;
+		}
+
+		color::Fg(color::Yellow, "}").fmt(f)
+	}
+}
+
+
 impl<'a> Display<'a> for ArgExpansion {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		match self {
 			Self::Home => color::Fg(color::Yellow, "~/").fmt(f),
-			Self::Range(start, end) => {
-				color::Fg(color::Yellow, "{").fmt(f)?;
-				start.fmt(f)?;
-				color::Fg(color::Yellow, "..").fmt(f)?;
-				end.fmt(f)?;
-				color::Fg(color::Yellow, "}").fmt(f)
+			Self::UserHome(user) => {
+				color::Fg(color::Yellow, "~").fmt(f)?;
+
+				color
+					::Fg(
+						color::Yellow,
+						String::from_utf8_lossy(user).escape_debug()
+					)
+					.fmt(f)?;
+
+				color::Fg(color::Yellow, "/").fmt(f)
 			},
+			Self::WorkingDir => color::Fg(color::Yellow, "~+/").fmt(f),
+			Self::PreviousDir => color::Fg(color::Yellow, "~-/").fmt(f),
+			Self::Range(range) => range.fmt(f),
 			Self::Collection(items) => {
 				color::Fg(color::Yellow, "{").fmt(f)?;
 
@@ -132,6 +166,7 @@ impl<'a> Display<'a> for ArgExpansion {
 			},
 
 			Self::Star => color::Fg(color::Yellow, "*").fmt(f),
+			Self::DoubleStar => color::Fg(color::Yellow, "**").fmt(f),
 			Self::Percent => color::Fg(color::Yellow, "%").fmt(f),
 			Self::CharClass(chars) => {
 				color::Fg(color::Yellow, "[").fmt(f)?;
@@ -145,6 +180,44 @@ impl<'a> Display<'a> for ArgExpansion {
 
 				color::Fg(color::Yellow, "]").fmt(f)
 			},
+			Self::Negation(patterns) => {
+				color::Fg(color::Yellow, "!(").fmt(f)?;
+
+				fmt::sep_by(
+					patterns.iter(),
+					f,
+					|pattern, f| {
+						color
+							::Fg(
+								color::Yellow,
+								String::from_utf8_lossy(pattern).escape_debug()
+							)
+							.fmt(f)
+					},
+					color::Fg(color::Yellow, "|")
+				)?;
+
+				color::Fg(color::Yellow, ")").fmt(f)
+			},
+			Self::Alternation(patterns) => {
+				color::Fg(color::Yellow, "@(").fmt(f)?;
+
+				fmt::sep_by(
+					patterns.iter(),
+					f,
+					|pattern, f| {
+						color
+							::Fg(
+								color::Yellow,
+								String::from_utf8_lossy(pattern).escape_debug()
+							)
+							.fmt(f)
+					},
+					color::Fg(color::Yellow, "|")
+				)?;
+
+				color::Fg(color::Yellow, ")").fmt(f)
+			},
 		}
 	}
 }
@@ -167,6 +240,11 @@ impl<'a> Display<'a> for ArgPart {
 				'"'.fmt(f)
 			},
 			Self::Expansion(expansion) => expansion.fmt(f, context),
+			Self::Splice { symbol, .. } => {
+				"${".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"...}".fmt(f)
+			}
 			Self::EnvAssign => color::Fg(color::Yellow, "=").fmt(f),
 		}
 	}
@@ -181,6 +259,9 @@ impl std::fmt::Display for CommandOperator {
 				match self {
 					Self::Output { append: true } => ">>",
 					Self::Output { append: false } => ">",
+					Self::Clobber => ">|",
+					Self::OutputAll { append: true } => "&>>",
+					Self::OutputAll { append: false } => "&>",
 					Self::Input { literal: true } => "<<",
 					Self::Input { literal: false } => "<",
 					Self::Try => "?",
@@ -210,6 +291,7 @@ impl<'a> Display<'a> for TokenKind {
 			Self::Command => "{".fmt(f),
 			Self::CaptureCommand => "${".fmt(f),
 			Self::AsyncCommand => "&{".fmt(f),
+			Self::IsolatedCommand => "!{".fmt(f),
 			Self::CloseCommand => "}".fmt(f),
 			Self::Argument(parts) => {
 				for part in parts.iter() {
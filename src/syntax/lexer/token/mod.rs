@@ -123,15 +123,35 @@ impl ArgUnit {
 }
 
 
+/// A brace range expansion, such as `{1..10}`, `{0..100..10}`, `{01..12}` or `{a..f}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgRange {
+	pub from: i64,
+	pub to: i64,
+	/// The distance between consecutive items, regardless of the direction.
+	pub step: u64,
+	/// The minimum width of the items, which are padded with zeros. Zero for no padding.
+	pub width: usize,
+	/// Whether the bounds are ASCII characters instead of integers.
+	pub chars: bool,
+}
+
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ArgExpansion {
 	Home, // ~/
-	Range(i64, i64), // {x..y}
+	UserHome(Box<[u8]>), // ~user/
+	WorkingDir, // ~+/
+	PreviousDir, // ~-/
+	Range(ArgRange), // {x..y..z}
 	Collection(Box<[ArgUnit]>), // {a,b,c}
 
 	Star, // *
+	DoubleStar, // **
 	Percent, // %
 	CharClass(Box<[u8]>), // [...]
+	Negation(Box<[Box<[u8]>]>), // !(a|b)
+	Alternation(Box<[Box<[u8]>]>), // @(a|b)
 }
 
 /// Argument parts may be single, double ou unquoted.
@@ -141,6 +161,10 @@ pub enum ArgPart {
 	SingleQuoted(Box<[u8]>),
 	DoubleQuoted(Box<[ArgUnit]>),
 	Expansion(ArgExpansion),
+	Splice { // ${name...}
+		symbol: Symbol,
+		pos: SourcePos,
+	},
 	EnvAssign,
 }
 
@@ -159,18 +183,21 @@ impl ArgPart {
 /// Operators in command blocks.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CommandOperator {
-	Output { append: bool }, // >, >>
-	Input { literal: bool }, // <, <<
-	Try,                     // ?
+	Output { append: bool },    // >, >>
+	Clobber,                    // >|
+	OutputAll { append: bool }, // &>, &>>
+	Input { literal: bool },    // <, <<
+	Try,                        // ?
 }
 
 
 impl CommandOperator {
-	/// Check if the operator is a input or output redirection.
+	/// Check if the operator is a input or output redirection, which may be prefixed by a
+	/// file descriptor. Redirections of all outputs may not.
 	pub fn is_redirection(&self) -> bool {
 		matches!(
 			self,
-			Self::Output { .. } | Self::Input { .. }
+			Self::Output { .. } | Self::Clobber | Self::Input { .. }
 		)
 	}
 }
@@ -195,10 +222,11 @@ pub enum TokenKind {
 	CloseBracket, // ]
 
 	// Command block tokens
-	Command,        // {
-	AsyncCommand,   // &{
-	CaptureCommand, // ${
-	CloseCommand,   // }
+	Command,         // {
+	AsyncCommand,    // &{
+	CaptureCommand,  // ${
+	IsolatedCommand, // !{
+	CloseCommand,    // }
 
 	// A single argument may be composed of many parts.
 	Argument(Box<[ArgPart]>),
@@ -225,7 +253,10 @@ impl TokenKind {
 	pub fn is_command_block_starter(&self) -> bool {
 		matches!(
 			self,
-			TokenKind::Command | TokenKind::AsyncCommand | TokenKind::CaptureCommand
+			TokenKind::Command
+				| TokenKind::AsyncCommand
+				| TokenKind::CaptureCommand
+				| TokenKind::IsolatedCommand
 		)
 	}
 
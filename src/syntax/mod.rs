@@ -10,7 +10,7 @@ mod tests;
 use std::cell::RefCell;
 
 use crate::symbol;
-pub use ast::Ast;
+pub use ast::{Arena, Ast};
 pub use error::{Error, Errors};
 use lexer::Lexer;
 use parser::Parser;
@@ -20,17 +20,17 @@ pub use fmt::AnalysisDisplayContext;
 
 /// Syntactical analysis.
 #[derive(Debug)]
-pub struct Analysis {
+pub struct Analysis<'a> {
 	/// The produced AST, possibly partial if there were errors.
-	pub ast: Ast,
+	pub ast: Ast<'a>,
 	/// Syntax errors.
 	pub errors: Errors,
 }
 
 
-impl Analysis {
-	/// Perform syntax analysis in the given source.
-	pub fn analyze(source: &Source, interner: &mut symbol::Interner) -> Self {
+impl<'a> Analysis<'a> {
+	/// Perform syntax analysis in the given source, allocating the AST in the given arena.
+	pub fn analyze(source: &Source, interner: &mut symbol::Interner, arena: &'a Arena) -> Self {
 		let cursor = lexer::Cursor::from(source);
 		let lexer = Lexer::new(cursor, interner);
 
@@ -47,7 +47,7 @@ impl Analysis {
 			}
 		});
 
-		let parser = Parser::new(tokens, |error| {
+		let parser = Parser::new(tokens, arena, |error| {
 			errors.borrow_mut().push(Error::Parser(error))
 		});
 
@@ -13,13 +13,13 @@ use super::{
 };
 
 
-impl<I, E> Parser<I, E>
+impl<'a, I, E> Parser<'a, I, E>
 where
 	I: Iterator<Item = Token>,
 	E: ErrorReporter,
 {
 	/// Parse a command block.
-	pub(super) fn parse_command_block(&mut self) -> sync::Result<ast::CommandBlock, Error> {
+	pub(super) fn parse_command_block(&mut self) -> sync::Result<ast::CommandBlock<'a>, Error> {
 		let kind = self
 			.eat(
 				|token| ast::CommandBlockKind
@@ -59,8 +59,8 @@ where
 
 
 	/// Parse a complete command, including pipelines.
-	fn parse_command(&mut self) -> ast::Command {
-		let mut tail = Vec::new();
+	fn parse_command(&mut self) -> ast::Command<'a> {
+		let mut tail = self.arena.vec();
 
 		let head = self.parse_basic_command()
 			.synchronize(self);
@@ -77,21 +77,24 @@ where
 
 		ast::Command {
 			head,
-			tail: tail.into(),
+			tail: tail.into_bump_slice(),
 		}
 	}
 
 
 	/// Parse a single basic command, including redirections and try operator.
-	fn parse_basic_command(&mut self) -> sync::Result<ast::BasicCommand, Error> {
-		let env = std::iter::from_fn(|| self.parse_env_assign()).collect();
+	fn parse_basic_command(&mut self) -> sync::Result<ast::BasicCommand<'a>, Error> {
+		let mut env = self.arena.vec();
+		while let Some(assign) = self.parse_env_assign() {
+			env.push(assign);
+		}
 
 		let command = self.parse_argument()
 			.with_sync(sync::Strategy::basic_command_terminator())?;
 
 		let pos = command.pos;
 
-		let mut arguments = Vec::new();
+		let mut arguments = self.arena.vec();
 		loop {
 			let is_redirection = matches!(
 				&self.token,
@@ -124,8 +127,8 @@ where
 		Ok(
 			ast::BasicCommand {
 				program: command,
-				env,
-				arguments: arguments.into(),
+				env: env.into_bump_slice(),
+				arguments: arguments.into_bump_slice(),
 				redirections,
 				abort_on_error,
 				pos,
@@ -135,14 +138,14 @@ where
 
 
 	/// Parse a single argument.
-	fn parse_argument(&mut self) -> Result<ast::Argument, Error> {
+	fn parse_argument(&mut self) -> Result<ast::Argument<'a>, Error> {
 		let (arg_parts, pos) = self.eat(|token| match token {
 			Token { kind: TokenKind::Argument(parts), pos } => Ok((parts, pos)),
 			token => Err((Error::unexpected_msg(token.clone(), "argument"), token)),
 		})?;
 
 		Ok(
-			Self::build_arg(
+			self.build_arg(
 				arg_parts.into_vec(), // Use vec's owned iterator.
 				pos
 			)
@@ -150,19 +153,22 @@ where
 	}
 
 	/// Parse an env-assign.
-	fn parse_env_assign(&mut self) -> Option<(ast::ArgUnit, ast::Argument)> {
+	fn parse_env_assign(&mut self) -> Option<(ast::ArgUnit<'a>, ast::Argument<'a>)> {
+		let arena = self.arena;
+
 		self.eat(|token| match token {
 			Token { kind: TokenKind::Argument(parts), pos }
 			if matches!(&parts[..], [ ArgPart::Unquoted(_), ArgPart::EnvAssign, .. ]) => {
 				let mut parts = parts.into_vec(); // Use vec's owned iterator.
 
-				let value = Self::build_arg(
+				let value = Self::build_arg_in(
+					arena,
 					parts.drain(2..),
 					pos
 				);
 
 				let key = match parts.drain(..).next() {
-					Some(ArgPart::Unquoted(key)) => Self::build_arg_unit(key),
+					Some(ArgPart::Unquoted(key)) => ast::ArgUnit::from_token(key, arena),
 					_ => unreachable!("pattern matched key is missing"),
 				};
 
@@ -175,8 +181,8 @@ where
 
 	/// Parse command operators.
 	/// Returns a pair of (redirections, abort_on_error).
-	fn parse_operators(&mut self) -> Result<(Box<[ast::Redirection]>, bool), Error> {
-		let mut redirections = Vec::new();
+	fn parse_operators(&mut self) -> Result<(&'a [ast::Redirection<'a>], bool), Error> {
+		let mut redirections = self.arena.vec();
 
 		loop {
 			match &self.token {
@@ -185,7 +191,35 @@ where
 				Some(Token { kind: TokenKind::CmdOperator(Operator::Try), .. }) => {
 					self.step();
 
-					return Ok((redirections.into(), false));
+					return Ok((redirections.into_bump_slice(), false));
+				}
+
+				// Redirection of both stdout and stderr, which is the same as `> file 2>1`.
+				&Some(Token { kind: TokenKind::CmdOperator(Operator::OutputAll { append }), .. }) => {
+					self.step();
+
+					let redirection = self.parse_file_target()
+						.with_sync(sync::Strategy::keep())
+						.map(
+							|target| ast::Redirection::Output {
+								source: io::stdout_fd(),
+								target:
+									if append {
+										ast::RedirectionTarget::Append(target)
+									} else {
+										ast::RedirectionTarget::Overwrite(target)
+									},
+							}
+						)
+						.synchronize(self);
+
+					redirections.push(redirection);
+					redirections.push(
+						ast::Redirection::Output {
+							source: io::stderr_fd(),
+							target: ast::RedirectionTarget::Fd(io::stdout_fd()),
+						}
+					);
 				}
 
 				Some(_) => {
@@ -199,12 +233,12 @@ where
 			}
 		}
 
-		Ok((redirections.into(), true))
+		Ok((redirections.into_bump_slice(), true))
 	}
 
 
 	/// Parse a single redirection operation.
-	fn parse_redirection(&mut self) -> sync::Result<ast::Redirection, Error> {
+	fn parse_redirection(&mut self) -> sync::Result<ast::Redirection<'a>, Error> {
 		match &self.token {
 			// Input redirection.
 			&Some(Token { kind: TokenKind::CmdOperator(Operator::Input { literal }), .. }) => {
@@ -238,20 +272,20 @@ where
 	/// Parse a single output redirection operation after the optional file descriptor.
 	fn parse_output_redirection(
 		&mut self, source: FileDescriptor
-	) -> sync::Result<ast::Redirection, Error> {
+	) -> sync::Result<ast::Redirection<'a>, Error> {
 		match &self.token {
 			&Some(Token { kind: TokenKind::CmdOperator(Operator::Output { append }), .. }) => {
 				self.step();
 
 				let target = if append { // >> file
-					let target = self.parse_argument()
+					let target = self.parse_file_target()
 						.with_sync(sync::Strategy::keep())?;
 
 					ast::RedirectionTarget::Append(target)
 				} else if let Some(fd) = self.parse_file_descriptor() { // > fd
 					ast::RedirectionTarget::Fd(fd)
 				} else { // > file
-					let target = self.parse_argument()
+					let target = self.parse_file_target()
 						.with_sync(sync::Strategy::keep())?;
 
 					ast::RedirectionTarget::Overwrite(target)
@@ -262,6 +296,17 @@ where
 				)
 			}
 
+			&Some(Token { kind: TokenKind::CmdOperator(Operator::Clobber), .. }) => { // >| file
+				self.step();
+
+				let target = self.parse_file_target()
+					.with_sync(sync::Strategy::keep())?;
+
+				Ok(
+					ast::Redirection::Output { source, target: ast::RedirectionTarget::Clobber(target) }
+				)
+			}
+
 			Some(token) => Err(Error::unexpected_msg(token.clone(), "output redirection"))
 				.with_sync(sync::Strategy::skip_one()),
 
@@ -271,6 +316,29 @@ where
 	}
 
 
+	/// Parse the file of an output redirection. Like file descriptors, the unquoted `null`
+	/// is special, and refers to the null device.
+	fn parse_file_target(&mut self) -> Result<ast::Argument<'a>, Error> {
+		match &self.token {
+			Some(Token { kind: TokenKind::Argument(parts), pos }) => {
+				match parts.as_ref() {
+					[ArgPart::Unquoted(ArgUnit::Literal(ref lit))] if lit.as_ref() == b"null" => {
+						let pos = *pos;
+						self.step();
+
+						let null = ArgUnit::Literal(crate::io::NULL.as_bytes().into());
+						Ok(self.build_arg(std::iter::once(ArgPart::Unquoted(null)), pos))
+					}
+
+					_ => self.parse_argument(),
+				}
+			}
+
+			_ => self.parse_argument(),
+		}
+	}
+
+
 	/// Parse a optional file descriptor from a argument.
 	fn parse_file_descriptor(&mut self) -> Option<FileDescriptor> {
 		match &self.token {
@@ -293,11 +361,20 @@ where
 		}
 	}
 
-	fn build_arg<J>(arg_parts: J, pos: SourcePos) -> ast::Argument
+	fn build_arg<J>(&self, arg_parts: J, pos: SourcePos) -> ast::Argument<'a>
 	where
 		J: IntoIterator<Item = ArgPart>,
 	{
-		let mut parts = Vec::<ast::ArgPart>::new();
+		Self::build_arg_in(self.arena, arg_parts, pos)
+	}
+
+	fn build_arg_in<J>(arena: &'a ast::Arena, arg_parts: J, pos: SourcePos) -> ast::Argument<'a>
+	where
+		J: IntoIterator<Item = ArgPart>,
+	{
+		type Parts<'b> = bumpalo::collections::Vec<'b, ast::ArgPart<'b>>;
+
+		let mut parts: Parts<'a> = arena.vec();
 		let mut literal = Vec::<u8>::new();
 
 		let join_owned_literal = |literal: &mut Vec<u8>, lit: Box<[u8]>| {
@@ -308,21 +385,21 @@ where
 			}
 		};
 
-		let push_literal = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>| {
+		let push_literal = |literal: &mut Vec<u8>, parts: &mut Parts<'a>| {
 			if !literal.is_empty() {
-				let literal = std::mem::take(literal).into();
 				parts.push(
-					ast::ArgPart::Unit(ast::ArgUnit::Literal(literal))
+					ast::ArgPart::Unit(ast::ArgUnit::Literal(arena.bytes(literal)))
 				);
+				literal.clear();
 			}
 		};
 
-		let push_part = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>, part| {
+		let push_part = |literal: &mut Vec<u8>, parts: &mut Parts<'a>, part| {
 			push_literal(literal, parts);
 			parts.push(part);
 		};
 
-		let push_dollar = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>, symbol, pos| {
+		let push_dollar = |literal: &mut Vec<u8>, parts: &mut Parts<'a>, symbol, pos| {
 			push_part(
 				literal,
 				parts,
@@ -352,7 +429,13 @@ where
 				ArgPart::Expansion(expansion) => push_part(
 					&mut literal,
 					&mut parts,
-					ast::ArgPart::Expansion(expansion.into())
+					ast::ArgPart::Expansion(ast::ArgExpansion::from_token(expansion, arena))
+				),
+
+				ArgPart::Splice { symbol, pos } => push_part(
+					&mut literal,
+					&mut parts,
+					ast::ArgPart::Splice { symbol, pos }
 				),
 
 				// Env assign past the first command should be treated as a literal.
@@ -364,15 +447,8 @@ where
 		push_literal(&mut literal, &mut parts);
 
 		ast::Argument {
-			parts: parts.into(),
+			parts: parts.into_bump_slice(),
 			pos
 		}
 	}
-
-	fn build_arg_unit(unit: ArgUnit) -> ast::ArgUnit {
-		match unit {
-			ArgUnit::Dollar { symbol, pos } => ast::ArgUnit::Dollar { symbol, pos },
-			ArgUnit::Literal(lit) => ast::ArgUnit::Literal(lit),
-		}
-	}
 }
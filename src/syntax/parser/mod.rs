@@ -40,7 +40,7 @@ where
 
 /// The parser for Hush syntax.
 #[derive(Debug)]
-pub struct Parser<I, E>
+pub struct Parser<'a, I, E>
 where
 	I: Iterator<Item = Token>,
 {
@@ -48,20 +48,22 @@ where
 	// because we must be able to move from `token`, but Peekable only returns a reference.
 	cursor: Peekable<I>,
 	token: Option<Token>,
+	/// The arena in which the AST is allocated.
+	arena: &'a ast::Arena,
 	error_reporter: E,
 }
 
 
-impl<I, E> Parser<I, E>
+impl<'a, I, E> Parser<'a, I, E>
 where
 	I: Iterator<Item = Token>,
 	E: ErrorReporter,
 {
-	/// Create a new parser for the given input.
-	pub fn new(mut cursor: I, error_reporter: E) -> Self {
+	/// Create a new parser for the given input, allocating the AST in the given arena.
+	pub fn new(mut cursor: I, arena: &'a ast::Arena, error_reporter: E) -> Self {
 		let token = cursor.next();
 
-		Self { cursor: cursor.peekable(), token, error_reporter }
+		Self { cursor: cursor.peekable(), token, arena, error_reporter }
 	}
 
 
@@ -113,14 +115,14 @@ where
 
 	/// Items divided by a separator.
 	/// A ending trailing separator is optional.
-	fn sep_by<P, R, Sep, End>(&mut self, mut parse: P, mut sep: Sep, end: End) -> Box<[R]>
+	fn sep_by<P, R, Sep, End>(&mut self, mut parse: P, mut sep: Sep, end: End) -> &'a [R]
 	where
 		P: FnMut(&mut Self) -> sync::Result<R, Error>,
 		R: ast::IllFormed,
 		Sep: FnMut(&TokenKind) -> bool,
 		End: Fn(&TokenKind) -> bool,
 	{
-		let mut items = Vec::new();
+		let mut items = self.arena.vec();
 
 		loop {
 			if let Some(Token { kind: token, .. }) = &self.token {
@@ -140,12 +142,12 @@ where
 			}
 		}
 
-		items.into()
+		items.into_bump_slice()
 	}
 
 
 	/// Comma-separated items.
-	fn comma_sep<P, R, End>(&mut self, parse: P, end: End) -> Box<[R]>
+	fn comma_sep<P, R, End>(&mut self, parse: P, end: End) -> &'a [R]
 	where
 		P: FnMut(&mut Self) -> sync::Result<R, Error>,
 		R: ast::IllFormed,
@@ -156,7 +158,7 @@ where
 
 
 	/// Semicolon-separated items.
-	fn semicolon_sep<P, R, End>(&mut self, parse: P, end: End) -> Box<[R]>
+	fn semicolon_sep<P, R, End>(&mut self, parse: P, end: End) -> &'a [R]
 	where
 		P: FnMut(&mut Self) -> sync::Result<R, Error>,
 		R: ast::IllFormed,
@@ -167,7 +169,7 @@ where
 }
 
 
-impl<I, E> Synchronizable<Error> for Parser<I, E>
+impl<'a, I, E> Synchronizable<Error> for Parser<'a, I, E>
 where
 	I: Iterator<Item = Token>,
 	E: ErrorReporter,
@@ -186,13 +188,13 @@ where
 }
 
 
-impl<I, E> Parser<I, E>
+impl<'a, I, E> Parser<'a, I, E>
 where
 	I: Iterator<Item = Token>,
 	E: ErrorReporter,
 {
 	/// Parse the input, producing a top-level block.
-	pub fn parse(mut self) -> ast::Block {
+	pub fn parse(mut self) -> ast::Block<'a> {
 		loop {
 			let block = self.parse_block();
 
@@ -220,8 +222,8 @@ where
 	/// return is parsed. The Lua-like grammar requires stopping after such conditions.
 	/// This method synchronizes on all errors, producing an empty block if no statements
 	/// can be parsed.
-	fn parse_block(&mut self) -> ast::Block {
-		let mut block = Vec::new();
+	fn parse_block(&mut self) -> ast::Block<'a> {
+		let mut block = self.arena.vec();
 
 		loop {
 			match &self.token {
@@ -249,12 +251,12 @@ where
 			}
 		}
 
-		block.into_boxed_slice().into()
+		block.into_bump_slice().into()
 	}
 
 
 	/// Parse a single statement.
-	fn parse_statement(&mut self) -> sync::Result<ast::Statement, Error> {
+	fn parse_statement(&mut self) -> sync::Result<ast::Statement<'a>, Error> {
 		match self.token.take() {
 			// Let.
 			Some(Token { kind: TokenKind::Keyword(Keyword::Let), .. }) => {
@@ -403,7 +405,7 @@ where
 
 
 	/// Parse a single expression.
-	fn parse_expression(&mut self) -> sync::Result<ast::Expr, Error> {
+	fn parse_expression(&mut self) -> sync::Result<ast::Expr<'a>, Error> {
 		macro_rules! binop {
 			($parse_higher_prec:expr, $check:expr) => {
 				move |parser: &mut Self| parser.parse_binop($parse_higher_prec, $check)
@@ -427,9 +429,9 @@ where
 		&mut self,
 		mut parse_higher_prec_op: P,
 		mut check: F,
-	) -> sync::Result<ast::Expr, Error>
+	) -> sync::Result<ast::Expr<'a>, Error>
 	where
-		P: FnMut(&mut Self) -> sync::Result<ast::Expr, Error>,
+		P: FnMut(&mut Self) -> sync::Result<ast::Expr<'a>, Error>,
 		F: FnMut(&Operator) -> bool,
 	{
 		let mut expr = parse_higher_prec_op(self)?;
@@ -442,9 +444,9 @@ where
 					let right = parse_higher_prec_op(self)?;
 
 					expr = ast::Expr::BinaryOp {
-						left: expr.into(),
+						left: self.arena.alloc(expr),
 						op: op.into(),
-						right: right.into(),
+						right: self.arena.alloc(right),
 						pos,
 					};
 				}
@@ -461,7 +463,7 @@ where
 
 
 	/// Parse a higher precedence expression, optionally starting with a prefix operator.
-	fn parse_prefix(&mut self) -> sync::Result<ast::Expr, Error> {
+	fn parse_prefix(&mut self) -> sync::Result<ast::Expr<'a>, Error> {
 		match self.token.take() {
 			Some(Token { kind: TokenKind::Operator(op), pos }) if op.is_prefix() => {
 				self.step();
@@ -470,7 +472,7 @@ where
 
 				Ok(ast::Expr::UnaryOp {
 					op: op.into(),
-					operand: operand.into(),
+					operand: self.arena.alloc(operand),
 					pos,
 				})
 			}
@@ -484,7 +486,7 @@ where
 
 
 	/// Parse a primary expression followed by a postfix operator.
-	fn parse_postfix(&mut self) -> sync::Result<ast::Expr, Error> {
+	fn parse_postfix(&mut self) -> sync::Result<ast::Expr<'a>, Error> {
 		let mut expr = self.parse_primary()?;
 
 		loop {
@@ -502,7 +504,7 @@ where
 						.with_sync(sync::Strategy::token(TokenKind::CloseParens))?;
 
 					expr = ast::Expr::Call {
-						function: expr.into(),
+						function: self.arena.alloc(expr),
 						args,
 						pos,
 					}
@@ -519,8 +521,8 @@ where
 						.with_sync(sync::Strategy::token(TokenKind::CloseBracket))?;
 
 					expr = ast::Expr::Access {
-						object: expr.into(),
-						field: field.into(),
+						object: self.arena.alloc(expr),
+						field: self.arena.alloc(field),
 						pos,
 					}
 				},
@@ -539,8 +541,8 @@ where
 					};
 
 					expr = ast::Expr::Access {
-						object: expr.into(),
-						field: field.into(),
+						object: self.arena.alloc(expr),
+						field: self.arena.alloc(field),
 						pos,
 					}
 				},
@@ -551,7 +553,7 @@ where
 
 					expr = ast::Expr::UnaryOp {
 						op: ast::UnaryOp::Try,
-						operand: Box::new(expr),
+						operand: self.arena.alloc(expr),
 						pos,
 					}
 				},
@@ -568,7 +570,7 @@ where
 
 
 	/// Parse a primary (highest precedence) expression.
-	fn parse_primary(&mut self) -> sync::Result<ast::Expr, Error> {
+	fn parse_primary(&mut self) -> sync::Result<ast::Expr<'a>, Error> {
 		match self.token.take() {
 			// Identifier.
 			Some(Token { kind: TokenKind::Identifier(identifier), pos }) => {
@@ -588,7 +590,7 @@ where
 			Some(Token { kind: TokenKind::Literal(literal), pos }) => {
 				self.step();
 
-				Ok(ast::Expr::Literal { literal: literal.into(), pos })
+				Ok(ast::Expr::Literal { literal: ast::Literal::from_token(literal, self.arena), pos })
 			}
 
 			// Array literal.
@@ -698,7 +700,7 @@ where
 				};
 
 				Ok(ast::Expr::If {
-					condition: condition.into(),
+					condition: self.arena.alloc(condition),
 					then,
 					otherwise,
 					pos,
@@ -749,7 +751,7 @@ where
 	#[allow(clippy::type_complexity)]
 	fn parse_function(
 		&mut self
-	) -> sync::Result<(Box<[(ast::Symbol, SourcePos)]>, ast::Block), Error> {
+	) -> sync::Result<(&'a [(ast::Symbol, SourcePos)], ast::Block<'a>), Error> {
 		let result = self.expect(TokenKind::OpenParens)
 			.with_sync(sync::Strategy::keep());
 
@@ -1,10 +1,7 @@
-use std::{
-	ffi::OsStr,
-	fs::File,
-	os::unix::ffi::OsStrExt,
-};
+use std::fs::File;
 
 use crate::{
+	ffi,
 	fmt::{self, Display},
 	symbol::{self, Symbol},
 };
@@ -23,7 +20,7 @@ pub struct Source {
 impl Source {
 	/// Load the source code from a file path.
 	pub fn from_path(symbol: Symbol, interner: &mut symbol::Interner) -> std::io::Result<Self> {
-		let path = OsStr::from_bytes(
+		let path = ffi::os_str(
 			interner
 				.resolve(symbol)
 				.expect("failed to resolve path symbol")
@@ -1,11 +1,10 @@
 use std::{
 	io,
 	path::Path,
-	os::unix::ffi::OsStrExt,
 };
 
-use crate::{fmt, symbol, syntax::AnalysisDisplayContext, tests};
-use super::{Analysis, Source};
+use crate::{ffi::OsStrExt, fmt, symbol, syntax::AnalysisDisplayContext, tests};
+use super::{Analysis, Arena, Source};
 
 
 fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
@@ -20,7 +19,8 @@ where
 		move |path, file| {
 			let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
 			let source = Source::from_reader(path_symbol, file)?;
-			let analysis = Analysis::analyze(&source, &mut interner);
+			let arena = Arena::new();
+			let analysis = Analysis::analyze(&source, &mut interner, &arena);
 
 			if !check(&analysis) {
 				panic!("{}", fmt::Show(
@@ -1,14 +1,83 @@
 use std::{
-	io,
+	io::{self, IsTerminal},
 	fmt::{self, Debug, Display},
 };
 
-pub use termion::color::{Black, Blue, Green, Red, Yellow};
+
+/// The escape sequence for the bold style.
+pub const BOLD: &str = "\x1b[1m";
+/// The escape sequence which resets all styles and colors.
+pub const RESET: &str = "\x1b[m";
+/// The escape sequence which resets the foreground color.
+const FG_RESET: &str = "\x1b[39m";
+
+
+/// A terminal color, written as ANSI escape sequences.
+pub trait Color: Copy {
+	/// The escape sequence that paints the foreground with the color.
+	const FG: &'static str;
+}
+
+
+macro_rules! color {
+	($name: ident, $code: literal) => {
+		#[derive(Debug, Clone, Copy)]
+		pub struct $name;
+
+		impl Color for $name {
+			const FG: &'static str = concat!("\x1b[38;5;", $code, "m");
+		}
+	}
+}
+
+
+color!(Red, 1);
+color!(Green, 2);
+color!(Yellow, 3);
+color!(Blue, 4);
 
 
 thread_local! {
-	static IS_TTY: bool = termion::is_tty(&io::stdout())
-		&& termion::is_tty(&io::stderr());
+	static IS_TTY: bool = io::stdout().is_terminal()
+		&& io::stderr().is_terminal()
+		&& enable_escapes();
+}
+
+
+/// Enable escape sequences in the terminal. Terminals always support them on Unix.
+#[cfg(unix)]
+fn enable_escapes() -> bool {
+	true
+}
+
+
+/// Enable escape sequences in the console, which requires virtual terminal processing.
+#[cfg(windows)]
+fn enable_escapes() -> bool {
+	use windows_sys::Win32::System::Console::{
+		GetConsoleMode,
+		GetStdHandle,
+		SetConsoleMode,
+		ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+		STD_ERROR_HANDLE,
+		STD_OUTPUT_HANDLE,
+	};
+
+	[STD_OUTPUT_HANDLE, STD_ERROR_HANDLE]
+		.iter()
+		.all(
+			|&output| {
+				let mut mode = 0;
+
+				// Safety: the pointer is valid. Both calls fail if the handle is not a console.
+				unsafe {
+					let handle = GetStdHandle(output);
+
+					GetConsoleMode(handle, &mut mode) != 0
+						&& SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+				}
+			}
+		)
 }
 
 
@@ -33,32 +102,22 @@ pub struct Fg<C, T>(pub C, pub T);
 
 impl<C, T> Debug for Fg<C, T>
 where
-	C: termion::color::Color + Copy,
+	C: Color,
 	T: Debug,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		tty_fmt!(
-			f,
-			termion::color::Fg(self.0),
-			self.1,
-			termion::color::Fg(termion::color::Reset)
-		)
+		tty_fmt!(f, C::FG, self.1, FG_RESET)
 	}
 }
 
 
 impl<C, T> Display for Fg<C, T>
 where
-	C: termion::color::Color + Copy,
+	C: Color,
 	T: Display,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		tty_fmt!(
-			f,
-			termion::color::Fg(self.0),
-			self.1,
-			termion::color::Fg(termion::color::Reset)
-		)
+		tty_fmt!(f, C::FG, self.1, FG_RESET)
 	}
 }
 
@@ -73,12 +132,7 @@ where
 	T: Debug,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		tty_fmt!(
-			f,
-			self.0,
-			self.1,
-			termion::style::Reset
-		)
+		tty_fmt!(f, self.0, self.1, RESET)
 	}
 }
 
@@ -89,12 +143,7 @@ where
 	T: Display,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		tty_fmt!(
-			f,
-			self.0,
-			self.1,
-			termion::style::Reset
-		)
+		tty_fmt!(f, self.0, self.1, RESET)
 	}
 }
 
@@ -108,7 +157,7 @@ where
 	T: Debug,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		Style(termion::style::Bold, &self.0).fmt(f)
+		Style(BOLD, &self.0).fmt(f)
 	}
 }
 
@@ -118,6 +167,6 @@ where
 	T: Display,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		Style(termion::style::Bold, &self.0).fmt(f)
+		Style(BOLD, &self.0).fmt(f)
 	}
 }
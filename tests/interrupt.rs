@@ -0,0 +1,95 @@
+// Terminals and job control signals are only available on Unix.
+#![cfg(unix)]
+
+use std::{
+	fs::File,
+	io::{self, Read, Write},
+	os::unix::{io::FromRawFd, process::CommandExt},
+	process::{Command, Stdio},
+	thread,
+	time::{Duration, Instant},
+};
+
+
+/// Ctrl-C in the terminal of a script must stop the script, not only the command it is
+/// waiting on.
+#[test]
+fn test_interrupt_ends_script() -> io::Result<()> {
+	let mut master = -1;
+	let mut slave = -1;
+
+	// Safety: the pointers are valid, and the optional arguments may be null.
+	if unsafe {
+		libc::openpty(
+			&mut master,
+			&mut slave,
+			std::ptr::null_mut(),
+			std::ptr::null(),
+			std::ptr::null(),
+		)
+	} < 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	// Safety: openpty produced these file descriptors, which we now own.
+	let (mut master, slave) = unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+
+	let mut command = Command::new(env!("CARGO_BIN_EXE_hush"));
+	command
+		.arg(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/interrupt.hsh"))
+		.stdin(Stdio::from(slave.try_clone()?))
+		.stdout(Stdio::from(slave.try_clone()?))
+		.stderr(Stdio::from(slave));
+
+	// Make the pseudo-terminal the controlling terminal of the shell, as in a terminal
+	// emulator, so that Ctrl-C is delivered to its foreground process group.
+	// Safety: setsid and ioctl are async signal safe.
+	unsafe {
+		command.pre_exec(|| {
+			if libc::setsid() < 0 || libc::ioctl(libc::STDIN_FILENO, libc::TIOCSCTTY, 0) < 0 {
+				return Err(io::Error::last_os_error());
+			}
+
+			Ok(())
+		});
+	}
+
+	let start = Instant::now();
+	let mut child = command.spawn()?;
+
+	// The command's copies of the slave side are closed when dropped, so that reading the
+	// master fails once the shell and its children exit.
+	drop(command);
+
+	let mut reader = master.try_clone()?;
+	let output = thread::spawn(move || {
+		let mut output = Vec::new();
+		let mut buffer = [0; 1024];
+		// Reading the master fails with EIO once the slave is closed.
+		while let Ok(size) = reader.read(&mut buffer) {
+			if size == 0 {
+				break;
+			}
+			output.extend_from_slice(&buffer[..size]);
+		}
+		output
+	});
+
+	thread::sleep(Duration::from_millis(500));
+	master.write_all(b"\x03")?;
+
+	let status = child.wait()?;
+	let elapsed = start.elapsed();
+	drop(master);
+	let output = output.join().expect("output thread panicked");
+
+	assert!(!status.success(), "script exited successfully: {}", status);
+	assert!(elapsed < Duration::from_secs(4), "script took {:?}", elapsed);
+	assert!(
+		!output.windows(8).any(|window| window == b"finished"),
+		"script continued after the interrupt: {}",
+		String::from_utf8_lossy(&output)
+	);
+
+	Ok(())
+}